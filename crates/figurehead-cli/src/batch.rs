@@ -0,0 +1,262 @@
+//! Batch conversion of several input files
+//!
+//! Inputs are paths or glob patterns, expanded in the order given; `-`
+//! reads further paths from stdin, one per line, so `find` or `git ls-files`
+//! can feed the batch. Each input is written next to itself with a new
+//! extension, or into an output directory. A failing file is recorded and
+//! the batch moves on, so one bad diagram doesn't hide the others.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+/// Expand paths and glob patterns into input files
+///
+/// Plain paths are kept even if they don't exist, so reading them reports
+/// the problem; a pattern matching nothing is an error. Each file is listed
+/// once, at its first mention.
+pub fn expand_inputs(
+    patterns: &[String],
+    stdin: impl BufRead,
+) -> (Vec<PathBuf>, Vec<(PathBuf, String)>) {
+    let mut patterns: Vec<String> = patterns.to_vec();
+    if let Some(index) = patterns.iter().position(|p| p == "-") {
+        let listed = stdin
+            .lines()
+            .map_while(|line| line.ok())
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty());
+        patterns.splice(index..=index, listed);
+    }
+
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    for pattern in patterns {
+        if !pattern.contains(['*', '?', '[']) {
+            files.push(PathBuf::from(pattern));
+            continue;
+        }
+        match glob::glob(&pattern) {
+            Ok(paths) => {
+                let matched: Vec<PathBuf> = paths.filter_map(|p| p.ok()).collect();
+                if matched.is_empty() {
+                    errors.push((PathBuf::from(&pattern), "no files match".to_string()));
+                }
+                files.extend(matched);
+            }
+            Err(e) => errors.push((PathBuf::from(&pattern), format!("bad pattern: {}", e))),
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+    (files, errors)
+}
+
+/// Where `input`'s rendering is written: beside it, or in `out_dir`
+pub fn output_path(input: &Path, out_dir: Option<&Path>, extension: &str) -> PathBuf {
+    let sibling = input.with_extension(extension);
+    match (out_dir, sibling.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => sibling,
+    }
+}
+
+/// Outcome of a batch conversion
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// Inputs converted, with the file each was written to
+    pub converted: Vec<(PathBuf, PathBuf)>,
+    /// Inputs or patterns that failed, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl BatchReport {
+    /// Whether every input was converted
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        let converted = self.converted.len();
+        write!(f, "Converted {} file{}", converted, plural(converted))?;
+        if !self.failed.is_empty() {
+            write!(f, ", {} failed:", self.failed.len())?;
+            for (path, error) in &self.failed {
+                write!(f, "\n  {}: {}", path.display(), error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Render each input with `render` and write it out
+///
+/// An output that would overwrite its own input, or a file already written
+/// in this batch (same-named inputs in different directories sent to one
+/// `out_dir`), is reported as a failure instead.
+pub fn run_batch(
+    inputs: Vec<PathBuf>,
+    mut failed: Vec<(PathBuf, String)>,
+    out_dir: Option<&Path>,
+    extension: &str,
+    mut render: impl FnMut(&str) -> Result<String>,
+) -> BatchReport {
+    if let Some(dir) = out_dir {
+        if let Err(e) = fs::create_dir_all(dir) {
+            failed.push((dir.to_path_buf(), format!("cannot create directory: {}", e)));
+            return BatchReport {
+                converted: Vec::new(),
+                failed,
+            };
+        }
+    }
+
+    let mut report = BatchReport {
+        converted: Vec::new(),
+        failed,
+    };
+    let mut written: HashMap<PathBuf, PathBuf> = HashMap::new();
+    for input in inputs {
+        let output = output_path(&input, out_dir, extension);
+        let result = if output == input {
+            Err(anyhow!("output would overwrite the input; use --out-dir"))
+        } else if let Some(first) = written.get(&output) {
+            Err(anyhow!(
+                "{} was already written from {}",
+                output.display(),
+                first.display()
+            ))
+        } else {
+            fs::read_to_string(&input)
+                .map_err(|e| anyhow!("cannot read: {}", e))
+                .and_then(|content| render(&content))
+                .and_then(|text| {
+                    fs::write(&output, text)
+                        .map_err(|e| anyhow!("cannot write {}: {}", output.display(), e))
+                })
+        };
+        match result {
+            Ok(()) => {
+                written.insert(output.clone(), input.clone());
+                report.converted.push((input, output));
+            }
+            Err(e) => report.failed.push((input, e.to_string())),
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_inputs_globs_and_stdin_lists() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a.mmd", "b.mmd", "notes.md"] {
+            fs::write(dir.path().join(name), "graph LR; A-->B").unwrap();
+        }
+        let pattern = dir.path().join("*.mmd").to_string_lossy().into_owned();
+        let listed = dir.path().join("notes.md");
+        let stdin = format!("{}\n\n", listed.display());
+
+        let (files, errors) = expand_inputs(
+            &[pattern.clone(), "-".to_string(), pattern],
+            stdin.as_bytes(),
+        );
+        assert_eq!(
+            files,
+            [
+                dir.path().join("a.mmd"),
+                dir.path().join("b.mmd"),
+                listed.clone()
+            ]
+        );
+        assert!(errors.is_empty());
+
+        let missing = dir.path().join("*.txt").to_string_lossy().into_owned();
+        let (files, errors) = expand_inputs(&[missing], &b""[..]);
+        assert!(files.is_empty());
+        assert_eq!(errors[0].1, "no files match");
+    }
+
+    #[test]
+    fn test_output_path_is_sibling_or_in_out_dir() {
+        let input = Path::new("docs/flow.mmd");
+        assert_eq!(output_path(input, None, "txt"), Path::new("docs/flow.txt"));
+        assert_eq!(
+            output_path(input, Some(Path::new("out")), "txt"),
+            Path::new("out/flow.txt")
+        );
+    }
+
+    #[test]
+    fn test_run_batch_reports_each_failure_and_keeps_going() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good.mmd");
+        let bad = dir.path().join("bad.mmd");
+        let text = dir.path().join("already.txt");
+        fs::write(&good, "ok").unwrap();
+        fs::write(&bad, "broken").unwrap();
+        fs::write(&text, "ok").unwrap();
+        let missing = dir.path().join("missing.mmd");
+
+        let report = run_batch(
+            vec![bad.clone(), good.clone(), text.clone(), missing.clone()],
+            Vec::new(),
+            None,
+            "txt",
+            |content| match content {
+                "ok" => Ok("drawn".to_string()),
+                _ => Err(anyhow!("Parse error")),
+            },
+        );
+
+        assert_eq!(
+            report.converted,
+            [(good.clone(), dir.path().join("good.txt"))]
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("good.txt")).unwrap(),
+            "drawn"
+        );
+        let failed: Vec<&PathBuf> = report.failed.iter().map(|(path, _)| path).collect();
+        assert_eq!(failed, [&bad, &text, &missing]);
+        assert!(!report.is_success());
+
+        let summary = report.to_string();
+        assert!(summary.starts_with("Converted 1 file, 3 failed:"));
+        assert!(summary.contains("bad.mmd: Parse error"));
+        assert!(summary.contains("overwrite the input"));
+    }
+
+    #[test]
+    fn test_out_dir_collisions_are_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("one/flow.mmd");
+        let second = dir.path().join("two/flow.mmd");
+        for path in [&first, &second] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "ok").unwrap();
+        }
+        let out = dir.path().join("out");
+
+        let report = run_batch(
+            vec![first, second.clone()],
+            Vec::new(),
+            Some(&out),
+            "txt",
+            |_| Ok("drawn".to_string()),
+        );
+        assert_eq!(report.converted.len(), 1);
+        assert_eq!(report.failed[0].0, second);
+        assert!(report.failed[0].1.contains("already written"));
+    }
+}
@@ -0,0 +1,347 @@
+//! Repeatable timings for rendering one diagram
+//!
+//! `figurehead bench` runs the whole pipeline on the same input many times
+//! and reports percentiles per stage (as seen by a
+//! [`ProcessObserver`](figurehead::plugins::ProcessObserver)), so performance
+//! reports and release comparisons come with numbers anyone can reproduce.
+//! Built with the `alloc-count` feature, it also counts heap allocations.
+
+use anyhow::Result;
+use figurehead::plugins::{Orchestrator, Stage, StageEvent, StageSize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Pipeline stages in the order they run
+const STAGES: [Stage; 4] = [Stage::Detect, Stage::Parse, Stage::Layout, Stage::Render];
+
+/// Summary of a set of timings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentiles {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl Percentiles {
+    /// Nearest-rank percentiles of `samples` (all zero when empty)
+    pub fn of(samples: &[Duration]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let rank = |percent: usize| {
+            let index = (sorted.len() * percent).div_ceil(100).saturating_sub(1);
+            sorted.get(index).copied().unwrap_or_default()
+        };
+        Self {
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+            max: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Heap allocations made while processing the diagram once (median run)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Allocations {
+    pub count: usize,
+    pub bytes: usize,
+}
+
+/// Results of a benchmark run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchReport {
+    pub diagram_type: String,
+    pub iterations: usize,
+    pub warmup: usize,
+    /// Nodes and edges in the parsed diagram
+    pub elements: Option<(usize, usize)>,
+    /// Laid-out canvas size, for renderers that report it
+    pub canvas: Option<(usize, usize)>,
+    pub output_bytes: usize,
+    pub output_lines: usize,
+    /// Timings of each stage that reported, in pipeline order
+    pub stages: Vec<(Stage, Percentiles)>,
+    /// Timings of the whole pipeline
+    pub total: Percentiles,
+    /// Only with the `alloc-count` feature
+    pub allocations: Option<Allocations>,
+}
+
+/// Process `content` `warmup + iterations` times, timing the last `iterations`
+pub fn run_bench(
+    orchestrator: &mut Orchestrator,
+    content: &str,
+    iterations: usize,
+    warmup: usize,
+) -> Result<BenchReport> {
+    type Events = Vec<(Stage, Duration, StageSize)>;
+    let events: Arc<Mutex<Events>> = Arc::default();
+    let seen = events.clone();
+    orchestrator.set_observer(Arc::new(move |event: &StageEvent| {
+        seen.lock()
+            .unwrap()
+            .push((event.stage, event.elapsed, event.size));
+    }));
+
+    for _ in 0..warmup {
+        orchestrator.process(content)?;
+    }
+
+    let mut report = BenchReport {
+        diagram_type: orchestrator.detect_diagram_type(content)?,
+        iterations,
+        warmup,
+        elements: None,
+        canvas: None,
+        output_bytes: 0,
+        output_lines: 0,
+        stages: Vec::new(),
+        total: Percentiles::of(&[]),
+        allocations: None,
+    };
+    let mut stage_samples: Vec<Vec<Duration>> = vec![Vec::new(); STAGES.len()];
+    let mut totals = Vec::with_capacity(iterations);
+    let mut allocations = Vec::with_capacity(iterations);
+
+    for _ in 0..iterations {
+        events.lock().unwrap().clear();
+        let allocated = alloc_count::snapshot();
+        let started = Instant::now();
+        let output = orchestrator.process(content)?;
+        totals.push(started.elapsed());
+        allocations.push(alloc_count::since(allocated));
+
+        report.output_bytes = output.len();
+        report.output_lines = output.lines().count();
+        for (stage, elapsed, size) in events.lock().unwrap().iter() {
+            let index = STAGES.iter().position(|s| s == stage).unwrap();
+            stage_samples[index].push(*elapsed);
+            match size {
+                StageSize::Elements { nodes, edges } => report.elements = Some((*nodes, *edges)),
+                StageSize::Canvas { width, height } => report.canvas = Some((*width, *height)),
+                _ => {}
+            }
+        }
+    }
+
+    report.stages = STAGES
+        .iter()
+        .zip(&stage_samples)
+        .filter(|(_, samples)| !samples.is_empty())
+        .map(|(stage, samples)| (*stage, Percentiles::of(samples)))
+        .collect();
+    report.total = Percentiles::of(&totals);
+    allocations.sort_by_key(|a: &Option<Allocations>| a.map(|a| a.count));
+    report.allocations = allocations.get(allocations.len() / 2).copied().flatten();
+    Ok(report)
+}
+
+/// A duration with a unit suited to its size
+fn format_duration(duration: Duration) -> String {
+    let micros = duration.as_secs_f64() * 1e6;
+    if micros < 1000.0 {
+        format!("{:.1}µs", micros)
+    } else if micros < 1e6 {
+        format!("{:.2}ms", micros / 1000.0)
+    } else {
+        format!("{:.2}s", micros / 1e6)
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Type:       {}", self.diagram_type)?;
+        writeln!(
+            f,
+            "Iterations: {} (after {} warmup)",
+            self.iterations, self.warmup
+        )?;
+        if let Some((nodes, edges)) = self.elements {
+            writeln!(f, "Elements:   {} nodes, {} edges", nodes, edges)?;
+        }
+        if let Some((width, height)) = self.canvas {
+            writeln!(f, "Canvas:     {}x{}", width, height)?;
+        }
+        writeln!(
+            f,
+            "Output:     {} bytes, {} lines",
+            self.output_bytes, self.output_lines
+        )?;
+        if let Some(allocations) = self.allocations {
+            writeln!(
+                f,
+                "Allocs:     {} per run, {} bytes",
+                allocations.count, allocations.bytes
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<8} {:>10} {:>10} {:>10} {:>10}",
+            "stage", "p50", "p90", "p99", "max"
+        )?;
+        let rows = self
+            .stages
+            .iter()
+            .map(|(stage, timings)| (stage.as_str(), timings))
+            .chain(std::iter::once(("total", &self.total)));
+        for (name, timings) in rows {
+            writeln!(
+                f,
+                "{:<8} {:>10} {:>10} {:>10} {:>10}",
+                name,
+                format_duration(timings.p50),
+                format_duration(timings.p90),
+                format_duration(timings.p99),
+                format_duration(timings.max)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Benchmark report as JSON, with times in microseconds
+pub fn bench_json(report: &BenchReport) -> serde_json::Value {
+    let timings = |timings: &Percentiles| {
+        let micros = |duration: Duration| duration.as_secs_f64() * 1e6;
+        serde_json::json!({
+            "p50_us": micros(timings.p50),
+            "p90_us": micros(timings.p90),
+            "p99_us": micros(timings.p99),
+            "max_us": micros(timings.max),
+        })
+    };
+    let stages: serde_json::Map<_, _> = report
+        .stages
+        .iter()
+        .map(|(stage, percentiles)| (stage.as_str().to_string(), timings(percentiles)))
+        .collect();
+    serde_json::json!({
+        "type": report.diagram_type,
+        "iterations": report.iterations,
+        "warmup": report.warmup,
+        "nodes": report.elements.map(|(nodes, _)| nodes),
+        "edges": report.elements.map(|(_, edges)| edges),
+        "canvas": report.canvas.map(|(width, height)| serde_json::json!({
+            "width": width,
+            "height": height,
+        })),
+        "output_bytes": report.output_bytes,
+        "output_lines": report.output_lines,
+        "stages": stages,
+        "total": timings(&report.total),
+        "allocations": report.allocations.map(|allocations| serde_json::json!({
+            "count": allocations.count,
+            "bytes": allocations.bytes,
+        })),
+    })
+}
+
+/// Heap allocation counting through a wrapping global allocator
+#[cfg(feature = "alloc-count")]
+mod alloc_count {
+    use super::Allocations;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    static BYTES: AtomicUsize = AtomicUsize::new(0);
+
+    /// The system allocator, counting every allocation it makes
+    struct CountingAllocator;
+
+    // SAFETY: every call is forwarded unchanged to the system allocator
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            COUNT.fetch_add(1, Ordering::Relaxed);
+            BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            COUNT.fetch_add(1, Ordering::Relaxed);
+            BYTES.fetch_add(new_size, Ordering::Relaxed);
+            System.realloc(ptr, layout, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAllocator = CountingAllocator;
+
+    /// Allocations made so far
+    pub fn snapshot() -> Option<Allocations> {
+        Some(Allocations {
+            count: COUNT.load(Ordering::Relaxed),
+            bytes: BYTES.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Allocations made since `start`
+    pub fn since(start: Option<Allocations>) -> Option<Allocations> {
+        let (start, now) = (start?, snapshot()?);
+        Some(Allocations {
+            count: now.count - start.count,
+            bytes: now.bytes - start.bytes,
+        })
+    }
+}
+
+/// Without the `alloc-count` feature nothing is counted
+#[cfg(not(feature = "alloc-count"))]
+mod alloc_count {
+    use super::Allocations;
+
+    pub fn snapshot() -> Option<Allocations> {
+        None
+    }
+
+    pub fn since(_start: Option<Allocations>) -> Option<Allocations> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_use_nearest_rank() {
+        let samples: Vec<_> = (1..=10).rev().map(Duration::from_millis).collect();
+        let percentiles = Percentiles::of(&samples);
+        assert_eq!(percentiles.p50, Duration::from_millis(5));
+        assert_eq!(percentiles.p90, Duration::from_millis(9));
+        assert_eq!(percentiles.p99, Duration::from_millis(10));
+        assert_eq!(percentiles.max, Duration::from_millis(10));
+        assert_eq!(Percentiles::of(&[]).max, Duration::ZERO);
+    }
+
+    #[test]
+    fn test_bench_reports_every_stage() {
+        let mut orchestrator = Orchestrator::with_all_plugins();
+        orchestrator.register_default_detectors();
+
+        let report = run_bench(&mut orchestrator, "graph LR\n A --> B --> C", 5, 1).unwrap();
+        let stages: Vec<_> = report.stages.iter().map(|(stage, _)| *stage).collect();
+        assert_eq!(stages, STAGES);
+        assert_eq!(report.diagram_type, "flowchart");
+        assert_eq!(report.elements.map(|(_, edges)| edges), Some(2));
+        assert!(report.output_lines > 0 && report.canvas.is_some());
+        assert!(report.total.p50 <= report.total.max);
+
+        let text = report.to_string();
+        assert!(text.contains("Iterations: 5 (after 1 warmup)"), "{}", text);
+        assert!(
+            text.lines().last().unwrap().starts_with("total"),
+            "{}",
+            text
+        );
+        assert!(bench_json(&report)["stages"]["layout"]["p50_us"].is_f64());
+    }
+}
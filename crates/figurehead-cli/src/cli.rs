@@ -3,15 +3,32 @@
 //! Provides a CLI to convert Mermaid.js diagram markup into ASCII diagrams.
 
 use anyhow::{anyhow, Result};
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 use std::fs;
 use std::io::{self, Read, Write};
 use std::path::PathBuf;
 
-use crate::colorizer::{colorize_output, extract_styles, StyleInfo};
+use crate::batch;
+use crate::bench::{bench_json, run_bench};
+use crate::config;
+use crate::mdbook::{run_preprocessor, supports_renderer};
+use crate::repl::run_repl;
+use crate::watch;
 use figurehead::core::logging::init_logging;
+use figurehead::plugins::class::ClassParser;
+use figurehead::plugins::flowchart::{
+    split_diagrams, EdgeSummary, FlowchartDatabase, FlowchartParser, FlowchartRenderer,
+    NodeDescription, RepeatedHeaders,
+};
+use figurehead::plugins::gitgraph::{FoldOptions, GitGraphRenderer};
+use figurehead::plugins::sequence::{ParticipantOrder, SequenceRenderer};
 use figurehead::plugins::Orchestrator;
-use figurehead::{CharacterSet, DiamondStyle, RenderConfig};
+use figurehead::unstable::flowchart::{ingest_ascii, LayoutConfig};
+use figurehead::{
+    replace_mermaid_blocks, AspectRatio, CanvasLimit, CanvasMode, Capabilities, CharacterSet,
+    Database, DiagramSheet, DiagramStats, DiamondStyle, EdgeCurve, EdgeTerminator, IdCase,
+    LabelPlacement, LineWeight, MarkdownFence, OverflowPolicy, RenderConfig, SplitLabels, Theme,
+};
 
 /// Figurehead - Convert Mermaid.js diagrams to ASCII art
 #[derive(Parser)]
@@ -34,6 +51,11 @@ pub struct Cli {
     /// Set log format (compact|pretty|json)
     #[arg(long, value_enum, default_value_t = LogFormat::Compact)]
     pub log_format: LogFormat,
+
+    /// Read option defaults from this TOML file instead of the nearest
+    /// figurehead.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
 }
 
 /// Log level options
@@ -84,6 +106,17 @@ pub enum Commands {
         #[arg(short, long)]
         input: Option<PathBuf>,
 
+        /// Convert several files at once: paths or glob patterns (quoted,
+        /// e.g. 'docs/**/*.mmd'), with `-` reading more paths from stdin.
+        /// Each is written beside its input with a .txt (.html, .json, .md)
+        /// extension, or into `--out-dir`
+        #[arg(value_name = "FILES", conflicts_with_all = ["input", "output", "watch"])]
+        files: Vec<String>,
+
+        /// With FILES, write the outputs into DIR instead of beside the inputs
+        #[arg(long, value_name = "DIR", requires = "files")]
+        out_dir: Option<PathBuf>,
+
         /// Output file for ASCII diagram (use - for stdout)
         #[arg(short, long)]
         output: Option<PathBuf>,
@@ -108,6 +141,200 @@ pub enum Commands {
         )]
         diamond: DiamondChoice,
 
+        /// Where to place edge labels
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = LabelPlacementChoice::Auto
+        )]
+        label_placement: LabelPlacementChoice,
+
+        /// How a label on every branch of a split (`A -->|ok| B & C`) is drawn
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = SplitLabelsChoice::Shared
+        )]
+        split_labels: SplitLabelsChoice,
+
+        /// Canvas for shapes and edges (braille is experimental, flowcharts only)
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = CanvasChoice::Text
+        )]
+        canvas: CanvasChoice,
+
+        /// In flowcharts, draw edge turns with rounded corners (`╭ ╮ ╰ ╯`)
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true",
+            default_value_t = false,
+            action = ArgAction::Set
+        )]
+        rounded_edges: bool,
+
+        /// In flowcharts, how edges between offset nodes are drawn (an init
+        /// directive's `curve` takes precedence)
+        #[arg(long, value_enum, default_value_t = CurveChoice::Step)]
+        curve: CurveChoice,
+
+        /// In flowcharts, draw nodes and edges with light, heavy (`┏━┓`) or
+        /// double (`╔═╗`) lines
+        #[arg(long, value_enum, default_value_t = LineWeightChoice::Light)]
+        line_weight: LineWeightChoice,
+
+        /// In flowcharts, draw BT and RL diagrams by mirroring TD and LR ones
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true",
+            default_value_t = false,
+            action = ArgAction::Set
+        )]
+        mirror_reversed: bool,
+
+        /// What to do with a diagram larger than `--overflow-limit`
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = OverflowChoice::Compact
+        )]
+        overflow: OverflowChoice,
+
+        /// Largest canvas drawn as-is, as WIDTHxHEIGHT in cells
+        #[arg(long, value_name = "WxH", default_value_t = CanvasLimit::default())]
+        overflow_limit: CanvasLimit,
+
+        /// In flowcharts, fit the output into COLS columns (e.g. the terminal
+        /// width) by wrapping layers, condensing labels and turning wide LR
+        /// charts top-down
+        #[arg(long, value_name = "COLS")]
+        max_width: Option<usize>,
+
+        /// In flowcharts, wrap wide layers onto extra rows to come close to
+        /// these proportions, e.g. 2:1 for slides or 16:9
+        #[arg(long, value_name = "W:H")]
+        aspect: Option<AspectRatio>,
+
+        /// In flowcharts, mark nodes with `click` links as `[1]`, `[2]`, ...
+        /// and list the links below the diagram
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true",
+            default_value_t = false,
+            action = ArgAction::Set
+        )]
+        link_footnotes: bool,
+
+        /// In flowcharts, frame the canvas, tag each node with its layer and
+        /// ID, star edge junctions and list their positions, to help report
+        /// layout issues
+        #[arg(long)]
+        debug_overlay: bool,
+
+        /// Keep a git graph section (branch) expanded when folding long histories
+        /// (repeatable; use `*` to expand everything)
+        #[arg(long, value_name = "SECTION")]
+        expand: Vec<String>,
+
+        /// In git graphs, list the branch drawn in each lane below the graph
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true",
+            default_value_t = false,
+            action = ArgAction::Set
+        )]
+        branch_legend: bool,
+
+        /// In flowcharts, wrap layers with more than N nodes onto extra rows
+        /// (0 = never wrap)
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        max_layer_nodes: usize,
+
+        /// In flowcharts, cap the sweeps spent reordering nodes to reduce
+        /// edge crossings; lower it for very large graphs (0 = name order)
+        #[arg(long, value_name = "N", default_value_t = 4)]
+        crossing_sweeps: usize,
+
+        /// What to do when a flowchart header (`graph LR`) appears again,
+        /// e.g. in concatenated files
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = RepeatedHeadersChoice::Warn
+        )]
+        repeated_headers: RepeatedHeadersChoice,
+
+        /// Whether flowchart node IDs and class names that differ only in
+        /// case (`Start`, `start`) name the same element
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = IdCaseChoice::Sensitive
+        )]
+        id_case: IdCaseChoice,
+
+        /// In sequence diagrams, number the arrows and list full messages below
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true",
+            default_value_t = false,
+            action = ArgAction::Set
+        )]
+        message_index: bool,
+
+        /// In sequence diagrams, draw a hop (`─(─`) where a message passes
+        /// another lifeline instead of a junction (`─┼─`)
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true",
+            default_value_t = false,
+            action = ArgAction::Set
+        )]
+        lifeline_hops: bool,
+
+        /// In sequence diagrams, the left-to-right order of participants
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = ParticipantOrderChoice::Declared
+        )]
+        participant_order: ParticipantOrderChoice,
+
+        /// In sequence diagrams, repeat the participant boxes at the bottom
+        #[arg(
+            long,
+            value_name = "BOOL",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "true",
+            default_value_t = false,
+            action = ArgAction::Set
+        )]
+        mirror_participants: bool,
+
+        /// In sequence diagrams, extend lifelines N rows past the last message
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        lifeline_padding: usize,
+
         /// When to use colors in output
         #[arg(
             long,
@@ -115,6 +342,42 @@ pub enum Commands {
             default_value_t = ColorChoice::Auto
         )]
         color: ColorChoice,
+
+        /// With color, give flowchart nodes and edges without a classDef the
+        /// colors of a Mermaid theme (default, dark, forest, neutral) or of
+        /// key=color pairs, e.g. stroke=#f80,fill=none (an init directive's
+        /// `theme` takes precedence)
+        #[arg(long, value_name = "THEME")]
+        theme: Option<Theme>,
+
+        /// Output format (html wraps the diagram in a styled `<pre>` block,
+        /// cells writes the drawing as a JSON grid of cells, json dumps the
+        /// parsed flowchart for debugging)
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = FormatChoice::Text
+        )]
+        format: FormatChoice,
+
+        /// Read the input as a Markdown document and draw each ```mermaid
+        /// block in place, leaving the rest of the document unchanged
+        #[arg(long, conflicts_with = "format")]
+        markdown: bool,
+
+        /// With `--markdown`, how drawn diagrams replace the mermaid blocks
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = MarkdownFenceChoice::Text,
+            requires = "markdown"
+        )]
+        markdown_fence: MarkdownFenceChoice,
+
+        /// Re-render whenever the input file changes, clearing the terminal
+        /// before each drawing (or rewriting `--output`)
+        #[arg(long, requires = "input")]
+        watch: bool,
     },
 
     /// Detect diagram type in input
@@ -129,6 +392,10 @@ pub enum Commands {
         /// Show in JSON format
         #[arg(long)]
         json: bool,
+
+        /// Describe supported statements, shapes, edges and output options
+        #[arg(long)]
+        capabilities: bool,
     },
 
     /// Validate Mermaid.js syntax
@@ -137,6 +404,87 @@ pub enum Commands {
         #[arg(short, long)]
         input: Option<PathBuf>,
     },
+
+    /// Report counts, depth and estimated size of a diagram without rendering it
+    Analyze {
+        /// Input file to read (use - for stdin)
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        /// Show in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Describe a single flowchart node: label, shape, classes, edges, subgraph
+    Describe {
+        /// Input file to read (use - for stdin)
+        input: Option<PathBuf>,
+
+        /// ID of the node to describe
+        #[arg(long)]
+        node: String,
+
+        /// Show in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Convert ASCII box-and-arrow art back to Mermaid (experimental)
+    Ingest {
+        /// Input file containing `+--+` boxes and `-->` links (use - for stdin)
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+
+        /// Output file for the Mermaid flowchart (use - for stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Render a diagram repeatedly and report per-stage timing percentiles
+    Bench {
+        /// Input file to benchmark (use - for stdin)
+        input: Option<PathBuf>,
+
+        /// Number of timed runs
+        #[arg(long, value_name = "N", default_value_t = 100)]
+        iterations: usize,
+
+        /// Untimed runs before the timed ones
+        #[arg(long, value_name = "N", default_value_t = 5)]
+        warmup: usize,
+
+        /// Show in JSON format
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Enter Mermaid statements interactively, re-rendering after each one
+    Repl {
+        /// Character set to use for rendering output
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = StyleChoice::Unicode
+        )]
+        style: StyleChoice,
+    },
+
+    /// Run as an mdBook preprocessor, drawing ```mermaid blocks as text
+    Mdbook {
+        #[command(subcommand)]
+        action: Option<MdbookAction>,
+    },
+}
+
+/// Calls mdBook makes besides preprocessing a book
+#[derive(Subcommand)]
+pub enum MdbookAction {
+    /// Exit successfully if the named renderer should be preprocessed
+    Supports {
+        /// Renderer name, e.g. `markdown` or `html`
+        renderer: String,
+    },
 }
 
 /// Supported output character sets
@@ -181,6 +529,189 @@ impl From<DiamondChoice> for DiamondStyle {
     }
 }
 
+/// Edge label placement strategies
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum LabelPlacementChoice {
+    /// Place labels near the target end of the edge
+    #[default]
+    Auto,
+    /// In top-down diagrams, place labels beside vertical lines (├ yes)
+    Beside,
+}
+
+impl From<LabelPlacementChoice> for LabelPlacement {
+    fn from(value: LabelPlacementChoice) -> Self {
+        match value {
+            LabelPlacementChoice::Auto => LabelPlacement::Auto,
+            LabelPlacementChoice::Beside => LabelPlacement::Beside,
+        }
+    }
+}
+
+/// Drawing of edges between offset flowchart nodes
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum CurveChoice {
+    /// Right-angle turns
+    #[default]
+    Step,
+    /// Cut short hops across with `/` and `\`
+    Linear,
+}
+
+impl From<CurveChoice> for EdgeCurve {
+    fn from(value: CurveChoice) -> Self {
+        match value {
+            CurveChoice::Step => EdgeCurve::Step,
+            CurveChoice::Linear => EdgeCurve::Linear,
+        }
+    }
+}
+
+/// Weight of flowchart node and edge lines
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum LineWeightChoice {
+    /// Thin lines
+    #[default]
+    Light,
+    /// Heavy lines; subgraphs and thick edges stay double
+    Heavy,
+    /// Double lines; subgraphs and thick edges turn heavy
+    Double,
+}
+
+impl From<LineWeightChoice> for LineWeight {
+    fn from(value: LineWeightChoice) -> Self {
+        match value {
+            LineWeightChoice::Light => LineWeight::Light,
+            LineWeightChoice::Heavy => LineWeight::Heavy,
+            LineWeightChoice::Double => LineWeight::Double,
+        }
+    }
+}
+
+/// Drawing of labels shared by every branch of a split
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum SplitLabelsChoice {
+    /// Draw the label once, beside the line before the junction
+    #[default]
+    Shared,
+    /// Repeat the label on every branch
+    PerBranch,
+}
+
+impl From<SplitLabelsChoice> for SplitLabels {
+    fn from(value: SplitLabelsChoice) -> Self {
+        match value {
+            SplitLabelsChoice::Shared => SplitLabels::Shared,
+            SplitLabelsChoice::PerBranch => SplitLabels::PerBranch,
+        }
+    }
+}
+
+/// Handling of diagrams over the canvas limit
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum OverflowChoice {
+    /// Draw the diagram however large it is
+    Render,
+    /// Redraw with short labels and wrapped layers, else print a notice
+    #[default]
+    Compact,
+    /// Print a one-line notice with the diagram's size
+    Summary,
+    /// Fail with an error
+    Error,
+}
+
+impl From<OverflowChoice> for OverflowPolicy {
+    fn from(value: OverflowChoice) -> Self {
+        match value {
+            OverflowChoice::Render => OverflowPolicy::Render,
+            OverflowChoice::Compact => OverflowPolicy::Compact,
+            OverflowChoice::Summary => OverflowPolicy::Summary,
+            OverflowChoice::Error => OverflowPolicy::Error,
+        }
+    }
+}
+
+/// Case handling for node IDs and class names
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum IdCaseChoice {
+    /// Keep IDs as written and warn when two differ only in case
+    #[default]
+    Sensitive,
+    /// Treat IDs that differ only in case as the same element
+    Insensitive,
+}
+
+impl From<IdCaseChoice> for IdCase {
+    fn from(value: IdCaseChoice) -> Self {
+        match value {
+            IdCaseChoice::Sensitive => IdCase::Sensitive,
+            IdCaseChoice::Insensitive => IdCase::Insensitive,
+        }
+    }
+}
+
+/// Policies for repeated flowchart headers
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum RepeatedHeadersChoice {
+    /// Merge into one diagram and log a warning
+    #[default]
+    Warn,
+    /// Fail with an error
+    Error,
+    /// Render each diagram separately
+    Split,
+}
+
+impl From<RepeatedHeadersChoice> for RepeatedHeaders {
+    fn from(value: RepeatedHeadersChoice) -> Self {
+        match value {
+            RepeatedHeadersChoice::Warn => RepeatedHeaders::Warn,
+            RepeatedHeadersChoice::Error => RepeatedHeaders::Error,
+            RepeatedHeadersChoice::Split => RepeatedHeaders::Split,
+        }
+    }
+}
+
+/// Sequence diagram participant orders
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum ParticipantOrderChoice {
+    /// Order of first mention
+    FirstMention,
+    /// Declared participants first, in declaration order
+    #[default]
+    Declared,
+}
+
+impl From<ParticipantOrderChoice> for ParticipantOrder {
+    fn from(value: ParticipantOrderChoice) -> Self {
+        match value {
+            ParticipantOrderChoice::FirstMention => ParticipantOrder::FirstMention,
+            ParticipantOrderChoice::Declared => ParticipantOrder::Declared,
+        }
+    }
+}
+
+/// Canvas modes for shapes and edges
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum CanvasChoice {
+    /// One box-drawing glyph per cell
+    #[default]
+    Text,
+    /// Braille dots (2x4 per cell) for smoother diagonals
+    Braille,
+}
+
+impl From<CanvasChoice> for CanvasMode {
+    fn from(value: CanvasChoice) -> Self {
+        match value {
+            CanvasChoice::Text => CanvasMode::Text,
+            CanvasChoice::Braille => CanvasMode::Braille,
+        }
+    }
+}
+
 /// When to colorize output
 #[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
 pub enum ColorChoice {
@@ -193,6 +724,52 @@ pub enum ColorChoice {
     Never,
 }
 
+/// Output formats for converted diagrams
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum FormatChoice {
+    /// Plain text, with ANSI colors when enabled
+    #[default]
+    Text,
+    /// HTML `<pre>` block with colors as inline styles
+    Html,
+    /// JSON cell grid (character, style and owning node per cell), one line
+    /// per diagram
+    Cells,
+    /// Parsed flowchart as JSON (nodes, edges, subgraphs and parse
+    /// warnings), one line per diagram; nothing is drawn
+    Json,
+}
+
+/// How `--markdown` output replaces mermaid blocks
+#[derive(Copy, Clone, Debug, ValueEnum, PartialEq, Eq, Default)]
+pub enum MarkdownFenceChoice {
+    /// In a ```text block
+    #[default]
+    Text,
+    /// As bare lines, without a fence
+    Bare,
+}
+
+impl From<MarkdownFenceChoice> for MarkdownFence {
+    fn from(value: MarkdownFenceChoice) -> Self {
+        match value {
+            MarkdownFenceChoice::Text => MarkdownFence::Text,
+            MarkdownFenceChoice::Bare => MarkdownFence::Bare,
+        }
+    }
+}
+
+/// How `convert` turns an input document into output text
+struct DocumentOptions {
+    skip_detection: bool,
+    style: CharacterSet,
+    repeated_headers: RepeatedHeadersChoice,
+    format: FormatChoice,
+    markdown: bool,
+    markdown_fence: MarkdownFenceChoice,
+    should_colorize: bool,
+}
+
 /// Main CLI application
 pub struct FigureheadApp {
     orchestrator: Orchestrator,
@@ -211,8 +788,15 @@ impl FigureheadApp {
         Self { orchestrator }
     }
 
-    fn build_config(style: StyleChoice, diamond: DiamondChoice) -> RenderConfig {
+    pub(crate) fn build_config(
+        style: StyleChoice,
+        diamond: DiamondChoice,
+        label_placement: LabelPlacementChoice,
+        canvas: CanvasChoice,
+    ) -> RenderConfig {
         RenderConfig::new(style.into(), diamond.into())
+            .with_label_placement(label_placement.into())
+            .with_canvas_mode(canvas.into())
     }
 
     /// Run the application with the given CLI arguments
@@ -227,6 +811,13 @@ impl FigureheadApp {
             .ok()
             .or_else(|| Some(cli.log_format.as_str().to_string()));
 
+        // mdBook reads the book back from stdout, where logs would go; a
+        // benchmark would time its own logging
+        let log_level_str = match cli.command {
+            Commands::Mdbook { .. } | Commands::Bench { .. } => Some("off".to_string()),
+            _ => log_level_str,
+        };
+
         // Reinitialize logging with CLI/environment settings
         if let Err(e) = init_logging(log_level_str.as_deref(), log_format_str.as_deref()) {
             eprintln!("Warning: Failed to initialize logging: {}", e);
@@ -239,23 +830,100 @@ impl FigureheadApp {
         match cli.command {
             Commands::Convert {
                 input,
+                files,
+                out_dir,
                 output,
                 skip_detection,
                 style,
                 diamond,
+                label_placement,
+                split_labels,
+                canvas,
+                rounded_edges,
+                curve,
+                line_weight,
+                mirror_reversed,
+                overflow,
+                overflow_limit,
+                max_width,
+                aspect,
+                link_footnotes,
+                debug_overlay,
+                expand,
+                branch_legend,
+                max_layer_nodes,
+                crossing_sweeps,
+                repeated_headers,
+                id_case,
+                message_index,
+                lifeline_hops,
+                participant_order,
+                mirror_participants,
+                lifeline_padding,
                 color,
+                theme,
+                format,
+                markdown,
+                markdown_fence,
+                watch,
             } => self.convert_command(
                 input,
+                files,
+                out_dir,
                 output,
                 skip_detection,
                 style,
                 diamond,
+                label_placement,
+                split_labels,
+                canvas,
+                rounded_edges,
+                curve,
+                line_weight,
+                mirror_reversed,
+                overflow,
+                overflow_limit,
+                max_width,
+                aspect,
+                link_footnotes,
+                debug_overlay,
+                expand,
+                branch_legend,
+                max_layer_nodes,
+                crossing_sweeps,
+                repeated_headers,
+                id_case,
+                message_index,
+                lifeline_hops,
+                participant_order,
+                mirror_participants,
+                lifeline_padding,
                 color,
+                theme,
+                format,
+                markdown,
+                markdown_fence,
+                watch,
                 cli.verbose,
             ),
             Commands::Detect { input } => self.detect_command(input, cli.verbose),
-            Commands::Types { json } => self.types_command(json, cli.verbose),
+            Commands::Types { json, capabilities } => {
+                self.types_command(json, capabilities, cli.verbose)
+            }
             Commands::Validate { input } => self.validate_command(input, cli.verbose),
+            Commands::Analyze { input, json } => self.analyze_command(input, json, cli.verbose),
+            Commands::Describe { input, node, json } => {
+                self.describe_command(input, &node, json, cli.verbose)
+            }
+            Commands::Ingest { input, output } => self.ingest_command(input, output, cli.verbose),
+            Commands::Bench {
+                input,
+                iterations,
+                warmup,
+                json,
+            } => self.bench_command(input, iterations, warmup, json, cli.verbose),
+            Commands::Repl { style } => self.repl_command(style, cli.verbose),
+            Commands::Mdbook { action } => self.mdbook_command(action, cli.config),
         }
     }
 
@@ -264,66 +932,291 @@ impl FigureheadApp {
     fn convert_command(
         &mut self,
         input: Option<PathBuf>,
+        files: Vec<String>,
+        out_dir: Option<PathBuf>,
         output: Option<PathBuf>,
         skip_detection: bool,
         style: StyleChoice,
         diamond: DiamondChoice,
+        label_placement: LabelPlacementChoice,
+        split_labels: SplitLabelsChoice,
+        canvas: CanvasChoice,
+        rounded_edges: bool,
+        curve: CurveChoice,
+        line_weight: LineWeightChoice,
+        mirror_reversed: bool,
+        overflow: OverflowChoice,
+        overflow_limit: CanvasLimit,
+        max_width: Option<usize>,
+        aspect: Option<AspectRatio>,
+        link_footnotes: bool,
+        debug_overlay: bool,
+        expand: Vec<String>,
+        branch_legend: bool,
+        max_layer_nodes: usize,
+        crossing_sweeps: usize,
+        repeated_headers: RepeatedHeadersChoice,
+        id_case: IdCaseChoice,
+        message_index: bool,
+        lifeline_hops: bool,
+        participant_order: ParticipantOrderChoice,
+        mirror_participants: bool,
+        lifeline_padding: usize,
         color: ColorChoice,
+        theme: Option<Theme>,
+        format: FormatChoice,
+        markdown: bool,
+        markdown_fence: MarkdownFenceChoice,
+        watch: bool,
         verbose: bool,
     ) -> Result<()> {
-        // Read input
-        let content = self.read_input(input)?;
-
-        if verbose {
-            eprintln!("Read {} bytes of input", content.len());
-        }
+        // HTML is never viewed in the terminal, so `auto` keeps the colors;
+        // Markdown is written for other tools, so only `always` colors it
+        let should_colorize = match format {
+            _ if markdown => color == ColorChoice::Always,
+            FormatChoice::Text if !files.is_empty() => color == ColorChoice::Always,
+            FormatChoice::Text => self.should_colorize(&output, color),
+            FormatChoice::Html | FormatChoice::Cells => color != ColorChoice::Never,
+            FormatChoice::Json => false,
+        };
 
-        // Apply style and diamond options to renderer
-        let config = Self::build_config(style, diamond);
+        // Apply style, diamond, label and canvas options to renderer; color
+        // turns on node style colors for flowcharts, the role palette for
+        // sequence, state and class diagrams and branch colors for git graphs
+        let config = Self::build_config(style, diamond, label_placement, canvas)
+            .with_split_labels(split_labels.into())
+            .with_rounded_edges(rounded_edges)
+            .with_edge_curve(curve.into())
+            .with_line_weight(line_weight.into())
+            .with_mirror_reversed(mirror_reversed)
+            .with_overflow_policy(overflow.into())
+            .with_overflow_limit(overflow_limit)
+            .with_max_width(max_width)
+            .with_aspect_hint(aspect)
+            .with_link_footnotes(link_footnotes)
+            .with_debug_overlay(debug_overlay)
+            .with_color(should_colorize)
+            .with_theme(theme);
         let mut orchestrator = Orchestrator::all_plugins(config);
         orchestrator.register_default_detectors();
+        orchestrator.set_flowchart_renderer(
+            FlowchartRenderer::with_config(config).with_layout_config(LayoutConfig {
+                max_layer_nodes,
+                crossing_sweeps,
+                ..LayoutConfig::default()
+            }),
+        );
+        let folding = expand
+            .into_iter()
+            .fold(FoldOptions::default(), FoldOptions::with_expanded);
+        orchestrator.set_gitgraph_renderer(
+            GitGraphRenderer::with_style(config.style)
+                .with_folding(folding)
+                .with_legend(branch_legend)
+                .with_color(should_colorize),
+        );
+        orchestrator.set_sequence_renderer(
+            SequenceRenderer::with_style(config.style)
+                .with_message_index(message_index)
+                .with_hops(lifeline_hops)
+                .with_participant_order(participant_order.into())
+                .with_mirrored_participants(mirror_participants)
+                .with_lifeline_padding(lifeline_padding)
+                .with_color(should_colorize),
+        );
+        orchestrator.set_flowchart_parser(
+            FlowchartParser::new()
+                .with_repeated_headers(repeated_headers.into())
+                .with_id_case(id_case.into()),
+        );
+        orchestrator.set_class_parser(ClassParser::new().with_id_case(id_case.into()));
         self.orchestrator = orchestrator;
 
-        // Process the diagram
-        // For flowcharts, we can get the database for proper style extraction
-        let should_colorize = self.should_colorize(&output, color);
-
-        let (ascii_output, styles) = if skip_detection {
-            // Direct flowchart processing - use database for styles
-            let (output, db) = self
-                .orchestrator
-                .process_flowchart_with_database(&content)?;
-            let styles = if should_colorize {
-                StyleInfo::from_database(&db)
-            } else {
-                StyleInfo::default()
-            };
-            (output, styles)
-        } else {
-            // Auto-detection - fall back to text-based style extraction
-            let output = self.orchestrator.process(&content)?;
-            let styles = if should_colorize {
-                extract_styles(&content)
-            } else {
-                StyleInfo::default()
-            };
-            (output, styles)
+        let document = DocumentOptions {
+            skip_detection,
+            style: config.style,
+            repeated_headers,
+            format,
+            markdown,
+            markdown_fence,
+            should_colorize,
         };
 
+        if !files.is_empty() {
+            let (inputs, unmatched) = batch::expand_inputs(&files, io::stdin().lock());
+            let extension = match format {
+                _ if markdown => "md",
+                FormatChoice::Text => "txt",
+                FormatChoice::Html => "html",
+                FormatChoice::Cells | FormatChoice::Json => "json",
+            };
+            let report = batch::run_batch(
+                inputs,
+                unmatched,
+                out_dir.as_deref(),
+                extension,
+                |content| self.render_document(content, &document),
+            );
+            if verbose {
+                for (input, written) in &report.converted {
+                    eprintln!("{} -> {}", input.display(), written.display());
+                }
+            }
+            eprintln!("{}", report);
+            if report.is_success() {
+                return Ok(());
+            }
+            let failed = report.failed.len();
+            return Err(anyhow!(
+                "{} of {} files failed",
+                failed,
+                failed + report.converted.len()
+            ));
+        }
+
+        if watch {
+            // `requires = "input"` guarantees a path
+            let path = input.unwrap_or_default();
+            if path.to_string_lossy() == "-" {
+                return Err(anyhow!("--watch needs an input file, not stdin"));
+            }
+            let to_terminal = output.as_ref().is_none_or(|p| p.to_string_lossy() == "-");
+            return watch::watch(&path, || {
+                let rendered = self
+                    .read_input(Some(path.clone()))
+                    .and_then(|content| self.render_document(&content, &document));
+                if to_terminal {
+                    if let Err(e) = watch::clear_terminal() {
+                        tracing::warn!(error = %e, "Could not clear the terminal");
+                    }
+                    println!("{}", watch::screen(&path, &rendered));
+                    return;
+                }
+                match rendered.and_then(|text| self.write_output(output.clone(), &text)) {
+                    Ok(()) => eprintln!("Rendered {}", path.display()),
+                    Err(e) => eprintln!("Error: {}", e),
+                }
+            });
+        }
+
+        let content = self.read_input(input)?;
+        if verbose {
+            eprintln!("Read {} bytes of input", content.len());
+        }
+        let final_output = self.render_document(&content, &document)?;
+
         if verbose {
             eprintln!("Successfully converted diagram to ASCII");
         }
 
-        // Apply colors if enabled and styles are present
-        let final_output = if should_colorize {
-            colorize_output(&content, &ascii_output, &styles)
-        } else {
-            ascii_output
-        };
         self.write_output(output, &final_output)?;
         Ok(())
     }
 
+    /// Render a whole input document the way `convert` writes it
+    fn render_document(&self, content: &str, options: &DocumentOptions) -> Result<String> {
+        // With `--repeated-headers split`, concatenated flowcharts are
+        // rendered one after another
+        let diagrams = match options.repeated_headers {
+            RepeatedHeadersChoice::Split => split_diagrams(content),
+            _ => vec![content],
+        };
+        Ok(match options.format {
+            _ if options.markdown => {
+                replace_mermaid_blocks(content, options.markdown_fence.into(), |source| {
+                    self.render_diagram(source, options.skip_detection)
+                        .map_err(|e| eprintln!("figurehead: kept a mermaid block: {}", e))
+                        .ok()
+                })
+            }
+            FormatChoice::Text => {
+                // Several diagrams each get a titled rule naming where they start
+                let mut sheet = DiagramSheet::new(options.style);
+                let mut line = 1;
+                for diagram in &diagrams {
+                    let title = format!("Diagram {} (line {})", sheet.len() + 1, line);
+                    let rendered = self.render_diagram(diagram, options.skip_detection)?;
+                    sheet.push((diagrams.len() > 1).then_some(title.as_str()), rendered);
+                    line += diagram.matches('\n').count();
+                }
+                sheet.render()
+            }
+            FormatChoice::Html => diagrams
+                .into_iter()
+                .map(|diagram| {
+                    self.render_html_diagram(
+                        diagram,
+                        options.skip_detection,
+                        options.should_colorize,
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?
+                .join("\n"),
+            FormatChoice::Cells => diagrams
+                .into_iter()
+                .map(|diagram| {
+                    let grid = if options.skip_detection {
+                        self.orchestrator.process_flowchart_cells(diagram)?
+                    } else {
+                        self.orchestrator.process_cells(diagram)?
+                    };
+                    Ok(serde_json::to_string(&grid)?)
+                })
+                .collect::<Result<Vec<_>>>()?
+                .join("\n"),
+            FormatChoice::Json => diagrams
+                .into_iter()
+                .map(|diagram| {
+                    if !options.skip_detection {
+                        let diagram_type = self.orchestrator.detect_diagram_type(diagram)?;
+                        if diagram_type != "flowchart" {
+                            return Err(anyhow!(
+                                "--format json only supports flowcharts so far, not {}",
+                                diagram_type
+                            ));
+                        }
+                    }
+                    let database = self.orchestrator.parse_flowchart(diagram)?;
+                    Ok(flowchart_json(&database).to_string())
+                })
+                .collect::<Result<Vec<_>>>()?
+                .join("\n"),
+        })
+    }
+
+    /// Render one diagram as an HTML `<pre>` block
+    ///
+    /// With color on, flowcharts take their CSS colors from `classDef` and
+    /// `style` statements and other diagrams keep the role palette.
+    fn render_html_diagram(
+        &self,
+        content: &str,
+        skip_detection: bool,
+        should_colorize: bool,
+    ) -> Result<String> {
+        if !should_colorize {
+            let output = self.render_diagram(content, skip_detection)?;
+            return Ok(figurehead::ansi_to_html(&output));
+        }
+        if skip_detection {
+            self.orchestrator.process_flowchart_html(content)
+        } else {
+            self.orchestrator.process_html(content)
+        }
+    }
+
+    /// Render one diagram as text
+    ///
+    /// Colors come from the renderers: node styles for flowcharts, the role
+    /// palette for other diagram types.
+    fn render_diagram(&self, content: &str, skip_detection: bool) -> Result<String> {
+        if skip_detection {
+            self.orchestrator.process_flowchart(content)
+        } else {
+            self.orchestrator.process(content)
+        }
+    }
+
     /// Determine if we should colorize the output based on color choice and output destination
     fn should_colorize(&self, output: &Option<PathBuf>, color: ColorChoice) -> bool {
         match color {
@@ -367,30 +1260,51 @@ impl FigureheadApp {
     }
 
     /// Handle the types command
-    fn types_command(&self, json: bool, verbose: bool) -> Result<()> {
+    fn types_command(&self, json: bool, capabilities: bool, verbose: bool) -> Result<()> {
         if verbose {
             eprintln!("Listing supported diagram types");
         }
 
-        if json {
+        let caps = figurehead::capabilities();
+
+        if capabilities {
+            if json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&capabilities_json(&caps))?
+                );
+            } else {
+                print_capabilities(&caps);
+            }
+        } else if json {
             // JSON output
-            let types = serde_json::json!({
-                "supported_types": [
-                    {
-                        "name": "flowchart",
-                        "description": "Flowchart diagrams with nodes and edges",
+            let supported: Vec<_> = caps
+                .diagram_types
+                .iter()
+                .map(|diagram| {
+                    serde_json::json!({
+                        "name": diagram.name,
+                        "description": diagram.description,
                         "status": "supported"
-                    }
-                ],
-                "total": 1
+                    })
+                })
+                .collect();
+            let types = serde_json::json!({
+                "supported_types": supported,
+                "total": caps.diagram_types.len()
             });
             println!("{}", serde_json::to_string_pretty(&types)?);
         } else {
             // Human-readable output
             println!("Supported diagram types:");
-            println!("  flowchart  - Flowchart diagrams with nodes and edges");
+            for diagram in &caps.diagram_types {
+                println!("  {:<10} - {}", diagram.name, diagram.description);
+            }
             println!();
-            println!("Total: 1 diagram type supported");
+            println!(
+                "Total: {} diagram types supported",
+                caps.diagram_types.len()
+            );
         }
 
         Ok(())
@@ -425,9 +1339,159 @@ impl FigureheadApp {
                     }
                 }
             }
-            Err(_) => {
-                println!("✗ Could not detect diagram type");
-                Err(anyhow!("Unknown diagram type"))
+            Err(_) => {
+                println!("✗ Could not detect diagram type");
+                Err(anyhow!("Unknown diagram type"))
+            }
+        }
+    }
+
+    /// Handle the analyze command
+    fn analyze_command(&self, input: Option<PathBuf>, json: bool, verbose: bool) -> Result<()> {
+        let content = self.read_input(input)?;
+
+        if verbose {
+            eprintln!("Read {} bytes of input", content.len());
+        }
+
+        let stats = figurehead::analyze(&content)?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&stats_json(&stats))?);
+        } else {
+            println!("Type:     {}", stats.diagram_type);
+            println!("Nodes:    {}", stats.nodes);
+            println!("Edges:    {}", stats.edges);
+            println!("Depth:    {}", stats.depth);
+            println!(
+                "Size:     ~{}x{}",
+                stats.estimated_width, stats.estimated_height
+            );
+            if !stats.features.is_empty() {
+                println!("Features: {}", stats.features.join(", "));
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle the bench command
+    fn bench_command(
+        &mut self,
+        input: Option<PathBuf>,
+        iterations: usize,
+        warmup: usize,
+        json: bool,
+        verbose: bool,
+    ) -> Result<()> {
+        if iterations == 0 {
+            return Err(anyhow!("--iterations must be at least 1"));
+        }
+        let content = self.read_input(input)?;
+
+        if verbose {
+            eprintln!("Read {} bytes of input", content.len());
+        }
+
+        let report = run_bench(&mut self.orchestrator, &content, iterations, warmup)?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&bench_json(&report))?);
+        } else {
+            print!("{}", report);
+        }
+        Ok(())
+    }
+
+    /// Handle the describe command
+    fn describe_command(
+        &self,
+        input: Option<PathBuf>,
+        node: &str,
+        json: bool,
+        verbose: bool,
+    ) -> Result<()> {
+        let content = self.read_input(input)?;
+
+        if verbose {
+            eprintln!("Read {} bytes of input", content.len());
+        }
+
+        let description = self.describe_node(&content, node)?;
+        if json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&description_json(&description))?
+            );
+        } else {
+            print!("{}", description);
+        }
+        Ok(())
+    }
+
+    /// Parse flowchart input and describe one of its nodes
+    fn describe_node(&self, content: &str, node: &str) -> Result<NodeDescription> {
+        let diagram_type = self.orchestrator.detect_diagram_type(content)?;
+        if diagram_type != "flowchart" {
+            return Err(anyhow!(
+                "describe supports flowcharts only (detected {})",
+                diagram_type
+            ));
+        }
+
+        let database = self.orchestrator.parse_flowchart(content)?;
+        database
+            .describe_node(node)
+            .ok_or_else(|| anyhow!("No node '{}' in diagram", node))
+    }
+
+    /// Handle the ingest command
+    fn ingest_command(
+        &self,
+        input: Option<PathBuf>,
+        output: Option<PathBuf>,
+        verbose: bool,
+    ) -> Result<()> {
+        let content = self.read_input(input)?;
+        let database = ingest_ascii(&content)?;
+
+        if verbose {
+            eprintln!(
+                "Found {} boxes and {} links",
+                database.node_count(),
+                database.edge_count()
+            );
+        }
+
+        self.write_output(output, &database.to_mermaid())
+    }
+
+    /// Handle the repl command
+    fn repl_command(&self, style: StyleChoice, verbose: bool) -> Result<()> {
+        let config = RenderConfig::new(style.into(), DiamondChoice::Box.into());
+        let mut orchestrator = Orchestrator::all_plugins(config);
+        orchestrator.register_default_detectors();
+
+        let interactive = crossterm::tty::IsTty::is_tty(&io::stdin());
+        if interactive || verbose {
+            eprintln!("figurehead repl - enter Mermaid statements, :help for commands");
+        }
+        run_repl(&orchestrator, io::stdin().lock(), io::stdout(), interactive)
+    }
+
+    /// Handle the mdbook command
+    ///
+    /// The preprocessor reads the same config file as `convert`.
+    fn mdbook_command(&self, action: Option<MdbookAction>, config: Option<PathBuf>) -> Result<()> {
+        match action {
+            Some(MdbookAction::Supports { renderer }) => {
+                if !supports_renderer(&renderer) {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+            None => {
+                let defaults = config::load(config)?
+                    .map(|config| config.options())
+                    .unwrap_or_default();
+                run_preprocessor(io::stdin().lock(), io::stdout().lock(), defaults)
             }
         }
     }
@@ -506,6 +1570,203 @@ impl Default for FigureheadApp {
     }
 }
 
+/// Diagram statistics as JSON, for documentation pipelines
+fn stats_json(stats: &DiagramStats) -> serde_json::Value {
+    serde_json::json!({
+        "type": stats.diagram_type,
+        "nodes": stats.nodes,
+        "edges": stats.edges,
+        "depth": stats.depth,
+        "estimated_width": stats.estimated_width,
+        "estimated_height": stats.estimated_height,
+        "features": stats.features,
+    })
+}
+
+/// Node description as JSON, for editor integrations
+fn flowchart_json(database: &FlowchartDatabase) -> serde_json::Value {
+    let marker = |terminator: EdgeTerminator| match terminator {
+        EdgeTerminator::Arrow => Some("arrow"),
+        EdgeTerminator::Open => Some("circle"),
+        EdgeTerminator::Cross => Some("cross"),
+        EdgeTerminator::Diamond { filled: true } => Some("diamond"),
+        EdgeTerminator::Diamond { filled: false } => Some("hollow_diamond"),
+        EdgeTerminator::Triangle => Some("triangle"),
+        EdgeTerminator::None => None,
+    };
+    let nodes: Vec<serde_json::Value> = database
+        .nodes()
+        .map(|node| {
+            serde_json::json!({
+                "id": node.id,
+                "label": node.label,
+                "shape": node.shape.to_string(),
+                "classes": node.classes,
+                "link": node.link.as_ref().map(|link| serde_json::json!({
+                    "url": link.url,
+                    "tooltip": link.tooltip,
+                })),
+                "hidden": database.is_node_hidden(&node.id),
+            })
+        })
+        .collect();
+    let edges: Vec<serde_json::Value> = database
+        .edges()
+        .map(|edge| {
+            serde_json::json!({
+                "from": edge.from,
+                "to": edge.to,
+                "type": edge.edge_type.to_string(),
+                "label": edge.label,
+                "start": marker(edge.start),
+                "min_length": edge.min_length,
+            })
+        })
+        .collect();
+    let subgraphs: Vec<serde_json::Value> = database
+        .subgraphs()
+        .map(|subgraph| {
+            serde_json::json!({
+                "id": subgraph.id,
+                "title": subgraph.title,
+                "members": subgraph.members,
+                "direction": subgraph.direction.map(|d| d.to_string()),
+                "parent": subgraph.parent,
+            })
+        })
+        .collect();
+    let warnings: Vec<serde_json::Value> = database
+        .parse_warnings()
+        .iter()
+        .map(|warning| {
+            serde_json::json!({
+                "message": warning.message,
+                "line": warning.line,
+                "column": warning.column,
+                "statement": warning.statement,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "type": "flowchart",
+        "direction": database.direction().to_string(),
+        "nodes": nodes,
+        "edges": edges,
+        "subgraphs": subgraphs,
+        "warnings": warnings,
+    })
+}
+
+fn description_json(description: &NodeDescription) -> serde_json::Value {
+    let edges = |edges: &[EdgeSummary]| -> Vec<serde_json::Value> {
+        edges
+            .iter()
+            .map(|e| {
+                serde_json::json!({
+                    "from": e.from,
+                    "to": e.to,
+                    "type": e.edge_type.to_string(),
+                    "label": e.label,
+                })
+            })
+            .collect()
+    };
+    serde_json::json!({
+        "id": description.id,
+        "label": description.label,
+        "shape": description.shape.to_string(),
+        "classes": description.classes,
+        "subgraph": description.subgraph,
+        "incoming": edges(&description.incoming),
+        "outgoing": edges(&description.outgoing),
+    })
+}
+
+/// Full capability description as JSON, for editor integrations
+fn capabilities_json(caps: &Capabilities) -> serde_json::Value {
+    let diagram_types: Vec<_> = caps
+        .diagram_types
+        .iter()
+        .map(|diagram| {
+            let statements: Vec<_> = diagram
+                .statements
+                .iter()
+                .map(|s| serde_json::json!({ "name": s.name, "syntax": s.syntax }))
+                .collect();
+            serde_json::json!({
+                "name": diagram.name,
+                "description": diagram.description,
+                "keywords": diagram.keywords,
+                "statements": statements,
+            })
+        })
+        .collect();
+    let shapes: Vec<_> = caps
+        .shapes
+        .iter()
+        .map(|s| serde_json::json!({ "name": s.name, "syntax": s.syntax }))
+        .collect();
+    let edge_types: Vec<_> = caps
+        .edge_types
+        .iter()
+        .map(|e| {
+            serde_json::json!({
+                "syntax": e.syntax,
+                "arrow": e.arrow,
+                "dotted": e.dotted,
+                "thick": e.thick,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "version": caps.version,
+        "diagram_types": diagram_types,
+        "shapes": shapes,
+        "edge_types": edge_types,
+        "output": {
+            "character_sets": caps.output.character_sets,
+            "diamond_styles": caps.output.diamond_styles,
+            "label_placements": caps.output.label_placements,
+            "split_labels": caps.output.split_labels,
+            "edge_curves": caps.output.edge_curves,
+            "line_weights": caps.output.line_weights,
+            "themes": caps.output.themes,
+            "canvas_modes": caps.output.canvas_modes,
+        },
+    })
+}
+
+fn print_capabilities(caps: &Capabilities) {
+    println!("figurehead {}", caps.version);
+    println!();
+    for diagram in &caps.diagram_types {
+        println!("{} ({})", diagram.name, diagram.keywords.join(", "));
+        for statement in &diagram.statements {
+            println!("  {:<12} {}", statement.name, statement.syntax);
+        }
+    }
+    println!();
+    println!("Shapes:");
+    for shape in &caps.shapes {
+        println!("  {:<14} {}", shape.name, shape.syntax);
+    }
+    let edges: Vec<_> = caps.edge_types.iter().map(|e| e.syntax.as_str()).collect();
+    println!("Edges: {}", edges.join(" "));
+    println!();
+    println!("Styles: {}", caps.output.character_sets.join(", "));
+    println!("Diamonds: {}", caps.output.diamond_styles.join(", "));
+    println!(
+        "Label placements: {}",
+        caps.output.label_placements.join(", ")
+    );
+    println!("Split labels: {}", caps.output.split_labels.join(", "));
+    println!("Edge curves: {}", caps.output.edge_curves.join(", "));
+    println!("Line weights: {}", caps.output.line_weights.join(", "));
+    println!("Themes: {}", caps.output.themes.join(", "));
+    println!("Canvases: {}", caps.output.canvas_modes.join(", "));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -531,18 +1792,80 @@ mod tests {
         match cli.command {
             Commands::Convert {
                 input,
+                files,
+                out_dir,
                 output,
                 skip_detection,
                 style,
                 diamond,
+                label_placement,
+                split_labels,
+                canvas,
+                rounded_edges,
+                curve,
+                line_weight,
+                mirror_reversed,
+                overflow,
+                overflow_limit,
+                max_width,
+                aspect,
+                link_footnotes,
+                debug_overlay,
+                expand,
+                branch_legend,
+                max_layer_nodes,
+                crossing_sweeps,
+                repeated_headers,
+                id_case,
+                message_index,
+                lifeline_hops,
+                participant_order,
+                mirror_participants,
+                lifeline_padding,
                 color,
+                theme,
+                format,
+                markdown,
+                markdown_fence,
+                watch,
             } => {
                 assert_eq!(input.unwrap().to_string_lossy(), "test.mmd");
                 assert_eq!(output.unwrap().to_string_lossy(), "output.txt");
                 assert!(!skip_detection);
                 assert_eq!(style, StyleChoice::Ascii);
                 assert_eq!(diamond, DiamondChoice::Box); // default
+                assert_eq!(label_placement, LabelPlacementChoice::Auto); // default
+                assert_eq!(split_labels, SplitLabelsChoice::Shared); // default
+                assert_eq!(canvas, CanvasChoice::Text); // default
+                assert!(!rounded_edges); // default
+                assert_eq!(curve, CurveChoice::Step); // default
+                assert_eq!(line_weight, LineWeightChoice::Light); // default
+                assert!(!mirror_reversed); // default
+                assert_eq!(overflow, OverflowChoice::Compact); // default
+                assert_eq!(overflow_limit, CanvasLimit::default()); // default
+                assert_eq!(max_width, None); // default
+                assert_eq!(aspect, None); // default
+                assert!(!link_footnotes); // default
+                assert!(!debug_overlay); // default
+                assert!(expand.is_empty()); // default
+                assert!(!branch_legend); // default
+                assert_eq!(max_layer_nodes, 0); // default
+                assert_eq!(crossing_sweeps, 4); // default
+                assert_eq!(repeated_headers, RepeatedHeadersChoice::Warn); // default
+                assert_eq!(id_case, IdCaseChoice::Sensitive); // default
+                assert!(!message_index); // default
+                assert!(!lifeline_hops); // default
+                assert_eq!(participant_order, ParticipantOrderChoice::Declared); // default
+                assert!(!mirror_participants); // default
+                assert_eq!(lifeline_padding, 0); // default
                 assert_eq!(color, ColorChoice::Auto); // default
+                assert_eq!(theme, None);
+                assert_eq!(format, FormatChoice::Text); // default
+                assert!(!markdown); // default
+                assert_eq!(markdown_fence, MarkdownFenceChoice::Text); // default
+                assert!(!watch); // default
+                assert!(files.is_empty()); // default
+                assert_eq!(out_dir, None); // default
             }
             _ => panic!("Expected Convert command"),
         }
@@ -561,6 +1884,302 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_parsing_label_placement_option() {
+        let args = vec!["figurehead", "convert", "--label-placement", "beside"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert {
+                label_placement, ..
+            } => {
+                assert_eq!(label_placement, LabelPlacementChoice::Beside);
+            }
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_canvas_option() {
+        let args = vec!["figurehead", "convert", "--canvas", "braille"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert { canvas, .. } => {
+                assert_eq!(canvas, CanvasChoice::Braille);
+            }
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_batch_files() {
+        let args = vec![
+            "figurehead",
+            "convert",
+            "docs/*.mmd",
+            "extra.mmd",
+            "--out-dir",
+            "out",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert { files, out_dir, .. } => {
+                assert_eq!(files, ["docs/*.mmd", "extra.mmd"]);
+                assert_eq!(out_dir.unwrap().to_string_lossy(), "out");
+            }
+            _ => panic!("Expected Convert command"),
+        }
+
+        // Batch output goes to files, so a single output or input is an error
+        assert!(Cli::try_parse_from(["figurehead", "convert", "a.mmd", "-o", "x.txt"]).is_err());
+        assert!(Cli::try_parse_from(["figurehead", "convert", "--out-dir", "out"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_max_width_option() {
+        let args = vec!["figurehead", "convert", "--max-width", "80"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert { max_width, .. } => assert_eq!(max_width, Some(80)),
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_aspect_option() {
+        let args = vec!["figurehead", "convert", "--aspect", "16:9"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert { aspect, .. } => {
+                assert_eq!(aspect, Some(AspectRatio::new(16, 9)))
+            }
+            _ => panic!("Expected Convert command"),
+        }
+        assert!(Cli::try_parse_from(["figurehead", "convert", "--aspect", "wide"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parsing_message_index_flag() {
+        let args = vec!["figurehead", "convert", "--message-index"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert { message_index, .. } => assert!(message_index),
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_lifeline_hops_flag() {
+        let args = vec!["figurehead", "convert", "--lifeline-hops"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert { lifeline_hops, .. } => assert!(lifeline_hops),
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_sequence_footer_options() {
+        let args = vec![
+            "figurehead",
+            "convert",
+            "--mirror-participants",
+            "--lifeline-padding",
+            "3",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert {
+                mirror_participants,
+                lifeline_padding,
+                ..
+            } => {
+                assert!(mirror_participants);
+                assert_eq!(lifeline_padding, 3);
+            }
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_participant_order_option() {
+        let args = vec![
+            "figurehead",
+            "convert",
+            "--participant-order",
+            "first-mention",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert {
+                participant_order, ..
+            } => assert_eq!(participant_order, ParticipantOrderChoice::FirstMention),
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_crossing_sweeps_option() {
+        let args = vec!["figurehead", "convert", "--crossing-sweeps", "1"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert {
+                crossing_sweeps, ..
+            } => assert_eq!(crossing_sweeps, 1),
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_max_layer_nodes_option() {
+        let args = vec!["figurehead", "convert", "--max-layer-nodes", "6"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert {
+                max_layer_nodes, ..
+            } => assert_eq!(max_layer_nodes, 6),
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_repeated_headers_option() {
+        let args = vec!["figurehead", "convert", "--repeated-headers", "split"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert {
+                repeated_headers, ..
+            } => assert_eq!(repeated_headers, RepeatedHeadersChoice::Split),
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_bench_command() {
+        let args = vec!["figurehead", "bench", "flow.mmd", "--iterations", "20"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Bench {
+                input,
+                iterations,
+                warmup,
+                json,
+            } => {
+                assert_eq!(input, Some(PathBuf::from("flow.mmd")));
+                assert_eq!(iterations, 20);
+                assert_eq!(warmup, 5); // default
+                assert!(!json);
+            }
+            _ => panic!("Expected Bench command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_format_option() {
+        let args = vec!["figurehead", "convert", "--format", "html"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert { format, .. } => assert_eq!(format, FormatChoice::Html),
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_json_format_dumps_the_parsed_flowchart() {
+        let app = FigureheadApp::new();
+        let options = DocumentOptions {
+            skip_detection: false,
+            style: CharacterSet::Unicode,
+            repeated_headers: RepeatedHeadersChoice::Warn,
+            format: FormatChoice::Json,
+            markdown: false,
+            markdown_fence: MarkdownFenceChoice::Text,
+            should_colorize: false,
+        };
+
+        let input = "graph LR\n  A[Start] <-->|go| B{Ok?}\n  subgraph \"Checks\"\n    B --> C\n  end\n  ~~~";
+        let output = app.render_document(input, &options).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json["direction"], "LR");
+        assert_eq!(json["nodes"][0]["label"], "Start");
+        assert_eq!(json["nodes"][1]["shape"], "diamond");
+        assert_eq!(json["edges"][0]["label"], "go");
+        assert_eq!(json["edges"][0]["start"], "arrow");
+        assert_eq!(json["subgraphs"][0]["title"], "Checks");
+        assert_eq!(json["warnings"][0]["line"], 6);
+
+        let err = app
+            .render_document("sequenceDiagram\n  A->>B: hi", &options)
+            .unwrap_err();
+        assert!(err.to_string().contains("only supports flowcharts"));
+    }
+
+    #[test]
+    fn test_cli_parsing_markdown_options() {
+        let args = vec![
+            "figurehead",
+            "convert",
+            "--markdown",
+            "--markdown-fence",
+            "bare",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert {
+                markdown,
+                markdown_fence,
+                ..
+            } => {
+                assert!(markdown);
+                assert_eq!(markdown_fence, MarkdownFenceChoice::Bare);
+            }
+            _ => panic!("Expected Convert command"),
+        }
+
+        // The fence only applies to Markdown, which is never HTML
+        assert!(
+            Cli::try_parse_from(["figurehead", "convert", "--markdown-fence", "bare"]).is_err()
+        );
+        assert!(
+            Cli::try_parse_from(["figurehead", "convert", "--markdown", "--format", "html"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parsing_expand_option() {
+        let args = vec![
+            "figurehead",
+            "convert",
+            "--expand",
+            "main",
+            "--expand",
+            "develop",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Convert { expand, .. } => {
+                assert_eq!(expand, vec!["main", "develop"]);
+            }
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
     #[test]
     fn test_cli_parsing_detect_command() {
         let args = vec!["figurehead", "detect", "--input", "test.mmd"];
@@ -580,8 +2199,23 @@ mod tests {
         let cli = Cli::try_parse_from(args).unwrap();
 
         match cli.command {
-            Commands::Types { json } => {
+            Commands::Types { json, capabilities } => {
                 assert!(json);
+                assert!(!capabilities);
+            }
+            _ => panic!("Expected Types command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_capabilities_flag() {
+        let args = vec!["figurehead", "types", "--capabilities", "--json"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Types { json, capabilities } => {
+                assert!(json);
+                assert!(capabilities);
             }
             _ => panic!("Expected Types command"),
         }
@@ -600,6 +2234,102 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_parsing_describe_command() {
+        let args = vec![
+            "figurehead",
+            "describe",
+            "flow.mmd",
+            "--node",
+            "B",
+            "--json",
+        ];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Describe { input, node, json } => {
+                assert_eq!(input.unwrap().to_string_lossy(), "flow.mmd");
+                assert_eq!(node, "B");
+                assert!(json);
+            }
+            _ => panic!("Expected Describe command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parsing_ingest_command() {
+        let args = vec!["figurehead", "ingest", "-i", "legacy.txt", "-o", "flow.mmd"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Ingest { input, output } => {
+                assert_eq!(input.unwrap().to_string_lossy(), "legacy.txt");
+                assert_eq!(output.unwrap().to_string_lossy(), "flow.mmd");
+            }
+            _ => panic!("Expected Ingest command"),
+        }
+    }
+
+    #[test]
+    fn test_stats_json() {
+        let stats = figurehead::analyze("graph LR\n    A --> B --> C").unwrap();
+        let json = stats_json(&stats);
+        assert_eq!(json["type"], "flowchart");
+        assert_eq!(json["depth"], 3);
+        assert!(json["estimated_width"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_cli_parsing_mdbook_command() {
+        let args = vec!["figurehead", "mdbook", "supports", "markdown"];
+        let cli = Cli::try_parse_from(args).unwrap();
+
+        match cli.command {
+            Commands::Mdbook {
+                action: Some(MdbookAction::Supports { renderer }),
+            } => assert_eq!(renderer, "markdown"),
+            _ => panic!("Expected Mdbook supports command"),
+        }
+
+        let cli = Cli::try_parse_from(vec!["figurehead", "mdbook"]).unwrap();
+        assert!(matches!(cli.command, Commands::Mdbook { action: None }));
+    }
+
+    #[test]
+    fn test_cli_parsing_repl_command() {
+        let cli = Cli::try_parse_from(["figurehead", "repl", "--style", "ascii"]).unwrap();
+        match cli.command {
+            Commands::Repl { style } => assert_eq!(style, StyleChoice::Ascii),
+            _ => panic!("Expected Repl command"),
+        }
+    }
+
+    #[test]
+    fn test_describe_node_in_flowchart() {
+        let app = FigureheadApp::new();
+        let input = "graph TD\n    A[Start] -->|go| B{Check}\n    B --> C";
+
+        let description = app.describe_node(input, "B").unwrap();
+        assert_eq!(description.label, "Check");
+        assert_eq!(description.incoming[0].to_string(), "A -->|go| B");
+
+        let json = description_json(&description);
+        assert_eq!(json["shape"], "diamond");
+        assert_eq!(json["outgoing"][0]["to"], "C");
+    }
+
+    #[test]
+    fn test_describe_rejects_unknown_node_and_other_diagrams() {
+        let app = FigureheadApp::new();
+        let err = app.describe_node("graph TD; A-->B;", "Z").unwrap_err();
+        assert!(err.to_string().contains("No node 'Z'"));
+
+        let err = app
+            .describe_node("sequenceDiagram\n    A->>B: hi", "A")
+            .unwrap_err();
+        assert!(err.to_string().contains("flowcharts only"));
+    }
+
     #[test]
     fn test_figurehead_app_creation() {
         // Verify the app can be created without panicking
@@ -699,17 +2429,36 @@ mod tests {
     #[test]
     fn test_types_command_json_format() {
         let app = FigureheadApp::new();
-        let result = app.types_command(true, false);
+        let result = app.types_command(true, false, false);
         assert!(result.is_ok());
     }
 
     #[test]
     fn test_types_command_human_format() {
         let app = FigureheadApp::new();
-        let result = app.types_command(false, false);
+        let result = app.types_command(false, false, false);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_types_command_capabilities() {
+        let app = FigureheadApp::new();
+        assert!(app.types_command(true, true, false).is_ok());
+        assert!(app.types_command(false, true, false).is_ok());
+    }
+
+    #[test]
+    fn test_capabilities_json_shape() {
+        let json = capabilities_json(&figurehead::capabilities());
+        assert_eq!(json["diagram_types"][0]["name"], "flowchart");
+        assert!(json["shapes"].as_array().unwrap().len() >= 10);
+        assert_eq!(json["edge_types"][0]["syntax"], "-->");
+        assert!(json["output"]["character_sets"]
+            .as_array()
+            .unwrap()
+            .contains(&serde_json::json!("ascii")));
+    }
+
     #[test]
     fn test_skip_detection_flag() {
         let args = vec!["figurehead", "convert", "--skip-detection"];
@@ -0,0 +1,496 @@
+//! Option defaults from a `figurehead.toml` file
+//!
+//! The file sets defaults for `convert`; flags on the command line still
+//! win. Top-level keys apply to every diagram type, and `[flowchart]`,
+//! `[sequence]` and `[gitgraph]` tables hold the options specific to one.
+//! Keys are the flag names without the leading dashes:
+//!
+//! ```toml
+//! style = "ascii"
+//! diamond = "tall"
+//! color = "never"
+//! max-width = 100
+//!
+//! [flowchart]
+//! curve = "linear"
+//! rounded-edges = true
+//!
+//! [sequence]
+//! message-index = true
+//! ```
+//!
+//! The file used is the first found of: `--config PATH` (or the
+//! `FIGUREHEAD_CONFIG` variable), `figurehead.toml` or `.figurehead.toml` in
+//! the current directory or any parent, so a repository can share one, and
+//! `figurehead.toml` in the user config directory (`$XDG_CONFIG_HOME` or
+//! `~/.config`). Files are not merged. A boolean turned on in the file is
+//! turned off again with `--flag=false`.
+//!
+//! `convert` and the mdBook preprocessor read the file; other commands run
+//! even when it is broken.
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Arg, ArgAction, Command};
+use serde_json::{Map, Value};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the config file
+pub const FILE_NAME: &str = "figurehead.toml";
+
+/// Environment variable naming the config file to use
+pub const ENV_VAR: &str = "FIGUREHEAD_CONFIG";
+
+/// Subcommand the defaults apply to
+const SUBCOMMAND: &str = "convert";
+
+/// Keys allowed at the top level of the file
+const GENERAL: &[&str] = &[
+    "style",
+    "diamond",
+    "color",
+    "max-width",
+    "canvas",
+    "overflow",
+    "overflow-limit",
+    "format",
+];
+
+/// Keys allowed in each diagram type's table
+const SECTIONS: &[(&str, &[&str])] = &[
+    (
+        "flowchart",
+        &[
+            "label-placement",
+            "split-labels",
+            "rounded-edges",
+            "curve",
+            "line-weight",
+            "theme",
+            "mirror-reversed",
+            "aspect",
+            "link-footnotes",
+            "max-layer-nodes",
+            "crossing-sweeps",
+            "repeated-headers",
+            "id-case",
+        ],
+    ),
+    (
+        "sequence",
+        &[
+            "message-index",
+            "lifeline-hops",
+            "participant-order",
+            "mirror-participants",
+            "lifeline-padding",
+        ],
+    ),
+    ("gitgraph", &["expand", "branch-legend"]),
+];
+
+/// Defaults read from a config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// File the defaults came from
+    pub path: PathBuf,
+    /// Flag name (without dashes) and its default values
+    pub defaults: Vec<(String, Vec<String>)>,
+}
+
+impl Config {
+    /// Parse the contents of a config file
+    pub fn parse(path: impl Into<PathBuf>, text: &str) -> Result<Self> {
+        let path = path.into();
+        let table: toml::Table = text
+            .parse()
+            .with_context(|| format!("Invalid config file '{}'", path.display()))?;
+
+        let mut defaults = Vec::new();
+        for (key, value) in &table {
+            if let Some((section, allowed)) = SECTIONS.iter().find(|(name, _)| name == key) {
+                let Some(options) = value.as_table() else {
+                    bail!("'{}' in '{}' must be a table", section, path.display());
+                };
+                for (key, value) in options {
+                    if !allowed.contains(&key.as_str()) {
+                        bail!(
+                            "Unknown option '{}' in [{}] of '{}' (expected one of: {})",
+                            key,
+                            section,
+                            path.display(),
+                            allowed.join(", ")
+                        );
+                    }
+                    defaults.push((key.clone(), values(key, value, &path)?));
+                }
+            } else if GENERAL.contains(&key.as_str()) {
+                defaults.push((key.clone(), values(key, value, &path)?));
+            } else {
+                bail!(
+                    "Unknown option '{}' in '{}' (expected one of: {}, or a [{}] table)",
+                    key,
+                    path.display(),
+                    GENERAL.join(", "),
+                    SECTIONS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .collect::<Vec<_>>()
+                        .join("], [")
+                );
+            }
+        }
+        Ok(Self { path, defaults })
+    }
+
+    /// Read and parse a config file
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read config file '{}': {}", path.display(), e))?;
+        Self::parse(path, &text)
+    }
+
+    /// Make the file's values the defaults of `command`'s options
+    ///
+    /// Each value is checked the way the flag's own value would be, so a
+    /// typo is reported with the file it came from.
+    pub fn apply(&self, command: Command) -> Result<Command> {
+        let convert = command
+            .find_subcommand(SUBCOMMAND)
+            .ok_or_else(|| anyhow!("No '{}' command", SUBCOMMAND))?
+            .clone();
+        for (key, values) in &self.defaults {
+            let id = key.replace('-', "_");
+            let arg = convert
+                .get_arguments()
+                .find(|arg| arg.get_id() == id.as_str())
+                .ok_or_else(|| anyhow!("No --{} option", key))?;
+            // A flag's own action (set-true, append) would refuse a value, so
+            // probe its value parser through a plain option
+            let probe = Command::new(SUBCOMMAND).no_binary_name(true).arg(
+                Arg::new("value")
+                    .long("value")
+                    .action(ArgAction::Set)
+                    .allow_hyphen_values(true)
+                    .value_parser(arg.get_value_parser().clone()),
+            );
+            for value in values {
+                if probe
+                    .clone()
+                    .try_get_matches_from(["--value", value])
+                    .is_err()
+                {
+                    let expected: Vec<String> = arg
+                        .get_possible_values()
+                        .iter()
+                        .map(|v| v.get_name().to_string())
+                        .collect();
+                    let hint = if expected.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" (expected one of: {})", expected.join(", "))
+                    };
+                    bail!(
+                        "Invalid '{}' in '{}': '{}'{}",
+                        key,
+                        self.path.display(),
+                        value,
+                        hint
+                    );
+                }
+            }
+        }
+
+        let defaults = self.defaults.clone();
+        Ok(command.mut_subcommand(SUBCOMMAND, move |mut convert| {
+            for (key, values) in defaults {
+                let id = key.replace('-', "_");
+                convert = convert.mut_arg(id, |arg| arg.default_values(values));
+            }
+            convert
+        }))
+    }
+
+    /// The file's values as `[preprocessor.figurehead]` options of
+    /// `book.toml`, with booleans as JSON booleans
+    pub fn options(&self) -> Map<String, Value> {
+        let value = |value: &str| match value {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            value => Value::String(value.to_string()),
+        };
+        self.defaults
+            .iter()
+            .map(|(key, values)| {
+                let values = match values.as_slice() {
+                    [single] => value(single),
+                    values => Value::Array(values.iter().map(|v| value(v)).collect()),
+                };
+                (key.clone(), values)
+            })
+            .collect()
+    }
+}
+
+/// A config value as flag values
+fn values(key: &str, value: &toml::Value, path: &Path) -> Result<Vec<String>> {
+    let scalar = |value: &toml::Value| match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(n) => Some(n.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    };
+    let values = match value {
+        toml::Value::Array(items) => items.iter().map(scalar).collect(),
+        value => scalar(value).map(|v| vec![v]),
+    };
+    values.ok_or_else(|| {
+        anyhow!(
+            "'{}' in '{}' must be a string, number or boolean",
+            key,
+            path.display()
+        )
+    })
+}
+
+/// Config file named on the command line (`--config PATH`)
+pub fn explicit_path(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// Config file that applies: `explicit`, then the one named by
+/// `FIGUREHEAD_CONFIG`, then the one [`discover`] finds from the current
+/// directory
+pub fn resolve(explicit: Option<PathBuf>) -> Result<Option<PathBuf>> {
+    let named = explicit.or_else(|| {
+        std::env::var_os(ENV_VAR)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    });
+    match named {
+        Some(path) => Ok(Some(path)),
+        None => Ok(discover(&std::env::current_dir()?)),
+    }
+}
+
+/// Read the config file that applies, if there is one
+pub fn load(explicit: Option<PathBuf>) -> Result<Option<Config>> {
+    resolve(explicit)?
+        .map(|path| Config::load(&path))
+        .transpose()
+}
+
+/// Nearest `figurehead.toml` or `.figurehead.toml` in `start` or a parent,
+/// then the one in the user config directory
+pub fn discover(start: &Path) -> Option<PathBuf> {
+    let in_tree = start.ancestors().find_map(|dir| {
+        [FILE_NAME.to_string(), format!(".{}", FILE_NAME)]
+            .into_iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    });
+    in_tree.or_else(|| user_config_path().filter(|path| path.is_file()))
+}
+
+/// `figurehead.toml` in `$XDG_CONFIG_HOME`, or in `~/.config`
+pub fn user_config_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join(FILE_NAME))
+}
+
+/// `command` with the defaults of the config file that applies to `args`
+///
+/// Only `convert` reads the file, so other commands run even when it is
+/// broken.
+pub fn command_with_config(command: Command, args: &[OsString]) -> Result<Command> {
+    if subcommand_name(&command, args).is_some_and(|name| name != SUBCOMMAND) {
+        return Ok(command);
+    }
+    match load(explicit_path(args))? {
+        Some(config) => config.apply(command),
+        None => Ok(command),
+    }
+}
+
+/// Name of the subcommand `args` run, skipping the options before it
+fn subcommand_name(command: &Command, args: &[OsString]) -> Option<String> {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let arg = arg.to_string_lossy();
+        match arg.strip_prefix("--") {
+            Some("") => return None,
+            Some(long) => {
+                let takes_value = command.get_arguments().any(|option| {
+                    option.get_long() == Some(long) && option.get_action().takes_values()
+                });
+                if takes_value {
+                    args.next();
+                }
+            }
+            None if arg.starts_with('-') => {}
+            None => return Some(arg.into_owned()),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Commands, StyleChoice};
+    use clap::{CommandFactory, FromArgMatches};
+
+    fn parse_with(config: &str, args: &[&str]) -> Result<Cli> {
+        let config = Config::parse("figurehead.toml", config)?;
+        let matches = config.apply(Cli::command())?.try_get_matches_from(args)?;
+        Ok(Cli::from_arg_matches(&matches)?)
+    }
+
+    #[test]
+    fn test_config_values_become_defaults() {
+        let config = "style = \"ascii\"\nmax-width = 80\n\n\
+                      [flowchart]\nrounded-edges = true\n\n\
+                      [gitgraph]\nexpand = [\"main\", \"dev\"]";
+        let cli = parse_with(config, &["figurehead", "convert"]).unwrap();
+        match cli.command {
+            Commands::Convert {
+                style,
+                max_width,
+                rounded_edges,
+                expand,
+                ..
+            } => {
+                assert_eq!(style, StyleChoice::Ascii);
+                assert_eq!(max_width, Some(80));
+                assert!(rounded_edges);
+                assert_eq!(expand, ["main", "dev"]);
+            }
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_flags_override_config() {
+        let cli = parse_with(
+            "style = \"ascii\"",
+            &["figurehead", "convert", "--style", "compact"],
+        )
+        .unwrap();
+        match cli.command {
+            Commands::Convert { style, .. } => assert_eq!(style, StyleChoice::Compact),
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_flags_turn_off_config_booleans() {
+        let config = "[flowchart]\nrounded-edges = true\n\n[sequence]\nmessage-index = true";
+        let cli = parse_with(
+            config,
+            &[
+                "figurehead",
+                "convert",
+                "--rounded-edges=false",
+                "--message-index",
+            ],
+        )
+        .unwrap();
+        match cli.command {
+            Commands::Convert {
+                rounded_edges,
+                message_index,
+                ..
+            } => {
+                assert!(!rounded_edges);
+                assert!(message_index);
+            }
+            _ => panic!("Expected Convert command"),
+        }
+    }
+
+    #[test]
+    fn test_broken_config_only_fails_convert() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("figurehead.toml");
+        fs::write(&path, "colour = \"never\"").unwrap();
+        let args = |command: &str| -> Vec<OsString> {
+            ["figurehead", "--config", path.to_str().unwrap(), command]
+                .iter()
+                .map(OsString::from)
+                .collect()
+        };
+
+        assert!(command_with_config(Cli::command(), &args("types")).is_ok());
+        let error = command_with_config(Cli::command(), &args("convert")).unwrap_err();
+        assert!(error.to_string().contains("Unknown option 'colour'"));
+    }
+
+    #[test]
+    fn test_options_for_mdbook() {
+        let config = Config::parse(
+            "f.toml",
+            "style = \"ascii\"\n[flowchart]\nrounded-edges = true\n[gitgraph]\nexpand = [\"main\"]",
+        )
+        .unwrap();
+        let options = config.options();
+        assert_eq!(options["style"], "ascii");
+        assert_eq!(options["rounded-edges"], true);
+        assert_eq!(options["expand"], "main");
+    }
+
+    #[test]
+    fn test_bad_config_names_the_key() {
+        let unknown = Config::parse("repo/figurehead.toml", "colour = \"never\"").unwrap_err();
+        assert!(unknown.to_string().contains("Unknown option 'colour'"));
+
+        let misplaced = Config::parse("f.toml", "[sequence]\ncurve = \"linear\"").unwrap_err();
+        assert!(misplaced.to_string().contains("in [sequence]"));
+
+        let invalid = Config::parse("f.toml", "style = \"fancy\"")
+            .unwrap()
+            .apply(Cli::command())
+            .unwrap_err()
+            .to_string();
+        assert!(invalid.contains("Invalid 'style'"), "{}", invalid);
+        assert!(
+            invalid.contains("expected one of: ascii, unicode"),
+            "{}",
+            invalid
+        );
+
+        let number = Config::parse("f.toml", "max-width = \"wide\"").unwrap();
+        assert!(number.apply(Cli::command()).is_err());
+    }
+
+    #[test]
+    fn test_explicit_path_and_discovery() {
+        let args: Vec<OsString> = ["figurehead", "--config=team.toml", "convert"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+        assert_eq!(explicit_path(&args), Some(PathBuf::from("team.toml")));
+
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("docs/diagrams");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.path().join(".figurehead.toml"), "style = \"ascii\"").unwrap();
+        assert_eq!(discover(&nested), Some(dir.path().join(".figurehead.toml")));
+    }
+}
@@ -1,13 +1,27 @@
 //! Figurehead CLI - Convert Mermaid.js diagrams to ASCII art
 
+mod batch;
+mod bench;
 mod cli;
-mod colorizer;
+mod config;
+mod mdbook;
+mod repl;
+mod watch;
 
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 
 fn main() {
-    // Parse CLI args first to get logging configuration
-    let cli_args = cli::Cli::parse();
+    // Option defaults from the config file, then CLI args on top
+    let args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    let command = match config::command_with_config(cli::Cli::command(), &args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let matches = command.get_matches_from(args);
+    let cli_args = cli::Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
     let mut app = cli::FigureheadApp::new();
 
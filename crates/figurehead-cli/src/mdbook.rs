@@ -0,0 +1,223 @@
+//! mdBook preprocessor that draws ```mermaid blocks as text diagrams
+//!
+//! mdBook runs `figurehead mdbook supports <renderer>` to ask whether a
+//! renderer is handled, then pipes `[context, book]` JSON through
+//! `figurehead mdbook` and reads the book back. Options come from the
+//! `[preprocessor.figurehead]` table of `book.toml`, using the same names and
+//! values as the `convert` flags (`style = "ascii"`, `rounded-edges = true`),
+//! on top of the `figurehead.toml` that `convert` would read.
+
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use std::io::{Read, Write};
+
+use crate::cli::{
+    CanvasChoice, CurveChoice, DiamondChoice, FigureheadApp, LabelPlacementChoice,
+    LineWeightChoice, OverflowChoice, SplitLabelsChoice, StyleChoice,
+};
+use figurehead::plugins::Orchestrator;
+use figurehead::{replace_mermaid_blocks, CanvasLimit, MarkdownFence, RenderConfig};
+
+/// Whether the preprocessor runs for a renderer
+///
+/// The HTML renderer is left to mermaid.js; every text-based renderer gets
+/// drawn diagrams. Listing `renderers` in `book.toml` overrides this.
+pub fn supports_renderer(renderer: &str) -> bool {
+    renderer != "html"
+}
+
+/// Read `[context, book]` from `input` and write the processed book to
+/// `output`
+///
+/// `defaults` are options from a config file; `book.toml` options override
+/// them key by key.
+pub fn run_preprocessor(
+    input: impl Read,
+    output: impl Write,
+    defaults: Map<String, Value>,
+) -> Result<()> {
+    let (context, mut book): (Value, Value) =
+        serde_json::from_reader(input).context("Expected [context, book] JSON from mdBook")?;
+    let mut options = defaults;
+    if let Some(book_options) = context
+        .pointer("/config/preprocessor/figurehead")
+        .and_then(Value::as_object)
+    {
+        options.extend(book_options.clone());
+    }
+
+    let mut orchestrator = Orchestrator::all_plugins(config_from_options(&options)?);
+    orchestrator.register_default_detectors();
+    for_each_chapter(&mut book, &mut |name, content| {
+        replace_mermaid_blocks(content, MarkdownFence::Text, |source| {
+            orchestrator
+                .process(source)
+                .map_err(|e| eprintln!("figurehead: skipped a diagram in '{}': {}", name, e))
+                .ok()
+        })
+    });
+
+    serde_json::to_writer(output, &book)?;
+    Ok(())
+}
+
+/// Resolve a render config from `[preprocessor.figurehead]` options
+///
+/// Values are parsed like the matching `convert` flags; missing keys take
+/// the flag defaults and unknown keys are ignored (mdBook adds its own, such
+/// as `command`).
+pub fn config_from_options(options: &Map<String, Value>) -> Result<RenderConfig> {
+    let config = FigureheadApp::build_config(
+        choice(options, "style")?.unwrap_or(StyleChoice::Unicode),
+        choice(options, "diamond")?.unwrap_or(DiamondChoice::Box),
+        choice(options, "label-placement")?.unwrap_or(LabelPlacementChoice::Auto),
+        choice(options, "canvas")?.unwrap_or(CanvasChoice::Text),
+    )
+    .with_split_labels(
+        choice(options, "split-labels")?
+            .unwrap_or(SplitLabelsChoice::Shared)
+            .into(),
+    )
+    .with_rounded_edges(flag(options, "rounded-edges")?)
+    .with_edge_curve(
+        choice(options, "curve")?
+            .unwrap_or(CurveChoice::Step)
+            .into(),
+    )
+    .with_line_weight(
+        choice(options, "line-weight")?
+            .unwrap_or(LineWeightChoice::Light)
+            .into(),
+    )
+    .with_mirror_reversed(flag(options, "mirror-reversed")?)
+    .with_overflow_policy(
+        choice(options, "overflow")?
+            .unwrap_or(OverflowChoice::Compact)
+            .into(),
+    );
+
+    Ok(match options.get("overflow-limit") {
+        Some(Value::String(limit)) => {
+            config.with_overflow_limit(limit.parse::<CanvasLimit>().map_err(|e| anyhow!(e))?)
+        }
+        Some(other) => return Err(anyhow!("overflow-limit must be a string, got {}", other)),
+        None => config,
+    })
+}
+
+fn choice<T: ValueEnum>(options: &Map<String, Value>, key: &str) -> Result<Option<T>> {
+    match options.get(key) {
+        None => Ok(None),
+        Some(Value::String(value)) => T::from_str(value, true)
+            .map(Some)
+            .map_err(|e| anyhow!("Invalid {} '{}': {}", key, value, e)),
+        Some(other) => Err(anyhow!("{} must be a string, got {}", key, other)),
+    }
+}
+
+fn flag(options: &Map<String, Value>, key: &str) -> Result<bool> {
+    match options.get(key) {
+        None => Ok(false),
+        Some(Value::Bool(value)) => Ok(*value),
+        Some(other) => Err(anyhow!("{} must be true or false, got {}", key, other)),
+    }
+}
+
+/// Call `process` on the name and content of every chapter, nested ones
+/// included, replacing the content with its result
+fn for_each_chapter(book: &mut Value, process: &mut impl FnMut(&str, &str) -> String) {
+    // mdBook 0.4 calls the top-level list `sections`, later versions `items`
+    for key in ["sections", "items", "sub_items"] {
+        let Some(Value::Array(items)) = book.get_mut(key) else {
+            continue;
+        };
+        for item in items {
+            let Some(chapter) = item.get_mut("Chapter") else {
+                continue;
+            };
+            let name = chapter
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            if let Some(Value::String(content)) = chapter.get_mut("content") {
+                *content = process(&name, content);
+            }
+            for_each_chapter(chapter, process);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_options_resolve_like_convert_flags() {
+        let options = json!({"command": "figurehead mdbook", "style": "ascii", "rounded-edges": true, "line-weight": "double", "overflow-limit": "80x40"});
+        let config = config_from_options(options.as_object().unwrap()).unwrap();
+        assert_eq!(config.style, figurehead::CharacterSet::Ascii);
+        assert!(config.rounded_edges);
+        assert_eq!(config.line_weight, figurehead::LineWeight::Double);
+        assert_eq!(config.overflow_limit, CanvasLimit::new(80, 40));
+
+        let bad = json!({"style": "fancy"});
+        assert!(config_from_options(bad.as_object().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_preprocessor_renders_nested_chapters() {
+        let chapter = |name: &str, content: &str, sub_items: Value| json!({"Chapter": {"name": name, "content": content, "sub_items": sub_items}});
+        let book = json!({"sections": [
+            chapter("Intro", "plain text", json!([
+                chapter("Flow", "```mermaid\ngraph TD\n  A --> B\n```\n", json!([])),
+            ])),
+            "Separator",
+        ]});
+        let context = json!({"config": {"preprocessor": {"figurehead": {"style": "ascii"}}}});
+        let input = serde_json::to_vec(&json!([context, book])).unwrap();
+
+        let mut output = Vec::new();
+        run_preprocessor(input.as_slice(), &mut output, Map::new()).unwrap();
+        let book: Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(book["sections"][0]["Chapter"]["content"], "plain text");
+        let flow = book["sections"][0]["Chapter"]["sub_items"][0]["Chapter"]["content"]
+            .as_str()
+            .unwrap();
+        assert!(flow.starts_with("```text\n"), "{}", flow);
+        assert!(flow.contains("| A |"), "{}", flow);
+        assert_eq!(book["sections"][1], "Separator");
+    }
+
+    #[test]
+    fn test_book_options_override_config_file() {
+        let book = json!({"sections": [
+            {"Chapter": {"name": "Flow", "content": "```mermaid\ngraph LR\n  A --> B\n```\n", "sub_items": []}},
+        ]});
+        let context =
+            json!({"config": {"preprocessor": {"figurehead": {"line-weight": "double"}}}});
+        let input = serde_json::to_vec(&json!([context, book])).unwrap();
+        let defaults = json!({"line-weight": "heavy", "style": "unicode"});
+
+        let mut output = Vec::new();
+        run_preprocessor(
+            input.as_slice(),
+            &mut output,
+            defaults.as_object().unwrap().clone(),
+        )
+        .unwrap();
+        let book: Value = serde_json::from_slice(&output).unwrap();
+        let flow = book["sections"][0]["Chapter"]["content"].as_str().unwrap();
+        assert!(flow.contains("║ A ║"), "{}", flow);
+        assert!(!flow.contains('┃'), "{}", flow);
+    }
+
+    #[test]
+    fn test_html_is_left_to_mermaid_js() {
+        assert!(!supports_renderer("html"));
+        assert!(supports_renderer("markdown"));
+    }
+}
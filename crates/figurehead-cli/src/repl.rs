@@ -0,0 +1,240 @@
+//! Interactive REPL for building diagrams one statement at a time
+//!
+//! Each line read is appended to the diagram source and the whole diagram is
+//! re-rendered, so the effect of every statement is visible immediately.
+//! Lines starting with `:` are REPL commands rather than Mermaid.
+
+use anyhow::Result;
+use figurehead::plugins::Orchestrator;
+use std::io::{BufRead, Write};
+
+/// Text printed by `:help`
+const HELP: &str = "\
+Type Mermaid statements one per line, starting with a header such as `graph TD`.
+Commands:
+  :undo     remove the last statement
+  :reset    start over with an empty diagram
+  :source   print the statements entered so far
+  :help     show this message
+  :quit     leave the REPL";
+
+/// What the REPL should do after handling a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplAction {
+    /// Show the re-rendered diagram
+    Render(String),
+    /// Show a message instead of a diagram
+    Message(String),
+    /// Nothing to show (blank line)
+    Nothing,
+    /// Leave the REPL
+    Quit,
+}
+
+/// Statements entered so far, re-rendered after every change
+#[derive(Debug, Default)]
+pub struct ReplSession {
+    statements: Vec<String>,
+}
+
+impl ReplSession {
+    /// Create an empty session
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The diagram source built from the statements entered so far
+    pub fn source(&self) -> String {
+        self.statements.join("\n")
+    }
+
+    /// Handle one line of input
+    ///
+    /// A statement that makes the diagram fail to render, or that the
+    /// parser skips with a warning, is reported and discarded, so the
+    /// session always holds a valid diagram.
+    pub fn handle(&mut self, orchestrator: &Orchestrator, line: &str) -> ReplAction {
+        let line = line.trim();
+        match line {
+            "" => ReplAction::Nothing,
+            ":quit" | ":q" | ":exit" => ReplAction::Quit,
+            ":help" | ":h" => ReplAction::Message(HELP.to_string()),
+            ":source" => ReplAction::Message(self.source()),
+            ":reset" => {
+                self.statements.clear();
+                ReplAction::Message("Diagram cleared".to_string())
+            }
+            ":undo" => match self.statements.pop() {
+                Some(_) if self.statements.is_empty() => {
+                    ReplAction::Message("Diagram cleared".to_string())
+                }
+                Some(_) => self.render(orchestrator),
+                None => ReplAction::Message("Nothing to undo".to_string()),
+            },
+            command if command.starts_with(':') => {
+                ReplAction::Message(format!("Unknown command '{}' (try :help)", command))
+            }
+            statement => {
+                self.statements.push(statement.to_string());
+                match orchestrator.process_with_warnings(&self.source()) {
+                    Ok((output, warnings)) if warnings.is_empty() => ReplAction::Render(output),
+                    Ok((_, warnings)) => {
+                        self.statements.pop();
+                        ReplAction::Message(format!(
+                            "Warning: {} (statement discarded)",
+                            warnings[0].message
+                        ))
+                    }
+                    Err(e) => {
+                        self.statements.pop();
+                        ReplAction::Message(format!("Error: {} (statement discarded)", e))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Render the current statements
+    fn render(&self, orchestrator: &Orchestrator) -> ReplAction {
+        match orchestrator.process(&self.source()) {
+            Ok(output) => ReplAction::Render(output),
+            Err(e) => ReplAction::Message(format!("Error: {}", e)),
+        }
+    }
+}
+
+/// Read lines from `input` until end of input or `:quit`, writing each
+/// re-rendered diagram to `output`
+///
+/// `prompt` prints `> ` before each line, for interactive terminals.
+pub fn run_repl(
+    orchestrator: &Orchestrator,
+    input: impl BufRead,
+    mut output: impl Write,
+    prompt: bool,
+) -> Result<()> {
+    let mut session = ReplSession::new();
+    let mut lines = input.lines();
+
+    loop {
+        if prompt {
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        match session.handle(orchestrator, &line?) {
+            ReplAction::Render(diagram) => writeln!(output, "{}\n", diagram.trim_end())?,
+            ReplAction::Message(message) => writeln!(output, "{}", message)?,
+            ReplAction::Nothing => {}
+            ReplAction::Quit => break,
+        }
+    }
+
+    if prompt {
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use figurehead::RenderConfig;
+
+    fn orchestrator() -> Orchestrator {
+        let mut orchestrator = Orchestrator::all_plugins(RenderConfig::default());
+        orchestrator.register_default_detectors();
+        orchestrator
+    }
+
+    #[test]
+    fn test_statements_accumulate_and_rerender() {
+        let orchestrator = orchestrator();
+        let mut session = ReplSession::new();
+
+        assert_eq!(
+            session.handle(&orchestrator, "graph TD"),
+            ReplAction::Render("[empty flowchart: no nodes]".to_string())
+        );
+        let ReplAction::Render(first) = session.handle(&orchestrator, "A --> B") else {
+            panic!("expected a diagram");
+        };
+        assert!(first.contains('A') && first.contains('B'));
+
+        let ReplAction::Render(second) = session.handle(&orchestrator, "B --> C") else {
+            panic!("expected a diagram");
+        };
+        assert!(second.contains('C'));
+        assert_eq!(session.source(), "graph TD\nA --> B\nB --> C");
+    }
+
+    #[test]
+    fn test_undo_restores_previous_diagram() {
+        let orchestrator = orchestrator();
+        let mut session = ReplSession::new();
+        session.handle(&orchestrator, "graph TD");
+        let before = session.handle(&orchestrator, "A --> B");
+        session.handle(&orchestrator, "B --> C");
+
+        assert_eq!(session.handle(&orchestrator, ":undo"), before);
+        assert_eq!(session.source(), "graph TD\nA --> B");
+    }
+
+    #[test]
+    fn test_commands() {
+        let orchestrator = orchestrator();
+        let mut session = ReplSession::new();
+
+        assert_eq!(
+            session.handle(&orchestrator, ":undo"),
+            ReplAction::Message("Nothing to undo".to_string())
+        );
+        assert_eq!(session.handle(&orchestrator, "   "), ReplAction::Nothing);
+        assert_eq!(session.handle(&orchestrator, ":quit"), ReplAction::Quit);
+        assert!(matches!(
+            session.handle(&orchestrator, ":bogus"),
+            ReplAction::Message(m) if m.contains("Unknown command")
+        ));
+
+        assert!(matches!(
+            session.handle(&orchestrator, "not a diagram"),
+            ReplAction::Message(m) if m.contains("statement discarded")
+        ));
+        assert_eq!(session.source(), "");
+
+        session.handle(&orchestrator, "graph LR");
+        session.handle(&orchestrator, ":reset");
+        assert_eq!(session.source(), "");
+    }
+
+    #[test]
+    fn test_statement_skipped_with_warning_is_discarded() {
+        let orchestrator = orchestrator();
+        let mut session = ReplSession::new();
+        session.handle(&orchestrator, "graph TD");
+        session.handle(&orchestrator, "A-->B");
+
+        assert!(matches!(
+            session.handle(&orchestrator, "this is !! garbage ]]"),
+            ReplAction::Message(m) if m.starts_with("Warning:") && m.contains("statement discarded")
+        ));
+        assert_eq!(
+            session.handle(&orchestrator, ":source"),
+            ReplAction::Message("graph TD\nA-->B".to_string())
+        );
+    }
+
+    #[test]
+    fn test_run_repl_stops_at_quit() {
+        let orchestrator = orchestrator();
+        let input = "graph TD\nA --> B\n:quit\nB --> C\n";
+        let mut output = Vec::new();
+        run_repl(&orchestrator, input.as_bytes(), &mut output, false).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains('B'));
+        assert!(!output.contains('C'));
+    }
+}
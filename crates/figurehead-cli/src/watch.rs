@@ -0,0 +1,138 @@
+//! Watch mode: re-render a diagram file every time it is saved
+//!
+//! The file's directory is watched rather than the file itself, because many
+//! editors save by writing a new file and renaming it over the old one,
+//! which would end a watch on the original file. The events one save
+//! produces are collapsed into a single re-render.
+
+use anyhow::{anyhow, Result};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Quiet period that ends a burst of events from one save
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// Whether `event` changed the file at `path`
+pub fn touches(event: &Event, path: &Path) -> bool {
+    let changes = matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Any
+    );
+    changes && event.paths.iter().any(|changed| same_file(changed, path))
+}
+
+/// Compare paths by file name and canonical directory, since events may
+/// report a path spelled differently from the one given on the command line
+fn same_file(a: &Path, b: &Path) -> bool {
+    let canonical_dir = |path: &Path| {
+        path.parent()
+            .map(|dir| {
+                if dir.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    dir
+                }
+            })
+            .and_then(|dir| dir.canonicalize().ok())
+    };
+    a.file_name() == b.file_name() && canonical_dir(a) == canonical_dir(b)
+}
+
+/// Screen shown after a re-render: a status line, then the diagram or the
+/// error that stopped it
+pub fn screen(path: &Path, rendered: &Result<String>) -> String {
+    let status = format!("Watching {} (Ctrl-C to stop)", path.display());
+    match rendered {
+        Ok(diagram) => format!("{}\n\n{}", status, diagram.trim_end()),
+        Err(e) => format!("{}\n\nError: {}", status, e),
+    }
+}
+
+/// Clear the terminal and move the cursor to the top left
+pub fn clear_terminal() -> io::Result<()> {
+    let mut stdout = io::stdout();
+    crossterm::execute!(
+        stdout,
+        crossterm::terminal::Clear(crossterm::terminal::ClearType::All),
+        crossterm::cursor::MoveTo(0, 0)
+    )?;
+    stdout.flush()
+}
+
+/// Call `on_change` now and after every change to `path`, until interrupted
+///
+/// Errors from `on_change` are the caller's to report; watching continues.
+pub fn watch(path: &Path, mut on_change: impl FnMut()) -> Result<()> {
+    let dir: PathBuf = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let (tx, rx) = mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| anyhow!("Failed to start watching: {}", e))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| anyhow!("Failed to watch '{}': {}", dir.display(), e))?;
+
+    on_change();
+    for event in &rx {
+        match event {
+            Ok(event) if touches(&event, path) => {}
+            Ok(_) => continue,
+            Err(e) => {
+                tracing::warn!(error = %e, "File watch error");
+                continue;
+            }
+        }
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+        on_change();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind};
+
+    fn event(kind: EventKind, path: &Path) -> Event {
+        Event::new(kind).add_path(path.to_path_buf())
+    }
+
+    #[test]
+    fn test_touches_only_changes_to_the_watched_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("flow.mmd");
+        let other = dir.path().join("notes.md");
+
+        assert!(touches(
+            &event(EventKind::Modify(ModifyKind::Any), &file),
+            &file
+        ));
+        assert!(touches(
+            &event(EventKind::Create(CreateKind::File), &file),
+            &file
+        ));
+        assert!(!touches(
+            &event(EventKind::Modify(ModifyKind::Any), &other),
+            &file
+        ));
+        assert!(!touches(
+            &event(EventKind::Access(AccessKind::Any), &file),
+            &file
+        ));
+    }
+
+    #[test]
+    fn test_screen_shows_diagram_or_error() {
+        let path = Path::new("flow.mmd");
+        let ok = screen(path, &Ok("┌───┐\n└───┘\n".to_string()));
+        assert_eq!(ok, "Watching flow.mmd (Ctrl-C to stop)\n\n┌───┐\n└───┘");
+
+        let err = screen(path, &Err(anyhow!("Parse error on line 2")));
+        assert!(err.ends_with("Error: Parse error on line 2"));
+    }
+}
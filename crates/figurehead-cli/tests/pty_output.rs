@@ -306,3 +306,20 @@ fn test_edge_label_positioning() {
         output
     );
 }
+
+/// Test that `--style ascii` reaches sequence diagrams
+#[test]
+fn test_sequence_ascii_style() {
+    let input = r#"sequenceDiagram
+    Alice->>Bob: Hello
+    Bob-->>Alice: Hi"#;
+
+    let output = run_cli(&["--style", "ascii"], input).expect("CLI should succeed");
+
+    assert!(output.contains("Hello"), "Output:\n{}", output);
+    assert!(
+        output.is_ascii(),
+        "Sequence diagram should be plain ASCII\nOutput:\n{}",
+        output
+    );
+}
@@ -1,7 +1,7 @@
-use figurehead::core::{Direction, EdgeType, LayoutAlgorithm, Renderer};
-use figurehead::plugins::flowchart::{
-    FlowchartDatabase, FlowchartLayoutAlgorithm, FlowchartRenderer,
-};
+use figurehead::core::{Direction, EdgeType, Renderer};
+use figurehead::plugins::flowchart::{FlowchartDatabase, FlowchartRenderer};
+use figurehead::unstable::flowchart::FlowchartLayoutAlgorithm;
+use figurehead::unstable::LayoutAlgorithm;
 
 fn main() {
     let mut db = FlowchartDatabase::with_direction(Direction::LeftRight);
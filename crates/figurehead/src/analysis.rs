@@ -0,0 +1,436 @@
+//! Diagram statistics without rendering
+//!
+//! [`analyze`] parses a diagram and estimates how big its rendering will be,
+//! so documentation pipelines can choose between inline ASCII and a linked
+//! image without paying for layout and drawing. Sizes are estimates from
+//! label widths and rank structure; the rendered diagram can differ by a few
+//! cells, mostly from edge labels and routing.
+
+use crate::core::{Database, Direction, EdgeData, NodeData, Parser};
+use crate::plugins::class::{ClassDatabase, ClassParser};
+use crate::plugins::er::{ErDatabase, ErParser};
+use crate::plugins::flowchart::{FlowchartDatabase, FlowchartParser};
+use crate::plugins::gitgraph::{GitGraphDatabase, GitGraphParser};
+use crate::plugins::sequence::{SequenceDatabase, SequenceParser};
+use crate::plugins::state::{StateDatabase, StateParser};
+use crate::plugins::Orchestrator;
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Columns between nodes in the same rank
+const NODE_GAP: usize = 1;
+/// Rows or columns between ranks, room for edges and their labels
+const RANK_GAP: usize = 4;
+/// Labels wrap at this many characters
+const MAX_LABEL_WIDTH: usize = 30;
+/// Columns between sequence diagram lifelines
+const LIFELINE_GAP: usize = 4;
+
+/// Size and structure of a diagram, computed without rendering it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagramStats {
+    /// Detected diagram type, as named by `figurehead detect`
+    pub diagram_type: String,
+    /// Nodes, participants, commits, classes, states or entities
+    pub nodes: usize,
+    /// Edges, messages, parent links, relationships or transitions
+    pub edges: usize,
+    /// Number of ranks along the flow (messages for sequence diagrams)
+    pub depth: usize,
+    /// Estimated width of the rendering, in columns
+    pub estimated_width: usize,
+    /// Estimated height of the rendering, in rows
+    pub estimated_height: usize,
+    /// Optional syntax the diagram uses, such as `subgraphs` or `styles`
+    pub features: Vec<&'static str>,
+}
+
+/// Detect, parse and measure a diagram without laying it out or drawing it
+///
+/// # Example
+/// ```rust
+/// let stats = figurehead::analyze("graph TD; A-->B; A-->C").unwrap();
+/// assert_eq!(stats.diagram_type, "flowchart");
+/// assert_eq!((stats.nodes, stats.edges, stats.depth), (3, 2, 2));
+/// ```
+pub fn analyze(input: &str) -> Result<DiagramStats> {
+    let mut orchestrator = Orchestrator::new();
+    orchestrator.register_default_detectors();
+    let diagram_type = orchestrator.detect_diagram_type(input)?;
+
+    let mut stats = match diagram_type.as_str() {
+        "sequence" => analyze_sequence(input)?,
+        "gitgraph" => {
+            let mut database = GitGraphDatabase::new();
+            GitGraphParser::new().parse(input, &mut database)?;
+            let sketch = Sketch::from_database(&database, database.direction(), |_| (3, 1));
+            sketch.stats(Vec::new())
+        }
+        "class" => analyze_class(input)?,
+        "state" => {
+            let mut database = StateDatabase::new();
+            StateParser::new().parse(input, &mut database)?;
+            let sketch =
+                Sketch::from_database(&database, Direction::TopDown, |node| label_box(&node.label));
+            let mut features = Vec::new();
+            if database.transitions().iter().any(|t| t.label.is_some()) {
+                features.push("edge_labels");
+            }
+            sketch.stats(features)
+        }
+        "er" => analyze_er(input)?,
+        _ => analyze_flowchart(input)?,
+    };
+    stats.diagram_type = diagram_type;
+    Ok(stats)
+}
+
+fn analyze_flowchart(input: &str) -> Result<DiagramStats> {
+    let mut database = FlowchartDatabase::new();
+    FlowchartParser::new().parse(input, &mut database)?;
+    let database = if database.has_hidden() {
+        database.visible_view()
+    } else {
+        database
+    };
+
+    let sketch = Sketch::from_database(&database, database.direction(), |node| {
+        label_box(&node.label)
+    });
+    let mut features = Vec::new();
+    if database.subgraph_count() > 0 {
+        features.push("subgraphs");
+    }
+    if database.class_count() > 0
+        || database
+            .nodes()
+            .any(|n| n.inline_style.is_some() || !n.classes.is_empty())
+        || database.edges().any(|e| e.style.is_some())
+    {
+        features.push("styles");
+    }
+    if database.edges().any(|e| e.label.is_some()) {
+        features.push("edge_labels");
+    }
+    Ok(sketch.stats(features))
+}
+
+fn analyze_sequence(input: &str) -> Result<DiagramStats> {
+    let mut database = SequenceDatabase::new();
+    SequenceParser::new().parse(input, &mut database)?;
+
+    // Participant boxes side by side; each message takes a label row and
+    // an arrow row between the header and footer boxes
+    let participants = database.participants();
+    let width = participants
+        .iter()
+        .map(|p| p.label.chars().count() + 4)
+        .sum::<usize>()
+        + LIFELINE_GAP * participants.len().saturating_sub(1);
+    let messages = database.message_count();
+    let height = if participants.is_empty() {
+        0
+    } else {
+        3 + 2 * messages + 1 + 3
+    };
+
+    Ok(DiagramStats {
+        diagram_type: String::new(),
+        nodes: participants.len(),
+        edges: messages,
+        depth: messages,
+        estimated_width: width,
+        estimated_height: height,
+        features: Vec::new(),
+    })
+}
+
+fn analyze_class(input: &str) -> Result<DiagramStats> {
+    let mut database = ClassDatabase::new();
+    ClassParser::new().parse(input, &mut database)?;
+
+    let index: HashMap<&str, usize> = database
+        .classes()
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c.name.as_str(), i))
+        .collect();
+    let sketch = Sketch {
+        boxes: database
+            .classes()
+            .iter()
+            .map(|class| {
+                let members = class.attributes.iter().chain(&class.methods);
+                let width = members
+                    .clone()
+                    .map(|m| {
+                        m.name.chars().count()
+                            + m.member_type.as_ref().map_or(0, |t| t.chars().count() + 1)
+                            + 1
+                    })
+                    .chain([class.name.chars().count()])
+                    .max()
+                    .unwrap_or(0)
+                    + 4;
+                let sections = usize::from(!class.attributes.is_empty())
+                    + usize::from(!class.methods.is_empty());
+                (width, 3 + members.count() + sections)
+            })
+            .collect(),
+        edges: database
+            .relationships()
+            .iter()
+            .filter_map(|r| Some((*index.get(r.from.as_str())?, *index.get(r.to.as_str())?)))
+            .collect(),
+        horizontal: database.direction().is_horizontal(),
+    };
+    let mut features = Vec::new();
+    if database.classes().iter().any(|c| c.annotation.is_some()) {
+        features.push("annotations");
+    }
+    if database.relationships().iter().any(|r| r.label.is_some()) {
+        features.push("edge_labels");
+    }
+    Ok(sketch.stats(features))
+}
+
+fn analyze_er(input: &str) -> Result<DiagramStats> {
+    let mut database = ErDatabase::new();
+    ErParser::new().parse(input, &mut database)?;
+
+    let index: HashMap<&str, usize> = database
+        .entities()
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.name.as_str(), i))
+        .collect();
+    let sketch = Sketch {
+        boxes: database
+            .entities()
+            .iter()
+            .map(|entity| {
+                let width = entity
+                    .attributes
+                    .iter()
+                    .map(|a| a.attr_type.chars().count() + a.name.chars().count() + 1)
+                    .chain([entity.label().chars().count()])
+                    .max()
+                    .unwrap_or(0)
+                    + 4;
+                let rows = if entity.attributes.is_empty() {
+                    0
+                } else {
+                    entity.attributes.len() + 1
+                };
+                (width, 3 + rows)
+            })
+            .collect(),
+        edges: database
+            .relationships()
+            .iter()
+            .filter_map(|r| Some((*index.get(r.from.as_str())?, *index.get(r.to.as_str())?)))
+            .collect(),
+        horizontal: false,
+    };
+    let mut features = Vec::new();
+    if database.entities().iter().any(|e| !e.attributes.is_empty()) {
+        features.push("attributes");
+    }
+    Ok(sketch.stats(features))
+}
+
+/// Width and height of a box around `label`, wrapped like flowchart labels
+fn label_box(label: &str) -> (usize, usize) {
+    let chars = label.chars().count();
+    let lines = chars.div_ceil(MAX_LABEL_WIDTH).max(1);
+    ((chars.min(MAX_LABEL_WIDTH) + 4).max(5), 2 + lines)
+}
+
+/// Boxes and edges of a ranked diagram, enough to estimate its size
+struct Sketch {
+    /// Width and height of each box
+    boxes: Vec<(usize, usize)>,
+    /// Edges as indices into `boxes`
+    edges: Vec<(usize, usize)>,
+    /// Whether ranks advance left-to-right instead of top-to-bottom
+    horizontal: bool,
+}
+
+impl Sketch {
+    fn from_database<D>(
+        database: &D,
+        direction: Direction,
+        size: impl Fn(&NodeData) -> (usize, usize),
+    ) -> Self
+    where
+        D: Database<Node = NodeData, Edge = EdgeData>,
+    {
+        let index: HashMap<&str, usize> = database
+            .nodes()
+            .enumerate()
+            .map(|(i, n)| (n.id.as_str(), i))
+            .collect();
+        Self {
+            boxes: database.nodes().map(size).collect(),
+            edges: database
+                .edges()
+                .filter_map(|e| Some((*index.get(e.from.as_str())?, *index.get(e.to.as_str())?)))
+                .collect(),
+            horizontal: direction.is_horizontal(),
+        }
+    }
+
+    /// Longest-path rank of each box, ignoring edges that close cycles
+    fn ranks(&self) -> Vec<usize> {
+        let count = self.boxes.len();
+        let mut successors = vec![Vec::new(); count];
+        for &(from, to) in &self.edges {
+            if from != to {
+                successors[from].push(to);
+            }
+        }
+
+        // Depth-first order; an edge to a node still on the stack is a back edge
+        let mut state = vec![0u8; count]; // 0 unvisited, 1 on stack, 2 done
+        let mut order = Vec::with_capacity(count);
+        let mut forward = vec![Vec::new(); count];
+        for root in 0..count {
+            if state[root] != 0 {
+                continue;
+            }
+            let mut stack = vec![(root, 0)];
+            state[root] = 1;
+            while let Some((node, next)) = stack.pop() {
+                if let Some(&child) = successors[node].get(next) {
+                    stack.push((node, next + 1));
+                    match state[child] {
+                        0 => {
+                            forward[node].push(child);
+                            state[child] = 1;
+                            stack.push((child, 0));
+                        }
+                        2 => forward[node].push(child),
+                        _ => {}
+                    }
+                } else {
+                    state[node] = 2;
+                    order.push(node);
+                }
+            }
+        }
+
+        // Reverse post-order is topological for the forward edges
+        let mut ranks = vec![0; count];
+        for &node in order.iter().rev() {
+            for &child in &forward[node] {
+                ranks[child] = ranks[child].max(ranks[node] + 1);
+            }
+        }
+        ranks
+    }
+
+    fn stats(&self, features: Vec<&'static str>) -> DiagramStats {
+        let ranks = self.ranks();
+        let depth = ranks.iter().max().map_or(0, |r| r + 1);
+
+        // Per rank: extent across the flow (boxes side by side) and along it
+        let mut across = vec![0; depth];
+        let mut along = vec![0; depth];
+        let mut members = vec![0usize; depth];
+        for (&(width, height), &rank) in self.boxes.iter().zip(&ranks) {
+            let (a, b) = if self.horizontal {
+                (height, width)
+            } else {
+                (width, height)
+            };
+            across[rank] += a;
+            along[rank] = along[rank].max(b);
+            members[rank] += 1;
+        }
+        let across = across
+            .iter()
+            .zip(&members)
+            .map(|(extent, &n)| extent + NODE_GAP * n.saturating_sub(1))
+            .max()
+            .unwrap_or(0);
+        let along = along.iter().sum::<usize>() + RANK_GAP * depth.saturating_sub(1);
+        let (width, height) = if self.horizontal {
+            (along, across)
+        } else {
+            (across, along)
+        };
+
+        DiagramStats {
+            diagram_type: String::new(),
+            nodes: self.boxes.len(),
+            edges: self.edges.len(),
+            depth,
+            estimated_width: width,
+            estimated_height: height,
+            features,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flowchart_depth_ignores_cycles() {
+        let stats = analyze("graph TD\n A --> B\n B --> C\n C --> A\n A --> D").unwrap();
+        assert_eq!(stats.diagram_type, "flowchart");
+        assert_eq!((stats.nodes, stats.edges), (4, 4));
+        assert_eq!(stats.depth, 3);
+    }
+
+    #[test]
+    fn test_flowchart_estimate_is_close_to_rendering() {
+        for input in [
+            "graph TD\n A[Start] --> B{Ready?}\n B --> C[Go]\n B --> D[Wait]",
+            "graph LR\n A[Load data] --> B[Transform] --> C[Store]",
+        ] {
+            let stats = analyze(input).unwrap();
+            let rendered = crate::render(input).unwrap();
+            let width = rendered.lines().map(|l| l.chars().count()).max().unwrap();
+            let height = rendered.lines().count();
+            assert!(
+                stats.estimated_width.abs_diff(width) <= 4,
+                "{:?}\n{}",
+                stats,
+                rendered
+            );
+            assert!(
+                stats.estimated_height.abs_diff(height) <= 4,
+                "{:?}\n{}",
+                stats,
+                rendered
+            );
+        }
+    }
+
+    #[test]
+    fn test_flowchart_features() {
+        let stats = analyze(
+            "graph TD\n subgraph \"S\"\n A -->|go| B\n end\n classDef hot fill:#f00\n class A hot",
+        )
+        .unwrap();
+        assert_eq!(stats.features, vec!["subgraphs", "styles", "edge_labels"]);
+        assert!(analyze("graph TD\n A --> B").unwrap().features.is_empty());
+    }
+
+    #[test]
+    fn test_other_diagram_types() {
+        let sequence = analyze("sequenceDiagram\n Alice->>Bob: Hi\n Bob->>Alice: Hey").unwrap();
+        assert_eq!(sequence.diagram_type, "sequence");
+        assert_eq!((sequence.nodes, sequence.edges, sequence.depth), (2, 2, 2));
+
+        let er = analyze("erDiagram\n CUSTOMER ||--o{ ORDER : places").unwrap();
+        assert_eq!(er.diagram_type, "er");
+        assert_eq!((er.nodes, er.edges, er.depth), (2, 1, 2));
+
+        let class = analyze("classDiagram\n Animal <|-- Dog").unwrap();
+        assert_eq!(class.diagram_type, "class");
+        assert_eq!(class.nodes, 2);
+    }
+}
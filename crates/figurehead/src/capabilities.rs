@@ -0,0 +1,367 @@
+//! Machine-readable description of what this build supports
+//!
+//! Editor integrations can call [`capabilities`] to find out which diagram
+//! types, statements, shapes, edge types and output options the installed
+//! version understands, instead of hardcoding a list per release.
+
+use crate::core::{
+    CanvasMode, CharacterSet, DiamondStyle, EdgeCurve, EdgeType, LabelPlacement, LineWeight,
+    NodeShape, SplitLabels, Theme,
+};
+
+/// A statement form accepted by a diagram parser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementCapability {
+    /// Short statement name, e.g. `edge` or `classDef`
+    pub name: &'static str,
+    /// Example of the accepted syntax
+    pub syntax: &'static str,
+}
+
+/// A supported diagram type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagramCapability {
+    /// Name used by the orchestrator and `figurehead detect`
+    pub name: &'static str,
+    /// One-line description
+    pub description: &'static str,
+    /// Header keywords that introduce the diagram
+    pub keywords: Vec<&'static str>,
+    /// Statement forms understood by the parser
+    pub statements: Vec<StatementCapability>,
+}
+
+/// A flowchart node shape
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapeCapability {
+    /// Shape name as used in `NodeShape`
+    pub name: String,
+    /// Mermaid syntax around the node label
+    pub syntax: &'static str,
+}
+
+/// A flowchart edge type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeCapability {
+    /// Edge operator as written in markup
+    pub syntax: String,
+    /// Whether the edge ends in an arrowhead
+    pub arrow: bool,
+    /// Whether the line is dotted
+    pub dotted: bool,
+    /// Whether the line is thick
+    pub thick: bool,
+}
+
+/// Rendering options that change the produced text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputCapabilities {
+    /// Character sets (`--style`)
+    pub character_sets: Vec<String>,
+    /// Decision node styles (`--diamond`)
+    pub diamond_styles: Vec<String>,
+    /// Edge label strategies (`--label-placement`)
+    pub label_placements: Vec<String>,
+    /// Drawing of labels shared by a split (`--split-labels`)
+    pub split_labels: Vec<String>,
+    /// Drawing of edges between offset nodes (`--curve`)
+    pub edge_curves: Vec<String>,
+    /// Weights of node and edge lines (`--line-weight`)
+    pub line_weights: Vec<String>,
+    /// Named color themes (`--theme`)
+    pub themes: Vec<String>,
+    /// Canvases for shapes and edges (`--canvas`)
+    pub canvas_modes: Vec<String>,
+}
+
+/// Everything this version of figurehead supports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Crate version
+    pub version: &'static str,
+    /// Supported diagram types
+    pub diagram_types: Vec<DiagramCapability>,
+    /// Flowchart node shapes
+    pub shapes: Vec<ShapeCapability>,
+    /// Flowchart edge types
+    pub edge_types: Vec<EdgeCapability>,
+    /// Output options
+    pub output: OutputCapabilities,
+}
+
+impl Capabilities {
+    /// Look up a diagram type by name
+    pub fn diagram(&self, name: &str) -> Option<&DiagramCapability> {
+        self.diagram_types.iter().find(|d| d.name == name)
+    }
+}
+
+/// Describe the diagram types, syntax and output options this build supports
+///
+/// # Example
+/// ```rust
+/// let caps = figurehead::capabilities();
+/// assert!(caps.diagram("flowchart").is_some());
+/// assert!(caps.output.character_sets.contains(&"ascii".to_string()));
+/// ```
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        diagram_types: diagram_types(),
+        shapes: NodeShape::ALL
+            .iter()
+            .map(|shape| ShapeCapability {
+                name: shape.to_string(),
+                syntax: shape.syntax(),
+            })
+            .collect(),
+        edge_types: EdgeType::ALL
+            .iter()
+            .map(|edge| EdgeCapability {
+                syntax: edge.to_string(),
+                arrow: edge.has_arrow(),
+                dotted: edge.is_dotted(),
+                thick: edge.is_thick(),
+            })
+            .collect(),
+        output: OutputCapabilities {
+            character_sets: names(&CharacterSet::ALL),
+            diamond_styles: names(&DiamondStyle::ALL),
+            label_placements: names(&LabelPlacement::ALL),
+            split_labels: names(&SplitLabels::ALL),
+            edge_curves: names(&EdgeCurve::ALL),
+            line_weights: names(&LineWeight::ALL),
+            themes: Theme::NAMES.iter().map(|name| name.to_string()).collect(),
+            canvas_modes: names(&CanvasMode::ALL),
+        },
+    }
+}
+
+fn names<T: std::fmt::Display>(values: &[T]) -> Vec<String> {
+    values.iter().map(ToString::to_string).collect()
+}
+
+fn statement(name: &'static str, syntax: &'static str) -> StatementCapability {
+    StatementCapability { name, syntax }
+}
+
+fn diagram_types() -> Vec<DiagramCapability> {
+    vec![
+        DiagramCapability {
+            name: "flowchart",
+            description: "Flowchart diagrams with nodes and edges",
+            keywords: vec!["graph", "flowchart", "digraph", "strict"],
+            statements: vec![
+                statement("node", "A[label]"),
+                statement("edge", "A -->|label| B"),
+                statement("fan-out", "A & B -->|label| C & D"),
+                statement("subgraph", "subgraph title ... end"),
+                statement("direction", "subgraph title direction LR ... end"),
+                statement("classDef", "classDef name fill:#f9f"),
+                statement("class", "class A,B name"),
+                statement("style", "style A fill:#f9f"),
+                statement("linkStyle", "linkStyle 0 stroke:#f00"),
+                statement("click", "click A \"https://example.com\" \"tooltip\""),
+                statement("init", "%%{init: {'flowchart': {'curve': 'linear'}}}%%"),
+                statement("hide", "%%fh: hide A, A-->B%%"),
+                statement("dot", "digraph { rankdir=LR; a -> b [label=\"x\"] }"),
+            ],
+        },
+        DiagramCapability {
+            name: "sequence",
+            description: "Sequence diagrams with participants and messages",
+            keywords: vec!["sequenceDiagram"],
+            statements: vec![
+                statement("participant", "participant A as Alice"),
+                statement("actor", "actor A as Alice"),
+                statement("message", "A->>B: label"),
+                statement("self-message", "A->>A: label"),
+                statement("async message", "A-)B: label"),
+                statement("reply", "B-->>A: label"),
+                statement("loop", "loop label ... end"),
+                statement("alt", "alt label ... else label ... end"),
+                statement("opt", "opt label ... end"),
+                statement("par", "par label ... and label ... end"),
+            ],
+        },
+        DiagramCapability {
+            name: "gitgraph",
+            description: "Git history graphs with branches and merges",
+            keywords: vec!["gitGraph"],
+            statements: vec![
+                statement("commit", "commit id: \"abc\" tag: \"v1\""),
+                statement("branch", "branch feature"),
+                statement("checkout", "checkout feature"),
+                statement("switch", "switch feature"),
+                statement("merge", "merge feature tag: \"v2\""),
+                statement("cherry-pick", "cherry-pick id: \"abc\""),
+                statement("options", "options { \"showBranches\": false } end"),
+            ],
+        },
+        DiagramCapability {
+            name: "class",
+            description: "Class diagrams with members and relationships",
+            keywords: vec!["classDiagram", "classDiagram-v2"],
+            statements: vec![
+                statement("class", "class Animal { +name }"),
+                statement("relationship", "Animal <|-- Dog"),
+                statement("direction", "direction RL"),
+                statement("cardinality", "Customer \"1\" --> \"*\" Order : places"),
+            ],
+        },
+        DiagramCapability {
+            name: "state",
+            description: "State diagrams with states and transitions",
+            keywords: vec!["stateDiagram", "stateDiagram-v2"],
+            statements: vec![
+                statement("state", "state \"Label\" as S1"),
+                statement("transition", "[*] --> S1 : label"),
+                statement("composite", "state S1 { ... }"),
+                statement("region", "state S1 { ... -- ... }"),
+                statement("fork", "state fork_state <<fork>>"),
+                statement("join", "state join_state <<join>>"),
+                statement("choice", "state check <<choice>>"),
+                statement("weight", "%%fh: weight S1-->S2 42%%"),
+            ],
+        },
+        DiagramCapability {
+            name: "er",
+            description: "Entity relationship diagrams with attributes and cardinalities",
+            keywords: vec!["erDiagram"],
+            statements: vec![
+                statement("entity", "CUSTOMER[Customer] { ... }"),
+                statement("attribute", "string name PK \"comment\""),
+                statement("relationship", "CUSTOMER ||--o{ ORDER : places"),
+            ],
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lists_every_orchestrated_diagram_type() {
+        let caps = capabilities();
+        let names: Vec<_> = caps.diagram_types.iter().map(|d| d.name).collect();
+        assert_eq!(
+            names,
+            ["flowchart", "sequence", "gitgraph", "class", "state", "er"]
+        );
+        assert!(caps
+            .diagram("sequence")
+            .unwrap()
+            .keywords
+            .contains(&"sequenceDiagram"));
+        assert!(caps.diagram("er").unwrap().keywords.contains(&"erDiagram"));
+        assert!(caps.diagram("pie").is_none());
+    }
+
+    fn syntax(diagram: &str, statement: &str) -> &'static str {
+        let caps = capabilities();
+        let diagram = caps.diagram(diagram).unwrap();
+        diagram
+            .statements
+            .iter()
+            .find(|s| s.name == statement)
+            .unwrap_or_else(|| panic!("no {} statement", statement))
+            .syntax
+    }
+
+    #[test]
+    fn test_lists_dot_input_under_flowchart() {
+        let flowchart = capabilities().diagram("flowchart").unwrap().clone();
+        assert!(flowchart.keywords.contains(&"digraph"));
+        assert!(syntax("flowchart", "dot").starts_with("digraph {"));
+    }
+
+    #[test]
+    fn test_lists_subgraph_direction() {
+        assert!(syntax("flowchart", "direction").contains("direction LR"));
+    }
+
+    #[test]
+    fn test_lists_flowchart_click_links() {
+        assert!(syntax("flowchart", "click").starts_with("click A \"https://"));
+    }
+
+    #[test]
+    fn test_lists_sequence_frames() {
+        assert_eq!(syntax("sequence", "loop"), "loop label ... end");
+        assert!(syntax("sequence", "alt").contains("else"));
+        assert!(syntax("sequence", "opt").starts_with("opt"));
+        assert!(syntax("sequence", "par").contains("and"));
+    }
+
+    #[test]
+    fn test_lists_self_and_async_messages() {
+        assert_eq!(syntax("sequence", "self-message"), "A->>A: label");
+        assert_eq!(syntax("sequence", "async message"), "A-)B: label");
+        assert_eq!(syntax("sequence", "reply"), "B-->>A: label");
+    }
+
+    #[test]
+    fn test_lists_state_pseudostates() {
+        assert!(syntax("state", "fork").ends_with("<<fork>>"));
+        assert!(syntax("state", "join").ends_with("<<join>>"));
+        assert!(syntax("state", "choice").ends_with("<<choice>>"));
+    }
+
+    #[test]
+    fn test_lists_concurrent_regions() {
+        assert_eq!(syntax("state", "composite"), "state S1 { ... }");
+        assert!(syntax("state", "region").contains(" -- "));
+    }
+
+    #[test]
+    fn test_lists_class_v2_header_and_direction() {
+        let class = capabilities().diagram("class").unwrap().clone();
+        assert_eq!(class.keywords, ["classDiagram", "classDiagram-v2"]);
+        assert_eq!(syntax("class", "direction"), "direction RL");
+    }
+
+    #[test]
+    fn test_lists_class_cardinalities() {
+        assert_eq!(
+            syntax("class", "cardinality"),
+            "Customer \"1\" --> \"*\" Order : places"
+        );
+    }
+
+    #[test]
+    fn test_shapes_and_edges_cover_core_enums() {
+        let caps = capabilities();
+        assert_eq!(caps.shapes.len(), NodeShape::ALL.len());
+        assert_eq!(caps.edge_types.len(), EdgeType::ALL.len());
+
+        let dotted = caps.edge_types.iter().find(|e| e.syntax == "-.->").unwrap();
+        assert!(dotted.arrow && dotted.dotted && !dotted.thick);
+
+        let hexagon = caps.shapes.iter().find(|s| s.name == "hexagon").unwrap();
+        assert_eq!(hexagon.syntax, "A{{label}}");
+    }
+
+    #[test]
+    fn test_output_options_round_trip_through_from_str() {
+        let output = capabilities().output;
+        for name in &output.character_sets {
+            assert!(name.parse::<CharacterSet>().is_ok(), "{}", name);
+        }
+        for name in &output.canvas_modes {
+            assert!(name.parse::<CanvasMode>().is_ok(), "{}", name);
+        }
+        for name in &output.split_labels {
+            assert!(name.parse::<SplitLabels>().is_ok(), "{}", name);
+        }
+        for name in &output.edge_curves {
+            assert!(name.parse::<EdgeCurve>().is_ok(), "{}", name);
+        }
+        for name in &output.line_weights {
+            assert!(name.parse::<LineWeight>().is_ok(), "{}", name);
+        }
+        for name in &output.themes {
+            assert!(name.parse::<Theme>().is_ok(), "{}", name);
+        }
+    }
+}
@@ -1,18 +1,25 @@
 //! Shared box drawing characters for diagram rendering
 //!
 //! This module provides consistent box drawing characters across all renderers,
-//! supporting both ASCII and Unicode modes.
+//! supporting both ASCII and Unicode modes, and the line weights a finished
+//! canvas can be redrawn at.
 
-use super::CharacterSet;
+use super::{AsciiCanvas, CharacterSet, LineWeight};
 
 /// Box drawing characters for rendering rectangular shapes
 #[derive(Debug, Clone, Copy)]
 pub struct BoxChars {
+    /// Top-left corner
     pub top_left: char,
+    /// Top-right corner
     pub top_right: char,
+    /// Bottom-left corner
     pub bottom_left: char,
+    /// Bottom-right corner
     pub bottom_right: char,
+    /// Top and bottom sides
     pub horizontal: char,
+    /// Left and right sides
     pub vertical: char,
     /// T-junction pointing right (for left edge separators)
     pub t_right: char,
@@ -108,16 +115,24 @@ impl Default for BoxChars {
 }
 
 /// Line drawing characters for edges and connections
+#[cfg(any(test, feature = "unstable"))]
 #[derive(Debug, Clone, Copy)]
 pub struct LineChars {
+    /// Horizontal line
     pub horizontal: char,
+    /// Vertical line
     pub vertical: char,
+    /// Arrowhead pointing up
     pub arrow_up: char,
+    /// Arrowhead pointing down
     pub arrow_down: char,
+    /// Arrowhead pointing left
     pub arrow_left: char,
+    /// Arrowhead pointing right
     pub arrow_right: char,
 }
 
+#[cfg(any(test, feature = "unstable"))]
 impl LineChars {
     /// Get line characters for the given style
     pub fn new(style: CharacterSet) -> Self {
@@ -152,12 +167,95 @@ impl LineChars {
     }
 }
 
+#[cfg(any(test, feature = "unstable"))]
 impl Default for LineChars {
     fn default() -> Self {
         Self::unicode()
     }
 }
 
+/// Light glyphs and their heavy counterparts
+///
+/// Rounded corners have no heavy form and become square.
+const HEAVY: &[(char, char)] = &[
+    ('─', '━'),
+    ('│', '┃'),
+    ('┌', '┏'),
+    ('┐', '┓'),
+    ('└', '┗'),
+    ('┘', '┛'),
+    ('├', '┣'),
+    ('┤', '┫'),
+    ('┬', '┳'),
+    ('┴', '┻'),
+    ('┼', '╋'),
+    ('╭', '┏'),
+    ('╮', '┓'),
+    ('╰', '┗'),
+    ('╯', '┛'),
+    ('┄', '┅'),
+    ('┆', '┇'),
+];
+
+/// Light glyphs and their double counterparts, then double glyphs (subgraph
+/// borders, thick edges) and the heavy ones they give way to
+const DOUBLE: &[(char, char)] = &[
+    ('─', '═'),
+    ('│', '║'),
+    ('┌', '╔'),
+    ('┐', '╗'),
+    ('└', '╚'),
+    ('┘', '╝'),
+    ('├', '╠'),
+    ('┤', '╣'),
+    ('┬', '╦'),
+    ('┴', '╩'),
+    ('┼', '╬'),
+    ('╭', '╔'),
+    ('╮', '╗'),
+    ('╰', '╚'),
+    ('╯', '╝'),
+    ('═', '━'),
+    ('║', '┃'),
+    ('╔', '┏'),
+    ('╗', '┓'),
+    ('╚', '┗'),
+    ('╝', '┛'),
+    ('╠', '┣'),
+    ('╣', '┫'),
+    ('╦', '┳'),
+    ('╩', '┻'),
+    ('╬', '╋'),
+];
+
+/// The glyph `c` is drawn as at `weight`
+///
+/// Glyphs without a counterpart, such as arrow heads and ASCII, are kept.
+pub fn weighted_glyph(c: char, weight: LineWeight) -> char {
+    let pairs = match weight {
+        LineWeight::Light => return c,
+        LineWeight::Heavy => HEAVY,
+        LineWeight::Double => DOUBLE,
+    };
+    pairs
+        .iter()
+        .find_map(|&(from, to)| (from == c).then_some(to))
+        .unwrap_or(c)
+}
+
+/// Redraw every line of a finished canvas at `weight`
+///
+/// Each cell is converted once, so double glyphs giving way to heavy ones
+/// never collide with light glyphs becoming double.
+pub fn reweigh_canvas(canvas: &mut AsciiCanvas, weight: LineWeight) {
+    if weight == LineWeight::Light {
+        return;
+    }
+    for cell in canvas.grid.iter_mut().flatten() {
+        *cell = weighted_glyph(*cell, weight);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,6 +288,19 @@ mod tests {
         assert_eq!(chars.horizontal, '═');
     }
 
+    #[test]
+    fn test_weighted_glyphs() {
+        assert_eq!(weighted_glyph('┌', LineWeight::Light), '┌');
+        assert_eq!(weighted_glyph('┌', LineWeight::Heavy), '┏');
+        assert_eq!(weighted_glyph('╭', LineWeight::Heavy), '┏');
+        assert_eq!(weighted_glyph('┼', LineWeight::Double), '╬');
+        // Double lines give way so they stay distinct
+        assert_eq!(weighted_glyph('═', LineWeight::Double), '━');
+        assert_eq!(weighted_glyph('═', LineWeight::Heavy), '═');
+        assert_eq!(weighted_glyph('▶', LineWeight::Heavy), '▶');
+        assert_eq!(weighted_glyph('-', LineWeight::Double), '-');
+    }
+
     #[test]
     fn test_line_chars_ascii() {
         let chars = LineChars::new(CharacterSet::Ascii);
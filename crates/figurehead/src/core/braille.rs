@@ -0,0 +1,240 @@
+//! Braille dot canvas for high-resolution rendering
+//!
+//! Each terminal cell holds a 2x4 grid of dots (Unicode block U+2800), so
+//! lines and shapes can be rasterized at four times the vertical and twice
+//! the horizontal resolution of a character grid. Text is kept on a separate
+//! layer and always wins over dots in the cells it occupies.
+
+use super::AsciiCanvas;
+
+/// Dots per cell horizontally
+pub const BRAILLE_DOTS_X: usize = 2;
+/// Dots per cell vertically
+pub const BRAILLE_DOTS_Y: usize = 4;
+
+/// First codepoint of the Unicode braille patterns block
+const BRAILLE_BASE: u32 = 0x2800;
+
+/// Bit for the dot at (column, row) within a cell
+const DOT_BITS: [[u8; BRAILLE_DOTS_Y]; BRAILLE_DOTS_X] =
+    [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// A canvas addressed in dot coordinates
+#[derive(Debug, Clone)]
+pub struct BrailleCanvas {
+    width: usize,
+    height: usize,
+    cells: Vec<Vec<u8>>,
+    text: AsciiCanvas,
+}
+
+impl BrailleCanvas {
+    /// Create a canvas covering `width` x `height` character cells
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![vec![0; width]; height],
+            text: AsciiCanvas::new(width, height),
+        }
+    }
+
+    /// Width in dots
+    pub fn dot_width(&self) -> usize {
+        self.width * BRAILLE_DOTS_X
+    }
+
+    /// Height in dots
+    pub fn dot_height(&self) -> usize {
+        self.height * BRAILLE_DOTS_Y
+    }
+
+    /// Set a single dot; out-of-bounds dots are ignored
+    pub fn set_dot(&mut self, x: isize, y: isize) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        let (x, y) = (x as usize, y as usize);
+        if x >= self.dot_width() || y >= self.dot_height() {
+            return;
+        }
+        self.cells[y / BRAILLE_DOTS_Y][x / BRAILLE_DOTS_X] |=
+            DOT_BITS[x % BRAILLE_DOTS_X][y % BRAILLE_DOTS_Y];
+    }
+
+    /// Returns true if the dot is set
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn is_set(&self, x: usize, y: usize) -> bool {
+        if x >= self.dot_width() || y >= self.dot_height() {
+            return false;
+        }
+        self.cells[y / BRAILLE_DOTS_Y][x / BRAILLE_DOTS_X]
+            & DOT_BITS[x % BRAILLE_DOTS_X][y % BRAILLE_DOTS_Y]
+            != 0
+    }
+
+    /// Draw a straight line between two dots (Bresenham)
+    pub fn line(&mut self, from: (isize, isize), to: (isize, isize)) {
+        self.stroke(from, to, usize::MAX, 0);
+    }
+
+    /// Draw a dashed line: `on` dots set, then `off` dots skipped
+    pub fn dashed_line(&mut self, from: (isize, isize), to: (isize, isize), on: usize, off: usize) {
+        self.stroke(from, to, on.max(1), off);
+    }
+
+    fn stroke(&mut self, from: (isize, isize), to: (isize, isize), on: usize, off: usize) {
+        let period = on.saturating_add(off);
+        let (mut x, mut y) = from;
+        let dx = (to.0 - x).abs();
+        let dy = -(to.1 - y).abs();
+        let sx = if x < to.0 { 1 } else { -1 };
+        let sy = if y < to.1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        let mut step = 0usize;
+
+        loop {
+            if step % period < on {
+                self.set_dot(x, y);
+            }
+            step += 1;
+            if (x, y) == to {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Draw a closed polygon through the given dots
+    pub fn polygon(&mut self, points: &[(isize, isize)]) {
+        for (i, &from) in points.iter().enumerate() {
+            let to = points[(i + 1) % points.len()];
+            self.line(from, to);
+        }
+    }
+
+    /// Draw a rectangle outline with inclusive corners
+    pub fn rect(&mut self, left: isize, top: isize, right: isize, bottom: isize) {
+        self.polygon(&[(left, top), (right, top), (right, bottom), (left, bottom)]);
+    }
+
+    /// Draw an ellipse outline inscribed in the given inclusive bounds
+    pub fn ellipse(&mut self, left: isize, top: isize, right: isize, bottom: isize) {
+        let cx = (left + right) as f64 / 2.0;
+        let cy = (top + bottom) as f64 / 2.0;
+        let rx = (right - left) as f64 / 2.0;
+        let ry = (bottom - top) as f64 / 2.0;
+        // Enough steps that neighbouring samples land on adjacent dots
+        let steps = ((rx + ry) * 4.0).ceil().max(8.0) as usize;
+
+        let mut prev: Option<(isize, isize)> = None;
+        for i in 0..=steps {
+            let t = i as f64 / steps as f64 * std::f64::consts::TAU;
+            let point = (
+                (cx + rx * t.cos()).round() as isize,
+                (cy + ry * t.sin()).round() as isize,
+            );
+            match prev {
+                Some(prev) => self.line(prev, point),
+                None => self.set_dot(point.0, point.1),
+            }
+            prev = Some(point);
+        }
+    }
+
+    /// Draw text at a cell position; text hides any dots beneath it
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &str) {
+        self.text.draw_text(x, y, text);
+    }
+
+    /// Draw text centered on a cell column
+    pub fn draw_text_centered(&mut self, center_x: usize, y: usize, text: &str) {
+        self.text.draw_text_centered(center_x, y, text);
+    }
+
+    /// Flatten dots and text into a character canvas
+    pub fn to_canvas(&self) -> AsciiCanvas {
+        let mut canvas = self.text.clone();
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, &bits) in row.iter().enumerate() {
+                if bits != 0 && canvas.get_char(x, y) == ' ' {
+                    let c = char::from_u32(BRAILLE_BASE + bits as u32).unwrap_or(' ');
+                    canvas.set_char(x, y, c);
+                }
+            }
+        }
+        canvas
+    }
+}
+
+impl std::fmt::Display for BrailleCanvas {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_canvas())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dot_bits_map_to_braille_patterns() {
+        let mut canvas = BrailleCanvas::new(1, 1);
+        canvas.set_dot(0, 0);
+        assert_eq!(canvas.to_string(), "⠁");
+
+        canvas.set_dot(1, 3);
+        assert_eq!(canvas.to_string(), "⢁");
+        assert!(canvas.is_set(1, 3));
+        assert!(!canvas.is_set(0, 3));
+    }
+
+    #[test]
+    fn test_horizontal_line_fills_cells() {
+        let mut canvas = BrailleCanvas::new(3, 1);
+        canvas.line((0, 0), (5, 0));
+        assert_eq!(canvas.to_string(), "⠉⠉⠉");
+    }
+
+    #[test]
+    fn test_diagonal_line_steps_within_cells() {
+        let mut canvas = BrailleCanvas::new(2, 2);
+        canvas.line((0, 0), (3, 7));
+        // A steep diagonal sets a dot in every row
+        for y in 0..8 {
+            assert!((0..4).any(|x| canvas.is_set(x, y)), "row {} empty", y);
+        }
+    }
+
+    #[test]
+    fn test_dashed_line_skips_gaps() {
+        let mut canvas = BrailleCanvas::new(4, 1);
+        canvas.dashed_line((0, 0), (7, 0), 2, 2);
+        let set: Vec<bool> = (0..8).map(|x| canvas.is_set(x, 0)).collect();
+        assert_eq!(set, [true, true, false, false, true, true, false, false]);
+    }
+
+    #[test]
+    fn test_text_hides_dots() {
+        let mut canvas = BrailleCanvas::new(3, 1);
+        canvas.line((0, 0), (5, 0));
+        canvas.draw_text(1, 0, "x");
+        assert_eq!(canvas.to_string(), "⠉x⠉");
+    }
+
+    #[test]
+    fn test_out_of_bounds_dots_are_ignored() {
+        let mut canvas = BrailleCanvas::new(1, 1);
+        canvas.line((-3, -3), (5, 5));
+        assert!(canvas.is_set(0, 0));
+        assert!(canvas.is_set(1, 1));
+    }
+}
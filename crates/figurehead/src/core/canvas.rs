@@ -5,9 +5,14 @@
 /// ASCII canvas representing a character grid for diagram rendering
 #[derive(Debug, Clone)]
 pub struct AsciiCanvas {
+    /// Width in cells
     pub width: usize,
+    /// Height in cells
     pub height: usize,
+    /// Rows of cells, top to bottom
     pub grid: Vec<Vec<char>>,
+    /// Cells written since [`track_writes`](Self::track_writes), if tracking
+    writes: Option<Vec<(usize, usize)>>,
 }
 
 impl AsciiCanvas {
@@ -18,6 +23,7 @@ impl AsciiCanvas {
             width,
             height,
             grid,
+            writes: None,
         }
     }
 
@@ -41,6 +47,23 @@ impl AsciiCanvas {
     pub fn set_char(&mut self, x: usize, y: usize, c: char) {
         self.ensure_size(x + 1, y + 1);
         self.grid[y][x] = c;
+        if let Some(writes) = &mut self.writes {
+            writes.push((x, y));
+        }
+    }
+
+    /// Start logging the position of every cell written
+    ///
+    /// Lets a renderer tell which cells one element was drawn over, even
+    /// where it redrew a character that was already there.
+    pub fn track_writes(&mut self) {
+        self.writes = Some(Vec::new());
+    }
+
+    /// Stop logging and return the cells written since
+    /// [`track_writes`](Self::track_writes), in order (repeats included)
+    pub fn take_writes(&mut self) -> Vec<(usize, usize)> {
+        self.writes.take().unwrap_or_default()
     }
 
     /// Get the character at the specified position
@@ -79,6 +102,7 @@ impl AsciiCanvas {
     }
 
     /// Draw a vertical line
+    #[cfg(any(test, feature = "unstable"))]
     pub fn draw_vertical_line(&mut self, x: usize, y: usize, length: usize, c: char) {
         for i in 0..length {
             self.set_char(x, y + i, c);
@@ -86,6 +110,98 @@ impl AsciiCanvas {
     }
 }
 
+/// Sentinel used while capturing a [`CellBlock`] to mark untouched cells
+const UNTOUCHED: char = '\0';
+
+/// A pre-rendered rectangular block of cells that can be blitted onto a canvas
+///
+/// Cells that were never written during capture are transparent, so blitting
+/// a block leaves whatever is underneath them intact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellBlock {
+    /// Width in cells
+    pub width: usize,
+    /// Height in cells
+    pub height: usize,
+    cells: Vec<Vec<Option<char>>>,
+}
+
+impl CellBlock {
+    /// Capture the cells written by `draw` onto a scratch canvas of the given size
+    ///
+    /// The scratch canvas grows as needed, but only cells inside the requested
+    /// area are guaranteed to be transparent when left untouched.
+    pub fn capture<F>(width: usize, height: usize, draw: F) -> Self
+    where
+        F: FnOnce(&mut AsciiCanvas),
+    {
+        let mut scratch = AsciiCanvas::new(width, height);
+        for row in &mut scratch.grid {
+            row.fill(UNTOUCHED);
+        }
+        draw(&mut scratch);
+
+        let cells = scratch
+            .grid
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&c| if c == UNTOUCHED { None } else { Some(c) })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            width: scratch.width,
+            height: scratch.height,
+            cells,
+        }
+    }
+
+    /// Get the cell at the specified position (`None` if transparent)
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn get(&self, x: usize, y: usize) -> Option<char> {
+        self.cells
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .flatten()
+    }
+}
+
+impl AsciiCanvas {
+    /// Copy the opaque cells of a block onto the canvas with its origin at (x, y)
+    pub fn blit(&mut self, x: usize, y: usize, block: &CellBlock) {
+        for (row_idx, row) in block.cells.iter().enumerate() {
+            for (col_idx, cell) in row.iter().enumerate() {
+                if let Some(c) = cell {
+                    self.set_char(x + col_idx, y + row_idx, *c);
+                }
+            }
+        }
+    }
+}
+
+impl AsciiCanvas {
+    /// Columns and rows that [`Display`](std::fmt::Display) trims from the
+    /// left and top, for mapping canvas positions onto the printed text
+    pub fn trim_offset(&self) -> (usize, usize) {
+        let rows: Vec<String> = self
+            .grid
+            .iter()
+            .map(|row| row.iter().collect::<String>().trim_end().to_string())
+            .collect();
+        let top = rows.iter().take_while(|row| row.is_empty()).count();
+        let left = rows
+            .iter()
+            .filter(|row| !row.is_empty())
+            .map(|row| row.chars().take_while(|c| *c == ' ').count())
+            .min()
+            .unwrap_or(0);
+        (left, top)
+    }
+}
+
 impl std::fmt::Display for AsciiCanvas {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut rows: Vec<String> = self
@@ -190,5 +306,37 @@ mod tests {
         canvas.draw_text(5, 3, "Test");
         let output = canvas.to_string();
         assert_eq!(output, "Test");
+        assert_eq!(canvas.trim_offset(), (5, 3));
+    }
+
+    #[test]
+    fn test_tracked_writes_include_redrawn_cells() {
+        let mut canvas = AsciiCanvas::new(6, 2);
+        canvas.draw_text(0, 0, "ab");
+        canvas.track_writes();
+        canvas.draw_text(1, 0, "bc");
+        canvas.set_char(0, 1, 'x');
+        assert_eq!(canvas.take_writes(), [(1, 0), (2, 0), (0, 1)]);
+
+        canvas.set_char(3, 1, 'y');
+        assert!(canvas.take_writes().is_empty());
+    }
+
+    #[test]
+    fn test_cell_block_capture_and_blit() {
+        let block = CellBlock::capture(3, 1, |c| {
+            c.set_char(0, 0, '[');
+            c.set_char(2, 0, ']');
+        });
+        assert_eq!(block.get(0, 0), Some('['));
+        assert_eq!(block.get(1, 0), None);
+
+        let mut canvas = AsciiCanvas::new(10, 3);
+        canvas.set_char(5, 1, '-');
+        canvas.blit(4, 1, &block);
+        assert_eq!(canvas.get_char(4, 1), '[');
+        // Transparent cell keeps what was underneath
+        assert_eq!(canvas.get_char(5, 1), '-');
+        assert_eq!(canvas.get_char(6, 1), ']');
     }
 }
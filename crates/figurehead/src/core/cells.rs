@@ -0,0 +1,194 @@
+//! Rendered diagrams as structured cell grids
+//!
+//! [`CellGrid`] holds the final drawing as rows of cells, each with its
+//! character, text attributes and the ID of the element drawn there. Tools
+//! building interactive views on top of a render (mouse hit-testing, hover
+//! highlights) read the grid instead of redoing the layout. With the `serde`
+//! feature the grid can be written out as JSON or any other serde format.
+
+use super::html::SgrState;
+use super::{Palette, RoleLayer};
+
+/// Text attributes of one cell
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CellAttributes {
+    /// Text color, any CSS color value
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub color: Option<String>,
+    /// Background color, any CSS color value
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub background: Option<String>,
+    /// Bold text
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub bold: bool,
+    /// Dimmed text
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub dim: bool,
+    /// Italic text
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub italic: bool,
+    /// Underlined text
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub underline: bool,
+}
+
+#[cfg(feature = "serde")]
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl CellAttributes {
+    /// Whether no attribute is set
+    pub fn is_plain(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl From<&SgrState> for CellAttributes {
+    fn from(state: &SgrState) -> Self {
+        Self {
+            color: state.foreground.clone(),
+            background: state.background.clone(),
+            bold: state.bold,
+            dim: state.dim,
+            italic: state.italic,
+            underline: state.underline,
+        }
+    }
+}
+
+/// One character cell of a [`CellGrid`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GridCell {
+    /// The drawn character (`' '` for blank cells)
+    pub ch: char,
+    /// Text attributes
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "CellAttributes::is_plain")
+    )]
+    pub style: CellAttributes,
+    /// ID of the element drawn over this cell, if any
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub owner: Option<String>,
+}
+
+impl GridCell {
+    fn blank() -> Self {
+        Self {
+            ch: ' ',
+            style: CellAttributes::default(),
+            owner: None,
+        }
+    }
+}
+
+/// A rendered diagram as a rectangular grid of cells
+///
+/// Coordinates are `(column, line)` in characters of the rendered text; short
+/// lines are padded with blank cells so every row is `width` cells long.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellGrid {
+    /// Cells per row
+    pub width: usize,
+    /// Number of rows
+    pub height: usize,
+    /// Cells, top row first
+    pub rows: Vec<Vec<GridCell>>,
+}
+
+impl CellGrid {
+    /// The cell at a position, if the grid reaches it
+    pub fn get(&self, x: usize, y: usize) -> Option<&GridCell> {
+        self.rows.get(y).and_then(|row| row.get(x))
+    }
+
+    /// ID of the element drawn at a position
+    pub fn owner_at(&self, x: usize, y: usize) -> Option<&str> {
+        self.get(x, y).and_then(|cell| cell.owner.as_deref())
+    }
+}
+
+impl RoleLayer {
+    /// Grid of rendered text with this layer's styles and owners
+    ///
+    /// Cells are `(column, line)` of `text`; roles take their colors from
+    /// `palette`.
+    pub fn to_grid(&self, text: &str, palette: &Palette) -> CellGrid {
+        let mut rows: Vec<Vec<GridCell>> = text
+            .split('\n')
+            .enumerate()
+            .map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(|(x, ch)| GridCell {
+                        ch,
+                        style: self
+                            .style(x, y)
+                            .map(|style| (&style.sgr_state(ch, palette)).into())
+                            .unwrap_or_default(),
+                        owner: self.owner(x, y).map(str::to_string),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        for (y, row) in rows.iter_mut().enumerate() {
+            for x in row.len()..width {
+                row.push(GridCell {
+                    owner: self.owner(x, y).map(str::to_string),
+                    ..GridCell::blank()
+                });
+            }
+        }
+        CellGrid {
+            width,
+            height: rows.len(),
+            rows,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_keeps_layer_styles_and_owners() {
+        let mut layer = RoleLayer::new();
+        layer.paint(1, 0, crate::core::Role::Node);
+        layer.paint_style(
+            0,
+            1,
+            &crate::core::CellStyle {
+                background: Some("#f9f".to_string()),
+                ..Default::default()
+            },
+        );
+        layer.set_owner(1, 0, "A");
+
+        let grid = layer.to_grid("[A] ->\nB", &Palette::default());
+        assert_eq!((grid.width, grid.height), (6, 2));
+        assert_eq!(
+            grid.get(1, 0).unwrap().style.color.as_deref(),
+            Some("#11a8cd")
+        );
+        assert!(grid.get(4, 0).unwrap().style.is_plain());
+        assert_eq!(grid.get(5, 1).unwrap().ch, ' ');
+        assert!(grid.get(6, 0).is_none());
+        assert_eq!(grid.owner_at(1, 0), Some("A"));
+        assert_eq!(grid.owner_at(0, 1), None);
+        assert_eq!(
+            grid.get(0, 1).unwrap().style.background.as_deref(),
+            Some("#f9f")
+        );
+    }
+}
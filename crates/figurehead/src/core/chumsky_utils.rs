@@ -1,10 +1,11 @@
 //! Shared chumsky parser utilities for diagram parsing
 //!
 //! This module provides common parser combinators used across different
-//! diagram type parsers.
+//! diagram type parsers, and the statement splitter that feeds them.
 
 use chumsky::prelude::*;
 use chumsky::text::whitespace;
+use std::ops::Range;
 
 /// Parse optional whitespace including newlines.
 ///
@@ -49,6 +50,181 @@ pub fn optional_whitespace_or_comment<'src>() -> impl Parser<'src, &'src str, ()
     whitespace_or_comment().or_not().ignored()
 }
 
+/// A statement cut from diagram input, with where it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpannedStatement {
+    /// Statement text, trimmed; block lines are joined with spaces
+    pub text: String,
+    /// Byte range of the statement in the input
+    pub span: Range<usize>,
+    /// 1-based line where the statement starts
+    pub line: usize,
+    /// 1-based column (in characters) where the statement starts
+    pub column: usize,
+}
+
+/// Splits diagram input into statements without losing their positions
+///
+/// Blank lines and `%%` comment lines are skipped. With semicolons enabled,
+/// `A-->B; B-->C` is two statements (semicolons inside double quotes, and
+/// those closing an entity such as `&amp;` or `#35;`, are kept). A line starting with one of the block keywords (e.g. `subgraph`)
+/// opens a block that runs, line by line, up to a line holding only `end`
+/// and becomes one statement.
+#[derive(Debug, Clone, Copy)]
+pub struct StatementSplitter<'a> {
+    semicolons: bool,
+    blocks: &'a [&'a str],
+}
+
+impl Default for StatementSplitter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> StatementSplitter<'a> {
+    /// Split on lines and semicolons, with no blocks
+    pub fn new() -> Self {
+        Self {
+            semicolons: true,
+            blocks: &[],
+        }
+    }
+
+    /// Set whether `;` separates statements on a line
+    pub fn with_semicolons(mut self, semicolons: bool) -> Self {
+        self.semicolons = semicolons;
+        self
+    }
+
+    /// Set the keywords (matched case-insensitively) that open an `end` block
+    pub fn with_blocks(mut self, blocks: &'a [&'a str]) -> Self {
+        self.blocks = blocks;
+        self
+    }
+
+    /// Split `input` into statements in source order
+    ///
+    /// Blocks nest: each opening keyword inside a block needs its own `end`.
+    /// A block still open at the end of the input is returned as it stands.
+    pub fn split(&self, input: &str) -> Vec<SpannedStatement> {
+        let mut statements = Vec::new();
+        // The open block, its lines so far and how many blocks deep we are
+        let mut block: Option<(SpannedStatement, Vec<&str>, usize)> = None;
+
+        for (index, (line_start, line)) in lines_with_offsets(input).enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with("%%") {
+                continue;
+            }
+
+            // Block bodies are kept whole, line by line
+            let segments = match block {
+                Some(_) => std::iter::once(0..line.len()).collect(),
+                None => self.segments(line),
+            };
+            for segment in segments {
+                let raw = &line[segment.clone()];
+                let text = raw.trim();
+                let start = line_start + segment.start + (raw.len() - raw.trim_start().len());
+                let span = start..start + text.len();
+
+                if let Some((open, parts, depth)) = block.as_mut() {
+                    parts.push(text);
+                    open.span.end = span.end;
+                    if self.opens_block(text) {
+                        *depth += 1;
+                    } else if text.eq_ignore_ascii_case("end") && *depth > 1 {
+                        *depth -= 1;
+                    } else if text.eq_ignore_ascii_case("end") {
+                        open.text = parts.join(" ");
+                        statements.push(open.clone());
+                        block = None;
+                    }
+                    continue;
+                }
+
+                let statement = SpannedStatement {
+                    text: text.to_string(),
+                    column: input[line_start..start].chars().count() + 1,
+                    line: index + 1,
+                    span,
+                };
+                if self.opens_block(text) {
+                    block = Some((statement, vec![text], 1));
+                } else {
+                    statements.push(statement);
+                }
+            }
+        }
+
+        if let Some((mut open, parts, _)) = block {
+            open.text = parts.join(" ");
+            statements.push(open);
+        }
+        statements
+    }
+
+    /// Byte ranges of the statements on one line
+    fn segments(&self, line: &str) -> Vec<Range<usize>> {
+        if !self.semicolons {
+            return std::iter::once(0..line.len()).collect();
+        }
+        let mut segments = Vec::new();
+        let mut quoted = false;
+        let mut start = 0;
+        for (i, c) in line.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ';' if !quoted && !closes_entity(&line[..i]) => {
+                    segments.push(start..i);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        segments.push(start..line.len());
+        segments.retain(|segment| !line[segment.clone()].trim().is_empty());
+        segments
+    }
+
+    fn opens_block(&self, text: &str) -> bool {
+        let lower = text.to_lowercase();
+        self.blocks.iter().any(|keyword| {
+            lower
+                .strip_prefix(&keyword.to_lowercase())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        })
+    }
+}
+
+/// Returns true if a `;` after `before` would end an entity (`&amp`, `#35`)
+fn closes_entity(before: &str) -> bool {
+    let rest = before.trim_end_matches(|c: char| c.is_ascii_alphanumeric());
+    let name_len = before.len() - rest.len();
+    (1..=8).contains(&name_len) && rest.ends_with(['&', '#'])
+}
+
+/// Lines of `input` with the byte offset where each starts
+fn lines_with_offsets(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line.trim_end_matches(['\n', '\r'])))
+    })
+}
+
+/// 1-based line and column (in characters) of a byte offset in `input`
+pub fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(input.len());
+    let before = &input[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,4 +269,70 @@ mod tests {
         // Not a comment
         assert!(parser.parse("% not a comment").into_result().is_err());
     }
+
+    #[test]
+    fn test_statement_splitter_tracks_spans() {
+        let input = "graph TD\n  A-->B; C[\"x; y\"]\n\n  %% note\n  é-->F";
+        let statements = StatementSplitter::new().split(input);
+        let texts: Vec<&str> = statements.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["graph TD", "A-->B", "C[\"x; y\"]", "é-->F"]);
+
+        for statement in &statements {
+            assert_eq!(&input[statement.span.clone()], statement.text);
+            assert_eq!(
+                line_column(input, statement.span.start),
+                (statement.line, statement.column)
+            );
+        }
+        assert_eq!((statements[2].line, statements[2].column), (2, 10));
+        assert_eq!(line_column(input, input.len()), (5, 8));
+    }
+
+    #[test]
+    fn test_statement_splitter_keeps_entity_semicolons() {
+        let statements = StatementSplitter::new().split("A[Fish &amp; #35;1 &#36;]; B");
+        let texts: Vec<&str> = statements.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(texts, vec!["A[Fish &amp; #35;1 &#36;]", "B"]);
+    }
+
+    #[test]
+    fn test_statement_splitter_blocks_and_semicolons() {
+        let input = "subgraph One; A-->B\n  B; C\n END\nD;E\nsubgraph Open\n  F";
+        let statements = StatementSplitter::new()
+            .with_blocks(&["subgraph"])
+            .split(input);
+        let texts: Vec<&str> = statements.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["subgraph One A-->B B; C END", "D", "E", "subgraph Open F"]
+        );
+        assert_eq!(statements[0].span, 0..input.find("END").unwrap() + 3);
+
+        let lines = StatementSplitter::new().with_semicolons(false).split("D;E");
+        assert_eq!(lines[0].text, "D;E");
+        // Only whole keywords open blocks
+        let plain = StatementSplitter::new()
+            .with_blocks(&["subgraph"])
+            .split("subgraphs-->x");
+        assert_eq!(plain[0].text, "subgraphs-->x");
+    }
+
+    #[test]
+    fn test_statement_splitter_nested_blocks() {
+        let input = "subgraph Outer
+ subgraph Inner
+  A
+ end
+ B
+end
+C";
+        let statements = StatementSplitter::new()
+            .with_blocks(&["subgraph"])
+            .split(input);
+        let texts: Vec<&str> = statements.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec!["subgraph Outer subgraph Inner A end B end", "C"]
+        );
+    }
 }
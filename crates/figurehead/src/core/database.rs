@@ -4,6 +4,10 @@
 //! Each diagram type implements this with its own node and edge data types.
 
 use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::{Direction, ParseWarning};
 
 /// Core trait for diagram databases
 ///
@@ -43,4 +47,384 @@ pub trait Database: Send + Sync {
 
     /// Get the number of edges
     fn edge_count(&self) -> usize;
+
+    /// Iterate over the subgraphs grouping nodes, in the order they were
+    /// added (a nested subgraph comes before the one enclosing it)
+    ///
+    /// Diagram types without grouping have none.
+    fn subgraphs(&self) -> impl Iterator<Item = &Subgraph> {
+        std::iter::empty()
+    }
+
+    /// Get a subgraph by ID
+    fn get_subgraph(&self, id: &str) -> Option<&Subgraph> {
+        self.subgraphs().find(|s| s.id == id)
+    }
+
+    /// Get the innermost subgraph that contains a node, if any
+    fn node_subgraph(&self, node_id: &str) -> Option<&Subgraph> {
+        self.subgraphs().find(|s| s.contains(node_id))
+    }
+
+    /// Get the subgraph a subgraph is nested in, if any
+    fn parent_subgraph(&self, id: &str) -> Option<&Subgraph> {
+        let parent = self.get_subgraph(id)?.parent.as_deref()?;
+        self.get_subgraph(parent)
+    }
+
+    /// Every subgraph that contains a node, innermost first
+    fn node_subgraphs(&self, node_id: &str) -> Vec<&Subgraph> {
+        let mut chain = Vec::new();
+        let mut current = self.node_subgraph(node_id);
+        while let Some(subgraph) = current {
+            chain.push(subgraph);
+            current = self.parent_subgraph(&subgraph.id);
+        }
+        chain
+    }
+
+    /// Get the number of subgraphs
+    fn subgraph_count(&self) -> usize {
+        self.subgraphs().count()
+    }
+
+    /// Warnings recorded while parsing into this database, with their
+    /// positions
+    ///
+    /// Diagram types whose parsers never skip statements have none.
+    fn parse_warnings(&self) -> &[ParseWarning] {
+        &[]
+    }
+
+    /// Take the recorded parse warnings, leaving none behind
+    fn take_parse_warnings(&mut self) -> Vec<ParseWarning> {
+        Vec::new()
+    }
+}
+
+/// A named group of nodes drawn inside one border (a flowchart `subgraph`)
+///
+/// A node belongs to one subgraph, the innermost; enclosing subgraphs are
+/// reached through [`Subgraph::parent`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subgraph {
+    /// Unique identifier for this subgraph (e.g., "subgraph_0" or "subgraph_" and
+    /// its slugified title when merged next to another "subgraph_0")
+    pub id: String,
+    /// Display title for the subgraph border
+    pub title: String,
+    /// Node IDs contained in this subgraph
+    pub members: Vec<String>,
+    /// Direction its members are laid out in (`direction LR` inside the
+    /// block); `None` follows the diagram
+    pub direction: Option<Direction>,
+    /// ID of the subgraph this one is nested in
+    pub parent: Option<String>,
+}
+
+impl Subgraph {
+    /// Create a new top-level subgraph with the given title and members
+    pub fn new(id: String, title: String, members: Vec<String>) -> Self {
+        Self {
+            id,
+            title,
+            members,
+            direction: None,
+            parent: None,
+        }
+    }
+
+    /// Whether a node is a direct member of this subgraph
+    pub fn contains(&self, node_id: &str) -> bool {
+        self.members.iter().any(|m| m == node_id)
+    }
+}
+
+/// How node identifiers that differ only in letter case are treated
+///
+/// Mermaid IDs are case-sensitive, so `Start` and `start` are two nodes.
+/// That is rarely what the author meant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum IdCase {
+    /// Keep IDs as written and warn about IDs that differ only in case
+    #[default]
+    Sensitive,
+    /// Resolve every spelling to the first one seen (`start` becomes `Start`)
+    Insensitive,
+}
+
+impl IdCase {
+    /// Every ID case mode
+    pub const ALL: [IdCase; 2] = [IdCase::Sensitive, IdCase::Insensitive];
+}
+
+impl fmt::Display for IdCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdCase::Sensitive => write!(f, "sensitive"),
+            IdCase::Insensitive => write!(f, "insensitive"),
+        }
+    }
+}
+
+impl std::str::FromStr for IdCase {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sensitive" => Ok(IdCase::Sensitive),
+            "insensitive" => Ok(IdCase::Insensitive),
+            _ => Err(format!(
+                "Unknown ID case mode '{}'. Use 'sensitive' or 'insensitive'",
+                s
+            )),
+        }
+    }
+}
+
+/// Maps the IDs a diagram mentions to the ones its database stores
+///
+/// Databases that opt in pass every incoming ID through [`IdResolver::resolve`].
+/// In [`IdCase::Insensitive`] mode the first spelling of an ID wins; in
+/// [`IdCase::Sensitive`] mode IDs are kept but near-duplicates are recorded
+/// as lint warnings.
+#[derive(Debug, Clone, Default)]
+pub struct IdResolver {
+    case: IdCase,
+    /// First spelling seen for each lowercased ID
+    spellings: HashMap<String, String>,
+    /// Every spelling seen, so each near-duplicate is reported once
+    seen: HashSet<String>,
+    warnings: Vec<String>,
+}
+
+impl IdResolver {
+    /// Create a resolver for the given mode
+    pub fn new(case: IdCase) -> Self {
+        Self {
+            case,
+            ..Self::default()
+        }
+    }
+
+    /// Current mode
+    pub fn case(&self) -> IdCase {
+        self.case
+    }
+
+    /// Change the mode; IDs resolved so far are kept
+    pub fn set_case(&mut self, case: IdCase) {
+        self.case = case;
+    }
+
+    /// Resolve an incoming ID, remembering it for later lookups
+    pub fn resolve(&mut self, id: &str) -> String {
+        let folded = id.to_lowercase();
+        let Some(first) = self.spellings.get(&folded) else {
+            self.spellings.insert(folded, id.to_string());
+            self.seen.insert(id.to_string());
+            return id.to_string();
+        };
+        if first == id {
+            return id.to_string();
+        }
+
+        match self.case {
+            IdCase::Insensitive => first.clone(),
+            IdCase::Sensitive => {
+                if self.seen.insert(id.to_string()) {
+                    self.warnings.push(format!(
+                        "IDs '{}' and '{}' differ only in case; treating them as different",
+                        first, id
+                    ));
+                }
+                id.to_string()
+            }
+        }
+    }
+
+    /// The stored form of an ID without remembering it
+    pub fn lookup<'a>(&'a self, id: &'a str) -> &'a str {
+        match self.case {
+            IdCase::Sensitive => id,
+            IdCase::Insensitive => self
+                .spellings
+                .get(&id.to_lowercase())
+                .map_or(id, String::as_str),
+        }
+    }
+
+    /// Take the near-duplicate warnings recorded so far
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Forget every ID, keeping the mode
+    pub fn clear(&mut self) {
+        self.spellings.clear();
+        self.seen.clear();
+        self.warnings.clear();
+    }
+}
+
+/// Hands out IDs that collide with none handed out or reserved before
+///
+/// Allocation is deterministic: the same requests in the same order give
+/// the same IDs, so generated IDs stay stable between runs.
+#[derive(Debug, Clone, Default)]
+pub struct IdAllocator {
+    taken: HashSet<String>,
+}
+
+impl IdAllocator {
+    /// Create an allocator with no IDs taken
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark an ID as taken, e.g. one written out in the source
+    pub fn reserve(&mut self, id: impl Into<String>) {
+        self.taken.insert(id.into());
+    }
+
+    /// Whether an ID is taken
+    pub fn contains(&self, id: &str) -> bool {
+        self.taken.contains(id)
+    }
+
+    /// `base` itself if it is free, otherwise the first free `base_2`, `base_3`, …
+    pub fn allocate(&mut self, base: &str) -> String {
+        let mut id = base.to_string();
+        let mut suffix = 2;
+        while self.taken.contains(&id) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        self.taken.insert(id.clone());
+        id
+    }
+
+    /// The first free `prefix_0`, `prefix_1`, …
+    pub fn next(&mut self, prefix: &str) -> String {
+        let id = (0..)
+            .map(|n| format!("{}_{}", prefix, n))
+            .find(|id| !self.taken.contains(id))
+            .expect("unbounded range");
+        self.taken.insert(id.clone());
+        id
+    }
+
+    /// Forget every taken ID
+    pub fn clear(&mut self) {
+        self.taken.clear();
+    }
+}
+
+/// An identifier made from free text: lowercase words joined by `_`
+///
+/// Returns an empty string when `text` has no letters or digits.
+///
+/// # Example
+/// ```rust
+/// use figurehead::id_slug;
+///
+/// assert_eq!(id_slug("Data Pipeline (v2)"), "data_pipeline_v2");
+/// ```
+pub fn id_slug(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// The IDs one diagram's nodes and subgraphs were given when merged into another
+///
+/// IDs that kept their name are not recorded; lookups return them unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdRemap {
+    nodes: HashMap<String, String>,
+    subgraphs: HashMap<String, String>,
+}
+
+impl IdRemap {
+    /// Record a node's new ID
+    pub fn rename_node(&mut self, from: impl Into<String>, to: impl Into<String>) {
+        let (from, to) = (from.into(), to.into());
+        if from != to {
+            self.nodes.insert(from, to);
+        }
+    }
+
+    /// Record a subgraph's new ID
+    pub fn rename_subgraph(&mut self, from: impl Into<String>, to: impl Into<String>) {
+        let (from, to) = (from.into(), to.into());
+        if from != to {
+            self.subgraphs.insert(from, to);
+        }
+    }
+
+    /// Whether a node was given a new ID
+    pub fn has_node(&self, id: &str) -> bool {
+        self.nodes.contains_key(id)
+    }
+
+    /// A node's ID after the merge
+    pub fn node<'a>(&'a self, id: &'a str) -> &'a str {
+        self.nodes.get(id).map_or(id, String::as_str)
+    }
+
+    /// A subgraph's ID after the merge
+    pub fn subgraph<'a>(&'a self, id: &'a str) -> &'a str {
+        self.subgraphs.get(id).map_or(id, String::as_str)
+    }
+
+    /// Whether every ID kept its name
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.subgraphs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sensitive_ids_warn_once_per_spelling() {
+        let mut ids = IdResolver::default();
+        assert_eq!(ids.resolve("Start"), "Start");
+        assert_eq!(ids.resolve("start"), "start");
+        assert_eq!(ids.resolve("start"), "start");
+        assert_eq!(ids.resolve("Start"), "Start");
+        assert_eq!(ids.lookup("START"), "START");
+        assert_eq!(
+            ids.take_warnings(),
+            ["IDs 'Start' and 'start' differ only in case; treating them as different"]
+        );
+    }
+
+    #[test]
+    fn test_insensitive_ids_resolve_to_first_spelling() {
+        let mut ids = IdResolver::new(IdCase::Insensitive);
+        assert_eq!(ids.resolve("Start"), "Start");
+        assert_eq!(ids.resolve("start"), "Start");
+        assert_eq!(ids.lookup("START"), "Start");
+        assert_eq!(ids.lookup("other"), "other");
+        assert!(ids.take_warnings().is_empty());
+        assert_eq!("Insensitive".parse::<IdCase>(), Ok(IdCase::Insensitive));
+        assert!("loose".parse::<IdCase>().is_err());
+    }
+
+    #[test]
+    fn test_id_allocator_skips_taken_ids() {
+        let mut ids = IdAllocator::new();
+        ids.reserve("subgraph_0");
+        assert_eq!(ids.next("subgraph"), "subgraph_1");
+        assert_eq!(ids.next("subgraph"), "subgraph_2");
+        assert_eq!(ids.allocate("subgraph_1"), "subgraph_1_2");
+        assert_eq!(ids.allocate("load"), "load");
+        assert_eq!(ids.allocate("load"), "load_2");
+        assert!(ids.contains("load_2"));
+        assert_eq!(id_slug("  --  "), "");
+    }
 }
@@ -29,6 +29,36 @@ pub trait Detector: Send + Sync {
 
     /// Get key patterns that this detector looks for
     fn patterns(&self) -> Vec<&'static str>;
+
+    /// Cheap check on the diagram's header line (see [`header_line`])
+    ///
+    /// Returns the confidence when the header settles it, or `None` when
+    /// [`Detector::confidence`] has to look at the full input. Detection tries
+    /// every detector's header check first and only scans the whole input when
+    /// none of them claims it. The default defers to the full check.
+    fn prefix_confidence(&self, header: &str) -> Option<f64> {
+        let _ = header;
+        None
+    }
+}
+
+/// First line of a diagram that is not blank, a `%%` comment or a directive
+///
+/// Only reads up to that line, so it is cheap on large inputs.
+pub fn header_line(input: &str) -> &str {
+    input
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("%%"))
+        .unwrap_or("")
+}
+
+/// Whether `header` opens with `keyword`, ignoring case, as a whole word
+pub fn header_keyword(header: &str, keyword: &str) -> bool {
+    header
+        .split_whitespace()
+        .next()
+        .is_some_and(|word| word.eq_ignore_ascii_case(keyword))
 }
 
 #[cfg(test)]
@@ -97,4 +127,20 @@ mod tests {
         assert!(arrow_confidence > 0.0);
         assert_eq!(detector.confidence("random text"), 0.0);
     }
+
+    #[test]
+    fn test_header_line_skips_comments_and_directives() {
+        let input = "\n%%{init: {\"theme\": \"dark\"}}%%\n  %% a comment\n  graph TD\nA --> B";
+        assert_eq!(header_line(input), "graph TD");
+        assert_eq!(header_line("%% only a comment"), "");
+        assert!(header_keyword("Graph LR", "graph"));
+        assert!(!header_keyword("graphs", "graph"));
+    }
+
+    #[test]
+    fn test_prefix_confidence_settles_headers() {
+        let flowchart = FlowchartDetector::new();
+        assert_eq!(flowchart.prefix_confidence("flowchart LR"), Some(1.0));
+        assert_eq!(flowchart.prefix_confidence("A --> B"), None);
+    }
 }
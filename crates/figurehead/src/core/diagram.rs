@@ -10,6 +10,8 @@ use std::sync::Arc;
 ///
 /// This trait represents a complete diagram type with its associated components.
 /// Each diagram type (flowchart, sequence, class diagram, etc.) should implement this trait.
+/// Diagram types defined outside this crate join the processing pipeline
+/// through [`Orchestrator::register_diagram`](crate::plugins::Orchestrator::register_diagram).
 ///
 /// # Example
 /// ```
@@ -0,0 +1,378 @@
+//! Mermaid `%%{init: ...}%%` directive support
+//!
+//! Init directives carry a JSON-ish payload (single or double quoted strings,
+//! bare keys, trailing commas). The payload is parsed into a small value tree
+//! and then checked against a typed schema: recognized keys become
+//! [`InitConfig`] fields, anything else is reported as a warning so a diagram
+//! written for mermaid.js still renders.
+
+use chumsky::prelude::*;
+
+/// A parsed directive value
+#[derive(Debug, Clone, PartialEq)]
+pub enum DirectiveValue {
+    /// Quoted string
+    String(String),
+    /// Number
+    Number(f64),
+    /// `true` or `false`
+    Bool(bool),
+    /// `null`
+    Null,
+    /// Array items in source order
+    Array(Vec<DirectiveValue>),
+    /// Object members in source order
+    Object(Vec<(String, DirectiveValue)>),
+}
+
+impl DirectiveValue {
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            DirectiveValue::String(_) => "string",
+            DirectiveValue::Number(_) => "number",
+            DirectiveValue::Bool(_) => "boolean",
+            DirectiveValue::Null => "null",
+            DirectiveValue::Array(_) => "array",
+            DirectiveValue::Object(_) => "object",
+        }
+    }
+}
+
+/// Flowchart options from the `flowchart` section of an init directive
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FlowchartInitConfig {
+    /// Edge curve style (`linear`, `basis`, `step`, ...)
+    pub curve: Option<String>,
+    /// Horizontal spacing between nodes, in Mermaid pixels
+    pub node_spacing: Option<f64>,
+    /// Vertical spacing between ranks, in Mermaid pixels
+    pub rank_spacing: Option<f64>,
+    /// Padding around the whole diagram, in Mermaid pixels
+    pub diagram_padding: Option<f64>,
+}
+
+/// Sequence diagram options from the `sequence` section of an init directive
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SequenceInitConfig {
+    /// Horizontal space between participants, in Mermaid pixels
+    pub actor_margin: Option<f64>,
+    /// Repeat the participant boxes below the lifelines
+    pub mirror_actors: Option<bool>,
+}
+
+/// Typed view of an init directive
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InitConfig {
+    /// Mermaid theme name
+    pub theme: Option<String>,
+    /// Options from the `flowchart` section
+    pub flowchart: FlowchartInitConfig,
+    /// Options from the `sequence` section
+    pub sequence: SequenceInitConfig,
+}
+
+/// Result of reading the init directive from diagram input
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InitDirective {
+    /// Recognized settings
+    pub config: InitConfig,
+    /// Unsupported keys, wrong value types and malformed payloads
+    pub warnings: Vec<String>,
+}
+
+impl InitDirective {
+    /// Find and parse the `%%{init: ...}%%` directive leading `input`
+    ///
+    /// Only blank lines and `%%` comments may come before the directive; a
+    /// `%%{` after the diagram header (in a label, say) is not one. Returns
+    /// `None` when the input has no init directive. Never fails: problems
+    /// are collected in [`InitDirective::warnings`].
+    pub fn from_input(input: &str) -> Option<Self> {
+        let body = leading_directive(input)?;
+
+        let mut directive = InitDirective::default();
+        let payload = format!("{{{}}}", body);
+        let members = match object_parser().parse(&payload).into_result() {
+            Ok(DirectiveValue::Object(members)) => members,
+            _ => {
+                directive
+                    .warnings
+                    .push("Ignoring malformed %%{...}%% directive".to_string());
+                return Some(directive);
+            }
+        };
+
+        let mut found_init = false;
+        for (key, value) in members {
+            match key.as_str() {
+                "init" | "initialize" => {
+                    found_init = true;
+                    directive.read_init(&value);
+                }
+                _ => directive
+                    .warnings
+                    .push(format!("Unsupported directive '{}' ignored", key)),
+            }
+        }
+
+        if found_init || !directive.warnings.is_empty() {
+            Some(directive)
+        } else {
+            None
+        }
+    }
+
+    fn read_init(&mut self, value: &DirectiveValue) {
+        let DirectiveValue::Object(members) = value else {
+            self.type_warning("init", "object", value);
+            return;
+        };
+        for (key, value) in members {
+            match key.as_str() {
+                "theme" => self.config.theme = self.string(key, value),
+                "flowchart" => self.read_flowchart(value),
+                "sequence" => self.read_sequence(value),
+                _ => self.unsupported(key),
+            }
+        }
+    }
+
+    fn read_flowchart(&mut self, value: &DirectiveValue) {
+        let DirectiveValue::Object(members) = value else {
+            self.type_warning("flowchart", "object", value);
+            return;
+        };
+        for (key, value) in members {
+            let path = format!("flowchart.{}", key);
+            match key.as_str() {
+                "curve" => self.config.flowchart.curve = self.string(&path, value),
+                "nodeSpacing" => self.config.flowchart.node_spacing = self.number(&path, value),
+                "rankSpacing" => self.config.flowchart.rank_spacing = self.number(&path, value),
+                "diagramPadding" => {
+                    self.config.flowchart.diagram_padding = self.number(&path, value)
+                }
+                _ => self.unsupported(&path),
+            }
+        }
+    }
+
+    fn read_sequence(&mut self, value: &DirectiveValue) {
+        let DirectiveValue::Object(members) = value else {
+            self.type_warning("sequence", "object", value);
+            return;
+        };
+        for (key, value) in members {
+            let path = format!("sequence.{}", key);
+            match key.as_str() {
+                "actorMargin" => self.config.sequence.actor_margin = self.number(&path, value),
+                "mirrorActors" => self.config.sequence.mirror_actors = self.bool(&path, value),
+                _ => self.unsupported(&path),
+            }
+        }
+    }
+
+    fn string(&mut self, path: &str, value: &DirectiveValue) -> Option<String> {
+        match value {
+            DirectiveValue::String(s) => Some(s.clone()),
+            _ => {
+                self.type_warning(path, "string", value);
+                None
+            }
+        }
+    }
+
+    fn number(&mut self, path: &str, value: &DirectiveValue) -> Option<f64> {
+        match value {
+            DirectiveValue::Number(n) if *n >= 0.0 => Some(*n),
+            _ => {
+                self.type_warning(path, "non-negative number", value);
+                None
+            }
+        }
+    }
+
+    fn bool(&mut self, path: &str, value: &DirectiveValue) -> Option<bool> {
+        match value {
+            DirectiveValue::Bool(b) => Some(*b),
+            _ => {
+                self.type_warning(path, "boolean", value);
+                None
+            }
+        }
+    }
+
+    fn type_warning(&mut self, path: &str, expected: &str, value: &DirectiveValue) {
+        self.warnings.push(format!(
+            "Init option '{}' expects a {}, got {}; ignored",
+            path,
+            expected,
+            value.kind()
+        ));
+    }
+
+    fn unsupported(&mut self, path: &str) {
+        self.warnings
+            .push(format!("Unsupported init option '{}' ignored", path));
+    }
+}
+
+/// Body of the `%%{...}%%` directive before the first line of the diagram
+fn leading_directive(input: &str) -> Option<&str> {
+    let mut rest = input;
+    loop {
+        rest = rest.trim_start();
+        if let Some(body) = rest.strip_prefix("%%{") {
+            return body.find("}%%").map(|end| &body[..end]);
+        }
+        if !rest.starts_with("%%") {
+            return None;
+        }
+        rest = rest.split_once('\n')?.1;
+    }
+}
+
+/// Parse a JSON-ish value (same syntax as an init directive payload)
+///
+/// Used for other Mermaid option blocks, such as the gitGraph `options`
+/// block. Returns `None` when the text is malformed.
+pub(crate) fn parse_directive_value(text: &str) -> Option<DirectiveValue> {
+    object_parser().parse(text).into_result().ok()
+}
+
+fn object_parser<'src>() -> impl Parser<'src, &'src str, DirectiveValue> + Clone {
+    recursive(|value| {
+        let ws = one_of(" \t\r\n").repeated();
+
+        let quoted = |q: char| {
+            just(q)
+                .ignore_then(none_of(q).repeated().collect::<String>())
+                .then_ignore(just(q))
+        };
+        let string = quoted('\'').or(quoted('"'));
+
+        let number = just('-')
+            .or_not()
+            .then(text::digits(10))
+            .then(just('.').then(text::digits(10)).or_not())
+            .to_slice()
+            .map(|s: &str| DirectiveValue::Number(s.parse().unwrap_or(0.0)));
+
+        let bare = text::ascii::ident().map(|s: &str| match s {
+            "true" => DirectiveValue::Bool(true),
+            "false" => DirectiveValue::Bool(false),
+            "null" => DirectiveValue::Null,
+            _ => DirectiveValue::String(s.to_string()),
+        });
+
+        let key = string.or(text::ascii::ident().map(str::to_string));
+        let member = key.padded_by(ws).then_ignore(just(':')).then(value.clone());
+
+        let object = member
+            .separated_by(just(','))
+            .allow_trailing()
+            .collect::<Vec<_>>()
+            .then_ignore(ws)
+            .delimited_by(just('{'), just('}'))
+            .map(DirectiveValue::Object);
+
+        let array = value
+            .clone()
+            .separated_by(just(','))
+            .allow_trailing()
+            .collect::<Vec<_>>()
+            .then_ignore(ws)
+            .delimited_by(just('['), just(']'))
+            .map(DirectiveValue::Array);
+
+        choice((
+            object,
+            array,
+            string.map(DirectiveValue::String),
+            number,
+            bare,
+        ))
+        .padded_by(ws)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_directive() {
+        assert!(InitDirective::from_input("graph TD\n    A --> B").is_none());
+        assert!(InitDirective::from_input("%% plain comment\ngraph TD").is_none());
+    }
+
+    #[test]
+    fn test_directive_must_lead_the_diagram() {
+        let after_comment = "\n%% theme below\n  %%{init: {'theme': 'dark'}}%%\ngraph TD";
+        let directive = InitDirective::from_input(after_comment).unwrap();
+        assert_eq!(directive.config.theme.as_deref(), Some("dark"));
+
+        let in_label = "graph TD\n    A[\"%%{init: {'theme': 'dark'}}%%\"] --> B";
+        assert!(InitDirective::from_input(in_label).is_none());
+    }
+
+    #[test]
+    fn test_parses_flowchart_and_theme() {
+        let input = "%%{init: { 'flowchart': {'curve': 'linear', 'nodeSpacing': 30}, 'theme':'dark' }}%%\ngraph TD\n    A --> B";
+        let directive = InitDirective::from_input(input).unwrap();
+
+        assert!(directive.warnings.is_empty(), "{:?}", directive.warnings);
+        assert_eq!(directive.config.theme.as_deref(), Some("dark"));
+        assert_eq!(directive.config.flowchart.curve.as_deref(), Some("linear"));
+        assert_eq!(directive.config.flowchart.node_spacing, Some(30.0));
+        assert_eq!(directive.config.flowchart.rank_spacing, None);
+    }
+
+    #[test]
+    fn test_parses_sequence_section() {
+        let input = "%%{init: {'sequence': {'actorMargin': 100, 'mirrorActors': false}}}%%\nsequenceDiagram";
+        let directive = InitDirective::from_input(input).unwrap();
+        assert!(directive.warnings.is_empty(), "{:?}", directive.warnings);
+        assert_eq!(directive.config.sequence.actor_margin, Some(100.0));
+        assert_eq!(directive.config.sequence.mirror_actors, Some(false));
+    }
+
+    #[test]
+    fn test_json_style_and_multiline_payload() {
+        let input =
+            "%%{\n  init: {\n    \"flowchart\": { \"rankSpacing\": 80.5, },\n  }\n}%%\ngraph LR";
+        let directive = InitDirective::from_input(input).unwrap();
+        assert!(directive.warnings.is_empty(), "{:?}", directive.warnings);
+        assert_eq!(directive.config.flowchart.rank_spacing, Some(80.5));
+    }
+
+    #[test]
+    fn test_unknown_keys_warn() {
+        let input = "%%{init: {'logLevel': 1, 'flowchart': {'htmlLabels': false}, 'sequence': {'wrap': true}}}%%";
+        let directive = InitDirective::from_input(input).unwrap();
+        assert_eq!(
+            directive.warnings,
+            vec![
+                "Unsupported init option 'logLevel' ignored",
+                "Unsupported init option 'flowchart.htmlLabels' ignored",
+                "Unsupported init option 'sequence.wrap' ignored",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrong_types_warn() {
+        let input = "%%{init: {'theme': 3, 'flowchart': {'nodeSpacing': 'wide'}, 'sequence': {'mirrorActors': 'yes'}}}%%";
+        let directive = InitDirective::from_input(input).unwrap();
+        assert_eq!(directive.config, InitConfig::default());
+        assert_eq!(directive.warnings.len(), 3);
+        assert!(directive.warnings[1].contains("flowchart.nodeSpacing"));
+        assert!(directive.warnings[2].contains("sequence.mirrorActors"));
+    }
+
+    #[test]
+    fn test_malformed_payload_warns() {
+        let directive = InitDirective::from_input("%%{init: {'theme': }}%%").unwrap();
+        assert_eq!(directive.warnings.len(), 1);
+        assert!(directive.warnings[0].contains("malformed"));
+    }
+}
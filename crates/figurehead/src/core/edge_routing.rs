@@ -1,27 +1,52 @@
 //! Shared edge routing for diagram renderers
 //!
 //! Provides common edge routing algorithms: straight lines, orthogonal paths,
-//! split edges (one-to-many), and merge edges (many-to-one).
+//! split edges (one-to-many), and merge edges (many-to-one), plus an
+//! [`ObstacleRouter`] that finds orthogonal paths around placed nodes.
 
-use super::{AsciiCanvas, CharacterSet, Direction};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+#[cfg(any(test, feature = "unstable"))]
+use super::AsciiCanvas;
+use super::{CharacterSet, Direction, Point, Rect};
 
 /// Character set for edge drawing
+///
+/// Renderers draw their own junctions and arrowheads; those fields are read
+/// by [`EdgeRouter`], which only exists for `figurehead::unstable` users.
+#[cfg_attr(not(feature = "unstable"), allow(dead_code))]
 #[derive(Debug, Clone, Copy)]
 pub struct EdgeChars {
+    /// Horizontal line
     pub horizontal: char,
+    /// Vertical line
     pub vertical: char,
-    pub corner_top_left: char,     // ┌ (goes RIGHT and DOWN)
-    pub corner_top_right: char,    // ┐ (goes LEFT and DOWN)
-    pub corner_bottom_left: char,  // └ (goes RIGHT and UP)
-    pub corner_bottom_right: char, // ┘ (goes LEFT and UP)
-    pub junction_down: char,       // ┬ (connects LEFT, RIGHT, DOWN - line from above splits)
-    pub junction_up: char,         // ┴ (connects LEFT, RIGHT, UP - line from below splits)
-    pub junction_right: char,      // ├ (connects UP, DOWN, RIGHT)
-    pub junction_left: char,       // ┤ (connects UP, DOWN, LEFT)
-    pub cross: char,               // ┼ (all four directions)
+    /// `┌`: turn going right and down
+    pub corner_top_left: char,
+    /// `┐`: turn going left and down
+    pub corner_top_right: char,
+    /// `└`: turn going right and up
+    pub corner_bottom_left: char,
+    /// `┘`: turn going left and up
+    pub corner_bottom_right: char,
+    /// `┬`: a line from above splitting left and right
+    pub junction_down: char,
+    /// `┴`: a line from below splitting left and right
+    pub junction_up: char,
+    /// `├`: a vertical line branching right
+    pub junction_right: char,
+    /// `┤`: a vertical line branching left
+    pub junction_left: char,
+    /// `┼`: lines crossing
+    pub cross: char,
+    /// Arrowhead pointing up
     pub arrow_up: char,
+    /// Arrowhead pointing down
     pub arrow_down: char,
+    /// Arrowhead pointing left
     pub arrow_left: char,
+    /// Arrowhead pointing right
     pub arrow_right: char,
 }
 
@@ -35,6 +60,15 @@ impl EdgeChars {
         }
     }
 
+    /// Get dotted (dashed) edge characters for the given style
+    pub fn dotted_for_style(style: CharacterSet) -> Self {
+        if style.is_ascii() {
+            Self::ascii_dotted()
+        } else {
+            Self::unicode_dotted()
+        }
+    }
+
     /// ASCII edge characters
     pub fn ascii() -> Self {
         Self {
@@ -76,13 +110,38 @@ impl EdgeChars {
             arrow_right: '▶',
         }
     }
+
+    /// ASCII dotted edge characters (`.` and `:` lines)
+    pub fn ascii_dotted() -> Self {
+        Self {
+            horizontal: '.',
+            vertical: ':',
+            ..Self::ascii()
+        }
+    }
+
+    /// Unicode dotted edge characters with hollow arrowheads
+    pub fn unicode_dotted() -> Self {
+        Self {
+            horizontal: '┄',
+            vertical: '┆',
+            arrow_up: '△',
+            arrow_down: '▽',
+            arrow_left: '◁',
+            arrow_right: '▷',
+            ..Self::unicode()
+        }
+    }
 }
 
 /// Edge routing helper for diagram renderers
+#[cfg(any(test, feature = "unstable"))]
 pub struct EdgeRouter {
+    /// Characters lines are drawn with
     pub chars: EdgeChars,
 }
 
+#[cfg(any(test, feature = "unstable"))]
 impl EdgeRouter {
     /// Create a new edge router with the given character set
     pub fn new(style: CharacterSet) -> Self {
@@ -363,6 +422,198 @@ impl EdgeRouter {
     }
 }
 
+/// Orthogonal router that steers edges around placed nodes
+///
+/// Runs A* over the canvas cells. Obstacle cells are never entered and every
+/// turn costs [`ObstacleRouter::with_bend_cost`] extra steps, so routes stay
+/// short with few corners. The result is a list of waypoints (endpoints and
+/// corners) that renderers draw segment by segment.
+#[derive(Debug, Clone)]
+pub struct ObstacleRouter {
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+    bend_cost: usize,
+}
+
+/// Headings a route can move in, indexed by [`heading_index`]
+const HEADINGS: [Direction; 4] = [
+    Direction::TopDown,
+    Direction::BottomUp,
+    Direction::LeftRight,
+    Direction::RightLeft,
+];
+
+fn heading_index(direction: Direction) -> usize {
+    match direction {
+        Direction::TopDown => 0,
+        Direction::BottomUp => 1,
+        Direction::LeftRight => 2,
+        Direction::RightLeft => 3,
+    }
+}
+
+impl ObstacleRouter {
+    /// Create a router for a `width` x `height` grid with no obstacles
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            blocked: vec![false; width * height],
+            bend_cost: 4,
+        }
+    }
+
+    /// Extra cost of each turn, in cells (default 4)
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn with_bend_cost(mut self, bend_cost: usize) -> Self {
+        self.bend_cost = bend_cost;
+        self
+    }
+
+    /// Mark every cell of `rect` (clipped to the grid) as blocked
+    pub fn add_obstacle(&mut self, rect: Rect) {
+        for y in rect.y..rect.bottom().min(self.height) {
+            for x in rect.x..rect.right().min(self.width) {
+                self.blocked[y * self.width + x] = true;
+            }
+        }
+    }
+
+    /// Whether a route may not pass through the cell (cells off the grid
+    /// are blocked)
+    pub fn is_blocked(&self, point: Point) -> bool {
+        point.x >= self.width || point.y >= self.height || self.blocked[self.index(point)]
+    }
+
+    fn index(&self, point: Point) -> usize {
+        point.y * self.width + point.x
+    }
+
+    /// The neighbouring cell in `heading`, if it is on the grid
+    fn neighbor(&self, point: Point, heading: Direction) -> Option<Point> {
+        let next = point.step(heading, 1);
+        (next != point && next.x < self.width && next.y < self.height).then_some(next)
+    }
+
+    /// Route an edge flowing in `direction` from `start` to `end`
+    ///
+    /// The route leaves `start` and reaches `end` moving in `direction`, with
+    /// two straight cells in front of `end` for the arrowhead. `start` and
+    /// `end` may lie on their nodes' borders; every other cell must be free.
+    /// Returns `None` when the obstacles leave no way through.
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn route(&self, start: Point, end: Point, direction: Direction) -> Option<Vec<Point>> {
+        let from = start.step(direction, 1);
+        let to = end.step(direction, -2);
+        if from == start || to.step(direction, 2) != end || self.is_blocked(end.step(direction, -1))
+        {
+            return None;
+        }
+
+        let cells = self.route_between(from, to, Some(direction), Some(direction))?;
+        let mut points = vec![start];
+        points.extend(cells);
+        points.push(end);
+        Some(simplify_path(&points))
+    }
+
+    /// Shortest path of free cells from `from` to `to`, as waypoints
+    ///
+    /// `leave` and `arrive` pin the heading out of `from` and into `to`;
+    /// `None` lets the route turn right away, as at a split or merge junction
+    /// shared with other edges. Both endpoints must be free.
+    pub fn route_between(
+        &self,
+        from: Point,
+        to: Point,
+        leave: Option<Direction>,
+        arrive: Option<Direction>,
+    ) -> Option<Vec<Point>> {
+        if self.is_blocked(from) || self.is_blocked(to) {
+            return None;
+        }
+
+        let state = |point: Point, heading: Direction| {
+            self.index(point) * HEADINGS.len() + heading_index(heading)
+        };
+        let mut cost = vec![usize::MAX; self.width * self.height * HEADINGS.len()];
+        let mut came_from = vec![usize::MAX; cost.len()];
+        let mut queue = BinaryHeap::new();
+        for heading in HEADINGS {
+            if leave.is_none_or(|leave| leave == heading) {
+                let start = state(from, heading);
+                cost[start] = 0;
+                queue.push(Reverse((from.distance(to), 0, start)));
+            }
+        }
+
+        let mut goal = None;
+        while let Some(Reverse((_, spent, current))) = queue.pop() {
+            if spent > cost[current] {
+                continue;
+            }
+            let cell = current / HEADINGS.len();
+            let point = Point::new(cell % self.width, cell / self.width);
+            let facing = HEADINGS[current % HEADINGS.len()];
+            if point == to && arrive.is_none_or(|arrive| arrive == facing) {
+                goal = Some(current);
+                break;
+            }
+            for heading in HEADINGS {
+                let Some(next) = self.neighbor(point, heading) else {
+                    continue;
+                };
+                if self.is_blocked(next) {
+                    continue;
+                }
+                let turn = if heading == facing { 0 } else { self.bend_cost };
+                let next_spent = spent + 1 + turn;
+                let next_state = state(next, heading);
+                if next_spent < cost[next_state] {
+                    cost[next_state] = next_spent;
+                    came_from[next_state] = current;
+                    queue.push(Reverse((
+                        next_spent + next.distance(to),
+                        next_spent,
+                        next_state,
+                    )));
+                }
+            }
+        }
+
+        let mut cells = Vec::new();
+        let mut current = goal?;
+        while current != usize::MAX {
+            let cell = current / HEADINGS.len();
+            cells.push(Point::new(cell % self.width, cell / self.width));
+            current = came_from[current];
+        }
+        cells.reverse();
+        Some(simplify_path(&cells))
+    }
+}
+
+/// Drop the points in the middle of straight runs (and repeated points),
+/// keeping the endpoints and corners of a path
+pub fn simplify_path(points: &[Point]) -> Vec<Point> {
+    let mut kept: Vec<Point> = Vec::with_capacity(points.len());
+    for &point in points {
+        if kept.last() == Some(&point) {
+            continue;
+        }
+        if let [.., before, last] = kept[..] {
+            let straight = (before.x == last.x && last.x == point.x)
+                || (before.y == last.y && last.y == point.y);
+            if straight {
+                kept.pop();
+            }
+        }
+        kept.push(point);
+    }
+    kept
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -433,4 +684,44 @@ mod tests {
         assert_eq!(canvas.get_char(5, 3), '└');
         assert_eq!(canvas.get_char(15, 3), '┘');
     }
+
+    #[test]
+    fn test_obstacle_router_goes_around_a_node() {
+        // Source above, target below, a node squarely in between
+        let mut router = ObstacleRouter::new(30, 20);
+        router.add_obstacle(Rect::new(8, 7, 6, 3));
+        let route = router
+            .route(Point::new(10, 2), Point::new(10, 15), Direction::TopDown)
+            .unwrap();
+
+        assert_eq!(route.first(), Some(&Point::new(10, 2)));
+        assert_eq!(route.last(), Some(&Point::new(10, 15)));
+        assert!(route.len() > 2, "{:?}", route);
+        for segment in crate::core::Segment::path(&route) {
+            assert!(segment.is_horizontal() || segment.is_vertical());
+            assert!(!segment.intersects_rect(&Rect::new(8, 7, 6, 3)));
+        }
+        // The arrow has a straight run into the target
+        let before_end = route[route.len() - 2];
+        assert_eq!(before_end.x, 10);
+        assert!(before_end.y <= 13);
+    }
+
+    #[test]
+    fn test_obstacle_router_keeps_straight_routes_straight() {
+        let router = ObstacleRouter::new(20, 20);
+        let route = router
+            .route(Point::new(4, 2), Point::new(4, 12), Direction::TopDown)
+            .unwrap();
+        assert_eq!(route, vec![Point::new(4, 2), Point::new(4, 12)]);
+    }
+
+    #[test]
+    fn test_obstacle_router_gives_up_when_walled_in() {
+        let mut router = ObstacleRouter::new(20, 20);
+        router.add_obstacle(Rect::new(0, 8, 20, 2));
+        assert!(router
+            .route(Point::new(4, 2), Point::new(4, 12), Direction::TopDown)
+            .is_none());
+    }
 }
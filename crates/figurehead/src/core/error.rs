@@ -7,33 +7,59 @@ use thiserror::Error;
 /// Core error types for diagram processing
 #[derive(Error, Debug)]
 pub enum DiagramError {
+    /// Markup could not be parsed
     #[error("Parse error: {message} at line {line}, column {column}")]
     ParseError {
+        /// What went wrong
         message: String,
+        /// 1-based line of the error
         line: usize,
+        /// 1-based column of the error
         column: usize,
     },
 
+    /// Layout failed
     #[error("Layout error: {message}")]
-    LayoutError { message: String },
+    LayoutError {
+        /// What went wrong
+        message: String,
+    },
 
+    /// Rendering failed
     #[error("Render error: {message}")]
-    RenderError { message: String },
+    RenderError {
+        /// What went wrong
+        message: String,
+    },
 
+    /// Database rejected an item
     #[error("Database error: {message}")]
-    DatabaseError { message: String },
+    DatabaseError {
+        /// What went wrong
+        message: String,
+    },
 
+    /// Detection failed
     #[error("Detection error: {message}")]
-    DetectionError { message: String },
+    DetectionError {
+        /// What went wrong
+        message: String,
+    },
 
+    /// Reading input or writing output failed
     #[error("IO error: {source}")]
     IoError {
+        /// Underlying IO error
         #[from]
         source: std::io::Error,
     },
 
+    /// No plugin handles this diagram type
     #[error("Unknown diagram type: {diagram_type}")]
-    UnknownDiagramType { diagram_type: String },
+    UnknownDiagramType {
+        /// Name given in the input
+        diagram_type: String,
+    },
 }
 
 impl DiagramError {
@@ -67,6 +93,110 @@ impl DiagramError {
     }
 }
 
+/// Where in the input an error points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceSpan {
+    /// 1-based line where the span starts
+    pub line: usize,
+    /// 1-based column (in characters) where the span starts
+    pub column: usize,
+    /// Length in characters
+    pub len: usize,
+}
+
+impl SourceSpan {
+    /// Span of `len` characters starting at `line`, `column`
+    pub fn new(line: usize, column: usize, len: usize) -> Self {
+        Self { line, column, len }
+    }
+}
+
+/// Error returned by the crate's top-level functions ([`render`](crate::render),
+/// [`render_pure`](crate::render_pure), [`parse`](crate::parse), ...)
+///
+/// Inside the pipeline errors travel as [`anyhow::Error`]; the variants other
+/// than [`Other`](Self::Other) can also be recovered from those with
+/// `error.downcast_ref::<FigureheadError>()`.
+///
+/// # Example
+/// ```
+/// use figurehead::{render, FigureheadError};
+///
+/// match render("sequenceDiagram\n  ~~~") {
+///     Err(FigureheadError::Parse { diagram_type, span, statement, .. }) => {
+///         assert_eq!(diagram_type, "sequence");
+///         assert_eq!((span.line, span.column, span.len), (2, 3, 3));
+///         assert_eq!(statement, "~~~");
+///     }
+///     other => panic!("expected a parse error, got {:?}", other),
+/// }
+/// ```
+#[derive(Error, Debug)]
+pub enum FigureheadError {
+    /// A statement could not be read
+    #[error("Parse error at line {}, column {}: {message}", span.line, span.column)]
+    Parse {
+        /// Diagram type whose parser failed, e.g. `flowchart`
+        diagram_type: String,
+        /// Position of the offending statement
+        span: SourceSpan,
+        /// Text of the offending statement
+        statement: String,
+        /// What went wrong
+        message: String,
+    },
+
+    /// No detector recognized the input
+    #[error("No suitable detector found for input")]
+    Undetected,
+
+    /// No plugin handles the requested diagram type
+    #[error("Unsupported diagram type: {diagram_type}")]
+    UnsupportedDiagramType {
+        /// Name that was asked for
+        diagram_type: String,
+    },
+
+    /// Any other failure, e.g. in layout or rendering
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl FigureheadError {
+    /// Parse error pointing at `statement`, which starts at `line`, `column`
+    pub fn parse(
+        diagram_type: impl Into<String>,
+        line: usize,
+        column: usize,
+        statement: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        let statement = statement.into();
+        Self::Parse {
+            diagram_type: diagram_type.into(),
+            span: SourceSpan::new(line, column, statement.chars().count()),
+            statement,
+            message: message.into(),
+        }
+    }
+
+    /// Position in the input the error points at, if any
+    pub fn span(&self) -> Option<SourceSpan> {
+        match self {
+            Self::Parse { span, .. } => Some(*span),
+            _ => None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for FigureheadError {
+    /// Recover a structured error raised inside the pipeline, or wrap the
+    /// error as [`FigureheadError::Other`]
+    fn from(error: anyhow::Error) -> Self {
+        error.downcast().unwrap_or_else(Self::Other)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,277 @@
+//! Footer sections listed under a diagram
+//!
+//! Legends, footnotes, message indexes and diagnostic summaries all end up as
+//! short `key text` entries below the drawing. A [`Footer`] collects them in
+//! titled [`FooterSection`]s and lays each section out in aligned columns
+//! that fit the diagram's width, so several of these features can share the
+//! space under one diagram.
+
+use unicode_width::UnicodeWidthStr;
+
+/// Spaces between footer columns
+const COLUMN_GAP: usize = 3;
+
+/// One `key text` line of a footer section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FooterEntry {
+    /// Short marker in the key column: a number, glyph or name (may be empty)
+    pub key: String,
+    /// Text following the key
+    pub text: String,
+}
+
+impl FooterEntry {
+    /// Create an entry
+    pub fn new(key: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            text: text.into(),
+        }
+    }
+}
+
+/// A group of footer entries with an optional title line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FooterSection {
+    /// Title line, if any
+    pub title: Option<String>,
+    /// Entries in display order
+    pub entries: Vec<FooterEntry>,
+}
+
+impl FooterSection {
+    /// Create an untitled section
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a section headed by `title`
+    pub fn titled(title: impl Into<String>) -> Self {
+        Self {
+            title: Some(title.into()),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add an entry
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn with_entry(mut self, key: impl Into<String>, text: impl Into<String>) -> Self {
+        self.push(key, text);
+        self
+    }
+
+    /// Add an entry in place
+    pub fn push(&mut self, key: impl Into<String>, text: impl Into<String>) {
+        self.entries.push(FooterEntry::new(key, text));
+    }
+
+    /// Returns true if the section has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Lay the entries out in as many columns as fit in `width`
+    ///
+    /// Keys are padded to a common width; when every key is a number they
+    /// are right-aligned so the digits line up. Entries run down each column
+    /// before moving to the next, like `ls`.
+    fn render_lines(&self, width: usize) -> Vec<String> {
+        let mut lines: Vec<String> = self.title.iter().cloned().collect();
+        if self.entries.is_empty() {
+            return lines;
+        }
+
+        let key_width = self
+            .entries
+            .iter()
+            .map(|e| e.key.width())
+            .max()
+            .unwrap_or(0);
+        let numeric = self
+            .entries
+            .iter()
+            .all(|e| !e.key.is_empty() && e.key.chars().all(|c| c.is_ascii_digit()));
+        let cells: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                if key_width == 0 {
+                    entry.text.clone()
+                } else if numeric {
+                    format!("{:>w$} {}", entry.key, entry.text, w = key_width)
+                } else {
+                    let pad = key_width - entry.key.width();
+                    format!("{}{} {}", entry.key, " ".repeat(pad), entry.text)
+                }
+            })
+            .collect();
+
+        let cell_width = cells.iter().map(|c| c.width()).max().unwrap_or(0);
+        let fit = ((width + COLUMN_GAP) / (cell_width + COLUMN_GAP)).max(1);
+        let rows = cells.len().div_ceil(fit);
+        let columns = cells.len().div_ceil(rows);
+
+        for row in 0..rows {
+            let mut line = String::new();
+            for column in 0..columns {
+                let Some(cell) = cells.get(column * rows + row) else {
+                    break;
+                };
+                if column > 0 {
+                    let used = line.width();
+                    let start = column * (cell_width + COLUMN_GAP);
+                    line.push_str(&" ".repeat(start - used));
+                }
+                line.push_str(cell);
+            }
+            lines.push(line.trim_end().to_string());
+        }
+        lines
+    }
+}
+
+/// Sections printed below a diagram
+///
+/// # Example
+/// ```
+/// use figurehead::unstable::{Footer, FooterSection};
+///
+/// let legend = FooterSection::titled("Legend")
+///     .with_entry("○", "commit")
+///     .with_entry("◆", "merge");
+/// let output = Footer::new().with_section(legend).attach("┌───────────────────┐\n└───────────────────┘");
+/// assert!(output.ends_with("Legend\n○ commit   ◆ merge"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Footer {
+    sections: Vec<FooterSection>,
+}
+
+impl Footer {
+    /// Create an empty footer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a section (empty sections are skipped when rendering)
+    pub fn with_section(mut self, section: FooterSection) -> Self {
+        self.push(section);
+        self
+    }
+
+    /// Add a section in place
+    pub fn push(&mut self, section: FooterSection) {
+        self.sections.push(section);
+    }
+
+    /// Sections in display order
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn sections(&self) -> &[FooterSection] {
+        &self.sections
+    }
+
+    /// Whether there is nothing to print
+    pub fn is_empty(&self) -> bool {
+        self.sections.iter().all(FooterSection::is_empty)
+    }
+
+    /// Render the sections for a diagram `width` columns wide, separated by
+    /// blank lines
+    pub fn render(&self, width: usize) -> String {
+        self.sections
+            .iter()
+            .filter(|section| !section.is_empty())
+            .map(|section| section.render_lines(width).join("\n"))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Append the footer to a rendered diagram, fitting its columns to the
+    /// diagram's widest line
+    ///
+    /// ANSI color codes in the diagram do not count towards its width.
+    pub fn attach(&self, diagram: &str) -> String {
+        if self.is_empty() {
+            return diagram.to_string();
+        }
+        let width = diagram.lines().map(visible_width).max().unwrap_or(0);
+        format!("{}\n\n{}", diagram, self.render(width))
+    }
+}
+
+/// Display width of a line, skipping `ESC [ ... m` color sequences
+pub(crate) fn visible_width(line: &str) -> usize {
+    if !line.contains('\x1b') {
+        return line.width();
+    }
+    let mut plain = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            plain.push(c);
+        }
+    }
+    plain.width()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(count: usize) -> FooterSection {
+        (1..=count).fold(FooterSection::new(), |section, i| {
+            section.with_entry(i.to_string(), format!("A→B: m{}", i))
+        })
+    }
+
+    #[test]
+    fn test_entries_fill_columns_top_to_bottom() {
+        let output = Footer::new().with_section(numbered(5)).render(30);
+        assert_eq!(
+            output,
+            "1 A→B: m1   4 A→B: m4\n2 A→B: m2   5 A→B: m5\n3 A→B: m3"
+        );
+    }
+
+    #[test]
+    fn test_narrow_width_keeps_one_column() {
+        let output = Footer::new().with_section(numbered(3)).render(4);
+        assert_eq!(output, "1 A→B: m1\n2 A→B: m2\n3 A→B: m3");
+    }
+
+    #[test]
+    fn test_numeric_keys_right_aligned_and_names_left_aligned() {
+        let lines = numbered(10).render_lines(0);
+        assert_eq!(lines[0], " 1 A→B: m1");
+        assert_eq!(lines[9], "10 A→B: m10");
+
+        let names = FooterSection::new()
+            .with_entry("W1", "unused node")
+            .with_entry("W10", "empty label")
+            .render_lines(0);
+        assert_eq!(names, vec!["W1  unused node", "W10 empty label"]);
+    }
+
+    #[test]
+    fn test_sections_are_separated_and_empty_ones_skipped() {
+        let footer = Footer::new()
+            .with_section(FooterSection::titled("Legend").with_entry("*", "commit"))
+            .with_section(FooterSection::titled("Notes"))
+            .with_section(FooterSection::titled("Warnings").with_entry("", "line 3 ignored"));
+        assert_eq!(
+            footer.render(40),
+            "Legend\n* commit\n\nWarnings\nline 3 ignored"
+        );
+    }
+
+    #[test]
+    fn test_attach_measures_width_without_color_codes() {
+        let diagram = "\x1b[36m+------------+\x1b[0m";
+        let output = Footer::new().with_section(numbered(2)).attach(diagram);
+        // 14 visible columns only fit one 9-wide entry per row
+        assert!(output.ends_with("\n\n1 A→B: m1\n2 A→B: m2"));
+        assert_eq!(Footer::new().attach(diagram), diagram);
+    }
+}
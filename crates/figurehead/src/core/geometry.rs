@@ -0,0 +1,386 @@
+//! Grid geometry shared by layouts and renderers
+//!
+//! Everything is measured in character cells. [`Rect`] spans are half-open:
+//! a rect at `x` with `width` 3 covers columns `x`, `x + 1` and `x + 2`, and
+//! [`Rect::right`] is the first column outside it. Edge routes are lists of
+//! [`Point`]s joined by axis-aligned [`Segment`]s.
+
+use super::Direction;
+
+/// A cell position on the canvas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Point {
+    /// Column
+    pub x: usize,
+    /// Row
+    pub y: usize,
+}
+
+impl Point {
+    /// Create a point
+    pub const fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+
+    /// Move by a signed offset, stopping at the canvas origin
+    pub fn offset(self, dx: isize, dy: isize) -> Self {
+        Self {
+            x: self.x.saturating_add_signed(dx),
+            y: self.y.saturating_add_signed(dy),
+        }
+    }
+
+    /// Move `distance` cells along a flow direction (negative moves against
+    /// it), stopping at the canvas origin
+    pub fn step(self, direction: Direction, distance: isize) -> Self {
+        match direction {
+            Direction::TopDown => self.offset(0, distance),
+            Direction::BottomUp => self.offset(0, -distance),
+            Direction::LeftRight => self.offset(distance, 0),
+            Direction::RightLeft => self.offset(-distance, 0),
+        }
+    }
+
+    /// Manhattan distance to another point
+    pub fn distance(self, other: Point) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+}
+
+impl From<(usize, usize)> for Point {
+    fn from((x, y): (usize, usize)) -> Self {
+        Self { x, y }
+    }
+}
+
+impl From<Point> for (usize, usize) {
+    fn from(point: Point) -> Self {
+        (point.x, point.y)
+    }
+}
+
+/// An axis-aligned block of cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect {
+    /// Left column
+    pub x: usize,
+    /// Top row
+    pub y: usize,
+    /// Width in cells
+    pub width: usize,
+    /// Height in cells
+    pub height: usize,
+}
+
+impl Rect {
+    /// Create a rect
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// First column to the right of the rect
+    pub fn right(&self) -> usize {
+        self.x + self.width
+    }
+
+    /// First row below the rect
+    pub fn bottom(&self) -> usize {
+        self.y + self.height
+    }
+
+    /// Top-left cell
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn origin(&self) -> Point {
+        Point::new(self.x, self.y)
+    }
+
+    /// Middle cell (rounded towards the origin)
+    pub fn center(&self) -> Point {
+        Point::new(self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    /// Point where an edge leaves the rect when flowing in `direction`
+    ///
+    /// This is the cell just outside the middle of the trailing side (below
+    /// the rect for top-down, right of it for left-right). Up- and
+    /// left-going flows leave from the leading border itself.
+    pub fn exit(&self, direction: Direction) -> Point {
+        let center = self.center();
+        match direction {
+            Direction::TopDown => Point::new(center.x, self.bottom()),
+            Direction::BottomUp => Point::new(center.x, self.y),
+            Direction::LeftRight => Point::new(self.right(), center.y),
+            Direction::RightLeft => Point::new(self.x, center.y),
+        }
+    }
+
+    /// Point where an edge flowing in `direction` enters the rect
+    ///
+    /// The mirror of [`Rect::exit`]: an edge from `a.exit(d)` to
+    /// `b.entry(d)` ends with its arrow in the gap in front of `b`.
+    pub fn entry(&self, direction: Direction) -> Point {
+        let center = self.center();
+        match direction {
+            Direction::TopDown => Point::new(center.x, self.y),
+            Direction::BottomUp => Point::new(center.x, self.bottom()),
+            Direction::LeftRight => Point::new(self.x, center.y),
+            Direction::RightLeft => Point::new(self.right(), center.y),
+        }
+    }
+
+    /// Whether the cell lies inside the rect
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= self.x && point.x < self.right() && point.y >= self.y && point.y < self.bottom()
+    }
+
+    /// Whether the two rects share at least one cell
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    /// Smallest rect covering both
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect::new(
+            x,
+            y,
+            self.right().max(other.right()) - x,
+            self.bottom().max(other.bottom()) - y,
+        )
+    }
+
+    /// Grow by `margin` cells on every side, clamped at the canvas origin
+    pub fn inflate(&self, margin: usize) -> Rect {
+        let x = self.x.saturating_sub(margin);
+        let y = self.y.saturating_sub(margin);
+        Rect::new(x, y, self.right() + margin - x, self.bottom() + margin - y)
+    }
+
+    /// Manhattan distance from a cell to the nearest cell of the rect
+    /// (0 when inside)
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn distance_to(&self, point: Point) -> usize {
+        let dx = if point.x < self.x {
+            self.x - point.x
+        } else {
+            point.x.saturating_sub(self.right().saturating_sub(1))
+        };
+        let dy = if point.y < self.y {
+            self.y - point.y
+        } else {
+            point.y.saturating_sub(self.bottom().saturating_sub(1))
+        };
+        dx + dy
+    }
+}
+
+/// A straight run of cells between two points (both included)
+///
+/// Routes only use horizontal and vertical segments; the cell helpers treat
+/// anything else by its bounding box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Segment {
+    /// First cell
+    pub start: Point,
+    /// Last cell
+    pub end: Point,
+}
+
+impl Segment {
+    /// Create a segment
+    pub const fn new(start: Point, end: Point) -> Self {
+        Self { start, end }
+    }
+
+    /// Segments joining consecutive points of a route
+    pub fn path(points: &[Point]) -> impl Iterator<Item = Segment> + '_ {
+        points.windows(2).map(|pair| Segment::new(pair[0], pair[1]))
+    }
+
+    /// Returns true if start and end share a row
+    pub fn is_horizontal(&self) -> bool {
+        self.start.y == self.end.y
+    }
+
+    /// Returns true if start and end share a column
+    pub fn is_vertical(&self) -> bool {
+        self.start.x == self.end.x
+    }
+
+    /// Number of cells along the segment, minus one
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn len(&self) -> usize {
+        self.start.distance(self.end)
+    }
+
+    /// Whether start and end are the same cell
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Cell halfway along the segment
+    pub fn midpoint(&self) -> Point {
+        Point::new(
+            (self.start.x + self.end.x) / 2,
+            (self.start.y + self.end.y) / 2,
+        )
+    }
+
+    /// Bounding rect of the cells the segment covers
+    pub fn bounds(&self) -> Rect {
+        let x = self.start.x.min(self.end.x);
+        let y = self.start.y.min(self.end.y);
+        Rect::new(
+            x,
+            y,
+            self.start.x.abs_diff(self.end.x) + 1,
+            self.start.y.abs_diff(self.end.y) + 1,
+        )
+    }
+
+    /// Whether the segment passes through the cell
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn contains(&self, point: Point) -> bool {
+        self.bounds().contains(point)
+    }
+
+    /// Whether the segment passes through any cell of the rect
+    pub fn intersects_rect(&self, rect: &Rect) -> bool {
+        self.bounds().intersects(rect)
+    }
+
+    /// The cell where two axis-aligned segments cross or touch
+    ///
+    /// Returns `None` when they do not meet. Overlapping parallel segments
+    /// meet at the first shared cell (closest to the origin).
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn intersection(&self, other: &Segment) -> Option<Point> {
+        let a = self.bounds();
+        let b = other.bounds();
+        if !a.intersects(&b) {
+            return None;
+        }
+        Some(Point::new(a.x.max(b.x), a.y.max(b.y)))
+    }
+
+    /// Every cell along the segment, from start to end
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn cells(&self) -> Vec<Point> {
+        let Segment { start, end } = *self;
+        if start.y == end.y {
+            let xs: Vec<usize> = if start.x <= end.x {
+                (start.x..=end.x).collect()
+            } else {
+                (end.x..=start.x).rev().collect()
+            };
+            xs.into_iter().map(|x| Point::new(x, start.y)).collect()
+        } else {
+            let ys: Vec<usize> = if start.y <= end.y {
+                (start.y..=end.y).collect()
+            } else {
+                (end.y..=start.y).rev().collect()
+            };
+            ys.into_iter().map(|y| Point::new(start.x, y)).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect_edges_are_half_open() {
+        let rect = Rect::new(2, 1, 5, 3);
+        assert_eq!((rect.right(), rect.bottom()), (7, 4));
+        assert!(rect.contains(Point::new(6, 3)));
+        assert!(!rect.contains(Point::new(7, 3)));
+        assert!(!rect.contains(Point::new(6, 4)));
+        assert_eq!(rect.center(), Point::new(4, 2));
+    }
+
+    #[test]
+    fn test_point_step_follows_direction() {
+        let point = Point::new(4, 4);
+        assert_eq!(point.step(Direction::TopDown, 2), Point::new(4, 6));
+        assert_eq!(point.step(Direction::BottomUp, 2), Point::new(4, 2));
+        assert_eq!(point.step(Direction::RightLeft, -1), Point::new(5, 4));
+        assert_eq!(point.step(Direction::LeftRight, -9), Point::new(0, 4));
+    }
+
+    #[test]
+    fn test_rect_exit_and_entry_follow_direction() {
+        let rect = Rect::new(10, 4, 7, 3);
+        assert_eq!(rect.exit(Direction::TopDown), Point::new(13, 7));
+        assert_eq!(rect.entry(Direction::TopDown), Point::new(13, 4));
+        assert_eq!(rect.exit(Direction::LeftRight), Point::new(17, 5));
+        assert_eq!(rect.entry(Direction::LeftRight), Point::new(10, 5));
+        assert_eq!(
+            rect.exit(Direction::BottomUp),
+            rect.entry(Direction::TopDown)
+        );
+        assert_eq!(
+            rect.exit(Direction::RightLeft),
+            rect.entry(Direction::LeftRight)
+        );
+    }
+
+    #[test]
+    fn test_rect_union_intersects_and_inflate() {
+        let a = Rect::new(0, 0, 4, 3);
+        let b = Rect::new(6, 2, 2, 2);
+        assert!(!a.intersects(&b));
+        assert_eq!(a.union(&b), Rect::new(0, 0, 8, 4));
+        assert!(a.inflate(3).intersects(&b));
+        assert_eq!(b.inflate(1), Rect::new(5, 1, 4, 4));
+        assert_eq!(a.inflate(1), Rect::new(0, 0, 5, 4));
+    }
+
+    #[test]
+    fn test_rect_distance_to() {
+        let rect = Rect::new(5, 5, 3, 3);
+        assert_eq!(rect.distance_to(Point::new(6, 6)), 0);
+        assert_eq!(rect.distance_to(Point::new(9, 6)), 2);
+        assert_eq!(rect.distance_to(Point::new(4, 3)), 3);
+    }
+
+    #[test]
+    fn test_segment_intersection() {
+        let horizontal = Segment::new(Point::new(0, 5), Point::new(10, 5));
+        let vertical = Segment::new(Point::new(4, 0), Point::new(4, 8));
+        let away = Segment::new(Point::new(12, 0), Point::new(12, 8));
+        assert_eq!(horizontal.intersection(&vertical), Some(Point::new(4, 5)));
+        assert_eq!(horizontal.intersection(&away), None);
+        assert!(vertical.contains(Point::new(4, 8)));
+        assert!(vertical.intersects_rect(&Rect::new(3, 7, 2, 2)));
+    }
+
+    #[test]
+    fn test_segment_cells_run_from_start() {
+        let segment = Segment::new(Point::new(3, 2), Point::new(1, 2));
+        assert_eq!(
+            segment.cells(),
+            vec![Point::new(3, 2), Point::new(2, 2), Point::new(1, 2)]
+        );
+        assert_eq!(segment.len(), 2);
+        assert_eq!(segment.midpoint(), Point::new(2, 2));
+    }
+
+    #[test]
+    fn test_path_segments() {
+        let route = [Point::new(0, 0), Point::new(0, 3), Point::new(5, 3)];
+        let segments: Vec<_> = Segment::path(&route).collect();
+        assert_eq!(segments.len(), 2);
+        assert!(segments[0].is_vertical() && segments[1].is_horizontal());
+    }
+}
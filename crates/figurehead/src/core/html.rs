@@ -0,0 +1,358 @@
+//! HTML export for rendered diagrams
+//!
+//! [`ansi_to_html`] wraps rendered text in a `<pre>` block and turns ANSI
+//! SGR sequences (as produced by the role palette and `--color`) into
+//! `<span>` elements with inline styles, so a diagram can be pasted into a
+//! static site and keep its terminal look.
+//!
+//! [`RoleLayer::to_html`] does the same for colors that never went through
+//! ANSI: renderers paint CSS colors onto character cells (e.g. from
+//! `classDef` and `style` statements) and the layer writes the spans
+//! directly.
+
+use std::fmt::Write;
+
+use super::{CellStyle, Palette, RoleLayer};
+
+/// Colors for SGR 30-37 / 90-97 (and the matching backgrounds)
+const BASIC_COLORS: [&str; 16] = [
+    "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+    "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+];
+
+/// Text attributes active at a point in the stream
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SgrState {
+    pub(crate) foreground: Option<String>,
+    pub(crate) background: Option<String>,
+    pub(crate) bold: bool,
+    pub(crate) dim: bool,
+    pub(crate) italic: bool,
+    pub(crate) underline: bool,
+}
+
+impl SgrState {
+    fn is_plain(&self) -> bool {
+        *self == SgrState::default()
+    }
+
+    fn css(&self) -> String {
+        let mut css = Vec::new();
+        if let Some(color) = &self.foreground {
+            css.push(format!("color:{}", color));
+        }
+        if let Some(color) = &self.background {
+            css.push(format!("background-color:{}", color));
+        }
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if self.dim {
+            css.push("opacity:0.7".to_string());
+        }
+        if self.italic {
+            css.push("font-style:italic".to_string());
+        }
+        if self.underline {
+            css.push("text-decoration:underline".to_string());
+        }
+        css.join(";")
+    }
+
+    /// Apply the parameters of one `ESC [ ... m` sequence
+    fn apply(&mut self, params: &str) {
+        let codes: Vec<u16> = if params.is_empty() {
+            vec![0]
+        } else {
+            params.split(';').map(|p| p.parse().unwrap_or(0)).collect()
+        };
+
+        let mut i = 0;
+        while i < codes.len() {
+            match codes[i] {
+                0 => *self = SgrState::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                22 => {
+                    self.bold = false;
+                    self.dim = false;
+                }
+                23 => self.italic = false,
+                24 => self.underline = false,
+                code @ 30..=37 => self.foreground = Some(BASIC_COLORS[(code - 30) as usize].into()),
+                code @ 90..=97 => {
+                    self.foreground = Some(BASIC_COLORS[(code - 90 + 8) as usize].into())
+                }
+                39 => self.foreground = None,
+                code @ 40..=47 => self.background = Some(BASIC_COLORS[(code - 40) as usize].into()),
+                code @ 100..=107 => {
+                    self.background = Some(BASIC_COLORS[(code - 100 + 8) as usize].into())
+                }
+                49 => self.background = None,
+                code @ (38 | 48) => {
+                    let (color, used) = extended_color(&codes[i + 1..]);
+                    if code == 38 {
+                        self.foreground = color;
+                    } else {
+                        self.background = color;
+                    }
+                    i += used;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+}
+
+/// Parse the arguments of a 38/48 sequence (`5;n` or `2;r;g;b`)
+///
+/// Returns the color and how many codes were consumed.
+fn extended_color(args: &[u16]) -> (Option<String>, usize) {
+    match args {
+        [5, n, ..] => (Some(indexed_color(*n)), 2),
+        [2, r, g, b, ..] => (
+            Some(format!(
+                "#{:02x}{:02x}{:02x}",
+                (*r).min(255),
+                (*g).min(255),
+                (*b).min(255)
+            )),
+            4,
+        ),
+        _ => (None, args.len()),
+    }
+}
+
+/// Hex color for an entry of the 256-color palette
+fn indexed_color(n: u16) -> String {
+    match n {
+        0..=15 => BASIC_COLORS[n as usize].to_string(),
+        16..=231 => {
+            let n = n - 16;
+            let level = |v: u16| if v == 0 { 0 } else { 55 + v * 40 };
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(n / 36),
+                level((n / 6) % 6),
+                level(n % 6)
+            )
+        }
+        _ => {
+            let gray = 8 + (n.min(255) - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", gray, gray, gray)
+        }
+    }
+}
+
+fn escape_into(out: &mut String, c: char) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        _ => out.push(c),
+    }
+}
+
+/// Close the open span, if any, and open one for `state` unless it is plain
+fn switch_span(out: &mut String, span_open: &mut bool, state: &SgrState) {
+    if *span_open {
+        out.push_str("</span>");
+        *span_open = false;
+    }
+    if !state.is_plain() {
+        let _ = write!(out, "<span style=\"{}\">", state.css());
+        *span_open = true;
+    }
+}
+
+/// Convert rendered (optionally ANSI-colored) text into an HTML `<pre>` block
+///
+/// Text is HTML-escaped; styled runs become `<span style="...">`. Unknown
+/// escape sequences are dropped.
+///
+/// # Example
+/// ```rust
+/// let html = figurehead::ansi_to_html("\x1b[36m[A]\x1b[0m -> <B>");
+/// assert_eq!(
+///     html,
+///     "<pre class=\"figurehead\"><span style=\"color:#11a8cd\">[A]</span> -&gt; &lt;B&gt;</pre>"
+/// );
+/// ```
+pub fn ansi_to_html(text: &str) -> String {
+    let mut out = String::from("<pre class=\"figurehead\">");
+    let mut current = SgrState::default();
+    let mut span_open = false;
+
+    for_each_styled_char(text, |c, state| {
+        if *state != current {
+            switch_span(&mut out, &mut span_open, state);
+            current = state.clone();
+        }
+        escape_into(&mut out, c);
+    });
+
+    if span_open {
+        out.push_str("</span>");
+    }
+    out.push_str("</pre>");
+    out
+}
+
+/// Visit each character of ANSI-colored text with the attributes set on it
+///
+/// Escape sequences are consumed, not visited; unknown ones are dropped.
+pub(crate) fn for_each_styled_char(text: &str, mut visit: impl FnMut(char, &SgrState)) {
+    let mut state = SgrState::default();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            visit(c, &state);
+            continue;
+        }
+        if chars.peek() != Some(&'[') {
+            continue;
+        }
+        chars.next();
+
+        let mut params = String::new();
+        let mut terminator = None;
+        for c in chars.by_ref() {
+            if c.is_ascii_digit() || c == ';' {
+                params.push(c);
+            } else {
+                terminator = Some(c);
+                break;
+            }
+        }
+        if terminator == Some('m') {
+            state.apply(&params);
+        }
+    }
+}
+
+impl CellStyle {
+    /// Attributes of a cell holding `c`, with the role's palette color
+    /// under the CSS colors
+    ///
+    /// Text colors skip blank cells; backgrounds cover them.
+    pub(crate) fn sgr_state(&self, c: char, palette: &Palette) -> SgrState {
+        let mut state = SgrState::default();
+        if c != ' ' {
+            if let Some(role) = self.role {
+                state.apply(palette.code(role));
+            }
+            if self.color.is_some() {
+                state.foreground.clone_from(&self.color);
+            }
+        }
+        if self.background.is_some() {
+            state.background.clone_from(&self.background);
+        }
+        state
+    }
+}
+
+impl RoleLayer {
+    /// Wrap rendered text in a `<pre>` block, coloring the painted cells
+    ///
+    /// Cells are `(column, line)` of `text`. Spans never cross a line break.
+    pub fn to_html(&self, text: &str, palette: &Palette) -> String {
+        let mut out = String::from("<pre class=\"figurehead\">");
+        let mut span_open = false;
+        let mut current = SgrState::default();
+
+        for (y, line) in text.split('\n').enumerate() {
+            if y > 0 {
+                if span_open {
+                    switch_span(&mut out, &mut span_open, &SgrState::default());
+                    current = SgrState::default();
+                }
+                out.push('\n');
+            }
+            for (x, c) in line.chars().enumerate() {
+                let state = self
+                    .style(x, y)
+                    .map(|style| style.sgr_state(c, palette))
+                    .unwrap_or_default();
+                if state != current {
+                    switch_span(&mut out, &mut span_open, &state);
+                    current = state;
+                }
+                escape_into(&mut out, c);
+            }
+        }
+
+        if span_open {
+            out.push_str("</span>");
+        }
+        out.push_str("</pre>");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text_is_escaped() {
+        assert_eq!(
+            ansi_to_html("A --> B & C"),
+            "<pre class=\"figurehead\">A --&gt; B &amp; C</pre>"
+        );
+    }
+
+    #[test]
+    fn test_palette_codes_become_spans() {
+        let html = ansi_to_html("\x1b[1;33mYes\x1b[0m\n\x1b[2m│\x1b[0m");
+        assert_eq!(
+            html,
+            "<pre class=\"figurehead\"><span style=\"color:#e5e510;font-weight:bold\">Yes</span>\n<span style=\"opacity:0.7\">│</span></pre>"
+        );
+    }
+
+    #[test]
+    fn test_truecolor_and_indexed_colors() {
+        let html = ansi_to_html("\x1b[38;2;255;0;128mX\x1b[39m \x1b[48;5;196mY\x1b[49m");
+        assert!(html.contains("<span style=\"color:#ff0080\">X</span> "));
+        assert!(html.contains("<span style=\"background-color:#ff0000\">Y</span>"));
+    }
+
+    #[test]
+    fn test_unclosed_style_is_closed() {
+        let html = ansi_to_html("\x1b[36mopen");
+        assert!(html.ends_with("open</span></pre>"));
+    }
+
+    #[test]
+    fn test_layer_colors_painted_cells() {
+        let mut layer = RoleLayer::new();
+        let fill = CellStyle {
+            background: Some("#f9f".to_string()),
+            ..CellStyle::default()
+        };
+        let text = CellStyle {
+            color: Some("red".to_string()),
+            ..CellStyle::default()
+        };
+        for x in 1..4 {
+            layer.paint_style(x, 0, &fill);
+        }
+        layer.paint_style(2, 0, &text);
+        layer.paint(0, 1, crate::core::Role::Edge);
+
+        assert_eq!(
+            layer.to_html("[a<b]\nc", &Palette::default()),
+            "<pre class=\"figurehead\">[<span style=\"background-color:#f9f\">a</span>\
+             <span style=\"color:red;background-color:#f9f\">&lt;</span>\
+             <span style=\"background-color:#f9f\">b</span>]\n\
+             <span style=\"color:#e5e510\">c</span></pre>"
+        );
+        assert_eq!(layer.style(0, 0), None);
+    }
+}
@@ -15,8 +15,10 @@ use super::Database;
 ///
 /// # Example
 /// ```
-/// use figurehead::core::{LayoutAlgorithm, Database};
-/// use figurehead::plugins::flowchart::{FlowchartDatabase, FlowchartLayoutAlgorithm};
+/// use figurehead::core::Database;
+/// use figurehead::plugins::flowchart::FlowchartDatabase;
+/// use figurehead::unstable::flowchart::FlowchartLayoutAlgorithm;
+/// use figurehead::unstable::LayoutAlgorithm;
 ///
 /// let db = FlowchartDatabase::new();
 /// let layout = FlowchartLayoutAlgorithm::new();
@@ -30,12 +32,15 @@ pub trait LayoutAlgorithm<D: Database>: Send + Sync {
     fn layout(&self, database: &D) -> Result<Self::Output>;
 
     /// Get the name of this layout algorithm
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     fn name(&self) -> &'static str;
 
     /// Get the version of this layout algorithm
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     fn version(&self) -> &'static str;
 
     /// Get the layout direction (LR, TB, etc.)
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     fn direction(&self) -> &'static str;
 }
 
@@ -0,0 +1,313 @@
+//! Canvas mirroring
+//!
+//! Flips a finished canvas left-to-right or top-to-bottom, swapping each
+//! glyph for its mirror image (`┌` ↔ `┐`, `▶` ↔ `◀`, ...) so lines still
+//! connect. Text is kept readable: runs of text keep their reading order and
+//! stacked lines of a wrapped label keep their top-to-bottom order.
+//!
+//! Rendering TD or LR and mirroring the result gives BT and RL diagrams with
+//! the same routing quality as the forward directions.
+
+use super::canvas::AsciiCanvas;
+
+/// Which way to flip a canvas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorAxis {
+    /// Swap left and right (LR → RL)
+    Horizontal,
+    /// Swap top and bottom (TD → BT)
+    Vertical,
+}
+
+/// Pairs of glyphs that turn into each other when flipped left-to-right
+const HORIZONTAL_PAIRS: &[(char, char)] = &[
+    ('┌', '┐'),
+    ('└', '┘'),
+    ('├', '┤'),
+    ('╭', '╮'),
+    ('╰', '╯'),
+    ('╔', '╗'),
+    ('╚', '╝'),
+    ('╠', '╣'),
+    ('╓', '╖'),
+    ('╙', '╜'),
+    ('╒', '╕'),
+    ('╘', '╛'),
+    ('┏', '┓'),
+    ('┗', '┛'),
+    ('┣', '┫'),
+    ('▶', '◀'),
+    ('▷', '◁'),
+    ('►', '◄'),
+    ('╱', '╲'),
+    ('/', '\\'),
+    ('<', '>'),
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+    ('▌', '▐'),
+];
+
+/// Pairs of glyphs that turn into each other when flipped top-to-bottom
+const VERTICAL_PAIRS: &[(char, char)] = &[
+    ('┌', '└'),
+    ('┐', '┘'),
+    ('┬', '┴'),
+    ('╭', '╰'),
+    ('╮', '╯'),
+    ('╔', '╚'),
+    ('╗', '╝'),
+    ('╦', '╩'),
+    ('╓', '╙'),
+    ('╖', '╜'),
+    ('╒', '╘'),
+    ('╕', '╛'),
+    ('┏', '┗'),
+    ('┓', '┛'),
+    ('┳', '┻'),
+    ('▲', '▼'),
+    ('△', '▽'),
+    ('╱', '╲'),
+    ('/', '\\'),
+    ('^', 'v'),
+    ('▀', '▄'),
+];
+
+/// The glyph `c` becomes when flipped along `axis`
+pub fn mirror_glyph(c: char, axis: MirrorAxis) -> char {
+    let pairs = match axis {
+        MirrorAxis::Horizontal => HORIZONTAL_PAIRS,
+        MirrorAxis::Vertical => VERTICAL_PAIRS,
+    };
+    pairs
+        .iter()
+        .find_map(|&(a, b)| {
+            if c == a {
+                Some(b)
+            } else if c == b {
+                Some(a)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(c)
+}
+
+/// Characters that draw lines in ASCII output but can also appear in labels
+fn is_ambiguous(c: char) -> bool {
+    matches!(
+        c,
+        '-' | '|' | '+' | '<' | '>' | '^' | '/' | '\\' | '.' | ':' | '=' | '*' | '_' | '#'
+    )
+}
+
+/// Characters that draw shape outlines but also wrap words in labels
+fn is_bracket(c: char) -> bool {
+    matches!(c, '(' | ')' | '[' | ']' | '{' | '}')
+}
+
+/// Whether `c` is drawing rather than text, regardless of its neighbours
+fn is_drawing(c: char) -> bool {
+    matches!(c as u32, 0x2190..=0x21FF | 0x2500..=0x25FF | 0x2800..=0x28FF)
+}
+
+/// Mark which cells of a row are text
+///
+/// Letters and digits are always text. Characters that double as ASCII
+/// line-drawing (`-`, `>`, ...) count as text only when a letter or digit
+/// sits on both sides of them, at most one space away, as in `re-try` or
+/// `x > 5`. Brackets count as text when they touch a letter or digit, and a
+/// `v` only when it does (a lone `v` is an arrow head).
+fn text_mask(row: &[char]) -> Vec<bool> {
+    let strong = |i: Option<usize>| {
+        i.and_then(|i| row.get(i))
+            .is_some_and(|&c| c.is_alphanumeric() && c != 'v')
+    };
+    let side = |i: usize, step: isize| {
+        let at = |d: isize| i.checked_add_signed(d * step);
+        strong(at(1)) || (at(1).and_then(|j| row.get(j)) == Some(&' ') && strong(at(2)))
+    };
+
+    row.iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            if c == ' ' || is_drawing(c) {
+                false
+            } else if c == 'v' || is_bracket(c) {
+                strong(i.checked_sub(1)) || strong(Some(i + 1))
+            } else if is_ambiguous(c) {
+                side(i, -1) && side(i, 1)
+            } else {
+                true
+            }
+        })
+        .collect()
+}
+
+/// A run of text cells: row and inclusive column range
+type Run = (usize, usize, usize);
+
+/// Find runs of text, joining words separated by single spaces
+fn text_runs(grid: &[Vec<char>]) -> Vec<Run> {
+    let mut runs = Vec::new();
+    for (y, row) in grid.iter().enumerate() {
+        let mask = text_mask(row);
+        let mut x = 0;
+        while x < row.len() {
+            if !mask[x] {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            let mut end = x;
+            x += 1;
+            while x < row.len() {
+                if mask[x] {
+                    end = x;
+                    x += 1;
+                } else if row[x] == ' ' && mask.get(x + 1) == Some(&true) {
+                    x += 1;
+                } else {
+                    break;
+                }
+            }
+            runs.push((y, start, end));
+        }
+    }
+    runs
+}
+
+/// Group runs on consecutive rows with overlapping columns into blocks, so a
+/// wrapped label moves as one piece
+fn text_blocks(runs: &[Run]) -> Vec<Vec<Run>> {
+    let mut blocks: Vec<Vec<Run>> = Vec::new();
+    for &run in runs {
+        let (y, start, end) = run;
+        let joined = blocks.iter_mut().find(|block| {
+            block
+                .iter()
+                .any(|&(by, bs, be)| by + 1 == y && bs <= end && start <= be)
+        });
+        match joined {
+            Some(block) => block.push(run),
+            None => blocks.push(vec![run]),
+        }
+    }
+    blocks
+}
+
+/// Flip a canvas along `axis`
+///
+/// Drawing glyphs are mirrored cell by cell. Text moves with its position
+/// (a label right of a node ends up left of it) but is not itself reversed:
+/// horizontally each run keeps its reading order, and vertically each block
+/// of stacked text rows keeps its row order.
+pub fn mirror_canvas(canvas: &AsciiCanvas, axis: MirrorAxis) -> AsciiCanvas {
+    let grid = &canvas.grid;
+    let height = grid.len();
+    let width = grid.iter().map(Vec::len).max().unwrap_or(0);
+    let cell = |x: usize, y: usize| grid[y].get(x).copied().unwrap_or(' ');
+    let flip = |x: usize, y: usize| match axis {
+        MirrorAxis::Horizontal => (width - 1 - x, y),
+        MirrorAxis::Vertical => (x, height - 1 - y),
+    };
+
+    let runs = text_runs(grid);
+    let mut is_text = vec![vec![false; width]; height];
+    for &(y, start, end) in &runs {
+        is_text[y][start..=end].fill(true);
+    }
+
+    let mut mirrored = AsciiCanvas::new(canvas.width.max(width), canvas.height.max(height));
+    for (y, row) in is_text.iter().enumerate() {
+        for (x, _) in row.iter().enumerate().filter(|(_, &text)| !text) {
+            let (mx, my) = flip(x, y);
+            mirrored.set_char(mx, my, mirror_glyph(cell(x, y), axis));
+        }
+    }
+
+    match axis {
+        MirrorAxis::Horizontal => {
+            for (y, start, end) in runs {
+                let new_start = width - 1 - end;
+                for x in start..=end {
+                    mirrored.set_char(new_start + (x - start), y, cell(x, y));
+                }
+            }
+        }
+        MirrorAxis::Vertical => {
+            for block in text_blocks(&runs) {
+                let top = block.iter().map(|r| r.0).min().unwrap_or(0);
+                let bottom = block.iter().map(|r| r.0).max().unwrap_or(0);
+                let new_top = height - 1 - bottom;
+                for (y, start, end) in block {
+                    for x in start..=end {
+                        mirrored.set_char(x, new_top + (y - top), cell(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    mirrored
+}
+
+/// Flip rendered text along `axis` (see [`mirror_canvas`])
+pub fn mirror_text(rendered: &str, axis: MirrorAxis) -> String {
+    let lines: Vec<&str> = rendered.lines().collect();
+    let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let mut canvas = AsciiCanvas::new(width, lines.len());
+    for (y, line) in lines.iter().enumerate() {
+        canvas.draw_text(0, y, line);
+    }
+    mirror_canvas(&canvas, axis).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyphs_swap_with_their_mirror_image() {
+        assert_eq!(mirror_glyph('┌', MirrorAxis::Horizontal), '┐');
+        assert_eq!(mirror_glyph('◀', MirrorAxis::Horizontal), '▶');
+        assert_eq!(mirror_glyph('┬', MirrorAxis::Vertical), '┴');
+        assert_eq!(mirror_glyph('▼', MirrorAxis::Vertical), '▲');
+        assert_eq!(mirror_glyph('─', MirrorAxis::Vertical), '─');
+    }
+
+    #[test]
+    fn test_horizontal_flip_keeps_text_readable() {
+        let flipped = mirror_text(
+            "┌──────┐   ┌───┐\n│ Load │──▶│ B │\n└──────┘   └───┘",
+            MirrorAxis::Horizontal,
+        );
+        assert_eq!(
+            flipped,
+            "┌───┐   ┌──────┐\n│ B │◀──│ Load │\n└───┘   └──────┘"
+        );
+    }
+
+    #[test]
+    fn test_vertical_flip_keeps_wrapped_labels_in_order() {
+        let original =
+            "┌───────┐\n│ first │\n│ line  │\n└───────┘\n    │\n    ▼\n  ┌───┐\n  │ B │\n  └───┘";
+        let flipped = mirror_text(original, MirrorAxis::Vertical);
+        assert_eq!(
+            flipped,
+            "  ┌───┐\n  │ B │\n  └───┘\n    ▲\n    │\n┌───────┐\n│ first │\n│ line  │\n└───────┘"
+        );
+    }
+
+    #[test]
+    fn test_ascii_operators_inside_labels_stay_text() {
+        let flipped = mirror_text(
+            "+-------+    +---+\n| x > 5 |--->| A |\n+-------+    +---+",
+            MirrorAxis::Horizontal,
+        );
+        assert_eq!(
+            flipped,
+            "+---+    +-------+\n| A |<---| x > 5 |\n+---+    +-------+"
+        );
+    }
+}
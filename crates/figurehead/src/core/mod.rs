@@ -3,34 +3,63 @@
 //! This module defines the fundamental traits that all diagram types must implement,
 //! following the mermaid.js architecture with SOLID principles.
 
-mod box_drawing;
-mod canvas;
+mod cells;
 pub mod chumsky_utils;
 mod database;
 mod detector;
 mod diagram;
-mod edge_routing;
+mod directive;
 mod error;
-mod layout;
+mod html;
 pub mod logging;
 mod parser;
 mod renderer;
-mod syntax;
+mod sheet;
+mod terminator;
 mod text;
+mod theme;
 mod types;
+mod warning;
 
-pub use box_drawing::*;
-pub use canvas::*;
+pub use cells::*;
 pub use chumsky_utils::*;
 pub use database::*;
 pub use detector::*;
 pub use diagram::*;
-pub use edge_routing::*;
+pub use directive::*;
 pub use error::*;
-pub use layout::*;
+pub use html::*;
 pub use logging::*;
 pub use parser::*;
 pub use renderer::*;
-pub use syntax::*;
+pub use sheet::*;
+pub use terminator::*;
 pub use text::*;
+pub use theme::*;
 pub use types::*;
+pub use warning::*;
+
+// Layout internals and drawing surfaces. They are public only through
+// `figurehead::unstable` (behind the `unstable` feature) and may change in any
+// release; inside the crate they are used through these re-exports.
+pub(crate) mod box_drawing;
+pub(crate) mod braille;
+pub(crate) mod canvas;
+pub(crate) mod edge_routing;
+pub(crate) mod footer;
+pub(crate) mod geometry;
+pub(crate) mod layout;
+pub(crate) mod mirror;
+pub(crate) mod palette;
+pub(crate) mod syntax;
+
+pub(crate) use box_drawing::*;
+pub(crate) use braille::*;
+pub(crate) use canvas::*;
+pub(crate) use edge_routing::*;
+pub(crate) use footer::*;
+pub(crate) use geometry::*;
+pub(crate) use layout::*;
+pub(crate) use mirror::*;
+pub(crate) use palette::*;
+pub(crate) use syntax::*;
@@ -0,0 +1,523 @@
+//! Role-based terminal colors for diagram output
+//!
+//! Renderers draw onto a plain [`AsciiCanvas`] and record what each cell
+//! represents in a [`RoleLayer`], usually through a [`RoleRecorder`] that
+//! diffs the canvas after each drawing pass. When color is enabled the layer
+//! turns the canvas into ANSI-colored text, so every diagram type shares one
+//! palette: boxes look alike whether they are participants, states or classes.
+//! The same layer carries CSS colors painted from node styles, and writes
+//! HTML as well as ANSI text.
+
+use super::{AsciiCanvas, CellGrid, Color};
+
+/// What a drawn cell represents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Primary boxes: participants, states, class outlines
+    Node,
+    /// Text naming a node: class names
+    Header,
+    /// Secondary text inside a node: class attributes and methods
+    Member,
+    /// Enclosing frames around groups of elements
+    Frame,
+    /// Sequence diagram lifelines
+    Lifeline,
+    /// Connections: messages, transitions, relationships
+    Edge,
+    /// Text attached to a connection
+    Label,
+    /// Commits and lines of a git graph branch, by lane index
+    Branch(usize),
+}
+
+/// ANSI SGR parameters for each role
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    /// Primary boxes: participants, states, class outlines
+    pub node: &'static str,
+    /// Text naming a node: class names
+    pub header: &'static str,
+    /// Class attributes and methods
+    pub member: &'static str,
+    /// Frames around groups of elements
+    pub frame: &'static str,
+    /// Sequence lifelines
+    pub lifeline: &'static str,
+    /// Connections: messages, transitions, relationships
+    pub edge: &'static str,
+    /// Text attached to a connection
+    pub label: &'static str,
+    /// Branch colors, cycled by lane index
+    pub branches: &'static [&'static str],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            node: "36",     // cyan
+            header: "1;36", // bold cyan
+            member: "37",   // white
+            frame: "34",    // blue
+            lifeline: "2",  // dim
+            edge: "33",     // yellow
+            label: "1;33",  // bold yellow
+            // green, magenta, blue, red, cyan, yellow: the first lane stands
+            // apart from the node and edge colors of other diagram types
+            branches: &["32", "35", "34", "31", "36", "33"],
+        }
+    }
+}
+
+impl Palette {
+    /// SGR parameters for a role
+    pub fn code(&self, role: Role) -> &'static str {
+        match role {
+            Role::Node => self.node,
+            Role::Header => self.header,
+            Role::Member => self.member,
+            Role::Frame => self.frame,
+            Role::Lifeline => self.lifeline,
+            Role::Edge => self.edge,
+            Role::Label => self.label,
+            Role::Branch(lane) => self
+                .branches
+                .get(lane % self.branches.len().max(1))
+                .copied()
+                .unwrap_or(self.edge),
+        }
+    }
+}
+
+/// How one cell is colored
+///
+/// `role` picks a [`Palette`] color; CSS colors (from `classDef` and `style`
+/// statements or a theme) are drawn over it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CellStyle {
+    /// What the cell represents
+    pub role: Option<Role>,
+    /// Text color, any CSS color value
+    pub color: Option<String>,
+    /// Background color, any CSS color value
+    pub background: Option<String>,
+}
+
+impl CellStyle {
+    /// ANSI SGR parameters for a cell holding `c` (empty when uncolored)
+    ///
+    /// Text colors skip blank cells; backgrounds cover them. CSS colors
+    /// without an RGB value (unknown names) are skipped.
+    fn sgr(&self, c: char, palette: &Palette) -> String {
+        let rgb = |css: &Option<String>| css.as_deref().and_then(Color::parse)?.to_rgb();
+        let mut params = Vec::new();
+        if c != ' ' {
+            if let Some(role) = self.role {
+                params.push(palette.code(role).to_string());
+            }
+            if let Some((r, g, b)) = rgb(&self.color) {
+                params.push(format!("38;2;{};{};{}", r, g, b));
+            }
+        }
+        if let Some((r, g, b)) = rgb(&self.background) {
+            params.push(format!("48;2;{};{};{}", r, g, b));
+        }
+        params.join(";")
+    }
+}
+
+/// Per-cell styles for a canvas or its rendered text
+///
+/// The one place renderers record colors: palette roles for the terminal,
+/// CSS colors from node styles, both written out as ANSI text or HTML.
+/// Cells can also name the element drawn over them, for [`CellGrid`]s.
+#[derive(Debug, Clone, Default)]
+pub struct RoleLayer {
+    cells: Vec<Vec<CellStyle>>,
+    owners: Vec<Vec<Option<String>>>,
+}
+
+impl RoleLayer {
+    /// Create an empty layer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Role of a cell, if one was recorded
+    #[cfg(any(test, feature = "unstable"))]
+    pub fn get(&self, x: usize, y: usize) -> Option<Role> {
+        self.style(x, y).and_then(|cell| cell.role)
+    }
+
+    /// Style of a cell, if anything was painted on it
+    pub fn style(&self, x: usize, y: usize) -> Option<&CellStyle> {
+        self.cells
+            .get(y)
+            .and_then(|row| row.get(x))
+            .filter(|cell| **cell != CellStyle::default())
+    }
+
+    /// Record the role of a single cell
+    pub fn paint(&mut self, x: usize, y: usize, role: Role) {
+        self.paint_style(
+            x,
+            y,
+            &CellStyle {
+                role: Some(role),
+                ..CellStyle::default()
+            },
+        );
+    }
+
+    /// Set the style of a cell; `None` fields keep what was painted before
+    pub fn paint_style(&mut self, x: usize, y: usize, style: &CellStyle) {
+        if self.cells.len() <= y {
+            self.cells.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.cells[y];
+        if row.len() <= x {
+            row.resize(x + 1, CellStyle::default());
+        }
+        let cell = &mut row[x];
+        if style.role.is_some() {
+            cell.role = style.role;
+        }
+        if style.color.is_some() {
+            cell.color.clone_from(&style.color);
+        }
+        if style.background.is_some() {
+            cell.background.clone_from(&style.background);
+        }
+    }
+
+    /// Mark a cell as drawn by the element `id`
+    pub fn set_owner(&mut self, x: usize, y: usize, id: &str) {
+        if self.owners.len() <= y {
+            self.owners.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.owners[y];
+        if row.len() <= x {
+            row.resize(x + 1, None);
+        }
+        row[x] = Some(id.to_string());
+    }
+
+    /// ID of the element drawn at a position
+    pub fn owner(&self, x: usize, y: usize) -> Option<&str> {
+        self.owners.get(y)?.get(x)?.as_deref()
+    }
+
+    /// Forget every recorded role, keeping CSS colors and owners
+    pub fn clear_roles(&mut self) {
+        for cell in self.cells.iter_mut().flatten() {
+            cell.role = None;
+        }
+    }
+
+    /// Record `role` for every cell that differs between two canvas states
+    ///
+    /// Renderers snapshot the canvas before a drawing pass and call this
+    /// afterwards, so draw routines need no knowledge of colors.
+    pub fn paint_changes(&mut self, before: &AsciiCanvas, after: &AsciiCanvas, role: Role) {
+        for y in 0..after.height {
+            for x in 0..after.width {
+                if before.get_char(x, y) != after.get_char(x, y) {
+                    self.paint(x, y, role);
+                }
+            }
+        }
+    }
+
+    /// Record `role` for the non-blank cells of a row span
+    pub fn paint_text(
+        &mut self,
+        canvas: &AsciiCanvas,
+        x: usize,
+        y: usize,
+        width: usize,
+        role: Role,
+    ) {
+        for cx in x..x + width {
+            if canvas.get_char(cx, y) != ' ' {
+                self.paint(cx, y, role);
+            }
+        }
+    }
+
+    /// The layer moved `left` columns and `top` lines towards the origin
+    ///
+    /// Maps a layer recorded against a canvas onto the canvas's printed
+    /// text (see [`AsciiCanvas::trim_offset`]).
+    pub fn shifted(&self, left: usize, top: usize) -> Self {
+        fn shift<T: Clone>(rows: &[Vec<T>], left: usize, top: usize) -> Vec<Vec<T>> {
+            rows.iter()
+                .skip(top)
+                .map(|row| row.iter().skip(left).cloned().collect())
+                .collect()
+        }
+        Self {
+            cells: shift(&self.cells, left, top),
+            owners: shift(&self.owners, left, top),
+        }
+    }
+
+    /// Render the canvas with ANSI colors
+    ///
+    /// Produces the same text layout as `canvas.to_string()` (trailing
+    /// blanks, empty edge rows and common indentation removed).
+    pub fn colorize(&self, canvas: &AsciiCanvas, palette: &Palette) -> String {
+        let (left, top) = canvas.trim_offset();
+        self.shifted(left, top)
+            .to_ansi(&canvas.to_string(), palette)
+    }
+
+    /// Rendered text with ANSI escapes around its styled runs
+    ///
+    /// Cells are `(column, line)` of `text`. Every line ends with colors
+    /// reset, and an unpainted layer leaves the text as it is.
+    pub fn to_ansi(&self, text: &str, palette: &Palette) -> String {
+        let mut lines = Vec::new();
+        for (y, line) in text.split('\n').enumerate() {
+            let mut out = String::new();
+            let mut current = String::new();
+            for (x, c) in line.chars().enumerate() {
+                let params = self
+                    .style(x, y)
+                    .map(|style| style.sgr(c, palette))
+                    .unwrap_or_default();
+                if params != current {
+                    if !current.is_empty() {
+                        out.push_str("\x1b[0m");
+                    }
+                    if !params.is_empty() {
+                        out.push_str(&format!("\x1b[{}m", params));
+                    }
+                    current = params;
+                }
+                out.push(c);
+            }
+            if !current.is_empty() {
+                out.push_str("\x1b[0m");
+            }
+            lines.push(out);
+        }
+        lines.join("\n")
+    }
+}
+
+/// Records roles pass by pass while a renderer draws
+///
+/// Disabled recorders do nothing and [`RoleRecorder::finish`] returns the
+/// plain canvas text, so renderers can call it unconditionally.
+#[derive(Debug, Clone)]
+pub struct RoleRecorder {
+    layer: RoleLayer,
+    /// The canvas as of the previous pass; `None` when disabled
+    snapshot: Option<AsciiCanvas>,
+}
+
+impl RoleRecorder {
+    /// Start recording against the current canvas contents
+    pub fn new(enabled: bool, canvas: &AsciiCanvas) -> Self {
+        Self {
+            layer: RoleLayer::new(),
+            snapshot: enabled.then(|| canvas.clone()),
+        }
+    }
+
+    /// Assign `role` to every cell drawn since the previous call
+    pub fn record(&mut self, canvas: &AsciiCanvas, role: Role) {
+        if let Some(snapshot) = &mut self.snapshot {
+            self.layer.paint_changes(snapshot, canvas, role);
+            snapshot.clone_from(canvas);
+        }
+    }
+
+    /// Assign `role` to the non-blank cells of a row span
+    pub fn paint_text(
+        &mut self,
+        canvas: &AsciiCanvas,
+        x: usize,
+        y: usize,
+        width: usize,
+        role: Role,
+    ) {
+        if self.snapshot.is_some() {
+            self.layer.paint_text(canvas, x, y, width, role);
+        }
+    }
+
+    /// Mark every cell of a box as drawn by the element `id`
+    pub fn own(&mut self, x: usize, y: usize, width: usize, height: usize, id: &str) {
+        if self.snapshot.is_some() {
+            for cy in y..y + height {
+                for cx in x..x + width {
+                    self.layer.set_owner(cx, cy, id);
+                }
+            }
+        }
+    }
+
+    /// Mark cells as drawn by the element `id`, e.g. those an edge's
+    /// drawing wrote (see [`AsciiCanvas::track_writes`])
+    pub fn own_cells(&mut self, cells: &[(usize, usize)], id: &str) {
+        if self.snapshot.is_some() {
+            for &(x, y) in cells {
+                self.layer.set_owner(x, y, id);
+            }
+        }
+    }
+
+    /// Final output: colored when recording, plain otherwise
+    pub fn finish(self, canvas: &AsciiCanvas) -> String {
+        match self.snapshot {
+            Some(_) => self.layer.colorize(canvas, &Palette::default()),
+            None => canvas.to_string(),
+        }
+    }
+
+    /// Final output as a [`CellGrid`] with the recorded owners
+    ///
+    /// Roles become palette colors only when `color` is set.
+    pub fn finish_cells(mut self, canvas: &AsciiCanvas, color: bool) -> CellGrid {
+        if !color {
+            self.layer.clear_roles();
+        }
+        let (left, top) = canvas.trim_offset();
+        self.layer
+            .shifted(left, top)
+            .to_grid(&canvas.to_string(), &Palette::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip_ansi(s: &str) -> String {
+        let mut out = String::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                for c in chars.by_ref() {
+                    if c == 'm' {
+                        break;
+                    }
+                }
+            } else {
+                out.push(c);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_colorize_matches_plain_layout() {
+        let mut canvas = AsciiCanvas::new(10, 4);
+        canvas.draw_text(2, 1, "box");
+        canvas.draw_text(4, 2, "--> x");
+
+        let mut layer = RoleLayer::new();
+        layer.paint_text(&canvas, 2, 1, 3, Role::Node);
+        layer.paint_text(&canvas, 4, 2, 5, Role::Edge);
+
+        let colored = layer.colorize(&canvas, &Palette::default());
+        assert_eq!(strip_ansi(&colored), canvas.to_string());
+        assert!(colored.starts_with("\x1b[36mbox\x1b[0m"));
+        assert!(colored.contains("\x1b[33m-->\x1b[0m \x1b[33mx\x1b[0m"));
+    }
+
+    #[test]
+    fn test_paint_changes_marks_only_new_cells() {
+        let mut canvas = AsciiCanvas::new(6, 1);
+        canvas.draw_text(0, 0, "ab");
+        let before = canvas.clone();
+        canvas.draw_text(3, 0, "cd");
+
+        let mut layer = RoleLayer::new();
+        layer.paint_changes(&before, &canvas, Role::Label);
+        assert_eq!(layer.get(0, 0), None);
+        assert_eq!(layer.get(3, 0), Some(Role::Label));
+        assert_eq!(layer.get(4, 0), Some(Role::Label));
+    }
+
+    #[test]
+    fn test_recorder_assigns_roles_per_pass() {
+        let mut canvas = AsciiCanvas::new(8, 1);
+        let mut recorder = RoleRecorder::new(true, &canvas);
+        canvas.draw_text(0, 0, "[A]");
+        recorder.record(&canvas, Role::Node);
+        canvas.draw_text(4, 0, "->");
+        recorder.record(&canvas, Role::Edge);
+
+        let output = recorder.finish(&canvas);
+        assert_eq!(output, "\x1b[36m[A]\x1b[0m \x1b[33m->\x1b[0m");
+
+        let plain = RoleRecorder::new(false, &canvas);
+        assert_eq!(plain.finish(&canvas), "[A] ->");
+    }
+
+    #[test]
+    fn test_css_colors_draw_over_roles() {
+        let mut layer = RoleLayer::new();
+        layer.paint(0, 0, Role::Node);
+        layer.paint(1, 0, Role::Node);
+        layer.paint_style(
+            1,
+            0,
+            &CellStyle {
+                color: Some("#f00".to_string()),
+                background: Some("blue".to_string()),
+                ..CellStyle::default()
+            },
+        );
+        layer.paint_style(
+            2,
+            0,
+            &CellStyle {
+                color: Some("unknown".to_string()),
+                ..CellStyle::default()
+            },
+        );
+
+        assert_eq!(layer.get(1, 0), Some(Role::Node));
+        assert_eq!(
+            layer.to_ansi("ab c", &Palette::default()),
+            "\x1b[36ma\x1b[0m\x1b[36;38;2;255;0;0;48;2;0;0;255mb\x1b[0m c"
+        );
+    }
+
+    #[test]
+    fn test_recorder_cells_keep_owners_in_printed_coordinates() {
+        let mut canvas = AsciiCanvas::new(8, 3);
+        let mut recorder = RoleRecorder::new(true, &canvas);
+        canvas.draw_text(2, 1, "[A]");
+        recorder.record(&canvas, Role::Node);
+        recorder.own(2, 1, 3, 1, "A");
+
+        let grid = recorder.clone().finish_cells(&canvas, false);
+        assert_eq!((grid.width, grid.height), (3, 1));
+        assert_eq!(grid.owner_at(0, 0), Some("A"));
+        assert!(grid.get(1, 0).unwrap().style.is_plain());
+
+        let colored = recorder.finish_cells(&canvas, true);
+        assert_eq!(
+            colored.get(1, 0).unwrap().style.color.as_deref(),
+            Some("#11a8cd")
+        );
+    }
+
+    #[test]
+    fn test_unpainted_canvas_is_plain() {
+        let mut canvas = AsciiCanvas::new(4, 1);
+        canvas.draw_text(0, 0, "abc");
+        assert_eq!(
+            RoleLayer::new().colorize(&canvas, &Palette::default()),
+            "abc"
+        );
+        assert_eq!(
+            RoleLayer::new().colorize(&AsciiCanvas::new(3, 3), &Palette::default()),
+            ""
+        );
+    }
+}
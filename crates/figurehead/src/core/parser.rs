@@ -5,7 +5,7 @@
 
 use anyhow::Result;
 
-use super::Database;
+use super::{Database, ParseWarning};
 
 /// Core trait for diagram parsers
 ///
@@ -33,6 +33,34 @@ pub trait Parser<D: Database>: Send + Sync {
 
     /// Check if the input can be parsed by this parser
     fn can_parse(&self, input: &str) -> bool;
+
+    /// Parse into `database` and return it with the warnings taken out
+    ///
+    /// # Example
+    /// ```
+    /// use figurehead::core::Parser;
+    /// use figurehead::plugins::flowchart::{FlowchartDatabase, FlowchartParser};
+    ///
+    /// let parsed = FlowchartParser::new()
+    ///     .parse_with_warnings("graph TD\n  A --> B\n  ~~~", FlowchartDatabase::new())
+    ///     .unwrap();
+    /// assert_eq!(parsed.warnings.len(), 1);
+    /// assert_eq!(parsed.warnings[0].line, Some(3));
+    /// ```
+    fn parse_with_warnings(&self, input: &str, mut database: D) -> Result<ParseResult<D>> {
+        self.parse(input, &mut database)?;
+        let warnings = database.take_parse_warnings();
+        Ok(ParseResult { database, warnings })
+    }
+}
+
+/// A parsed diagram and the warnings raised while parsing it
+#[derive(Debug, Clone)]
+pub struct ParseResult<D> {
+    /// The parsed diagram
+    pub database: D,
+    /// Statements that were skipped or had no effect, with their positions
+    pub warnings: Vec<ParseWarning>,
 }
 
 #[cfg(test)]
@@ -64,4 +92,20 @@ mod tests {
         assert_eq!(database.node_count(), 2);
         assert_eq!(database.edge_count(), 1);
     }
+
+    #[test]
+    fn test_parse_with_warnings_takes_them_out_of_the_database() {
+        use crate::plugins::er::{ErDatabase, ErParser};
+
+        let parsed = ErParser::new()
+            .parse_with_warnings(
+                "erDiagram\n  CUSTOMER ||--o{ ORDER : places\n  ???",
+                ErDatabase::new(),
+            )
+            .unwrap();
+        assert_eq!(parsed.database.entity_count(), 2);
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].line, Some(3));
+        assert!(parsed.database.parse_warnings().is_empty());
+    }
 }
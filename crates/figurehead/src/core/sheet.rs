@@ -0,0 +1,170 @@
+//! Several rendered diagrams in one output
+//!
+//! Markdown extraction and split input produce more than one diagram at a
+//! time. A [`DiagramSheet`] lays them out one after another, each under a
+//! rule carrying its title, drawn with the active character set:
+//!
+//! ```text
+//! ═══ flowchart at line 1 ═══     === flowchart at line 1 ===
+//! ```
+
+use super::box_drawing::BoxChars;
+use super::footer::visible_width;
+use super::CharacterSet;
+
+/// Rule characters before a title
+const RULE_LEAD: usize = 3;
+
+/// Diagrams rendered one after another, separated by titled rules
+///
+/// # Example
+/// ```rust
+/// use figurehead::{CharacterSet, DiagramSheet};
+///
+/// let sheet = DiagramSheet::new(CharacterSet::Ascii)
+///     .with_diagram(Some("first"), "+---+\n| A |\n+---+")
+///     .with_diagram(Some("second"), "+---+\n| B |\n+---+");
+/// assert!(sheet.render().starts_with("=== first ===\n+---+"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiagramSheet {
+    style: CharacterSet,
+    diagrams: Vec<(Option<String>, String)>,
+}
+
+impl DiagramSheet {
+    /// Create an empty sheet drawing its rules with `style`
+    pub fn new(style: CharacterSet) -> Self {
+        Self {
+            style,
+            diagrams: Vec::new(),
+        }
+    }
+
+    /// Add a rendered diagram, under `title` if given
+    pub fn with_diagram(mut self, title: Option<&str>, diagram: impl Into<String>) -> Self {
+        self.push(title, diagram);
+        self
+    }
+
+    /// Add a rendered diagram in place
+    pub fn push(&mut self, title: Option<&str>, diagram: impl Into<String>) {
+        self.diagrams
+            .push((title.map(str::to_string), diagram.into()));
+    }
+
+    /// Number of diagrams on the sheet
+    pub fn len(&self) -> usize {
+        self.diagrams.len()
+    }
+
+    /// Whether the sheet has no diagrams
+    pub fn is_empty(&self) -> bool {
+        self.diagrams.is_empty()
+    }
+
+    /// Lay the diagrams out, separated by blank lines
+    ///
+    /// Titled diagrams sit under a titled rule; untitled ones after the
+    /// first are set apart by a plain rule. A lone untitled diagram is
+    /// returned as it is. Rules are as wide as the widest diagram.
+    pub fn render(&self) -> String {
+        if let [(None, diagram)] = self.diagrams.as_slice() {
+            return diagram.clone();
+        }
+
+        let width = self
+            .diagrams
+            .iter()
+            .flat_map(|(_, diagram)| diagram.lines())
+            .map(visible_width)
+            .max()
+            .unwrap_or(0);
+        self.diagrams
+            .iter()
+            .enumerate()
+            .map(|(index, (title, diagram))| {
+                let diagram = diagram.trim_end_matches('\n');
+                match title {
+                    Some(title) => format!(
+                        "{}\n{}",
+                        title_rule(self.style, Some(title), width),
+                        diagram
+                    ),
+                    None if index > 0 => {
+                        format!("{}\n{}", title_rule(self.style, None, width), diagram)
+                    }
+                    None => diagram.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// A horizontal rule `width` columns wide, with `title` set into it
+///
+/// The rule uses the double-line character of the character set, so it
+/// stands apart from the single lines inside diagrams. A title too long for
+/// `width` keeps a short rule on each side and runs past it.
+///
+/// # Example
+/// ```rust
+/// use figurehead::{title_rule, CharacterSet};
+///
+/// assert_eq!(title_rule(CharacterSet::Unicode, Some("Flow"), 14), "═══ Flow ═════");
+/// assert_eq!(title_rule(CharacterSet::Ascii, None, 5), "=====");
+/// ```
+pub fn title_rule(style: CharacterSet, title: Option<&str>, width: usize) -> String {
+    let rule = BoxChars::double(style).horizontal;
+    let Some(title) = title else {
+        return rule.to_string().repeat(width.max(RULE_LEAD));
+    };
+
+    let lead = rule.to_string().repeat(RULE_LEAD);
+    let used = RULE_LEAD + 1 + visible_width(title) + 1;
+    let tail = rule
+        .to_string()
+        .repeat(width.saturating_sub(used).max(RULE_LEAD));
+    format!("{} {} {}", lead, title, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheet_titles_and_separates_diagrams() {
+        let sheet = DiagramSheet::new(CharacterSet::Unicode)
+            .with_diagram(Some("one"), "┌───────┐\n│ Alpha │\n└───────┘\n")
+            .with_diagram(None, "┌───┐\n│ B │\n└───┘");
+        assert_eq!(
+            sheet.render(),
+            "═══ one ═══\n┌───────┐\n│ Alpha │\n└───────┘\n\n═════════\n┌───┐\n│ B │\n└───┘"
+        );
+
+        // Rules follow the character set
+        let ascii = DiagramSheet::new(CharacterSet::Ascii)
+            .with_diagram(Some("one"), "A")
+            .render();
+        assert_eq!(ascii, "=== one ===\nA");
+    }
+
+    #[test]
+    fn test_lone_untitled_diagram_is_unchanged() {
+        let sheet = DiagramSheet::new(CharacterSet::Unicode).with_diagram(None, "A\n");
+        assert_eq!(sheet.render(), "A\n");
+        assert_eq!(DiagramSheet::new(CharacterSet::Ascii).render(), "");
+    }
+
+    #[test]
+    fn test_title_rule_fills_the_width() {
+        let rule = title_rule(CharacterSet::Unicode, Some("Title"), 20);
+        assert_eq!(rule, "═══ Title ══════════");
+        assert_eq!(visible_width(&rule), 20);
+        assert_eq!(
+            title_rule(CharacterSet::Compact, Some("Long title"), 4),
+            "=== Long title ==="
+        );
+    }
+}
@@ -10,22 +10,34 @@ use anyhow::Result;
 pub enum SyntaxNode {
     /// A node/vertex in the diagram
     Node {
+        /// Node ID
         id: String,
+        /// Display label, if given
         label: Option<String>,
+        /// Extra attributes
         metadata: SyntaxMetadata,
     },
     /// An edge/connection between nodes
     Edge {
+        /// Source node ID
         from: String,
+        /// Target node ID
         to: String,
+        /// Edge label, if given
         label: Option<String>,
+        /// Extra attributes
         metadata: SyntaxMetadata,
     },
     /// A grouping construct (e.g., subgraph)
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     Group {
+        /// Group ID
         id: String,
+        /// Display label, if given
         label: Option<String>,
+        /// Nodes inside the group
         children: Vec<SyntaxNode>,
+        /// Extra attributes
         metadata: SyntaxMetadata,
     },
 }
@@ -38,15 +50,18 @@ pub struct SyntaxMetadata {
 }
 
 impl SyntaxMetadata {
+    /// Create empty metadata
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Add an attribute
     pub fn with_attr(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.attributes.insert(key.into(), value.into());
         self
     }
 
+    /// Value of an attribute
     pub fn get(&self, key: &str) -> Option<&String> {
         self.attributes.get(key)
     }
@@ -63,12 +78,15 @@ pub trait SyntaxParser: Send + Sync {
     /// Returns a vector of syntax nodes representing the parsed structure.
     /// The parser should handle errors gracefully and return partial results
     /// when possible.
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     fn parse(&self, input: &str) -> Result<Vec<SyntaxNode>>;
 
     /// Get the name of this syntax parser
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     fn name(&self) -> &'static str;
 
     /// Get the version of this syntax parser
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     fn version(&self) -> &'static str;
 
     /// Check if the input can be parsed by this syntax parser
@@ -0,0 +1,160 @@
+//! Edge terminators
+//!
+//! A terminator is the marker drawn where an edge meets its target: an
+//! arrowhead, a UML triangle or diamond, a circle, a cross. Glyphs depend on
+//! the heading of the edge and the character set. Every renderer looks them
+//! up here, so a marker added once is available to all diagram types.
+
+use super::{CharacterSet, Direction, EdgeType};
+
+/// Marker at the target end of an edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum EdgeTerminator {
+    /// Filled arrowhead: `▶` / `>`
+    #[default]
+    Arrow,
+    /// Circle: `○` / `o`
+    Open,
+    /// Cross: `×` / `x`
+    Cross,
+    /// Diamond: `◆` or `◇` / `*` or `o`
+    Diamond {
+        /// Solid (`◆`) rather than hollow (`◇`)
+        filled: bool,
+    },
+    /// Hollow triangle: `▷` / `>`
+    Triangle,
+    /// No marker; the line runs up to the target
+    None,
+}
+
+impl EdgeTerminator {
+    /// Terminator drawn for a flowchart edge type
+    ///
+    /// Dotted arrows use the hollow triangle so they read as lighter than
+    /// solid ones.
+    pub fn for_edge_type(edge_type: EdgeType) -> Self {
+        match edge_type {
+            EdgeType::Arrow | EdgeType::ThickArrow => EdgeTerminator::Arrow,
+            EdgeType::DottedArrow => EdgeTerminator::Triangle,
+            EdgeType::OpenArrow => EdgeTerminator::Open,
+            EdgeType::CrossArrow => EdgeTerminator::Cross,
+            EdgeType::Line | EdgeType::DottedLine | EdgeType::ThickLine | EdgeType::Invisible => {
+                EdgeTerminator::None
+            }
+        }
+    }
+
+    /// Glyph for an edge travelling in `heading` when it reaches its target
+    ///
+    /// Returns `None` for [`EdgeTerminator::None`].
+    pub fn glyph(self, heading: Direction, style: CharacterSet) -> Option<char> {
+        let ascii = style.is_ascii();
+        let pointed = |ascii_set: [char; 4], unicode_set: [char; 4]| {
+            let set = if ascii { ascii_set } else { unicode_set };
+            match heading {
+                Direction::TopDown => set[0],
+                Direction::BottomUp => set[1],
+                Direction::LeftRight => set[2],
+                Direction::RightLeft => set[3],
+            }
+        };
+
+        let glyph = match self {
+            EdgeTerminator::Arrow => pointed(['v', '^', '>', '<'], ['▼', '▲', '▶', '◀']),
+            EdgeTerminator::Triangle => pointed(['v', '^', '>', '<'], ['▽', '△', '▷', '◁']),
+            EdgeTerminator::Open => {
+                if ascii {
+                    'o'
+                } else {
+                    '○'
+                }
+            }
+            EdgeTerminator::Cross => {
+                if ascii {
+                    'x'
+                } else {
+                    '×'
+                }
+            }
+            EdgeTerminator::Diamond { filled } => match (filled, ascii) {
+                (true, true) => '*',
+                (false, true) => 'o',
+                (true, false) => '◆',
+                (false, false) => '◇',
+            },
+            EdgeTerminator::None => return None,
+        };
+        Some(glyph)
+    }
+
+    /// Returns true if a marker is drawn
+    pub fn is_visible(self) -> bool {
+        self != EdgeTerminator::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arrow_follows_heading() {
+        let arrow = EdgeTerminator::Arrow;
+        assert_eq!(
+            arrow.glyph(Direction::TopDown, CharacterSet::Unicode),
+            Some('▼')
+        );
+        assert_eq!(
+            arrow.glyph(Direction::RightLeft, CharacterSet::Unicode),
+            Some('◀')
+        );
+        assert_eq!(
+            arrow.glyph(Direction::BottomUp, CharacterSet::Ascii),
+            Some('^')
+        );
+    }
+
+    #[test]
+    fn test_markers_per_character_set() {
+        let cases = [
+            (EdgeTerminator::Open, '○', 'o'),
+            (EdgeTerminator::Cross, '×', 'x'),
+            (EdgeTerminator::Diamond { filled: true }, '◆', '*'),
+            (EdgeTerminator::Diamond { filled: false }, '◇', 'o'),
+            (EdgeTerminator::Triangle, '▷', '>'),
+        ];
+        for (terminator, unicode, ascii) in cases {
+            let heading = Direction::LeftRight;
+            assert_eq!(
+                terminator.glyph(heading, CharacterSet::Unicode),
+                Some(unicode)
+            );
+            assert_eq!(terminator.glyph(heading, CharacterSet::Ascii), Some(ascii));
+        }
+        assert_eq!(
+            EdgeTerminator::None.glyph(Direction::TopDown, CharacterSet::Unicode),
+            None
+        );
+    }
+
+    #[test]
+    fn test_edge_type_mapping() {
+        assert_eq!(
+            EdgeTerminator::for_edge_type(EdgeType::OpenArrow),
+            EdgeTerminator::Open
+        );
+        assert_eq!(
+            EdgeTerminator::for_edge_type(EdgeType::CrossArrow),
+            EdgeTerminator::Cross
+        );
+        for edge_type in EdgeType::ALL {
+            assert_eq!(
+                EdgeTerminator::for_edge_type(edge_type).is_visible(),
+                edge_type.has_arrow(),
+                "{:?}",
+                edge_type
+            );
+        }
+    }
+}
@@ -2,11 +2,13 @@
 //!
 //! This module contains common text manipulation functions used across plugins.
 
+use std::borrow::Cow;
 use unicode_width::UnicodeWidthStr;
 
 /// Wrap text to fit within a maximum width, breaking on word boundaries.
 ///
 /// Returns a vector of lines, each fitting within `max_width` display columns.
+/// Explicit line breaks (`\n`, from `<br>` tags) always start a new line.
 /// If `max_width` is 0, or the label fits on one line, returns a single-element vector.
 ///
 /// # Example
@@ -15,8 +17,15 @@ use unicode_width::UnicodeWidthStr;
 ///
 /// let lines = wrap_label("This is a long label", 10);
 /// assert_eq!(lines, vec!["This is a", "long label"]);
+/// assert_eq!(wrap_label("one\ntwo", 0), vec!["one", "two"]);
 /// ```
 pub fn wrap_label(label: &str, max_width: usize) -> Vec<String> {
+    if label.contains('\n') {
+        return label
+            .split('\n')
+            .flat_map(|line| wrap_label(line, max_width))
+            .collect();
+    }
     if max_width == 0 || UnicodeWidthStr::width(label) <= max_width {
         return vec![label.to_string()];
     }
@@ -56,6 +65,226 @@ pub fn wrap_label(label: &str, max_width: usize) -> Vec<String> {
     }
 }
 
+/// Turn Mermaid `<br>` tags into line breaks
+///
+/// Accepts `<br>`, `<br/>` and `<br />` in any letter case, with the spaces
+/// around a tag dropped. Other tags are left alone.
+///
+/// # Example
+/// ```
+/// use figurehead::core::line_breaks;
+///
+/// assert_eq!(line_breaks("one<br/>two <BR> three"), "one\ntwo\nthree");
+/// assert_eq!(line_breaks("a <b> c"), "a <b> c");
+/// ```
+pub fn line_breaks(label: &str) -> Cow<'_, str> {
+    if !label.to_ascii_lowercase().contains("<br") {
+        return Cow::Borrowed(label);
+    }
+
+    let mut out = String::with_capacity(label.len());
+    let mut rest = label;
+    while let Some(start) = rest.find('<') {
+        let tag_len = br_tag_len(&rest[start..]);
+        if tag_len == 0 {
+            out.push_str(&rest[..=start]);
+            rest = &rest[start + 1..];
+            continue;
+        }
+        out.push_str(rest[..start].trim_end());
+        out.push('\n');
+        rest = rest[start + tag_len..].trim_start();
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Length of a `<br>`, `<br/>` or `<br />` tag at the start of `text`, or 0
+fn br_tag_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    if bytes.len() < 4 || !bytes[..3].eq_ignore_ascii_case(b"<br") {
+        return 0;
+    }
+    let mut end = 3;
+    while bytes.get(end) == Some(&b' ') {
+        end += 1;
+    }
+    if bytes.get(end) == Some(&b'/') {
+        end += 1;
+    }
+    match bytes.get(end) {
+        Some(b'>') => end + 1,
+        _ => 0,
+    }
+}
+
+/// Plain text of a Mermaid markdown string (`` "`**bold** text`" ``)
+///
+/// Drops the backticks and the `**`, `__`, `*` and `_` emphasis markers
+/// around words; terminals have no bold or italics to show them with.
+/// Text without surrounding backticks is returned unchanged.
+///
+/// # Example
+/// ```
+/// use figurehead::core::markdown_text;
+///
+/// assert_eq!(markdown_text("`**Bold** and _italic_ snake_case`"), "Bold and italic snake_case");
+/// assert_eq!(markdown_text("plain *text*"), "plain *text*");
+/// ```
+pub fn markdown_text(text: &str) -> Cow<'_, str> {
+    let Some(inner) = text
+        .strip_prefix('`')
+        .and_then(|rest| rest.strip_suffix('`'))
+    else {
+        return Cow::Borrowed(text);
+    };
+
+    let lines: Vec<String> = inner
+        .split('\n')
+        .map(|line| {
+            line.split(' ')
+                .map(|word| {
+                    let word = word.trim_start_matches(['*', '_']);
+                    word.trim_end_matches(['*', '_'])
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect();
+    Cow::Owned(lines.join("\n"))
+}
+
+/// Decode HTML entities and Mermaid `#…;` escapes in a label
+///
+/// Handles named references (`&amp;`, `#quot;`), decimal ones (`&#35;`,
+/// `#35;`) and hex ones (`&#x2665;`). Names it does not know, and numbers
+/// that would decode to control characters, are left as written, so text
+/// such as `Item #1; next` survives unchanged.
+///
+/// # Example
+/// ```
+/// use figurehead::core::decode_entities;
+///
+/// assert_eq!(decode_entities("a &amp; b #quot;c#quot; #35;1"), "a & b \"c\" #1");
+/// assert_eq!(decode_entities("Item #1; next"), "Item #1; next");
+/// ```
+pub fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains(';') || !text.contains(['&', '#']) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(['&', '#']) {
+        out.push_str(&rest[..start]);
+        let candidate = &rest[start..];
+        match decode_entity(candidate) {
+            Some((decoded, len)) => {
+                out.push(decoded);
+                rest = &candidate[len..];
+            }
+            None => {
+                out.push_str(&candidate[..1]);
+                rest = &candidate[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Character and length of an entity at the start of `text`
+fn decode_entity(text: &str) -> Option<(char, usize)> {
+    // `&name;`, `&#35;` and `&#x23;` from HTML; `#name;` and `#35;` from Mermaid
+    let (prefix, body) = text.split_at(1);
+    let end = body.find(';').filter(|&end| (1..=10).contains(&end))?;
+    let name = &body[..end];
+
+    let decoded = match name.strip_prefix('#') {
+        Some(number) if prefix == "&" => numeric_entity(number)?,
+        Some(_) => return None,
+        None if prefix == "#" && name.starts_with(|c: char| c.is_ascii_digit()) => {
+            numeric_entity(name)?
+        }
+        None => match name {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            "nbsp" => ' ',
+            "copy" => '©',
+            "reg" => '®',
+            "deg" => '°',
+            "times" => '×',
+            "divide" => '÷',
+            "hellip" => '…',
+            "ndash" => '–',
+            "mdash" => '—',
+            "laquo" => '«',
+            "raquo" => '»',
+            "larr" => '←',
+            "rarr" => '→',
+            "uarr" => '↑',
+            "darr" => '↓',
+            "hearts" => '♥',
+            _ => return None,
+        },
+    };
+    Some((decoded, 1 + end + 1))
+}
+
+/// Character for a decimal (`35`) or hex (`x23`) code point
+fn numeric_entity(number: &str) -> Option<char> {
+    let code = match number.strip_prefix(['x', 'X']) {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => number.parse().ok()?,
+    };
+    char::from_u32(code).filter(|c| !c.is_control())
+}
+
+/// Clean up copy-paste artifacts before parsing
+///
+/// Markup copied from web pages and Windows editors often carries a UTF-8
+/// byte order mark, CRLF line endings, non-breaking spaces and statement
+/// terminating `;`s. This strips the BOM, turns `\r\n` and lone `\r` into
+/// `\n`, replaces non-breaking spaces with plain ones and drops trailing
+/// semicolons from each line. Line numbers are preserved, so positions
+/// reported against the normalized text still point into the original.
+///
+/// # Example
+/// ```
+/// use figurehead::core::normalize_input;
+///
+/// let input = "\u{feff}graph TD;\r\n    A\u{a0}-->\u{a0}B;\r\n";
+/// assert_eq!(normalize_input(input), "graph TD\n    A --> B\n");
+/// ```
+pub fn normalize_input(input: &str) -> Cow<'_, str> {
+    let input = input.strip_prefix('\u{feff}').unwrap_or(input);
+    let clean = !input.contains(['\r', '\u{a0}', '\u{202f}', '\u{2007}'])
+        && input.lines().all(|line| !line.trim_end().ends_with(';'));
+    if clean {
+        return Cow::Borrowed(input);
+    }
+
+    let unified = input.replace("\r\n", "\n").replace('\r', "\n");
+    let mut normalized = String::with_capacity(unified.len());
+    for line in unified.split_inclusive('\n') {
+        let (content, newline) = match line.strip_suffix('\n') {
+            Some(content) => (content, "\n"),
+            None => (line, ""),
+        };
+        let content = content.trim_end();
+        let content = content.trim_end_matches(';').trim_end();
+        normalized.extend(content.chars().map(|c| match c {
+            '\u{a0}' | '\u{202f}' | '\u{2007}' => ' ',
+            c => c,
+        }));
+        normalized.push_str(newline);
+    }
+    Cow::Owned(normalized)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,4 +335,55 @@ mod tests {
         let result = wrap_label("one two three four five", 8);
         assert_eq!(result, vec!["one two", "three", "four", "five"]);
     }
+
+    #[test]
+    fn test_wrap_keeps_explicit_breaks() {
+        let result = wrap_label("short\na much longer line", 8);
+        assert_eq!(result, vec!["short", "a much", "longer", "line"]);
+    }
+
+    #[test]
+    fn test_line_breaks_accepts_every_br_form() {
+        assert_eq!(line_breaks("a<br>b<br/>c<br />d<BR/>e"), "a\nb\nc\nd\ne");
+        assert!(matches!(line_breaks("no breaks"), Cow::Borrowed(_)));
+        assert_eq!(line_breaks("<brand> <br"), "<brand> <br");
+    }
+
+    #[test]
+    fn test_markdown_text_keeps_inner_underscores() {
+        assert_eq!(markdown_text("`__init__ is *fine*`"), "init is fine");
+        assert_eq!(markdown_text("`a_b`"), "a_b");
+        assert_eq!(markdown_text("`unterminated"), "`unterminated");
+    }
+
+    #[test]
+    fn test_decode_entities_forms() {
+        assert_eq!(
+            decode_entities("&lt;b&gt; #amp; &#x2665; #9829; &#65;"),
+            "<b> & ♥ ♥ A"
+        );
+        // Unknown names, bare numbers after `&` and control characters stay
+        assert_eq!(
+            decode_entities("&bogus; &35; #0; &#;"),
+            "&bogus; &35; #0; &#;"
+        );
+        assert!(matches!(decode_entities("a & b"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_normalize_leaves_clean_input_borrowed() {
+        let input = "graph TD\n    A; B --> C\n";
+        assert!(matches!(normalize_input(input), Cow::Borrowed(s) if s == input));
+    }
+
+    #[test]
+    fn test_normalize_keeps_line_count() {
+        let input = "\u{feff}sequenceDiagram\r\n\r\n    Alice->>Bob: Hi;\r    Bob-->>Alice: Yo ;  ";
+        let normalized = normalize_input(input);
+        assert_eq!(
+            normalized,
+            "sequenceDiagram\n\n    Alice->>Bob: Hi\n    Bob-->>Alice: Yo"
+        );
+        assert_eq!(normalized.lines().count(), 4);
+    }
 }
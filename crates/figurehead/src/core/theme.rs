@@ -0,0 +1,187 @@
+//! Color themes for flowchart output
+//!
+//! A [`Theme`] gives colored flowcharts the look of a Mermaid theme: nodes
+//! get its border, fill and text colors and edges its line color. Nodes with
+//! a `classDef` or `style` keep those colors on top. Themes come from
+//! [`RenderConfig::theme`](super::RenderConfig::theme) or from the `theme`
+//! key of an init directive, which takes precedence:
+//!
+//! ```text
+//! %%{init: {'theme': 'forest'}}%%
+//! ```
+//!
+//! Besides the named themes, a custom one is written as comma-separated
+//! `key=color` pairs over the default theme, e.g.
+//! `stroke=#f80,fill=none,line=gray`.
+
+use std::fmt;
+
+use super::Color;
+
+/// An RGB color
+pub type Rgb = (u8, u8, u8);
+
+/// Default colors for flowchart nodes and edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Theme {
+    /// Node borders
+    pub stroke: Rgb,
+    /// Background inside nodes (`None` leaves the terminal background)
+    pub fill: Option<Rgb>,
+    /// Node labels and edge labels
+    pub text: Rgb,
+    /// Edges and arrow heads
+    pub line: Rgb,
+}
+
+impl Theme {
+    /// Mermaid's `default` theme: purple borders on lavender
+    pub const DEFAULT: Theme = Theme {
+        stroke: (0x93, 0x70, 0xdb),
+        fill: Some((0xec, 0xec, 0xff)),
+        text: (0x33, 0x33, 0x33),
+        line: (0x33, 0x33, 0x33),
+    };
+
+    /// Mermaid's `dark` theme: light lines on charcoal, for dark terminals
+    pub const DARK: Theme = Theme {
+        stroke: (0x81, 0xb1, 0xdb),
+        fill: Some((0x1f, 0x20, 0x20)),
+        text: (0xcc, 0xcc, 0xcc),
+        line: (0xd3, 0xd3, 0xd3),
+    };
+
+    /// Mermaid's `forest` theme: dark green borders on light green
+    pub const FOREST: Theme = Theme {
+        stroke: (0x13, 0x54, 0x0c),
+        fill: Some((0xcd, 0xe4, 0x98)),
+        text: (0x00, 0x00, 0x00),
+        line: (0x00, 0x80, 0x00),
+    };
+
+    /// Mermaid's `neutral` theme: grays, for printing
+    pub const NEUTRAL: Theme = Theme {
+        stroke: (0x99, 0x99, 0x99),
+        fill: Some((0xee, 0xee, 0xee)),
+        text: (0x33, 0x33, 0x33),
+        line: (0x66, 0x66, 0x66),
+    };
+
+    /// Names of the built-in themes
+    pub const NAMES: [&'static str; 4] = ["default", "dark", "forest", "neutral"];
+
+    /// Built-in theme by Mermaid name; `base` is the default theme
+    pub fn named(name: &str) -> Option<Theme> {
+        match name.to_lowercase().as_str() {
+            "default" | "base" => Some(Theme::DEFAULT),
+            "dark" => Some(Theme::DARK),
+            "forest" => Some(Theme::FOREST),
+            "neutral" => Some(Theme::NEUTRAL),
+            _ => None,
+        }
+    }
+
+    /// Name of this theme, if it is a built-in one
+    pub fn name(&self) -> Option<&'static str> {
+        Theme::NAMES
+            .into_iter()
+            .find(|name| Theme::named(name).as_ref() == Some(self))
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DEFAULT
+    }
+}
+
+impl fmt::Display for Theme {
+    /// The theme's name, or its colors as `key=#rrggbb` pairs
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = self.name() {
+            return write!(f, "{}", name);
+        }
+        let hex = |(r, g, b): Rgb| format!("#{:02x}{:02x}{:02x}", r, g, b);
+        write!(
+            f,
+            "stroke={},fill={},text={},line={}",
+            hex(self.stroke),
+            self.fill.map_or("none".to_string(), hex),
+            hex(self.text),
+            hex(self.line)
+        )
+    }
+}
+
+impl std::str::FromStr for Theme {
+    type Err = String;
+
+    /// Parse a theme name or `key=color` pairs over the default theme
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(theme) = Theme::named(s.trim()) {
+            return Ok(theme);
+        }
+        if !s.contains('=') {
+            return Err(format!(
+                "Unknown theme '{}'. Use {}, or key=color pairs (stroke, fill, text, line)",
+                s,
+                Theme::NAMES.join(", ")
+            ));
+        }
+
+        let mut theme = Theme::DEFAULT;
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("Expected key=color in theme, got '{}'", pair))?;
+            let (key, value) = (key.trim(), value.trim());
+            let rgb = || {
+                Color::parse(value)
+                    .and_then(|color| color.to_rgb())
+                    .ok_or_else(|| format!("Unknown color '{}' for theme {}", value, key))
+            };
+            match key {
+                "stroke" => theme.stroke = rgb()?,
+                "fill" if value == "none" => theme.fill = None,
+                "fill" => theme.fill = Some(rgb()?),
+                "text" => theme.text = rgb()?,
+                "line" => theme.line = rgb()?,
+                _ => {
+                    return Err(format!(
+                        "Unknown theme key '{}'. Use stroke, fill, text, or line",
+                        key
+                    ))
+                }
+            }
+        }
+        Ok(theme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_themes_round_trip() {
+        for name in Theme::NAMES {
+            let theme: Theme = name.parse().unwrap();
+            assert_eq!(theme.to_string(), name);
+        }
+        assert_eq!(Theme::named("Base"), Some(Theme::DEFAULT));
+        assert!("solarized".parse::<Theme>().is_err());
+    }
+
+    #[test]
+    fn test_custom_theme_overrides_the_default() {
+        let theme: Theme = "stroke=#f80, fill=none, line=red".parse().unwrap();
+        assert_eq!(theme.stroke, (0xff, 0x88, 0x00));
+        assert_eq!(theme.fill, None);
+        assert_eq!(theme.text, Theme::DEFAULT.text);
+        assert_eq!(theme.line, (0xff, 0x00, 0x00));
+        assert_eq!(theme.to_string().parse::<Theme>(), Ok(theme));
+
+        assert!("stroke=#zzz".parse::<Theme>().is_err());
+        assert!("border=#fff".parse::<Theme>().is_err());
+    }
+}
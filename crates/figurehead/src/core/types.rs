@@ -3,6 +3,7 @@
 //! This module contains the fundamental types used throughout Figurehead:
 //! node shapes, edge types, flow direction, and data structures.
 
+use super::{EdgeTerminator, Theme};
 use std::fmt;
 
 /// Character set for rendering output
@@ -26,6 +27,14 @@ pub enum CharacterSet {
 }
 
 impl CharacterSet {
+    /// Every character set, in order of increasing glyph requirements
+    pub const ALL: [CharacterSet; 4] = [
+        CharacterSet::Ascii,
+        CharacterSet::Unicode,
+        CharacterSet::UnicodeMath,
+        CharacterSet::Compact,
+    ];
+
     /// Returns true if this character set uses only ASCII
     pub fn is_ascii(&self) -> bool {
         matches!(self, CharacterSet::Ascii)
@@ -78,6 +87,10 @@ pub enum DiamondStyle {
 }
 
 impl DiamondStyle {
+    /// Every diamond style
+    pub const ALL: [DiamondStyle; 3] =
+        [DiamondStyle::Tall, DiamondStyle::Box, DiamondStyle::Inline];
+
     /// Returns the height in rows needed for this style
     pub fn height(&self, label_lines: usize) -> usize {
         match self {
@@ -131,6 +144,391 @@ impl std::str::FromStr for DiamondStyle {
     }
 }
 
+/// Strategy for placing edge labels
+///
+/// Controls where labels are drawn relative to the edge they annotate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum LabelPlacement {
+    /// Place labels on or above the segment nearest the target
+    #[default]
+    Auto,
+    /// In top-down layouts, place labels to the right of a vertical segment
+    /// with a connector tick, keeping them clear of the upper node:
+    /// ```text
+    ///     │
+    ///     ├ yes
+    ///     ▼
+    /// ```
+    Beside,
+}
+
+impl LabelPlacement {
+    /// Every label placement strategy
+    pub const ALL: [LabelPlacement; 2] = [LabelPlacement::Auto, LabelPlacement::Beside];
+}
+
+impl fmt::Display for LabelPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabelPlacement::Auto => write!(f, "auto"),
+            LabelPlacement::Beside => write!(f, "beside"),
+        }
+    }
+}
+
+impl std::str::FromStr for LabelPlacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(LabelPlacement::Auto),
+            "beside" => Ok(LabelPlacement::Beside),
+            _ => Err(format!(
+                "Unknown label placement '{}'. Use 'auto' or 'beside'",
+                s
+            )),
+        }
+    }
+}
+
+/// How identical labels on the branches of a split are drawn
+///
+/// `A -->|ok| B & C` gives both edges the label `ok`; when they leave their
+/// source through a shared junction the label can be drawn once on the
+/// trunk or once per branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum SplitLabels {
+    /// Draw the label once, beside the trunk before the junction
+    #[default]
+    Shared,
+    /// Draw the label on every branch
+    PerBranch,
+}
+
+impl SplitLabels {
+    /// Every split label mode
+    pub const ALL: [SplitLabels; 2] = [SplitLabels::Shared, SplitLabels::PerBranch];
+}
+
+impl fmt::Display for SplitLabels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SplitLabels::Shared => write!(f, "shared"),
+            SplitLabels::PerBranch => write!(f, "per-branch"),
+        }
+    }
+}
+
+impl std::str::FromStr for SplitLabels {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "shared" => Ok(SplitLabels::Shared),
+            "per-branch" => Ok(SplitLabels::PerBranch),
+            _ => Err(format!(
+                "Unknown split label mode '{}'. Use 'shared' or 'per-branch'",
+                s
+            )),
+        }
+    }
+}
+
+/// How flowchart edges between offset nodes are drawn (Mermaid's `curve`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum EdgeCurve {
+    /// Orthogonal routes that turn at right angles (`step`)
+    #[default]
+    Step,
+    /// Short hops between offset nodes cut across with `/` and `\`
+    /// (`linear`); longer ones are routed as for `step`
+    Linear,
+}
+
+impl EdgeCurve {
+    /// Every edge curve
+    pub const ALL: [EdgeCurve; 2] = [EdgeCurve::Step, EdgeCurve::Linear];
+}
+
+impl fmt::Display for EdgeCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeCurve::Step => write!(f, "step"),
+            EdgeCurve::Linear => write!(f, "linear"),
+        }
+    }
+}
+
+impl std::str::FromStr for EdgeCurve {
+    type Err = String;
+
+    /// Parse a Mermaid curve name; `stepBefore` and `stepAfter` are steps
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "step" | "stepbefore" | "stepafter" => Ok(EdgeCurve::Step),
+            "linear" => Ok(EdgeCurve::Linear),
+            _ => Err(format!(
+                "Unknown edge curve '{}'. Use 'step' or 'linear'",
+                s
+            )),
+        }
+    }
+}
+
+/// Weight of the lines drawing flowchart nodes and edges
+///
+/// Lets a whole diagram stand out, e.g. a summary drawn heavy next to
+/// detail diagrams drawn light. Subgraph borders and thick edges change
+/// with it so they stay distinct from nodes and plain edges. Has no effect
+/// on ASCII and compact output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum LineWeight {
+    /// Thin lines: `┌─┐ │ └─┘`
+    #[default]
+    Light,
+    /// Heavy lines: `┏━┓ ┃ ┗━┛`; rounded corners become square
+    Heavy,
+    /// Double lines: `╔═╗ ║ ╚═╝`; subgraph borders and thick edges, double
+    /// by default, are drawn heavy instead
+    Double,
+}
+
+impl LineWeight {
+    /// Every line weight
+    pub const ALL: [LineWeight; 3] = [LineWeight::Light, LineWeight::Heavy, LineWeight::Double];
+}
+
+impl fmt::Display for LineWeight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineWeight::Light => write!(f, "light"),
+            LineWeight::Heavy => write!(f, "heavy"),
+            LineWeight::Double => write!(f, "double"),
+        }
+    }
+}
+
+impl std::str::FromStr for LineWeight {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "light" => Ok(LineWeight::Light),
+            "heavy" => Ok(LineWeight::Heavy),
+            "double" => Ok(LineWeight::Double),
+            _ => Err(format!(
+                "Unknown line weight '{}'. Use 'light', 'heavy', or 'double'",
+                s
+            )),
+        }
+    }
+}
+
+/// Canvas used to rasterize shapes and edges
+///
+/// Text labels are always drawn as normal characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum CanvasMode {
+    /// One glyph per cell using the selected character set
+    #[default]
+    Text,
+    /// Experimental: 2x4 braille dots per cell for smooth diagonals.
+    /// Requires a font with Unicode braille patterns (U+2800).
+    Braille,
+}
+
+impl CanvasMode {
+    /// Every canvas mode
+    pub const ALL: [CanvasMode; 2] = [CanvasMode::Text, CanvasMode::Braille];
+}
+
+impl fmt::Display for CanvasMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanvasMode::Text => write!(f, "text"),
+            CanvasMode::Braille => write!(f, "braille"),
+        }
+    }
+}
+
+impl std::str::FromStr for CanvasMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(CanvasMode::Text),
+            "braille" => Ok(CanvasMode::Braille),
+            _ => Err(format!(
+                "Unknown canvas mode '{}'. Use 'text' or 'braille'",
+                s
+            )),
+        }
+    }
+}
+
+/// What to do when a diagram would be drawn larger than its [`CanvasLimit`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum OverflowPolicy {
+    /// Draw the full diagram regardless of size
+    Render,
+    /// Redraw flowcharts with short wrapped labels, wrapped layers and the
+    /// compact character set; summarize if that is still too large
+    #[default]
+    Compact,
+    /// Replace the drawing with a one-line notice giving its size
+    Summary,
+    /// Fail with an error
+    Error,
+}
+
+impl OverflowPolicy {
+    /// Every overflow policy
+    pub const ALL: [OverflowPolicy; 4] = [
+        OverflowPolicy::Render,
+        OverflowPolicy::Compact,
+        OverflowPolicy::Summary,
+        OverflowPolicy::Error,
+    ];
+}
+
+impl fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverflowPolicy::Render => write!(f, "render"),
+            OverflowPolicy::Compact => write!(f, "compact"),
+            OverflowPolicy::Summary => write!(f, "summary"),
+            OverflowPolicy::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl std::str::FromStr for OverflowPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "render" => Ok(OverflowPolicy::Render),
+            "compact" => Ok(OverflowPolicy::Compact),
+            "summary" => Ok(OverflowPolicy::Summary),
+            "error" => Ok(OverflowPolicy::Error),
+            _ => Err(format!(
+                "Unknown overflow policy '{}'. Use 'render', 'compact', 'summary' or 'error'",
+                s
+            )),
+        }
+    }
+}
+
+/// Largest canvas drawn before the [`OverflowPolicy`] applies, in cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CanvasLimit {
+    /// Maximum width in columns
+    pub width: usize,
+    /// Maximum height in rows
+    pub height: usize,
+}
+
+impl CanvasLimit {
+    /// Create a limit of `width` columns by `height` rows
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height }
+    }
+
+    /// Whether a canvas of this size is over the limit
+    pub fn exceeded_by(&self, width: usize, height: usize) -> bool {
+        width > self.width || height > self.height
+    }
+
+    /// One-line notice standing in for a diagram that is over the limit
+    pub fn notice(&self, diagram_type: &str, width: usize, height: usize) -> String {
+        format!(
+            "[{} too large to draw: {}x{} cells, limit {}x{}]",
+            diagram_type, width, height, self.width, self.height
+        )
+    }
+}
+
+impl Default for CanvasLimit {
+    fn default() -> Self {
+        Self::new(500, 500)
+    }
+}
+
+impl fmt::Display for CanvasLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+impl std::str::FromStr for CanvasLimit {
+    type Err = String;
+
+    /// Parse `WIDTHxHEIGHT`, e.g. `500x500`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = s
+            .to_lowercase()
+            .split_once('x')
+            .and_then(|(w, h)| Some(Self::new(w.trim().parse().ok()?, h.trim().parse().ok()?)));
+        parsed.ok_or_else(|| {
+            format!(
+                "Invalid canvas limit '{}'. Use WIDTHxHEIGHT, e.g. 500x500",
+                s
+            )
+        })
+    }
+}
+
+/// Target proportions for a drawing, as width to height (`2:1` for slides)
+///
+/// Proportions are those seen on screen: a terminal cell is taken to be
+/// twice as tall as it is wide, so a 2:1 drawing has four columns per row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AspectRatio {
+    /// Width part of the ratio
+    pub width: u32,
+    /// Height part of the ratio
+    pub height: u32,
+}
+
+impl AspectRatio {
+    /// Displayed height of a cell, in cell widths
+    pub const CELL_ASPECT: f64 = 2.0;
+
+    /// Create a ratio of `width` to `height`
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    /// How far a canvas of `columns` by `rows` cells is from this ratio
+    ///
+    /// 0.0 is a perfect match; a drawing twice too wide scores the same as
+    /// one twice too tall.
+    pub fn distance(&self, columns: usize, rows: usize) -> f64 {
+        let shown = columns.max(1) as f64 / (rows.max(1) as f64 * Self::CELL_ASPECT);
+        let target = self.width as f64 / self.height as f64;
+        (shown / target).ln().abs()
+    }
+}
+
+impl fmt::Display for AspectRatio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.width, self.height)
+    }
+}
+
+impl std::str::FromStr for AspectRatio {
+    type Err = String;
+
+    /// Parse `WIDTH:HEIGHT`, e.g. `16:9`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parsed = s
+            .split_once(':')
+            .and_then(|(w, h)| Some(Self::new(w.trim().parse().ok()?, h.trim().parse().ok()?)))
+            .filter(|ratio| ratio.width > 0 && ratio.height > 0);
+        parsed.ok_or_else(|| format!("Invalid aspect ratio '{}'. Use WIDTH:HEIGHT, e.g. 2:1", s))
+    }
+}
+
 /// Configuration for rendering output
 ///
 /// Combines all rendering options into a single struct for cleaner APIs.
@@ -142,6 +540,39 @@ pub struct RenderConfig {
     pub diamond_style: DiamondStyle,
     /// Enable color output (requires terminal support)
     pub color: bool,
+    /// Colors for flowchart nodes and edges without a `classDef` or
+    /// `style`, when color is on (`None` = leave them uncolored)
+    pub theme: Option<Theme>,
+    /// Where edge labels are placed
+    pub label_placement: LabelPlacement,
+    /// Whether a label shared by every branch of a split is drawn once
+    pub split_labels: SplitLabels,
+    /// Canvas used for shapes and edges
+    pub canvas_mode: CanvasMode,
+    /// Draw edge turns with rounded corners (`╭ ╮ ╰ ╯`) instead of sharp ones
+    pub rounded_edges: bool,
+    /// How edges between offset flowchart nodes are drawn
+    pub edge_curve: EdgeCurve,
+    /// Weight of the lines drawing flowchart nodes and edges
+    pub line_weight: LineWeight,
+    /// Draw BT and RL flowcharts by mirroring a TD or LR rendering
+    pub mirror_reversed: bool,
+    /// What to do when a diagram would be larger than `overflow_limit`
+    pub overflow_policy: OverflowPolicy,
+    /// Largest canvas drawn as-is
+    pub overflow_limit: CanvasLimit,
+    /// Widest output in columns, e.g. the terminal width; flowcharts over it
+    /// are re-laid out narrower (`None` = no limit)
+    pub max_width: Option<usize>,
+    /// Proportions flowcharts are laid out to approach, by wrapping wide
+    /// layers onto extra rows (`None` = lay out as usual)
+    pub aspect_hint: Option<AspectRatio>,
+    /// Mark linked flowchart nodes with `[n]` and list their links below the
+    /// diagram
+    pub link_footnotes: bool,
+    /// Frame flowcharts with their canvas bounds and mark node layers, IDs
+    /// and junctions, for diagnosing layout issues
+    pub debug_overlay: bool,
 }
 
 /// A color value parsed from Mermaid style syntax
@@ -328,6 +759,20 @@ impl RenderConfig {
             style,
             diamond_style,
             color: false,
+            theme: None,
+            label_placement: LabelPlacement::Auto,
+            split_labels: SplitLabels::Shared,
+            canvas_mode: CanvasMode::Text,
+            rounded_edges: false,
+            edge_curve: EdgeCurve::Step,
+            line_weight: LineWeight::Light,
+            mirror_reversed: false,
+            overflow_policy: OverflowPolicy::Compact,
+            overflow_limit: CanvasLimit::default(),
+            max_width: None,
+            aspect_hint: None,
+            link_footnotes: false,
+            debug_overlay: false,
         }
     }
 
@@ -336,6 +781,90 @@ impl RenderConfig {
         self.color = color;
         self
     }
+
+    /// Create a config that colors unstyled flowchart nodes and edges with a theme
+    pub fn with_theme(mut self, theme: Option<Theme>) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Create a config with the given edge label placement strategy
+    pub fn with_label_placement(mut self, placement: LabelPlacement) -> Self {
+        self.label_placement = placement;
+        self
+    }
+
+    /// Create a config that draws labels shared by a split once or per branch
+    pub fn with_split_labels(mut self, split_labels: SplitLabels) -> Self {
+        self.split_labels = split_labels;
+        self
+    }
+
+    /// Create a config with the given canvas mode
+    pub fn with_canvas_mode(mut self, mode: CanvasMode) -> Self {
+        self.canvas_mode = mode;
+        self
+    }
+
+    /// Create a config with rounded or sharp edge corners
+    pub fn with_rounded_edges(mut self, rounded: bool) -> Self {
+        self.rounded_edges = rounded;
+        self
+    }
+
+    /// Create a config that draws edges between offset nodes with the given curve
+    pub fn with_edge_curve(mut self, curve: EdgeCurve) -> Self {
+        self.edge_curve = curve;
+        self
+    }
+
+    /// Create a config that draws flowchart nodes and edges with the given line weight
+    pub fn with_line_weight(mut self, weight: LineWeight) -> Self {
+        self.line_weight = weight;
+        self
+    }
+
+    /// Create a config with the given policy for diagrams over the canvas limit
+    pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Create a config with the given canvas limit
+    pub fn with_overflow_limit(mut self, limit: CanvasLimit) -> Self {
+        self.overflow_limit = limit;
+        self
+    }
+
+    /// Create a config that fits flowcharts into `max_width` columns
+    pub fn with_max_width(mut self, max_width: Option<usize>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Create a config that lays flowcharts out close to the given proportions
+    pub fn with_aspect_hint(mut self, aspect: Option<AspectRatio>) -> Self {
+        self.aspect_hint = aspect;
+        self
+    }
+
+    /// Create a config that lists flowchart links as footnotes
+    pub fn with_link_footnotes(mut self, footnotes: bool) -> Self {
+        self.link_footnotes = footnotes;
+        self
+    }
+
+    /// Create a config that draws the flowchart layout debug overlay
+    pub fn with_debug_overlay(mut self, overlay: bool) -> Self {
+        self.debug_overlay = overlay;
+        self
+    }
+
+    /// Create a config that draws BT/RL flowcharts by mirroring TD/LR output
+    pub fn with_mirror_reversed(mut self, mirror: bool) -> Self {
+        self.mirror_reversed = mirror;
+        self
+    }
 }
 
 /// Node shapes matching Mermaid.js syntax
@@ -344,10 +873,14 @@ pub enum NodeShape {
     /// Rectangle: `A[label]`
     #[default]
     Rectangle,
-    /// Rounded rectangle (stadium): `A(label)`
+    /// Rounded rectangle: `A(label)`
     RoundedRect,
+    /// Stadium (pill): `A([label])`
+    Stadium,
     /// Circle: `A((label))`
     Circle,
+    /// Double circle: `A(((label)))`
+    DoubleCircle,
     /// Diamond (decision): `A{label}`
     Diamond,
     /// Hexagon: `A{{label}}`
@@ -360,25 +893,75 @@ pub enum NodeShape {
     Asymmetric,
     /// Parallelogram: `A[/label/]`
     Parallelogram,
+    /// Parallelogram leaning the other way: `A[\label\]`
+    ParallelogramAlt,
     /// Trapezoid: `A[/label\]`
     Trapezoid,
+    /// Trapezoid with the wide side on top: `A[\label/]`
+    InvTrapezoid,
     /// Terminal state: `[*]` in state diagrams (start/end)
     Terminal,
 }
 
+impl NodeShape {
+    /// Every node shape
+    pub const ALL: [NodeShape; 15] = [
+        NodeShape::Rectangle,
+        NodeShape::RoundedRect,
+        NodeShape::Stadium,
+        NodeShape::Circle,
+        NodeShape::DoubleCircle,
+        NodeShape::Diamond,
+        NodeShape::Hexagon,
+        NodeShape::Subroutine,
+        NodeShape::Cylinder,
+        NodeShape::Asymmetric,
+        NodeShape::Parallelogram,
+        NodeShape::ParallelogramAlt,
+        NodeShape::Trapezoid,
+        NodeShape::InvTrapezoid,
+        NodeShape::Terminal,
+    ];
+
+    /// Mermaid syntax that produces this shape
+    pub fn syntax(&self) -> &'static str {
+        match self {
+            NodeShape::Rectangle => "A[label]",
+            NodeShape::RoundedRect => "A(label)",
+            NodeShape::Stadium => "A([label])",
+            NodeShape::Circle => "A((label))",
+            NodeShape::DoubleCircle => "A(((label)))",
+            NodeShape::Diamond => "A{label}",
+            NodeShape::Hexagon => "A{{label}}",
+            NodeShape::Subroutine => "A[[label]]",
+            NodeShape::Cylinder => "A[(label)]",
+            NodeShape::Asymmetric => "A>label]",
+            NodeShape::Parallelogram => "A[/label/]",
+            NodeShape::ParallelogramAlt => "A[\\label\\]",
+            NodeShape::Trapezoid => "A[/label\\]",
+            NodeShape::InvTrapezoid => "A[\\label/]",
+            NodeShape::Terminal => "[*]",
+        }
+    }
+}
+
 impl fmt::Display for NodeShape {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             NodeShape::Rectangle => write!(f, "rectangle"),
             NodeShape::RoundedRect => write!(f, "rounded"),
+            NodeShape::Stadium => write!(f, "stadium"),
             NodeShape::Circle => write!(f, "circle"),
+            NodeShape::DoubleCircle => write!(f, "double-circle"),
             NodeShape::Diamond => write!(f, "diamond"),
             NodeShape::Hexagon => write!(f, "hexagon"),
             NodeShape::Subroutine => write!(f, "subroutine"),
             NodeShape::Cylinder => write!(f, "cylinder"),
             NodeShape::Asymmetric => write!(f, "asymmetric"),
             NodeShape::Parallelogram => write!(f, "parallelogram"),
+            NodeShape::ParallelogramAlt => write!(f, "parallelogram-alt"),
             NodeShape::Trapezoid => write!(f, "trapezoid"),
+            NodeShape::InvTrapezoid => write!(f, "inv-trapezoid"),
             NodeShape::Terminal => write!(f, "terminal"),
         }
     }
@@ -409,6 +992,19 @@ pub enum EdgeType {
 }
 
 impl EdgeType {
+    /// Every edge type
+    pub const ALL: [EdgeType; 9] = [
+        EdgeType::Arrow,
+        EdgeType::Line,
+        EdgeType::DottedArrow,
+        EdgeType::DottedLine,
+        EdgeType::ThickArrow,
+        EdgeType::ThickLine,
+        EdgeType::Invisible,
+        EdgeType::OpenArrow,
+        EdgeType::CrossArrow,
+    ];
+
     /// Returns true if this edge type has an arrowhead
     pub fn has_arrow(&self) -> bool {
         matches!(
@@ -517,6 +1113,33 @@ pub struct NodeData {
     pub classes: Vec<String>,
     /// Inline style (from `style nodeId ...` statement)
     pub inline_style: Option<StyleDefinition>,
+    /// Hyperlink (from `click nodeId "url"` statement)
+    pub link: Option<NodeLink>,
+}
+
+/// Hyperlink attached to a node by a flowchart `click` statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeLink {
+    /// Link target as written
+    pub url: String,
+    /// Tooltip text, if one was given
+    pub tooltip: Option<String>,
+}
+
+impl NodeLink {
+    /// Create a link without a tooltip
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            tooltip: None,
+        }
+    }
+
+    /// Set the tooltip shown for the link
+    pub fn with_tooltip(mut self, tooltip: impl Into<String>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
 }
 
 impl NodeData {
@@ -528,6 +1151,7 @@ impl NodeData {
             shape: NodeShape::Rectangle,
             classes: Vec::new(),
             inline_style: None,
+            link: None,
         }
     }
 
@@ -539,6 +1163,7 @@ impl NodeData {
             shape,
             classes: Vec::new(),
             inline_style: None,
+            link: None,
         }
     }
 
@@ -554,6 +1179,11 @@ impl NodeData {
     pub fn set_style(&mut self, style: StyleDefinition) {
         self.inline_style = Some(style);
     }
+
+    /// Set the hyperlink for this node
+    pub fn set_link(&mut self, link: NodeLink) {
+        self.link = Some(link);
+    }
 }
 
 /// An edge connecting two nodes with metadata
@@ -569,6 +1199,12 @@ pub struct EdgeData {
     pub label: Option<String>,
     /// Style for this edge (from `linkStyle` statement)
     pub style: Option<StyleDefinition>,
+    /// Fewest layers the edge spans; extra dashes (`A ----> B`) raise it
+    /// above 1 to push the target further down the flow
+    pub min_length: usize,
+    /// Marker at the source end, as in `A <--> B` or `A o--o B`; the
+    /// target end's marker follows `edge_type`
+    pub start: EdgeTerminator,
 }
 
 impl EdgeData {
@@ -580,6 +1216,8 @@ impl EdgeData {
             edge_type: EdgeType::Arrow,
             label: None,
             style: None,
+            min_length: 1,
+            start: EdgeTerminator::None,
         }
     }
 
@@ -591,6 +1229,8 @@ impl EdgeData {
             edge_type,
             label: None,
             style: None,
+            min_length: 1,
+            start: EdgeTerminator::None,
         }
     }
 
@@ -607,9 +1247,23 @@ impl EdgeData {
             edge_type,
             label: Some(label.into()),
             style: None,
+            min_length: 1,
+            start: EdgeTerminator::None,
         }
     }
 
+    /// Mark the source end as well, making the edge bidirectional
+    pub fn with_start(mut self, start: EdgeTerminator) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Require at least `min_length` layers between the two ends
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length.max(1);
+        self
+    }
+
     /// Set style for this edge
     pub fn set_style(&mut self, style: StyleDefinition) {
         self.style = Some(style);
@@ -709,6 +1363,98 @@ mod tests {
         assert_eq!(CharacterSet::Compact.to_string(), "compact");
     }
 
+    #[test]
+    fn test_label_placement_parsing() {
+        assert_eq!(LabelPlacement::default(), LabelPlacement::Auto);
+        assert_eq!(
+            "beside".parse::<LabelPlacement>(),
+            Ok(LabelPlacement::Beside)
+        );
+        assert_eq!("AUTO".parse::<LabelPlacement>(), Ok(LabelPlacement::Auto));
+        assert!("above".parse::<LabelPlacement>().is_err());
+        assert_eq!(LabelPlacement::Beside.to_string(), "beside");
+    }
+
+    #[test]
+    fn test_split_labels_parsing() {
+        assert_eq!(SplitLabels::default(), SplitLabels::Shared);
+        assert_eq!(
+            "per-branch".parse::<SplitLabels>(),
+            Ok(SplitLabels::PerBranch)
+        );
+        assert!("both".parse::<SplitLabels>().is_err());
+        assert_eq!(SplitLabels::PerBranch.to_string(), "per-branch");
+    }
+
+    #[test]
+    fn test_overflow_options_parsing() {
+        assert_eq!(OverflowPolicy::default(), OverflowPolicy::Compact);
+        assert_eq!(
+            "summary".parse::<OverflowPolicy>(),
+            Ok(OverflowPolicy::Summary)
+        );
+        assert!("shrink".parse::<OverflowPolicy>().is_err());
+
+        assert_eq!(CanvasLimit::default(), CanvasLimit::new(500, 500));
+        assert_eq!(
+            "120X40".parse::<CanvasLimit>(),
+            Ok(CanvasLimit::new(120, 40))
+        );
+        assert!("120".parse::<CanvasLimit>().is_err());
+        assert!(CanvasLimit::new(120, 40).exceeded_by(121, 10));
+        assert!(!CanvasLimit::new(120, 40).exceeded_by(120, 40));
+    }
+
+    #[test]
+    fn test_aspect_ratio_parsing_and_distance() {
+        assert_eq!("16:9".parse::<AspectRatio>(), Ok(AspectRatio::new(16, 9)));
+        assert_eq!(AspectRatio::new(2, 1).to_string(), "2:1");
+        assert!("2x1".parse::<AspectRatio>().is_err());
+        assert!("2:0".parse::<AspectRatio>().is_err());
+
+        // Four columns per row look 2:1 on screen
+        let slides = AspectRatio::new(2, 1);
+        assert_eq!(slides.distance(40, 10), 0.0);
+        assert!((slides.distance(80, 10) - slides.distance(20, 10)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_edge_curve_parsing() {
+        assert_eq!(EdgeCurve::default(), EdgeCurve::Step);
+        assert_eq!("linear".parse::<EdgeCurve>(), Ok(EdgeCurve::Linear));
+        assert_eq!("stepAfter".parse::<EdgeCurve>(), Ok(EdgeCurve::Step));
+        assert!("basis".parse::<EdgeCurve>().is_err());
+        assert_eq!(EdgeCurve::Linear.to_string(), "linear");
+    }
+
+    #[test]
+    fn test_line_weight_parsing() {
+        assert_eq!(LineWeight::default(), LineWeight::Light);
+        assert_eq!("Heavy".parse::<LineWeight>(), Ok(LineWeight::Heavy));
+        assert!("bold".parse::<LineWeight>().is_err());
+        assert_eq!(LineWeight::Double.to_string(), "double");
+        assert_eq!(
+            RenderConfig::default()
+                .with_line_weight(LineWeight::Heavy)
+                .line_weight,
+            LineWeight::Heavy
+        );
+    }
+
+    #[test]
+    fn test_canvas_mode_parsing() {
+        assert_eq!(CanvasMode::default(), CanvasMode::Text);
+        assert_eq!("Braille".parse::<CanvasMode>(), Ok(CanvasMode::Braille));
+        assert!("dots".parse::<CanvasMode>().is_err());
+        assert_eq!(CanvasMode::Braille.to_string(), "braille");
+        assert_eq!(
+            RenderConfig::default()
+                .with_canvas_mode(CanvasMode::Braille)
+                .canvas_mode,
+            CanvasMode::Braille
+        );
+    }
+
     #[test]
     fn test_node_shape_display() {
         assert_eq!(NodeShape::Rectangle.to_string(), "rectangle");
@@ -721,6 +1467,10 @@ mod tests {
         assert_eq!(NodeShape::Asymmetric.to_string(), "asymmetric");
         assert_eq!(NodeShape::Parallelogram.to_string(), "parallelogram");
         assert_eq!(NodeShape::Trapezoid.to_string(), "trapezoid");
+        assert_eq!(NodeShape::Stadium.to_string(), "stadium");
+        assert_eq!(NodeShape::DoubleCircle.to_string(), "double-circle");
+        assert_eq!(NodeShape::ParallelogramAlt.to_string(), "parallelogram-alt");
+        assert_eq!(NodeShape::InvTrapezoid.to_string(), "inv-trapezoid");
         assert_eq!(NodeShape::Terminal.to_string(), "terminal");
     }
 
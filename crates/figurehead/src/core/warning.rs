@@ -0,0 +1,203 @@
+//! Parse warnings and lenient statement parsing
+//!
+//! Diagram parsers read their input one statement at a time through
+//! [`LenientStatements`]: a statement that cannot be read becomes a located
+//! [`ParseWarning`] instead of failing the parse, so one bad line in a long
+//! diagram only loses that line. The parse still fails when nothing at all
+//! could be read.
+
+use std::fmt;
+
+use anyhow::Result;
+use tracing::warn;
+
+use super::{FigureheadError, SpannedStatement};
+
+/// A parse warning, located in the input when possible
+///
+/// Editors can use the position to underline the offending statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// Human-readable description
+    pub message: String,
+    /// 1-based line of the statement, if the warning points into the input
+    pub line: Option<usize>,
+    /// 1-based column (in characters) where the statement starts
+    pub column: Option<usize>,
+    /// Text of the statement that caused the warning
+    pub statement: Option<String>,
+}
+
+impl ParseWarning {
+    /// Create a warning that is not tied to a position in the input
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            line: None,
+            column: None,
+            statement: None,
+        }
+    }
+
+    /// Attach the position of the offending statement
+    pub fn at(mut self, line: usize, column: usize) -> Self {
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+
+    /// Attach the text of the offending statement
+    pub fn with_statement(mut self, statement: impl Into<String>) -> Self {
+        self.statement = Some(statement.into());
+        self
+    }
+
+    /// Warning pointing at a statement of the input
+    pub(crate) fn for_statement(statement: &SpannedStatement, message: impl Into<String>) -> Self {
+        Self::new(message)
+            .at(statement.line, statement.column)
+            .with_statement(statement.text.clone())
+    }
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{}:{}: {}", line, column, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Bookkeeping for parsing statements one at a time, skipping bad ones
+///
+/// Parsers report each statement as read ([`accept`](Self::accept)) or not
+/// ([`reject`](Self::reject)), and [`finish`](Self::finish) hands back the
+/// warnings to record on the database.
+#[derive(Debug)]
+pub(crate) struct LenientStatements {
+    diagram_type: &'static str,
+    warnings: Vec<ParseWarning>,
+    accepted: usize,
+    first_rejected: Option<SpannedStatement>,
+}
+
+impl LenientStatements {
+    /// Bookkeeping for a parser of `diagram_type`, named in its errors
+    pub(crate) fn new(diagram_type: &'static str) -> Self {
+        Self {
+            diagram_type,
+            warnings: Vec::new(),
+            accepted: 0,
+            first_rejected: None,
+        }
+    }
+
+    /// Record a statement that was read
+    pub(crate) fn accept(&mut self) {
+        self.accepted += 1;
+    }
+
+    /// Record a statement that could not be read, and skip it
+    pub(crate) fn reject(&mut self, statement: &SpannedStatement, error: impl fmt::Display) {
+        warn!(
+            line = statement.line,
+            column = statement.column,
+            statement = %statement.text,
+            %error,
+            "Skipped invalid statement"
+        );
+        self.warnings.push(ParseWarning::for_statement(
+            statement,
+            format!("Skipped invalid statement '{}': {}", statement.text, error),
+        ));
+        self.first_rejected.get_or_insert_with(|| statement.clone());
+    }
+
+    /// Read a statement with `parse`, skipping it if that fails
+    pub(crate) fn parse<T>(
+        &mut self,
+        statement: &SpannedStatement,
+        parse: impl FnOnce(&str) -> Result<T>,
+    ) -> Option<T> {
+        match parse(&statement.text) {
+            Ok(parsed) => {
+                self.accept();
+                Some(parsed)
+            }
+            Err(error) => {
+                self.reject(statement, error);
+                None
+            }
+        }
+    }
+
+    /// Record a warning about a statement that was read but had no effect
+    pub(crate) fn warn(&mut self, statement: &SpannedStatement, message: impl Into<String>) {
+        let warning = ParseWarning::for_statement(statement, message);
+        warn!(%warning, "Statement ignored");
+        self.warnings.push(warning);
+    }
+
+    /// The warnings, or a [`FigureheadError::Parse`] pointing at the first
+    /// bad statement when no statement could be read at all
+    pub(crate) fn finish(self) -> Result<Vec<ParseWarning>> {
+        match self.first_rejected {
+            Some(statement) if self.accepted == 0 => Err(FigureheadError::parse(
+                self.diagram_type,
+                statement.line,
+                statement.column,
+                &statement.text,
+                format!("unrecognized statement '{}'", statement.text),
+            )
+            .into()),
+            _ => Ok(self.warnings),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{SourceSpan, StatementSplitter};
+
+    #[test]
+    fn test_bad_statements_become_warnings_until_nothing_is_read() {
+        let statements = StatementSplitter::new().split("good\n  bad one\ngood");
+        let mut lenient = LenientStatements::new("test");
+        for statement in &statements {
+            lenient.parse(statement, |text| match text {
+                "good" => Ok(()),
+                _ => Err(anyhow::anyhow!("not good")),
+            });
+        }
+        let warnings = lenient.finish().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].to_string(),
+            "2:3: Skipped invalid statement 'bad one': not good"
+        );
+
+        let mut lenient = LenientStatements::new("test");
+        lenient.reject(&statements[1], "not good");
+        let error = lenient.finish().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Parse error at line 2, column 3: unrecognized statement 'bad one'"
+        );
+        match error.downcast::<FigureheadError>().unwrap() {
+            FigureheadError::Parse {
+                diagram_type,
+                span,
+                statement,
+                ..
+            } => {
+                assert_eq!(diagram_type, "test");
+                assert_eq!(span, SourceSpan::new(2, 3, 7));
+                assert_eq!(statement, "bad one");
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(LenientStatements::new("test").finish().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,425 @@
+//! Documents holding several diagrams
+//!
+//! [`parse_document`] finds every diagram in one input and parses each with
+//! the plugin for its detected type. Markdown is read for its fenced
+//! ` ```mermaid ` blocks; any other text is split wherever a diagram header
+//! (`graph`, `sequenceDiagram`, ...) starts a line, so concatenated `.mmd`
+//! files work too. [`render_markdown`] draws those blocks in place, turning
+//! a README into one that reads well as plain text.
+
+use crate::capabilities::capabilities;
+use crate::core::{Parser, RenderConfig};
+use crate::plugins::class::{ClassDatabase, ClassParser};
+use crate::plugins::er::{ErDatabase, ErParser};
+use crate::plugins::flowchart::{FlowchartDatabase, FlowchartParser};
+use crate::plugins::gitgraph::{GitGraphDatabase, GitGraphParser};
+use crate::plugins::sequence::{SequenceDatabase, SequenceParser};
+use crate::plugins::state::{StateDatabase, StateParser};
+use crate::plugins::Orchestrator;
+use anyhow::Result;
+use tracing::warn;
+
+/// A diagram parsed by the plugin for its type
+pub enum ParsedDiagram {
+    /// A `graph`/`flowchart` diagram
+    Flowchart(FlowchartDatabase),
+    /// A `sequenceDiagram`
+    Sequence(SequenceDatabase),
+    /// A `gitGraph`
+    GitGraph(GitGraphDatabase),
+    /// A `classDiagram`
+    Class(ClassDatabase),
+    /// A `stateDiagram`
+    State(StateDatabase),
+    /// An `erDiagram`
+    Er(ErDatabase),
+}
+
+impl ParsedDiagram {
+    /// Diagram type, as named by `figurehead detect`
+    pub fn diagram_type(&self) -> &'static str {
+        match self {
+            Self::Flowchart(_) => "flowchart",
+            Self::Sequence(_) => "sequence",
+            Self::GitGraph(_) => "gitgraph",
+            Self::Class(_) => "class",
+            Self::State(_) => "state",
+            Self::Er(_) => "er",
+        }
+    }
+
+    /// Parse `input` as a diagram of the given type
+    fn parse(diagram_type: &str, input: &str) -> Result<Self> {
+        Ok(match diagram_type {
+            "sequence" => {
+                let mut database = SequenceDatabase::new();
+                SequenceParser::new().parse(input, &mut database)?;
+                Self::Sequence(database)
+            }
+            "gitgraph" => {
+                let mut database = GitGraphDatabase::new();
+                GitGraphParser::new().parse(input, &mut database)?;
+                Self::GitGraph(database)
+            }
+            "class" => {
+                let mut database = ClassDatabase::new();
+                ClassParser::new().parse(input, &mut database)?;
+                Self::Class(database)
+            }
+            "state" => {
+                let mut database = StateDatabase::new();
+                StateParser::new().parse(input, &mut database)?;
+                Self::State(database)
+            }
+            "er" => {
+                let mut database = ErDatabase::new();
+                ErParser::new().parse(input, &mut database)?;
+                Self::Er(database)
+            }
+            _ => {
+                let mut database = FlowchartDatabase::new();
+                FlowchartParser::new().parse(input, &mut database)?;
+                Self::Flowchart(database)
+            }
+        })
+    }
+}
+
+/// One diagram found in a document
+pub struct DiagramBlock<'a> {
+    /// 1-based line of the document where the diagram source starts
+    pub line: usize,
+    /// Diagram source, without any surrounding fence
+    pub source: &'a str,
+    /// Detected diagram type, as named by `figurehead detect`; `None` when
+    /// no detector recognized the source
+    pub diagram_type: Option<String>,
+    /// The parsed diagram, or why it could not be detected or parsed
+    pub diagram: Result<ParsedDiagram>,
+}
+
+/// Find, detect and parse every diagram in a document
+///
+/// Each diagram is parsed as the iterator reaches it, so a caller can stop
+/// early or render one diagram before the next is parsed. A diagram that
+/// fails to parse is yielded with its error; the rest are still parsed.
+///
+/// # Example
+/// ```rust
+/// let markdown = "# Design\n\n```mermaid\ngraph LR\n  A-->B\n```\n\n\
+///                 ```mermaid\nsequenceDiagram\n  A->>B: hi\n```\n";
+/// let types: Vec<_> = figurehead::parse_document(markdown)
+///     .map(|block| (block.line, block.diagram_type.unwrap()))
+///     .collect();
+/// assert_eq!(types, [(4, "flowchart".into()), (9, "sequence".into())]);
+/// ```
+pub fn parse_document(input: &str) -> impl Iterator<Item = DiagramBlock<'_>> {
+    let mut orchestrator = Orchestrator::new();
+    orchestrator.register_default_detectors();
+
+    diagram_sources(input)
+        .into_iter()
+        .map(
+            move |(line, source)| match orchestrator.detect_diagram_type(source) {
+                Ok(diagram_type) => DiagramBlock {
+                    line,
+                    source,
+                    diagram: ParsedDiagram::parse(&diagram_type, source),
+                    diagram_type: Some(diagram_type),
+                },
+                Err(error) => DiagramBlock {
+                    line,
+                    source,
+                    diagram_type: None,
+                    diagram: Err(error),
+                },
+            },
+        )
+}
+
+/// Diagram sources in a document with the line each starts on
+///
+/// Fenced mermaid blocks when there are any, otherwise the whole input
+/// split at diagram headers. Sources with nothing but blank lines are left
+/// out.
+fn diagram_sources(input: &str) -> Vec<(usize, &str)> {
+    let fenced = fenced_sources(input);
+    let sources = if fenced.is_empty() {
+        concatenated_sources(input)
+    } else {
+        fenced
+    };
+    sources
+        .into_iter()
+        .filter(|(_, source)| !source.trim().is_empty())
+        .collect()
+}
+
+/// Bodies of the closed ```mermaid (or ~~~mermaid) blocks in markdown
+fn fenced_sources(input: &str) -> Vec<(usize, &str)> {
+    let mut sources = Vec::new();
+    let mut open: Option<(&str, usize, usize)> = None;
+    let mut offset = 0;
+
+    for (index, line) in input.split_inclusive('\n').enumerate() {
+        match open {
+            Some((fence, line_number, start)) => {
+                if is_closing_fence(line, fence) {
+                    sources.push((line_number, &input[start..offset]));
+                    open = None;
+                }
+            }
+            None => {
+                if let Some(fence) = mermaid_fence(line) {
+                    open = Some((fence, index + 2, offset + line.len()));
+                }
+            }
+        }
+        offset += line.len();
+    }
+    sources
+}
+
+/// How drawn diagrams replace the mermaid blocks of a markdown document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MarkdownFence {
+    /// In a ` ```text ` block, using the mermaid block's fence
+    #[default]
+    Text,
+    /// As bare lines with no fence around them
+    Bare,
+}
+
+/// Draw every ```mermaid block of a markdown document in place
+///
+/// Blocks that fail to render are kept as they were, with a logged warning,
+/// so one bad diagram does not lose the rest of the document. Everything
+/// outside the mermaid blocks is copied unchanged.
+///
+/// # Example
+/// ```rust
+/// use figurehead::{render_markdown, CharacterSet, MarkdownFence, RenderConfig};
+///
+/// let readme = "# Flow\n\n```mermaid\ngraph LR\n  A-->B\n```\n";
+/// let config = RenderConfig::new(CharacterSet::Ascii, Default::default());
+/// let text = render_markdown(readme, &config, MarkdownFence::Text);
+/// assert!(text.starts_with("# Flow\n\n```text\n+---+"));
+/// assert!(text.ends_with("```\n"));
+/// ```
+pub fn render_markdown(input: &str, config: &RenderConfig, fence: MarkdownFence) -> String {
+    let mut orchestrator = Orchestrator::all_plugins(*config);
+    orchestrator.register_default_detectors();
+    replace_mermaid_blocks(input, fence, |source| {
+        orchestrator
+            .process(source)
+            .map_err(|error| warn!(%error, "Kept a mermaid block that failed to render"))
+            .ok()
+    })
+}
+
+/// Replace each fenced ```mermaid block with what `render` returns for its
+/// source; blocks it returns `None` for, and unclosed blocks, are kept
+pub fn replace_mermaid_blocks(
+    content: &str,
+    fence: MarkdownFence,
+    mut render: impl FnMut(&str) -> Option<String>,
+) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut lines = content.split_inclusive('\n');
+    while let Some(line) = lines.next() {
+        let Some(marker) = mermaid_fence(line) else {
+            out.push_str(line);
+            continue;
+        };
+
+        let mut block = vec![line];
+        let mut source = String::new();
+        let mut closed = false;
+        for inner in lines.by_ref() {
+            block.push(inner);
+            if is_closing_fence(inner, marker) {
+                closed = true;
+                break;
+            }
+            source.push_str(inner);
+        }
+
+        match closed.then(|| render(&source)).flatten() {
+            Some(diagram) => {
+                let newline = if block.last().is_some_and(|l| l.ends_with('\n')) {
+                    "\n"
+                } else {
+                    ""
+                };
+                let diagram = diagram.trim_end_matches('\n');
+                match fence {
+                    MarkdownFence::Text => out.push_str(&format!(
+                        "{}text\n{}\n{}{}",
+                        marker, diagram, marker, newline
+                    )),
+                    MarkdownFence::Bare => out.push_str(&format!("{}{}", diagram, newline)),
+                }
+            }
+            None => block.into_iter().for_each(|l| out.push_str(l)),
+        }
+    }
+    out
+}
+
+/// The fence (e.g. "```") opening a mermaid block on this line, if any
+fn mermaid_fence(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == marker).count();
+    let info = trimmed[len..].trim();
+    (len >= 3 && info.split_whitespace().next() == Some("mermaid")).then(|| &trimmed[..len])
+}
+
+fn is_closing_fence(line: &str, fence: &str) -> bool {
+    let trimmed = line.trim();
+    let marker = fence.chars().next().unwrap_or('`');
+    trimmed.len() >= fence.len() && trimmed.chars().all(|c| c == marker)
+}
+
+/// The input split before every diagram header after the first
+///
+/// Anything before the first header (init directives, comments) stays with
+/// the first diagram, as in
+/// [`split_diagrams`](crate::plugins::flowchart::split_diagrams).
+fn concatenated_sources(input: &str) -> Vec<(usize, &str)> {
+    let keywords: Vec<&str> = capabilities()
+        .diagram_types
+        .iter()
+        .flat_map(|diagram| diagram.keywords.iter().copied())
+        .collect();
+    let is_header = |line: &str| {
+        line.split(|c: char| c.is_whitespace() || c == ';' || c == ':')
+            .next()
+            .is_some_and(|word| keywords.contains(&word))
+    };
+
+    let mut sources = Vec::new();
+    let (mut start, mut start_line) = (0, 1);
+    let mut offset = 0;
+    let mut seen_header = false;
+    for (index, line) in input.split_inclusive('\n').enumerate() {
+        if is_header(line.trim_start()) {
+            if seen_header {
+                sources.push((start_line, &input[start..offset]));
+                (start, start_line) = (offset, index + 1);
+            }
+            seen_header = true;
+        }
+        offset += line.len();
+    }
+    sources.push((start_line, &input[start..]));
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Database;
+
+    #[test]
+    fn test_markdown_yields_only_mermaid_blocks() {
+        let markdown = "# Notes\n\n```rust\nfn main() {}\n```\n\n~~~~ mermaid\n\
+                        graph TD\n  A-->B\n~~~~\n\nText\n\n```mermaid\nerDiagram\n  \
+                        CUSTOMER ||--o{ ORDER : places\n```\n\n```mermaid\ngraph LR\n  X-->Y\n";
+        let blocks: Vec<_> = parse_document(markdown).collect();
+
+        // The last block is never closed, so it is not a diagram
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].line, 8);
+        assert_eq!(blocks[0].source, "graph TD\n  A-->B\n");
+        match &blocks[0].diagram {
+            Ok(ParsedDiagram::Flowchart(database)) => assert_eq!(database.node_count(), 2),
+            _ => panic!("expected a flowchart"),
+        }
+        assert_eq!(blocks[1].diagram_type.as_deref(), Some("er"));
+        assert_eq!(
+            blocks[1].diagram.as_ref().map(|d| d.diagram_type()).ok(),
+            Some("er")
+        );
+    }
+
+    #[test]
+    fn test_concatenated_diagrams_are_split_at_headers() {
+        let input = "%% shared notes\ngraph TD\n  A-->B\n\nsequenceDiagram\n  A->>B: hi\n\
+                     stateDiagram-v2\n  [*] --> Idle\ngitGraph\n  commit\n";
+        let blocks: Vec<_> = parse_document(input).collect();
+
+        let found: Vec<_> = blocks
+            .iter()
+            .map(|block| (block.line, block.diagram_type.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, "flowchart"),
+                (5, "sequence"),
+                (7, "state"),
+                (9, "gitgraph")
+            ]
+        );
+        assert!(blocks[0].source.starts_with("%% shared notes\n"));
+        assert!(blocks.iter().all(|block| block.diagram.is_ok()));
+    }
+
+    #[test]
+    fn test_mermaid_blocks_are_replaced() {
+        let content =
+            "# Flow\n\n```mermaid\ngraph TD\n  A --> B\n```\n\n```rust\nfn main() {}\n```\n";
+        let replace = |fence| {
+            replace_mermaid_blocks(content, fence, |source| {
+                assert_eq!(source, "graph TD\n  A --> B\n");
+                Some("[diagram]".to_string())
+            })
+        };
+        assert_eq!(
+            replace(MarkdownFence::Text),
+            "# Flow\n\n```text\n[diagram]\n```\n\n```rust\nfn main() {}\n```\n"
+        );
+        assert_eq!(
+            replace(MarkdownFence::Bare),
+            "# Flow\n\n[diagram]\n\n```rust\nfn main() {}\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_failed_and_unclosed_blocks_are_kept() {
+        let content = "~~~~mermaid\nnot a diagram\n~~~~\n```mermaid\ngraph TD\n";
+        assert_eq!(
+            replace_mermaid_blocks(content, MarkdownFence::Text, |_| None),
+            content
+        );
+        assert_eq!(
+            replace_mermaid_blocks(content, MarkdownFence::Text, |_| Some("x".into())),
+            "~~~~text\nx\n~~~~\n```mermaid\ngraph TD\n"
+        );
+
+        // A block that does not render is kept; the next one is still drawn
+        let markdown = "```mermaid\npie\n```\n\n```mermaid\ngraph LR\n  A-->B\n```\n";
+        let rendered = render_markdown(markdown, &RenderConfig::default(), MarkdownFence::Text);
+        assert!(
+            rendered.starts_with("```mermaid\npie\n```\n\n```text\n"),
+            "{}",
+            rendered
+        );
+        assert!(rendered.contains("│ A │"), "{}", rendered);
+    }
+
+    #[test]
+    fn test_undetected_blocks_carry_their_error() {
+        let markdown = "```mermaid\npie title Pets\n  \"Dogs\" : 3\n```\n\n\
+                        ```mermaid\n\n```\n\n```mermaid\ngraph TD\n  A-->B\n```\n";
+        let blocks: Vec<_> = parse_document(markdown).collect();
+
+        // The empty block is skipped; the pie chart is reported, not dropped
+        assert_eq!(blocks.len(), 2);
+        assert!(blocks[0].diagram_type.is_none());
+        assert!(blocks[0].diagram.is_err());
+        assert_eq!(blocks[1].diagram_type.as_deref(), Some("flowchart"));
+        assert!(parse_document("   \n").next().is_none());
+    }
+}
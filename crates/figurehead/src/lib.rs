@@ -34,27 +34,116 @@
 //! let renderer = FlowchartRenderer::new();
 //! let ascii = renderer.render(&database).unwrap();
 //! ```
+//!
+//! # Stability
+//!
+//! The [`prelude`] and the items it names (the render functions,
+//! [`RenderConfig`] and its options, parsers, renderers and the read APIs
+//! of the diagram databases) follow semver. Layout internals and drawing
+//! surfaces live in `figurehead::unstable`, behind the `unstable` feature;
+//! they can change in any release, so pin an exact version when using them.
 
+#![deny(missing_docs)]
+
+pub mod analysis;
+pub mod capabilities;
 pub mod core;
+pub mod document;
 pub mod plugins;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
 
+pub use analysis::{analyze, DiagramStats};
+pub use capabilities::{capabilities, Capabilities};
 pub use core::*;
+pub use document::{
+    parse_document, render_markdown, replace_mermaid_blocks, DiagramBlock, MarkdownFence,
+    ParsedDiagram,
+};
 
-/// Prelude module for convenient imports
+/// Stable API for convenient imports
+///
+/// Everything here follows semver: render entry points, render options and
+/// the parse/read side of each diagram type.
 pub mod prelude {
     pub use crate::core::{
-        CharacterSet, Database, Detector, DiamondStyle, Direction, EdgeData, EdgeType,
-        LayoutAlgorithm, NodeData, NodeShape, Parser, RenderConfig, Renderer,
+        CanvasLimit, CanvasMode, CharacterSet, Database, Detector, DiamondStyle, Direction,
+        EdgeData, EdgeType, FigureheadError, LabelPlacement, NodeData, NodeShape, OverflowPolicy,
+        ParseResult, Parser, RenderConfig, Renderer, SourceSpan, SplitLabels,
     };
+    pub use crate::plugins::class::{ClassDatabase, ClassParser, ClassRenderer};
+    pub use crate::plugins::er::{ErDatabase, ErParser, ErRenderer};
     pub use crate::plugins::flowchart::{
-        FlowchartDatabase, FlowchartDetector, FlowchartLayoutAlgorithm, FlowchartParser,
-        FlowchartRenderer,
+        FlowchartDatabase, FlowchartDetector, FlowchartParser, FlowchartRenderer,
+    };
+    pub use crate::plugins::gitgraph::{GitGraphDatabase, GitGraphParser, GitGraphRenderer};
+    pub use crate::plugins::sequence::{SequenceDatabase, SequenceParser, SequenceRenderer};
+    pub use crate::plugins::state::{StateDatabase, StateParser, StateRenderer};
+    pub use crate::plugins::Orchestrator;
+    pub use crate::{
+        analyze, parse, parse_document, render, render_markdown, render_pure, render_with_style,
+        DiagramBlock, DiagramStats, MarkdownFence, ParsedDiagram,
     };
 }
 
+/// Layout internals and drawing surfaces
+///
+/// Requires the `unstable` feature. These types expose how diagrams are
+/// positioned and drawn (canvases, geometry, layout results) and change
+/// whenever the layout engine does; they are not covered by semver.
+#[cfg(feature = "unstable")]
+pub mod unstable {
+    pub use crate::core::box_drawing::*;
+    pub use crate::core::braille::*;
+    pub use crate::core::canvas::*;
+    pub use crate::core::edge_routing::*;
+    pub use crate::core::footer::*;
+    pub use crate::core::geometry::*;
+    pub use crate::core::layout::*;
+    pub use crate::core::mirror::*;
+    pub use crate::core::palette::*;
+    pub use crate::core::syntax::*;
+
+    /// Class diagram layout
+    pub mod class {
+        pub use crate::plugins::class::layout::{
+            ClassLayoutAlgorithm, ClassLayoutResult, EndLabel, PositionedClass,
+            PositionedRelationship,
+        };
+    }
+
+    /// Entity relationship diagram layout
+    pub mod er {
+        pub use crate::plugins::er::layout::*;
+    }
+
+    /// Flowchart layout, layout validation and ASCII-art import
+    pub mod flowchart {
+        pub use crate::plugins::flowchart::ingest::ingest_ascii;
+        pub use crate::plugins::flowchart::layout::*;
+        pub use crate::plugins::flowchart::validation::*;
+    }
+
+    /// Git graph layout and the syntax-tree parser
+    pub mod gitgraph {
+        pub use crate::plugins::gitgraph::layout::*;
+        pub use crate::plugins::gitgraph::syntax_parser::GitGraphSyntaxParser;
+    }
+
+    /// Sequence diagram layout
+    pub mod sequence {
+        pub use crate::plugins::sequence::layout::*;
+    }
+
+    /// State diagram layout
+    pub mod state {
+        pub use crate::plugins::state::layout::*;
+    }
+}
+
 /// Render Mermaid flowchart syntax to ASCII art
 ///
 /// This is the simplest way to convert a Mermaid diagram to ASCII.
@@ -65,7 +154,8 @@ pub mod prelude {
 ///
 /// # Returns
 /// * `Ok(String)` - The ASCII art representation
-/// * `Err` - If parsing or rendering fails
+/// * `Err` - If parsing or rendering fails; a [`FigureheadError::Parse`]
+///   points at the offending statement
 ///
 /// # Example
 /// ```rust
@@ -75,12 +165,12 @@ pub mod prelude {
 /// assert!(ascii.contains("Start"));
 /// assert!(ascii.contains("End"));
 /// ```
-pub fn render(input: &str) -> anyhow::Result<String> {
+pub fn render(input: &str) -> Result<String, FigureheadError> {
     use crate::plugins::orchestrator::Orchestrator;
 
     let mut orchestrator = Orchestrator::with_all_plugins();
     orchestrator.register_default_detectors();
-    orchestrator.process(input)
+    Ok(orchestrator.process(input)?)
 }
 
 /// Render Mermaid flowchart syntax with a specific character set
@@ -93,7 +183,8 @@ pub fn render(input: &str) -> anyhow::Result<String> {
 ///
 /// # Returns
 /// * `Ok(String)` - The ASCII art representation
-/// * `Err` - If parsing or rendering fails
+/// * `Err` - If parsing or rendering fails; a [`FigureheadError::Parse`]
+///   points at the offending statement
 ///
 /// # Example
 /// ```rust
@@ -105,7 +196,7 @@ pub fn render(input: &str) -> anyhow::Result<String> {
 /// // Compact mode with single-glyph nodes
 /// let compact = render_with_style("graph LR; A-->B", CharacterSet::Compact).unwrap();
 /// ```
-pub fn render_with_style(input: &str, style: CharacterSet) -> anyhow::Result<String> {
+pub fn render_with_style(input: &str, style: CharacterSet) -> Result<String, FigureheadError> {
     use crate::core::{Parser as _, Renderer as _};
     use crate::plugins::flowchart::{FlowchartDatabase, FlowchartParser, FlowchartRenderer};
 
@@ -114,7 +205,37 @@ pub fn render_with_style(input: &str, style: CharacterSet) -> anyhow::Result<Str
     parser.parse(input, &mut database)?;
 
     let renderer = FlowchartRenderer::with_style(style);
-    renderer.render(&database)
+    Ok(renderer.render(&database)?)
+}
+
+/// Render any supported diagram with the given options, without side effects
+///
+/// The output depends only on `input` and `config`. Everything a render
+/// collects along the way lives on the call's own stack: parse warnings stay
+/// on the per-call database and layout timing is handed through the render
+/// call, so build systems can call this from any number of worker threads at
+/// once. Only `tracing` events are emitted, to whatever subscriber the caller
+/// installed.
+///
+/// # Example
+/// ```rust
+/// use figurehead::{render_pure, CharacterSet, RenderConfig};
+///
+/// let config = RenderConfig::new(CharacterSet::Ascii, Default::default());
+/// let handles: Vec<_> = ["graph LR; A-->B", "sequenceDiagram\n  A->>B: hi"]
+///     .into_iter()
+///     .map(|input| std::thread::spawn(move || render_pure(input, &config)))
+///     .collect();
+/// for handle in handles {
+///     assert!(!handle.join().unwrap().unwrap().is_empty());
+/// }
+/// ```
+pub fn render_pure(input: &str, config: &RenderConfig) -> Result<String, FigureheadError> {
+    use crate::plugins::orchestrator::Orchestrator;
+
+    let mut orchestrator = Orchestrator::all_plugins(*config);
+    orchestrator.register_default_detectors();
+    Ok(orchestrator.process(input)?)
 }
 
 /// Parse Mermaid flowchart syntax into a database without rendering
@@ -131,7 +252,7 @@ pub fn render_with_style(input: &str, style: CharacterSet) -> anyhow::Result<Str
 /// assert_eq!(db.edge_count(), 2);
 /// assert_eq!(db.direction(), Direction::TopDown);
 /// ```
-pub fn parse(input: &str) -> anyhow::Result<plugins::flowchart::FlowchartDatabase> {
+pub fn parse(input: &str) -> Result<plugins::flowchart::FlowchartDatabase, FigureheadError> {
     use crate::core::Parser as _;
     use crate::plugins::flowchart::{FlowchartDatabase, FlowchartParser};
 
@@ -141,6 +262,28 @@ pub fn parse(input: &str) -> anyhow::Result<plugins::flowchart::FlowchartDatabas
     Ok(database)
 }
 
+/// Parse Mermaid flowchart syntax, returning the database and its parse warnings
+///
+/// Statements the parser skips or ignores don't fail the parse; they are
+/// returned as warnings so callers can surface them next to the source.
+///
+/// # Example
+/// ```rust
+/// use figurehead::parse_with_warnings;
+/// use figurehead::prelude::Database;
+///
+/// let parsed = parse_with_warnings("graph TD\n  A --> B\n  ~~~").unwrap();
+/// assert_eq!(parsed.database.node_count(), 2);
+/// assert_eq!(parsed.warnings.len(), 1);
+/// ```
+pub fn parse_with_warnings(
+    input: &str,
+) -> Result<ParseResult<plugins::flowchart::FlowchartDatabase>, FigureheadError> {
+    use crate::plugins::flowchart::{FlowchartDatabase, FlowchartParser};
+
+    Ok(FlowchartParser::new().parse_with_warnings(input, FlowchartDatabase::new())?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,6 +362,56 @@ mod tests {
         assert_eq!(db.direction(), Direction::LeftRight);
     }
 
+    #[test]
+    fn test_render_pure_honours_ascii_style() {
+        let config = RenderConfig::new(CharacterSet::Ascii, Default::default());
+        for input in [
+            "sequenceDiagram\n    Alice->>Bob: Hello\n    Bob-->>Alice: Hi",
+            "stateDiagram-v2\n    [*] --> Idle\n    Idle --> Running\n    Running --> [*]",
+        ] {
+            let output = render_pure(input, &config).unwrap();
+            assert!(
+                !output
+                    .chars()
+                    .any(|c| ('\u{2500}'..='\u{259f}').contains(&c)),
+                "{}",
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_pure_is_send_sync_and_repeatable() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        assert_send_sync(
+            &(render_pure as fn(&str, &RenderConfig) -> Result<String, FigureheadError>),
+        );
+        assert_send_sync(&RenderConfig::default());
+
+        let inputs = [
+            "graph TD\n    A --> B\n    %%fh: hide Missing%%",
+            "gitGraph\n   commit\n   commit",
+            "stateDiagram-v2\n    [*] --> Idle",
+        ];
+        let config = RenderConfig::default();
+        let expected: Vec<String> = inputs
+            .iter()
+            .map(|input| render_pure(input, &config).unwrap())
+            .collect();
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .flat_map(|_| inputs.iter())
+                .map(|input| scope.spawn(move || (*input, render_pure(input, &config).unwrap())))
+                .collect();
+            for handle in handles {
+                let (input, output) = handle.join().unwrap();
+                let index = inputs.iter().position(|i| *i == input).unwrap();
+                assert_eq!(output, expected[index]);
+            }
+        });
+    }
+
     #[test]
     fn test_render_sequence() {
         let input = "sequenceDiagram\n    Alice->>Bob: Hello";
@@ -4,6 +4,7 @@
 
 use super::database::{Classifier, RelationshipKind, Visibility};
 use crate::core::chumsky_utils::{optional_whitespace, whitespace_required};
+use crate::core::Direction;
 use anyhow::Result;
 use chumsky::prelude::*;
 use chumsky::text::{ident, whitespace};
@@ -31,18 +32,23 @@ pub struct ParsedRelationship {
     pub to: String,
     pub kind: RelationshipKind,
     pub label: Option<String>,
+    pub from_cardinality: Option<String>,
+    pub to_cardinality: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
     Class(ParsedClass),
     Relationship(ParsedRelationship),
+    /// `direction LR`
+    Direction(Direction),
 }
 
 /// Chumsky-based class diagram parser
 pub struct ChumskyClassParser;
 
 impl ChumskyClassParser {
+    /// Create a parser
     pub fn new() -> Self {
         Self
     }
@@ -68,8 +74,10 @@ impl ChumskyClassParser {
     }
 
     fn diagram_parser<'src>() -> impl Parser<'src, &'src str, Vec<Statement>> {
-        // Skip the classDiagram header if present
-        let header = text::keyword("classDiagram")
+        // Skip the classDiagram (or classDiagram-v2) header if present
+        let header = just("classDiagram-v2")
+            .or(just("classdiagram-v2"))
+            .or(text::keyword("classDiagram"))
             .or(text::keyword("classdiagram"))
             .or_not();
 
@@ -89,11 +97,21 @@ impl ChumskyClassParser {
     }
 
     fn statement_parser<'src>() -> impl Parser<'src, &'src str, Statement> + Clone {
-        Self::class_parser()
-            .map(Statement::Class)
+        Self::direction_parser()
+            .or(Self::class_parser().map(Statement::Class))
             .or(Self::relationship_parser().map(Statement::Relationship))
     }
 
+    fn direction_parser<'src>() -> impl Parser<'src, &'src str, Statement> + Clone {
+        text::keyword("direction")
+            .ignore_then(just(' ').or(just('\t')).repeated().at_least(1))
+            .ignore_then(
+                choice((just("TB"), just("TD"), just("BT"), just("LR"), just("RL")))
+                    .map(|d: &str| d.parse::<Direction>().unwrap()),
+            )
+            .map(Statement::Direction)
+    }
+
     fn class_parser<'src>() -> impl Parser<'src, &'src str, ParsedClass> + Clone {
         let ws = optional_whitespace();
 
@@ -219,19 +237,35 @@ impl ChumskyClassParser {
             .map(|s: &str| s.trim().to_string())
             .or_not();
 
+        // Quoted cardinality next to either class: "1", "0..*"
+        let cardinality = just('"')
+            .ignore_then(none_of("\"\n\r").repeated().to_slice())
+            .then_ignore(just('"'))
+            .then_ignore(inline_ws)
+            .map(|s: &str| s.trim().to_string())
+            .or_not();
+
         class_name
             .then_ignore(inline_ws)
+            .then(cardinality)
             .then(rel_kind)
             .then_ignore(inline_ws)
+            .then(cardinality)
             .then(class_name)
             .then_ignore(inline_ws)
             .then(label)
-            .map(|(((from, kind), to), label)| ParsedRelationship {
-                from,
-                to,
-                kind,
-                label: label.filter(|s| !s.is_empty()),
-            })
+            .map(
+                |(((((from, from_cardinality), kind), to_cardinality), to), label)| {
+                    ParsedRelationship {
+                        from,
+                        to,
+                        kind,
+                        label: label.filter(|s| !s.is_empty()),
+                        from_cardinality: from_cardinality.filter(|s| !s.is_empty()),
+                        to_cardinality: to_cardinality.filter(|s| !s.is_empty()),
+                    }
+                },
+            )
     }
 }
 
@@ -309,6 +343,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_relationship_with_cardinalities() {
+        let parser = ChumskyClassParser::new();
+        let result = parser
+            .parse_statement(r#"Customer "1" --> "0..*" Order : places"#)
+            .unwrap();
+
+        match result {
+            Statement::Relationship(rel) => {
+                assert_eq!(rel.from_cardinality.as_deref(), Some("1"));
+                assert_eq!(rel.to_cardinality.as_deref(), Some("0..*"));
+                assert_eq!(rel.label.as_deref(), Some("places"));
+            }
+            _ => panic!("Expected relationship statement"),
+        }
+
+        let result = parser.parse_statement(r#"Order *-- "many" Line"#).unwrap();
+        match result {
+            Statement::Relationship(rel) => {
+                assert_eq!(rel.from_cardinality, None);
+                assert_eq!(rel.to_cardinality.as_deref(), Some("many"));
+            }
+            _ => panic!("Expected relationship statement"),
+        }
+    }
+
     #[test]
     fn test_parse_composition() {
         let parser = ChumskyClassParser::new();
@@ -2,19 +2,24 @@
 //!
 //! Stores classes and relationships for class diagrams.
 
-use crate::core::Database;
+use crate::core::{Database, Direction, IdCase, IdResolver, ParseWarning};
 use anyhow::Result;
 
 /// Visibility modifier for class members
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Visibility {
-    Public,    // +
-    Private,   // -
-    Protected, // #
-    Package,   // ~
+    /// `+`
+    Public,
+    /// `-`
+    Private,
+    /// `#`
+    Protected,
+    /// `~`
+    Package,
 }
 
 impl Visibility {
+    /// Parse a visibility marker
     pub fn from_char(c: char) -> Option<Self> {
         match c {
             '+' => Some(Visibility::Public),
@@ -25,6 +30,7 @@ impl Visibility {
         }
     }
 
+    /// Marker character for this visibility
     pub fn to_char(self) -> char {
         match self {
             Visibility::Public => '+',
@@ -38,11 +44,14 @@ impl Visibility {
 /// Classifier for methods (abstract, static)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Classifier {
-    Abstract, // *
-    Static,   // $
+    /// `*`
+    Abstract,
+    /// `$`
+    Static,
 }
 
 impl Classifier {
+    /// Parse a classifier marker
     pub fn from_char(c: char) -> Option<Self> {
         match c {
             '*' => Some(Classifier::Abstract),
@@ -51,6 +60,7 @@ impl Classifier {
         }
     }
 
+    /// Marker character for this classifier
     pub fn to_char(self) -> char {
         match self {
             Classifier::Abstract => '*',
@@ -62,14 +72,20 @@ impl Classifier {
 /// A class member (attribute or method)
 #[derive(Debug, Clone, PartialEq)]
 pub struct Member {
+    /// Visibility marker, if given
     pub visibility: Option<Visibility>,
+    /// Member name (with parameters for methods)
     pub name: String,
+    /// Declared type or return type
     pub member_type: Option<String>,
+    /// Abstract or static marker
     pub classifier: Option<Classifier>,
+    /// Whether the member is a method
     pub is_method: bool,
 }
 
 impl Member {
+    /// Create an attribute
     pub fn new(name: impl Into<String>) -> Self {
         Self {
             visibility: None,
@@ -80,10 +96,12 @@ impl Member {
         }
     }
 
+    /// Create an attribute
     pub fn attribute(name: impl Into<String>) -> Self {
         Self::new(name)
     }
 
+    /// Create a method
     pub fn method(name: impl Into<String>) -> Self {
         Self {
             visibility: None,
@@ -94,16 +112,19 @@ impl Member {
         }
     }
 
+    /// Set the visibility
     pub fn with_visibility(mut self, v: Visibility) -> Self {
         self.visibility = Some(v);
         self
     }
 
+    /// Set the type or return type
     pub fn with_type(mut self, t: impl Into<String>) -> Self {
         self.member_type = Some(t.into());
         self
     }
 
+    /// Set the classifier
     pub fn with_classifier(mut self, c: Classifier) -> Self {
         self.classifier = Some(c);
         self
@@ -113,13 +134,18 @@ impl Member {
 /// A class in the diagram
 #[derive(Debug, Clone)]
 pub struct Class {
+    /// Class name
     pub name: String,
+    /// Attributes in declaration order
     pub attributes: Vec<Member>,
+    /// Methods in declaration order
     pub methods: Vec<Member>,
+    /// Annotation such as `interface` (without `<<` `>>`)
     pub annotation: Option<String>,
 }
 
 impl Class {
+    /// Create an empty class
     pub fn new(name: impl Into<String>) -> Self {
         Self {
             name: name.into(),
@@ -129,15 +155,18 @@ impl Class {
         }
     }
 
+    /// Set the annotation
     pub fn with_annotation(mut self, annotation: impl Into<String>) -> Self {
         self.annotation = Some(annotation.into());
         self
     }
 
+    /// Add an attribute
     pub fn add_attribute(&mut self, member: Member) {
         self.attributes.push(member);
     }
 
+    /// Add a method
     pub fn add_method(&mut self, member: Member) {
         self.methods.push(member);
     }
@@ -146,28 +175,66 @@ impl Class {
 /// Relationship type between classes
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RelationshipKind {
-    Inheritance, // <|--
-    Composition, // *--
-    Aggregation, // o--
-    Association, // -->
-    Dependency,  // ..>
-    Realization, // ..|>
-    Link,        // --
-    DashedLink,  // ..
+    /// `<|--`
+    Inheritance,
+    /// `*--`
+    Composition,
+    /// `o--`
+    Aggregation,
+    /// `-->`
+    Association,
+    /// `..>`
+    Dependency,
+    /// `..|>`
+    Realization,
+    /// `--`
+    Link,
+    /// `..`
+    DashedLink,
+}
+
+impl RelationshipKind {
+    /// Returns true if the marker sits at the source class, as the
+    /// inheritance triangle does at the parent in `Parent <|-- Child`
+    pub fn marks_source(self) -> bool {
+        matches!(
+            self,
+            RelationshipKind::Inheritance
+                | RelationshipKind::Composition
+                | RelationshipKind::Aggregation
+        )
+    }
+
+    /// Returns true if the relationship is drawn with a dashed line
+    pub fn is_dashed(self) -> bool {
+        matches!(
+            self,
+            RelationshipKind::Dependency
+                | RelationshipKind::Realization
+                | RelationshipKind::DashedLink
+        )
+    }
 }
 
 /// A relationship between classes
 #[derive(Debug, Clone)]
 pub struct Relationship {
+    /// Source class name
     pub from: String,
+    /// Target class name
     pub to: String,
+    /// Relationship type
     pub kind: RelationshipKind,
+    /// Label after `:`
     pub label: Option<String>,
+    /// Cardinality at the source end
     pub from_cardinality: Option<String>,
+    /// Cardinality at the target end
     pub to_cardinality: Option<String>,
 }
 
 impl Relationship {
+    /// Create an unlabeled relationship
     pub fn new(from: impl Into<String>, to: impl Into<String>, kind: RelationshipKind) -> Self {
         Self {
             from: from.into(),
@@ -179,66 +246,131 @@ impl Relationship {
         }
     }
 
+    /// Set the label
     pub fn with_label(mut self, label: impl Into<String>) -> Self {
         self.label = Some(label.into());
         self
     }
+
+    /// Set the cardinality at the source end (`"1"` in `A "1" --> B`)
+    pub fn with_from_cardinality(mut self, cardinality: impl Into<String>) -> Self {
+        self.from_cardinality = Some(cardinality.into());
+        self
+    }
+
+    /// Set the cardinality at the target end (`"*"` in `A --> "*" B`)
+    pub fn with_to_cardinality(mut self, cardinality: impl Into<String>) -> Self {
+        self.to_cardinality = Some(cardinality.into());
+        self
+    }
 }
 
 /// Class diagram database
 pub struct ClassDatabase {
     classes: Vec<Class>,
     relationships: Vec<Relationship>,
+    direction: Direction,
+    ids: IdResolver,
+    /// Warnings recorded while parsing into this database
+    warnings: Vec<ParseWarning>,
 }
 
 impl ClassDatabase {
+    /// Create an empty database
     pub fn new() -> Self {
         Self {
             classes: Vec::new(),
             relationships: Vec::new(),
+            direction: Direction::TopDown,
+            ids: IdResolver::default(),
+            warnings: Vec::new(),
         }
     }
 
-    pub fn add_class(&mut self, class: Class) -> Result<()> {
+    /// Record a parse warning
+    pub(crate) fn add_parse_warning(&mut self, warning: ParseWarning) {
+        self.warnings.push(warning);
+    }
+
+    /// Set the layout direction from a `direction` statement
+    pub fn set_direction(&mut self, direction: Direction) {
+        self.direction = direction;
+    }
+
+    /// Get the layout direction
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Set how class names that differ only in case are treated
+    pub fn set_id_case(&mut self, case: IdCase) {
+        self.ids.set_case(case);
+    }
+
+    /// Get how class names that differ only in case are treated
+    pub fn id_case(&self) -> IdCase {
+        self.ids.case()
+    }
+
+    /// Take the warnings about class names that differ only in case
+    pub fn take_id_warnings(&mut self) -> Vec<String> {
+        self.ids.take_warnings()
+    }
+
+    /// Add a class
+    pub fn add_class(&mut self, mut class: Class) -> Result<()> {
+        class.name = self.ids.resolve(&class.name);
         self.classes.push(class);
         Ok(())
     }
 
-    pub fn add_relationship(&mut self, rel: Relationship) -> Result<()> {
+    /// Add a relationship
+    pub fn add_relationship(&mut self, mut rel: Relationship) -> Result<()> {
+        rel.from = self.ids.resolve(&rel.from);
+        rel.to = self.ids.resolve(&rel.to);
         self.relationships.push(rel);
         Ok(())
     }
 
+    /// All classes in declaration order
     pub fn classes(&self) -> &[Class] {
         &self.classes
     }
 
+    /// All relationships in declaration order
     pub fn relationships(&self) -> &[Relationship] {
         &self.relationships
     }
 
+    /// Number of classes
     pub fn class_count(&self) -> usize {
         self.classes.len()
     }
 
+    /// Number of relationships
     pub fn relationship_count(&self) -> usize {
         self.relationships.len()
     }
 
+    /// Look up a class by name
     pub fn get_class(&self, name: &str) -> Option<&Class> {
+        let name = self.ids.lookup(name);
         self.classes.iter().find(|c| c.name == name)
     }
 
+    /// Look up a class by name for editing
     pub fn get_class_mut(&mut self, name: &str) -> Option<&mut Class> {
+        let name = self.ids.lookup(name);
         self.classes.iter_mut().find(|c| c.name == name)
     }
 
     /// Get or create a class by name
     pub fn get_or_create_class(&mut self, name: &str) -> &mut Class {
-        if self.get_class(name).is_none() {
-            self.classes.push(Class::new(name));
+        let name = self.ids.resolve(name);
+        if self.get_class(&name).is_none() {
+            self.classes.push(Class::new(&name));
         }
-        self.get_class_mut(name).unwrap()
+        self.get_class_mut(&name).unwrap()
     }
 }
 
@@ -252,6 +384,14 @@ impl Database for ClassDatabase {
     type Node = Class;
     type Edge = Relationship;
 
+    fn parse_warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    fn take_parse_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
     fn add_node(&mut self, node: Self::Node) -> Result<()> {
         self.add_class(node)
     }
@@ -275,6 +415,9 @@ impl Database for ClassDatabase {
     fn clear(&mut self) {
         self.classes.clear();
         self.relationships.clear();
+        self.direction = Direction::TopDown;
+        self.ids.clear();
+        self.warnings.clear();
     }
 
     fn node_count(&self) -> usize {
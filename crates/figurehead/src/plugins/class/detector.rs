@@ -2,12 +2,13 @@
 //!
 //! Identifies class diagram syntax from input text.
 
-use crate::core::Detector;
+use crate::core::{header_keyword, Detector};
 
 /// Detector for class diagram syntax
 pub struct ClassDetector;
 
 impl ClassDetector {
+    /// Create a detector
     pub fn new() -> Self {
         Self
     }
@@ -71,8 +72,13 @@ impl Detector for ClassDetector {
         "class"
     }
 
+    fn prefix_confidence(&self, header: &str) -> Option<f64> {
+        (header_keyword(header, "classDiagram") || header_keyword(header, "classDiagram-v2"))
+            .then_some(1.0)
+    }
+
     fn patterns(&self) -> Vec<&'static str> {
-        vec!["classDiagram"]
+        vec!["classDiagram", "classDiagram-v2"]
     }
 }
 
@@ -86,6 +92,7 @@ mod tests {
         assert!(detector.detect("classDiagram\n    class Animal"));
         assert!(detector.detect("classdiagram\n    class Animal"));
         assert!(detector.detect("CLASSDIAGRAM\n    class Animal"));
+        assert!(detector.detect("classDiagram-v2\n    class Animal"));
     }
 
     #[test]
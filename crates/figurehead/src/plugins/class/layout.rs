@@ -1,11 +1,21 @@
 //! Class diagram layout algorithm
 //!
-//! Calculates positions for class boxes in a grid layout.
+//! Calculates positions for class boxes in a grid layout, and routes each
+//! relationship from its own port on the class boxes. Neighbours in a line
+//! are joined straight across the gap between them, and classes in
+//! adjacent lines through the gap after the first line, turning there if
+//! their ports don't line up. Other relationships run in a lane of their
+//! own: in the gap after the line for classes sharing a line, or out to a
+//! channel beyond every class for lines further apart.
+
+use std::collections::{HashMap, HashSet};
 
 use anyhow::Result;
 use unicode_width::UnicodeWidthStr;
 
-use super::database::{Class, ClassDatabase, Classifier, RelationshipKind, Visibility};
+use super::database::{
+    Class, ClassDatabase, Classifier, Relationship, RelationshipKind, Visibility,
+};
 
 /// Positioned class box for rendering
 #[derive(Debug, Clone)]
@@ -27,10 +37,25 @@ pub struct PositionedRelationship {
     pub to_class: String,
     pub kind: RelationshipKind,
     pub label: Option<String>,
-    pub from_x: usize,
-    pub from_y: usize,
-    pub to_x: usize,
-    pub to_y: usize,
+    /// Top-left cell of the label
+    pub label_x: usize,
+    pub label_y: usize,
+    /// Orthogonal route from the `from_class` end to the `to_class` end;
+    /// each end is the cell just outside its class box
+    pub points: Vec<(usize, usize)>,
+    /// Cardinality next to the `from_class` end of the line
+    pub from_cardinality: Option<EndLabel>,
+    /// Cardinality next to the `to_class` end of the line
+    pub to_cardinality: Option<EndLabel>,
+}
+
+/// Text placed next to one end of a relationship line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndLabel {
+    pub text: String,
+    /// Top-left cell of the text
+    pub x: usize,
+    pub y: usize,
 }
 
 /// Layout result containing all positioned elements
@@ -42,6 +67,93 @@ pub struct ClassLayoutResult {
     pub height: usize,
 }
 
+/// How a relationship is routed between its classes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RouteKind {
+    /// Neighbours in a line: straight across the gap between them
+    Across,
+    /// Adjacent lines: from the far side of the first class to the near
+    /// side of the second
+    Stacked,
+    /// Same line otherwise: out of both far sides and along a lane
+    Lane,
+    /// Lines further apart: out to a channel beyond every class and back
+    Detour,
+}
+
+/// Routing decisions for one relationship
+#[derive(Debug, Clone)]
+struct Route {
+    kind: RouteKind,
+    /// Class placed first (the earlier line, or earlier in the line)
+    first: usize,
+    second: usize,
+    /// The relationship's `from` class is `second`
+    flipped: bool,
+    /// Port of each end along its side; unused across neighbours
+    ports: (Option<usize>, Option<usize>),
+    /// Runs claimed in the first gap and, for detours, the last gap
+    runs: (Option<usize>, Option<usize>),
+    channel: usize,
+}
+
+impl Route {
+    /// Cardinality at the first (or second) class's end
+    fn cardinality<'a>(&self, rel: &'a Relationship, first_end: bool) -> Option<&'a str> {
+        let text = if first_end != self.flipped {
+            &rel.from_cardinality
+        } else {
+            &rel.to_cardinality
+        };
+        text.as_deref()
+    }
+
+    fn port(&self, first_end: bool) -> Option<usize> {
+        if first_end {
+            self.ports.0
+        } else {
+            self.ports.1
+        }
+    }
+
+    /// Whether the route is a single straight line
+    fn is_straight(&self) -> bool {
+        match self.kind {
+            RouteKind::Across => true,
+            RouteKind::Stacked => self.ports.0 == self.ports.1,
+            RouteKind::Lane | RouteKind::Detour => false,
+        }
+    }
+}
+
+/// Texts and runs in the gap after one line
+#[derive(Debug, Default, Clone)]
+struct Gap {
+    /// Room before the runs
+    head: usize,
+    /// Sizes of the runs, in claim order
+    runs: Vec<usize>,
+    /// Room after the runs
+    tail: usize,
+}
+
+impl Gap {
+    /// Claim a run of `size` cells, returning its index
+    fn claim(&mut self, size: usize) -> usize {
+        self.runs.push(size);
+        self.runs.len() - 1
+    }
+
+    fn runs_size(&self) -> usize {
+        self.runs.iter().sum()
+    }
+
+    /// Offset of a run from the first
+    fn runs_before(&self, run: usize) -> usize {
+        self.runs[..run].iter().sum()
+    }
+}
+
 /// Class diagram layout algorithm
 pub struct ClassLayoutAlgorithm {
     box_padding: usize,
@@ -151,6 +263,34 @@ impl ClassLayoutAlgorithm {
         (width, height)
     }
 
+    /// Columns needed between two classes side by side for the label and
+    /// cardinalities of a relationship between them
+    fn side_by_side_gap(&self, rel: &Relationship) -> usize {
+        let width = |text: &Option<String>| text.as_deref().map_or(0, str::width);
+        let (from, to) = (width(&rel.from_cardinality), width(&rel.to_cardinality));
+        let cardinalities = from + to + usize::from(from > 0 && to > 0);
+        // Triangles may take two characters (`<|` in ASCII); keep some line
+        let marker = match rel.kind {
+            RelationshipKind::Inheritance | RelationshipKind::Realization => 3,
+            _ => self.box_spacing,
+        };
+        // Keep two dots of a dashed line clear of its marker
+        let marker = marker + usize::from(rel.kind.is_dashed());
+        match width(&rel.label).max(cardinalities) {
+            0 => marker.max(self.box_spacing),
+            text => (text + 3).max(marker).max(self.box_spacing),
+        }
+    }
+
+    /// Rows needed between two stacked classes for the label and
+    /// cardinalities of a relationship between them (one row each, and
+    /// one more for a dashed line)
+    fn stacked_gap(&self, rel: &Relationship) -> usize {
+        let texts = [&rel.label, &rel.from_cardinality, &rel.to_cardinality];
+        let dashed = usize::from(rel.kind.is_dashed());
+        self.box_spacing + texts.iter().filter(|t| t.is_some()).count() + dashed
+    }
+
     /// Layout the diagram
     pub fn layout(&self, database: &ClassDatabase) -> Result<ClassLayoutResult> {
         let classes = database.classes();
@@ -195,96 +335,560 @@ impl ClassLayoutAlgorithm {
                         )
                     })
                     .collect();
-                (c, width, height, attrs, methods)
+                (width, height, attrs, methods)
             })
             .collect();
 
-        // Arrange in rows
-        let mut positioned = Vec::new();
-        let mut x = 0;
-        let mut y = 0;
-        let mut row_height = 0;
-        let mut max_width = 0;
-        let mut classes_in_row = 0;
-
-        for (class, width, height, attrs, methods) in class_info {
-            // Start new row if needed
-            if classes_in_row >= self.max_classes_per_row {
-                y += row_height + self.box_spacing;
-                x = 0;
-                row_height = 0;
-                classes_in_row = 0;
+        // Arrange in lines of up to `max_classes_per_row`: rows for TD/BT,
+        // columns for LR/RL, with the line order flipped for BT/RL. Below,
+        // "cross" runs along a line and "depth" from one line to the next.
+        let direction = database.direction();
+        let horizontal = direction.is_horizontal();
+        let mut lines: Vec<Vec<usize>> = (0..classes.len())
+            .collect::<Vec<_>>()
+            .chunks(self.max_classes_per_row)
+            .map(<[usize]>::to_vec)
+            .collect();
+        if direction.is_reversed() {
+            lines.reverse();
+        }
+        let mut place = vec![(0, 0); classes.len()];
+        for (l, line) in lines.iter().enumerate() {
+            for (p, &class) in line.iter().enumerate() {
+                place[class] = (l, p);
+            }
+        }
+
+        let index: HashMap<&str, usize> = classes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.name.as_str(), i))
+            .collect();
+        let relationships: Vec<&Relationship> = database
+            .relationships()
+            .iter()
+            .filter(|r| index.contains_key(r.from.as_str()) && index.contains_key(r.to.as_str()))
+            .collect();
+
+        // Classify routes; only the first relationship between neighbours
+        // takes the straight line across
+        let mut neighbours = HashSet::new();
+        let mut routes: Vec<Route> = relationships
+            .iter()
+            .map(|rel| {
+                let (from, to) = (index[rel.from.as_str()], index[rel.to.as_str()]);
+                let flipped = place[to] < place[from];
+                let (first, second) = if flipped { (to, from) } else { (from, to) };
+                let ((first_line, first_pos), (second_line, second_pos)) =
+                    (place[first], place[second]);
+                let kind = if first_line == second_line {
+                    if second_pos == first_pos + 1 && neighbours.insert((first, second)) {
+                        RouteKind::Across
+                    } else {
+                        RouteKind::Lane
+                    }
+                } else if second_line == first_line + 1 {
+                    RouteKind::Stacked
+                } else {
+                    RouteKind::Detour
+                };
+                Route {
+                    kind,
+                    first,
+                    second,
+                    flipped,
+                    ports: (None, None),
+                    runs: (None, None),
+                    channel: 0,
+                }
+            })
+            .collect();
+
+        // Which class sides the route ends attach to: (class, far side)
+        let mut sides: HashMap<(usize, bool), Vec<(usize, bool)>> = HashMap::new();
+        for (i, route) in routes.iter().enumerate() {
+            let second_far = match route.kind {
+                RouteKind::Across => continue,
+                RouteKind::Lane => true,
+                RouteKind::Stacked | RouteKind::Detour => false,
+            };
+            sides
+                .entry((route.first, true))
+                .or_default()
+                .push((i, true));
+            sides
+                .entry((route.second, second_far))
+                .or_default()
+                .push((i, false));
+        }
+
+        // Ports sit two apart along a side; below TD classes the texts
+        // right of each leg need room as well: the cardinality, and the
+        // label beside a straight line between lines
+        let end_room: Vec<(usize, usize)> = routes
+            .iter()
+            .zip(&relationships)
+            .map(|(route, rel)| {
+                let text = |first_end| route.cardinality(rel, first_end).map_or(0, str::width);
+                let label = match route.kind {
+                    RouteKind::Stacked => rel.label.as_deref().map_or(0, str::width),
+                    _ => 0,
+                };
+                (text(true).max(label), text(false))
+            })
+            .collect();
+        let spacing = |ends: &[(usize, bool)]| {
+            if horizontal {
+                return 2;
+            }
+            ends.iter()
+                .map(|&(i, first_end)| {
+                    let (first, second) = end_room[i];
+                    if first_end {
+                        first
+                    } else {
+                        second
+                    }
+                })
+                .filter(|&width| width > 0)
+                .map(|width| width + 3)
+                .max()
+                .unwrap_or(0)
+                .max(2)
+        };
+
+        // Box sizes, grown along the busiest sides to fit their ports
+        let mut sizes: Vec<(usize, usize)> = class_info.iter().map(|c| (c.0, c.1)).collect();
+        for (&(class, _), ends) in &sides {
+            let needed = (ends.len() - 1) * spacing(ends) + 3;
+            let (width, height) = &mut sizes[class];
+            let cross = if horizontal { height } else { width };
+            *cross = (*cross).max(needed);
+        }
+        let cross_len = |c: usize| if horizontal { sizes[c].1 } else { sizes[c].0 };
+        let depth_len = |c: usize| if horizontal { sizes[c].0 } else { sizes[c].1 };
+
+        // Widen the gaps that labels and cardinalities between neighbours
+        // sit in, then place each class along its line
+        let mut cross_gaps: Vec<Vec<usize>> = lines
+            .iter()
+            .map(|line| vec![self.box_spacing; line.len()])
+            .collect();
+        for (route, rel) in routes.iter().zip(&relationships) {
+            if route.kind == RouteKind::Across {
+                let (line, pos) = place[route.first];
+                let gap = if horizontal {
+                    self.stacked_gap(rel)
+                } else {
+                    self.side_by_side_gap(rel)
+                };
+                cross_gaps[line][pos] = cross_gaps[line][pos].max(gap);
+            }
+        }
+        let mut cross_at = vec![0; classes.len()];
+        for (l, line) in lines.iter().enumerate() {
+            let mut cross = 0;
+            for (p, &class) in line.iter().enumerate() {
+                cross_at[class] = cross;
+                cross += cross_len(class) + cross_gaps[l][p];
+            }
+        }
+        let content_cross = (0..classes.len())
+            .map(|c| cross_at[c] + cross_len(c))
+            .max()
+            .unwrap_or(0);
+
+        // A lone end on each side of a stacked route lines up with the other
+        // where the classes overlap, so the line runs straight
+        let inner = |c: usize| cross_at[c] + 1..=cross_at[c] + cross_len(c) - 2;
+        let centre = |c: usize| cross_at[c] + cross_len(c) / 2;
+        let lone = |class: usize, far: bool| sides.get(&(class, far)).map_or(0, Vec::len) == 1;
+        for route in &mut routes {
+            if route.kind != RouteKind::Stacked || !lone(route.first, true) {
+                continue;
+            }
+            if !lone(route.second, false) {
+                continue;
+            }
+            let (upper, lower) = (inner(route.first), inner(route.second));
+            let port = if lower.contains(&centre(route.first)) {
+                Some(centre(route.first))
+            } else {
+                let (left, right) = (
+                    (*upper.start()).max(*lower.start()),
+                    (*upper.end()).min(*lower.end()),
+                );
+                (left <= right).then(|| (left + right) / 2)
+            };
+            route.ports = (port, port);
+        }
+
+        // Spread the other ends along their sides, ordered by where the
+        // other end lies so neighbouring lines don't cross
+        let mut assigned = Vec::new();
+        for (&(class, _), ends) in &sides {
+            let mut ends: Vec<(usize, bool)> = ends
+                .iter()
+                .copied()
+                .filter(|&(i, first_end)| routes[i].port(first_end).is_none())
+                .collect();
+            if ends.is_empty() {
+                continue;
+            }
+            ends.sort_by_key(|&(i, first_end)| {
+                let route = &routes[i];
+                let other = if first_end { route.second } else { route.first };
+                (centre(other), !first_end)
+            });
+            let step = spacing(&ends);
+            let start = cross_at[class] + (cross_len(class) - (ends.len() - 1) * step) / 2;
+            for (k, &(i, first_end)) in ends.iter().enumerate() {
+                assigned.push((i, first_end, start + k * step));
+            }
+        }
+        for (i, first_end, port) in assigned {
+            let ports = &mut routes[i].ports;
+            if first_end {
+                ports.0 = Some(port);
+            } else {
+                ports.1 = Some(port);
+            }
+        }
+
+        // A lone end of a stacked route still lines up with the other end's
+        // port where that falls inside its class
+        for route in &mut routes {
+            if route.kind != RouteKind::Stacked || route.is_straight() {
+                continue;
+            }
+            let (upper, lower) = (route.ports.0.unwrap_or(0), route.ports.1.unwrap_or(0));
+            if lone(route.second, false) && inner(route.second).contains(&upper) {
+                route.ports.1 = Some(upper);
+            } else if lone(route.first, true) && inner(route.first).contains(&lower) {
+                route.ports.0 = Some(lower);
+            }
+        }
+
+        // Claim runs in the gap after each line: one per turn, lane and
+        // detour leg. Sideways runs are columns, with the label beside them.
+        let mut gaps = vec![Gap::default(); lines.len()];
+        let mut channels = 0;
+        for (route, rel) in routes.iter_mut().zip(&relationships) {
+            let label_width = rel.label.as_deref().map_or(0, str::width);
+            let size = if horizontal && label_width > 0 {
+                label_width + 3
+            } else {
+                1
+            };
+            let line = place[route.first].0;
+            match route.kind {
+                RouteKind::Across => {}
+                RouteKind::Stacked if route.is_straight() => {}
+                RouteKind::Stacked | RouteKind::Lane => route.runs.0 = Some(gaps[line].claim(size)),
+                RouteKind::Detour => {
+                    route.runs.0 = Some(gaps[line].claim(size));
+                    route.runs.1 = Some(gaps[place[route.second].0 - 1].claim(1));
+                    route.channel = channels;
+                    channels += 1;
+                }
+            }
+        }
+
+        // Room for texts before and after the runs: labels and
+        // cardinalities of straight lines, and cardinalities beside the
+        // legs of sideways routes
+        for (route, rel) in routes.iter().zip(&relationships) {
+            let line = place[route.first].0;
+            let text = |first_end| {
+                route
+                    .cardinality(rel, first_end)
+                    .map_or(0, |t| t.width() + 1)
+            };
+            match route.kind {
+                RouteKind::Across => {}
+                RouteKind::Stacked if route.is_straight() => {
+                    let gap = &mut gaps[line];
+                    let across = if horizontal {
+                        self.side_by_side_gap(rel)
+                    } else {
+                        self.stacked_gap(rel)
+                    };
+                    gap.head = gap.head.max(across - self.box_spacing);
+                    if horizontal && !gap.runs.is_empty() {
+                        gap.tail = gap.tail.max(text(false));
+                    }
+                }
+                _ if horizontal => {
+                    gaps[line].head = gaps[line].head.max(text(true));
+                    let (gap, room) = if route.kind == RouteKind::Lane {
+                        (&mut gaps[line].head, text(false))
+                    } else {
+                        (&mut gaps[place[route.second].0 - 1].tail, text(false))
+                    };
+                    *gap = (*gap).max(room);
+                }
+                _ => {}
             }
+        }
 
+        // Place the lines
+        let line_depths: Vec<usize> = lines
+            .iter()
+            .map(|line| line.iter().map(|&c| depth_len(c)).max().unwrap_or(0))
+            .collect();
+        let mut line_at = Vec::with_capacity(lines.len());
+        let mut depth = 0;
+        for (l, gap) in gaps.iter().enumerate() {
+            line_at.push(depth);
+            depth += line_depths[l] + self.box_spacing + gap.head + gap.runs_size() + gap.tail;
+        }
+        let gap_top = |l: usize| line_at[l] + line_depths[l];
+        let run_at =
+            |l: usize, run: usize| gap_top(l) + 1 + gaps[l].head + gaps[l].runs_before(run);
+        let at = |cross: usize, depth: usize| {
+            if horizontal {
+                (depth, cross)
+            } else {
+                (cross, depth)
+            }
+        };
+        let depth_at = |c: usize| line_at[place[c].0];
+
+        let mut positioned = Vec::new();
+        for &class in lines.iter().flatten() {
+            let (x, y) = at(cross_at[class], depth_at(class));
+            let (_, _, attrs, methods) = &class_info[class];
             positioned.push(PositionedClass {
-                name: class.name.clone(),
+                name: classes[class].name.clone(),
                 x,
                 y,
-                width,
-                height,
-                annotation: class.annotation.clone(),
-                attributes: attrs,
-                methods,
+                width: sizes[class].0,
+                height: sizes[class].1,
+                annotation: classes[class].annotation.clone(),
+                attributes: attrs.clone(),
+                methods: methods.clone(),
             });
-
-            x += width + self.box_spacing;
-            max_width = max_width.max(x);
-            row_height = row_height.max(height);
-            classes_in_row += 1;
         }
 
-        let total_width = max_width;
-        let total_height = y + row_height;
+        // Route each relationship from its first class to its second; the
+        // ends are the cells just outside the class boxes
+        let far = |c: usize| depth_at(c) + depth_len(c);
+        let near = |c: usize| depth_at(c) - 1;
+        let channel_at = |channel: usize| content_cross + self.box_spacing * (channel + 1) - 1;
+        let end_label = |text: Option<&str>, x: usize, y: usize| {
+            text.map(|text| EndLabel {
+                text: text.to_string(),
+                x,
+                y,
+            })
+        };
 
-        // Position relationships between classes
-        let mut positioned_relationships = Vec::new();
-        for rel in database.relationships() {
-            // Find from and to class positions
-            let from_class = positioned.iter().find(|c| c.name == rel.from);
-            let to_class = positioned.iter().find(|c| c.name == rel.to);
+        let paths: Vec<Vec<(usize, usize)>> = routes
+            .iter()
+            .map(|route| {
+                let (a, b) = (route.first, route.second);
+                let (line_a, line_b) = (place[a].0, place[b].0);
+                let (pa, pb) = (route.ports.0.unwrap_or(0), route.ports.1.unwrap_or(0));
+                match route.kind {
+                    RouteKind::Across => {
+                        // Both classes start the line; cross at the middle of
+                        // the first where it faces the second
+                        let (start, a_len, b_len) = (depth_at(a), depth_len(a), depth_len(b));
+                        let d = if a_len / 2 + 2 <= b_len {
+                            start + a_len / 2
+                        } else {
+                            start + (a_len.min(b_len) - 1) / 2
+                        };
+                        vec![at(cross_at[a] + cross_len(a), d), at(cross_at[b] - 1, d)]
+                    }
+                    RouteKind::Stacked => match route.runs.0 {
+                        Some(run) => {
+                            let t = run_at(line_a, run);
+                            vec![at(pa, far(a)), at(pa, t), at(pb, t), at(pb, near(b))]
+                        }
+                        None => vec![at(pa, far(a)), at(pb, near(b))],
+                    },
+                    RouteKind::Lane => {
+                        let t = run_at(line_a, route.runs.0.unwrap_or(0));
+                        vec![at(pa, far(a)), at(pa, t), at(pb, t), at(pb, far(b))]
+                    }
+                    RouteKind::Detour => {
+                        let t1 = run_at(line_a, route.runs.0.unwrap_or(0));
+                        let t2 = run_at(line_b - 1, route.runs.1.unwrap_or(0));
+                        let channel = channel_at(route.channel);
+                        vec![
+                            at(pa, far(a)),
+                            at(pa, t1),
+                            at(channel, t1),
+                            at(channel, t2),
+                            at(pb, t2),
+                            at(pb, near(b)),
+                        ]
+                    }
+                }
+            })
+            .collect();
 
-            if let (Some(from), Some(to)) = (from_class, to_class) {
-                // Determine if classes are on same row (horizontal) or different rows (vertical)
-                let same_row = from.y == to.y;
+        // Which routes draw over each cell, for keeping labels clear of
+        // other lines
+        let mut covered: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (i, points) in paths.iter().enumerate() {
+            for pair in points.windows(2) {
+                let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                for x in x1.min(x2)..=x1.max(x2) {
+                    for y in y1.min(y2)..=y1.max(y2) {
+                        covered.entry((x, y)).or_default().push(i);
+                    }
+                }
+            }
+        }
 
-                let (from_x, from_y, to_x, to_y) = if same_row {
-                    // Horizontal: connect right edge of left class to left edge of right class
-                    let (left, right) = if from.x < to.x {
-                        (from, to)
+        let mut positioned_relationships = Vec::with_capacity(routes.len());
+        for (i, ((route, rel), mut points)) in
+            routes.iter().zip(&relationships).zip(paths).enumerate()
+        {
+            let line_a = place[route.first].0;
+            let label_width = rel.label.as_deref().map_or(0, str::width);
+            let first_text = route.cardinality(rel, true);
+            let second_text = route.cardinality(rel, false);
+            let (first_end, second_end) = (points[0], points[points.len() - 1]);
+            let ((label_x, label_y), first_cardinality, second_cardinality) = if route.is_straight()
+            {
+                if first_end.1 == second_end.1 {
+                    // Label above the line, cardinalities below it at each
+                    // end; a line between lines centres its label in the
+                    // room before any runs
+                    let y = first_end.1;
+                    let middle = if route.kind == RouteKind::Stacked {
+                        gap_top(line_a) + gaps[line_a].head + self.box_spacing
                     } else {
-                        (to, from)
+                        second_end.0 + 1
                     };
-                    let y = left.y + left.height / 2; // Middle of class height
-                    (left.x + left.width, y, right.x, y)
+                    let second_width = second_text.map_or(0, str::width);
+                    (
+                        (
+                            ((first_end.0 + middle) / 2).saturating_sub(label_width / 2),
+                            y.saturating_sub(1),
+                        ),
+                        end_label(first_text, first_end.0 + 1, y + 1),
+                        end_label(
+                            second_text,
+                            second_end.0.saturating_sub(second_width),
+                            y + 1,
+                        ),
+                    )
                 } else {
-                    // Vertical: connect bottom of top class to top of bottom class
-                    let (top, bottom) = if from.y < to.y {
-                        (from, to)
+                    // Texts to the right of the line: the top cardinality,
+                    // the label, and the bottom cardinality above any runs
+                    let x = first_end.0;
+                    let bottom = if route.kind == RouteKind::Stacked {
+                        second_end.1 - 1 - gaps[line_a].runs_size()
                     } else {
-                        (to, from)
+                        second_end.1 - 1
                     };
-                    let x = top.x + top.width / 2;
-                    (x, top.y + top.height, x, bottom.y)
+                    (
+                        (x + 2, first_end.1 + usize::from(first_text.is_some())),
+                        end_label(first_text, x + 2, first_end.1),
+                        end_label(second_text, x + 2, bottom),
+                    )
+                }
+            } else {
+                // Label on or beside the first run, clear of other lines;
+                // cardinalities beside the legs
+                let (run_start, run_end) = (points[1], points[2]);
+                let blocked = |x: usize, y: usize| {
+                    covered
+                        .get(&(x, y))
+                        .is_some_and(|routes| routes.iter().any(|&r| r != i))
+                };
+                let clear = |x: usize, y: usize| (x..x + label_width).all(|x| !blocked(x, y));
+                let label = if horizontal {
+                    // Beside the run, on the row nearest its middle
+                    let (top, bottom) = (run_start.1.min(run_end.1), run_start.1.max(run_end.1));
+                    let (x, middle) = (run_start.0 + 2, (top + bottom) / 2);
+                    let y = (top..=bottom)
+                        .filter(|&y| clear(x, y))
+                        .min_by_key(|&y| y.abs_diff(middle))
+                        .unwrap_or(middle);
+                    (x, y)
+                } else {
+                    // On the run if it fits, else right of it, stepping
+                    // past the lines it would cut
+                    let (left, right) = (run_start.0.min(run_end.0), run_start.0.max(run_end.0));
+                    let y = run_start.1;
+                    let centred = ((left + right) / 2).saturating_sub(label_width / 2);
+                    if centred > left && centred + label_width < right && clear(centred, y) {
+                        (centred, y)
+                    } else {
+                        let mut x = right + 2;
+                        while let Some(cut) = (x..x + label_width).find(|&x| blocked(x, y)) {
+                            x = cut + 2;
+                        }
+                        (x, y)
+                    }
+                };
+                let beside = |text: Option<&str>, (x, y): (usize, usize), far: bool| {
+                    if !horizontal {
+                        end_label(text, x + 2, y)
+                    } else if far {
+                        end_label(text, x + 1, y + 1)
+                    } else {
+                        end_label(text, x.saturating_sub(text.map_or(0, str::width)), y + 1)
+                    }
                 };
+                (
+                    label,
+                    beside(first_text, first_end, true),
+                    beside(second_text, second_end, route.kind == RouteKind::Lane),
+                )
+            };
 
-                positioned_relationships.push(PositionedRelationship {
-                    from_class: rel.from.clone(),
-                    to_class: rel.to.clone(),
-                    kind: rel.kind,
-                    label: rel.label.clone(),
-                    from_x,
-                    from_y,
-                    to_x,
-                    to_y,
-                });
+            let (from_cardinality, to_cardinality) = if route.flipped {
+                points.reverse();
+                (second_cardinality, first_cardinality)
+            } else {
+                (first_cardinality, second_cardinality)
+            };
+            positioned_relationships.push(PositionedRelationship {
+                from_class: rel.from.clone(),
+                to_class: rel.to.clone(),
+                kind: rel.kind,
+                label: rel.label.clone(),
+                label_x,
+                label_y,
+                points,
+                from_cardinality,
+                to_cardinality,
+            });
+        }
+
+        // Extents of everything placed
+        let mut width = 0;
+        let mut height = 0;
+        let mut extend = |right: usize, bottom: usize| {
+            width = width.max(right);
+            height = height.max(bottom);
+        };
+        for class in &positioned {
+            extend(class.x + class.width, class.y + class.height);
+        }
+        for rel in &positioned_relationships {
+            for &(x, y) in &rel.points {
+                extend(x + 1, y + 1);
+            }
+            if let Some(label) = &rel.label {
+                extend(rel.label_x + label.width(), rel.label_y + 1);
+            }
+            for end in rel.from_cardinality.iter().chain(&rel.to_cardinality) {
+                extend(end.x + end.text.width(), end.y + 1);
             }
         }
 
         Ok(ClassLayoutResult {
             classes: positioned,
             relationships: positioned_relationships,
-            width: total_width,
-            height: total_height,
+            width,
+            height,
         })
     }
 }
@@ -448,10 +1052,12 @@ mod tests {
         let class_b = result.classes.iter().find(|c| c.name == "B").unwrap();
 
         // Same row: horizontal connection from right edge of A to left edge of B
-        assert_eq!(rel.from_x, class_a.x + class_a.width);
-        assert_eq!(rel.from_y, class_a.y + class_a.height / 2);
-        assert_eq!(rel.to_x, class_b.x);
-        assert_eq!(rel.to_y, class_b.y + class_b.height / 2);
+        let y = class_a.y + class_a.height / 2;
+        assert_eq!(
+            rel.points,
+            [(class_a.x + class_a.width, y), (class_b.x - 1, y)]
+        );
+        assert_eq!(y, class_b.y + class_b.height / 2);
     }
 
     #[test]
@@ -472,4 +1078,115 @@ mod tests {
 
         assert_eq!(result.relationships[0].label, Some("places".to_string()));
     }
+
+    #[test]
+    fn test_cardinalities_sit_at_their_class_ends_with_room() {
+        use super::super::database::Relationship;
+
+        let mut db = ClassDatabase::new();
+        db.add_class(Class::new("Order")).unwrap();
+        db.add_class(Class::new("Customer")).unwrap();
+        db.add_relationship(
+            Relationship::new("Customer", "Order", RelationshipKind::Association)
+                .with_label("places")
+                .with_from_cardinality("1")
+                .with_to_cardinality("0..*"),
+        )
+        .unwrap();
+
+        let result = ClassLayoutAlgorithm::new().layout(&db).unwrap();
+        let (order, customer) = (&result.classes[0], &result.classes[1]);
+        // The gap between the boxes fits the label
+        assert!(customer.x - (order.x + order.width) >= "places".len() + 3);
+
+        // Customer is on the right, so its "1" sits at the arrow end
+        let rel = &result.relationships[0];
+        let from = rel.from_cardinality.as_ref().unwrap();
+        let to = rel.to_cardinality.as_ref().unwrap();
+        assert_eq!((from.text.as_str(), to.text.as_str()), ("1", "0..*"));
+        assert_eq!(to.x, order.x + order.width + 1);
+        assert_eq!(from.x + 1, customer.x - 1);
+        assert_eq!(from.y, rel.points[0].1 + 1);
+        assert_eq!(rel.label_y + 1, rel.points[0].1);
+    }
+
+    #[test]
+    fn test_each_relationship_has_its_own_port() {
+        use crate::core::Direction;
+
+        for direction in [Direction::TopDown, Direction::LeftRight] {
+            let mut db = ClassDatabase::new();
+            for name in ["A", "C", "D", "E", "F"] {
+                db.add_class(Class::new(name)).unwrap();
+            }
+            db.set_direction(direction);
+            for (to, kind) in [
+                ("C", RelationshipKind::Inheritance),
+                ("D", RelationshipKind::Composition),
+                ("E", RelationshipKind::Dependency),
+                ("F", RelationshipKind::Association),
+                ("A", RelationshipKind::Association),
+            ] {
+                db.add_relationship(Relationship::new("A", to, kind))
+                    .unwrap();
+            }
+
+            let result = ClassLayoutAlgorithm::new().layout(&db).unwrap();
+            let mut ends: Vec<(usize, usize)> = result
+                .relationships
+                .iter()
+                .flat_map(|rel| [rel.points[0], *rel.points.last().unwrap()])
+                .collect();
+            ends.sort();
+            ends.dedup();
+            assert_eq!(ends.len(), 10, "{:?}", direction);
+
+            // No line runs through a class box
+            for rel in &result.relationships {
+                for pair in rel.points.windows(2) {
+                    let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+                    assert!(x1 == x2 || y1 == y2, "{:?}", rel.points);
+                    for x in x1.min(x2)..=x1.max(x2) {
+                        for y in y1.min(y2)..=y1.max(y2) {
+                            let inside = result.classes.iter().find(|c| {
+                                (c.x..c.x + c.width).contains(&x)
+                                    && (c.y..c.y + c.height).contains(&y)
+                            });
+                            assert!(inside.is_none(), "{:?} {:?}", rel.points, inside);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_direction_arranges_lines() {
+        use crate::core::Direction;
+
+        let layout_in = |direction| {
+            let mut db = ClassDatabase::new();
+            for name in ["A", "B", "C", "D"] {
+                db.add_class(Class::new(name)).unwrap();
+            }
+            db.set_direction(direction);
+            ClassLayoutAlgorithm::new().layout(&db).unwrap()
+        };
+        let pos = |result: &ClassLayoutResult, name: &str| {
+            let class = result.classes.iter().find(|c| c.name == name).unwrap();
+            (class.x, class.y)
+        };
+
+        // LR fills columns: A, B, C stacked, D in the next column
+        let lr = layout_in(Direction::LeftRight);
+        assert_eq!(pos(&lr, "A").0, pos(&lr, "C").0);
+        assert!(pos(&lr, "B").1 > pos(&lr, "A").1);
+        assert!(pos(&lr, "D").0 > pos(&lr, "A").0);
+
+        // RL and BT put the first line last
+        let rl = layout_in(Direction::RightLeft);
+        assert!(pos(&rl, "D").0 < pos(&rl, "A").0);
+        let bt = layout_in(Direction::BottomUp);
+        assert!(pos(&bt, "D").1 < pos(&bt, "A").1);
+    }
 }
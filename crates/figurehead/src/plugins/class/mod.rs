@@ -1,11 +1,21 @@
 //! Class diagram plugin
 //!
 //! Implements class diagram parsing and rendering.
+//!
+//! Relationships may carry quoted cardinalities next to either class and a
+//! label after `:` (`Customer "1" --> "0..*" Order : places`); both are drawn
+//! beside the relationship line.
+//!
+//! Each relationship kind gets its UML marker against the class it belongs
+//! to: a hollow triangle at the parent of `<|--` (`◁`, `<|` in ASCII), a
+//! filled or hollow diamond at the whole of `*--` and `o--`, and arrows at
+//! the target of `-->`, `..>` and `..|>`; dashed kinds use dotted lines.
 
 mod chumsky_parser;
 mod database;
 mod detector;
-mod layout;
+#[allow(missing_docs)]
+pub(crate) mod layout;
 mod parser;
 mod renderer;
 
@@ -14,8 +24,5 @@ pub use database::{
     Class, ClassDatabase, Classifier, Member, Relationship, RelationshipKind, Visibility,
 };
 pub use detector::ClassDetector;
-pub use layout::{
-    ClassLayoutAlgorithm, ClassLayoutResult, PositionedClass, PositionedRelationship,
-};
 pub use parser::ClassParser;
 pub use renderer::ClassRenderer;
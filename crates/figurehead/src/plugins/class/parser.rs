@@ -4,20 +4,62 @@
 
 use super::chumsky_parser::{ChumskyClassParser, Statement};
 use super::database::{Class, ClassDatabase, Member, Relationship};
-use crate::core::Parser;
+use crate::core::{
+    normalize_input, IdCase, LenientStatements, Parser, SpannedStatement, StatementSplitter,
+};
 use anyhow::Result;
 
 /// Class diagram parser using chumsky
 pub struct ClassParser {
     chumsky: ChumskyClassParser,
+    id_case: IdCase,
 }
 
 impl ClassParser {
+    /// Create a parser
     pub fn new() -> Self {
         Self {
             chumsky: ChumskyClassParser::new(),
+            id_case: IdCase::default(),
         }
     }
+
+    /// Set whether class names that differ only in case (`User`, `user`)
+    /// name the same class
+    pub fn with_id_case(mut self, case: IdCase) -> Self {
+        self.id_case = case;
+        self
+    }
+
+    /// Current class name case handling
+    pub fn id_case(&self) -> IdCase {
+        self.id_case
+    }
+
+    /// Top-level statements, each class body joined to its `class` line
+    ///
+    /// Body lines are kept on separate lines, as members end at a newline.
+    fn statements(input: &str) -> Vec<SpannedStatement> {
+        let mut statements: Vec<SpannedStatement> = Vec::new();
+        let mut in_body = false;
+        for statement in StatementSplitter::new().with_semicolons(false).split(input) {
+            let text = &statement.text;
+            if in_body {
+                in_body = !text.contains('}');
+                let open = statements.last_mut().unwrap();
+                open.text.push('\n');
+                open.text.push_str(text);
+                open.span.end = statement.span.end;
+                continue;
+            }
+            if text.to_lowercase().starts_with("classdiagram") {
+                continue;
+            }
+            in_body = text.contains('{') && !text.contains('}');
+            statements.push(statement);
+        }
+        statements
+    }
 }
 
 impl Default for ClassParser {
@@ -28,7 +70,17 @@ impl Default for ClassParser {
 
 impl Parser<ClassDatabase> for ClassParser {
     fn parse(&self, input: &str, database: &mut ClassDatabase) -> Result<()> {
-        let statements = self.chumsky.parse_diagram(input)?;
+        let normalized = normalize_input(input);
+        let input = normalized.as_ref();
+        database.set_id_case(self.id_case);
+
+        let mut lenient = LenientStatements::new("class");
+        let statements: Vec<_> = Self::statements(input)
+            .iter()
+            .filter_map(|statement| {
+                lenient.parse(statement, |text| self.chumsky.parse_statement(text))
+            })
+            .collect();
 
         for statement in statements {
             match statement {
@@ -60,11 +112,25 @@ impl Parser<ClassDatabase> for ClassParser {
                     if let Some(label) = parsed_rel.label {
                         rel = rel.with_label(label);
                     }
+                    if let Some(cardinality) = parsed_rel.from_cardinality {
+                        rel = rel.with_from_cardinality(cardinality);
+                    }
+                    if let Some(cardinality) = parsed_rel.to_cardinality {
+                        rel = rel.with_to_cardinality(cardinality);
+                    }
                     database.add_relationship(rel)?;
                 }
+                Statement::Direction(direction) => database.set_direction(direction),
             }
         }
 
+        for warning in lenient.finish()? {
+            database.add_parse_warning(warning);
+        }
+        for warning in database.take_id_warnings() {
+            tracing::warn!(%warning, "Near-duplicate class names");
+        }
+
         Ok(())
     }
 
@@ -86,6 +152,7 @@ impl Parser<ClassDatabase> for ClassParser {
 mod tests {
     use super::super::database::{Classifier, RelationshipKind, Visibility};
     use super::*;
+    use crate::core::Database;
 
     #[test]
     fn test_parse_empty_class() {
@@ -302,6 +369,24 @@ mod tests {
         assert_eq!(rel.label, Some("places".to_string()));
     }
 
+    #[test]
+    fn test_parse_relationship_cardinalities() {
+        let parser = ClassParser::new();
+        let mut db = ClassDatabase::new();
+
+        parser
+            .parse(
+                "classDiagram\n    Customer \"1\" --> \"*\" Order : places",
+                &mut db,
+            )
+            .unwrap();
+
+        let rel = &db.relationships()[0];
+        assert_eq!(rel.from_cardinality.as_deref(), Some("1"));
+        assert_eq!(rel.to_cardinality.as_deref(), Some("*"));
+        assert_eq!(rel.label.as_deref(), Some("places"));
+    }
+
     #[test]
     fn test_parse_mixed_classes_and_relationships() {
         let parser = ClassParser::new();
@@ -321,4 +406,69 @@ mod tests {
         assert_eq!(db.class_count(), 2);
         assert_eq!(db.relationship_count(), 1);
     }
+
+    #[test]
+    fn test_parse_v2_header_and_direction() {
+        use crate::core::Direction;
+
+        let parser = ClassParser::new();
+        let mut db = ClassDatabase::new();
+        parser
+            .parse(
+                "classDiagram-v2\n    direction RL\n    Animal <|-- Dog",
+                &mut db,
+            )
+            .unwrap();
+
+        assert_eq!(db.direction(), Direction::RightLeft);
+        assert_eq!(db.class_count(), 2);
+
+        let mut db = ClassDatabase::new();
+        parser.parse("classDiagram\n    class A", &mut db).unwrap();
+        assert_eq!(db.direction(), Direction::TopDown);
+    }
+
+    #[test]
+    fn test_id_case_insensitive_merges_class_names() {
+        let input = "classDiagram\n    User <|-- Admin\n    user --> Session";
+
+        let mut db = ClassDatabase::new();
+        ClassParser::new().parse(input, &mut db).unwrap();
+        assert_eq!(db.class_count(), 4);
+
+        let mut db = ClassDatabase::new();
+        ClassParser::new()
+            .with_id_case(IdCase::Insensitive)
+            .parse(input, &mut db)
+            .unwrap();
+        assert_eq!(db.class_count(), 3);
+        assert_eq!(db.relationships()[1].from, "User");
+        assert!(db.get_class("USER").is_some());
+    }
+
+    #[test]
+    fn test_rejected_statement_is_skipped_with_warning() {
+        let mut db = ClassDatabase::new();
+        ClassParser::new()
+            .parse(
+                "classDiagram\n    class A {\n        +name\n    }\n    A ~~ B\n    A <|-- C\n",
+                &mut db,
+            )
+            .unwrap();
+        assert_eq!(db.class_count(), 2);
+        assert_eq!(db.classes()[0].attributes.len(), 1);
+        let warnings = db.parse_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!((warnings[0].line, warnings[0].column), (Some(5), Some(5)));
+        assert_eq!(warnings[0].statement.as_deref(), Some("A ~~ B"));
+
+        // With nothing readable the parse fails at the first bad statement
+        let error = ClassParser::new()
+            .parse("classDiagram\n    A ~~ B\n", &mut ClassDatabase::new())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Parse error at line 2, column 5: unrecognized statement 'A ~~ B'"
+        );
+    }
 }
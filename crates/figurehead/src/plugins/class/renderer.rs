@@ -9,14 +9,36 @@ use super::database::{ClassDatabase, RelationshipKind};
 use super::layout::{
     ClassLayoutAlgorithm, ClassLayoutResult, PositionedClass, PositionedRelationship,
 };
-use crate::core::{AsciiCanvas, BoxChars, CharacterSet};
+use crate::core::{
+    AsciiCanvas, BoxChars, CellGrid, CharacterSet, Direction, EdgeChars, EdgeTerminator, Renderer,
+    Role, RoleRecorder,
+};
+use crate::plugins::observer::LayoutProbe;
 
 /// Class diagram renderer
-pub struct ClassRenderer;
+pub struct ClassRenderer {
+    style: CharacterSet,
+    color: bool,
+}
 
 impl ClassRenderer {
+    /// Create a renderer with the default character set
     pub fn new() -> Self {
-        Self
+        Self::with_style(CharacterSet::Unicode)
+    }
+
+    /// Create a renderer with the given character set
+    pub fn with_style(style: CharacterSet) -> Self {
+        Self {
+            style,
+            color: false,
+        }
+    }
+
+    /// Color class boxes, names, members, relationships and labels with ANSI codes
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
     }
 
     /// Draw text centered within a given width
@@ -39,8 +61,7 @@ impl ClassRenderer {
         let y = class.y;
         let w = class.width;
 
-        // Use shared box drawing characters (Unicode mode)
-        let chars = BoxChars::rectangle(CharacterSet::Unicode);
+        let chars = BoxChars::rectangle(self.style);
 
         // Current y position for drawing
         let mut cy = y;
@@ -94,111 +115,187 @@ impl ClassRenderer {
             }
         }
 
+        // Blank rows where the box was grown to fit its ports
+        while cy + 1 < y + class.height {
+            canvas.set_char(x, cy, chars.vertical);
+            canvas.draw_horizontal_line(x + 1, cy, w - 2, ' ');
+            canvas.set_char(x + w - 1, cy, chars.vertical);
+            cy += 1;
+        }
+
         // Bottom border
         canvas.set_char(x, cy, chars.bottom_left);
         canvas.draw_horizontal_line(x + 1, cy, w - 2, chars.horizontal);
         canvas.set_char(x + w - 1, cy, chars.bottom_right);
     }
 
-    /// Get line character for a relationship type
-    fn line_char_for(kind: RelationshipKind) -> char {
-        match kind {
-            RelationshipKind::Inheritance => '─',
-            RelationshipKind::Realization => '╌',
-            RelationshipKind::Composition => '─',
-            RelationshipKind::Aggregation => '─',
-            RelationshipKind::Association => '─',
-            RelationshipKind::Dependency => '╌',
-            RelationshipKind::Link => '─',
-            RelationshipKind::DashedLink => '╌',
+    /// Get edge characters for a relationship type (dashed or solid)
+    fn edge_chars_for(&self, kind: RelationshipKind) -> EdgeChars {
+        if kind.is_dashed() {
+            EdgeChars::dotted_for_style(self.style)
+        } else {
+            EdgeChars::for_style(self.style)
         }
     }
 
-    /// Get arrow head character for a relationship type
-    fn arrow_char_for(kind: RelationshipKind) -> char {
+    /// Terminator drawn against the marked class of a relationship
+    fn terminator_for(kind: RelationshipKind) -> EdgeTerminator {
         match kind {
-            RelationshipKind::Inheritance => '◁',
-            RelationshipKind::Realization => '◁',
-            RelationshipKind::Composition => '◆',
-            RelationshipKind::Aggregation => '◇',
-            RelationshipKind::Association => '▷',
-            RelationshipKind::Dependency => '▷',
-            RelationshipKind::Link => '─',
-            RelationshipKind::DashedLink => '╌',
+            RelationshipKind::Inheritance | RelationshipKind::Realization => {
+                EdgeTerminator::Triangle
+            }
+            RelationshipKind::Composition => EdgeTerminator::Diamond { filled: true },
+            RelationshipKind::Aggregation => EdgeTerminator::Diamond { filled: false },
+            // Plain arrows match flowchart heads: filled for solid, hollow for dashed
+            RelationshipKind::Association => EdgeTerminator::Arrow,
+            RelationshipKind::Dependency => EdgeTerminator::Triangle,
+            RelationshipKind::Link | RelationshipKind::DashedLink => EdgeTerminator::None,
         }
     }
 
-    /// Draw relationship line (without arrow head)
-    fn draw_relationship_line(&self, canvas: &mut AsciiCanvas, rel: &PositionedRelationship) {
-        let line_char = Self::line_char_for(rel.kind);
-        let is_horizontal = rel.from_y == rel.to_y;
+    /// Marker for a relationship reaching its marked class heading in
+    /// `direction`
+    ///
+    /// ASCII has no hollow triangle, so sideways inheritance and realization
+    /// markers are spelled with two characters (`<|`, `|>`).
+    fn marker_for(&self, kind: RelationshipKind, direction: Direction) -> Option<String> {
+        match kind {
+            RelationshipKind::Inheritance | RelationshipKind::Realization
+                if self.style.is_ascii() && direction.is_horizontal() =>
+            {
+                let marker = if direction == Direction::LeftRight {
+                    "|>"
+                } else {
+                    "<|"
+                };
+                Some(marker.to_string())
+            }
+            _ => Self::terminator_for(kind)
+                .glyph(direction, self.style)
+                .map(String::from),
+        }
+    }
 
-        if is_horizontal {
-            let y = rel.from_y;
-            let (left_x, right_x) = if rel.from_x < rel.to_x {
-                (rel.from_x, rel.to_x)
+    /// Direction a line travels from one end to the other
+    fn heading(from: (usize, usize), to: (usize, usize)) -> Direction {
+        if from.1 == to.1 {
+            if to.0 >= from.0 {
+                Direction::LeftRight
             } else {
-                (rel.to_x, rel.from_x)
-            };
-
-            for x in left_x..right_x {
-                canvas.set_char(x, y, line_char);
+                Direction::RightLeft
             }
+        } else if to.1 > from.1 {
+            Direction::TopDown
         } else {
-            let x = rel.from_x;
-            let (top_y, bottom_y) = if rel.from_y < rel.to_y {
-                (rel.from_y, rel.to_y)
+            Direction::BottomUp
+        }
+    }
+
+    /// Draw a relationship's route with corners at each turn, crossing
+    /// the lines drawn before it
+    fn draw_route(&self, canvas: &mut AsciiCanvas, rel: &PositionedRelationship) {
+        let chars = self.edge_chars_for(rel.kind);
+        let (solid, dotted) = (
+            EdgeChars::for_style(self.style),
+            EdgeChars::dotted_for_style(self.style),
+        );
+        let draw = |canvas: &mut AsciiCanvas, x: usize, y: usize, line: char, across| {
+            let across: [char; 2] = across;
+            let c = if across.contains(&canvas.get_char(x, y)) {
+                chars.cross
             } else {
-                (rel.to_y, rel.from_y)
+                line
             };
+            canvas.set_char(x, y, c);
+        };
 
-            for y in top_y..bottom_y {
-                canvas.set_char(x, y, '│');
+        for pair in rel.points.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            if x1 == x2 {
+                for y in y1.min(y2)..=y1.max(y2) {
+                    draw(
+                        canvas,
+                        x1,
+                        y,
+                        chars.vertical,
+                        [solid.horizontal, dotted.horizontal],
+                    );
+                }
+            } else {
+                for x in x1.min(x2)..=x1.max(x2) {
+                    draw(
+                        canvas,
+                        x,
+                        y1,
+                        chars.horizontal,
+                        [solid.vertical, dotted.vertical],
+                    );
+                }
             }
         }
+
+        for turn in rel.points.windows(3) {
+            let (prev, (x, y), next) = (turn[0], turn[1], turn[2]);
+            let up = prev.1 < y || next.1 < y;
+            let left = prev.0 < x || next.0 < x;
+            let corner = match (up, left) {
+                (true, true) => chars.corner_bottom_right,
+                (true, false) => chars.corner_bottom_left,
+                (false, true) => chars.corner_top_right,
+                (false, false) => chars.corner_top_left,
+            };
+            canvas.set_char(x, y, corner);
+        }
     }
 
-    /// Draw relationship arrow head only
-    fn draw_relationship_arrow(&self, canvas: &mut AsciiCanvas, rel: &PositionedRelationship) {
-        let arrow_char = Self::arrow_char_for(rel.kind);
-        let is_horizontal = rel.from_y == rel.to_y;
+    /// Draw the relationship marker against its marked class
+    fn draw_relationship_marker(&self, canvas: &mut AsciiCanvas, rel: &PositionedRelationship) {
+        let points = &rel.points;
+        let (direction, (x, y)) = match points.len() {
+            0 | 1 => return,
+            _ if rel.kind.marks_source() => (Self::heading(points[1], points[0]), points[0]),
+            n => (Self::heading(points[n - 2], points[n - 1]), points[n - 1]),
+        };
+        let Some(marker) = self.marker_for(rel.kind, direction) else {
+            return;
+        };
 
-        if is_horizontal {
-            let y = rel.from_y;
-            canvas.set_char(rel.to_x.saturating_sub(1), y, arrow_char);
+        // The marker ends on the line's last cell, against the class
+        let length = marker.chars().count();
+        let x = if direction == Direction::LeftRight {
+            (x + 1).saturating_sub(length)
         } else {
-            let x = rel.from_x;
-            canvas.set_char(
-                x,
-                rel.to_y.saturating_sub(1),
-                if rel.to_y > rel.from_y { '▽' } else { '△' },
-            );
-        }
+            x
+        };
+        canvas.draw_text(x, y, &marker);
     }
 
-    /// Draw relationship label (drawn last so it's on top)
+    /// Draw relationship label and cardinalities (drawn last so they're on top)
     fn draw_relationship_label(&self, canvas: &mut AsciiCanvas, rel: &PositionedRelationship) {
         if let Some(ref label) = rel.label {
-            let is_horizontal = rel.from_y == rel.to_y;
-
-            if is_horizontal {
-                let y = rel.from_y;
-                let (left_x, right_x) = if rel.from_x < rel.to_x {
-                    (rel.from_x, rel.to_x)
-                } else {
-                    (rel.to_x, rel.from_x)
-                };
-                let mid_x = (left_x + right_x) / 2;
-                let label_start = mid_x.saturating_sub(label.len() / 2);
-                canvas.draw_text(label_start, y.saturating_sub(1), label);
-            }
+            canvas.draw_text(rel.label_x, rel.label_y, label);
+        }
+        for end in rel.from_cardinality.iter().chain(&rel.to_cardinality) {
+            canvas.draw_text(end.x, end.y, &end.text);
         }
     }
 
     /// Render the layout to ASCII art
     pub fn render(&self, layout: &ClassLayoutResult) -> Result<String> {
+        Ok(match self.draw_layout(layout, self.color) {
+            Some((canvas, roles)) => roles.finish(&canvas),
+            None => String::new(),
+        })
+    }
+
+    /// Draw the layout, recording roles and owners when `record` is set
+    fn draw_layout(
+        &self,
+        layout: &ClassLayoutResult,
+        record: bool,
+    ) -> Option<(AsciiCanvas, RoleRecorder)> {
         if layout.classes.is_empty() {
-            return Ok(String::new());
+            return None;
         }
 
         // Add extra space for relationship lines
@@ -208,36 +305,94 @@ impl ClassRenderer {
             2
         };
         let mut canvas = AsciiCanvas::new(layout.width + 1, layout.height + extra_height + 1);
+        let mut roles = RoleRecorder::new(record, &canvas);
+        let id = |rel: &PositionedRelationship| format!("{}->{}", rel.from_class, rel.to_class);
 
         // Draw relationship lines first
         for rel in &layout.relationships {
-            self.draw_relationship_line(&mut canvas, rel);
+            canvas.track_writes();
+            self.draw_route(&mut canvas, rel);
+            roles.own_cells(&canvas.take_writes(), &id(rel));
         }
 
-        // Draw arrow heads (before classes, so they appear in gaps)
+        // Draw markers (before classes, so they appear in gaps)
         for rel in &layout.relationships {
-            self.draw_relationship_arrow(&mut canvas, rel);
+            canvas.track_writes();
+            self.draw_relationship_marker(&mut canvas, rel);
+            roles.own_cells(&canvas.take_writes(), &id(rel));
         }
+        roles.record(&canvas, Role::Edge);
 
         // Draw classes on top (overwrites any overlapping lines)
         for class in &layout.classes {
             self.draw_class(&mut canvas, class);
+            roles.own(class.x, class.y, class.width, class.height, &class.name);
+        }
+        roles.record(&canvas, Role::Node);
+        for class in &layout.classes {
+            Self::paint_compartments(&mut roles, &canvas, class);
         }
 
         // Draw relationship labels last (so they're visible on top)
         for rel in &layout.relationships {
+            canvas.track_writes();
             self.draw_relationship_label(&mut canvas, rel);
+            roles.own_cells(&canvas.take_writes(), &id(rel));
         }
+        roles.record(&canvas, Role::Label);
 
-        Ok(canvas.to_string())
+        Some((canvas, roles))
+    }
+
+    /// Mark the class name and member rows drawn by `draw_class`
+    fn paint_compartments(roles: &mut RoleRecorder, canvas: &AsciiCanvas, class: &PositionedClass) {
+        let inner = class.width.saturating_sub(2);
+        let mut y = class.y + 1;
+        roles.paint_text(canvas, class.x + 1, y, inner, Role::Header);
+        y += 1;
+
+        for section in [&class.attributes, &class.methods] {
+            if section.is_empty() {
+                continue;
+            }
+            y += 1; // separator
+            for _ in section.iter() {
+                roles.paint_text(canvas, class.x + 1, y, inner, Role::Member);
+                y += 1;
+            }
+        }
     }
 
     /// Convenience method to render directly from database
     pub fn render_database(&self, database: &ClassDatabase) -> Result<String> {
+        self.render_probed(database, &mut LayoutProbe::default())
+    }
+
+    /// Render from the database, timing the layout through `probe`
+    pub(crate) fn render_probed(
+        &self,
+        database: &ClassDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<String> {
         let layout = ClassLayoutAlgorithm::new();
+        let started = probe.start();
         let result = layout.layout(database)?;
+        probe.finish(started, result.width, result.height);
         self.render(&result)
     }
+
+    /// Render from the database as a [`CellGrid`]
+    ///
+    /// Each cell of a class box carries the class name, and cells of a
+    /// relationship's line, markers and labels carry `from->to`; roles
+    /// become palette colors when color is on.
+    pub fn render_cells(&self, database: &ClassDatabase) -> Result<CellGrid> {
+        let layout = ClassLayoutAlgorithm::new().layout(database)?;
+        Ok(match self.draw_layout(&layout, true) {
+            Some((canvas, roles)) => roles.finish_cells(&canvas, self.color),
+            None => CellGrid::default(),
+        })
+    }
 }
 
 impl Default for ClassRenderer {
@@ -246,6 +401,26 @@ impl Default for ClassRenderer {
     }
 }
 
+impl Renderer<ClassDatabase> for ClassRenderer {
+    type Output = String;
+
+    fn render(&self, database: &ClassDatabase) -> Result<Self::Output> {
+        self.render_database(database)
+    }
+
+    fn name(&self) -> &'static str {
+        "ascii"
+    }
+
+    fn version(&self) -> &'static str {
+        "0.1.0"
+    }
+
+    fn format(&self) -> &'static str {
+        "ascii"
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::database::{Class, Classifier, Member, Visibility};
@@ -416,4 +591,174 @@ mod tests {
 
         assert!(result.contains("places"));
     }
+
+    #[test]
+    fn test_render_cardinalities_beside_vertical_line() {
+        use super::super::database::Relationship;
+
+        let mut db = ClassDatabase::new();
+        for name in ["A", "B", "C", "Wheel"] {
+            db.add_class(Class::new(name)).unwrap();
+        }
+        db.add_relationship(
+            Relationship::new("A", "Wheel", RelationshipKind::Composition)
+                .with_label("has")
+                .with_from_cardinality("1")
+                .with_to_cardinality("4"),
+        )
+        .unwrap();
+
+        let result = ClassRenderer::new().render_database(&db).unwrap();
+        let lines: Vec<&str> = result.lines().collect();
+        let row = |text: &str| lines.iter().position(|l| l.contains(text)).unwrap();
+        // Diamond and top cardinality, label, bottom cardinality, then the box
+        assert!(row("◆ 1") < row("│ has"), "{}", result);
+        assert!(row("│ has") < row("│ 4"), "{}", result);
+        assert!(row("│ 4") < row("Wheel"), "{}", result);
+    }
+
+    #[test]
+    fn test_dashed_relationships_use_dotted_lines() {
+        use super::super::database::Relationship;
+
+        for kind in [RelationshipKind::Dependency, RelationshipKind::Realization] {
+            let mut db = ClassDatabase::new();
+            db.add_class(Class::new("Client")).unwrap();
+            db.add_class(Class::new("Service")).unwrap();
+            db.add_relationship(Relationship::new("Client", "Service", kind))
+                .unwrap();
+
+            let result = ClassRenderer::new().render_database(&db).unwrap();
+            let chars = EdgeChars::unicode_dotted();
+            assert!(
+                result.contains(chars.horizontal) || result.contains(chars.vertical),
+                "Expected dotted line for {:?}:\n{}",
+                kind,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_dependency_is_dotted_on_every_leg() {
+        use super::super::database::Relationship;
+
+        let render = |style| {
+            let mut db = ClassDatabase::new();
+            for name in ["A", "B", "C", "D"] {
+                db.add_class(Class::new(name)).unwrap();
+            }
+            db.add_relationship(Relationship::new("A", "B", RelationshipKind::Dependency))
+                .unwrap();
+            db.add_relationship(Relationship::new("C", "D", RelationshipKind::Dependency))
+                .unwrap();
+            ClassRenderer::with_style(style)
+                .render_database(&db)
+                .unwrap()
+        };
+
+        // Side by side, two dots clear of the marker; turning from C down
+        // to D, dotted all the way
+        let unicode = render(CharacterSet::Unicode);
+        assert!(unicode.contains("│┄┄▷│"), "{}", unicode);
+        assert!(unicode.contains("┄┄┄┄"), "{}", unicode);
+        assert!(unicode.contains('┆'), "{}", unicode);
+        let ascii = render(CharacterSet::Ascii);
+        assert!(ascii.contains("|..>|"), "{}", ascii);
+        assert!(ascii.contains("...."), "{}", ascii);
+        assert!(ascii.contains(':'), "{}", ascii);
+    }
+
+    #[test]
+    fn test_markers_follow_relationship_kind_and_style() {
+        let unicode = ClassRenderer::new();
+        let ascii = ClassRenderer::with_style(CharacterSet::Ascii);
+        let marker = |renderer: &ClassRenderer, kind, direction| {
+            renderer.marker_for(kind, direction).unwrap_or_default()
+        };
+        use RelationshipKind::*;
+
+        assert_eq!(marker(&unicode, Dependency, Direction::LeftRight), "▷");
+        assert_eq!(marker(&unicode, Association, Direction::LeftRight), "▶");
+        assert_eq!(marker(&unicode, Realization, Direction::TopDown), "▽");
+        assert_eq!(marker(&unicode, Inheritance, Direction::RightLeft), "◁");
+        assert_eq!(marker(&unicode, Composition, Direction::BottomUp), "◆");
+        assert_eq!(marker(&unicode, Aggregation, Direction::LeftRight), "◇");
+        assert_eq!(marker(&unicode, DashedLink, Direction::TopDown), "");
+
+        assert_eq!(marker(&ascii, Inheritance, Direction::RightLeft), "<|");
+        assert_eq!(marker(&ascii, Realization, Direction::LeftRight), "|>");
+        assert_eq!(marker(&ascii, Inheritance, Direction::BottomUp), "^");
+        assert_eq!(marker(&ascii, Composition, Direction::LeftRight), "*");
+        assert_eq!(marker(&ascii, Aggregation, Direction::LeftRight), "o");
+        assert_eq!(marker(&ascii, Association, Direction::LeftRight), ">");
+    }
+
+    #[test]
+    fn test_markers_sit_at_the_uml_end() {
+        use super::super::database::Relationship;
+
+        let render = |kind, style| {
+            let mut db = ClassDatabase::new();
+            db.add_class(Class::new("Parent")).unwrap();
+            db.add_class(Class::new("Child")).unwrap();
+            db.add_relationship(Relationship::new("Parent", "Child", kind))
+                .unwrap();
+            let output = ClassRenderer::with_style(style)
+                .render_database(&db)
+                .unwrap();
+            output.lines().nth(1).unwrap().to_string()
+        };
+
+        // `Parent <|-- Child` and `Parent *-- Child` mark the parent
+        let row = render(RelationshipKind::Inheritance, CharacterSet::Unicode);
+        assert!(row.contains("Parent │◁─"), "{}", row);
+        let row = render(RelationshipKind::Inheritance, CharacterSet::Ascii);
+        assert!(row.contains("Parent |<|-"), "{}", row);
+        let row = render(RelationshipKind::Composition, CharacterSet::Ascii);
+        assert!(row.contains("Parent |*-"), "{}", row);
+        // `Parent ..|> Child` and `Parent ..> Child` point at the child
+        let row = render(RelationshipKind::Realization, CharacterSet::Ascii);
+        assert!(row.contains("|>| Child"), "{}", row);
+        let row = render(RelationshipKind::Dependency, CharacterSet::Ascii);
+        assert!(row.contains(".>| Child"), "{}", row);
+    }
+
+    #[test]
+    fn test_solid_relationship_has_no_dotted_chars() {
+        use super::super::database::Relationship;
+
+        let mut db = ClassDatabase::new();
+        db.add_class(Class::new("Customer")).unwrap();
+        db.add_class(Class::new("Order")).unwrap();
+        db.add_relationship(Relationship::new(
+            "Customer",
+            "Order",
+            RelationshipKind::Association,
+        ))
+        .unwrap();
+
+        let result = ClassRenderer::new().render_database(&db).unwrap();
+        assert!(!result.contains('┄') && !result.contains('┆'));
+    }
+
+    #[test]
+    fn test_color_distinguishes_compartments() {
+        let mut db = ClassDatabase::new();
+        let mut class = Class::new("Animal");
+        class.add_attribute(Member::attribute("name").with_visibility(Visibility::Public));
+        db.add_class(class).unwrap();
+
+        let colored = ClassRenderer::new()
+            .with_color(true)
+            .render_database(&db)
+            .unwrap();
+        let palette = crate::core::Palette::default();
+        assert!(colored.contains(&format!(
+            "\x1b[{}mAnimal\x1b[0m",
+            palette.code(Role::Header)
+        )));
+        assert!(colored.contains(&format!("\x1b[{}m+name", palette.code(Role::Member))));
+        assert!(colored.contains(&format!("\x1b[{}m┌", palette.code(Role::Node))));
+    }
 }
@@ -0,0 +1,384 @@
+//! Entity relationship diagram database
+//!
+//! Stores entities with their attributes and the relationships between them.
+
+use crate::core::{Database, ParseWarning};
+use anyhow::Result;
+
+/// Key constraint on an attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeKey {
+    /// `PK`
+    Primary,
+    /// `FK`
+    Foreign,
+    /// `UK`
+    Unique,
+}
+
+impl AttributeKey {
+    /// Parse a key marker (`PK`, `FK` or `UK`)
+    pub fn from_marker(marker: &str) -> Option<Self> {
+        match marker {
+            "PK" => Some(AttributeKey::Primary),
+            "FK" => Some(AttributeKey::Foreign),
+            "UK" => Some(AttributeKey::Unique),
+            _ => None,
+        }
+    }
+
+    /// Marker as written in markup
+    pub fn marker(self) -> &'static str {
+        match self {
+            AttributeKey::Primary => "PK",
+            AttributeKey::Foreign => "FK",
+            AttributeKey::Unique => "UK",
+        }
+    }
+}
+
+/// An entity attribute such as `string name PK "comment"`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    /// Declared type
+    pub attr_type: String,
+    /// Attribute name
+    pub name: String,
+    /// Key constraints in declaration order
+    pub keys: Vec<AttributeKey>,
+    /// Trailing quoted comment
+    pub comment: Option<String>,
+}
+
+impl Attribute {
+    /// Create an attribute without keys or comment
+    pub fn new(attr_type: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            attr_type: attr_type.into(),
+            name: name.into(),
+            keys: Vec::new(),
+            comment: None,
+        }
+    }
+
+    /// Add a key constraint
+    pub fn with_key(mut self, key: AttributeKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Set the comment
+    pub fn with_comment(mut self, comment: impl Into<String>) -> Self {
+        self.comment = Some(comment.into());
+        self
+    }
+}
+
+/// An entity in the diagram
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entity {
+    /// Entity name, used to refer to it in relationships
+    pub name: String,
+    /// Display label from `name[Label]`, if different from the name
+    pub alias: Option<String>,
+    /// Attributes in declaration order
+    pub attributes: Vec<Attribute>,
+}
+
+impl Entity {
+    /// Create an entity without attributes
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            alias: None,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Set the display label
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+
+    /// Add an attribute
+    pub fn add_attribute(&mut self, attribute: Attribute) {
+        self.attributes.push(attribute);
+    }
+
+    /// Text shown in the entity's header
+    pub fn label(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// How many instances of an entity take part in a relationship
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cardinality {
+    /// `|o` / `o|`
+    ZeroOrOne,
+    /// `||`
+    ExactlyOne,
+    /// `}o` / `o{`
+    ZeroOrMore,
+    /// `}|` / `|{`
+    OneOrMore,
+}
+
+impl Cardinality {
+    /// Parse the marker on the left of a relationship (`|o`, `||`, `}o`, `}|`)
+    pub fn from_left(marker: &str) -> Option<Self> {
+        match marker {
+            "|o" => Some(Cardinality::ZeroOrOne),
+            "||" => Some(Cardinality::ExactlyOne),
+            "}o" => Some(Cardinality::ZeroOrMore),
+            "}|" => Some(Cardinality::OneOrMore),
+            _ => None,
+        }
+    }
+
+    /// Parse the marker on the right of a relationship (`o|`, `||`, `o{`, `|{`)
+    pub fn from_right(marker: &str) -> Option<Self> {
+        match marker {
+            "o|" => Some(Cardinality::ZeroOrOne),
+            "||" => Some(Cardinality::ExactlyOne),
+            "o{" => Some(Cardinality::ZeroOrMore),
+            "|{" => Some(Cardinality::OneOrMore),
+            _ => None,
+        }
+    }
+
+    /// Whether zero instances are allowed
+    pub fn is_optional(self) -> bool {
+        matches!(self, Cardinality::ZeroOrOne | Cardinality::ZeroOrMore)
+    }
+
+    /// Whether more than one instance is allowed
+    pub fn is_many(self) -> bool {
+        matches!(self, Cardinality::ZeroOrMore | Cardinality::OneOrMore)
+    }
+}
+
+/// A relationship between two entities
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relationship {
+    /// First entity name
+    pub from: String,
+    /// Second entity name
+    pub to: String,
+    /// Cardinality at the first entity
+    pub from_cardinality: Cardinality,
+    /// Cardinality at the second entity
+    pub to_cardinality: Cardinality,
+    /// Solid `--` (identifying) rather than dashed `..` line
+    pub identifying: bool,
+    /// Label after `:`
+    pub label: Option<String>,
+}
+
+impl Relationship {
+    /// Create an unlabeled identifying relationship
+    pub fn new(
+        from: impl Into<String>,
+        from_cardinality: Cardinality,
+        to: impl Into<String>,
+        to_cardinality: Cardinality,
+    ) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+            from_cardinality,
+            to_cardinality,
+            identifying: true,
+            label: None,
+        }
+    }
+
+    /// Draw the relationship dashed (`..`)
+    pub fn non_identifying(mut self) -> Self {
+        self.identifying = false;
+        self
+    }
+
+    /// Set the label
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+}
+
+/// Entity relationship diagram database
+#[derive(Debug, Clone, Default)]
+pub struct ErDatabase {
+    entities: Vec<Entity>,
+    relationships: Vec<Relationship>,
+    /// Warnings recorded while parsing into this database
+    warnings: Vec<ParseWarning>,
+}
+
+impl ErDatabase {
+    /// Create an empty database
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a parse warning
+    pub(crate) fn add_parse_warning(&mut self, warning: ParseWarning) {
+        self.warnings.push(warning);
+    }
+
+    /// Add an entity, merging its alias and attributes into an existing one
+    /// of the same name
+    pub fn add_entity(&mut self, entity: Entity) -> Result<()> {
+        match self.get_entity_mut(&entity.name) {
+            Some(existing) => {
+                if entity.alias.is_some() {
+                    existing.alias = entity.alias;
+                }
+                existing.attributes.extend(entity.attributes);
+            }
+            None => self.entities.push(entity),
+        }
+        Ok(())
+    }
+
+    /// Add a relationship, creating any entity it names that doesn't exist yet
+    pub fn add_relationship(&mut self, relationship: Relationship) -> Result<()> {
+        self.get_or_create_entity(&relationship.from);
+        self.get_or_create_entity(&relationship.to);
+        self.relationships.push(relationship);
+        Ok(())
+    }
+
+    /// All entities in declaration order
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// All relationships in declaration order
+    pub fn relationships(&self) -> &[Relationship] {
+        &self.relationships
+    }
+
+    /// Number of entities
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Number of relationships
+    pub fn relationship_count(&self) -> usize {
+        self.relationships.len()
+    }
+
+    /// Look up an entity by name
+    pub fn get_entity(&self, name: &str) -> Option<&Entity> {
+        self.entities.iter().find(|e| e.name == name)
+    }
+
+    /// Look up an entity by name for editing
+    pub fn get_entity_mut(&mut self, name: &str) -> Option<&mut Entity> {
+        self.entities.iter_mut().find(|e| e.name == name)
+    }
+
+    /// Get or create an entity by name
+    pub fn get_or_create_entity(&mut self, name: &str) -> &mut Entity {
+        if self.get_entity(name).is_none() {
+            self.entities.push(Entity::new(name));
+        }
+        self.get_entity_mut(name).unwrap()
+    }
+}
+
+impl Database for ErDatabase {
+    type Node = Entity;
+    type Edge = Relationship;
+
+    fn parse_warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    fn take_parse_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn add_node(&mut self, node: Self::Node) -> Result<()> {
+        self.add_entity(node)
+    }
+
+    fn add_edge(&mut self, edge: Self::Edge) -> Result<()> {
+        self.add_relationship(edge)
+    }
+
+    fn get_node(&self, id: &str) -> Option<&Self::Node> {
+        self.get_entity(id)
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = &Self::Node> {
+        self.entities.iter()
+    }
+
+    fn edges(&self) -> impl Iterator<Item = &Self::Edge> {
+        self.relationships.iter()
+    }
+
+    fn clear(&mut self) {
+        self.entities.clear();
+        self.relationships.clear();
+    }
+
+    fn node_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.relationships.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relationship_creates_missing_entities() {
+        let mut db = ErDatabase::new();
+        db.add_relationship(Relationship::new(
+            "CUSTOMER",
+            Cardinality::ExactlyOne,
+            "ORDER",
+            Cardinality::ZeroOrMore,
+        ))
+        .unwrap();
+
+        assert_eq!(db.entity_count(), 2);
+        assert_eq!(db.relationship_count(), 1);
+        assert!(db.get_entity("ORDER").is_some());
+    }
+
+    #[test]
+    fn test_repeated_entity_blocks_merge() {
+        let mut db = ErDatabase::new();
+        let mut first = Entity::new("CUSTOMER");
+        first.add_attribute(Attribute::new("string", "name"));
+        db.add_entity(first).unwrap();
+
+        let mut second = Entity::new("CUSTOMER").with_alias("Customer");
+        second.add_attribute(Attribute::new("int", "id").with_key(AttributeKey::Primary));
+        db.add_entity(second).unwrap();
+
+        let customer = db.get_entity("CUSTOMER").unwrap();
+        assert_eq!(db.entity_count(), 1);
+        assert_eq!(customer.label(), "Customer");
+        assert_eq!(customer.attributes.len(), 2);
+    }
+
+    #[test]
+    fn test_cardinality_markers() {
+        assert_eq!(Cardinality::from_left("}o"), Some(Cardinality::ZeroOrMore));
+        assert_eq!(Cardinality::from_right("o{"), Some(Cardinality::ZeroOrMore));
+        assert_eq!(Cardinality::from_right("}o"), None);
+        assert!(Cardinality::ZeroOrOne.is_optional());
+        assert!(!Cardinality::ExactlyOne.is_many());
+        assert!(Cardinality::OneOrMore.is_many());
+    }
+}
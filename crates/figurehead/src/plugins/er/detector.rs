@@ -0,0 +1,83 @@
+//! Entity relationship diagram detector
+//!
+//! Identifies `erDiagram` syntax from input text.
+
+use crate::core::{header_keyword, header_line, Detector};
+
+/// Left halves of the relationship operators, e.g. `||--` in `||--o{`
+const OPERATOR_STARTS: [&str; 8] = [
+    "||--", "|o--", "}o--", "}|--", "||..", "|o..", "}o..", "}|..",
+];
+
+/// Detector for entity relationship diagram syntax
+pub struct ErDetector;
+
+impl ErDetector {
+    /// Create a detector
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ErDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Detector for ErDetector {
+    fn detect(&self, input: &str) -> bool {
+        self.confidence(input) > 0.5
+    }
+
+    fn confidence(&self, input: &str) -> f64 {
+        let first_line = header_line(input);
+
+        if first_line.to_lowercase().starts_with("erdiagram") {
+            return 1.0;
+        }
+
+        // Crow's foot operators don't appear in other diagram types
+        if OPERATOR_STARTS.iter().any(|op| input.contains(op)) {
+            return 0.6;
+        }
+
+        0.0
+    }
+
+    fn diagram_type(&self) -> &'static str {
+        "er"
+    }
+
+    fn prefix_confidence(&self, header: &str) -> Option<f64> {
+        header_keyword(header, "erDiagram").then_some(1.0)
+    }
+
+    fn patterns(&self) -> Vec<&'static str> {
+        vec!["erDiagram", "||--o{", "}|..|{"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_er_diagram() {
+        let detector = ErDetector::new();
+        assert_eq!(
+            detector.confidence("erDiagram\n    CUSTOMER ||--o{ ORDER : places"),
+            1.0
+        );
+        assert!(detector.detect("CUSTOMER ||--o{ ORDER : places"));
+        assert!(detector.detect("CUSTOMER }|..|{ ADDRESS : uses"));
+    }
+
+    #[test]
+    fn test_rejects_other_diagrams() {
+        let detector = ErDetector::new();
+        assert!(!detector.detect("graph TD; A-->B"));
+        assert!(!detector.detect("classDiagram\n    Animal <|-- Dog"));
+        assert!(!detector.detect("stateDiagram-v2\n    [*] --> Idle"));
+    }
+}
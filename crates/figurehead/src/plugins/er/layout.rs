@@ -0,0 +1,709 @@
+//! Entity relationship diagram layout algorithm
+//!
+//! Entities are stacked in ranks so that every relationship runs downward
+//! from the entity that names it first. Relationships leave the bottom of
+//! the upper entity and enter the top of the lower one, with each end given
+//! its own attachment column so the crow's foot markers never overlap.
+//!
+//! The gap below each rank is split into bands, top to bottom: two rows for
+//! markers at the upper entities, one row per label, one row per horizontal
+//! run, and two rows for markers at the lower entities. Relationships within
+//! a rank (and self relationships) hang below it as a U, and relationships
+//! skipping ranks detour through a channel right of every entity.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use unicode_width::UnicodeWidthStr;
+
+use super::database::{Attribute, Cardinality, Entity, ErDatabase};
+
+/// Positioned entity box for rendering
+#[derive(Debug, Clone)]
+pub struct PositionedEntity {
+    pub name: String,
+    pub label: String,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    /// Attribute rows, already aligned into columns
+    pub attributes: Vec<String>,
+}
+
+/// Cardinality marker at one end of a relationship
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndMarker {
+    /// Column of the line at this end
+    pub x: usize,
+    /// Row next to the entity border
+    pub y: usize,
+    pub cardinality: Cardinality,
+    /// The marker hangs below its entity (otherwise it sits above it)
+    pub below_entity: bool,
+}
+
+/// Positioned relationship for rendering
+#[derive(Debug, Clone)]
+pub struct PositionedRelationship {
+    pub from: String,
+    pub to: String,
+    pub identifying: bool,
+    pub label: Option<String>,
+    /// Top-left cell of the label
+    pub label_x: usize,
+    pub label_y: usize,
+    /// Orthogonal route from the `from` end to the `to` end
+    pub points: Vec<(usize, usize)>,
+    pub from_end: EndMarker,
+    pub to_end: EndMarker,
+}
+
+/// Layout result containing all positioned elements
+#[derive(Debug)]
+pub struct ErLayoutResult {
+    pub entities: Vec<PositionedEntity>,
+    pub relationships: Vec<PositionedRelationship>,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// How a relationship is routed between its entities
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RouteKind {
+    /// Adjacent ranks: down, across, down
+    Direct,
+    /// Same rank or same entity: down, across, back up
+    Hanging,
+    /// Ranks further apart: down, out to the channel, down, back in, down
+    Detour,
+}
+
+/// Routing decisions for one relationship
+#[derive(Debug, Clone)]
+struct Route {
+    kind: RouteKind,
+    /// Upper entity (the left one for hanging routes)
+    upper: usize,
+    /// Lower entity (the right one for hanging routes)
+    lower: usize,
+    /// The relationship's `from` entity is `lower`
+    flipped: bool,
+    upper_x: usize,
+    lower_x: usize,
+    /// Turn row indices within the first and (for detours) last gap
+    turns: (Option<usize>, Option<usize>),
+    label_row: Option<usize>,
+    channel: usize,
+}
+
+/// Relationships crossing the gap below one rank
+#[derive(Debug, Default, Clone)]
+struct Gap {
+    labels: usize,
+    turns: usize,
+    used: bool,
+}
+
+/// Entity relationship diagram layout algorithm
+pub struct ErLayoutAlgorithm {
+    box_padding: usize,
+    entity_spacing: usize,
+    rank_sep: usize,
+    channel_spacing: usize,
+}
+
+impl ErLayoutAlgorithm {
+    pub fn new() -> Self {
+        Self {
+            box_padding: 1,
+            entity_spacing: 4,
+            rank_sep: 2,
+            channel_spacing: 2,
+        }
+    }
+
+    /// Format attributes into aligned `type name keys "comment"` rows
+    fn format_attributes(attributes: &[Attribute]) -> Vec<String> {
+        let type_width = attributes
+            .iter()
+            .map(|a| a.attr_type.width())
+            .max()
+            .unwrap_or(0);
+        let name_width = attributes.iter().map(|a| a.name.width()).max().unwrap_or(0);
+        let keys: Vec<String> = attributes
+            .iter()
+            .map(|a| {
+                a.keys
+                    .iter()
+                    .map(|k| k.marker())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect();
+        let keys_width = keys.iter().map(|k| k.width()).max().unwrap_or(0);
+
+        attributes
+            .iter()
+            .zip(&keys)
+            .map(|(attribute, keys)| {
+                let mut row = format!(
+                    "{}{} {}",
+                    attribute.attr_type,
+                    " ".repeat(type_width - attribute.attr_type.width()),
+                    attribute.name
+                );
+                if keys_width > 0 {
+                    row.push_str(&" ".repeat(name_width - attribute.name.width() + 1));
+                    row.push_str(keys);
+                }
+                if let Some(comment) = &attribute.comment {
+                    row.push_str(&" ".repeat(keys_width - keys.width() + 1));
+                    row.push_str(&format!("\"{}\"", comment));
+                }
+                row.trim_end().to_string()
+            })
+            .collect()
+    }
+
+    /// Mark relationships that close a cycle, found by depth-first search
+    fn back_edges(entity_count: usize, edges: &[(usize, usize)]) -> Vec<bool> {
+        fn visit(node: usize, edges: &[(usize, usize)], state: &mut [u8], back: &mut [bool]) {
+            state[node] = 1;
+            for (index, &(from, to)) in edges.iter().enumerate() {
+                if from != node || from == to {
+                    continue;
+                }
+                match state[to] {
+                    0 => visit(to, edges, state, back),
+                    1 => back[index] = true,
+                    _ => {}
+                }
+            }
+            state[node] = 2;
+        }
+
+        let mut state = vec![0u8; entity_count];
+        let mut back = vec![false; edges.len()];
+        for node in 0..entity_count {
+            if state[node] == 0 {
+                visit(node, edges, &mut state, &mut back);
+            }
+        }
+        back
+    }
+
+    /// Assign each entity the length of the longest relationship path above it
+    fn assign_ranks(entity_count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+        let back = Self::back_edges(entity_count, edges);
+        let forward: Vec<(usize, usize)> = edges
+            .iter()
+            .zip(&back)
+            .filter(|((from, to), back)| from != to && !**back)
+            .map(|(edge, _)| *edge)
+            .collect();
+
+        let mut rank = vec![0; entity_count];
+        for _ in 0..entity_count {
+            let mut changed = false;
+            for &(from, to) in &forward {
+                if rank[to] < rank[from] + 1 {
+                    rank[to] = rank[from] + 1;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        rank
+    }
+
+    /// Box size for an entity with `attachments` lines on its busiest side
+    fn entity_size(&self, entity: &Entity, rows: &[String], attachments: usize) -> (usize, usize) {
+        let content = rows
+            .iter()
+            .map(|r| r.width())
+            .chain(std::iter::once(entity.label().width()))
+            .max()
+            .unwrap_or(0);
+        // Crow's feet are three cells wide, so keep attachments four apart
+        let width = (content + self.box_padding * 2 + 2).max(attachments * 4 + 1);
+        let height = if rows.is_empty() { 3 } else { 4 + rows.len() };
+        (width, height)
+    }
+
+    /// Layout the diagram
+    pub fn layout(&self, database: &ErDatabase) -> Result<ErLayoutResult> {
+        let entities = database.entities();
+        if entities.is_empty() {
+            return Ok(ErLayoutResult {
+                entities: Vec::new(),
+                relationships: Vec::new(),
+                width: 0,
+                height: 0,
+            });
+        }
+
+        let index: HashMap<&str, usize> = entities
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.name.as_str(), i))
+            .collect();
+        let edges: Vec<(usize, usize)> = database
+            .relationships()
+            .iter()
+            .filter_map(|r| Some((*index.get(r.from.as_str())?, *index.get(r.to.as_str())?)))
+            .collect();
+        let relationships: Vec<_> = database
+            .relationships()
+            .iter()
+            .filter(|r| index.contains_key(r.from.as_str()) && index.contains_key(r.to.as_str()))
+            .collect();
+
+        // Ranks, ordered within each rank by the average position of the
+        // entities they connect to in the rank above
+        let rank = Self::assign_ranks(entities.len(), &edges);
+        let rank_count = rank.iter().max().map_or(0, |r| r + 1);
+        let mut ranks: Vec<Vec<usize>> = vec![Vec::new(); rank_count];
+        for (entity, &r) in rank.iter().enumerate() {
+            ranks[r].push(entity);
+        }
+        let mut order = vec![0.0; entities.len()];
+        for (r, members) in ranks.iter_mut().enumerate() {
+            if r > 0 {
+                let key = |entity: usize, fallback: f64| {
+                    let above: Vec<f64> = edges
+                        .iter()
+                        .filter_map(|&(a, b)| match (a == entity, b == entity) {
+                            (true, false) if rank[b] + 1 == r => Some(order[b]),
+                            (false, true) if rank[a] + 1 == r => Some(order[a]),
+                            _ => None,
+                        })
+                        .collect();
+                    if above.is_empty() {
+                        fallback
+                    } else {
+                        above.iter().sum::<f64>() / above.len() as f64
+                    }
+                };
+                let mut keyed: Vec<(f64, usize)> = members
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &e)| (key(e, i as f64), e))
+                    .collect();
+                keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+                *members = keyed.into_iter().map(|(_, e)| e).collect();
+            }
+            for (i, &entity) in members.iter().enumerate() {
+                order[entity] = i as f64;
+            }
+        }
+
+        // Classify routes; entity indices are placeholders until positions exist
+        let mut routes: Vec<Route> = edges
+            .iter()
+            .map(|&(from, to)| {
+                let flipped =
+                    rank[from] > rank[to] || (rank[from] == rank[to] && order[from] > order[to]);
+                let (upper, lower) = if flipped { (to, from) } else { (from, to) };
+                let kind = match rank[lower] - rank[upper] {
+                    0 => RouteKind::Hanging,
+                    1 => RouteKind::Direct,
+                    _ => RouteKind::Detour,
+                };
+                Route {
+                    kind,
+                    upper,
+                    lower,
+                    flipped,
+                    upper_x: 0,
+                    lower_x: 0,
+                    turns: (None, None),
+                    label_row: None,
+                    channel: 0,
+                }
+            })
+            .collect();
+
+        // Which entity sides each route attaches to: (entity, bottom side)
+        let mut sides: HashMap<(usize, bool), Vec<(usize, bool)>> = HashMap::new();
+        for (i, route) in routes.iter().enumerate() {
+            sides
+                .entry((route.upper, true))
+                .or_default()
+                .push((i, true));
+            let lower_bottom = route.kind == RouteKind::Hanging;
+            sides
+                .entry((route.lower, lower_bottom))
+                .or_default()
+                .push((i, false));
+        }
+
+        // Box sizes
+        let rows: Vec<Vec<String>> = entities
+            .iter()
+            .map(|e| Self::format_attributes(&e.attributes))
+            .collect();
+        let sizes: Vec<(usize, usize)> = entities
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                let busiest = [true, false]
+                    .iter()
+                    .map(|&bottom| sides.get(&(i, bottom)).map_or(0, Vec::len))
+                    .max()
+                    .unwrap_or(0);
+                self.entity_size(e, &rows[i], busiest)
+            })
+            .collect();
+
+        // Horizontal placement, each rank centred under the widest
+        let rank_widths: Vec<usize> = ranks
+            .iter()
+            .map(|members| {
+                members.iter().map(|&e| sizes[e].0).sum::<usize>()
+                    + members.len().saturating_sub(1) * self.entity_spacing
+            })
+            .collect();
+        let content_width = rank_widths.iter().copied().max().unwrap_or(0);
+        let mut xs = vec![0; entities.len()];
+        for (members, width) in ranks.iter().zip(&rank_widths) {
+            let mut x = (content_width - width) / 2;
+            for &entity in members {
+                xs[entity] = x;
+                x += sizes[entity].0 + self.entity_spacing;
+            }
+        }
+        let center = |entity: usize| xs[entity] + sizes[entity].0 / 2;
+
+        // Spread attachment columns along each side, ordered by where the
+        // other end lies so neighbouring lines don't cross
+        for ((entity, _), ends) in sides.iter_mut() {
+            let other = |&(route, upper_end): &(usize, bool)| {
+                let r = &routes[route];
+                let other = if upper_end { r.lower } else { r.upper };
+                // Both ends of a self relationship sit on the same side
+                (center(other), !upper_end)
+            };
+            ends.sort_by_key(other);
+            let width = sizes[*entity].0;
+            for (k, &(route, upper_end)) in ends.iter().enumerate() {
+                let x = xs[*entity] + (k + 1) * width / (ends.len() + 1);
+                if upper_end {
+                    routes[route].upper_x = x;
+                } else {
+                    routes[route].lower_x = x;
+                }
+            }
+        }
+
+        // Claim label and turn rows in the gaps
+        let mut gaps = vec![Gap::default(); rank_count];
+        let mut channels = 0;
+        for (i, route) in routes.iter_mut().enumerate() {
+            let first = rank[route.upper];
+            let gap = &mut gaps[first];
+            gap.used = true;
+            if relationships[i].label.is_some() {
+                route.label_row = Some(gap.labels);
+                gap.labels += 1;
+            }
+            let needs_turn = route.kind != RouteKind::Direct || route.upper_x != route.lower_x;
+            if needs_turn {
+                route.turns.0 = Some(gap.turns);
+                gap.turns += 1;
+            }
+            if route.kind == RouteKind::Detour {
+                let last = &mut gaps[rank[route.lower] - 1];
+                last.used = true;
+                route.turns.1 = Some(last.turns);
+                last.turns += 1;
+                route.channel = channels;
+                channels += 1;
+            }
+        }
+
+        // Vertical placement
+        let row_heights: Vec<usize> = ranks
+            .iter()
+            .map(|members| members.iter().map(|&e| sizes[e].1).max().unwrap_or(0))
+            .collect();
+        let mut rank_y = Vec::with_capacity(rank_count);
+        let mut y = 0;
+        for (r, gap) in gaps.iter().enumerate() {
+            rank_y.push(y);
+            let last = r + 1 == rank_count;
+            let gap_height = match (gap.used, last) {
+                (true, false) => 4 + gap.labels + gap.turns,
+                (true, true) => 2 + gap.labels + gap.turns,
+                (false, false) => self.rank_sep,
+                (false, true) => 0,
+            };
+            y += row_heights[r] + gap_height;
+        }
+        let height = y;
+        let gap_top = |r: usize| rank_y[r] + row_heights[r];
+        let turn_row = |r: usize, turn: usize| gap_top(r) + 2 + gaps[r].labels + turn;
+
+        let positioned_entities: Vec<PositionedEntity> = entities
+            .iter()
+            .enumerate()
+            .map(|(i, e)| PositionedEntity {
+                name: e.name.clone(),
+                label: e.label().to_string(),
+                x: xs[i],
+                y: rank_y[rank[i]],
+                width: sizes[i].0,
+                height: sizes[i].1,
+                attributes: rows[i].clone(),
+            })
+            .collect();
+
+        let mut width = content_width;
+        let channel_x = |channel: usize| content_width + self.channel_spacing * (channel + 1) - 1;
+
+        // Vertical lines crossing each gap's label rows
+        let mut legs: Vec<Vec<usize>> = vec![Vec::new(); rank_count];
+        for route in &routes {
+            let first = rank[route.upper];
+            legs[first].push(route.upper_x);
+            if route.kind == RouteKind::Hanging {
+                legs[first].push(route.lower_x);
+            }
+            if route.kind == RouteKind::Detour {
+                for gap in &mut legs[first + 1..rank[route.lower]] {
+                    gap.push(channel_x(route.channel));
+                }
+            }
+        }
+        let mut positioned_relationships = Vec::with_capacity(routes.len());
+        for (route, relationship) in routes.iter().zip(&relationships) {
+            let upper = &positioned_entities[route.upper];
+            let lower = &positioned_entities[route.lower];
+            let (ux, lx) = (route.upper_x, route.lower_x);
+            let start = (ux, upper.y + upper.height);
+            let first_gap = rank[route.upper];
+
+            let mut points = vec![start];
+            let lower_below = route.kind == RouteKind::Hanging;
+            match route.kind {
+                RouteKind::Direct => {
+                    if let Some(turn) = route.turns.0 {
+                        let ty = turn_row(first_gap, turn);
+                        points.push((ux, ty));
+                        points.push((lx, ty));
+                    }
+                    points.push((lx, lower.y - 1));
+                }
+                RouteKind::Hanging => {
+                    let ty = turn_row(first_gap, route.turns.0.unwrap_or(0));
+                    points.push((ux, ty));
+                    points.push((lx, ty));
+                    points.push((lx, lower.y + lower.height));
+                }
+                RouteKind::Detour => {
+                    let cx = channel_x(route.channel);
+                    let t1 = turn_row(first_gap, route.turns.0.unwrap_or(0));
+                    let t2 = turn_row(rank[route.lower] - 1, route.turns.1.unwrap_or(0));
+                    points.extend([(ux, t1), (cx, t1), (cx, t2), (lx, t2)]);
+                    points.push((lx, lower.y - 1));
+                    width = width.max(cx + 1);
+                }
+            }
+
+            let (label_x, label_y) = match route.label_row {
+                Some(row) => {
+                    let leg = if route.kind == RouteKind::Hanging {
+                        ux.max(lx)
+                    } else {
+                        ux
+                    };
+                    // Keep the label beside its own line: right of it, else
+                    // left of it, else step past the lines it would cut
+                    let label_width = relationship.label.as_deref().map_or(0, |l| l.width());
+                    let crossing = |x: usize, end: usize| {
+                        legs[first_gap]
+                            .iter()
+                            .filter(|&&c| c >= x && c < end)
+                            .max()
+                            .copied()
+                    };
+                    let right = leg + 2;
+                    let left = leg.checked_sub(label_width + 1);
+                    let x = if crossing(right, right + label_width).is_none() {
+                        right
+                    } else if let Some(left) = left.filter(|&x| crossing(x, leg).is_none()) {
+                        left
+                    } else {
+                        let mut x = right;
+                        while let Some(c) = crossing(x, x + label_width) {
+                            x = c + 2;
+                        }
+                        x
+                    };
+                    (x, gap_top(first_gap) + 2 + row)
+                }
+                None => (0, 0),
+            };
+            if let Some(label) = &relationship.label {
+                width = width.max(label_x + label.width());
+            }
+
+            let upper_end = EndMarker {
+                x: ux,
+                y: start.1,
+                cardinality: Cardinality::ExactlyOne,
+                below_entity: true,
+            };
+            let lower_end = EndMarker {
+                x: lx,
+                y: points.last().map_or(0, |p| p.1),
+                cardinality: Cardinality::ExactlyOne,
+                below_entity: lower_below,
+            };
+            let (mut from_end, mut to_end) = (upper_end, lower_end);
+            if route.flipped {
+                points.reverse();
+                std::mem::swap(&mut from_end, &mut to_end);
+            }
+            from_end.cardinality = relationship.from_cardinality;
+            to_end.cardinality = relationship.to_cardinality;
+
+            positioned_relationships.push(PositionedRelationship {
+                from: relationship.from.clone(),
+                to: relationship.to.clone(),
+                identifying: relationship.identifying,
+                label: relationship.label.clone(),
+                label_x,
+                label_y,
+                points,
+                from_end,
+                to_end,
+            });
+        }
+
+        Ok(ErLayoutResult {
+            entities: positioned_entities,
+            relationships: positioned_relationships,
+            width,
+            height,
+        })
+    }
+}
+
+impl Default for ErLayoutAlgorithm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::database::{AttributeKey, Relationship};
+    use super::*;
+
+    fn relate(db: &mut ErDatabase, from: &str, to: &str) {
+        db.add_relationship(Relationship::new(
+            from,
+            Cardinality::ExactlyOne,
+            to,
+            Cardinality::ZeroOrMore,
+        ))
+        .unwrap();
+    }
+
+    fn entity<'a>(result: &'a ErLayoutResult, name: &str) -> &'a PositionedEntity {
+        result.entities.iter().find(|e| e.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_empty_layout() {
+        let result = ErLayoutAlgorithm::new().layout(&ErDatabase::new()).unwrap();
+        assert!(result.entities.is_empty());
+        assert_eq!((result.width, result.height), (0, 0));
+    }
+
+    #[test]
+    fn test_attributes_align_in_columns() {
+        let rows = ErLayoutAlgorithm::format_attributes(&[
+            Attribute::new("string", "name"),
+            Attribute::new("int", "id").with_key(AttributeKey::Primary),
+            Attribute::new("int", "customer_id")
+                .with_key(AttributeKey::Foreign)
+                .with_comment("owner"),
+        ]);
+        assert_eq!(
+            rows,
+            vec![
+                "string name",
+                "int    id          PK",
+                "int    customer_id FK \"owner\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relationships_run_downward() {
+        let mut db = ErDatabase::new();
+        relate(&mut db, "CUSTOMER", "ORDER");
+        relate(&mut db, "ORDER", "LINE-ITEM");
+
+        let result = ErLayoutAlgorithm::new().layout(&db).unwrap();
+        let customer = entity(&result, "CUSTOMER");
+        let order = entity(&result, "ORDER");
+        let item = entity(&result, "LINE-ITEM");
+        assert!(customer.y + customer.height < order.y);
+        assert!(order.y + order.height < item.y);
+
+        let places = &result.relationships[0];
+        assert_eq!(places.from_end.y, customer.y + customer.height);
+        assert!(places.from_end.below_entity);
+        assert_eq!(places.to_end.y, order.y - 1);
+        assert!(!places.to_end.below_entity);
+    }
+
+    #[test]
+    fn test_cycles_and_self_relationships_terminate() {
+        let mut db = ErDatabase::new();
+        relate(&mut db, "A", "B");
+        relate(&mut db, "B", "A");
+        relate(&mut db, "A", "A");
+
+        let result = ErLayoutAlgorithm::new().layout(&db).unwrap();
+        assert_eq!(result.relationships.len(), 3);
+        let a = entity(&result, "A");
+        let own = &result.relationships[2];
+        assert!(own.from_end.below_entity && own.to_end.below_entity);
+        assert_ne!(own.from_end.x, own.to_end.x);
+        assert!(own.points.iter().all(|&(_, y)| y >= a.y + a.height));
+    }
+
+    #[test]
+    fn test_long_relationships_detour_right_of_entities() {
+        let mut db = ErDatabase::new();
+        relate(&mut db, "A", "B");
+        relate(&mut db, "B", "C");
+        relate(&mut db, "A", "C");
+
+        let result = ErLayoutAlgorithm::new().layout(&db).unwrap();
+        let right = result.entities.iter().map(|e| e.x + e.width).max().unwrap();
+        let detour = &result.relationships[2];
+        assert!(detour.points.iter().any(|&(x, _)| x >= right));
+        assert!(result.width > right);
+    }
+
+    #[test]
+    fn test_labels_fit_the_canvas() {
+        let mut db = ErDatabase::new();
+        db.add_relationship(
+            Relationship::new("A", Cardinality::ExactlyOne, "B", Cardinality::OneOrMore)
+                .with_label("a rather long relationship label"),
+        )
+        .unwrap();
+
+        let result = ErLayoutAlgorithm::new().layout(&db).unwrap();
+        let rel = &result.relationships[0];
+        assert!(rel.label_x + 32 <= result.width);
+        assert!(rel.label_y > rel.from_end.y && rel.label_y < rel.to_end.y);
+    }
+}
@@ -0,0 +1,63 @@
+//! Entity relationship diagram plugin
+//!
+//! Renders entities as boxes listing their attributes, joined by
+//! relationships with crow's foot cardinality markers.
+//!
+//! Syntax examples:
+//! ```text
+//! erDiagram
+//!     CUSTOMER ||--o{ ORDER : places
+//!     ORDER ||--|{ LINE-ITEM : contains
+//!     CUSTOMER {
+//!         string name
+//!         int id PK
+//!     }
+//! ```
+
+mod database;
+mod detector;
+#[allow(missing_docs)]
+pub(crate) mod layout;
+mod parser;
+mod renderer;
+
+pub use database::{Attribute, AttributeKey, Cardinality, Entity, ErDatabase, Relationship};
+pub use detector::ErDetector;
+pub use parser::ErParser;
+pub use renderer::ErRenderer;
+
+use crate::core::{Detector, Diagram};
+use std::sync::Arc;
+
+/// Entity relationship diagram implementation
+pub struct ErDiagram;
+
+impl Diagram for ErDiagram {
+    type Database = ErDatabase;
+    type Parser = ErParser;
+    type Renderer = ErRenderer;
+
+    fn detector() -> Arc<dyn Detector> {
+        Arc::new(ErDetector::new())
+    }
+
+    fn create_parser() -> Self::Parser {
+        ErParser::new()
+    }
+
+    fn create_database() -> Self::Database {
+        ErDatabase::new()
+    }
+
+    fn create_renderer() -> Self::Renderer {
+        ErRenderer::new()
+    }
+
+    fn name() -> &'static str {
+        "er"
+    }
+
+    fn version() -> &'static str {
+        "0.1.0"
+    }
+}
@@ -0,0 +1,400 @@
+//! Entity relationship diagram parser using chumsky
+//!
+//! Parses `erDiagram` syntax into the database. Entity blocks span several
+//! lines, so the input is read line by line and attribute lines are collected
+//! until the closing `}`.
+
+use super::database::{Attribute, AttributeKey, Cardinality, Entity, ErDatabase, Relationship};
+use crate::core::{normalize_input, FigureheadError, ParseWarning, Parser as CoreParser};
+use anyhow::Result;
+use chumsky::prelude::*;
+use tracing::{debug, warn};
+
+/// An entity as referenced in a statement: `NAME` or `NAME[Label]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityRef {
+    /// Entity name
+    pub name: String,
+    /// Display label given in brackets
+    pub alias: Option<String>,
+}
+
+/// Parsed ER diagram statement
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    /// Relationship: `A ||--o{ B : label`
+    Relationship {
+        from: EntityRef,
+        from_cardinality: Cardinality,
+        identifying: bool,
+        to_cardinality: Cardinality,
+        to: EntityRef,
+        label: Option<String>,
+    },
+    /// Start of an attribute block: `A {` (with `}` on the same line for an
+    /// empty block)
+    EntityBlock { entity: EntityRef, closed: bool },
+    /// A bare entity declaration: `A`
+    Entity(EntityRef),
+}
+
+type Extra<'src> = extra::Err<Rich<'src, char>>;
+
+/// ER diagram parser
+pub struct ErParser;
+
+impl ErParser {
+    /// Create a parser
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Parse a double-quoted string
+    fn quoted<'src>() -> impl chumsky::Parser<'src, &'src str, String, Extra<'src>> + Clone {
+        just('"')
+            .ignore_then(none_of('"').repeated().collect::<String>())
+            .then_ignore(just('"'))
+    }
+
+    /// Parse an entity name (letters, digits, `_` and `-`, or quoted)
+    fn name<'src>() -> impl chumsky::Parser<'src, &'src str, String, Extra<'src>> + Clone {
+        let bare = any()
+            .filter(|c: &char| c.is_alphanumeric() || *c == '_' || *c == '-')
+            .repeated()
+            .at_least(1)
+            .collect::<String>();
+        Self::quoted().or(bare)
+    }
+
+    /// Parse an entity reference with an optional `[Label]` alias
+    fn entity_ref<'src>() -> impl chumsky::Parser<'src, &'src str, EntityRef, Extra<'src>> + Clone {
+        let alias = Self::quoted()
+            .or(none_of(']').repeated().collect::<String>())
+            .delimited_by(just('['), just(']'))
+            .map(|alias| alias.trim().to_string());
+
+        Self::name()
+            .then(alias.or_not())
+            .map(|(name, alias)| EntityRef { name, alias })
+    }
+
+    /// Parse a relationship operator such as `||--o{` into
+    /// (left cardinality, identifying, right cardinality)
+    fn operator<'src>(
+    ) -> impl chumsky::Parser<'src, &'src str, (Cardinality, bool, Cardinality), Extra<'src>> + Clone
+    {
+        let left = choice((just("|o"), just("||"), just("}o"), just("}|")))
+            .map(|m| Cardinality::from_left(m).unwrap());
+        let line = just("--").to(true).or(just("..").to(false));
+        let right = choice((just("o|"), just("||"), just("o{"), just("|{")))
+            .map(|m| Cardinality::from_right(m).unwrap());
+
+        left.then(line)
+            .then(right)
+            .map(|((left, identifying), right)| (left, identifying, right))
+    }
+
+    /// Parse a relationship: `A ||--o{ B` with an optional `: label`
+    fn relationship_parser<'src>(
+    ) -> impl chumsky::Parser<'src, &'src str, Statement, Extra<'src>> + Clone {
+        let label = just(':')
+            .padded()
+            .ignore_then(
+                Self::quoted().or(any()
+                    .repeated()
+                    .collect::<String>()
+                    .map(|s| s.trim().to_string())),
+            )
+            .or_not();
+
+        Self::entity_ref()
+            .then_ignore(text::whitespace())
+            .then(Self::operator())
+            .then_ignore(text::whitespace())
+            .then(Self::entity_ref())
+            .then_ignore(text::whitespace())
+            .then(label)
+            .map(
+                |(((from, (from_cardinality, identifying, to_cardinality)), to), label)| {
+                    Statement::Relationship {
+                        from,
+                        from_cardinality,
+                        identifying,
+                        to_cardinality,
+                        to,
+                        label: label.filter(|l| !l.is_empty()),
+                    }
+                },
+            )
+    }
+
+    /// Parse `A {`, `A {}` or a bare `A`
+    fn entity_parser<'src>() -> impl chumsky::Parser<'src, &'src str, Statement, Extra<'src>> + Clone
+    {
+        let block = just('{')
+            .padded()
+            .ignore_then(just('}').padded().or_not())
+            .map(|close| close.is_some());
+
+        Self::entity_ref()
+            .then(block.or_not())
+            .map(|(entity, block)| match block {
+                Some(closed) => Statement::EntityBlock { entity, closed },
+                None => Statement::Entity(entity),
+            })
+    }
+
+    /// Parse a statement outside an attribute block
+    pub fn parse_statement(&self, input: &str) -> Result<Statement> {
+        Self::relationship_parser()
+            .or(Self::entity_parser())
+            .then_ignore(end())
+            .parse(input.trim())
+            .into_result()
+            .map_err(|errors| anyhow::anyhow!("Parse error: {:?}", errors))
+    }
+
+    /// Parse an attribute line: `type name [PK, FK] ["comment"]`
+    pub fn parse_attribute(&self, input: &str) -> Result<Attribute> {
+        let word = any()
+            .filter(|c: &char| !c.is_whitespace() && *c != '"' && *c != ',')
+            .repeated()
+            .at_least(1)
+            .collect::<String>();
+        let key = choice((just("PK"), just("FK"), just("UK")))
+            .map(|m| AttributeKey::from_marker(m).unwrap());
+        let keys = key
+            .separated_by(just(',').padded())
+            .at_least(1)
+            .collect::<Vec<_>>();
+
+        let parser = word
+            .then_ignore(text::whitespace().at_least(1))
+            .then(word)
+            .then(text::whitespace().at_least(1).ignore_then(keys).or_not())
+            .then(text::whitespace().ignore_then(Self::quoted()).or_not())
+            .then_ignore(end())
+            .map(|(((attr_type, name), keys), comment)| Attribute {
+                attr_type,
+                name,
+                keys: keys.unwrap_or_default(),
+                comment,
+            });
+
+        parser
+            .parse(input.trim())
+            .into_result()
+            .map_err(|errors| anyhow::anyhow!("Parse error: {:?}", errors))
+    }
+}
+
+impl Default for ErParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Add a referenced entity, recording its alias if it has one
+fn declare(database: &mut ErDatabase, entity: &EntityRef) -> Result<()> {
+    let mut declared = Entity::new(&entity.name);
+    declared.alias = entity.alias.clone();
+    database.add_entity(declared)
+}
+
+/// Warning for a line that could not be read, worded like
+/// [`LenientStatements`](crate::core::LenientStatements) words them
+fn skipped_warning(
+    (line, column): (usize, usize),
+    statement: &str,
+    error: anyhow::Error,
+) -> ParseWarning {
+    ParseWarning::new(format!(
+        "Skipped invalid statement '{}': {}",
+        statement, error
+    ))
+    .at(line, column)
+    .with_statement(statement)
+}
+
+impl CoreParser<ErDatabase> for ErParser {
+    fn parse(&self, input: &str, database: &mut ErDatabase) -> Result<()> {
+        let normalized = normalize_input(input);
+        let input = normalized.as_ref();
+
+        let mut open_block: Option<Entity> = None;
+        let mut skipped = Vec::new();
+
+        for (index, line) in input.lines().enumerate() {
+            let trimmed = line.trim();
+            let position = (
+                index + 1,
+                line.chars().take_while(|c| c.is_whitespace()).count() + 1,
+            );
+            if trimmed.is_empty()
+                || trimmed.starts_with("%%")
+                || trimmed.to_lowercase().starts_with("erdiagram")
+            {
+                continue;
+            }
+
+            if let Some(entity) = open_block.as_mut() {
+                if trimmed == "}" {
+                    database.add_entity(open_block.take().unwrap())?;
+                    continue;
+                }
+                match self.parse_attribute(trimmed) {
+                    Ok(attribute) => entity.add_attribute(attribute),
+                    Err(error) => {
+                        warn!(line = index + 1, attribute = %trimmed, %error, "Skipped invalid attribute");
+                        database.add_parse_warning(skipped_warning(position, trimmed, error));
+                        skipped.push((position, trimmed));
+                    }
+                }
+                continue;
+            }
+
+            match self.parse_statement(trimmed) {
+                Ok(Statement::Relationship {
+                    from,
+                    from_cardinality,
+                    identifying,
+                    to_cardinality,
+                    to,
+                    label,
+                }) => {
+                    declare(database, &from)?;
+                    declare(database, &to)?;
+                    let mut relationship =
+                        Relationship::new(from.name, from_cardinality, to.name, to_cardinality);
+                    relationship.identifying = identifying;
+                    relationship.label = label;
+                    database.add_relationship(relationship)?;
+                }
+                Ok(Statement::EntityBlock { entity, closed }) => {
+                    let mut block = Entity::new(&entity.name);
+                    block.alias = entity.alias;
+                    if closed {
+                        database.add_entity(block)?;
+                    } else {
+                        open_block = Some(block);
+                    }
+                }
+                Ok(Statement::Entity(entity)) => declare(database, &entity)?,
+                Err(error) => {
+                    warn!(line = index + 1, statement = %trimmed, %error, "Skipped invalid statement");
+                    database.add_parse_warning(skipped_warning(position, trimmed, error));
+                    skipped.push((position, trimmed));
+                }
+            }
+        }
+
+        if let Some(entity) = open_block {
+            warn!(entity = %entity.name, "Attribute block not closed");
+            database.add_entity(entity)?;
+        }
+
+        if let Some(&((line, column), statement)) = skipped.first() {
+            if database.entity_count() == 0 {
+                let invalid: Vec<_> = skipped.iter().map(|&(_, text)| text).collect();
+                let message = format!(
+                    "no valid statements found. Invalid syntax: {}",
+                    invalid.join(", ")
+                );
+                return Err(FigureheadError::parse("er", line, column, statement, message).into());
+            }
+        }
+
+        debug!(
+            entity_count = database.entity_count(),
+            relationship_count = database.relationship_count(),
+            "Parsed ER diagram"
+        );
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "er"
+    }
+
+    fn version(&self) -> &'static str {
+        "0.1.0"
+    }
+
+    fn can_parse(&self, input: &str) -> bool {
+        input.trim().to_lowercase().starts_with("erdiagram")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> ErDatabase {
+        let mut db = ErDatabase::new();
+        ErParser::new().parse(input, &mut db).unwrap();
+        db
+    }
+
+    #[test]
+    fn test_parse_relationships() {
+        let db = parse(
+            "erDiagram\n    CUSTOMER ||--o{ ORDER : places\n    CUSTOMER }|..|{ DELIVERY-ADDRESS : \"uses\"",
+        );
+
+        assert_eq!(db.entity_count(), 3);
+        let places = &db.relationships()[0];
+        assert_eq!(
+            (places.from.as_str(), places.to.as_str()),
+            ("CUSTOMER", "ORDER")
+        );
+        assert_eq!(places.from_cardinality, Cardinality::ExactlyOne);
+        assert_eq!(places.to_cardinality, Cardinality::ZeroOrMore);
+        assert!(places.identifying);
+        assert_eq!(places.label.as_deref(), Some("places"));
+
+        let uses = &db.relationships()[1];
+        assert_eq!(uses.from_cardinality, Cardinality::OneOrMore);
+        assert_eq!(uses.to_cardinality, Cardinality::OneOrMore);
+        assert!(!uses.identifying);
+        assert_eq!(uses.to, "DELIVERY-ADDRESS");
+    }
+
+    #[test]
+    fn test_parse_attribute_block() {
+        let db = parse(
+            "erDiagram\n    CUSTOMER {\n        string name\n        int id PK, FK \"the key\"\n        varchar(255) email UK\n    }\n    CUSTOMER |o--|| ACCOUNT",
+        );
+
+        let customer = db.get_entity("CUSTOMER").unwrap();
+        assert_eq!(customer.attributes.len(), 3);
+        assert_eq!(customer.attributes[0], Attribute::new("string", "name"));
+        assert_eq!(
+            customer.attributes[1],
+            Attribute::new("int", "id")
+                .with_key(AttributeKey::Primary)
+                .with_key(AttributeKey::Foreign)
+                .with_comment("the key")
+        );
+        assert_eq!(customer.attributes[2].attr_type, "varchar(255)");
+        assert_eq!(customer.attributes[2].keys, vec![AttributeKey::Unique]);
+        assert_eq!(db.relationships()[0].label, None);
+    }
+
+    #[test]
+    fn test_parse_aliases_and_bare_entities() {
+        let db = parse("erDiagram\n    p[Person] {\n    }\n    a[\"Bank account\"]\n    LONELY\n    p ||--o{ a : owns");
+
+        assert_eq!(db.get_entity("p").unwrap().label(), "Person");
+        assert_eq!(db.get_entity("a").unwrap().label(), "Bank account");
+        assert!(db.get_entity("LONELY").is_some());
+        assert_eq!(db.entity_count(), 3);
+    }
+
+    #[test]
+    fn test_invalid_input_is_an_error() {
+        let mut db = ErDatabase::new();
+        assert!(ErParser::new()
+            .parse("erDiagram\n    A <|-- B", &mut db)
+            .is_err());
+    }
+}
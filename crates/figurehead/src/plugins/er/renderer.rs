@@ -0,0 +1,451 @@
+//! Entity relationship diagram ASCII renderer
+//!
+//! Draws entities as boxes with a header and attribute rows, and
+//! relationships as orthogonal lines ending in crow's foot markers.
+
+use super::database::{Cardinality, ErDatabase};
+use super::layout::{
+    EndMarker, ErLayoutAlgorithm, ErLayoutResult, PositionedEntity, PositionedRelationship,
+};
+use crate::core::{
+    AsciiCanvas, BoxChars, CellGrid, CharacterSet, EdgeChars, Renderer, Role, RoleRecorder,
+};
+use crate::plugins::observer::LayoutProbe;
+use anyhow::Result;
+
+/// Marker glyphs for one character set
+struct MarkerChars {
+    /// Bar across the line for "one", and where perpendicular lines cross
+    bar: char,
+    /// Ring drawn at the far end of an optional marker
+    ring: char,
+    /// Crow's foot toes, leaning like `╲` and `╱`
+    toes: (char, char),
+    /// Line leaving the bottom and top border of an entity
+    attach_down: char,
+    attach_up: char,
+}
+
+const UNICODE_MARKERS: MarkerChars = MarkerChars {
+    bar: '┼',
+    ring: '○',
+    toes: ('╲', '╱'),
+    attach_down: '┬',
+    attach_up: '┴',
+};
+
+const ASCII_MARKERS: MarkerChars = MarkerChars {
+    bar: '+',
+    ring: 'o',
+    toes: ('\\', '/'),
+    attach_down: '+',
+    attach_up: '+',
+};
+
+/// Entity relationship diagram renderer
+pub struct ErRenderer {
+    style: CharacterSet,
+    color: bool,
+}
+
+impl ErRenderer {
+    /// Create a renderer with the default character set
+    pub fn new() -> Self {
+        Self::with_style(CharacterSet::Unicode)
+    }
+
+    /// Create a renderer with the given character set
+    ///
+    /// ASCII and compact output draw entities, lines and crow's feet with
+    /// plain ASCII (`+--+`, `:`, `\|/`).
+    pub fn with_style(style: CharacterSet) -> Self {
+        Self {
+            style,
+            color: false,
+        }
+    }
+
+    /// Color entities, attributes, relationships and labels with ANSI codes
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
+
+    /// Character set for lines and markers; compact output has no
+    /// single-glyph entities, so it is drawn like ASCII
+    fn line_style(&self) -> CharacterSet {
+        match self.style {
+            CharacterSet::Ascii | CharacterSet::Compact => CharacterSet::Ascii,
+            _ => CharacterSet::Unicode,
+        }
+    }
+
+    fn markers(&self) -> &'static MarkerChars {
+        if self.line_style().is_ascii() {
+            &ASCII_MARKERS
+        } else {
+            &UNICODE_MARKERS
+        }
+    }
+
+    /// Line characters: dashed for non-identifying relationships
+    fn edge_chars(&self, identifying: bool) -> EdgeChars {
+        if identifying {
+            EdgeChars::for_style(self.line_style())
+        } else {
+            EdgeChars::dotted_for_style(self.line_style())
+        }
+    }
+
+    /// Draw an entity box
+    fn draw_entity(&self, canvas: &mut AsciiCanvas, entity: &PositionedEntity) {
+        let chars = BoxChars::rectangle(self.style);
+        let (x, w) = (entity.x, entity.width);
+        let mut y = entity.y;
+
+        canvas.set_char(x, y, chars.top_left);
+        canvas.draw_horizontal_line(x + 1, y, w - 2, chars.horizontal);
+        canvas.set_char(x + w - 1, y, chars.top_right);
+        y += 1;
+
+        canvas.set_char(x, y, chars.vertical);
+        canvas.draw_text_centered(x + w / 2, y, &entity.label);
+        canvas.set_char(x + w - 1, y, chars.vertical);
+        y += 1;
+
+        if !entity.attributes.is_empty() {
+            canvas.set_char(x, y, chars.t_right);
+            canvas.draw_horizontal_line(x + 1, y, w - 2, chars.horizontal);
+            canvas.set_char(x + w - 1, y, chars.t_left);
+            y += 1;
+
+            for row in &entity.attributes {
+                canvas.set_char(x, y, chars.vertical);
+                canvas.draw_text(x + 2, y, row);
+                canvas.set_char(x + w - 1, y, chars.vertical);
+                y += 1;
+            }
+        }
+
+        canvas.set_char(x, y, chars.bottom_left);
+        canvas.draw_horizontal_line(x + 1, y, w - 2, chars.horizontal);
+        canvas.set_char(x + w - 1, y, chars.bottom_right);
+    }
+
+    /// Mark the header and attribute rows drawn by `draw_entity`
+    fn paint_compartments(
+        roles: &mut RoleRecorder,
+        canvas: &AsciiCanvas,
+        entity: &PositionedEntity,
+    ) {
+        let inner = entity.width.saturating_sub(2);
+        roles.paint_text(canvas, entity.x + 1, entity.y + 1, inner, Role::Header);
+        for row in 0..entity.attributes.len() {
+            roles.paint_text(
+                canvas,
+                entity.x + 1,
+                entity.y + 3 + row,
+                inner,
+                Role::Member,
+            );
+        }
+    }
+
+    /// Set a line cell, turning perpendicular crossings into a cross
+    fn draw_line_cell(
+        &self,
+        canvas: &mut AsciiCanvas,
+        (x, y): (usize, usize),
+        line: char,
+        across: [char; 2],
+    ) {
+        let c = if across.contains(&canvas.get_char(x, y)) {
+            self.markers().bar
+        } else {
+            line
+        };
+        canvas.set_char(x, y, c);
+    }
+
+    /// Draw a relationship's route with corners at each turn
+    fn draw_route(&self, canvas: &mut AsciiCanvas, rel: &PositionedRelationship) {
+        let chars = self.edge_chars(rel.identifying);
+        let (solid, dotted) = (self.edge_chars(true), self.edge_chars(false));
+        let horizontals = [solid.horizontal, dotted.horizontal];
+        let verticals = [solid.vertical, dotted.vertical];
+
+        for pair in rel.points.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            if x1 == x2 {
+                for y in y1.min(y2)..=y1.max(y2) {
+                    self.draw_line_cell(canvas, (x1, y), chars.vertical, horizontals);
+                }
+            } else {
+                for x in x1.min(x2)..=x1.max(x2) {
+                    self.draw_line_cell(canvas, (x, y1), chars.horizontal, verticals);
+                }
+            }
+        }
+
+        for turn in rel.points.windows(3) {
+            let (prev, (x, y), next) = (turn[0], turn[1], turn[2]);
+            let up = prev.1 < y || next.1 < y;
+            let left = prev.0 < x || next.0 < x;
+            let corner = match (up, left) {
+                (true, true) => chars.corner_bottom_right,
+                (true, false) => chars.corner_bottom_left,
+                (false, true) => chars.corner_top_right,
+                (false, false) => chars.corner_top_left,
+            };
+            canvas.set_char(x, y, corner);
+        }
+    }
+
+    /// Draw the crow's foot marker at one end of a relationship
+    ///
+    /// The cell next to the entity shows the maximum (a bar for one, a crow's
+    /// foot for many); the cell beyond it shows the minimum (a ring for zero,
+    /// a bar for one).
+    fn draw_marker(&self, canvas: &mut AsciiCanvas, end: &EndMarker) {
+        let markers = self.markers();
+        let far_y = if end.below_entity {
+            end.y + 1
+        } else {
+            end.y.saturating_sub(1)
+        };
+
+        if end.cardinality.is_many() {
+            let (back, forward) = markers.toes;
+            let (left, right) = if end.below_entity {
+                (back, forward)
+            } else {
+                (forward, back)
+            };
+            canvas.set_char(end.x - 1, end.y, left);
+            canvas.set_char(end.x + 1, end.y, right);
+        } else {
+            canvas.set_char(end.x, end.y, markers.bar);
+        }
+
+        let far = match end.cardinality {
+            Cardinality::ZeroOrOne | Cardinality::ZeroOrMore => markers.ring,
+            Cardinality::ExactlyOne | Cardinality::OneOrMore => markers.bar,
+        };
+        canvas.set_char(end.x, far_y, far);
+    }
+
+    /// Join a relationship end to the entity border it leaves
+    fn draw_attachment(&self, canvas: &mut AsciiCanvas, end: &EndMarker) {
+        let markers = self.markers();
+        if end.below_entity {
+            canvas.set_char(end.x, end.y - 1, markers.attach_down);
+        } else {
+            canvas.set_char(end.x, end.y + 1, markers.attach_up);
+        }
+    }
+
+    /// Render the layout to ASCII art
+    pub fn render_layout(&self, layout: &ErLayoutResult) -> String {
+        match self.draw_layout(layout, self.color) {
+            Some((canvas, roles)) => roles.finish(&canvas),
+            None => String::new(),
+        }
+    }
+
+    /// Draw the layout, recording roles and owners when `record` is set
+    fn draw_layout(
+        &self,
+        layout: &ErLayoutResult,
+        record: bool,
+    ) -> Option<(AsciiCanvas, RoleRecorder)> {
+        if layout.entities.is_empty() {
+            return None;
+        }
+
+        let mut canvas = AsciiCanvas::new(layout.width + 1, layout.height + 1);
+        let mut roles = RoleRecorder::new(record, &canvas);
+        let id = |rel: &PositionedRelationship| format!("{}->{}", rel.from, rel.to);
+
+        for rel in &layout.relationships {
+            canvas.track_writes();
+            self.draw_route(&mut canvas, rel);
+            roles.own_cells(&canvas.take_writes(), &id(rel));
+        }
+        for rel in &layout.relationships {
+            canvas.track_writes();
+            self.draw_marker(&mut canvas, &rel.from_end);
+            self.draw_marker(&mut canvas, &rel.to_end);
+            roles.own_cells(&canvas.take_writes(), &id(rel));
+        }
+        roles.record(&canvas, Role::Edge);
+
+        for entity in &layout.entities {
+            self.draw_entity(&mut canvas, entity);
+            roles.own(
+                entity.x,
+                entity.y,
+                entity.width,
+                entity.height,
+                &entity.name,
+            );
+        }
+        for rel in &layout.relationships {
+            self.draw_attachment(&mut canvas, &rel.from_end);
+            self.draw_attachment(&mut canvas, &rel.to_end);
+        }
+        roles.record(&canvas, Role::Node);
+        for entity in &layout.entities {
+            Self::paint_compartments(&mut roles, &canvas, entity);
+        }
+
+        // Labels last so crossing lines never cut through them
+        for rel in &layout.relationships {
+            if let Some(label) = &rel.label {
+                canvas.track_writes();
+                canvas.draw_text(rel.label_x, rel.label_y, label);
+                roles.own_cells(&canvas.take_writes(), &id(rel));
+            }
+        }
+        roles.record(&canvas, Role::Label);
+
+        Some((canvas, roles))
+    }
+
+    /// Render the database to ASCII
+    pub fn render(&self, database: &ErDatabase) -> Result<String> {
+        self.render_probed(database, &mut LayoutProbe::default())
+    }
+
+    /// Render the database, timing the layout through `probe`
+    pub(crate) fn render_probed(
+        &self,
+        database: &ErDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<String> {
+        let layout_algo = ErLayoutAlgorithm::new();
+        let started = probe.start();
+        let layout = layout_algo.layout(database)?;
+        probe.finish(started, layout.width, layout.height);
+
+        Ok(self.render_layout(&layout))
+    }
+
+    /// Render the database as a [`CellGrid`]
+    ///
+    /// Each cell of an entity box carries the entity name, and cells of a
+    /// relationship's line, markers and label carry `from->to`; roles
+    /// become palette colors when color is on.
+    pub fn render_cells(&self, database: &ErDatabase) -> Result<CellGrid> {
+        let layout = ErLayoutAlgorithm::new().layout(database)?;
+        Ok(match self.draw_layout(&layout, true) {
+            Some((canvas, roles)) => roles.finish_cells(&canvas, self.color),
+            None => CellGrid::default(),
+        })
+    }
+}
+
+impl Default for ErRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer<ErDatabase> for ErRenderer {
+    type Output = String;
+
+    fn render(&self, database: &ErDatabase) -> Result<Self::Output> {
+        self.render(database)
+    }
+
+    fn name(&self) -> &'static str {
+        "ascii"
+    }
+
+    fn version(&self) -> &'static str {
+        "0.1.0"
+    }
+
+    fn format(&self) -> &'static str {
+        "ascii"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Parser;
+    use crate::plugins::er::ErParser;
+
+    fn render(input: &str) -> String {
+        let mut db = ErDatabase::new();
+        ErParser::new().parse(input, &mut db).unwrap();
+        ErRenderer::new().render(&db).unwrap()
+    }
+
+    #[test]
+    fn test_render_empty() {
+        assert_eq!(ErRenderer::new().render(&ErDatabase::new()).unwrap(), "");
+    }
+
+    #[test]
+    fn test_entity_box_lists_attributes() {
+        let output =
+            render("erDiagram\n    CUSTOMER {\n        string name\n        int id PK\n    }");
+        assert!(output.contains("CUSTOMER"));
+        assert!(output.contains("├"));
+        assert!(output.contains("string name"));
+        assert!(output.contains("int    id   PK"));
+    }
+
+    #[test]
+    fn test_crows_foot_markers() {
+        let output = render("erDiagram\n    CUSTOMER ||--o{ ORDER : places");
+        let lines: Vec<&str> = output.lines().collect();
+
+        // Exactly one at CUSTOMER: two bars under its box
+        let customer_bottom = lines.iter().position(|l| l.contains('┬')).unwrap();
+        assert_eq!(lines[customer_bottom + 1].trim(), "┼");
+        assert_eq!(lines[customer_bottom + 2].trim(), "┼");
+
+        // Zero or more at ORDER: a ring, then a crow's foot opening onto it
+        let order_top = lines.iter().position(|l| l.contains('┴')).unwrap();
+        assert_eq!(lines[order_top - 1].trim(), "╱│╲");
+        assert_eq!(lines[order_top - 2].trim(), "○");
+        assert!(output.contains("places"));
+    }
+
+    #[test]
+    fn test_non_identifying_relationships_are_dashed() {
+        let output = render("erDiagram\n    A ||..|{ B");
+        assert!(output.contains('┆'));
+        assert!(!render("erDiagram\n    A ||--|{ B").contains('┆'));
+    }
+
+    #[test]
+    fn test_ascii_and_compact_styles_draw_plain_ascii() {
+        let mut db = ErDatabase::new();
+        ErParser::new()
+            .parse(
+                "erDiagram\n    CUSTOMER ||--o{ ORDER : places\n    CUSTOMER }|..|{ ADDRESS : uses",
+                &mut db,
+            )
+            .unwrap();
+        for style in [CharacterSet::Ascii, CharacterSet::Compact] {
+            let output = ErRenderer::with_style(style).render(&db).unwrap();
+            assert!(output.is_ascii(), "{:?}:\n{}", style, output);
+            assert!(output.contains("+----------+"), "{}", output);
+            assert!(output.contains(':'), "{}", output);
+            assert!(output.contains("/|\\"), "{}", output);
+        }
+    }
+
+    #[test]
+    fn test_color_output() {
+        let mut db = ErDatabase::new();
+        ErParser::new()
+            .parse("erDiagram\n    A ||--o{ B : has", &mut db)
+            .unwrap();
+        let colored = ErRenderer::new().with_color(true).render(&db).unwrap();
+        assert!(colored.contains("\x1b["));
+    }
+}
@@ -0,0 +1,283 @@
+//! Node markers for review comments
+//!
+//! [`annotate`] decorates a flowchart after it has been drawn: each requested
+//! node gets a marker (`A`, `B`, ...) stamped on its top border and repeated
+//! in a left margin, and a table maps the markers back to node IDs. Reviewers
+//! can then write "box B" instead of attaching a screenshot.
+
+use anyhow::Result;
+use std::fmt;
+
+use super::{FlowchartDatabase, FlowchartRenderer};
+use crate::core::Database;
+
+/// Where a node was drawn, in the character cells of the rendered text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeRegion {
+    /// Node ID
+    pub id: String,
+    /// Column of the node's left edge
+    pub x: usize,
+    /// Line of the node's top edge
+    pub y: usize,
+    /// Width in cells
+    pub width: usize,
+    /// Height in lines
+    pub height: usize,
+}
+
+/// Where an edge was drawn, in the character cells of the rendered text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeRegion {
+    /// Source node ID
+    pub from: String,
+    /// Target node ID
+    pub to: String,
+    /// Cells of the line, its arrow head and its label
+    ///
+    /// A junction shared by several edges belongs to the first one drawn.
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl EdgeRegion {
+    /// ID naming the edge in a [`CellGrid`](crate::CellGrid), `from->to`
+    pub fn id(&self) -> String {
+        format!("{}->{}", self.from, self.to)
+    }
+}
+
+/// A rendered flowchart and where each of its nodes and edges was drawn
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderedFlowchart {
+    /// The drawing, as returned by [`Renderer::render`](crate::core::Renderer::render)
+    pub text: String,
+    /// Drawn nodes in layout order; empty for summaries and braille output
+    pub nodes: Vec<NodeRegion>,
+    /// Drawn edges in layout order; empty for summaries, braille and the
+    /// debug overlay
+    pub edges: Vec<EdgeRegion>,
+}
+
+impl RenderedFlowchart {
+    /// Output without node positions
+    pub(crate) fn text_only(text: String) -> Self {
+        Self {
+            text,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Region of a drawn node
+    pub fn node(&self, id: &str) -> Option<&NodeRegion> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+}
+
+/// A marker and the node it labels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// Marker text, e.g. `B`
+    pub marker: String,
+    /// Node ID
+    pub node_id: String,
+    /// Node label
+    pub label: String,
+}
+
+impl fmt::Display for Annotation {
+    /// Formats a table row, e.g. `B  Check: Valid input?`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.label == self.node_id {
+            write!(f, "{}  {}", self.marker, self.node_id)
+        } else {
+            write!(f, "{}  {}: {}", self.marker, self.node_id, self.label)
+        }
+    }
+}
+
+/// A drawing with node markers and the table explaining them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnnotatedDiagram {
+    /// The drawing with markers stamped on and a margin of markers
+    pub text: String,
+    /// Markers in the order the nodes were requested
+    pub annotations: Vec<Annotation>,
+}
+
+impl AnnotatedDiagram {
+    /// The marker table, one row per annotated node
+    pub fn table(&self) -> String {
+        self.annotations
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl fmt::Display for AnnotatedDiagram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n\n{}", self.text, self.table())
+    }
+}
+
+/// Marker for the annotation at `index`: `A` to `Z`, then `AA`, `AB`, ...
+pub fn marker(index: usize) -> String {
+    let mut marker = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        n -= 1;
+        marker.push(b'A' + (n % 26) as u8);
+        n /= 26;
+    }
+    marker.iter().rev().map(|&b| b as char).collect()
+}
+
+/// Stamp markers on the given nodes of a rendered flowchart
+///
+/// Markers are assigned in the order of `node_ids`. Fails for IDs that are
+/// not in `database` or were not drawn (hidden nodes, summarized diagrams).
+pub fn annotate(
+    rendered: &RenderedFlowchart,
+    database: &FlowchartDatabase,
+    node_ids: &[&str],
+) -> Result<AnnotatedDiagram> {
+    let mut annotations = Vec::new();
+    let mut regions = Vec::new();
+    for (index, id) in node_ids.iter().enumerate() {
+        let node = database
+            .get_node(id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown node '{}'", id))?;
+        let region = rendered
+            .node(&node.id)
+            .ok_or_else(|| anyhow::anyhow!("Node '{}' is not drawn", id))?;
+        annotations.push(Annotation {
+            marker: marker(index),
+            node_id: node.id.clone(),
+            label: node.label.clone(),
+        });
+        regions.push(region);
+    }
+
+    let mut grid: Vec<Vec<char>> = rendered
+        .text
+        .lines()
+        .map(|line| line.chars().collect())
+        .collect();
+    let mut margin = vec![Vec::new(); grid.len()];
+
+    for (annotation, region) in annotations.iter().zip(&regions) {
+        let Some(row) = grid.get_mut(region.y) else {
+            continue;
+        };
+        // Stamp inside the top border so the corners stay intact
+        if annotation.marker.len() + 2 <= region.width {
+            for (offset, c) in annotation.marker.chars().enumerate() {
+                if let Some(cell) = row.get_mut(region.x + 1 + offset) {
+                    *cell = c;
+                }
+            }
+        }
+        margin[region.y].push((region.x, annotation.marker.as_str()));
+    }
+
+    let margin: Vec<String> = margin
+        .into_iter()
+        .map(|mut markers| {
+            markers.sort();
+            markers
+                .iter()
+                .map(|(_, marker)| *marker)
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect();
+    let margin_width = margin.iter().map(String::len).max().unwrap_or(0);
+
+    let text = grid
+        .iter()
+        .zip(&margin)
+        .map(|(row, markers)| {
+            let line: String = row.iter().collect();
+            format!("{:<width$} {}", markers, line, width = margin_width)
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(AnnotatedDiagram { text, annotations })
+}
+
+impl FlowchartRenderer {
+    /// Render the diagram with markers on the given nodes
+    ///
+    /// Shorthand for [`render_with_regions`](Self::render_with_regions)
+    /// followed by [`annotate`].
+    pub fn render_annotated(
+        &self,
+        database: &FlowchartDatabase,
+        node_ids: &[&str],
+    ) -> Result<AnnotatedDiagram> {
+        annotate(&self.render_with_regions(database)?, database, node_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Renderer;
+
+    #[test]
+    fn test_markers_continue_past_z() {
+        assert_eq!(marker(0), "A");
+        assert_eq!(marker(25), "Z");
+        assert_eq!(marker(26), "AA");
+        assert_eq!(marker(27), "AB");
+    }
+
+    #[test]
+    fn test_annotated_nodes_get_border_and_margin_markers() {
+        let db = crate::parse("graph TD\n A[Start] --> B{Valid?}\n B --> C[Done]").unwrap();
+        let renderer = FlowchartRenderer::new();
+        let rendered = renderer.render_with_regions(&db).unwrap();
+        assert_eq!(rendered.text, renderer.render(&db).unwrap());
+
+        let annotated = annotate(&rendered, &db, &["C", "A"]).unwrap();
+        let lines: Vec<&str> = annotated.text.lines().collect();
+        let start = rendered.node("A").unwrap();
+        let done = rendered.node("C").unwrap();
+        assert!(lines[start.y].starts_with("B "), "{}", annotated.text);
+        assert!(lines[done.y].starts_with("A "), "{}", annotated.text);
+        assert!(lines[start.y].contains("┌B─"), "{}", annotated.text);
+        assert!(lines[done.y].contains("┌A─"), "{}", annotated.text);
+        assert_eq!(annotated.table(), "A  C: Done\nB  A: Start");
+    }
+
+    #[test]
+    fn test_regions_follow_mirrored_rendering() {
+        let db = crate::parse("graph BT\n A[Start] --> B[End]").unwrap();
+        let renderer = FlowchartRenderer::new().with_mirror_reversed(true);
+        let rendered = renderer.render_with_regions(&db).unwrap();
+
+        let lines: Vec<&str> = rendered.text.lines().collect();
+        let start = rendered.node("A").unwrap();
+        assert!(lines[start.y + 1].contains("Start"), "{}", rendered.text);
+        assert!(start.y > rendered.node("B").unwrap().y);
+    }
+
+    #[test]
+    fn test_unknown_and_hidden_nodes_are_errors() {
+        let db = crate::parse("graph TD\n %%fh: hide B%%\n A --> B").unwrap();
+        let rendered = FlowchartRenderer::new().render_with_regions(&db).unwrap();
+        assert!(annotate(&rendered, &db, &["Z"])
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown node 'Z'"));
+        assert!(annotate(&rendered, &db, &["B"])
+            .unwrap_err()
+            .to_string()
+            .contains("not drawn"));
+    }
+}
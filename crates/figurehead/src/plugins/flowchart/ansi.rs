@@ -0,0 +1,75 @@
+//! Terminal output colored from `classDef` and `style` statements
+//!
+//! The ANSI counterpart of the HTML output: `stroke` colors the border,
+//! `fill` the background inside it and `color` the label text, written as
+//! 24-bit escapes. Colors without an RGB value (unknown names) are skipped.
+//! A [`Theme`] colors everything else first.
+
+use super::html::style_layer;
+use super::{FlowchartDatabase, RenderedFlowchart};
+use crate::core::{Palette, Theme};
+
+/// The rendered text with the theme and node styles as ANSI escapes
+pub(super) fn to_ansi(
+    rendered: &RenderedFlowchart,
+    database: &FlowchartDatabase,
+    theme: Option<Theme>,
+) -> String {
+    style_layer(rendered, database, theme).to_ansi(&rendered.text, &Palette::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::FlowchartRenderer;
+    use crate::core::{RenderConfig, Renderer, Theme};
+
+    #[test]
+    fn test_color_config_paints_styled_nodes() {
+        let db = crate::parse(
+            "graph LR\n A[Go] --> B[Stop]\n classDef warn fill:#f9f,stroke:#333,color:red\n class A warn",
+        )
+        .unwrap();
+
+        let plain = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(!plain.contains('\x1b'));
+
+        let renderer = FlowchartRenderer::with_config(RenderConfig::default().with_color(true));
+        let colored = renderer.render(&db).unwrap();
+        assert!(colored.contains("\x1b[38;2;51;51;51m┌"), "{}", colored);
+        assert!(
+            colored.contains("\x1b[38;2;255;0;0;48;2;255;153;255mGo"),
+            "{}",
+            colored
+        );
+        // The unstyled node is left alone
+        assert!(colored.contains("│ Stop │"), "{}", colored);
+    }
+
+    #[test]
+    fn test_theme_colors_unstyled_nodes_and_edges() {
+        let db =
+            crate::parse("graph LR\n A[Go] --> B[Stop]\n classDef warn stroke:#f00\n class A warn")
+                .unwrap();
+        let config = RenderConfig::default()
+            .with_color(true)
+            .with_theme(Some(Theme::FOREST));
+        let colored = FlowchartRenderer::with_config(config).render(&db).unwrap();
+
+        // Forest borders, fill and text on the plain node, green edges
+        assert!(colored.contains("\x1b[38;2;19;84;12m┌"), "{}", colored);
+        assert!(
+            colored.contains("\x1b[38;2;0;0;0;48;2;205;228;152m"),
+            "{}",
+            colored
+        );
+        assert!(colored.contains("\x1b[38;2;0;128;0m─"), "{}", colored);
+        // The classDef stroke wins over the theme
+        assert!(colored.contains("\x1b[38;2;255;0;0m┌"), "{}", colored);
+
+        // Without color the theme does nothing
+        let plain = FlowchartRenderer::with_config(config.with_color(false))
+            .render(&db)
+            .unwrap();
+        assert!(!plain.contains('\x1b'));
+    }
+}
@@ -0,0 +1,275 @@
+//! Experimental braille rendering for flowcharts
+//!
+//! Rasterizes the cell layout onto a [`BrailleCanvas`] so shapes and edges
+//! are drawn with 2x4 dots per cell. Edges are drawn as straight segments
+//! between their endpoints, giving smooth diagonals instead of stair-stepped
+//! orthogonal routes. Node and edge labels stay as normal characters.
+
+use unicode_width::UnicodeWidthStr;
+
+use super::{FlowchartDatabase, FlowchartLayoutResult, PositionedNode, MAX_LABEL_WIDTH};
+use crate::core::{
+    wrap_label, BrailleCanvas, Database, EdgeTerminator, EdgeType, NodeShape, Point, Segment,
+    BRAILLE_DOTS_X, BRAILLE_DOTS_Y,
+};
+
+type Dot = (isize, isize);
+
+/// Length of arrowhead wings in dots
+const ARROW_LENGTH: f64 = 3.0;
+
+/// Dot bounds of a cell rectangle, inset to the middle of its border cells
+#[derive(Debug, Clone, Copy)]
+struct DotBox {
+    left: isize,
+    top: isize,
+    right: isize,
+    bottom: isize,
+}
+
+impl DotBox {
+    fn from_cells(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            left: (x * BRAILLE_DOTS_X + 1) as isize,
+            top: (y * BRAILLE_DOTS_Y + 1) as isize,
+            right: ((x + width.max(1) - 1) * BRAILLE_DOTS_X) as isize,
+            bottom: ((y + height.max(1) - 1) * BRAILLE_DOTS_Y + 2) as isize,
+        }
+    }
+
+    fn mid_x(&self) -> isize {
+        (self.left + self.right) / 2
+    }
+
+    fn mid_y(&self) -> isize {
+        (self.top + self.bottom) / 2
+    }
+}
+
+/// Center dot of a cell
+fn cell_dot(point: Point) -> Dot {
+    (
+        (point.x * BRAILLE_DOTS_X) as isize,
+        (point.y * BRAILLE_DOTS_Y + 1) as isize,
+    )
+}
+
+/// Render a laid out flowchart onto a braille canvas
+pub(super) fn render_braille(
+    database: &FlowchartDatabase,
+    layout: &FlowchartLayoutResult,
+) -> String {
+    let mut canvas = BrailleCanvas::new(layout.width, layout.height);
+
+    for subgraph in &layout.subgraphs {
+        let bounds = DotBox::from_cells(subgraph.x, subgraph.y, subgraph.width, subgraph.height);
+        canvas.rect(bounds.left, bounds.top, bounds.right, bounds.bottom);
+        if !subgraph.title.is_empty() {
+            let title = format!(" {} ", subgraph.title);
+            canvas.draw_text_centered(subgraph.x + subgraph.width / 2, subgraph.y, &title);
+        }
+    }
+
+    for edge in &layout.edges {
+        let edge_data = database
+            .edges()
+            .find(|e| e.from == edge.from_id && e.to == edge.to_id);
+        let edge_type = edge_data.map(|e| e.edge_type).unwrap_or(EdgeType::Arrow);
+        if edge_type == EdgeType::Invisible || edge.waypoints.len() < 2 {
+            continue;
+        }
+
+        // Two-point routes become a single straight (possibly diagonal) segment;
+        // longer routes (back-edges) keep their bends so they avoid nodes
+        let points: Vec<Dot> = edge.waypoints.iter().copied().map(cell_dot).collect();
+        for pair in points.windows(2) {
+            draw_segment(&mut canvas, pair[0], pair[1], edge_type);
+        }
+
+        let tip = points[points.len() - 1];
+        let before = points[points.len() - 2];
+        draw_terminator(
+            &mut canvas,
+            before,
+            tip,
+            EdgeTerminator::for_edge_type(edge_type),
+        );
+        if let Some(edge) = edge_data {
+            draw_terminator(&mut canvas, points[1], points[0], edge.start);
+        }
+
+        if let Some(label) = edge_data.and_then(|e| e.label.as_deref()) {
+            let mid = midpoint(&edge.waypoints);
+            canvas.draw_text_centered(mid.x, mid.y, label);
+        }
+    }
+
+    for node in &layout.nodes {
+        if let Some(node_data) = database.get_node(&node.id) {
+            draw_shape(&mut canvas, node, node_data.shape);
+            draw_node_label(&mut canvas, node, &node_data.label);
+        }
+    }
+
+    canvas.to_string()
+}
+
+fn draw_segment(canvas: &mut BrailleCanvas, from: Dot, to: Dot, edge_type: EdgeType) {
+    if edge_type.is_dotted() {
+        canvas.dashed_line(from, to, 2, 2);
+    } else if edge_type.is_thick() {
+        canvas.line(from, to);
+        // Double the stroke one dot to the side
+        let (ox, oy) = if (to.0 - from.0).abs() >= (to.1 - from.1).abs() {
+            (0, 1)
+        } else {
+            (1, 0)
+        };
+        canvas.line((from.0 + ox, from.1 + oy), (to.0 + ox, to.1 + oy));
+    } else {
+        canvas.line(from, to);
+    }
+}
+
+fn draw_terminator(canvas: &mut BrailleCanvas, from: Dot, tip: Dot, terminator: EdgeTerminator) {
+    match terminator {
+        EdgeTerminator::None => {}
+        EdgeTerminator::Open => canvas.ellipse(tip.0 - 1, tip.1 - 1, tip.0 + 1, tip.1 + 1),
+        EdgeTerminator::Cross => {
+            canvas.line((tip.0 - 1, tip.1 - 1), (tip.0 + 1, tip.1 + 1));
+            canvas.line((tip.0 - 1, tip.1 + 1), (tip.0 + 1, tip.1 - 1));
+        }
+        EdgeTerminator::Arrow | EdgeTerminator::Triangle | EdgeTerminator::Diamond { .. } => {
+            let dx = (tip.0 - from.0) as f64;
+            let dy = (tip.1 - from.1) as f64;
+            let len = (dx * dx + dy * dy).sqrt();
+            if len == 0.0 {
+                return;
+            }
+            let (ux, uy) = (dx / len, dy / len);
+            // Wings swept back from the tip at roughly 35 degrees
+            for side in [-1.0, 1.0] {
+                let wx = tip.0 as f64 - ARROW_LENGTH * ux + side * 0.7 * ARROW_LENGTH * uy;
+                let wy = tip.1 as f64 - ARROW_LENGTH * uy - side * 0.7 * ARROW_LENGTH * ux;
+                canvas.line(tip, (wx.round() as isize, wy.round() as isize));
+            }
+        }
+    }
+}
+
+fn draw_shape(canvas: &mut BrailleCanvas, node: &PositionedNode, shape: NodeShape) {
+    let b = DotBox::from_cells(node.x, node.y, node.width, node.height);
+    let (l, t, r, bo) = (b.left, b.top, b.right, b.bottom);
+    let (mx, my) = (b.mid_x(), b.mid_y());
+
+    match shape {
+        NodeShape::Rectangle => canvas.rect(l, t, r, bo),
+        NodeShape::RoundedRect | NodeShape::Stadium => canvas.polygon(&[
+            (l + 2, t),
+            (r - 2, t),
+            (r, t + 2),
+            (r, bo - 2),
+            (r - 2, bo),
+            (l + 2, bo),
+            (l, bo - 2),
+            (l, t + 2),
+        ]),
+        NodeShape::Subroutine => {
+            canvas.rect(l, t, r, bo);
+            canvas.line((l + 2, t), (l + 2, bo));
+            canvas.line((r - 2, t), (r - 2, bo));
+        }
+        NodeShape::Diamond => canvas.polygon(&[(mx, t), (r, my), (mx, bo), (l, my)]),
+        NodeShape::Circle | NodeShape::Terminal => canvas.ellipse(l, t, r, bo),
+        NodeShape::DoubleCircle => {
+            canvas.ellipse(l, t, r, bo);
+            canvas.ellipse(l + 2, t + 2, r - 2, bo - 2);
+        }
+        NodeShape::Hexagon => canvas.polygon(&[
+            (l + 3, t),
+            (r - 3, t),
+            (r, my),
+            (r - 3, bo),
+            (l + 3, bo),
+            (l, my),
+        ]),
+        NodeShape::Asymmetric => canvas.polygon(&[(l, t), (r, t), (r, bo), (l, bo), (l + 3, my)]),
+        NodeShape::Cylinder => {
+            canvas.ellipse(l, t, r, t + 4);
+            canvas.line((l, t + 2), (l, bo));
+            canvas.line((l, bo), (r, bo));
+            canvas.line((r, bo), (r, t + 2));
+        }
+        NodeShape::Parallelogram => canvas.polygon(&[(l + 3, t), (r, t), (r - 3, bo), (l, bo)]),
+        NodeShape::ParallelogramAlt => canvas.polygon(&[(l, t), (r - 3, t), (r, bo), (l + 3, bo)]),
+        NodeShape::Trapezoid => canvas.polygon(&[(l + 3, t), (r - 3, t), (r, bo), (l, bo)]),
+        NodeShape::InvTrapezoid => canvas.polygon(&[(l, t), (r, t), (r - 3, bo), (l + 3, bo)]),
+    }
+}
+
+fn draw_node_label(canvas: &mut BrailleCanvas, node: &PositionedNode, label: &str) {
+    let lines = wrap_label(label, MAX_LABEL_WIDTH);
+    let start_y = node.y + node.height.saturating_sub(lines.len()) / 2;
+    for (i, line) in lines.iter().enumerate() {
+        let line_width = UnicodeWidthStr::width(line.as_str());
+        let x = node.x + node.width.saturating_sub(line_width) / 2;
+        canvas.draw_text(x, start_y + i, line);
+    }
+}
+
+/// Cell at the middle of a waypoint path, measured along its length
+fn midpoint(waypoints: &[Point]) -> Point {
+    let first = waypoints[0];
+    let last = waypoints[waypoints.len() - 1];
+    if waypoints.len() > 2 {
+        return waypoints[waypoints.len() / 2];
+    }
+    Segment::new(first, last).midpoint()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{CanvasMode, Parser, Renderer};
+    use crate::plugins::flowchart::{FlowchartDatabase, FlowchartParser, FlowchartRenderer};
+
+    fn render(input: &str) -> String {
+        let mut db = FlowchartDatabase::new();
+        FlowchartParser::new().parse(input, &mut db).unwrap();
+        FlowchartRenderer::new()
+            .with_canvas_mode(CanvasMode::Braille)
+            .render(&db)
+            .unwrap()
+    }
+
+    fn is_braille(c: char) -> bool {
+        ('\u{2800}'..='\u{28FF}').contains(&c)
+    }
+
+    #[test]
+    fn test_braille_keeps_labels_as_text() {
+        let output = render("graph TD\n    A[Start] --> B[End]");
+        assert!(output.contains("Start"));
+        assert!(output.contains("End"));
+        assert!(output.chars().any(is_braille));
+        // No box-drawing glyphs leak into braille output
+        assert!(!output.contains('─') && !output.contains('│'));
+    }
+
+    #[test]
+    fn test_braille_draws_edge_labels() {
+        let output = render("graph LR\n    A -->|yes| B");
+        assert!(output.contains("yes"));
+    }
+
+    #[test]
+    fn test_braille_handles_every_shape() {
+        let output = render(
+            "graph TD\n    A[rect] --> B(round)\n    B --> C{diamond}\n    C --> D((circle))\n    D --> E{{hex}}\n    E --> F[(db)]\n    F --> G[[sub]]\n    G --> H>flag]\n    H --> I[/para/]\n    I --> J[/trap\\]",
+        );
+        for label in [
+            "rect", "round", "diamond", "circle", "hex", "db", "sub", "flag", "para", "trap",
+        ] {
+            assert!(output.contains(label), "missing {}", label);
+        }
+    }
+}
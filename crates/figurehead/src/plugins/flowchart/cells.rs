@@ -0,0 +1,67 @@
+//! Flowcharts as structured cell grids
+//!
+//! Cells inside a node's region are owned by that node and cells an edge
+//! was drawn over by the edge; node styles are painted the same way as for
+//! the HTML output.
+
+use anyhow::Result;
+
+use super::html::style_layer;
+use super::{FlowchartDatabase, FlowchartRenderer};
+use crate::core::{CellGrid, Palette};
+
+impl FlowchartRenderer {
+    /// Render the diagram as a [`CellGrid`]
+    ///
+    /// Each cell of a drawn node carries the node's ID and the colors of its
+    /// `classDef` or `style` statements, over the theme's when color is on.
+    /// Cells of an edge's line, arrow head and label carry the edge's
+    /// [`id`](super::EdgeRegion::id), unless a node is drawn over them.
+    /// Diagrams drawn without node positions (summaries, braille) come out
+    /// without any owners.
+    pub fn render_cells(&self, database: &FlowchartDatabase) -> Result<CellGrid> {
+        let rendered = self.render_with_regions(database)?;
+        let theme = self.theme().filter(|_| self.color());
+        let mut layer = style_layer(&rendered, database, theme);
+        for edge in &rendered.edges {
+            let id = edge.id();
+            for &(x, y) in &edge.cells {
+                layer.set_owner(x, y, &id);
+            }
+        }
+        for region in &rendered.nodes {
+            for y in region.y..region.y + region.height {
+                for x in region.x..region.x + region.width {
+                    layer.set_owner(x, y, &region.id);
+                }
+            }
+        }
+        Ok(layer.to_grid(&rendered.text, &Palette::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cells_carry_node_owner_and_style() {
+        let db = crate::parse("graph LR\n A[Go] --> B[Stop]\n style A fill:#f9f").unwrap();
+        let renderer = FlowchartRenderer::new();
+        let rendered = renderer.render_with_regions(&db).unwrap();
+        let grid = renderer.render_cells(&db).unwrap();
+
+        let go = rendered.node("A").unwrap();
+        let label = grid.get(go.x + 2, go.y + 1).unwrap();
+        assert_eq!(label.ch, 'G');
+        assert_eq!(label.owner.as_deref(), Some("A"));
+        assert_eq!(label.style.background.as_deref(), Some("#f9f"));
+
+        let stop = rendered.node("B").unwrap();
+        assert_eq!(grid.owner_at(stop.x, stop.y), Some("B"));
+        assert!(grid.get(stop.x, stop.y).unwrap().style.is_plain());
+        // The edge between the nodes belongs to the edge
+        assert_eq!(grid.owner_at(go.x + go.width, go.y + 1), Some("A->B"));
+        assert_eq!(grid.get(go.x + go.width, go.y + 1).unwrap().ch, '─');
+    }
+}
@@ -3,10 +3,13 @@
 //! Parses individual Mermaid.js flowchart statements into AST structures.
 
 use super::whitespace::optional_whitespace;
-use crate::core::{Direction, EdgeType, NodeShape, StyleDefinition};
+use crate::core::{
+    decode_entities, line_breaks, markdown_text, Direction, EdgeTerminator, EdgeType, NodeLink,
+    NodeShape, StyleDefinition,
+};
 use anyhow::Result;
 use chumsky::prelude::*;
-use chumsky::text::ident;
+use chumsky::text::{ident, inline_whitespace};
 
 /// Chumsky-based flowchart parser
 pub struct ChumskyFlowchartParser;
@@ -59,6 +62,8 @@ impl ChumskyFlowchartParser {
                 .or(Self::style_parser())
                 .or(Self::class_parser())
                 .or(Self::linkstyle_parser())
+                .or(Self::click_parser())
+                .or(Self::direction_parser())
                 .or(Self::subgraph_parser(statements.clone()))
                 .or(Self::edge_parser().map(Statement::Edge))
                 .or(Self::node_parser().map(Statement::Node))
@@ -109,6 +114,55 @@ impl ChumskyFlowchartParser {
             })
     }
 
+    /// Parse `click nodeId "url" "tooltip"` and `click nodeId href "url"`;
+    /// the JavaScript callback forms (`click nodeId callback`, `click nodeId
+    /// call callback()`) are accepted but carry no link
+    fn click_parser<'src>() -> impl Parser<'src, &'src str, Statement> + Clone {
+        let gap = || inline_whitespace().at_least(1);
+        let quoted = || {
+            none_of("\"\n")
+                .repeated()
+                .collect::<String>()
+                .delimited_by(just('"'), just('"'))
+        };
+        let link = just("href")
+            .then(gap())
+            .or_not()
+            .ignore_then(quoted())
+            .then(gap().ignore_then(quoted()).or_not())
+            .then_ignore(gap().then(ident()).or_not())
+            .then_ignore(inline_whitespace())
+            .map(|(url, tooltip)| {
+                let link = NodeLink::new(url);
+                Some(match tooltip {
+                    Some(tooltip) => link.with_tooltip(decode_entities(&tooltip)),
+                    None => link,
+                })
+            });
+        let callback = ident().then(none_of("\n\r;").repeated()).to(None);
+        just("click")
+            .then(gap())
+            .ignore_then(ident().map(|s: &str| s.to_string()))
+            .then_ignore(gap())
+            .then(link.or(callback))
+            .map(|(node_id, link)| Statement::Click(node_id, link))
+    }
+
+    /// Parse `direction LR` (sets the direction of the enclosing subgraph)
+    fn direction_parser<'src>() -> impl Parser<'src, &'src str, Statement> + Clone {
+        let direction = choice((
+            just("TB").to(Direction::TopDown),
+            just("TD").to(Direction::TopDown),
+            just("BT").to(Direction::BottomUp),
+            just("LR").to(Direction::LeftRight),
+            just("RL").to(Direction::RightLeft),
+        ));
+        just("direction")
+            .then(optional_whitespace())
+            .ignore_then(direction)
+            .map(Statement::Direction)
+    }
+
     /// Parse a comma-separated list of identifiers: `A,B,C`
     fn id_list_parser<'src>() -> impl Parser<'src, &'src str, Vec<String>> + Clone {
         ident()
@@ -158,7 +212,7 @@ impl ChumskyFlowchartParser {
             .then_ignore(just(']'))
             .map(|(id, label)| (id, label, NodeShape::Rectangle));
 
-        // A(label) - Rounded rectangle / stadium
+        // A(label) - Rounded rectangle
         let rounded = node_id
             .then_ignore(just('('))
             .then(Self::label_parser())
@@ -179,6 +233,20 @@ impl ChumskyFlowchartParser {
             .then_ignore(just("))"))
             .map(|(id, label)| (id, label, NodeShape::Circle));
 
+        // A(((label))) - Double circle
+        let double_circle = node_id
+            .then_ignore(just("((("))
+            .then(Self::label_parser())
+            .then_ignore(just(")))"))
+            .map(|(id, label)| (id, label, NodeShape::DoubleCircle));
+
+        // A([label]) - Stadium
+        let stadium = node_id
+            .then_ignore(just("(["))
+            .then(Self::label_parser())
+            .then_ignore(just("])"))
+            .map(|(id, label)| (id, label, NodeShape::Stadium));
+
         // A[[label]] - Subroutine
         let subroutine = node_id
             .then_ignore(just("[["))
@@ -214,6 +282,20 @@ impl ChumskyFlowchartParser {
             .then_ignore(just("\\]"))
             .map(|(id, label)| (id, label, NodeShape::Trapezoid));
 
+        // A[\label\] - Parallelogram leaning the other way
+        let parallelogram_alt = node_id
+            .then_ignore(just("[\\"))
+            .then(Self::label_parser_no_slash())
+            .then_ignore(just("\\]"))
+            .map(|(id, label)| (id, label, NodeShape::ParallelogramAlt));
+
+        // A[\label/] - Inverted trapezoid
+        let inv_trapezoid = node_id
+            .then_ignore(just("[\\"))
+            .then(Self::label_parser_no_slash())
+            .then_ignore(just("/]"))
+            .map(|(id, label)| (id, label, NodeShape::InvTrapezoid));
+
         // A>label] - Asymmetric (flag)
         let asymmetric = node_id
             .then_ignore(just('>'))
@@ -225,9 +307,13 @@ impl ChumskyFlowchartParser {
         hexagon
             .or(cylinder)
             .or(subroutine)
+            .or(double_circle)
             .or(circle)
+            .or(stadium)
             .or(parallelogram)
             .or(trapezoid)
+            .or(parallelogram_alt)
+            .or(inv_trapezoid)
             .or(rectangular)
             .or(rounded)
             .or(diamond)
@@ -245,26 +331,58 @@ impl ChumskyFlowchartParser {
     fn edge_parser<'src>() -> impl Parser<'src, &'src str, Edge> + Clone {
         let node_id = Self::node_reference();
 
-        // Edge connectors - order by specificity (longer first)
-        let thick_arrow = just("==>").to(EdgeType::ThickArrow);
-        let thick_line = just("===").to(EdgeType::ThickLine);
-        let dotted_arrow = just("-.->").to(EdgeType::DottedArrow);
-        let dotted_line = just("-.-").to(EdgeType::DottedLine);
-        let arrow = just("-->").to(EdgeType::Arrow);
-        let line = just("---").to(EdgeType::Line);
-        let open_arrow = just("--o").to(EdgeType::OpenArrow);
-        let cross_arrow = just("--x").to(EdgeType::CrossArrow);
-        let invisible = just("~~~").to(EdgeType::Invisible);
-
-        let edge_connector = thick_arrow
-            .or(thick_line)
-            .or(dotted_arrow)
-            .or(dotted_line)
-            .or(arrow)
-            .or(line)
-            .or(open_arrow)
-            .or(cross_arrow)
-            .or(invisible)
+        // Edge connectors - order by specificity (longer first). Each extra
+        // dash, dot or equals sign past the shortest form lengthens the edge
+        // by one layer, as in `A ---> B`.
+        let run = |c: char, shortest: usize| {
+            just(c)
+                .repeated()
+                .at_least(shortest)
+                .count()
+                .map(move |n| n + 1 - shortest)
+        };
+        let thick_arrow = run('=', 2)
+            .then_ignore(just('>'))
+            .map(|len| (EdgeType::ThickArrow, len));
+        let thick_line = run('=', 3).map(|len| (EdgeType::ThickLine, len));
+        let dotted_arrow = just('-')
+            .ignore_then(run('.', 1))
+            .then_ignore(just("->"))
+            .map(|len| (EdgeType::DottedArrow, len));
+        let dotted_line = just('-')
+            .ignore_then(run('.', 1))
+            .then_ignore(just('-'))
+            .map(|len| (EdgeType::DottedLine, len));
+        let arrow = run('-', 2)
+            .then_ignore(just('>'))
+            .map(|len| (EdgeType::Arrow, len));
+        let line = run('-', 3).map(|len| (EdgeType::Line, len));
+        let open_arrow = run('-', 2)
+            .then_ignore(just('o'))
+            .map(|len| (EdgeType::OpenArrow, len));
+        let cross_arrow = run('-', 2)
+            .then_ignore(just('x'))
+            .map(|len| (EdgeType::CrossArrow, len));
+        let invisible = run('~', 3).map(|len| (EdgeType::Invisible, len));
+
+        // A marker before the connector decorates the source end too:
+        // `<-->`, `o--o`, `x--x`
+        let edge_connector = one_of("<ox")
+            .or_not()
+            .then(
+                thick_arrow
+                    .or(thick_line)
+                    .or(dotted_arrow)
+                    .or(dotted_line)
+                    .or(arrow)
+                    .or(open_arrow)
+                    .or(cross_arrow)
+                    .or(line)
+                    .or(invisible),
+            )
+            .map(|(marker, (edge_type, length))| {
+                (edge_type, length, start_marker(marker, edge_type))
+            })
             .then_ignore(optional_whitespace());
 
         // Edge label: |label|
@@ -279,14 +397,18 @@ impl ChumskyFlowchartParser {
             .then(edge_connector)
             .then(edge_label)
             .then(node_id)
-            .map(|(((from_ref, edge_type), label), to_ref)| Edge {
-                from: from_ref.id.clone(),
-                to: to_ref.id.clone(),
-                from_ref,
-                to_ref,
-                edge_type,
-                label,
-            })
+            .map(
+                |(((from_ref, (edge_type, length, start)), label), to_ref)| Edge {
+                    from: from_ref.id.clone(),
+                    to: to_ref.id.clone(),
+                    from_ref,
+                    to_ref,
+                    edge_type,
+                    label,
+                    length,
+                    start,
+                },
+            )
             .labelled("edge definition")
     }
 
@@ -332,6 +454,16 @@ impl ChumskyFlowchartParser {
             .then_ignore(just("))"))
             .map(|label| (label, NodeShape::Circle));
 
+        let triple_paren = just("(((")
+            .ignore_then(Self::label_parser())
+            .then_ignore(just(")))"))
+            .map(|label| (label, NodeShape::DoubleCircle));
+
+        let stadium = just("([")
+            .ignore_then(Self::label_parser())
+            .then_ignore(just("])"))
+            .map(|label| (label, NodeShape::Stadium));
+
         let cylinder = just("[(")
             .ignore_then(Self::label_parser())
             .then_ignore(just(")]"))
@@ -367,13 +499,27 @@ impl ChumskyFlowchartParser {
             .then_ignore(just("\\]"))
             .map(|label| (label, NodeShape::Trapezoid));
 
+        let parallelogram_alt = just("[\\")
+            .ignore_then(Self::label_parser_no_slash())
+            .then_ignore(just("\\]"))
+            .map(|label| (label, NodeShape::ParallelogramAlt));
+
+        let inv_trapezoid = just("[\\")
+            .ignore_then(Self::label_parser_no_slash())
+            .then_ignore(just("/]"))
+            .map(|label| (label, NodeShape::InvTrapezoid));
+
         // Order by specificity
         double_bracket
             .or(double_brace)
+            .or(triple_paren)
             .or(double_paren)
+            .or(stadium)
             .or(cylinder)
             .or(parallelogram)
             .or(trapezoid)
+            .or(parallelogram_alt)
+            .or(inv_trapezoid)
             .or(bracket)
             .or(paren)
             .or(brace)
@@ -412,22 +558,53 @@ impl ChumskyFlowchartParser {
     }
 
     fn label_parser<'src>() -> impl Parser<'src, &'src str, String> + Clone {
-        none_of("[](){}|\"\n\r\t")
-            .repeated()
-            .at_least(1)
-            .collect::<String>()
+        Self::quoted_label()
+            .or(none_of("[](){}|\"\n\r\t")
+                .repeated()
+                .at_least(1)
+                .collect::<String>()
+                .map(|label| label_text(&label)))
             .labelled("label")
     }
 
     fn label_parser_no_slash<'src>() -> impl Parser<'src, &'src str, String> + Clone {
-        none_of("[](){}|\"/\\\n\r\t")
+        Self::quoted_label()
+            .or(none_of("[](){}|\"/\\\n\r\t")
+                .repeated()
+                .at_least(1)
+                .collect::<String>()
+                .map(|label| label_text(&label)))
+            .labelled("label")
+    }
+
+    /// `"text"` label, which may hold delimiters; a markdown string
+    /// (`` "`text`" ``) loses its emphasis markers
+    fn quoted_label<'src>() -> impl Parser<'src, &'src str, String> + Clone {
+        none_of("\"\n")
             .repeated()
-            .at_least(1)
             .collect::<String>()
-            .labelled("label")
+            .delimited_by(just('"'), just('"'))
+            .map(|text| label_text(&markdown_text(&text)))
     }
 }
 
+/// Source-end marker for the character written before a connector
+fn start_marker(marker: Option<char>, edge_type: EdgeType) -> EdgeTerminator {
+    match marker {
+        Some('<') if edge_type.is_dotted() => EdgeTerminator::Triangle,
+        Some('<') => EdgeTerminator::Arrow,
+        Some('o') => EdgeTerminator::Open,
+        Some('x') => EdgeTerminator::Cross,
+        _ => EdgeTerminator::None,
+    }
+}
+
+/// Text of a label as written: `<br>` tags become line breaks, then
+/// entities are decoded (so `&lt;br&gt;` stays visible text)
+fn label_text(raw: &str) -> String {
+    decode_entities(&line_breaks(raw)).into_owned()
+}
+
 impl Default for ChumskyFlowchartParser {
     fn default() -> Self {
         Self::new()
@@ -463,6 +640,10 @@ pub struct Edge {
     pub to_ref: NodeRef,
     pub edge_type: EdgeType,
     pub label: Option<String>,
+    /// Layers the connector asks for: 1, plus one per extra dash
+    pub length: usize,
+    /// Marker written before the connector, for the source end
+    pub start: EdgeTerminator,
 }
 
 /// A parsed statement from the diagram
@@ -479,6 +660,10 @@ pub enum Statement {
     Class(Vec<String>, String),
     /// `linkStyle 0,1,2 stroke:#ff3`
     LinkStyle(Vec<usize>, StyleDefinition),
+    /// `click nodeId "url" "tooltip"`; `None` for JavaScript callbacks
+    Click(String, Option<NodeLink>),
+    /// `direction LR` inside a subgraph
+    Direction(Direction),
 }
 
 #[cfg(test)]
@@ -683,6 +868,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_extended_connectors_set_edge_length() {
+        let parser = ChumskyFlowchartParser::new();
+        let edge = |input: &str| match parser.parse_statement(input).unwrap() {
+            Statement::Edge(edge) => (edge.edge_type, edge.length),
+            other => panic!("expected an edge, got {:?}", other),
+        };
+
+        assert_eq!(edge("A --> B"), (EdgeType::Arrow, 1));
+        assert_eq!(edge("A ----> B"), (EdgeType::Arrow, 3));
+        assert_eq!(edge("A ---- B"), (EdgeType::Line, 2));
+        assert_eq!(edge("A ===> B"), (EdgeType::ThickArrow, 2));
+        assert_eq!(edge("A ==== B"), (EdgeType::ThickLine, 2));
+        assert_eq!(edge("A -..-> B"), (EdgeType::DottedArrow, 2));
+        assert_eq!(edge("A -...- B"), (EdgeType::DottedLine, 3));
+        assert_eq!(edge("A ---x B"), (EdgeType::CrossArrow, 2));
+        assert_eq!(edge("A ~~~~ B"), (EdgeType::Invisible, 2));
+    }
+
+    #[test]
+    fn test_bidirectional_connectors_mark_the_source_end() {
+        let parser = ChumskyFlowchartParser::new();
+        let edge = |input: &str| match parser.parse_statement(input).unwrap() {
+            Statement::Edge(edge) => (edge.start, edge.edge_type),
+            other => panic!("expected an edge, got {:?}", other),
+        };
+
+        assert_eq!(edge("A <--> B"), (EdgeTerminator::Arrow, EdgeType::Arrow));
+        assert_eq!(
+            edge("A <-.-> B"),
+            (EdgeTerminator::Triangle, EdgeType::DottedArrow)
+        );
+        assert_eq!(
+            edge("A <==> B"),
+            (EdgeTerminator::Arrow, EdgeType::ThickArrow)
+        );
+        assert_eq!(
+            edge("A o--o B"),
+            (EdgeTerminator::Open, EdgeType::OpenArrow)
+        );
+        assert_eq!(
+            edge("A x--x B"),
+            (EdgeTerminator::Cross, EdgeType::CrossArrow)
+        );
+        assert_eq!(edge("A --> B"), (EdgeTerminator::None, EdgeType::Arrow));
+    }
+
+    #[test]
+    fn test_click_statements_carry_links() {
+        let parser = ChumskyFlowchartParser::new();
+        let click = |input: &str| match parser.parse_statement(input).unwrap() {
+            Statement::Click(id, link) => (id, link),
+            other => panic!("expected a click, got {:?}", other),
+        };
+
+        assert_eq!(
+            click("click A \"https://example.com\" \"Docs &amp; more\""),
+            (
+                "A".to_string(),
+                Some(NodeLink::new("https://example.com").with_tooltip("Docs & more"))
+            )
+        );
+        assert_eq!(
+            click("click B href \"https://example.com/b\" _blank"),
+            (
+                "B".to_string(),
+                Some(NodeLink::new("https://example.com/b"))
+            )
+        );
+        assert_eq!(
+            click("click C callback \"Tooltip\""),
+            ("C".to_string(), None)
+        );
+        assert_eq!(
+            click("click D call callback(\"x\")"),
+            ("D".to_string(), None)
+        );
+
+        // A node whose ID starts with `click` is still a node
+        assert!(matches!(
+            parser.parse_statement("clickMe --> B"),
+            Ok(Statement::Edge(_))
+        ));
+    }
+
     #[test]
     fn test_all_edge_connector_types() {
         let parser = ChumskyFlowchartParser::new();
@@ -869,6 +1139,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_subgraph_direction() {
+        let parser = ChumskyFlowchartParser::new();
+
+        let input = "subgraph Row\n    direction LR\n    A --> B\nend";
+        let Statement::Subgraph(_, children) = parser.parse_statement(input).unwrap() else {
+            panic!("Expected subgraph statement");
+        };
+        assert_eq!(children[0], Statement::Direction(Direction::LeftRight));
+
+        // Not a direction: still an edge from a node called `direction`
+        assert!(matches!(
+            parser.parse_statement("direction --> B").unwrap(),
+            Statement::Edge(_)
+        ));
+    }
+
+    #[test]
+    fn test_quoted_and_markdown_labels() {
+        let parser = ChumskyFlowchartParser::new();
+
+        let Statement::Edge(edge) = parser
+            .parse_statement(r#"A["f(x) {y}"] -->|"a|b<br/>c"| B("`**Bold** <br> move`")"#)
+            .unwrap()
+        else {
+            panic!("Expected edge statement");
+        };
+        assert_eq!(edge.from_ref.label.as_deref(), Some("f(x) {y}"));
+        assert_eq!(edge.label.as_deref(), Some("a|b\nc"));
+        assert_eq!(edge.to_ref.label.as_deref(), Some("Bold\nmove"));
+
+        let Statement::Node(node) = parser.parse_statement("C[one<br/>two]").unwrap() else {
+            panic!("Expected node statement");
+        };
+        assert_eq!(node.label, "one\ntwo");
+    }
+
+    #[test]
+    fn test_labels_decode_entities() {
+        let parser = ChumskyFlowchartParser::new();
+
+        let Statement::Node(node) = parser
+            .parse_statement(r##"A["#quot;Fish#quot; &amp; chips #35;1 &lt;br&gt;"]"##)
+            .unwrap()
+        else {
+            panic!("Expected node statement");
+        };
+        assert_eq!(node.label, "\"Fish\" & chips #1 <br>");
+    }
+
     #[test]
     fn test_subgraph_without_quotes() {
         let parser = ChumskyFlowchartParser::new();
@@ -933,7 +1253,7 @@ mod tests {
         assert!(parser.parse_statement("A -->").is_err());
 
         // Invalid connector
-        assert!(parser.parse_statement("A ----> B").is_err());
+        assert!(parser.parse_statement("A -> B").is_err());
     }
 
     #[test]
@@ -7,31 +7,19 @@ use anyhow::Result;
 use std::collections::HashMap;
 use tracing::{debug, trace};
 
-use crate::core::{Database, Direction, EdgeData, EdgeType, NodeData, NodeShape, StyleDefinition};
-
-/// A subgraph container grouping related nodes
-#[derive(Debug, Clone)]
-pub struct Subgraph {
-    /// Unique identifier for this subgraph (e.g., "subgraph_0" or slugified title)
-    pub id: String,
-    /// Display title for the subgraph border
-    pub title: String,
-    /// Node IDs contained in this subgraph
-    pub members: Vec<String>,
-}
+use super::ParseWarning;
+use crate::core::{
+    id_slug, Database, Direction, EdgeData, EdgeType, IdAllocator, IdCase, IdRemap, IdResolver,
+    NodeData, NodeLink, NodeShape, StyleDefinition,
+};
 
-impl Subgraph {
-    /// Create a new subgraph with the given title and members
-    pub fn new(id: String, title: String, members: Vec<String>) -> Self {
-        Self { id, title, members }
-    }
-}
+pub use crate::core::Subgraph;
 
 /// Flowchart database implementation
 ///
 /// Stores nodes, edges, and metadata for flowchart diagrams.
 /// Maintains insertion order for deterministic layout.
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct FlowchartDatabase {
     /// Flow direction for the diagram
     direction: Direction,
@@ -43,10 +31,18 @@ pub struct FlowchartDatabase {
     node_order: Vec<String>,
     /// Subgraphs in insertion order
     subgraphs: Vec<Subgraph>,
-    /// Counter for generating unique subgraph IDs
-    subgraph_counter: usize,
+    /// Subgraph IDs handed out so far
+    subgraph_ids: IdAllocator,
     /// Class definitions from `classDef` statements
     class_defs: HashMap<String, StyleDefinition>,
+    /// Nodes kept in the model but left out of layout and rendering
+    hidden_nodes: Vec<String>,
+    /// Edges (from, to) kept in the model but left out of layout and rendering
+    hidden_edges: Vec<(String, String)>,
+    /// Case handling for node IDs
+    ids: IdResolver,
+    /// Warnings recorded while parsing into this database
+    warnings: Vec<ParseWarning>,
 }
 
 impl FlowchartDatabase {
@@ -73,9 +69,37 @@ impl FlowchartDatabase {
         self.direction
     }
 
+    /// Set how node IDs that differ only in case are treated
+    pub fn set_id_case(&mut self, case: IdCase) {
+        self.ids.set_case(case);
+    }
+
+    /// Get how node IDs that differ only in case are treated
+    pub fn id_case(&self) -> IdCase {
+        self.ids.case()
+    }
+
+    /// Take the warnings about node IDs that differ only in case
+    pub fn take_id_warnings(&mut self) -> Vec<String> {
+        self.ids.take_warnings()
+    }
+
+    /// Record a parse warning
+    pub(crate) fn add_parse_warning(&mut self, warning: ParseWarning) {
+        self.warnings.push(warning);
+    }
+
+    /// Take the messages of the recorded parse warnings
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        self.take_parse_warnings()
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect()
+    }
+
     /// Check if a node exists
     pub fn has_node(&self, id: &str) -> bool {
-        self.nodes.contains_key(id)
+        self.nodes.contains_key(self.ids.lookup(id))
     }
 
     /// Get in-degree (number of incoming edges) for a node
@@ -211,8 +235,7 @@ impl FlowchartDatabase {
     /// Returns the generated subgraph ID. Nodes that are already in another
     /// subgraph are silently ignored (first subgraph wins).
     pub fn add_subgraph(&mut self, title: String, members: Vec<String>) -> String {
-        let id = format!("subgraph_{}", self.subgraph_counter);
-        self.subgraph_counter += 1;
+        let id = self.subgraph_ids.next("subgraph");
 
         // Filter out nodes that are already in another subgraph
         let existing_members: std::collections::HashSet<&str> = self
@@ -223,6 +246,7 @@ impl FlowchartDatabase {
 
         let filtered_members: Vec<String> = members
             .into_iter()
+            .map(|m| self.ids.resolve(&m))
             .filter(|m| {
                 if existing_members.contains(m.as_str()) {
                     trace!(node_id = %m, subgraph_id = %id, "Node already in another subgraph, skipping");
@@ -247,26 +271,229 @@ impl FlowchartDatabase {
         id
     }
 
-    /// Get a subgraph by ID
-    pub fn get_subgraph(&self, id: &str) -> Option<&Subgraph> {
-        self.subgraphs.iter().find(|s| s.id == id)
+    /// Lay out a subgraph's members in their own direction
+    pub fn set_subgraph_direction(&mut self, id: &str, direction: Direction) {
+        if let Some(subgraph) = self.subgraphs.iter_mut().find(|s| s.id == id) {
+            subgraph.direction = Some(direction);
+        }
     }
 
-    /// Iterate over all subgraphs
-    pub fn subgraphs(&self) -> impl Iterator<Item = &Subgraph> {
-        self.subgraphs.iter()
+    /// Nest a subgraph inside another one
+    pub fn set_subgraph_parent(&mut self, id: &str, parent: &str) {
+        if let Some(subgraph) = self.subgraphs.iter_mut().find(|s| s.id == id) {
+            subgraph.parent = Some(parent.to_string());
+        }
     }
 
-    /// Get the subgraph that contains a given node, if any
-    pub fn node_subgraph(&self, node_id: &str) -> Option<&Subgraph> {
-        self.subgraphs
+    /// Copy another diagram into this one, keeping every ID unique
+    ///
+    /// Nodes with an ID already in use are shared between the diagrams, or,
+    /// when a `namespace` is given, renamed to `namespace_id`. Subgraphs whose
+    /// ID is taken are renamed after their title (`subgraph_payments`). The
+    /// returned [`IdRemap`] maps the other diagram's IDs to the new ones.
+    ///
+    /// # Example
+    /// ```rust
+    /// use figurehead::plugins::flowchart::FlowchartDatabase;
+    /// use figurehead::Database;
+    ///
+    /// let mut first = FlowchartDatabase::new();
+    /// first.add_simple_edge("A", "B").unwrap();
+    /// first.add_subgraph("Orders".into(), vec!["A".into()]);
+    ///
+    /// let mut second = FlowchartDatabase::new();
+    /// second.add_simple_edge("A", "C").unwrap();
+    /// second.add_subgraph("Payments".into(), vec!["A".into()]);
+    ///
+    /// let remap = first.merge(&second, Some("pay"));
+    /// assert_eq!(remap.node("A"), "pay_A");
+    /// assert_eq!(remap.subgraph("subgraph_0"), "subgraph_payments");
+    /// assert_eq!(first.node_subgraph("pay_A").unwrap().title, "Payments");
+    /// ```
+    pub fn merge(&mut self, other: &FlowchartDatabase, namespace: Option<&str>) -> IdRemap {
+        let mut remap = IdRemap::default();
+        let mut node_ids = IdAllocator::new();
+        for id in self.mentioned_ids() {
+            node_ids.reserve(id);
+        }
+        if let Some(namespace) = namespace {
+            for id in other.mentioned_ids() {
+                if node_ids.contains(id) && !remap.has_node(id) {
+                    let renamed = node_ids.allocate(&format!("{}_{}", id_slug(namespace), id));
+                    remap.rename_node(id, renamed);
+                }
+            }
+        }
+        for node in other.nodes() {
+            let _ = self.add_node(NodeData {
+                id: remap.node(&node.id).to_string(),
+                ..node.clone()
+            });
+        }
+        for edge in other.edges() {
+            let _ = self.add_edge(EdgeData {
+                from: remap.node(&edge.from).to_string(),
+                to: remap.node(&edge.to).to_string(),
+                ..edge.clone()
+            });
+        }
+        for id in &other.hidden_nodes {
+            self.hide_node(remap.node(id));
+        }
+        for (from, to) in &other.hidden_edges {
+            self.hide_edge(remap.node(from), remap.node(to));
+        }
+        for (name, style) in &other.class_defs {
+            self.class_defs
+                .entry(name.clone())
+                .or_insert_with(|| style.clone());
+        }
+
+        // Nodes keep the first subgraph they were put in
+        let grouped: std::collections::HashSet<String> = self
+            .subgraphs
             .iter()
-            .find(|s| s.members.iter().any(|m| m == node_id))
+            .flat_map(|s| s.members.iter().cloned())
+            .collect();
+        for subgraph in &other.subgraphs {
+            let id = if self.subgraph_ids.contains(&subgraph.id) {
+                match id_slug(&subgraph.title) {
+                    slug if slug.is_empty() => self.subgraph_ids.next("subgraph"),
+                    slug => self.subgraph_ids.allocate(&format!("subgraph_{}", slug)),
+                }
+            } else {
+                self.subgraph_ids.reserve(subgraph.id.clone());
+                subgraph.id.clone()
+            };
+            remap.rename_subgraph(&subgraph.id, &id);
+        }
+        for subgraph in &other.subgraphs {
+            let members = subgraph
+                .members
+                .iter()
+                .map(|m| remap.node(m).to_string())
+                .filter(|m| !grouped.contains(m))
+                .collect();
+            self.subgraphs.push(Subgraph {
+                id: remap.subgraph(&subgraph.id).to_string(),
+                members,
+                parent: subgraph
+                    .parent
+                    .as_deref()
+                    .map(|parent| remap.subgraph(parent).to_string()),
+                ..subgraph.clone()
+            });
+        }
+
+        debug!(
+            node_count = self.node_count(),
+            subgraph_count = self.subgraphs.len(),
+            "Merged database"
+        );
+        remap
     }
 
-    /// Get the count of subgraphs
-    pub fn subgraph_count(&self) -> usize {
-        self.subgraphs.len()
+    /// Node IDs of the nodes and of every edge end, in order of appearance
+    fn mentioned_ids(&self) -> impl Iterator<Item = &str> {
+        self.node_order.iter().map(String::as_str).chain(
+            self.edges
+                .iter()
+                .flat_map(|e| [e.from.as_str(), e.to.as_str()]),
+        )
+    }
+
+    /// Leave a node, and every edge touching it, out of layout and rendering
+    ///
+    /// The node stays in the model, so analysis and exports still see it.
+    pub fn hide_node(&mut self, id: impl Into<String>) {
+        let id = self.ids.lookup(&id.into()).to_string();
+        if !self.hidden_nodes.contains(&id) {
+            self.hidden_nodes.push(id);
+        }
+    }
+
+    /// Leave every edge from `from` to `to` out of layout and rendering
+    pub fn hide_edge(&mut self, from: impl Into<String>, to: impl Into<String>) {
+        let key = (
+            self.ids.lookup(&from.into()).to_string(),
+            self.ids.lookup(&to.into()).to_string(),
+        );
+        if !self.hidden_edges.contains(&key) {
+            self.hidden_edges.push(key);
+        }
+    }
+
+    /// Check if a node is hidden
+    pub fn is_node_hidden(&self, id: &str) -> bool {
+        self.hidden_nodes.iter().any(|h| h == id)
+    }
+
+    /// Check if an edge is hidden, directly or through one of its nodes
+    pub fn is_edge_hidden(&self, edge: &EdgeData) -> bool {
+        self.is_node_hidden(&edge.from)
+            || self.is_node_hidden(&edge.to)
+            || self
+                .hidden_edges
+                .iter()
+                .any(|(from, to)| *from == edge.from && *to == edge.to)
+    }
+
+    /// Returns true if anything is hidden
+    pub fn has_hidden(&self) -> bool {
+        !self.hidden_nodes.is_empty() || !self.hidden_edges.is_empty()
+    }
+
+    /// Copy of the diagram with a `[n]` footnote marker after the label of
+    /// each linked node, with the links in marker order
+    ///
+    /// Markers are numbered in node order, starting at 1; hidden nodes get
+    /// none.
+    pub fn link_footnotes(&self) -> (FlowchartDatabase, Vec<NodeLink>) {
+        let mut marked = self.clone();
+        let mut links = Vec::new();
+        for id in self.node_order.iter().filter(|id| !self.is_node_hidden(id)) {
+            let Some(node) = marked.nodes.get_mut(id) else {
+                continue;
+            };
+            if let Some(link) = &node.link {
+                links.push(link.clone());
+                node.label = format!("{} [{}]", node.label, links.len());
+            }
+        }
+        (marked, links)
+    }
+
+    /// Copy of the diagram without hidden nodes and edges
+    ///
+    /// Subgraphs keep their IDs; those left without members are dropped.
+    pub fn visible_view(&self) -> FlowchartDatabase {
+        let mut view = FlowchartDatabase::with_direction(self.direction);
+        for node in self.nodes().filter(|n| !self.is_node_hidden(&n.id)) {
+            let _ = view.add_node(node.clone());
+        }
+        for edge in self.edges.iter().filter(|e| !self.is_edge_hidden(e)) {
+            let _ = view.add_edge(edge.clone());
+        }
+        view.subgraphs = self
+            .subgraphs
+            .iter()
+            .filter_map(|subgraph| {
+                let members: Vec<String> = subgraph
+                    .members
+                    .iter()
+                    .filter(|m| !self.is_node_hidden(m))
+                    .cloned()
+                    .collect();
+                (!members.is_empty()).then(|| Subgraph {
+                    members,
+                    ..subgraph.clone()
+                })
+            })
+            .collect();
+        view.subgraph_ids = self.subgraph_ids.clone();
+        view.class_defs = self.class_defs.clone();
+        view.ids = self.ids.clone();
+        view
     }
 }
 
@@ -274,7 +501,16 @@ impl Database for FlowchartDatabase {
     type Node = NodeData;
     type Edge = EdgeData;
 
-    fn add_node(&mut self, node: NodeData) -> Result<()> {
+    fn parse_warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    fn take_parse_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn add_node(&mut self, mut node: NodeData) -> Result<()> {
+        node.id = self.ids.resolve(&node.id);
         trace!(node_id = %node.id, node_label = %node.label, node_shape = ?node.shape, "Adding node to database");
         if !self.nodes.contains_key(&node.id) {
             self.node_order.push(node.id.clone());
@@ -284,7 +520,9 @@ impl Database for FlowchartDatabase {
         Ok(())
     }
 
-    fn add_edge(&mut self, edge: EdgeData) -> Result<()> {
+    fn add_edge(&mut self, mut edge: EdgeData) -> Result<()> {
+        edge.from = self.ids.resolve(&edge.from);
+        edge.to = self.ids.resolve(&edge.to);
         trace!(
             edge_from = %edge.from,
             edge_to = %edge.to,
@@ -298,7 +536,7 @@ impl Database for FlowchartDatabase {
     }
 
     fn get_node(&self, id: &str) -> Option<&NodeData> {
-        self.nodes.get(id)
+        self.nodes.get(self.ids.lookup(id))
     }
 
     fn nodes(&self) -> impl Iterator<Item = &NodeData> {
@@ -314,8 +552,12 @@ impl Database for FlowchartDatabase {
         self.edges.clear();
         self.node_order.clear();
         self.subgraphs.clear();
-        self.subgraph_counter = 0;
+        self.subgraph_ids.clear();
         self.class_defs.clear();
+        self.hidden_nodes.clear();
+        self.hidden_edges.clear();
+        self.ids.clear();
+        self.warnings.clear();
     }
 
     fn node_count(&self) -> usize {
@@ -325,6 +567,14 @@ impl Database for FlowchartDatabase {
     fn edge_count(&self) -> usize {
         self.edges.len()
     }
+
+    fn subgraphs(&self) -> impl Iterator<Item = &Subgraph> {
+        self.subgraphs.iter()
+    }
+
+    fn subgraph_count(&self) -> usize {
+        self.subgraphs.len()
+    }
 }
 
 // Convenience methods for adding nodes/edges with less boilerplate
@@ -391,7 +641,7 @@ impl FlowchartDatabase {
     ///
     /// Returns true if the node exists and the class was applied.
     pub fn apply_class(&mut self, node_id: &str, class_name: &str) -> bool {
-        if let Some(node) = self.nodes.get_mut(node_id) {
+        if let Some(node) = self.nodes.get_mut(self.ids.lookup(node_id)) {
             node.add_class(class_name);
             trace!(node_id = %node_id, class_name = %class_name, "Applied class to node");
             true
@@ -404,7 +654,7 @@ impl FlowchartDatabase {
     ///
     /// Example: `style A fill:#f9f,stroke:#333`
     pub fn apply_node_style(&mut self, node_id: &str, style: StyleDefinition) -> bool {
-        if let Some(node) = self.nodes.get_mut(node_id) {
+        if let Some(node) = self.nodes.get_mut(self.ids.lookup(node_id)) {
             node.set_style(style);
             trace!(node_id = %node_id, "Applied inline style to node");
             true
@@ -413,6 +663,19 @@ impl FlowchartDatabase {
         }
     }
 
+    /// Attach a hyperlink to a node
+    ///
+    /// Example: `click A "https://example.com" "Docs"`
+    pub fn apply_node_link(&mut self, node_id: &str, link: NodeLink) -> bool {
+        if let Some(node) = self.nodes.get_mut(self.ids.lookup(node_id)) {
+            node.set_link(link);
+            trace!(node_id = %node_id, "Attached link to node");
+            true
+        } else {
+            false
+        }
+    }
+
     /// Apply style to an edge by index
     ///
     /// Example: `linkStyle 0 stroke:#ff3,stroke-width:4px`
@@ -430,7 +693,7 @@ impl FlowchartDatabase {
     ///
     /// Combines class definitions and inline styles. Inline styles take precedence.
     pub fn resolve_node_style(&self, node_id: &str) -> Option<StyleDefinition> {
-        let node = self.nodes.get(node_id)?;
+        let node = self.nodes.get(self.ids.lookup(node_id))?;
 
         let mut style = StyleDefinition::default();
 
@@ -691,6 +954,50 @@ mod tests {
         assert_eq!(id, "subgraph_0");
     }
 
+    #[test]
+    fn test_merge_keeps_ids_unique() {
+        let mut first = FlowchartDatabase::new();
+        for id in ["A", "B"] {
+            first.add_simple_node(id, id).unwrap();
+        }
+        first.add_simple_edge("A", "B").unwrap();
+        first.add_subgraph("Orders".to_string(), vec!["A".to_string()]);
+
+        let mut second = FlowchartDatabase::new();
+        for id in ["A", "C", "D"] {
+            second.add_simple_node(id, id).unwrap();
+        }
+        second.add_simple_edge("A", "C").unwrap();
+        second.add_simple_edge("C", "D").unwrap();
+        let inner = second.add_subgraph("Store".to_string(), vec!["D".to_string()]);
+        let outer = second.add_subgraph(String::new(), vec!["C".to_string()]);
+        second.set_subgraph_parent(&inner, &outer);
+
+        // Shared node IDs join the diagrams
+        let mut shared = first.clone();
+        let remap = shared.merge(&second, None);
+        assert_eq!(shared.node_count(), 4);
+        assert_eq!(shared.successors("A"), ["B", "C"]);
+        assert_eq!(remap.subgraph("subgraph_0"), "subgraph_store");
+        assert_eq!(remap.subgraph("subgraph_1"), "subgraph_1");
+
+        // A namespace keeps them apart, and parents follow the renames
+        let remap = first.merge(&second, Some("Second diagram"));
+        assert_eq!(remap.node("A"), "second_diagram_A");
+        assert_eq!(remap.node("C"), "C");
+        assert_eq!(first.successors("second_diagram_A"), ["C"]);
+        let ids: Vec<_> = first.subgraphs().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["subgraph_0", "subgraph_store", "subgraph_1"]);
+        assert_eq!(
+            first.parent_subgraph("subgraph_store").unwrap().id,
+            "subgraph_1"
+        );
+
+        // Later subgraphs skip the merged IDs
+        let id = first.add_subgraph("Late".to_string(), vec![]);
+        assert_eq!(id, "subgraph_2");
+    }
+
     #[test]
     fn test_class_definition() {
         let mut db = FlowchartDatabase::new();
@@ -803,4 +1110,30 @@ mod tests {
         db.clear();
         assert_eq!(db.class_count(), 0);
     }
+
+    #[test]
+    fn test_hidden_elements_stay_in_model() {
+        let mut db = FlowchartDatabase::new();
+        for id in ["A", "B", "C"] {
+            db.add_simple_node(id, id).unwrap();
+        }
+        db.add_simple_edge("A", "B").unwrap();
+        db.add_simple_edge("B", "C").unwrap();
+        db.add_simple_edge("A", "C").unwrap();
+        db.add_subgraph("only B".to_string(), vec!["B".to_string()]);
+        db.add_subgraph("rest".to_string(), vec!["A".to_string(), "C".to_string()]);
+        db.hide_node("B");
+        db.hide_edge("A", "C");
+
+        assert_eq!(db.node_count(), 3);
+        assert_eq!(db.edge_count(), 3);
+
+        let view = db.visible_view();
+        let ids: Vec<_> = view.nodes().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, ["A", "C"]);
+        assert_eq!(view.edge_count(), 0);
+        assert_eq!(view.subgraph_count(), 1);
+        assert_eq!(view.subgraphs().next().unwrap().id, "subgraph_1");
+        assert!(!view.has_hidden());
+    }
 }
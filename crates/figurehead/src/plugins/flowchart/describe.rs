@@ -0,0 +1,162 @@
+//! Textual summaries of single nodes
+//!
+//! [`FlowchartDatabase::describe_node`] collects what is known about one
+//! node (label, shape, classes, edges, subgraph) so editor integrations can
+//! answer "what is `B`?" without anyone scanning the rendered diagram.
+
+use std::fmt;
+
+use super::FlowchartDatabase;
+use crate::core::{Database, EdgeType, NodeShape};
+
+/// An edge seen from one of its ends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeSummary {
+    /// Source node ID
+    pub from: String,
+    /// Target node ID
+    pub to: String,
+    /// Visual type of the edge
+    pub edge_type: EdgeType,
+    /// Edge label, if any
+    pub label: Option<String>,
+}
+
+impl fmt::Display for EdgeSummary {
+    /// Formats the edge in Mermaid syntax, e.g. `A -->|yes| B`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{} {}|{}| {}", self.from, self.edge_type, label, self.to),
+            None => write!(f, "{} {} {}", self.from, self.edge_type, self.to),
+        }
+    }
+}
+
+/// Summary of a single flowchart node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeDescription {
+    /// Node ID
+    pub id: String,
+    /// Display label
+    pub label: String,
+    /// Visual shape
+    pub shape: NodeShape,
+    /// Classes applied with `:::name` or `class`
+    pub classes: Vec<String>,
+    /// Title of the subgraph containing the node
+    pub subgraph: Option<String>,
+    /// Edges ending at the node, in declaration order
+    pub incoming: Vec<EdgeSummary>,
+    /// Edges starting at the node, in declaration order
+    pub outgoing: Vec<EdgeSummary>,
+}
+
+impl fmt::Display for NodeDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Node {}", self.id)?;
+        writeln!(f, "  Label:    {}", self.label)?;
+        writeln!(f, "  Shape:    {}", self.shape)?;
+        if !self.classes.is_empty() {
+            writeln!(f, "  Classes:  {}", self.classes.join(", "))?;
+        }
+        if let Some(subgraph) = &self.subgraph {
+            writeln!(f, "  Subgraph: {}", subgraph)?;
+        }
+        for (title, edges) in [("Incoming", &self.incoming), ("Outgoing", &self.outgoing)] {
+            writeln!(f, "  {}: {}", title, edges.len())?;
+            for edge in edges {
+                writeln!(f, "    {}", edge)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FlowchartDatabase {
+    /// Describe a node, or `None` if there is no node with this ID
+    ///
+    /// # Example
+    /// ```rust
+    /// use figurehead::plugins::flowchart::FlowchartDatabase;
+    ///
+    /// let mut db = FlowchartDatabase::new();
+    /// db.add_simple_node("A", "Start").unwrap();
+    /// db.add_simple_node("B", "End").unwrap();
+    /// db.add_simple_edge("A", "B").unwrap();
+    ///
+    /// let description = db.describe_node("B").unwrap();
+    /// assert_eq!(description.label, "End");
+    /// assert_eq!(description.incoming[0].to_string(), "A --> B");
+    /// ```
+    pub fn describe_node(&self, id: &str) -> Option<NodeDescription> {
+        let node = self.get_node(id)?;
+        let summary = |edge: &crate::core::EdgeData| EdgeSummary {
+            from: edge.from.clone(),
+            to: edge.to.clone(),
+            edge_type: edge.edge_type,
+            label: edge.label.clone(),
+        };
+
+        Some(NodeDescription {
+            id: node.id.clone(),
+            label: node.label.clone(),
+            shape: node.shape,
+            classes: node.classes.clone(),
+            subgraph: self.node_subgraph(id).map(|s| s.title.clone()),
+            incoming: self.edges().filter(|e| e.to == id).map(summary).collect(),
+            outgoing: self.edges().filter(|e| e.from == id).map(summary).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> FlowchartDatabase {
+        let mut db = FlowchartDatabase::new();
+        db.add_simple_node("A", "Start").unwrap();
+        db.add_shaped_node("B", "Check", NodeShape::Diamond)
+            .unwrap();
+        db.add_simple_node("C", "Done").unwrap();
+        db.add_simple_edge("A", "B").unwrap();
+        db.add_labeled_edge("B", "C", EdgeType::Arrow, "yes")
+            .unwrap();
+        db.add_typed_edge("B", "A", EdgeType::DottedArrow).unwrap();
+        db.add_subgraph("Checks".to_string(), vec!["B".to_string()]);
+        db.apply_class("B", "warn");
+        db
+    }
+
+    #[test]
+    fn test_describe_collects_node_details() {
+        let description = sample().describe_node("B").unwrap();
+        assert_eq!(description.label, "Check");
+        assert_eq!(description.shape, NodeShape::Diamond);
+        assert_eq!(description.classes, vec!["warn"]);
+        assert_eq!(description.subgraph.as_deref(), Some("Checks"));
+        assert_eq!(description.incoming.len(), 1);
+        assert_eq!(
+            description
+                .outgoing
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["B -->|yes| C", "B -.-> A"]
+        );
+    }
+
+    #[test]
+    fn test_describe_text_format() {
+        let text = sample().describe_node("A").unwrap().to_string();
+        assert_eq!(
+            text,
+            "Node A\n  Label:    Start\n  Shape:    rectangle\n  Incoming: 1\n    B -.-> A\n  Outgoing: 1\n    A --> B\n"
+        );
+    }
+
+    #[test]
+    fn test_describe_unknown_node() {
+        assert!(sample().describe_node("Z").is_none());
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! Detects flowchart diagram syntax patterns.
 
-use crate::core::Detector;
+use crate::core::{header_keyword, Detector};
 use tracing::{debug, info, trace};
 
 /// Flowchart detector implementation
@@ -20,6 +20,7 @@ impl Default for FlowchartDetector {
 }
 
 impl FlowchartDetector {
+    /// Create a detector
     pub fn new() -> Self {
         Self
     }
@@ -154,6 +155,12 @@ impl Detector for FlowchartDetector {
         "flowchart"
     }
 
+    fn prefix_confidence(&self, header: &str) -> Option<f64> {
+        let dot = header_keyword(header, "digraph") || header_keyword(header, "strict");
+        (dot || header_keyword(header, "graph") || header_keyword(header, "flowchart"))
+            .then_some(1.0)
+    }
+
     fn patterns(&self) -> Vec<&'static str> {
         let mut patterns = vec![
             "graph",
@@ -0,0 +1,848 @@
+//! DOT (Graphviz) input for flowcharts
+//!
+//! Reads `digraph { a -> b }` and `graph { a -- b }` files into a
+//! [`FlowchartDatabase`], so they are drawn by the same renderer as
+//! Mermaid flowcharts. [`FlowchartParser`](super::FlowchartParser) hands
+//! any input with a DOT header to [`DotParser`].
+//!
+//! Supported:
+//! - node and edge statements, edge chains (`a -> b -> c`) and fan-out
+//!   to anonymous subgraphs (`a -> { b c }`); ports (`a:n`) are dropped
+//! - `node [...]` / `edge [...]` defaults, scoped to their subgraph
+//! - `cluster*` subgraphs, drawn as (nested) subgraphs titled by `label`
+//! - `rankdir`, and the node and edge attributes `label`, `shape`,
+//!   `style`, `color`, `fillcolor`, `fontcolor`, `arrowhead` and `dir`
+//!
+//! Other attributes are ignored, as are plain subgraphs' layout hints such
+//! as `rank=same`.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use tracing::{debug, info, span, trace, warn, Level};
+
+use super::{FlowchartDatabase, ParseWarning};
+use crate::core::{
+    header_keyword, Database, Direction, EdgeData, EdgeTerminator, EdgeType, NodeData, NodeShape,
+    Parser, StyleDefinition,
+};
+
+/// Parser for DOT graphs into a [`FlowchartDatabase`]
+///
+/// # Example
+/// ```rust
+/// use figurehead::plugins::flowchart::{DotParser, FlowchartDatabase};
+/// use figurehead::{Database, Parser};
+///
+/// let mut db = FlowchartDatabase::new();
+/// DotParser::new()
+///     .parse("digraph { rankdir=LR; a -> b [label=\"next\"] }", &mut db)
+///     .unwrap();
+/// assert_eq!(db.edge_count(), 1);
+/// assert_eq!(db.edges().next().unwrap().label.as_deref(), Some("next"));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DotParser;
+
+impl DotParser {
+    /// Create a DOT parser
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+/// Whether `input` is a DOT graph rather than Mermaid
+///
+/// DOT opens with `digraph`, `strict`, or `graph` followed by an optional
+/// name and `{`; Mermaid's `graph TD` has no brace.
+pub fn is_dot(input: &str) -> bool {
+    let header = input
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with("//") && !line.starts_with('#'))
+        .unwrap_or("");
+    if header_keyword(header, "digraph") || header_keyword(header, "strict") {
+        return true;
+    }
+    header_keyword(header, "graph") && {
+        let rest = header[5..].trim_start();
+        rest.starts_with('{')
+            || rest
+                .split_once('{')
+                .is_some_and(|(name, _)| name.split_whitespace().count() == 1)
+    }
+}
+
+impl Parser<FlowchartDatabase> for DotParser {
+    fn parse(&self, input: &str, database: &mut FlowchartDatabase) -> Result<()> {
+        let parse_span = span!(Level::INFO, "parse_dot", input_len = input.len());
+        let _enter = parse_span.enter();
+
+        let tokens = tokenize(input)?;
+        trace!(token_count = tokens.len(), "Tokenized DOT input");
+        let graph = Reader::new(tokens).graph()?;
+        graph.populate(database);
+
+        info!(
+            node_count = database.node_count(),
+            edge_count = database.edge_count(),
+            "DOT parsing completed"
+        );
+        Ok(())
+    }
+
+    fn name(&self) -> &'static str {
+        "dot"
+    }
+
+    fn version(&self) -> &'static str {
+        "0.1.0"
+    }
+
+    fn can_parse(&self, input: &str) -> bool {
+        is_dot(input)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    /// Identifier, number, quoted string or HTML string
+    Id(String),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Equals,
+    Semicolon,
+    Comma,
+    Colon,
+    /// `->` (directed) or `--`
+    EdgeOp,
+}
+
+/// Split DOT source into tokens, each with its 1-based line
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line += 1;
+                line_start = true;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+            // Preprocessor-style lines are comments
+            '#' if line_start => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.next_if_eq(&'/').is_some() => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '/' if chars.next_if_eq(&'*').is_some() => {
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '{' => tokens.push((Token::LBrace, line)),
+            '}' => tokens.push((Token::RBrace, line)),
+            '[' => tokens.push((Token::LBracket, line)),
+            ']' => tokens.push((Token::RBracket, line)),
+            '=' => tokens.push((Token::Equals, line)),
+            ';' => tokens.push((Token::Semicolon, line)),
+            ',' => tokens.push((Token::Comma, line)),
+            ':' => tokens.push((Token::Colon, line)),
+            '-' if chars.next_if(|&c| c == '>' || c == '-').is_some() => {
+                tokens.push((Token::EdgeOp, line))
+            }
+            '"' => {
+                let start = line;
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('"') => text.push('"'),
+                            // Line continuation
+                            Some('\n') => line += 1,
+                            Some(c) => {
+                                text.push('\\');
+                                text.push(c);
+                            }
+                            None => break,
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            text.push(c);
+                        }
+                        None => {
+                            return Err(anyhow!("Parse error: unclosed string on line {}", start))
+                        }
+                    }
+                }
+                tokens.push((Token::Id(text), start));
+            }
+            '<' => {
+                let start = line;
+                let mut depth = 1;
+                let mut text = String::new();
+                for c in chars.by_ref() {
+                    match c {
+                        '<' => depth += 1,
+                        '>' => depth -= 1,
+                        '\n' => line += 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    text.push(c);
+                }
+                if depth > 0 {
+                    return Err(anyhow!(
+                        "Parse error: unclosed HTML label on line {}",
+                        start
+                    ));
+                }
+                tokens.push((Token::Id(strip_tags(&text)), start));
+            }
+            '+' if matches!(tokens.last(), Some((Token::Id(_), _))) => {
+                // `"a" + "b"` joins two quoted strings
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                if chars.next_if_eq(&'"').is_none() {
+                    return Err(anyhow!(
+                        "Parse error: '+' must join two strings on line {}",
+                        line
+                    ));
+                }
+                let mut text = String::new();
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                    text.push(c);
+                }
+                if let Some((Token::Id(previous), _)) = tokens.last_mut() {
+                    previous.push_str(&text);
+                }
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' || !c.is_ascii() => {
+                let mut text = c.to_string();
+                while let Some(c) =
+                    chars.next_if(|&c| c.is_alphanumeric() || c == '_' || c == '.' || !c.is_ascii())
+                {
+                    text.push(c);
+                }
+                tokens.push((Token::Id(text), line));
+            }
+            c => return Err(anyhow!("Parse error: unexpected '{}' on line {}", c, line)),
+        }
+        line_start = false;
+    }
+    Ok(tokens)
+}
+
+/// Text of an HTML label with its tags removed
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+type Attrs = Vec<(String, String)>;
+
+/// Last value of an attribute
+fn attr<'a>(attrs: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .rev()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+/// A `cluster` subgraph
+#[derive(Debug, Default)]
+struct Cluster {
+    name: String,
+    label: Option<String>,
+    parent: Option<usize>,
+    members: Vec<String>,
+}
+
+/// Defaults in force inside one `{ }` block
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    node: Attrs,
+    edge: Attrs,
+    cluster: Option<usize>,
+}
+
+/// Everything read from a DOT graph, before it goes into a database
+#[derive(Debug, Default)]
+struct DotGraph {
+    directed: bool,
+    direction: Direction,
+    nodes: Vec<String>,
+    node_attrs: HashMap<String, Attrs>,
+    edges: Vec<(String, String, Attrs)>,
+    clusters: Vec<Cluster>,
+    warnings: Vec<String>,
+}
+
+/// Recursive-descent reader over DOT tokens
+struct Reader {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    graph: DotGraph,
+    scopes: Vec<Scope>,
+}
+
+impl Reader {
+    fn new(tokens: Vec<(Token, usize)>) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            graph: DotGraph::default(),
+            scopes: vec![Scope::default()],
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Id(id)) if id.eq_ignore_ascii_case(keyword))
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<()> {
+        if self.eat(&token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", what)))
+        }
+    }
+
+    fn error(&self, message: &str) -> anyhow::Error {
+        let found = match self.peek() {
+            Some(Token::Id(id)) => format!("'{}'", id),
+            Some(token) => format!("{:?}", token),
+            None => "end of input".to_string(),
+        };
+        anyhow!(
+            "Parse error: {} on line {}, found {}",
+            message,
+            self.line(),
+            found
+        )
+    }
+
+    fn id(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(Token::Id(id)) => {
+                let id = id.clone();
+                self.pos += 1;
+                Some(id)
+            }
+            _ => None,
+        }
+    }
+
+    fn scope(&self) -> &Scope {
+        self.scopes.last().expect("root scope")
+    }
+
+    /// `[strict] (graph|digraph) [ID] { stmt_list }`
+    fn graph(mut self) -> Result<DotGraph> {
+        if self.peek_keyword("strict") {
+            self.pos += 1;
+        }
+        if self.peek_keyword("digraph") {
+            self.graph.directed = true;
+        } else if !self.peek_keyword("graph") {
+            return Err(self.error("expected 'graph' or 'digraph'"));
+        }
+        self.pos += 1;
+        if !matches!(self.peek(), Some(Token::LBrace)) {
+            self.id();
+        }
+        self.expect(Token::LBrace, "'{'")?;
+        self.statements()?;
+        self.expect(Token::RBrace, "'}'")?;
+        if self.peek().is_some() {
+            return Err(self.error("expected end of input after the graph"));
+        }
+        Ok(self.graph)
+    }
+
+    /// Statements up to the closing `}`; returns the nodes they mention
+    fn statements(&mut self) -> Result<Vec<String>> {
+        let mut mentioned = Vec::new();
+        while !matches!(self.peek(), Some(Token::RBrace) | None) {
+            mentioned.extend(self.statement()?);
+            while self.eat(&Token::Semicolon) {}
+        }
+        Ok(mentioned)
+    }
+
+    fn statement(&mut self) -> Result<Vec<String>> {
+        for (keyword, target) in [("graph", 0), ("node", 1), ("edge", 2)] {
+            if self.peek_keyword(keyword)
+                && matches!(self.tokens.get(self.pos + 1), Some((Token::LBracket, _)))
+            {
+                self.pos += 1;
+                let attrs = self.attr_lists()?;
+                let scope = self.scopes.last_mut().expect("root scope");
+                match target {
+                    0 => {
+                        for (key, value) in attrs {
+                            self.graph_attr(&key, &value);
+                        }
+                    }
+                    1 => scope.node.extend(attrs),
+                    _ => scope.edge.extend(attrs),
+                }
+                return Ok(Vec::new());
+            }
+        }
+
+        // `key = value` at graph level
+        if matches!(self.peek(), Some(Token::Id(_)))
+            && matches!(self.tokens.get(self.pos + 1), Some((Token::Equals, _)))
+        {
+            let key = self.id().unwrap_or_default();
+            self.pos += 1;
+            let value = self.id().ok_or_else(|| self.error("expected a value"))?;
+            self.graph_attr(&key, &value);
+            return Ok(Vec::new());
+        }
+
+        let mut ends = vec![self.endpoint()?];
+        while self.eat(&Token::EdgeOp) {
+            ends.push(self.endpoint()?);
+        }
+        let attrs = if matches!(self.peek(), Some(Token::LBracket)) {
+            self.attr_lists()?
+        } else {
+            Vec::new()
+        };
+
+        if ends.len() == 1 {
+            let nodes = ends.pop().unwrap_or_default();
+            if let [node] = nodes.as_slice() {
+                if !attrs.is_empty() {
+                    self.graph
+                        .node_attrs
+                        .entry(node.clone())
+                        .or_default()
+                        .extend(attrs);
+                }
+            }
+            return Ok(nodes);
+        }
+
+        let mut edge_attrs = self.scope().edge.clone();
+        edge_attrs.extend(attrs);
+        for pair in ends.windows(2) {
+            for from in &pair[0] {
+                for to in &pair[1] {
+                    self.graph
+                        .edges
+                        .push((from.clone(), to.clone(), edge_attrs.clone()));
+                }
+            }
+        }
+        Ok(ends.concat())
+    }
+
+    /// A node ID (with an optional port) or a subgraph; returns its nodes
+    fn endpoint(&mut self) -> Result<Vec<String>> {
+        if self.peek_keyword("subgraph") || matches!(self.peek(), Some(Token::LBrace)) {
+            return self.subgraph();
+        }
+        let id = self
+            .id()
+            .ok_or_else(|| self.error("expected a node, edge or attribute statement"))?;
+        // Ports and compass points only steer Graphviz's routing
+        while self.eat(&Token::Colon) {
+            self.id().ok_or_else(|| self.error("expected a port"))?;
+        }
+        self.mention(&id);
+        Ok(vec![id])
+    }
+
+    /// `[subgraph [ID]] { stmt_list }`
+    fn subgraph(&mut self) -> Result<Vec<String>> {
+        let mut name = None;
+        if self.peek_keyword("subgraph") {
+            self.pos += 1;
+            if !matches!(self.peek(), Some(Token::LBrace)) {
+                name = self.id();
+            }
+        }
+        self.expect(Token::LBrace, "'{' to open the subgraph")?;
+
+        let mut scope = self.scope().clone();
+        if let Some(name) = name.filter(|n| n.starts_with("cluster")) {
+            self.graph.clusters.push(Cluster {
+                name,
+                parent: scope.cluster,
+                ..Cluster::default()
+            });
+            scope.cluster = Some(self.graph.clusters.len() - 1);
+        }
+        self.scopes.push(scope);
+        let mentioned = self.statements();
+        self.scopes.pop();
+        let mentioned = mentioned?;
+        self.expect(Token::RBrace, "'}' to close the subgraph")?;
+        Ok(mentioned)
+    }
+
+    /// One or more `[ key = value, ... ]` lists
+    fn attr_lists(&mut self) -> Result<Attrs> {
+        let mut attrs = Vec::new();
+        while self.eat(&Token::LBracket) {
+            while !self.eat(&Token::RBracket) {
+                let key = self
+                    .id()
+                    .ok_or_else(|| self.error("expected an attribute"))?;
+                let value = if self.eat(&Token::Equals) {
+                    self.id().ok_or_else(|| self.error("expected a value"))?
+                } else {
+                    "true".to_string()
+                };
+                attrs.push((key, value));
+                let _ = self.eat(&Token::Comma) || self.eat(&Token::Semicolon);
+            }
+        }
+        Ok(attrs)
+    }
+
+    /// Record a node, with the defaults in force where it first appears
+    fn mention(&mut self, id: &str) {
+        let scope = self.scopes.last().expect("root scope");
+        if !self.graph.node_attrs.contains_key(id) {
+            self.graph.nodes.push(id.to_string());
+            self.graph
+                .node_attrs
+                .insert(id.to_string(), scope.node.clone());
+        }
+        if let Some(cluster) = scope.cluster {
+            let taken = self
+                .graph
+                .clusters
+                .iter()
+                .any(|c| c.members.iter().any(|m| m == id));
+            if !taken {
+                self.graph.clusters[cluster].members.push(id.to_string());
+            }
+        }
+    }
+
+    fn graph_attr(&mut self, key: &str, value: &str) {
+        match (key, self.scope().cluster) {
+            ("label", Some(cluster)) => self.graph.clusters[cluster].label = Some(label(value)),
+            ("rankdir", None) => match value.to_uppercase().as_str() {
+                "TB" => self.graph.direction = Direction::TopDown,
+                "LR" => self.graph.direction = Direction::LeftRight,
+                "BT" => self.graph.direction = Direction::BottomUp,
+                "RL" => self.graph.direction = Direction::RightLeft,
+                _ => self
+                    .graph
+                    .warnings
+                    .push(format!("Unknown rankdir '{}'; ignored", value)),
+            },
+            _ => trace!(key, value, "Ignoring graph attribute"),
+        }
+    }
+}
+
+/// Label text with DOT's escapes resolved
+fn label(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\l", " ")
+        .replace("\\r", " ")
+        .replace("\\\\", "\\")
+        .trim()
+        .to_string()
+}
+
+/// Flowchart shape for a DOT `shape` (and `style=rounded`)
+fn shape(attrs: &[(String, String)], warnings: &mut Vec<String>) -> NodeShape {
+    let rounded = attr(attrs, "style").is_some_and(|style| style.contains("rounded"));
+    match attr(attrs, "shape").map(str::to_lowercase).as_deref() {
+        None | Some("ellipse" | "oval" | "egg") => NodeShape::RoundedRect,
+        Some(
+            "box" | "rect" | "rectangle" | "square" | "plaintext" | "plain" | "none" | "note"
+            | "underline",
+        ) => match rounded {
+            true => NodeShape::RoundedRect,
+            false => NodeShape::Rectangle,
+        },
+        Some("circle" | "point" | "tripleoctagon") => NodeShape::Circle,
+        Some("doublecircle") => NodeShape::DoubleCircle,
+        Some("diamond" | "mdiamond") => NodeShape::Diamond,
+        Some("hexagon" | "octagon" | "doubleoctagon") => NodeShape::Hexagon,
+        Some("cylinder") => NodeShape::Cylinder,
+        Some("parallelogram") => NodeShape::Parallelogram,
+        Some("trapezium") => NodeShape::Trapezoid,
+        Some("invtrapezium") => NodeShape::InvTrapezoid,
+        Some("cds" | "rarrow" | "larrow" | "rpromoter" | "lpromoter") => NodeShape::Asymmetric,
+        Some("component" | "box3d" | "tab" | "folder") => NodeShape::Subroutine,
+        Some("mrecord" | "record") => NodeShape::Rectangle,
+        Some(other) => {
+            warnings.push(format!("Unknown DOT shape '{}'; drawn as a box", other));
+            NodeShape::Rectangle
+        }
+    }
+}
+
+/// Mermaid-style declaration for the DOT color attributes, if any
+fn style(attrs: &[(String, String)], stroke_key: &str) -> Option<StyleDefinition> {
+    let parts: Vec<String> = [
+        ("fill", "fillcolor"),
+        ("stroke", stroke_key),
+        ("color", "fontcolor"),
+    ]
+    .iter()
+    .filter_map(|(property, key)| Some(format!("{}:{}", property, attr(attrs, key)?)))
+    .collect();
+    (!parts.is_empty()).then(|| StyleDefinition::parse(&parts.join(",")))
+}
+
+impl DotGraph {
+    fn edge_type(&self, attrs: &[(String, String)]) -> EdgeType {
+        let style = attr(attrs, "style").unwrap_or("");
+        let headless = !self.directed
+            || attr(attrs, "dir") == Some("none")
+            || attr(attrs, "arrowhead") == Some("none");
+        if style.contains("invis") {
+            return EdgeType::Invisible;
+        }
+        match attr(attrs, "arrowhead") {
+            Some("dot" | "odot") if !headless => return EdgeType::OpenArrow,
+            Some("tee") if !headless => return EdgeType::CrossArrow,
+            _ => {}
+        }
+        match (
+            headless,
+            style.contains("dashed") || style.contains("dotted"),
+            style.contains("bold"),
+        ) {
+            (false, true, _) => EdgeType::DottedArrow,
+            (true, true, _) => EdgeType::DottedLine,
+            (false, false, true) => EdgeType::ThickArrow,
+            (true, false, true) => EdgeType::ThickLine,
+            (false, false, false) => EdgeType::Arrow,
+            (true, false, false) => EdgeType::Line,
+        }
+    }
+
+    fn populate(mut self, database: &mut FlowchartDatabase) {
+        database.set_direction(self.direction);
+
+        for id in &self.nodes {
+            let attrs = &self.node_attrs[id];
+            let text = match attr(attrs, "label") {
+                Some(text) => label(&text.replace("\\N", id)),
+                None => id.clone(),
+            };
+            let mut node = NodeData::with_shape(id, text, shape(attrs, &mut self.warnings));
+            if let Some(style) = style(attrs, "color") {
+                node.set_style(style);
+            }
+            let _ = database.add_node(node);
+        }
+
+        for (from, to, attrs) in &self.edges {
+            let (from, to) = match attr(attrs, "dir") {
+                Some("back") => (to, from),
+                _ => (from, to),
+            };
+            let mut edge = EdgeData::with_type(from, to, self.edge_type(attrs));
+            edge.label = attr(attrs, "label").map(label);
+            if let Some(min_length) = attr(attrs, "minlen").and_then(|n| n.parse().ok()) {
+                edge = edge.with_min_length(min_length);
+            }
+            if self.directed && attr(attrs, "dir") == Some("both") {
+                edge.start = EdgeTerminator::for_edge_type(edge.edge_type);
+            }
+            if let Some(style) = style(attrs, "color") {
+                edge.set_style(style);
+            }
+            let _ = database.add_edge(edge);
+        }
+
+        let ids: Vec<String> = self
+            .clusters
+            .iter()
+            .map(|cluster| {
+                let title = cluster.label.clone().unwrap_or_else(|| {
+                    cluster
+                        .name
+                        .trim_start_matches("cluster")
+                        .trim_start_matches('_')
+                        .to_string()
+                });
+                database.add_subgraph(title, cluster.members.clone())
+            })
+            .collect();
+        for (cluster, id) in self.clusters.iter().zip(&ids) {
+            if let Some(parent) = cluster.parent {
+                database.set_subgraph_parent(id, &ids[parent]);
+            }
+        }
+        debug!(clusters = ids.len(), "Added DOT clusters");
+
+        for warning in self.warnings {
+            warn!(%warning, "DOT input");
+            database.add_parse_warning(ParseWarning::new(warning));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> FlowchartDatabase {
+        let mut db = FlowchartDatabase::new();
+        DotParser::new().parse(input, &mut db).unwrap();
+        db
+    }
+
+    #[test]
+    fn test_dot_headers_are_recognized() {
+        assert!(is_dot("digraph { a -> b }"));
+        assert!(is_dot("// build graph\nstrict digraph deps {\n}"));
+        assert!(is_dot("graph G {\n a -- b\n}"));
+        assert!(is_dot("graph {"));
+        assert!(!is_dot("graph TD\n A --> B"));
+        assert!(!is_dot("graph\n A --> B"));
+        assert!(!is_dot("flowchart LR\n A{x} --> B"));
+    }
+
+    #[test]
+    fn test_dot_nodes_edges_and_attributes() {
+        let db = parse(
+            r##"digraph build {
+                /* layout */
+                rankdir = LR;
+                node [shape=box];
+                start [label="Start\nhere", shape=ellipse];
+                start -> compile -> test [label="ok"];
+                test -> { deploy report } [style=dashed];
+                compile:e -> lint [dir=back, color=red];
+                "fail" [shape=diamond fillcolor="#f99"]
+            }"##,
+        );
+        assert_eq!(db.direction(), Direction::LeftRight);
+        let start = db.get_node("start").unwrap();
+        assert_eq!(
+            (start.label.as_str(), start.shape),
+            ("Start here", NodeShape::RoundedRect)
+        );
+        assert_eq!(db.get_node("compile").unwrap().shape, NodeShape::Rectangle);
+        assert_eq!(db.get_node("fail").unwrap().shape, NodeShape::Diamond);
+        assert!(db.get_node("fail").unwrap().inline_style.is_some());
+
+        let edges: Vec<_> = db
+            .edges()
+            .map(|e| {
+                (
+                    e.from.as_str(),
+                    e.to.as_str(),
+                    e.edge_type,
+                    e.label.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                ("start", "compile", EdgeType::Arrow, Some("ok")),
+                ("compile", "test", EdgeType::Arrow, Some("ok")),
+                ("test", "deploy", EdgeType::DottedArrow, None),
+                ("test", "report", EdgeType::DottedArrow, None),
+                ("lint", "compile", EdgeType::Arrow, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dot_minlen_and_dir_both() {
+        let db = parse("digraph { a -> b [minlen=3]; b -> c [dir=both] }");
+        let ends: Vec<_> = db.edges().map(|e| (e.min_length, e.start)).collect();
+        assert_eq!(
+            ends,
+            [(3, EdgeTerminator::None), (1, EdgeTerminator::Arrow)]
+        );
+    }
+
+    #[test]
+    fn test_dot_clusters_become_nested_subgraphs() {
+        let db = parse(
+            "graph {\n subgraph cluster_cloud {\n  label=\"Cloud\"\n  subgraph cluster_db { a -- b }\n  c\n }\n c -- a\n d\n}",
+        );
+        assert_eq!(db.edges().next().unwrap().edge_type, EdgeType::Line);
+        assert_eq!(db.node_subgraph("a").unwrap().title, "db");
+        assert_eq!(db.node_subgraph("c").unwrap().title, "Cloud");
+        assert!(db.node_subgraph("d").is_none());
+        let titles: Vec<_> = db
+            .node_subgraphs("b")
+            .into_iter()
+            .map(|s| s.title.as_str())
+            .collect();
+        assert_eq!(titles, ["db", "Cloud"]);
+    }
+
+    #[test]
+    fn test_dot_errors_name_the_line() {
+        let mut db = FlowchartDatabase::new();
+        let error = DotParser::new()
+            .parse("digraph {\n a -> \n}", &mut db)
+            .unwrap_err();
+        assert!(error.to_string().contains("line 3"), "{}", error);
+        assert!(DotParser::new()
+            .parse("digraph { a [label=\"open }", &mut db)
+            .is_err());
+    }
+}
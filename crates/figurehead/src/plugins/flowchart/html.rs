@@ -0,0 +1,211 @@
+//! HTML output colored from `classDef` and `style` statements
+//!
+//! Node styles are painted straight onto the drawn node regions: `stroke`
+//! colors the border, `fill` the background inside it and `color` the label
+//! text. Nothing goes through ANSI escapes, so the full CSS color is kept.
+//! With a [`Theme`] and color on, every other node and the edges between
+//! them take the theme's colors first.
+
+use anyhow::Result;
+
+use super::{FlowchartDatabase, FlowchartRenderer, RenderedFlowchart};
+use crate::core::{CellStyle, Color, Palette, Rgb, RoleLayer, StyleDefinition, Theme};
+use crate::plugins::observer::LayoutProbe;
+
+/// Paint the theme and each styled node of a rendered flowchart onto a
+/// [`RoleLayer`]
+pub(super) fn style_layer(
+    rendered: &RenderedFlowchart,
+    database: &FlowchartDatabase,
+    theme: Option<Theme>,
+) -> RoleLayer {
+    let mut layer = RoleLayer::new();
+    if let Some(theme) = theme {
+        for_each_themed_cell(rendered, |x, y, part| {
+            layer.paint_style(x, y, &theme_style(&theme, part))
+        });
+    }
+    for_each_styled_cell(rendered, database, |x, y, style, on_border| {
+        let (border, inside) = cell_styles(style);
+        layer.paint_style(x, y, if on_border { &border } else { &inside });
+    });
+    layer
+}
+
+/// Visit the cells of every styled node with the node's style
+///
+/// Border cells are visited only where something was drawn (`on_border`
+/// set); every cell inside the border is visited.
+pub(super) fn for_each_styled_cell(
+    rendered: &RenderedFlowchart,
+    database: &FlowchartDatabase,
+    mut visit: impl FnMut(usize, usize, &StyleDefinition, bool),
+) {
+    let grid: Vec<Vec<char>> = rendered
+        .text
+        .lines()
+        .map(|line| line.chars().collect())
+        .collect();
+    let is_drawn = |x: usize, y: usize| {
+        grid.get(y)
+            .and_then(|row| row.get(x))
+            .is_some_and(|c| *c != ' ')
+    };
+
+    for region in &rendered.nodes {
+        let Some(style) = database.resolve_node_style(&region.id) else {
+            continue;
+        };
+        let (right, bottom) = (region.x + region.width, region.y + region.height);
+        for y in region.y..bottom {
+            for x in region.x..right {
+                let on_border = y == region.y || y + 1 == bottom || x == region.x || x + 1 == right;
+                if !on_border || is_drawn(x, y) {
+                    visit(x, y, &style, on_border);
+                }
+            }
+        }
+    }
+}
+
+/// What a cell colored by a [`Theme`] belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ThemePart {
+    /// A drawn cell of a node's border
+    Border,
+    /// Any cell inside a node's border
+    Inside,
+    /// An edge, arrow head or subgraph frame
+    Line,
+    /// Letters and digits between nodes: edge labels, subgraph titles
+    Label,
+}
+
+/// Visit every cell a theme colors
+///
+/// Node cells are visited as in [`for_each_styled_cell`], for every node;
+/// drawn cells outside the nodes are lines, or labels if alphanumeric.
+pub(super) fn for_each_themed_cell(
+    rendered: &RenderedFlowchart,
+    mut visit: impl FnMut(usize, usize, ThemePart),
+) {
+    let grid: Vec<Vec<char>> = rendered
+        .text
+        .lines()
+        .map(|line| line.chars().collect())
+        .collect();
+    let in_node = |x: usize, y: usize| {
+        rendered.nodes.iter().find(|region| {
+            (region.x..region.x + region.width).contains(&x)
+                && (region.y..region.y + region.height).contains(&y)
+        })
+    };
+
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &c) in row.iter().enumerate() {
+            let part = match in_node(x, y) {
+                Some(region) => {
+                    let on_border = y == region.y
+                        || y + 1 == region.y + region.height
+                        || x == region.x
+                        || x + 1 == region.x + region.width;
+                    match (on_border, c) {
+                        (false, _) => ThemePart::Inside,
+                        (true, ' ') => continue,
+                        (true, _) => ThemePart::Border,
+                    }
+                }
+                None if c == ' ' => continue,
+                None if c.is_alphanumeric() => ThemePart::Label,
+                None => ThemePart::Line,
+            };
+            visit(x, y, part);
+        }
+    }
+}
+
+/// CSS for a cell a theme colors
+pub(super) fn theme_style(theme: &Theme, part: ThemePart) -> CellStyle {
+    let css = |(r, g, b): Rgb| format!("#{:02x}{:02x}{:02x}", r, g, b);
+    match part {
+        ThemePart::Border => CellStyle {
+            color: Some(css(theme.stroke)),
+            ..CellStyle::default()
+        },
+        ThemePart::Inside => CellStyle {
+            color: Some(css(theme.text)),
+            background: theme.fill.map(css),
+            ..CellStyle::default()
+        },
+        ThemePart::Line => CellStyle {
+            color: Some(css(theme.line)),
+            ..CellStyle::default()
+        },
+        ThemePart::Label => CellStyle {
+            color: Some(css(theme.text)),
+            ..CellStyle::default()
+        },
+    }
+}
+
+/// CSS for a node's border cells and for the cells inside it
+pub(super) fn cell_styles(style: &StyleDefinition) -> (CellStyle, CellStyle) {
+    let css = |color: &Option<Color>| color.as_ref().map(ToString::to_string);
+    let border = CellStyle {
+        color: css(&style.stroke),
+        ..CellStyle::default()
+    };
+    let inside = CellStyle {
+        color: css(&style.text_color),
+        background: css(&style.fill),
+        ..CellStyle::default()
+    };
+    (border, inside)
+}
+
+impl FlowchartRenderer {
+    /// Render the diagram as an HTML `<pre>` block colored by node styles
+    ///
+    /// Nodes without a `classDef` or `style` stay uncolored unless color is
+    /// on and a theme is set; diagrams drawn without node positions
+    /// (summaries, braille) come out as plain HTML.
+    pub fn render_html(&self, database: &FlowchartDatabase) -> Result<String> {
+        self.render_html_probed(database, &mut LayoutProbe::default())
+    }
+
+    /// Render HTML, timing the layout through `probe`
+    pub(crate) fn render_html_probed(
+        &self,
+        database: &FlowchartDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<String> {
+        let rendered = self.render_regions(database, probe)?;
+        let theme = self.theme().filter(|_| self.color());
+        Ok(style_layer(&rendered, database, theme).to_html(&rendered.text, &Palette::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_styles_become_css() {
+        let db = crate::parse(
+            "graph LR\n A[Go] --> B[Stop]\n classDef warn fill:#f9f,stroke:#333,color:red\n class A warn",
+        )
+        .unwrap();
+        let renderer = FlowchartRenderer::new();
+        let html = renderer.render_html(&db).unwrap();
+
+        assert!(html.starts_with("<pre class=\"figurehead\">"));
+        assert!(html.contains("<span style=\"color:#333\">┌"), "{}", html);
+        assert!(
+            html.contains("<span style=\"color:red;background-color:#f9f\">Go</span>"),
+            "{}",
+            html
+        );
+        // The unstyled node is left alone
+        assert!(html.contains("│ Stop │"), "{}", html);
+    }
+}
@@ -0,0 +1,568 @@
+//! Import of simple ASCII box-and-arrow art (experimental)
+//!
+//! [`ingest_ascii`] reads hand-drawn diagrams back into a
+//! [`FlowchartDatabase`], so they can be turned into Mermaid with
+//! [`FlowchartDatabase::to_mermaid`] and maintained from then on.
+//!
+//! Recognized:
+//! - boxes with `+--+` / `|` borders, or light box-drawing borders (`┌─┐`)
+//! - straight or bent links between them made of `-`, `|` and `+`
+//!   (`=` for thick, `.` / `:` for dotted), with heads `>` `<` `v` `^`
+//!   (or `▶` `◀` `▼` `▲`) touching the target box or one space short of it
+//! - labels written into horizontal links: `--yes-->`
+//!
+//! Every box becomes a rectangle node whose ID is derived from its text.
+//! Anything else (free text, diagonals, other shapes) is ignored.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::{anyhow, Result};
+use tracing::debug;
+
+use super::FlowchartDatabase;
+use crate::core::{Direction, EdgeType, IdAllocator, Point, Rect};
+
+/// Parse ASCII box-and-arrow art into a flowchart database
+///
+/// The flow direction is the one most links follow.
+///
+/// # Example
+/// ```
+/// use figurehead::unstable::flowchart::ingest_ascii;
+///
+/// let art = "\
+/// +-------+      +------+
+/// | Start |--go->| Done |
+/// +-------+      +------+";
+/// let db = ingest_ascii(art).unwrap();
+/// assert_eq!(db.to_mermaid(), "flowchart LR\n    Start\n    Done\n    Start -->|go| Done\n");
+/// ```
+pub fn ingest_ascii(art: &str) -> Result<FlowchartDatabase> {
+    let grid = Grid::new(art);
+    let boxes = find_boxes(&grid);
+    if boxes.is_empty() {
+        return Err(anyhow!("No boxes found in ASCII art"));
+    }
+
+    let mut db = FlowchartDatabase::new();
+    let ids = node_ids(&boxes);
+    for (art_box, id) in boxes.iter().zip(&ids) {
+        db.add_simple_node(id, &art_box.label)?;
+    }
+
+    let links = find_links(&grid, &boxes);
+    db.set_direction(flow_direction(&links, &boxes));
+    for link in &links {
+        let (from, to) = (&ids[link.from], &ids[link.to]);
+        match &link.label {
+            Some(label) => db.add_labeled_edge(from, to, link.edge_type, label)?,
+            None => db.add_typed_edge(from, to, link.edge_type)?,
+        }
+    }
+
+    debug!(
+        node_count = boxes.len(),
+        edge_count = links.len(),
+        "Ingested ASCII art"
+    );
+    Ok(db)
+}
+
+/// Character grid padded to a rectangle
+struct Grid {
+    rows: Vec<Vec<char>>,
+    width: usize,
+}
+
+impl Grid {
+    fn new(art: &str) -> Self {
+        let mut rows: Vec<Vec<char>> = art.lines().map(|l| l.chars().collect()).collect();
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut rows {
+            row.resize(width, ' ');
+        }
+        Self { rows, width }
+    }
+
+    fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    fn at(&self, point: Point) -> char {
+        self.rows
+            .get(point.y)
+            .and_then(|row| row.get(point.x))
+            .copied()
+            .unwrap_or(' ')
+    }
+
+    fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        (0..self.height()).flat_map(move |y| (0..self.width).map(move |x| Point::new(x, y)))
+    }
+}
+
+/// A box found in the art
+struct ArtBox {
+    rect: Rect,
+    label: String,
+}
+
+/// A link between two boxes, by index into the box list
+struct Link {
+    from: usize,
+    to: usize,
+    edge_type: EdgeType,
+    label: Option<String>,
+}
+
+/// Grid direction from one cell to a neighbour
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Side {
+    const ALL: [Side; 4] = [Side::Left, Side::Right, Side::Up, Side::Down];
+
+    fn opposite(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+            Side::Up => Side::Down,
+            Side::Down => Side::Up,
+        }
+    }
+
+    /// Neighbouring cell, or `None` past the top or left edge
+    fn step(self, point: Point) -> Option<Point> {
+        match self {
+            Side::Left => point.x.checked_sub(1).map(|x| Point::new(x, point.y)),
+            Side::Right => Some(Point::new(point.x + 1, point.y)),
+            Side::Up => point.y.checked_sub(1).map(|y| Point::new(point.x, y)),
+            Side::Down => Some(Point::new(point.x, point.y + 1)),
+        }
+    }
+}
+
+fn is_corner(c: char) -> bool {
+    matches!(c, '+' | '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼')
+}
+
+/// Characters allowed along a box's top and bottom border
+fn is_border_h(c: char) -> bool {
+    matches!(c, '-' | '─') || is_corner(c)
+}
+
+/// Characters allowed along a box's left and right border
+fn is_border_v(c: char) -> bool {
+    matches!(c, '|' | '│') || is_corner(c)
+}
+
+/// Where a link character connects to its neighbours
+fn connects(c: char, side: Side) -> bool {
+    let horizontal = matches!(side, Side::Left | Side::Right);
+    match c {
+        '-' | '─' | '=' | '.' | '>' | '<' | '▶' | '◀' => horizontal,
+        '|' | '│' | ':' | 'v' | 'V' | '^' | '▼' | '▲' => !horizontal,
+        '+' | '┼' => true,
+        '┌' => matches!(side, Side::Right | Side::Down),
+        '┐' => matches!(side, Side::Left | Side::Down),
+        '└' => matches!(side, Side::Right | Side::Up),
+        '┘' => matches!(side, Side::Left | Side::Up),
+        '├' => side != Side::Left,
+        '┤' => side != Side::Right,
+        '┬' => side != Side::Up,
+        '┴' => side != Side::Down,
+        _ => false,
+    }
+}
+
+/// Side an arrowhead points to
+fn head_side(c: char) -> Option<Side> {
+    match c {
+        '>' | '▶' => Some(Side::Right),
+        '<' | '◀' => Some(Side::Left),
+        'v' | 'V' | '▼' => Some(Side::Down),
+        '^' | '▲' => Some(Side::Up),
+        _ => None,
+    }
+}
+
+/// Find closed boxes in reading order of their top-left corners
+fn find_boxes(grid: &Grid) -> Vec<ArtBox> {
+    let mut boxes: Vec<ArtBox> = Vec::new();
+    for point in grid.points() {
+        if boxes.iter().any(|b| b.rect.contains(point)) {
+            continue;
+        }
+        if let Some(rect) = box_at(grid, point) {
+            let label = (rect.y + 1..rect.bottom() - 1)
+                .map(|y| {
+                    (rect.x + 1..rect.right() - 1)
+                        .map(|x| grid.at(Point::new(x, y)))
+                        .collect::<String>()
+                        .trim()
+                        .to_string()
+                })
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            boxes.push(ArtBox { rect, label });
+        }
+    }
+    boxes
+}
+
+/// The smallest closed box whose top-left corner is at `origin`
+fn box_at(grid: &Grid, origin: Point) -> Option<Rect> {
+    let Point { x, y } = origin;
+    let at = |x: usize, y: usize| grid.at(Point::new(x, y));
+    if !matches!(at(x, y), '+' | '┌') || !is_border_h(at(x + 1, y)) || !is_border_v(at(x, y + 1))
+    {
+        return None;
+    }
+
+    let mut right = x + 1;
+    while right < grid.width && is_border_h(at(right, y)) {
+        if right > x + 1 && is_corner(at(right, y)) && is_border_v(at(right, y + 1)) {
+            let mut bottom = y + 1;
+            while bottom < grid.height() && is_border_v(at(x, bottom)) {
+                if bottom > y + 1 && is_corner(at(x, bottom)) {
+                    let rect = Rect::new(x, y, right - x + 1, bottom - y + 1);
+                    if is_closed(grid, rect) {
+                        return Some(rect);
+                    }
+                }
+                bottom += 1;
+            }
+        }
+        right += 1;
+    }
+    None
+}
+
+/// Whether the bottom and right borders of `rect` are drawn
+fn is_closed(grid: &Grid, rect: Rect) -> bool {
+    let (right, bottom) = (rect.right() - 1, rect.bottom() - 1);
+    (rect.x..=right).all(|x| is_border_h(grid.at(Point::new(x, bottom))))
+        && (rect.y..=bottom).all(|y| is_border_v(grid.at(Point::new(right, y))))
+        && matches!(
+            grid.at(Point::new(right, bottom)),
+            '+' | '┘' | '┤' | '┴' | '┼'
+        )
+}
+
+/// Node IDs derived from box text, made unique (`Load data` → `Load_data`)
+fn node_ids(boxes: &[ArtBox]) -> Vec<String> {
+    let mut ids = IdAllocator::new();
+    boxes
+        .iter()
+        .enumerate()
+        .map(|(index, art_box)| {
+            let words: Vec<String> = art_box
+                .label
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|w| !w.is_empty())
+                .map(str::to_string)
+                .collect();
+            let mut base = words.join("_");
+            if base.is_empty() {
+                base = format!("N{}", index + 1);
+            } else if base.starts_with(|c: char| c.is_ascii_digit()) {
+                base = format!("N{}", base);
+            }
+            ids.allocate(&base)
+        })
+        .collect()
+}
+
+/// Trace the links between boxes
+fn find_links(grid: &Grid, boxes: &[ArtBox]) -> Vec<Link> {
+    let box_at = |point: Point| boxes.iter().position(|b| b.rect.contains(point));
+    let labels = find_inline_labels(grid, boxes);
+    let cell = |point: Point| -> char {
+        if labels.contains_key(&point) {
+            '-'
+        } else {
+            grid.at(point)
+        }
+    };
+    let is_link = |point: Point| -> bool {
+        if box_at(point).is_some() {
+            return false;
+        }
+        let c = cell(point);
+        match head_side(c) {
+            // A head only counts when a line leads into it from behind
+            Some(side) => side
+                .opposite()
+                .step(point)
+                .is_some_and(|behind| connects(cell(behind), side)),
+            None => Side::ALL.iter().any(|side| connects(c, *side)),
+        }
+    };
+    let linked = |point: Point, side: Side| -> Option<Point> {
+        let next = side.step(point)?;
+        (is_link(next) && connects(cell(point), side) && connects(cell(next), side.opposite()))
+            .then_some(next)
+    };
+
+    let mut seen: HashSet<Point> = HashSet::new();
+    let mut links = Vec::new();
+    for start in grid.points() {
+        if seen.contains(&start) || !is_link(start) {
+            continue;
+        }
+
+        // Flood-fill one connected run of link characters
+        let mut component = vec![start];
+        let mut queue = VecDeque::from([start]);
+        seen.insert(start);
+        while let Some(point) = queue.pop_front() {
+            for side in Side::ALL {
+                if let Some(next) = linked(point, side) {
+                    if seen.insert(next) {
+                        component.push(next);
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+
+        // Boxes touched by the run's loose ends, and whether with a head
+        let mut tails: Vec<usize> = Vec::new();
+        let mut heads: Vec<usize> = Vec::new();
+        for &point in &component {
+            let c = cell(point);
+            for side in Side::ALL {
+                if !connects(c, side) || linked(point, side).is_some() {
+                    continue;
+                }
+                let Some(next) = side.step(point) else {
+                    continue;
+                };
+                let touched = box_at(next).or_else(|| {
+                    (grid.at(next) == ' ')
+                        .then(|| side.step(next).and_then(box_at))
+                        .flatten()
+                });
+                if let Some(index) = touched {
+                    let list = if head_side(c) == Some(side) {
+                        &mut heads
+                    } else {
+                        &mut tails
+                    };
+                    if !list.contains(&index) {
+                        list.push(index);
+                    }
+                }
+            }
+        }
+        tails.retain(|index| !heads.contains(index));
+
+        let chars: Vec<char> = component.iter().map(|p| grid.at(*p)).collect();
+        let thick = chars.contains(&'=');
+        let dotted = chars.iter().any(|c| matches!(c, '.' | ':'));
+        let label = component.iter().find_map(|p| labels.get(p)).cloned();
+        let edge_type = |arrow: bool| match (arrow, thick, dotted) {
+            (true, true, _) => EdgeType::ThickArrow,
+            (true, false, true) => EdgeType::DottedArrow,
+            (true, false, false) => EdgeType::Arrow,
+            (false, true, _) => EdgeType::ThickLine,
+            (false, false, true) => EdgeType::DottedLine,
+            (false, false, false) => EdgeType::Line,
+        };
+
+        let pairs: Vec<(usize, usize, bool)> = match (heads.as_slice(), tails.as_slice()) {
+            ([], [first, rest @ ..]) => rest.iter().map(|to| (*first, *to, false)).collect(),
+            ([first, rest @ ..], []) => rest.iter().map(|to| (*first, *to, true)).collect(),
+            _ => tails
+                .iter()
+                .flat_map(|from| heads.iter().map(move |to| (*from, *to, true)))
+                .collect(),
+        };
+        for (from, to, arrow) in pairs {
+            links.push(Link {
+                from,
+                to,
+                edge_type: edge_type(arrow),
+                label: label.clone(),
+            });
+        }
+    }
+    links
+}
+
+/// Text written into horizontal links (`--yes-->`), keyed by each cell it
+/// covers; those cells are then traced as plain line
+fn find_inline_labels(grid: &Grid, boxes: &[ArtBox]) -> HashMap<Point, String> {
+    let in_box = |point: Point| boxes.iter().any(|b| b.rect.contains(point));
+    let is_line = |c: char| matches!(c, '-' | '─' | '=' | '.');
+    let mut labels = HashMap::new();
+
+    for y in 0..grid.height() {
+        let mut x = 0;
+        while x + 1 < grid.width {
+            let start = Point::new(x, y);
+            if !is_line(grid.at(start)) || in_box(start) {
+                x += 1;
+                continue;
+            }
+            // Collect up to the next line character on this row
+            let mut end = x + 1;
+            while end < grid.width
+                && !is_line(grid.at(Point::new(end, y)))
+                && !in_box(Point::new(end, y))
+            {
+                end += 1;
+            }
+            let text: String = (x + 1..end).map(|x| grid.at(Point::new(x, y))).collect();
+            let closed = end < grid.width && !in_box(Point::new(end, y));
+            let plain = !text.contains("  ")
+                && !text.trim().is_empty()
+                && !text
+                    .chars()
+                    .any(|c| connects(c, Side::Left) || is_border_v(c));
+            if closed && plain {
+                for label_x in x + 1..end {
+                    labels.insert(Point::new(label_x, y), text.trim().to_string());
+                }
+            }
+            x = end;
+        }
+    }
+    labels
+}
+
+/// Direction most links follow, from source box to target box
+fn flow_direction(links: &[Link], boxes: &[ArtBox]) -> Direction {
+    let mut counts: HashMap<Direction, usize> = HashMap::new();
+    for link in links {
+        let from = boxes[link.from].rect.center();
+        let to = boxes[link.to].rect.center();
+        let dx = to.x as isize - from.x as isize;
+        let dy = to.y as isize - from.y as isize;
+        let direction = if dx.abs() > dy.abs() {
+            if dx > 0 {
+                Direction::LeftRight
+            } else {
+                Direction::RightLeft
+            }
+        } else if dy < 0 {
+            Direction::BottomUp
+        } else {
+            Direction::TopDown
+        };
+        *counts.entry(direction).or_default() += 1;
+    }
+    [
+        Direction::TopDown,
+        Direction::LeftRight,
+        Direction::BottomUp,
+        Direction::RightLeft,
+    ]
+    .into_iter()
+    .max_by_key(|direction| {
+        (
+            counts.get(direction).copied().unwrap_or(0),
+            *direction == Direction::TopDown,
+        )
+    })
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Database;
+
+    fn edges(db: &FlowchartDatabase) -> Vec<(String, String, EdgeType, Option<String>)> {
+        db.edges()
+            .map(|e| (e.from.clone(), e.to.clone(), e.edge_type, e.label.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_vertical_chain_with_bend() {
+        let art = "\
++-------+
+| Start |
++-------+
+    |
+    v
++-----------+
+| Load data |
++-----------+
+    |
+    +------+
+           |
+           v
+       +------+
+       | Done |
+       +------+";
+        let db = ingest_ascii(art).unwrap();
+        assert_eq!(db.direction(), Direction::TopDown);
+        assert_eq!(db.get_node("Load_data").unwrap().label, "Load data");
+        assert_eq!(
+            edges(&db),
+            vec![
+                ("Start".into(), "Load_data".into(), EdgeType::Arrow, None),
+                ("Load_data".into(), "Done".into(), EdgeType::Arrow, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_branching_link_with_labels_and_styles() {
+        let art = "\
++---+          +---+
+| A |--yes---->| B |
++---+          +---+
+  |
+  +======> +---+
+           | C |
+  ....     +---+
++---+  +---+
+| D |..| E |
++---+  +---+";
+        let db = ingest_ascii(art).unwrap();
+        let edges = edges(&db);
+        assert!(edges.contains(&("A".into(), "B".into(), EdgeType::Arrow, Some("yes".into()))));
+        assert!(edges.contains(&("A".into(), "C".into(), EdgeType::ThickArrow, None)));
+        assert!(edges.contains(&("D".into(), "E".into(), EdgeType::DottedLine, None)));
+        assert_eq!(edges.len(), 3);
+    }
+
+    #[test]
+    fn test_unicode_boxes_from_rendered_output() {
+        let art = crate::render("graph LR; A[Client]-->B[Server]-->C[Store]").unwrap();
+        let db = ingest_ascii(&art).unwrap();
+        assert_eq!(db.direction(), Direction::LeftRight);
+        assert_eq!(
+            edges(&db)
+                .into_iter()
+                .map(|(from, to, _, _)| (from, to))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Client".into(), "Server".into()),
+                ("Server".into(), "Store".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_and_empty_labels_get_unique_ids() {
+        let art = "+---+ +---+ +---+\n| X | | X | |   |\n+---+ +---+ +---+";
+        let db = ingest_ascii(art).unwrap();
+        let ids: Vec<&str> = db.nodes().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["X", "X_2", "N3"]);
+    }
+
+    #[test]
+    fn test_no_boxes_is_an_error() {
+        assert!(ingest_ascii("just text --> more text").is_err());
+    }
+}
@@ -9,7 +9,10 @@ use tracing::{debug, info, span, trace, Level};
 use unicode_width::UnicodeWidthStr;
 
 use super::FlowchartDatabase;
-use crate::core::{wrap_label, Database, Direction, LayoutAlgorithm, NodeShape};
+use crate::core::{
+    simplify_path, Database, Direction, FlowchartInitConfig, LayoutAlgorithm, NodeShape,
+    ObstacleRouter, Point, Rect, Segment,
+};
 
 /// Position data for a laid out node
 #[derive(Debug, Clone)]
@@ -21,20 +24,31 @@ pub struct PositionedNode {
     pub height: usize,
 }
 
+impl PositionedNode {
+    /// Cells covered by the node
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+}
+
 /// Position data for a laid out edge
 #[derive(Debug, Clone)]
 pub struct PositionedEdge {
     pub from_id: String,
     pub to_id: String,
-    pub waypoints: Vec<(usize, usize)>,
+    pub waypoints: Vec<Point>,
     /// For grouped edges from same source (split), the shared junction point
-    pub junction: Option<(usize, usize)>,
+    pub junction: Option<Point>,
     /// For grouped edges to same target (merge), the shared junction point
-    pub merge_junction: Option<(usize, usize)>,
+    pub merge_junction: Option<Point>,
     /// Index within the edge group (0 = first/leftmost in TD)
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     pub group_index: Option<usize>,
     /// Total edges in this group
     pub group_size: Option<usize>,
+    /// Lane followed to reach a target on a wrapped row (see
+    /// [`LayoutConfig::max_layer_nodes`]); the waypoints hold the full route
+    pub lane: Option<usize>,
 }
 
 /// Position data for a laid out subgraph (container)
@@ -48,6 +62,22 @@ pub struct PositionedSubgraph {
     pub height: usize,
 }
 
+impl PositionedEdge {
+    /// Segments of the route between consecutive waypoints
+    #[cfg(feature = "unstable")]
+    pub fn segments(&self) -> impl Iterator<Item = crate::core::Segment> + '_ {
+        crate::core::Segment::path(&self.waypoints)
+    }
+}
+
+impl PositionedSubgraph {
+    /// Cells covered by the subgraph border and its contents
+    #[cfg(feature = "unstable")]
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y, self.width, self.height)
+    }
+}
+
 /// Layout output containing positioned elements
 #[derive(Debug)]
 pub struct FlowchartLayoutResult {
@@ -68,6 +98,15 @@ pub struct LayoutConfig {
     pub padding: usize,
     pub max_label_width: usize, // Max width before label wraps (0 = no wrap)
     pub diamond_style: crate::core::DiamondStyle,
+    /// Wrap layers with more nodes onto extra rows in the same rank band
+    /// (0 = never wrap)
+    pub max_layer_nodes: usize,
+    /// Crossing-minimization sweeps over the layers; lower it to speed up
+    /// very large graphs (0 = keep nodes in name order)
+    pub crossing_sweeps: usize,
+    /// Shrink each rank gap to what the edges and labels crossing it need,
+    /// with `rank_sep` as the upper bound (false = always `rank_sep`)
+    pub adaptive_rank_sep: bool,
 }
 
 impl Default for LayoutConfig {
@@ -80,10 +119,60 @@ impl Default for LayoutConfig {
             padding: 1,          // was 2: canvas edge padding
             max_label_width: 30, // Wrap labels longer than 30 chars
             diamond_style: crate::core::DiamondStyle::Box,
+            max_layer_nodes: 0,
+            crossing_sweeps: 4,
+            adaptive_rank_sep: true,
         }
     }
 }
 
+/// Mermaid's default node and rank spacing, in pixels
+const MERMAID_DEFAULT_SPACING: f64 = 50.0;
+/// Mermaid's default diagram padding, in pixels
+const MERMAID_DEFAULT_PADDING: f64 = 8.0;
+
+impl LayoutConfig {
+    /// Apply the `flowchart` section of an init directive
+    ///
+    /// Pixel spacings are scaled relative to Mermaid's defaults, so
+    /// `nodeSpacing: 100` doubles the default gap. Rank spacing never drops
+    /// below the default, which edge drawing needs, and an explicit
+    /// `rankSpacing` turns off adaptive rank gaps. Returns warnings for
+    /// values that can't be honored.
+    pub fn apply_init(&mut self, init: &FlowchartInitConfig) -> Vec<String> {
+        let defaults = LayoutConfig::default();
+        let scale = |px: f64, cells: usize, base: f64| (px / base * cells as f64).round() as usize;
+        let mut warnings = Vec::new();
+
+        if let Some(px) = init.node_spacing {
+            self.node_sep = scale(px, defaults.node_sep, MERMAID_DEFAULT_SPACING).max(1);
+        }
+        if let Some(px) = init.rank_spacing {
+            self.rank_sep =
+                scale(px, defaults.rank_sep, MERMAID_DEFAULT_SPACING).max(defaults.rank_sep);
+            self.adaptive_rank_sep = false;
+        }
+        if let Some(px) = init.diagram_padding {
+            self.padding = scale(px, defaults.padding, MERMAID_DEFAULT_PADDING);
+        }
+        if let Some(curve) = &init.curve {
+            // Step curves match the orthogonal routes and `linear` is drawn by
+            // the renderer (`EdgeCurve`); anything else falls back to routes
+            if !matches!(
+                curve.as_str(),
+                "linear" | "step" | "stepBefore" | "stepAfter"
+            ) {
+                warnings.push(format!(
+                    "Curve '{}' is not supported; edges use orthogonal routing",
+                    curve
+                ));
+            }
+        }
+
+        warnings
+    }
+}
+
 /// Flowchart layout algorithm implementation
 pub struct FlowchartLayoutAlgorithm {
     config: LayoutConfig,
@@ -105,45 +194,334 @@ impl FlowchartLayoutAlgorithm {
         &mut self.config
     }
 
-    /// Wrap a label into multiple lines if it exceeds max_label_width
-    fn wrap_label(&self, label: &str) -> Vec<String> {
-        wrap_label(label, self.config.max_label_width)
-    }
-
     /// Calculate node dimensions based on shape and label
     fn calculate_node_size(&self, label: &str, shape: NodeShape) -> (usize, usize) {
-        let wrapped_lines = self.wrap_label(label);
-        let label_width = wrapped_lines
+        let lines = super::sizing::label_lines(label, shape, self.config.max_label_width);
+        let (width, height) = super::sizing::node_size(shape, &lines, self.config.diamond_style);
+        (
+            width.max(self.config.min_node_width),
+            height.max(self.config.min_node_height),
+        )
+    }
+
+    /// Extra space to reserve in each gap between ranks for edge labels
+    ///
+    /// Entry `g` is for the gap between layers `g` and `g + 1`. A labeled
+    /// edge's label is drawn just before its arrow, so it claims the gap in
+    /// front of its target's layer: one extra row when the flow is vertical,
+    /// or the label's width plus a column of clearance when it is horizontal.
+    /// Back edges are routed around the diagram and claim nothing. Reversed
+    /// flows draw labels beside their arrowheads rather than in the gap, so
+    /// they get no extra room.
+    fn label_room(
+        &self,
+        database: &FlowchartDatabase,
+        layers: &HashMap<&str, usize>,
+        layer_count: usize,
+        direction: Direction,
+    ) -> Vec<usize> {
+        let vertical = matches!(direction, Direction::TopDown | Direction::BottomUp);
+        let mut room = vec![0; layer_count.saturating_sub(1)];
+        if direction.is_reversed() {
+            return room;
+        }
+        for edge in database.edges() {
+            let Some(label) = edge.label.as_deref().filter(|l| !l.is_empty()) else {
+                continue;
+            };
+            let (Some(&from), Some(&to)) =
+                (layers.get(edge.from.as_str()), layers.get(edge.to.as_str()))
+            else {
+                continue;
+            };
+            if to <= from {
+                continue;
+            }
+            // Drawn on one row, with line breaks as spaces
+            let needed = if vertical {
+                1
+            } else {
+                label.width() + label.matches('\n').count() + 1
+            };
+            room[to - 1] = room[to - 1].max(needed);
+        }
+        room
+    }
+
+    /// Smallest gap between layer `gap` and the next that fits the edges
+    /// drawn in it, before label room
+    ///
+    /// A straight edge needs two cells (a stub and the arrowhead). A bend,
+    /// split junction or merge junction needs a third, and a split and merge
+    /// in the same gap a fourth, and a marker at an edge's source end one
+    /// more. Edges spanning several layers, wrapped bands
+    /// and subgraph boundaries keep the full `rank_sep`.
+    fn needed_rank_gap(
+        &self,
+        gap: usize,
+        database: &FlowchartDatabase,
+        layers: &HashMap<&str, usize>,
+        nodes: &HashMap<&str, &PositionedNode>,
+        direction: Direction,
+    ) -> usize {
+        let full = self.config.rank_sep;
+        let subgraph_of = |id: &str| database.node_subgraph(id).map(|sg| sg.id.as_str());
+        let mut in_layer: [Vec<Option<&str>>; 2] = [Vec::new(), Vec::new()];
+        for (&id, &layer) in layers {
+            if layer == gap || layer == gap + 1 {
+                in_layer[layer - gap].push(subgraph_of(id));
+            }
+        }
+        for side in &mut in_layer {
+            side.sort();
+            side.dedup();
+        }
+        if in_layer[0] != in_layer[1] {
+            return full;
+        }
+
+        let center = |node: &PositionedNode| match direction {
+            Direction::TopDown | Direction::BottomUp => node.x + node.width / 2,
+            Direction::LeftRight | Direction::RightLeft => node.y + node.height / 2,
+        };
+        let is_forward = |from: usize, to: usize| to > from;
+        let (mut bend, mut split, mut merge, mut marked) = (false, false, false, false);
+        for edge in database.edges() {
+            let (Some(&from), Some(&to)) =
+                (layers.get(edge.from.as_str()), layers.get(edge.to.as_str()))
+            else {
+                continue;
+            };
+            if !is_forward(from, to) || from > gap || to <= gap {
+                continue;
+            }
+            if to > gap + 1 || from < gap {
+                return full;
+            }
+            let (Some(source), Some(target)) =
+                (nodes.get(edge.from.as_str()), nodes.get(edge.to.as_str()))
+            else {
+                continue;
+            };
+            bend |= center(source) != center(target);
+            marked |= edge.start.is_visible();
+            split |= database.edges().filter(|e| e.from == edge.from).count() > 1;
+            merge |= database
+                .edges()
+                .filter(|e| e.to == edge.to)
+                .filter(|e| {
+                    layers
+                        .get(e.from.as_str())
+                        .is_some_and(|&f| is_forward(f, to))
+                })
+                .count()
+                > 1;
+        }
+
+        let needed = match (split, merge) {
+            (true, true) => 4,
+            // Horizontal splits need a column for the junction's vertical run
+            (true, false) if !direction.is_vertical() => 4,
+            (true, false) | (false, true) => 3,
+            (false, false) if bend => 3,
+            (false, false) => 2,
+        };
+        // Reversed flows draw their arrowheads a cell short of the target
+        let offset = usize::from(direction.is_reversed());
+        (needed + offset + usize::from(marked)).min(full)
+    }
+
+    /// Close up rank gaps wider than their edges need
+    ///
+    /// Moves each layer back along the flow by the space saved in the gaps
+    /// before it and returns the total saved. Gaps next to a wrapped band
+    /// are left alone, since lanes are routed through them.
+    #[allow(clippy::too_many_arguments)]
+    fn compact_ranks(
+        &self,
+        database: &FlowchartDatabase,
+        layers: &HashMap<&str, usize>,
+        bands: &[Vec<Vec<&str>>],
+        label_room: &[usize],
+        direction: Direction,
+        positioned_nodes: &mut [PositionedNode],
+    ) -> usize {
+        let vertical = direction.is_vertical();
+        let extent = |node: &PositionedNode| {
+            if vertical {
+                (node.y, node.y + node.height)
+            } else {
+                (node.x, node.x + node.width)
+            }
+        };
+        let layer_of = |node: &PositionedNode| layers.get(node.id.as_str()).copied();
+
+        // Savings per gap, from the positions before anything moves
+        let mut savings = vec![0; bands.len().saturating_sub(1)];
+        {
+            let nodes: HashMap<&str, &PositionedNode> = positioned_nodes
+                .iter()
+                .map(|n| (n.id.as_str(), n))
+                .collect();
+            let span = |layer: usize| {
+                positioned_nodes
+                    .iter()
+                    .filter(|n| layer_of(n) == Some(layer))
+                    .map(extent)
+                    .reduce(|(s1, e1), (s2, e2)| (s1.min(s2), e1.max(e2)))
+            };
+            for (gap, saving) in savings.iter_mut().enumerate() {
+                if bands[gap].len() > 1 || bands[gap + 1].len() > 1 {
+                    continue;
+                }
+                let (Some(first), Some(second)) = (span(gap), span(gap + 1)) else {
+                    continue;
+                };
+                // Reversed flows place the later layer first
+                let actual = if direction.is_reversed() {
+                    first.0.saturating_sub(second.1)
+                } else {
+                    second.0.saturating_sub(first.1)
+                };
+                let room = label_room.get(gap).copied().unwrap_or(0);
+                let needed = self.needed_rank_gap(gap, database, layers, &nodes, direction);
+                *saving = actual.saturating_sub(needed + room);
+            }
+        }
+
+        // A layer moves back by what every gap placed before it saved
+        for node in positioned_nodes.iter_mut() {
+            let Some(layer) = layer_of(node) else {
+                continue;
+            };
+            let shift: usize = if direction.is_reversed() {
+                savings[layer..].iter().sum()
+            } else {
+                savings[..layer].iter().sum()
+            };
+            if vertical {
+                node.y -= shift;
+            } else {
+                node.x -= shift;
+            }
+        }
+        savings.iter().sum()
+    }
+
+    /// Size of a wrapped band across the flow: slots plus the gaps holding
+    /// lanes, for the widest row
+    fn band_cross_size(
+        &self,
+        rows: &[Vec<&str>],
+        node_sizes: &HashMap<&str, (usize, usize)>,
+        direction: Direction,
+    ) -> usize {
+        let vertical = matches!(direction, Direction::TopDown | Direction::BottomUp);
+        let slot = rows
             .iter()
-            .map(|l| UnicodeWidthStr::width(l.as_str()))
+            .flatten()
+            .map(|&id| {
+                let (width, height) = node_sizes[id];
+                if vertical {
+                    width
+                } else {
+                    height
+                }
+            })
             .max()
             .unwrap_or(0);
-        let label_lines = wrapped_lines.len();
-
-        let (extra_width, extra_height): (usize, i32) = match shape {
-            NodeShape::Rectangle | NodeShape::RoundedRect | NodeShape::Subroutine => (4, 0),
-            NodeShape::Diamond => {
-                // Diamond height depends on the diamond style
-                use crate::core::DiamondStyle;
-                let height_extra = match self.config.diamond_style {
-                    DiamondStyle::Box => 0,     // 3 lines total
-                    DiamondStyle::Inline => -2, // 1 line total (will be clamped to min)
-                    DiamondStyle::Tall => 2,    // 5 lines total
-                };
-                (6, height_extra)
+        let gap = super::wrapping::lane_gap(rows.len(), self.config.node_sep);
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        columns * (slot + gap)
+    }
+
+    /// Place the rows of a wrapped band
+    ///
+    /// `cross_start` and `rank_start` are the band's top-left corner in
+    /// canvas coordinates. Nodes on rows after the first get a lane, recorded
+    /// in `lanes`. Returns the band's extent along the flow.
+    #[allow(clippy::too_many_arguments)]
+    fn place_wrapped_band(
+        &self,
+        rows: &[Vec<&str>],
+        node_sizes: &HashMap<&str, (usize, usize)>,
+        direction: Direction,
+        cross_start: usize,
+        rank_start: usize,
+        positioned_nodes: &mut Vec<PositionedNode>,
+        lanes: &mut HashMap<String, usize>,
+    ) -> usize {
+        use super::wrapping::{lane_gap, lane_offset, WRAP_ROW_SEP};
+
+        let vertical = matches!(direction, Direction::TopDown | Direction::BottomUp);
+        // (across the flow, along the flow)
+        let size_of = |id: &str| {
+            let (width, height) = node_sizes[id];
+            if vertical {
+                (width, height)
+            } else {
+                (height, width)
             }
-            NodeShape::Circle | NodeShape::Terminal => (4, 0),
-            NodeShape::Hexagon => (6, 0),
-            NodeShape::Asymmetric | NodeShape::Parallelogram | NodeShape::Trapezoid => (6, 0),
-            NodeShape::Cylinder => (6, 2),
         };
 
-        let width = (label_width + extra_width).max(self.config.min_node_width);
-        // Add extra height for multi-line labels (each extra line adds 1)
-        let base_height = (3i32 + extra_height).max(1) as usize;
-        let height = (base_height + label_lines.saturating_sub(1)).max(self.config.min_node_height);
+        let slot = rows
+            .iter()
+            .flatten()
+            .map(|&id| size_of(id).0)
+            .max()
+            .unwrap_or(0);
+        let gap = lane_gap(rows.len(), self.config.node_sep);
+        let thickness: Vec<usize> = rows
+            .iter()
+            .map(|row| row.iter().map(|&id| size_of(id).1).max().unwrap_or(0))
+            .collect();
+        let extent = thickness.iter().sum::<usize>() + (rows.len() - 1) * WRAP_ROW_SEP;
+
+        let mut offset = 0;
+        for (row_index, row) in rows.iter().enumerate() {
+            let row_thickness = thickness[row_index];
+            // Reversed flows enter the band from its far side
+            let row_start = if direction.is_reversed() {
+                rank_start + extent - offset - row_thickness
+            } else {
+                rank_start + offset
+            };
+            offset += row_thickness + WRAP_ROW_SEP;
+
+            for (slot_index, &node_id) in row.iter().enumerate() {
+                let (cross_size, rank_size) = size_of(node_id);
+                let slot_start = cross_start + slot_index * (slot + gap);
+                let cross = slot_start + (slot - cross_size) / 2;
+                // Align nodes on the side edges come in from
+                let rank = if direction.is_reversed() {
+                    row_start + row_thickness - rank_size
+                } else {
+                    row_start
+                };
+                if row_index > 0 {
+                    lanes.insert(
+                        node_id.to_string(),
+                        slot_start + slot + lane_offset(row_index),
+                    );
+                }
+
+                let (x, y) = if vertical {
+                    (cross, rank)
+                } else {
+                    (rank, cross)
+                };
+                let (width, height) = node_sizes[node_id];
+                positioned_nodes.push(PositionedNode {
+                    id: node_id.to_string(),
+                    x,
+                    y,
+                    width,
+                    height,
+                });
+            }
+        }
 
-        (width, height)
+        extent
     }
 }
 
@@ -170,6 +548,17 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
 
         let direction = database.direction();
 
+        // Subgraphs with a direction of their own are laid out first and then
+        // placed as single nodes; from here on `database` is that outer graph
+        let full = database;
+        let blocks = super::subgraph_direction::direction_blocks(&self.config, full)?;
+        let outer = if blocks.is_empty() {
+            None
+        } else {
+            Some(super::subgraph_direction::outer_graph(full, &blocks)?)
+        };
+        let database = outer.as_ref().unwrap_or(full);
+
         // Collect nodes and calculate sizes
         let size_span = span!(Level::DEBUG, "calculate_node_sizes");
         let _size_enter = size_span.enter();
@@ -190,30 +579,16 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
             let size = self.calculate_node_size(&node.label, node.shape);
             node_sizes.insert(&node.id, size);
         }
+        for block in &blocks {
+            node_sizes.insert(&block.placeholder, block.size);
+        }
         debug!(node_count = nodes.len(), "Calculated node sizes");
         drop(_size_enter);
 
         // Assign layers using topological sort
         let layer_span = span!(Level::DEBUG, "assign_layers");
         let _layer_enter = layer_span.enter();
-        let sorted = database.topological_sort();
-        let mut layers: HashMap<&str, usize> = HashMap::new();
-
-        for &node_id in &sorted {
-            // Layer = max layer of predecessors + 1
-            let preds = database.predecessors(node_id);
-            let layer = if preds.is_empty() {
-                0
-            } else {
-                preds
-                    .iter()
-                    .filter_map(|&p| layers.get(p))
-                    .max()
-                    .map(|&l| l + 1)
-                    .unwrap_or(0)
-            };
-            layers.insert(node_id, layer);
-        }
+        let layers = assign_layers(database);
 
         // Group nodes by layer
         let max_layer = layers.values().max().copied().unwrap_or(0);
@@ -228,8 +603,35 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
         }
 
         // Apply barycenter ordering to minimize edge crossings
-        let crossing_count =
-            super::ordering::order_layers_barycenter(database, &mut layer_nodes, 4);
+        let crossing_count = super::ordering::order_layers_barycenter(
+            database,
+            &mut layer_nodes,
+            self.config.crossing_sweeps,
+        );
+
+        // Split layers that are too wide into rows within their rank band
+        let bands: Vec<Vec<Vec<&str>>> = layer_nodes
+            .iter()
+            .map(|layer| super::wrapping::wrap_layer(database, layer, self.config.max_layer_nodes))
+            .collect();
+        let mut wrap_lanes: HashMap<String, usize> = HashMap::new();
+        let label_room = self.label_room(database, &layers, layer_nodes.len(), direction);
+        // Gap after placing layer `index` (label room is only reserved for
+        // forward flows, which place layers in index order)
+        let rank_gap =
+            |index: usize| self.config.rank_sep + label_room.get(index).copied().unwrap_or(0);
+        // Nodes with a self-loop keep room beside them for it: on the right
+        // (with its label) in vertical flows, above in horizontal ones
+        let mut looped: HashMap<&str, usize> = HashMap::new();
+        for edge in database.edges().filter(|e| e.from == e.to) {
+            let label = match &edge.label {
+                Some(label) if direction.is_vertical() => label.width() + 2,
+                _ => 0,
+            };
+            let room = looped.entry(edge.from.as_str()).or_default();
+            *room = (*room).max(1 + label);
+        }
+        let loop_room = |id: &str| looped.get(id).copied().unwrap_or(0);
 
         debug!(
             max_layer,
@@ -248,7 +650,7 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
             Direction::LeftRight | Direction::RightLeft => {
                 // Normalize widths within layers
                 let mut layer_max_widths: HashMap<usize, usize> = HashMap::new();
-                for &node_id in &sorted {
+                for &node_id in layers.keys() {
                     if let (Some(&layer), Some(&(width, _))) =
                         (layers.get(node_id), node_sizes.get(node_id))
                     {
@@ -256,7 +658,7 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
                         *max = (*max).max(width);
                     }
                 }
-                for &node_id in &sorted {
+                for &node_id in layers.keys() {
                     if let (Some(&layer), Some((_, height))) =
                         (layers.get(node_id), node_sizes.get(node_id).copied())
                     {
@@ -279,10 +681,17 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
             Direction::TopDown | Direction::BottomUp => {
                 // Vertical layout: layers are rows (Y), nodes distributed on X
                 // Find the widest layer (sum of node widths + gaps) for centering
-                let widest_layer_width = layer_nodes
+                let widest_layer_width = bands
                     .iter()
-                    .map(|layer| {
-                        let total: usize = layer.iter().map(|&id| node_sizes[id].0).sum();
+                    .map(|band| {
+                        if band.len() > 1 {
+                            return self.band_cross_size(band, &node_sizes, direction);
+                        }
+                        let layer = &band[0];
+                        let total: usize = layer
+                            .iter()
+                            .map(|&id| node_sizes[id].0 + loop_room(id))
+                            .sum();
                         total + layer.len().saturating_sub(1) * self.config.node_sep
                     })
                     .max()
@@ -290,15 +699,48 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
                 // Center X is at padding + widest_layer_width / 2
                 let center_x = self.config.padding + widest_layer_width / 2;
 
-                let mut y = self.config.padding;
-
-                let layer_iter: Box<dyn Iterator<Item = &Vec<&str>>> = if direction.is_reversed() {
-                    Box::new(layer_nodes.iter().rev())
+                // Loops on the first row rise above it
+                let first_row = if direction.is_reversed() {
+                    None
                 } else {
-                    Box::new(layer_nodes.iter())
+                    bands.first()
                 };
+                let mut y = self.config.padding
+                    + first_row
+                        .into_iter()
+                        .flatten()
+                        .flatten()
+                        .map(|&id| loop_room(id))
+                        .max()
+                        .unwrap_or(0);
+
+                let band_iter: Box<dyn Iterator<Item = (usize, &Vec<Vec<&str>>)>> =
+                    if direction.is_reversed() {
+                        Box::new(bands.iter().enumerate().rev())
+                    } else {
+                        Box::new(bands.iter().enumerate())
+                    };
+
+                for (layer_idx, band) in band_iter {
+                    if band.len() > 1 {
+                        let band_width = self.band_cross_size(band, &node_sizes, direction);
+                        let start_x = center_x.saturating_sub(band_width / 2);
+                        let band_height = self.place_wrapped_band(
+                            band,
+                            &node_sizes,
+                            direction,
+                            start_x,
+                            y,
+                            &mut positioned_nodes,
+                            &mut wrap_lanes,
+                        );
+                        max_width = max_width.max(start_x + band_width);
+                        y += band_height + rank_gap(layer_idx);
+                        max_height = max_height.max(y);
+                        continue;
+                    }
 
-                for layer in layer_iter {
+                    let layer = &band[0];
                     let mut layer_height = 0;
 
                     if layer.len() == 1 {
@@ -317,9 +759,11 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
                         max_width = max_width.max(x + width + self.config.padding);
                     } else {
                         // Multiple nodes - distribute across from center
-                        let total_width: usize =
-                            layer.iter().map(|&id| node_sizes[id].0).sum::<usize>()
-                                + (layer.len() - 1) * self.config.node_sep;
+                        let total_width: usize = layer
+                            .iter()
+                            .map(|&id| node_sizes[id].0 + loop_room(id))
+                            .sum::<usize>()
+                            + layer.len().saturating_sub(1) * self.config.node_sep;
                         let start_x = center_x.saturating_sub(total_width / 2);
                         let mut x = start_x;
 
@@ -333,13 +777,13 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
                                 height,
                             });
 
-                            x += width + self.config.node_sep;
+                            x += width + loop_room(node_id) + self.config.node_sep;
                             layer_height = layer_height.max(height);
                             max_width = max_width.max(x);
                         }
                     }
 
-                    y += layer_height + self.config.rank_sep;
+                    y += layer_height + rank_gap(layer_idx);
                     max_height = max_height.max(y);
                 }
             }
@@ -347,34 +791,61 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
                 // Horizontal layout: layers are columns (X), nodes distributed on Y
                 // First, calculate the maximum height needed for any layer
                 let mut layer_max_heights: Vec<usize> = Vec::new();
-                for layer in &layer_nodes {
-                    let layer_height: usize =
-                        layer.iter().map(|&id| node_sizes[id].1).sum::<usize>()
-                            + layer.len().saturating_sub(1) * self.config.node_sep;
+                for band in &bands {
+                    if band.len() > 1 {
+                        layer_max_heights.push(self.band_cross_size(band, &node_sizes, direction));
+                        continue;
+                    }
+                    let layer = &band[0];
+                    let layer_height: usize = layer
+                        .iter()
+                        .map(|&id| node_sizes[id].1 + loop_room(id))
+                        .sum::<usize>()
+                        + layer.len().saturating_sub(1) * self.config.node_sep;
                     layer_max_heights.push(layer_height);
                 }
                 let total_max_height = *layer_max_heights.iter().max().unwrap_or(&0);
 
                 let mut x = self.config.padding;
 
-                let layer_iter: Box<dyn Iterator<Item = (usize, &Vec<&str>)>> =
+                let band_iter: Box<dyn Iterator<Item = (usize, &Vec<Vec<&str>>)>> =
                     if direction.is_reversed() {
-                        Box::new(layer_nodes.iter().enumerate().rev())
+                        Box::new(bands.iter().enumerate().rev())
                     } else {
-                        Box::new(layer_nodes.iter().enumerate())
+                        Box::new(bands.iter().enumerate())
                     };
 
-                for (layer_idx, layer) in layer_iter {
+                for (layer_idx, band) in band_iter {
                     // Calculate total height of this layer's nodes
                     let layer_height = layer_max_heights[layer_idx];
-                    // Center the layer vertically
-                    let start_y =
-                        self.config.padding + (total_max_height.saturating_sub(layer_height)) / 2;
+                    // Center the layer vertically, rounding like node centers
+                    // (`y + height / 2`) so a 4-row node lines up with 3-row ones
+                    let start_y = self.config.padding + total_max_height / 2
+                        - layer_height.min(total_max_height) / 2;
+
+                    if band.len() > 1 {
+                        let band_width = self.place_wrapped_band(
+                            band,
+                            &node_sizes,
+                            direction,
+                            start_y,
+                            x,
+                            &mut positioned_nodes,
+                            &mut wrap_lanes,
+                        );
+                        max_height = max_height.max(start_y + layer_height);
+                        x += band_width + rank_gap(layer_idx);
+                        max_width = max_width.max(x);
+                        continue;
+                    }
+
+                    let layer = &band[0];
                     let mut y = start_y;
                     let mut layer_width = 0;
 
                     for &node_id in layer {
                         let (width, height) = node_sizes[node_id];
+                        y += loop_room(node_id);
                         positioned_nodes.push(PositionedNode {
                             id: node_id.to_string(),
                             x,
@@ -388,7 +859,7 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
                         max_height = max_height.max(y);
                     }
 
-                    x += layer_width + self.config.rank_sep;
+                    x += layer_width + rank_gap(layer_idx);
                     max_width = max_width.max(x);
                 }
                 // Ensure max_height accounts for the centered layout
@@ -396,6 +867,25 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
             }
         }
 
+        if self.config.adaptive_rank_sep {
+            let saved = self.compact_ranks(
+                database,
+                &layers,
+                &bands,
+                &label_room,
+                direction,
+                &mut positioned_nodes,
+            );
+            if direction.is_vertical() {
+                max_height -= saved;
+            } else {
+                max_width -= saved;
+            }
+        }
+
+        // Blocks open back up into their members, bringing their own edges
+        let block_edges = super::subgraph_direction::expand_blocks(&blocks, &mut positioned_nodes);
+
         debug!(
             positioned_node_count = positioned_nodes.len(),
             max_width, max_height, "Node positioning completed"
@@ -410,12 +900,19 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
         let mut edges_by_source: HashMap<&str, Vec<&crate::core::EdgeData>> = HashMap::new();
         // Group edges by target node (for merges)
         let mut edges_by_target: HashMap<&str, Vec<&crate::core::EdgeData>> = HashMap::new();
-        for edge in database.edges() {
+        // Self-loops stay out of both: they never share a junction. Edges
+        // inside a block already have their routes
+        let routed: Vec<_> = full
+            .edges()
+            .filter(|e| !super::subgraph_direction::is_inside_block(&blocks, e))
+            .collect();
+        for &edge in routed.iter().filter(|e| e.from != e.to) {
             edges_by_source.entry(&edge.from).or_default().push(edge);
             edges_by_target.entry(&edge.to).or_default().push(edge);
         }
 
-        let mut positioned_edges = Vec::new();
+        let mut positioned_edges = block_edges;
+        let mut has_back_edges = false;
         let node_positions: HashMap<&str, &PositionedNode> = positioned_nodes
             .iter()
             .map(|n| (n.id.as_str(), n))
@@ -423,7 +920,7 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
 
         // Pre-calculate merge junctions for targets with multiple incoming FORWARD edges
         // (back-edges enter from the side and shouldn't count toward merge junctions)
-        let mut merge_junctions: HashMap<&str, (usize, usize)> = HashMap::new();
+        let mut merge_junctions: HashMap<&str, Point> = HashMap::new();
         for (target_id, incoming_edges) in &edges_by_target {
             if let Some(to) = node_positions.get(*target_id) {
                 // Count only forward edges (not back-edges)
@@ -445,12 +942,8 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
                     .count();
 
                 if forward_edge_count > 1 {
-                    let merge_point = match direction {
-                        Direction::TopDown => (to.x + to.width / 2, to.y.saturating_sub(2)),
-                        Direction::BottomUp => (to.x + to.width / 2, to.y + to.height + 2),
-                        Direction::LeftRight => (to.x.saturating_sub(2), to.y + to.height / 2),
-                        Direction::RightLeft => (to.x + to.width + 2, to.y + to.height / 2),
-                    };
+                    // Two cells in front of the target: junction, then arrow
+                    let merge_point = to.rect().entry(direction).step(direction, -2);
                     merge_junctions.insert(*target_id, merge_point);
                 }
             }
@@ -464,23 +957,8 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
             let group_size = edges.len();
             let is_split = group_size > 1;
 
-            // Calculate junction point for splits
-            let junction = if is_split {
-                match direction {
-                    Direction::TopDown => Some((from.x + from.width / 2, from.y + from.height + 1)),
-                    Direction::BottomUp => {
-                        Some((from.x + from.width / 2, from.y.saturating_sub(1)))
-                    }
-                    Direction::LeftRight => {
-                        Some((from.x + from.width + 1, from.y + from.height / 2))
-                    }
-                    Direction::RightLeft => {
-                        Some((from.x.saturating_sub(1), from.y + from.height / 2))
-                    }
-                }
-            } else {
-                None
-            };
+            // Calculate junction point for splits, one cell past the exit
+            let junction = is_split.then(|| from.rect().exit(direction).step(direction, 1));
 
             // Sort edges for consistent ordering (by target position)
             let mut sorted_edges: Vec<_> = edges.into_iter().collect();
@@ -497,7 +975,7 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
                 };
 
                 // Check if this edge is part of a merge
-                let merge_junction = merge_junctions.get(edge.to.as_str()).copied();
+                let mut merge_junction = merge_junctions.get(edge.to.as_str()).copied();
 
                 // Detect back-edges (edges going against the flow direction)
                 // Back-edge: when normal routing would require going "backwards"
@@ -509,92 +987,86 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
                     Direction::RightLeft => to.x > from.x, // target right of source
                 };
 
+                // Forward edges into a wrapped row follow their lane instead
+                let lane = if is_back_edge {
+                    None
+                } else {
+                    wrap_lanes.get(edge.to.as_str()).copied()
+                };
+
                 // Calculate exit and entry points (and waypoints for back-edges)
-                let waypoints = if is_back_edge {
-                    // Route back-edges around the diagram
-                    let route_x = max_width + 2; // Route 2 cells past right edge
-                    match direction {
-                        Direction::TopDown => {
-                            // Exit from right side, go right, up, left, enter from right
-                            let exit_x = from.x + from.width;
-                            let exit_y = from.y + from.height / 2;
-                            let entry_x = to.x + to.width;
-                            let entry_y = to.y + to.height / 2;
-                            vec![
-                                (exit_x, exit_y),
-                                (route_x, exit_y),
-                                (route_x, entry_y),
-                                (entry_x, entry_y),
-                            ]
-                        }
-                        Direction::BottomUp => {
-                            let exit_x = from.x + from.width;
-                            let exit_y = from.y + from.height / 2;
-                            let entry_x = to.x + to.width;
-                            let entry_y = to.y + to.height / 2;
-                            vec![
-                                (exit_x, exit_y),
-                                (route_x, exit_y),
-                                (route_x, entry_y),
-                                (entry_x, entry_y),
-                            ]
-                        }
-                        Direction::LeftRight => {
-                            let route_y = max_height + 2;
-                            let exit_x = from.x + from.width / 2;
-                            let exit_y = from.y + from.height;
-                            let entry_x = to.x + to.width / 2;
-                            let entry_y = to.y + to.height;
-                            vec![
-                                (exit_x, exit_y),
-                                (exit_x, route_y),
-                                (entry_x, route_y),
-                                (entry_x, entry_y),
-                            ]
+                let waypoints = if let Some(lane) = lane {
+                    merge_junction = None;
+                    super::wrapping::lane_route(direction, from, to, lane)
+                } else if is_back_edge {
+                    has_back_edges = true;
+                    // Route back-edges around the diagram: past the right edge
+                    // for vertical flows, below the bottom for horizontal ones
+                    let (from, to) = (from.rect(), to.rect());
+                    if direction.is_vertical() {
+                        let route_x = max_width + 2;
+                        let exit = from.exit(Direction::LeftRight);
+                        let entry = to.exit(Direction::LeftRight);
+                        vec![
+                            exit,
+                            Point::new(route_x, exit.y),
+                            Point::new(route_x, entry.y),
+                            entry,
+                        ]
+                    } else {
+                        let route_y = max_height + 2;
+                        let exit = from.exit(Direction::TopDown);
+                        let entry = to.exit(Direction::TopDown);
+                        vec![
+                            exit,
+                            Point::new(exit.x, route_y),
+                            Point::new(entry.x, route_y),
+                            entry,
+                        ]
+                    }
+                } else {
+                    // Normal forward edge, detoured if it would cut through
+                    // another node
+                    let (exit, entry) = (from.rect().exit(direction), to.rect().entry(direction));
+                    let blockers: Vec<Rect> = positioned_nodes
+                        .iter()
+                        .filter(|n| n.id != from.id && n.id != to.id)
+                        .map(|n| n.rect())
+                        .collect();
+                    let crosses = Segment::path(&default_route(exit, entry))
+                        .any(|segment| blockers.iter().any(|r| segment.intersects_rect(r)));
+                    if crosses {
+                        let mut router = ObstacleRouter::new(
+                            max_width + self.config.padding + 2,
+                            max_height + self.config.padding + 2,
+                        );
+                        router.add_obstacle(from.rect());
+                        router.add_obstacle(to.rect());
+                        for rect in &blockers {
+                            router.add_obstacle(rect.inflate(1));
                         }
-                        Direction::RightLeft => {
-                            let route_y = max_height + 2;
-                            let exit_x = from.x + from.width / 2;
-                            let exit_y = from.y + from.height;
-                            let entry_x = to.x + to.width / 2;
-                            let entry_y = to.y + to.height;
-                            vec![
-                                (exit_x, exit_y),
-                                (exit_x, route_y),
-                                (entry_x, route_y),
-                                (entry_x, entry_y),
-                            ]
+                        // Splits and merges may turn right at their shared
+                        // junction; other edges leave and arrive straight
+                        let first = junction.unwrap_or(exit.step(direction, 1));
+                        let last = merge_junction.unwrap_or(entry.step(direction, -2));
+                        let pinned = |shared: Option<Point>| shared.is_none().then_some(direction);
+                        match router.route_between(
+                            first,
+                            last,
+                            pinned(junction),
+                            pinned(merge_junction),
+                        ) {
+                            Some(cells) => {
+                                let mut points = vec![exit];
+                                points.extend(cells);
+                                points.push(entry);
+                                simplify_path(&points)
+                            }
+                            None => vec![exit, entry],
                         }
+                    } else {
+                        vec![exit, entry]
                     }
-                } else {
-                    // Normal forward edge
-                    let (exit_x, exit_y, entry_x, entry_y) = match direction {
-                        Direction::TopDown => (
-                            from.x + from.width / 2,
-                            from.y + from.height,
-                            to.x + to.width / 2,
-                            to.y,
-                        ),
-                        Direction::BottomUp => (
-                            from.x + from.width / 2,
-                            from.y,
-                            to.x + to.width / 2,
-                            to.y + to.height,
-                        ),
-                        Direction::LeftRight => (
-                            from.x + from.width,
-                            from.y + from.height / 2,
-                            to.x,
-                            to.y + to.height / 2,
-                        ),
-                        Direction::RightLeft => (
-                            from.x,
-                            from.y + from.height / 2,
-                            to.x + to.width,
-                            to.y + to.height / 2,
-                        ),
-                    };
-                    vec![(exit_x, exit_y), (entry_x, entry_y)]
                 };
 
                 positioned_edges.push(PositionedEdge {
@@ -605,6 +1077,21 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
                     merge_junction,
                     group_index: if is_split { Some(group_index) } else { None },
                     group_size: if is_split { Some(group_size) } else { None },
+                    lane,
+                });
+            }
+        }
+        for edge in routed.iter().filter(|e| e.from == e.to) {
+            if let Some(node) = node_positions.get(edge.from.as_str()) {
+                positioned_edges.push(PositionedEdge {
+                    from_id: edge.from.clone(),
+                    to_id: edge.to.clone(),
+                    waypoints: self_loop_route(node.rect(), direction),
+                    junction: None,
+                    merge_junction: None,
+                    group_index: None,
+                    group_size: None,
+                    lane: None,
                 });
             }
         }
@@ -625,7 +1112,7 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
             .collect();
 
         let mut positioned_subgraphs = Vec::new();
-        for subgraph in database.subgraphs() {
+        for subgraph in full.subgraphs() {
             if subgraph.members.is_empty() {
                 // Empty subgraph: render as minimal box at top-left with padding
                 positioned_subgraphs.push(PositionedSubgraph {
@@ -641,24 +1128,16 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
             }
 
             // Find bounding box of all member nodes
-            let mut min_x = usize::MAX;
-            let mut min_y = usize::MAX;
-            let mut max_x = 0usize;
-            let mut max_y = 0usize;
-
-            for member_id in &subgraph.members {
-                if let Some(node) = node_positions.get(member_id.as_str()) {
-                    min_x = min_x.min(node.x);
-                    min_y = min_y.min(node.y);
-                    max_x = max_x.max(node.x + node.width);
-                    max_y = max_y.max(node.y + node.height);
-                }
-            }
-
-            if min_x == usize::MAX {
+            let bounds = subgraph
+                .members
+                .iter()
+                .filter_map(|member_id| node_positions.get(member_id.as_str()))
+                .map(|node| node.rect())
+                .reduce(|a, b| a.union(&b));
+            let Some(bounds) = bounds else {
                 // No members found (shouldn't happen, but defensive)
                 continue;
-            }
+            };
 
             // Add padding for border: more breathing room around nodes
             let border_padding = 2; // padding around nodes inside subgraph
@@ -667,10 +1146,10 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
             positioned_subgraphs.push(PositionedSubgraph {
                 id: subgraph.id.clone(),
                 title: subgraph.title.clone(),
-                x: min_x.saturating_sub(border_padding),
-                y: min_y.saturating_sub(border_padding + title_height),
-                width: (max_x - min_x) + border_padding * 2,
-                height: (max_y - min_y) + border_padding * 2 + title_height,
+                x: bounds.x.saturating_sub(border_padding),
+                y: bounds.y.saturating_sub(border_padding + title_height),
+                width: bounds.width + border_padding * 2,
+                height: bounds.height + border_padding * 2 + title_height,
             });
         }
         debug!(
@@ -680,11 +1159,15 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
         drop(_subgraph_enter);
 
         // Check if any back-edges need extra width for routing around diagram
-        let has_back_edges = positioned_edges.iter().any(|e| e.waypoints.len() > 2);
         let back_edge_margin = if has_back_edges { 4 } else { 0 }; // route_x uses max_width + 2
 
-        let final_width = max_width + self.config.padding + back_edge_margin;
-        let final_height = max_height + self.config.padding;
+        let mut final_width = max_width + self.config.padding + back_edge_margin;
+        let mut final_height = max_height + self.config.padding;
+        // Back-edges routed below the diagram (LR/RL) must stay on the canvas
+        for point in positioned_edges.iter().flat_map(|e| &e.waypoints) {
+            final_width = final_width.max(point.x + 1);
+            final_height = final_height.max(point.y + 1);
+        }
         info!(
             node_count = positioned_nodes.len(),
             edge_count = positioned_edges.len(),
@@ -716,10 +1199,120 @@ impl LayoutAlgorithm<FlowchartDatabase> for FlowchartLayoutAlgorithm {
     }
 }
 
+/// Layer of each node, counted from the start of the flow
+///
+/// A node's layer is the largest layer of its predecessors plus the edge's
+/// minimum length (1 unless the connector has extra dashes).
+pub(crate) fn assign_layers(database: &FlowchartDatabase) -> HashMap<&str, usize> {
+    let mut layers: HashMap<&str, usize> = HashMap::new();
+    for node_id in database.topological_sort() {
+        let layer = database
+            .edges()
+            .filter(|e| e.to == node_id)
+            .filter_map(|e| layers.get(e.from.as_str()).map(|&l| l + e.min_length))
+            .max()
+            .unwrap_or(0);
+        layers.insert(node_id, layer);
+    }
+    layers
+}
+
+/// Route for an edge from a node back to itself
+///
+/// Vertical flows loop out of the top (bottom for BT) near the right corner
+/// and come back into the right side; horizontal flows loop over the top.
+/// Either way the loop stays off the sides that flow edges use.
+fn self_loop_route(rect: Rect, direction: Direction) -> Vec<Point> {
+    let (right, middle) = (rect.right(), rect.center().y);
+    let near_right = right.saturating_sub(2).max(rect.x + 1);
+    match direction {
+        Direction::TopDown | Direction::BottomUp => {
+            let (start, outside) = if direction == Direction::TopDown {
+                (rect.y.saturating_sub(1), rect.y.saturating_sub(2))
+            } else {
+                (rect.bottom(), rect.bottom() + 1)
+            };
+            vec![
+                Point::new(near_right, start),
+                Point::new(near_right, outside),
+                Point::new(right + 1, outside),
+                Point::new(right + 1, middle),
+                Point::new(right - 1, middle),
+            ]
+        }
+        Direction::LeftRight | Direction::RightLeft => {
+            let near_left = rect.x + 1;
+            let near_right = near_right.max(near_left + 1);
+            let above = rect.y.saturating_sub(2);
+            vec![
+                Point::new(near_left, rect.y.saturating_sub(1)),
+                Point::new(near_left, above),
+                Point::new(near_right, above),
+                Point::new(near_right, rect.y),
+            ]
+        }
+    }
+}
+
+/// The path the renderer draws for a two-point edge, used to check whether
+/// it would pass through other nodes
+fn default_route(exit: Point, entry: Point) -> Vec<Point> {
+    if exit.x == entry.x || exit.y == entry.y {
+        vec![exit, entry]
+    } else if entry.y > exit.y {
+        let turn_y = entry.y.saturating_sub(2).max(exit.y + 1);
+        vec![
+            exit,
+            Point::new(exit.x, turn_y),
+            Point::new(entry.x, turn_y),
+            entry,
+        ]
+    } else {
+        let turn_x = if entry.x > exit.x {
+            entry.x.saturating_sub(1)
+        } else {
+            entry.x + 1
+        };
+        vec![
+            exit,
+            Point::new(turn_x, exit.y),
+            Point::new(turn_x, entry.y),
+        ]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_apply_init_scales_spacing() {
+        let mut config = LayoutConfig::default();
+        let warnings = config.apply_init(&FlowchartInitConfig {
+            curve: Some("linear".to_string()),
+            node_spacing: Some(150.0),
+            rank_spacing: Some(10.0),
+            diagram_padding: Some(16.0),
+        });
+
+        assert!(warnings.is_empty());
+        assert_eq!(config.node_sep, 3);
+        // Never tighter than the default rank gap
+        assert_eq!(config.rank_sep, LayoutConfig::default().rank_sep);
+        assert_eq!(config.padding, 2);
+    }
+
+    #[test]
+    fn test_apply_init_warns_on_curve() {
+        let mut config = LayoutConfig::default();
+        let warnings = config.apply_init(&FlowchartInitConfig {
+            curve: Some("basis".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("basis"));
+    }
+
     #[test]
     fn test_basic_linear_layout_lr() {
         let mut db = FlowchartDatabase::with_direction(Direction::LeftRight);
@@ -744,6 +1337,32 @@ mod tests {
         assert!(node_by_id["B"].x < node_by_id["C"].x);
     }
 
+    #[test]
+    fn test_edge_min_length_leaves_empty_layers() {
+        for direction in [Direction::TopDown, Direction::LeftRight] {
+            let mut db = FlowchartDatabase::with_direction(direction);
+            for id in ["A", "B", "C", "X", "Y"] {
+                db.add_simple_node(id, id).unwrap();
+            }
+            db.add_simple_edge("A", "B").unwrap();
+            db.add_edge(crate::core::EdgeData::new("A", "C").with_min_length(3))
+                .unwrap();
+            db.add_simple_edge("B", "X").unwrap();
+            db.add_simple_edge("X", "Y").unwrap();
+
+            let result = FlowchartLayoutAlgorithm::new().layout(&db).unwrap();
+            let node = |id: &str| result.nodes.iter().find(|n| n.id == id).unwrap();
+            let rank = |id: &str| match direction {
+                Direction::TopDown => node(id).y,
+                _ => node(id).x,
+            };
+
+            // C sits in the third layer below A, level with Y
+            assert!(rank("C") > rank("X"), "{:?}", direction);
+            assert_eq!(rank("C"), rank("Y"), "{:?}", direction);
+        }
+    }
+
     #[test]
     fn test_basic_linear_layout_td() {
         let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
@@ -1088,8 +1707,14 @@ mod tests {
             let to_node = result.nodes.iter().find(|n| n.id == edge.to_id).unwrap();
 
             // First waypoint should be near from_node, last near to_node
-            let (first_x, first_y) = edge.waypoints[0];
-            let (last_x, last_y) = edge.waypoints[edge.waypoints.len() - 1];
+            let Point {
+                x: first_x,
+                y: first_y,
+            } = edge.waypoints[0];
+            let Point {
+                x: last_x,
+                y: last_y,
+            } = edge.waypoints[edge.waypoints.len() - 1];
 
             // Check that waypoints are positioned correctly based on direction
             match direction {
@@ -1156,6 +1781,36 @@ mod tests {
         assert!(subgraph.y + subgraph.height >= node_b.y + node_b.height);
     }
 
+    #[test]
+    fn test_subgraph_direction_overrides_diagram() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+
+        for (id, label) in [("S", "Start"), ("A", "One"), ("B", "Two"), ("E", "End")] {
+            db.add_simple_node(id, label).unwrap();
+        }
+        db.add_simple_edge("S", "A").unwrap();
+        db.add_simple_edge("A", "B").unwrap();
+        db.add_simple_edge("B", "E").unwrap();
+        let id = db.add_subgraph("Row".to_string(), vec!["A".to_string(), "B".to_string()]);
+        db.set_subgraph_direction(&id, Direction::LeftRight);
+
+        let result = FlowchartLayoutAlgorithm::new().layout(&db).unwrap();
+        let node = |id: &str| result.nodes.iter().find(|n| n.id == id).unwrap();
+        let (s, a, b, e) = (node("S"), node("A"), node("B"), node("E"));
+
+        // Inside the subgraph: side by side, left to right
+        assert_eq!(a.y, b.y);
+        assert!(a.x + a.width < b.x);
+        // Around it: still top to bottom
+        assert!(s.y + s.height < a.y);
+        assert!(b.y + b.height < e.y);
+        assert_eq!(result.nodes.len(), 4);
+        assert_eq!(result.edges.len(), 3);
+
+        let subgraph = &result.subgraphs[0];
+        assert!(subgraph.x <= a.x && b.x + b.width <= subgraph.x + subgraph.width);
+    }
+
     #[test]
     fn test_empty_subgraph_layout() {
         let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
@@ -1174,4 +1829,148 @@ mod tests {
         assert!(subgraph.width >= 4); // At least title width + borders
         assert!(subgraph.height >= 3); // Minimum height
     }
+
+    #[test]
+    fn test_labeled_edges_reserve_room_in_rank_gap() {
+        let gap_after_a = |direction: Direction, label: Option<&str>| {
+            let mut db = FlowchartDatabase::with_direction(direction);
+            db.add_simple_node("A", "A").unwrap();
+            db.add_simple_node("B", "B").unwrap();
+            match label {
+                Some(label) => db
+                    .add_labeled_edge("A", "B", crate::core::EdgeType::Arrow, label)
+                    .unwrap(),
+                None => db.add_simple_edge("A", "B").unwrap(),
+            }
+            let fixed_gaps = LayoutConfig {
+                adaptive_rank_sep: false,
+                ..LayoutConfig::default()
+            };
+            let result = FlowchartLayoutAlgorithm::with_config(fixed_gaps)
+                .layout(&db)
+                .unwrap();
+            let rect = |id: &str| result.nodes.iter().find(|n| n.id == id).unwrap().rect();
+            let (a, b) = (rect("A"), rect("B"));
+            if direction == Direction::TopDown {
+                b.y - (a.y + a.height)
+            } else {
+                b.x - (a.x + a.width)
+            }
+        };
+
+        let rank_sep = LayoutConfig::default().rank_sep;
+        assert_eq!(gap_after_a(Direction::TopDown, None), rank_sep);
+        assert_eq!(gap_after_a(Direction::TopDown, Some("yes")), rank_sep + 1);
+        assert_eq!(gap_after_a(Direction::LeftRight, None), rank_sep);
+        assert_eq!(
+            gap_after_a(Direction::LeftRight, Some("retry")),
+            rank_sep + "retry".len() + 1
+        );
+    }
+
+    #[test]
+    fn test_edges_skipping_a_layer_detour_around_nodes() {
+        for direction in [Direction::TopDown, Direction::LeftRight] {
+            let mut db = FlowchartDatabase::with_direction(direction);
+            for id in ["A", "B", "C"] {
+                db.add_simple_node(id, id).unwrap();
+            }
+            db.add_simple_edge("A", "B").unwrap();
+            db.add_simple_edge("B", "C").unwrap();
+            db.add_simple_edge("A", "C").unwrap();
+            let result = FlowchartLayoutAlgorithm::new().layout(&db).unwrap();
+
+            let skip = result
+                .edges
+                .iter()
+                .find(|e| e.from_id == "A" && e.to_id == "C")
+                .unwrap();
+            assert!(skip.waypoints.len() > 2, "{:?}", skip.waypoints);
+            let b = result.nodes.iter().find(|n| n.id == "B").unwrap().rect();
+            for segment in Segment::path(&skip.waypoints) {
+                assert!(!segment.intersects_rect(&b), "{:?} crosses B", segment);
+            }
+        }
+    }
+
+    #[test]
+    fn test_self_loops_stay_beside_their_node() {
+        for direction in [
+            Direction::TopDown,
+            Direction::BottomUp,
+            Direction::LeftRight,
+        ] {
+            let mut db = FlowchartDatabase::with_direction(direction);
+            for id in ["A", "B", "C"] {
+                db.add_simple_node(id, id).unwrap();
+            }
+            db.add_simple_edge("A", "A").unwrap();
+            db.add_simple_edge("A", "C").unwrap();
+            db.add_simple_edge("B", "C").unwrap();
+            let result = FlowchartLayoutAlgorithm::new().layout(&db).unwrap();
+
+            let edge = |from: &str, to: &str| {
+                result
+                    .edges
+                    .iter()
+                    .find(|e| e.from_id == from && e.to_id == to)
+                    .unwrap()
+            };
+            let looped = edge("A", "A");
+            assert!(looped.junction.is_none() && looped.merge_junction.is_none());
+            // The loop is not a branch of A's split
+            assert!(edge("A", "C").group_size.is_none());
+
+            let a = result.nodes.iter().find(|n| n.id == "A").unwrap().rect();
+            let last = *looped.waypoints.last().unwrap();
+            let first = looped.waypoints[0];
+            assert!(
+                a.contains(last),
+                "{:?} ends outside {:?}",
+                looped.waypoints,
+                a
+            );
+            assert!(!a.contains(first));
+            for other in result.nodes.iter().filter(|n| n.id != "A") {
+                for segment in Segment::path(&looped.waypoints) {
+                    assert!(!segment.intersects_rect(&other.rect()), "{:?}", direction);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_adaptive_rank_gaps_fit_their_edges() {
+        // A straight chain, then a split into two branches
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        for id in ["A", "B", "C", "D"] {
+            db.add_simple_node(id, id).unwrap();
+        }
+        db.add_simple_edge("A", "B").unwrap();
+        db.add_simple_edge("B", "C").unwrap();
+        db.add_simple_edge("B", "D").unwrap();
+
+        let result = FlowchartLayoutAlgorithm::new().layout(&db).unwrap();
+        let rect = |id: &str| result.nodes.iter().find(|n| n.id == id).unwrap().rect();
+        assert_eq!(rect("B").y - rect("A").bottom(), 2);
+        assert_eq!(rect("C").y - rect("B").bottom(), 3);
+
+        let fixed = FlowchartLayoutAlgorithm::with_config(LayoutConfig {
+            adaptive_rank_sep: false,
+            ..LayoutConfig::default()
+        })
+        .layout(&db)
+        .unwrap();
+        assert_eq!(result.height + 3, fixed.height);
+    }
+
+    #[test]
+    fn test_rank_spacing_directive_keeps_fixed_gaps() {
+        let mut config = LayoutConfig::default();
+        config.apply_init(&FlowchartInitConfig {
+            rank_spacing: Some(100.0),
+            ..Default::default()
+        });
+        assert!(!config.adaptive_rank_sep);
+    }
 }
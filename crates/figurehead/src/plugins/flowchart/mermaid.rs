@@ -0,0 +1,303 @@
+//! Mermaid source output for flowchart databases
+//!
+//! [`FlowchartDatabase::to_mermaid`] writes a database back out as flowchart
+//! markup, so diagrams built in code or imported from other formats can be
+//! maintained as Mermaid from then on.
+
+use super::FlowchartDatabase;
+use crate::core::{Database, EdgeData, EdgeTerminator, NodeShape};
+
+impl FlowchartDatabase {
+    /// Write the diagram as Mermaid flowchart markup
+    ///
+    /// The output covers the direction, nodes with their shapes and labels,
+    /// subgraphs, edges with their types and labels, and `click` links;
+    /// parsing it again gives the same structure. Styles and classes are not
+    /// written.
+    ///
+    /// # Example
+    /// ```
+    /// use figurehead::plugins::flowchart::FlowchartDatabase;
+    /// use figurehead::prelude::*;
+    ///
+    /// let mut db = FlowchartDatabase::new();
+    /// db.add_shaped_node("A", "Ready?", NodeShape::Diamond).unwrap();
+    /// db.add_simple_node("B", "B").unwrap();
+    /// db.add_labeled_edge("A", "B", EdgeType::Arrow, "yes").unwrap();
+    /// assert_eq!(db.to_mermaid(), "flowchart TD\n    A{Ready?}\n    B\n    A -->|yes| B\n");
+    /// ```
+    pub fn to_mermaid(&self) -> String {
+        let mut out = format!("flowchart {}\n", self.direction());
+
+        for subgraph in self.subgraphs() {
+            out.push_str(&format!(
+                "    subgraph \"{}\"\n",
+                subgraph.title.replace('"', "#quot;")
+            ));
+            if let Some(direction) = subgraph.direction {
+                out.push_str(&format!("        direction {}\n", direction));
+            }
+            for id in &subgraph.members {
+                if let Some(node) = self.get_node(id) {
+                    out.push_str(&format!(
+                        "        {}\n",
+                        node_syntax(id, &node.label, node.shape)
+                    ));
+                }
+            }
+            out.push_str("    end\n");
+        }
+
+        for node in self.nodes() {
+            if self.node_subgraph(&node.id).is_none() {
+                out.push_str(&format!(
+                    "    {}\n",
+                    node_syntax(&node.id, &node.label, node.shape)
+                ));
+            }
+        }
+
+        for edge in self.edges() {
+            match &edge.label {
+                Some(label) => out.push_str(&format!(
+                    "    {} {}|{}| {}\n",
+                    edge.from,
+                    connector(edge),
+                    edge_label(label),
+                    edge.to
+                )),
+                None => out.push_str(&format!(
+                    "    {} {} {}\n",
+                    edge.from,
+                    connector(edge),
+                    edge.to
+                )),
+            }
+        }
+
+        for node in self.nodes() {
+            if let Some(link) = &node.link {
+                let url = link.url.replace('"', "%22");
+                match &link.tooltip {
+                    Some(tooltip) => out.push_str(&format!(
+                        "    click {} \"{}\" \"{}\"\n",
+                        node.id,
+                        url,
+                        tooltip.replace('"', "#quot;")
+                    )),
+                    None => out.push_str(&format!("    click {} \"{}\"\n", node.id, url)),
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Node declaration such as `A{label}`; a plain rectangle labeled with its
+/// own ID is just `A`
+fn node_syntax(id: &str, label: &str, shape: NodeShape) -> String {
+    let (open, close) = match shape {
+        NodeShape::Rectangle | NodeShape::Terminal => ("[", "]"),
+        NodeShape::RoundedRect => ("(", ")"),
+        NodeShape::Stadium => ("([", "])"),
+        NodeShape::Circle => ("((", "))"),
+        NodeShape::DoubleCircle => ("(((", ")))"),
+        NodeShape::Diamond => ("{", "}"),
+        NodeShape::Hexagon => ("{{", "}}"),
+        NodeShape::Subroutine => ("[[", "]]"),
+        NodeShape::Cylinder => ("[(", ")]"),
+        NodeShape::Asymmetric => (">", "]"),
+        NodeShape::Parallelogram => ("[/", "/]"),
+        NodeShape::ParallelogramAlt => ("[\\", "\\]"),
+        NodeShape::Trapezoid => ("[/", "\\]"),
+        NodeShape::InvTrapezoid => ("[\\", "/]"),
+    };
+    if label == id && matches!(shape, NodeShape::Rectangle | NodeShape::Terminal) {
+        id.to_string()
+    } else {
+        format!("{}{}{}{}", id, open, quote(label), close)
+    }
+}
+
+/// Connector for `edge`: its type's operator with one more of the repeated
+/// character (`---->`, `-..->`) per layer past the first it must span, and
+/// the source marker (`<-->`, `o--o`) in front
+fn connector(edge: &EdgeData) -> String {
+    let written = edge.edge_type.to_string();
+    let (head, tail) = written.split_at(1);
+    let repeated = tail.chars().next().unwrap_or('-');
+    let extra: String = std::iter::repeat_n(repeated, edge.min_length.saturating_sub(1)).collect();
+    let marker = match edge.start {
+        EdgeTerminator::Arrow | EdgeTerminator::Triangle => "<",
+        EdgeTerminator::Open => "o",
+        EdgeTerminator::Cross => "x",
+        EdgeTerminator::Diamond { .. } | EdgeTerminator::None => "",
+    };
+    format!("{}{}{}{}", marker, head, extra, tail)
+}
+
+/// Edge label text for `-->|label|`, quoted only when it contains a pipe
+fn edge_label(text: &str) -> String {
+    let text = text.replace('\n', "<br/>");
+    if text.contains(['|', '"']) {
+        format!("\"{}\"", text.replace('"', "#quot;"))
+    } else {
+        text
+    }
+}
+
+/// Wrap text in double quotes when it contains Mermaid delimiters
+///
+/// Line breaks are written as `<br/>` tags.
+fn quote(text: &str) -> String {
+    const DELIMITERS: &[char] = &[
+        '[', ']', '(', ')', '{', '}', '<', '>', '|', '"', '/', '\\', ';',
+    ];
+    let text = text.replace('\n', "<br/>");
+    if text.contains(DELIMITERS) {
+        format!("\"{}\"", text.replace('"', "#quot;"))
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Direction, EdgeType, Parser};
+    use crate::plugins::flowchart::FlowchartParser;
+
+    #[test]
+    fn test_round_trips_through_parser() {
+        let input = "graph LR\n    subgraph \"Backend\"\n        direction TB\n        API[(Store)] --> Q{{Queue}}\n    end\n    C(Client) -.->|GET /items| API\n    Q ==> W[/Worker/]";
+        let mut db = FlowchartDatabase::new();
+        FlowchartParser::new().parse(input, &mut db).unwrap();
+
+        let mermaid = db.to_mermaid();
+        let mut reparsed = FlowchartDatabase::new();
+        FlowchartParser::new()
+            .parse(&mermaid, &mut reparsed)
+            .unwrap();
+
+        assert_eq!(reparsed.direction(), Direction::LeftRight);
+        for node in db.nodes() {
+            let copy = reparsed.get_node(&node.id).unwrap();
+            assert_eq!((&copy.label, copy.shape), (&node.label, node.shape));
+        }
+        let edges: Vec<_> = reparsed
+            .edges()
+            .map(|e| {
+                (
+                    e.from.as_str(),
+                    e.to.as_str(),
+                    e.edge_type,
+                    e.label.as_deref(),
+                )
+            })
+            .collect();
+        assert!(edges.contains(&("C", "API", EdgeType::DottedArrow, Some("GET /items"))));
+        assert!(edges.contains(&("Q", "W", EdgeType::ThickArrow, None)));
+        let backend = reparsed.node_subgraph("API").unwrap();
+        assert_eq!(backend.title, "Backend");
+        assert_eq!(backend.direction, Some(Direction::TopDown));
+    }
+
+    #[test]
+    fn test_new_shapes_round_trip() {
+        let input = "graph TD\n    A([Start]) --> B(((Done)))\n    C[\\In\\] --> D[\\Out/]";
+        let mut db = FlowchartDatabase::new();
+        FlowchartParser::new().parse(input, &mut db).unwrap();
+
+        let mermaid = db.to_mermaid();
+        assert!(mermaid.contains("A([Start])"), "{}", mermaid);
+        assert!(mermaid.contains("B(((Done)))"), "{}", mermaid);
+        assert!(mermaid.contains("C[\\In\\]"), "{}", mermaid);
+        assert!(mermaid.contains("D[\\Out/]"), "{}", mermaid);
+
+        let mut reparsed = FlowchartDatabase::new();
+        FlowchartParser::new()
+            .parse(&mermaid, &mut reparsed)
+            .unwrap();
+        for node in db.nodes() {
+            assert_eq!(reparsed.get_node(&node.id).unwrap().shape, node.shape);
+        }
+    }
+
+    #[test]
+    fn test_extended_connectors_round_trip() {
+        let input = "graph TD\n    A ---->|far| B\n    B -..- C\n    C ===> D";
+        let mut db = FlowchartDatabase::new();
+        FlowchartParser::new().parse(input, &mut db).unwrap();
+
+        let mermaid = db.to_mermaid();
+        assert!(mermaid.contains("A ---->|far| B"), "{}", mermaid);
+        assert!(mermaid.contains("B -..- C"), "{}", mermaid);
+        assert!(mermaid.contains("C ===> D"), "{}", mermaid);
+
+        let input = "graph LR\n    A <--> B\n    B o--o C\n    C <-.-> D";
+        let mut db = FlowchartDatabase::new();
+        FlowchartParser::new().parse(input, &mut db).unwrap();
+        let mermaid = db.to_mermaid();
+        for edge in ["A <--> B", "B o--o C", "C <-.-> D"] {
+            assert!(mermaid.contains(edge), "{}", mermaid);
+        }
+    }
+
+    #[test]
+    fn test_click_links_round_trip() {
+        let input = "graph LR\n    A --> B\n    click A \"https://example.com\" \"Say #quot;hi#quot;\"\n    click B href \"https://example.com/b\"";
+        let mut db = FlowchartDatabase::new();
+        FlowchartParser::new().parse(input, &mut db).unwrap();
+
+        let mermaid = db.to_mermaid();
+        assert!(
+            mermaid.ends_with(
+                "    click A \"https://example.com\" \"Say #quot;hi#quot;\"\n    click B \"https://example.com/b\"\n"
+            ),
+            "{}",
+            mermaid
+        );
+
+        let mut reparsed = FlowchartDatabase::new();
+        FlowchartParser::new()
+            .parse(&mermaid, &mut reparsed)
+            .unwrap();
+        for node in db.nodes() {
+            assert_eq!(reparsed.get_node(&node.id).unwrap().link, node.link);
+        }
+    }
+
+    #[test]
+    fn test_quotes_labels_with_delimiters() {
+        assert_eq!(node_syntax("A", "A", NodeShape::Rectangle), "A");
+        assert_eq!(
+            node_syntax("A", "f(x)", NodeShape::Rectangle),
+            "A[\"f(x)\"]"
+        );
+        assert_eq!(node_syntax("B", "Done", NodeShape::RoundedRect), "B(Done)");
+    }
+
+    #[test]
+    fn test_quoted_and_multi_line_labels_round_trip() {
+        let mut db = FlowchartDatabase::new();
+        db.add_shaped_node("A", "f(\"x\")\nper line", NodeShape::Rectangle)
+            .unwrap();
+        db.add_shaped_node("B", "B", NodeShape::Rectangle).unwrap();
+        db.add_labeled_edge("A", "B", EdgeType::Arrow, "two\nlines")
+            .unwrap();
+
+        let mermaid = db.to_mermaid();
+        assert!(
+            mermaid.contains("A[\"f(#quot;x#quot;)<br/>per line\"]"),
+            "{}",
+            mermaid
+        );
+        let mut reparsed = FlowchartDatabase::new();
+        FlowchartParser::new()
+            .parse(&mermaid, &mut reparsed)
+            .unwrap();
+        assert_eq!(reparsed.get_node("A").unwrap().label, "f(\"x\")\nper line");
+        let edge = reparsed.edges().next().unwrap();
+        assert_eq!(edge.label.as_deref(), Some("two\nlines"));
+    }
+}
@@ -2,24 +2,66 @@
 //!
 //! This module contains the flowchart diagram implementation for Mermaid.js
 //! flowchart syntax support.
+//!
+//! Labels may be quoted (`A["f(x)"]`) to hold delimiters, or written as
+//! markdown strings (`` A["`**bold**`"] ``). `<br/>` tags become line breaks:
+//! the node grows a row per line, while edge labels stay on one row.
+//! Entities such as `&amp;`, `#quot;` and `#35;` are decoded last, so an
+//! escaped `&lt;br&gt;` is shown rather than breaking the line.
+//!
+//! Extra dashes, dots or equals signs in a connector (`A ----> B`,
+//! `A -..-> B`) lengthen the edge: its target sits one layer further down
+//! the flow per extra character, leaving room in congested areas.
+//!
+//! A `<`, `o` or `x` before the connector (`A <--> B`, `A o--o B`,
+//! `A x--x B`) marks the source end as well as the target.
+//!
+//! `click A "https://…" "tooltip"` attaches a link to a node. Callback forms
+//! are accepted and ignored; with link footnotes turned on, linked nodes get
+//! a `[n]` marker and the links are listed below the diagram.
+//!
+//! The debug overlay frames the drawing with its canvas bounds and marks
+//! each node's layer and ID and every edge junction, with a table of their
+//! positions underneath, to help diagnose and report layout issues.
 
 use crate::core::{Detector, Diagram};
 use std::sync::Arc;
 
+mod annotate;
+mod ansi;
+mod braille;
+mod cells;
 mod chumsky_parser;
 mod database;
+mod describe;
 mod detector;
-mod layout;
+mod dot;
+mod html;
+#[cfg(any(test, feature = "unstable"))]
+pub(crate) mod ingest;
+#[allow(missing_docs)]
+pub(crate) mod layout;
+mod mermaid;
 mod ordering;
+mod overlay;
 mod parser;
 mod renderer;
+mod sizing;
+mod subgraph_direction;
+#[allow(missing_docs)]
+pub(crate) mod validation;
 mod whitespace;
+mod wrapping;
 
+pub use annotate::*;
 pub use database::*;
+pub use describe::*;
 pub use detector::*;
-pub use layout::*;
+pub use dot::*;
+pub(crate) use layout::*;
 pub use parser::*;
 pub use renderer::*;
+pub(crate) use validation::*;
 
 /// Flowchart diagram implementation
 pub struct FlowchartDiagram;
@@ -1,7 +1,7 @@
 //! Barycenter ordering algorithm for edge crossing minimization
 //!
 //! Implements the ordering phase of the Sugiyama layout algorithm,
-//! using barycenter heuristics to minimize edge crossings.
+//! using barycenter and median heuristics to minimize edge crossings.
 
 use std::collections::HashMap;
 
@@ -104,6 +104,43 @@ pub fn compute_barycenters(
         .collect()
 }
 
+/// Compute median positions for nodes in a layer.
+///
+/// Like [`compute_barycenters`], but takes the median neighbor position
+/// (the mean of the two middle ones for an even count), which is not pulled
+/// aside by a single far-away neighbor.
+pub fn compute_medians(
+    layer: &[&str],
+    ref_layer: &[&str],
+    db: &FlowchartDatabase,
+    direction: SweepDirection,
+) -> Vec<Option<f64>> {
+    let ref_pos: HashMap<&str, usize> =
+        ref_layer.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+
+    layer
+        .iter()
+        .map(|&node| {
+            let neighbors: Vec<&str> = match direction {
+                SweepDirection::Downward => db.predecessors(node),
+                SweepDirection::Upward => db.successors(node),
+            };
+            let mut positions: Vec<usize> = neighbors
+                .iter()
+                .filter_map(|&n| ref_pos.get(n).copied())
+                .collect();
+            positions.sort_unstable();
+
+            let mid = positions.len() / 2;
+            match positions.len() {
+                0 => None,
+                len if len % 2 == 1 => Some(positions[mid] as f64),
+                _ => Some((positions[mid - 1] + positions[mid]) as f64 / 2.0),
+            }
+        })
+        .collect()
+}
+
 /// Order nodes in a layer by their barycenter values.
 ///
 /// Nodes with barycenters are sorted by their barycenter value.
@@ -139,8 +176,11 @@ pub fn order_layer_by_barycenter(layer: &mut Vec<&str>, barycenters: &[Option<f6
 
 /// Apply barycenter ordering to minimize edge crossings.
 ///
-/// Performs multiple sweeps (alternating downward/upward) to iteratively
-/// improve the ordering. Keeps track of the best ordering found.
+/// Performs up to `iterations` sweeps (alternating downward/upward) to
+/// iteratively improve the ordering, stopping early once no crossings are
+/// left. Each layer takes its barycenter order, or its median order when
+/// that crosses fewer edges to the reference layer. Keeps track of the best
+/// ordering found; 0 iterations keeps the given order.
 ///
 /// Returns the crossing count of the best ordering found.
 pub fn order_layers_barycenter(
@@ -156,6 +196,9 @@ pub fn order_layers_barycenter(
     let mut best_cc = cross_count(layers, db);
 
     for i in 0..iterations {
+        if best_cc == 0 {
+            break;
+        }
         let downward = i % 2 == 0;
 
         // Determine layer indices to process
@@ -177,10 +220,22 @@ pub fn order_layers_barycenter(
                 SweepDirection::Upward
             };
 
-            // Compute barycenters and reorder
+            // Compute barycenters and reorder, falling back to the median
+            // order when it crosses less
             let barycenters =
                 compute_barycenters(&layers[layer_idx], &layers[ref_idx], db, direction);
+            let medians = compute_medians(&layers[layer_idx], &layers[ref_idx], db, direction);
+            let mut by_median = layers[layer_idx].clone();
             order_layer_by_barycenter(&mut layers[layer_idx], &barycenters);
+            order_layer_by_barycenter(&mut by_median, &medians);
+
+            let crossings = |layer: &[&str]| match direction {
+                SweepDirection::Downward => two_layer_cross_count(&layers[ref_idx], layer, db),
+                SweepDirection::Upward => two_layer_cross_count(layer, &layers[ref_idx], db),
+            };
+            if crossings(&by_median) < crossings(&layers[layer_idx]) {
+                layers[layer_idx] = by_median;
+            }
         }
 
         // Check if this ordering is better
@@ -644,4 +699,38 @@ mod tests {
         // Should achieve 0 crossings for diamond
         assert_eq!(final_cc, 0);
     }
+
+    #[test]
+    fn test_median_ignores_a_far_neighbor() {
+        // D hangs off A, B and E; its barycenter is pulled right by E, its
+        // median stays on B
+        let mut db = create_db();
+        for id in ["A", "B", "C", "E", "D"] {
+            db.add_simple_node(id, id).unwrap();
+        }
+        for from in ["A", "B", "E"] {
+            db.add_simple_edge(from, "D").unwrap();
+        }
+
+        let north = ["A", "B", "C", "E"];
+        let layer = ["D"];
+        let barycenter = compute_barycenters(&layer, &north, &db, SweepDirection::Downward);
+        let median = compute_medians(&layer, &north, &db, SweepDirection::Downward);
+        assert!((barycenter[0].unwrap() - 4.0 / 3.0).abs() < 1e-9);
+        assert_eq!(median[0], Some(1.0));
+    }
+
+    #[test]
+    fn test_order_layers_zero_iterations_keeps_order() {
+        let mut db = create_db();
+        for id in ["A", "B", "C", "D"] {
+            db.add_simple_node(id, id).unwrap();
+        }
+        db.add_simple_edge("A", "C").unwrap();
+        db.add_simple_edge("B", "D").unwrap();
+
+        let mut layers = vec![vec!["A", "B"], vec!["D", "C"]];
+        assert_eq!(order_layers_barycenter(&db, &mut layers, 0), 1);
+        assert_eq!(layers[1], vec!["D", "C"]);
+    }
 }
@@ -0,0 +1,194 @@
+//! Layout debug overlay
+//!
+//! With [`RenderConfig::debug_overlay`](crate::core::RenderConfig::debug_overlay)
+//! on, the drawing is framed by the full canvas, each node's top border is
+//! stamped with `layer:id`, and split and merge junctions are starred. A
+//! `Layout` table under the drawing lists the same positions in canvas
+//! coordinates, so a layout bug report can quote exact cells.
+
+use super::{assign_layers, FlowchartDatabase, FlowchartLayoutResult, NodeRegion};
+use super::{PositionedNode, RenderedFlowchart};
+use crate::core::{AsciiCanvas, CharacterSet, Footer, FooterSection, Point};
+
+/// Characters used for the canvas frame and junction marks
+struct OverlayChars {
+    corners: [char; 4],
+    horizontal: char,
+    vertical: char,
+    junction: char,
+}
+
+impl OverlayChars {
+    fn for_style(style: CharacterSet) -> Self {
+        match style {
+            CharacterSet::Ascii | CharacterSet::Compact => Self {
+                corners: ['+', '+', '+', '+'],
+                horizontal: '.',
+                vertical: ':',
+                junction: '*',
+            },
+            _ => Self {
+                corners: ['┌', '┐', '└', '┘'],
+                horizontal: '┄',
+                vertical: '┆',
+                junction: '●',
+            },
+        }
+    }
+}
+
+/// `layer:id` tag stamped on a node's top border
+fn node_tag(layer: Option<usize>, node: &PositionedNode) -> String {
+    match layer {
+        Some(layer) => format!("{}:{}", layer, node.id),
+        None => node.id.clone(),
+    }
+}
+
+/// Draw the overlay over a finished canvas and frame it
+///
+/// Node regions are shifted by the frame; the canvas is not trimmed, so
+/// positions in the table match the layout.
+pub(crate) fn debug_overlay(
+    mut canvas: AsciiCanvas,
+    database: &FlowchartDatabase,
+    layout: &FlowchartLayoutResult,
+    style: CharacterSet,
+) -> RenderedFlowchart {
+    let chars = OverlayChars::for_style(style);
+    let layers = assign_layers(database);
+
+    // Junctions first, so a tag on a node border right above one stays
+    let mut junctions: Vec<(&str, Point)> = Vec::new();
+    for edge in &layout.edges {
+        let kinds = [("split", edge.junction), ("merge", edge.merge_junction)];
+        for (kind, point) in kinds {
+            if let Some(point) = point.filter(|p| !junctions.contains(&(kind, *p))) {
+                junctions.push((kind, point));
+            }
+        }
+    }
+    for (_, point) in &junctions {
+        canvas.set_char(point.x, point.y, chars.junction);
+    }
+
+    // Stamp inside the top border so the corners stay intact
+    for node in &layout.nodes {
+        let tag = node_tag(layers.get(node.id.as_str()).copied(), node);
+        let room = node.width.saturating_sub(2);
+        for (offset, c) in tag.chars().take(room).enumerate() {
+            canvas.set_char(node.x + 1 + offset, node.y, c);
+        }
+    }
+
+    let width = canvas.width;
+    let title: String = match style {
+        CharacterSet::Ascii | CharacterSet::Compact => format!(" {}x{} ", width, canvas.height),
+        _ => format!(" {}×{} ", width, canvas.height),
+    };
+    let title: String = title.chars().take(width).collect();
+    let mut lines = Vec::with_capacity(canvas.height + 2);
+    lines.push(format!(
+        "{}{}{}{}",
+        chars.corners[0],
+        title,
+        chars
+            .horizontal
+            .to_string()
+            .repeat(width - title.chars().count()),
+        chars.corners[1]
+    ));
+    for row in &canvas.grid {
+        let cells: String = row.iter().collect();
+        lines.push(format!("{}{}{}", chars.vertical, cells, chars.vertical));
+    }
+    lines.push(format!(
+        "{}{}{}",
+        chars.corners[2],
+        chars.horizontal.to_string().repeat(width),
+        chars.corners[3]
+    ));
+
+    let mut section = FooterSection::titled("Layout");
+    let mut nodes: Vec<&PositionedNode> = layout.nodes.iter().collect();
+    nodes.sort_by_key(|node| (layers.get(node.id.as_str()), node.y, node.x));
+    for node in nodes {
+        section.push(
+            node_tag(layers.get(node.id.as_str()).copied(), node),
+            format!("({}, {}) {}x{}", node.x, node.y, node.width, node.height),
+        );
+    }
+    for (kind, point) in &junctions {
+        section.push(
+            chars.junction.to_string(),
+            format!("{} ({}, {})", kind, point.x, point.y),
+        );
+    }
+    let text = Footer::new()
+        .with_section(section)
+        .attach(&lines.join("\n"));
+
+    let nodes = layout
+        .nodes
+        .iter()
+        .map(|node| NodeRegion {
+            id: node.id.clone(),
+            x: node.x + 1,
+            y: node.y + 1,
+            width: node.width,
+            height: node.height,
+        })
+        .collect();
+    RenderedFlowchart {
+        text,
+        nodes,
+        edges: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::FlowchartRenderer;
+    use crate::core::Renderer;
+
+    #[test]
+    fn test_overlay_marks_layers_ids_and_junctions() {
+        let db = crate::parse("graph TD\n A[Start] --> B[Left]\n A --> C[Right]").unwrap();
+        let output = FlowchartRenderer::new()
+            .with_debug_overlay(true)
+            .render(&db)
+            .unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(lines[0].starts_with("┌ "), "{}", output);
+        assert!(lines[0].contains('×'), "{}", output);
+        assert!(output.contains("┌0:A"), "{}", output);
+        assert!(output.contains("┌1:B"), "{}", output);
+        assert!(output.contains("┌1:C"), "{}", output);
+        assert!(output.contains('●'), "{}", output);
+        assert!(output.contains("Layout"), "{}", output);
+        assert!(output.contains("split ("), "{}", output);
+    }
+
+    #[test]
+    fn test_overlay_regions_follow_the_frame() {
+        let db = crate::parse("graph LR\n A --> B").unwrap();
+        let renderer = FlowchartRenderer::with_style(crate::core::CharacterSet::Ascii)
+            .with_debug_overlay(true);
+        let rendered = renderer.render_with_regions(&db).unwrap();
+        let lines: Vec<&str> = rendered.text.lines().collect();
+
+        assert!(lines[0].starts_with("+ "), "{}", rendered.text);
+        let a = rendered.node("A").unwrap();
+        let row: Vec<char> = lines[a.y].chars().collect();
+        assert_eq!(row[a.x + 1..a.x + 4], ['0', ':', 'A'], "{}", rendered.text);
+    }
+
+    #[test]
+    fn test_overlay_is_off_by_default() {
+        let db = crate::parse("graph TD\n A --> B").unwrap();
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(!output.contains("Layout"));
+        assert!(!output.contains("0:A"));
+    }
+}
@@ -5,45 +5,202 @@
 
 use super::chumsky_parser::{ChumskyFlowchartParser, NodeRef, Statement};
 use super::FlowchartDatabase;
-use crate::core::{Database, EdgeData, NodeData, Parser};
+pub use crate::core::ParseWarning;
+use crate::core::{
+    normalize_input, Database, EdgeData, FigureheadError, IdCase, LenientStatements, NodeData,
+    Parser, SpannedStatement, StatementSplitter,
+};
 use anyhow::Result;
-use std::cell::RefCell;
-use std::cmp::Ordering;
+use std::fmt;
 use tracing::{debug, error, info, span, trace, warn, Level};
 
-thread_local! {
-    /// Thread-local storage for collecting parse warnings
-    static PARSE_WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+/// What to do when a flowchart header appears again mid-document
+///
+/// Concatenated files often contain several `graph ...` headers. Only the
+/// first one sets the direction, so the later diagrams would otherwise be
+/// merged into it silently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum RepeatedHeaders {
+    /// Merge everything into one diagram and record a parse warning per
+    /// repeated header
+    #[default]
+    Warn,
+    /// Fail the parse
+    Error,
+    /// Parse only the first diagram; callers render the others separately
+    /// with [`split_diagrams`]
+    Split,
 }
 
-/// Clear any accumulated warnings
-pub fn clear_warnings() {
-    PARSE_WARNINGS.with(|w| w.borrow_mut().clear());
+impl RepeatedHeaders {
+    /// Every repeated header policy
+    pub const ALL: [RepeatedHeaders; 3] = [
+        RepeatedHeaders::Warn,
+        RepeatedHeaders::Error,
+        RepeatedHeaders::Split,
+    ];
 }
 
-/// Get all accumulated warnings and clear them
-pub fn take_warnings() -> Vec<String> {
-    PARSE_WARNINGS.with(|w| std::mem::take(&mut *w.borrow_mut()))
+impl fmt::Display for RepeatedHeaders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepeatedHeaders::Warn => write!(f, "warn"),
+            RepeatedHeaders::Error => write!(f, "error"),
+            RepeatedHeaders::Split => write!(f, "split"),
+        }
+    }
 }
 
-/// Add a warning to the collection
-fn add_warning(warning: String) {
-    PARSE_WARNINGS.with(|w| w.borrow_mut().push(warning));
+impl std::str::FromStr for RepeatedHeaders {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "warn" => Ok(RepeatedHeaders::Warn),
+            "error" => Ok(RepeatedHeaders::Error),
+            "split" => Ok(RepeatedHeaders::Split),
+            _ => Err(format!(
+                "Unknown repeated header policy '{}'. Use 'warn', 'error' or 'split'",
+                s
+            )),
+        }
+    }
 }
 
-const CONNECTORS: [&str; 9] = [
-    "-.->", "==>", "===", "-->", "---", "-.-", "--o", "--x", "~~~",
-];
-
 /// Flowchart parser implementation
-pub struct FlowchartParser;
+#[derive(Debug, Clone, Copy)]
+pub struct FlowchartParser {
+    repeated_headers: RepeatedHeaders,
+    id_case: IdCase,
+}
 
 impl FlowchartParser {
+    /// Create a parser
     pub fn new() -> Self {
-        Self
+        Self {
+            repeated_headers: RepeatedHeaders::default(),
+            id_case: IdCase::default(),
+        }
+    }
+
+    /// Set how a second `graph`/`flowchart` header in the input is handled
+    pub fn with_repeated_headers(mut self, policy: RepeatedHeaders) -> Self {
+        self.repeated_headers = policy;
+        self
+    }
+
+    /// Current repeated header policy
+    pub fn repeated_headers(&self) -> RepeatedHeaders {
+        self.repeated_headers
+    }
+
+    /// Set whether node IDs that differ only in case (`Start`, `start`)
+    /// name the same node
+    pub fn with_id_case(mut self, case: IdCase) -> Self {
+        self.id_case = case;
+        self
+    }
+
+    /// Current node ID case handling
+    pub fn id_case(&self) -> IdCase {
+        self.id_case
+    }
+
+    /// Apply the repeated header policy, returning the text to parse
+    fn first_diagram<'a>(
+        &self,
+        input: &'a str,
+        database: &mut FlowchartDatabase,
+    ) -> Result<&'a str> {
+        let headers = header_lines(input);
+        let Some(&(_, first)) = headers.first() else {
+            return Ok(input);
+        };
+
+        for &(line, header) in &headers[1..] {
+            match self.repeated_headers {
+                RepeatedHeaders::Warn => {
+                    warn!(line, %header, "Repeated flowchart header");
+                    database.add_parse_warning(
+                        ParseWarning::new(format!(
+                            "Repeated header '{}' on line {} ignored; keeping '{}'",
+                            header, line, first
+                        ))
+                        .at(line, column_of(input, line, header))
+                        .with_statement(header),
+                    );
+                }
+                RepeatedHeaders::Error => {
+                    return Err(FigureheadError::parse(
+                        "flowchart",
+                        line,
+                        column_of(input, line, header),
+                        header,
+                        format!(
+                            "repeated header '{}'; the input holds more than one diagram",
+                            header
+                        ),
+                    )
+                    .into());
+                }
+                RepeatedHeaders::Split => {
+                    debug!(diagrams = headers.len(), "Parsing the first diagram only");
+                    return Ok(split_diagrams(input)[0]);
+                }
+            }
+        }
+
+        Ok(input)
     }
 }
 
+/// Split input holding several flowcharts at each repeated header
+///
+/// Anything before the first header (init directives, comments) stays with
+/// the first diagram. Input with at most one header is returned whole.
+///
+/// # Example
+/// ```rust
+/// use figurehead::plugins::flowchart::split_diagrams;
+///
+/// let parts = split_diagrams("graph TD\n  A-->B\ngraph LR\n  C-->D\n");
+/// assert_eq!(parts, vec!["graph TD\n  A-->B\n", "graph LR\n  C-->D\n"]);
+/// ```
+pub fn split_diagrams(input: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    let mut seen_header = false;
+
+    for line in input.split_inclusive('\n') {
+        if header_of(line).is_some() {
+            if seen_header {
+                parts.push(&input[start..offset]);
+                start = offset;
+            }
+            seen_header = true;
+        }
+        offset += line.len();
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+/// Header lines in the input as (1-based line number, header text)
+fn header_lines(input: &str) -> Vec<(usize, &str)> {
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| header_of(line).map(|header| (index + 1, header)))
+        .collect()
+}
+
+/// The `graph`/`flowchart` declaration a line starts with, if any
+fn header_of(line: &str) -> Option<&str> {
+    let first = line.trim().split(';').next()?.trim();
+    is_graph_declaration(first).then_some(first)
+}
+
 impl Default for FlowchartParser {
     fn default() -> Self {
         Self::new()
@@ -57,7 +214,17 @@ impl Parser<FlowchartDatabase> for FlowchartParser {
 
         trace!("Starting flowchart parsing");
 
+        if super::is_dot(input) {
+            debug!("Input is a DOT graph");
+            return super::DotParser::new().parse(input, database);
+        }
+
+        let normalized = normalize_input(input);
+        let input = normalized.as_ref();
+
         let chumsky = ChumskyFlowchartParser::new();
+        let input = self.first_diagram(input, database)?;
+        database.set_id_case(self.id_case);
 
         // First, try to extract the direction from the header
         let direction_span = span!(Level::DEBUG, "parse_direction");
@@ -72,52 +239,66 @@ impl Parser<FlowchartDatabase> for FlowchartParser {
         }
         drop(_direction_enter);
 
-        let mut skipped_statements = Vec::new();
+        let mut statements = LenientStatements::new("flowchart");
         let mut node_count = 0;
         let mut edge_count = 0;
 
         // Parse statements
         let statements_span = span!(Level::DEBUG, "parse_statements");
         let _statements_enter = statements_span.enter();
-        for statement_text in extract_statements(input) {
-            match chumsky.parse_statement(&statement_text) {
-                Ok(statement) => {
-                    trace!(statement = ?statement, "Parsing statement");
-                    match &statement {
-                        Statement::Node(_) => node_count += 1,
-                        Statement::Edge(_) => edge_count += 1,
-                        _ => {}
-                    }
-                    if let Err(e) = apply_statement(&statement, database) {
-                        error!(error = %e, statement = ?statement, "Failed to apply statement");
-                        return Err(e);
-                    }
-                }
-                Err(e) => {
-                    let warning = format!("Skipped invalid statement '{}': {}", statement_text, e);
-                    warn!(error = %e, statement = %statement_text, "Failed to parse statement");
-                    add_warning(warning);
-                    skipped_statements.push(statement_text);
-                }
+        for located in extract_located_statements(input) {
+            let Some(statement) = statements.parse(&located, |text| chumsky.parse_statement(text))
+            else {
+                continue;
+            };
+            trace!(statement = ?statement, "Parsing statement");
+            match &statement {
+                Statement::Node(_) => node_count += 1,
+                Statement::Edge(_) => edge_count += 1,
+                _ => {}
+            }
+            if let Err(e) = apply_statement(&statement, database) {
+                error!(error = %e, statement = ?statement, "Failed to apply statement");
+                return Err(e);
             }
         }
         drop(_statements_enter);
 
-        if !skipped_statements.is_empty() {
-            warn!(
-                skipped_count = skipped_statements.len(),
-                skipped_statements = ?skipped_statements,
-                "Skipped invalid statements"
+        let warnings = statements.finish()?;
+        // Styles and classes alone do not make a diagram
+        if node_count == 0 && edge_count == 0 && !warnings.is_empty() {
+            error!("No valid statements parsed");
+            let skipped: Vec<_> = warnings
+                .iter()
+                .filter_map(|warning| warning.statement.as_deref())
+                .collect();
+            let message = format!(
+                "no valid statements found. Invalid syntax: {}",
+                skipped.join(", ")
             );
+            let first = warnings.iter().find_map(|warning| {
+                Some((
+                    warning.line?,
+                    warning.column?,
+                    warning.statement.as_deref()?,
+                ))
+            });
+            return Err(match first {
+                Some((line, column, statement)) => {
+                    FigureheadError::parse("flowchart", line, column, statement, message).into()
+                }
+                None => anyhow::anyhow!("Parse error: {}", message),
+            });
+        }
+        for warning in warnings {
+            database.add_parse_warning(warning);
+        }
 
-            // If we have no valid nodes/edges but had statements to parse, that's an error
-            if node_count == 0 && edge_count == 0 {
-                error!("No valid statements parsed");
-                return Err(anyhow::anyhow!(
-                    "Parse error: no valid statements found. Invalid syntax: {}",
-                    skipped_statements.join(", ")
-                ));
-            }
+        apply_fh_directives(input, database);
+
+        for warning in database.take_id_warnings() {
+            warn!(%warning, "Near-duplicate node IDs");
+            database.add_parse_warning(ParseWarning::new(warning));
         }
 
         info!(node_count, edge_count, "Parsing completed successfully");
@@ -134,56 +315,135 @@ impl Parser<FlowchartDatabase> for FlowchartParser {
     }
 
     fn can_parse(&self, input: &str) -> bool {
-        CONNECTORS.iter().any(|connector| input.contains(connector))
+        super::is_dot(input) || find_next_connector(input, 0).is_some()
     }
 }
 
-fn extract_statements(input: &str) -> Vec<String> {
-    let mut statements = Vec::new();
-    let mut current_subgraph: Vec<String> = Vec::new();
-    let mut in_subgraph = false;
-
-    let normalized_input = normalize_inline_labels(input);
-
-    for line in normalized_input.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() || trimmed.starts_with("%%") {
+/// Apply figurehead-specific `%%fh: ...%%` directives
+///
+/// Mermaid treats these lines as comments, so the same source still renders
+/// there. Supported: `%%fh: hide A, B, A-->C%%` keeps the listed nodes and
+/// edges in the model but leaves them out of layout and rendering.
+fn apply_fh_directives(input: &str, database: &mut FlowchartDatabase) {
+    for (index, line) in input.lines().enumerate() {
+        let Some(body) = line.trim().strip_prefix("%%fh:") else {
             continue;
-        }
-
-        if in_subgraph {
-            current_subgraph.push(trimmed.to_string());
-            if trimmed.eq_ignore_ascii_case("end") {
-                statements.push(current_subgraph.join(" "));
-                current_subgraph.clear();
-                in_subgraph = false;
+        };
+        let directive = DirectiveSource {
+            line: index + 1,
+            column: column_of(input, index + 1, line.trim()),
+            text: line.trim(),
+        };
+        let body = body.trim().trim_end_matches("%%").trim();
+        let (command, args) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+
+        match command {
+            "hide" => {
+                for target in args.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+                    hide_target(target, &directive, database);
+                }
+            }
+            _ => {
+                warn!(%command, "Unknown figurehead directive");
+                directive.warn(
+                    database,
+                    format!("Unknown figurehead directive '{}' ignored", command),
+                );
             }
-            continue;
         }
+    }
+}
 
-        for segment in trimmed.split(';') {
-            let segment = segment.trim();
-            if segment.is_empty() {
-                continue;
-            }
+/// A `%%fh:` directive line, for locating its warnings
+struct DirectiveSource<'a> {
+    line: usize,
+    column: usize,
+    text: &'a str,
+}
 
-            if segment.to_lowercase().starts_with("subgraph") {
-                in_subgraph = true;
-                current_subgraph.push(segment.to_string());
-                break;
-            }
+impl DirectiveSource<'_> {
+    fn warn(&self, database: &mut FlowchartDatabase, message: String) {
+        database.add_parse_warning(
+            ParseWarning::new(message)
+                .at(self.line, self.column)
+                .with_statement(self.text),
+        );
+    }
+}
 
-            if is_graph_declaration(segment) {
-                continue;
-            }
+/// Hide a node (`A`) or the edges between two nodes (`A-->B`)
+fn hide_target(target: &str, directive: &DirectiveSource, database: &mut FlowchartDatabase) {
+    if let Some((pos, connector)) = find_next_connector(target, 0) {
+        let from = target[..pos].trim();
+        let to = target[pos + connector.len()..].trim();
+        if database.edges_between(from, to).is_empty() {
+            directive.warn(database, format!("Cannot hide unknown edge '{}'", target));
+        } else {
+            debug!(%from, %to, "Hiding edge");
+            database.hide_edge(from, to);
+        }
+    } else if database.has_node(target) {
+        debug!(node_id = %target, "Hiding node");
+        database.hide_node(target);
+    } else {
+        directive.warn(database, format!("Cannot hide unknown node '{}'", target));
+    }
+}
 
-            statements.extend(split_chained_edges(segment));
+#[cfg(test)]
+fn extract_statements(input: &str) -> Vec<String> {
+    extract_located_statements(input)
+        .into_iter()
+        .map(|statement| statement.text)
+        .collect()
+}
+
+/// Split the input into flowchart statements, one per edge of a chain
+///
+/// Positions refer to the input as written; inline labels are normalized
+/// per statement afterwards, so they never shift a span.
+fn extract_located_statements(input: &str) -> Vec<SpannedStatement> {
+    let mut statements = Vec::new();
+
+    for statement in StatementSplitter::new()
+        .with_blocks(&["subgraph"])
+        .split(input)
+    {
+        let text = normalize_inline_labels(&statement.text);
+        if text.to_lowercase().starts_with("subgraph") {
+            statements.push(SpannedStatement { text, ..statement });
+            continue;
         }
+        if is_graph_declaration(&text) {
+            continue;
+        }
+
+        statements.extend(
+            split_chained_edges(&text)
+                .into_iter()
+                .map(|text| SpannedStatement {
+                    text,
+                    ..statement.clone()
+                }),
+        );
     }
 
     statements
 }
 
+/// 1-based column where `text` starts on line `line` of the input
+fn column_of(input: &str, line: usize, text: &str) -> usize {
+    input
+        .lines()
+        .nth(line - 1)
+        .and_then(|source| source.find(text).map(|i| source[..i].chars().count() + 1))
+        .unwrap_or(1)
+}
+
+/// Split `A --> B --> C` into one edge statement per connector, expanding
+/// `A & B --> C & D` into an edge for every pair of nodes
+///
+/// A `|label|` after a connector labels every edge expanded from it.
 fn split_chained_edges(statement: &str) -> Vec<String> {
     let trimmed = statement.trim();
     let mut connectors = Vec::new();
@@ -194,7 +454,7 @@ fn split_chained_edges(statement: &str) -> Vec<String> {
         if let Some((pos, conn)) = find_next_connector(trimmed, cursor) {
             let node = trimmed[cursor..pos].trim();
             if !node.is_empty() {
-                nodes.push(node.to_string());
+                nodes.push(node);
             }
             connectors.push(conn);
             cursor = pos + conn.len();
@@ -206,7 +466,7 @@ fn split_chained_edges(statement: &str) -> Vec<String> {
     if cursor <= trimmed.len() {
         let node = trimmed[cursor..].trim();
         if !node.is_empty() {
-            nodes.push(node.to_string());
+            nodes.push(node);
         }
     }
 
@@ -214,24 +474,119 @@ fn split_chained_edges(statement: &str) -> Vec<String> {
         return vec![trimmed.to_string()];
     }
 
+    // A label written after a connector belongs to that connector, not to
+    // the node that follows it
+    let mut labels = vec![""; connectors.len()];
+    for (i, node) in nodes.iter_mut().enumerate().skip(1) {
+        if let Some(end) = node.strip_prefix('|').and_then(|rest| rest.find('|')) {
+            if let Some(label) = labels.get_mut(i - 1) {
+                *label = &node[..end + 2];
+            }
+            *node = node[end + 2..].trim_start();
+        }
+    }
+
     let mut edges = Vec::new();
     for (i, connector) in connectors.iter().enumerate() {
+        // `o--o` and `x--x` need a space to stay apart from the node ID
+        let gap = if connector.starts_with(['o', 'x']) {
+            " "
+        } else {
+            ""
+        };
         if let (Some(from), Some(to)) = (nodes.get(i), nodes.get(i + 1)) {
-            edges.push(format!("{}{}{}", from, connector, to));
+            for from in split_node_list(from) {
+                for to in split_node_list(to) {
+                    edges.push(format!("{}{}{}{}{}", from, gap, connector, labels[i], to));
+                }
+            }
         }
     }
 
     edges
 }
 
-fn find_next_connector(statement: &str, start: usize) -> Option<(usize, &'static str)> {
-    CONNECTORS
-        .iter()
-        .filter_map(|&conn| statement[start..].find(conn).map(|pos| (start + pos, conn)))
-        .min_by(|a, b| match a.0.cmp(&b.0) {
-            Ordering::Equal => b.1.len().cmp(&a.1.len()),
-            other => other,
-        })
+/// Split `A & B[Tom & Jerry]` on the `&`s outside node shapes and quotes
+fn split_node_list(nodes: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in nodes.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '[' | '(' | '{' if !quoted => depth += 1,
+            ']' | ')' | '}' if !quoted => depth = depth.saturating_sub(1),
+            '&' if !quoted && depth == 0 => {
+                parts.push(nodes[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(nodes[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn find_next_connector(statement: &str, start: usize) -> Option<(usize, &str)> {
+    statement[start..]
+        .char_indices()
+        .find_map(|(i, _)| connector_at(statement, start + i).map(|conn| (start + i, conn)))
+}
+
+/// Connector written at `index`, including any extra dashes and a marker
+/// for the source end (`<-->`, `o--o`)
+fn connector_at(statement: &str, index: usize) -> Option<&str> {
+    let rest = &statement[index..];
+    let marker = match rest.as_bytes().first()? {
+        b'<' => 1,
+        // Otherwise the last letter of a node ID
+        b'o' | b'x' if statement[..index].ends_with(char::is_whitespace) => 1,
+        _ => 0,
+    };
+    let len = marker + connector_len(&rest[marker..])?;
+    Some(&rest[..len])
+}
+
+/// Length of the connector `text` starts with
+///
+/// Mirrors the edge grammar: `-->`, `---`, `==>`, `===`, `-.->`, `-.-`,
+/// `--o`, `--x` and `~~~`, where the repeated character may be written
+/// more times (`---->`, `-..->`) to lengthen the edge.
+fn connector_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let run = |from: usize, c: u8| bytes[from..].iter().take_while(|&&b| b == c).count();
+    let at = |i: usize| bytes.get(i).copied();
+    match at(0)? {
+        b'=' => {
+            let n = run(0, b'=');
+            match at(n) {
+                Some(b'>') if n >= 2 => Some(n + 1),
+                _ => (n >= 3).then_some(n),
+            }
+        }
+        b'~' => {
+            let n = run(0, b'~');
+            (n >= 3).then_some(n)
+        }
+        b'-' if at(1) == Some(b'.') => {
+            let dots = run(1, b'.');
+            match (at(1 + dots), at(2 + dots)) {
+                (Some(b'-'), Some(b'>')) => Some(dots + 3),
+                (Some(b'-'), _) => Some(dots + 2),
+                _ => None,
+            }
+        }
+        b'-' => {
+            let n = run(0, b'-');
+            match at(n) {
+                Some(b'>' | b'o' | b'x') if n >= 2 => Some(n + 1),
+                _ => (n >= 3).then_some(n),
+            }
+        }
+        _ => None,
+    }
 }
 
 fn normalize_inline_labels(input: &str) -> String {
@@ -251,9 +606,7 @@ fn normalize_inline_labels(input: &str) -> String {
                     suffix_idx += 1;
                 }
 
-                if let Some(&connector) = CONNECTORS.iter().find(|&&conn| {
-                    suffix_idx + conn.len() <= len && input[suffix_idx..].starts_with(conn)
-                }) {
+                if let Some(connector) = connector_at(input, suffix_idx) {
                     let suffix_end = suffix_idx + connector.len();
                     let mut prefix_idx = i;
                     while prefix_idx > 0 {
@@ -307,19 +660,44 @@ fn apply_statement(statement: &Statement, database: &mut FlowchartDatabase) -> R
             } else {
                 EdgeData::with_type(&edge.from, &edge.to, edge.edge_type)
             };
-            database.add_edge(edge_data)?;
+            database.add_edge(
+                edge_data
+                    .with_min_length(edge.length)
+                    .with_start(edge.start),
+            )?;
         }
         Statement::Subgraph(title, children) => {
             // Collect node IDs from children before applying them
             let member_ids = collect_node_ids(children);
 
             // Apply child statements to add nodes and edges
+            let nested_from = database.subgraph_count();
             for child in children {
                 apply_statement(child, database)?;
             }
 
-            // Register the subgraph with its members
-            database.add_subgraph(title.clone(), member_ids);
+            // Register the subgraph with its members; subgraphs added by the
+            // children and not yet nested anywhere sit directly inside it
+            let id = database.add_subgraph(title.clone(), member_ids);
+            let nested: Vec<String> = database
+                .subgraphs()
+                .skip(nested_from)
+                .filter(|s| s.parent.is_none() && s.id != id)
+                .map(|s| s.id.clone())
+                .collect();
+            for child_id in nested {
+                database.set_subgraph_parent(&child_id, &id);
+            }
+            let direction = children.iter().rev().find_map(|child| match child {
+                Statement::Direction(direction) => Some(*direction),
+                _ => None,
+            });
+            if let Some(direction) = direction {
+                database.set_subgraph_direction(&id, direction);
+            }
+        }
+        Statement::Direction(_) => {
+            // Only meaningful inside a subgraph, where the arm above reads it
         }
         Statement::ClassDef(name, style) => {
             // Define a CSS class
@@ -343,6 +721,12 @@ fn apply_statement(statement: &Statement, database: &mut FlowchartDatabase) -> R
                 database.apply_edge_style(index, style.clone());
             }
         }
+        Statement::Click(node_id, Some(link)) => {
+            database.apply_node_link(node_id, link.clone());
+        }
+        Statement::Click(_, None) => {
+            // Callbacks run script in a browser; there is nothing to show
+        }
     }
 
     Ok(())
@@ -378,7 +762,9 @@ fn collect_node_ids(statements: &[Statement]) -> Vec<String> {
             Statement::ClassDef(_, _)
             | Statement::Style(_, _)
             | Statement::Class(_, _)
-            | Statement::LinkStyle(_, _) => {}
+            | Statement::LinkStyle(_, _)
+            | Statement::Click(_, _)
+            | Statement::Direction(_) => {}
         }
     }
     ids
@@ -422,7 +808,7 @@ fn is_graph_declaration(line: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{Database, Direction, EdgeType, NodeShape};
+    use crate::core::{Database, Direction, EdgeTerminator, EdgeType, NodeShape, Subgraph};
 
     #[test]
     fn test_extract_statements_basics() {
@@ -464,6 +850,26 @@ mod tests {
         assert_eq!(edges, vec!["A-->B", "B-->C", "C-->D"]);
     }
 
+    #[test]
+    fn test_split_chained_edges_expands_ampersands() {
+        let edges = split_chained_edges("A & B-->|ok|C & D[Tom & Jerry]");
+        assert_eq!(
+            edges,
+            vec![
+                "A-->|ok|C",
+                "A-->|ok|D[Tom & Jerry]",
+                "B-->|ok|C",
+                "B-->|ok|D[Tom & Jerry]"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_chained_edges_keeps_labels_on_their_connector() {
+        let edges = split_chained_edges("A-->|x| B-->|y| C");
+        assert_eq!(edges, vec!["A-->|x|B", "B-->|y|C"]);
+    }
+
     #[test]
     fn test_split_chained_edges_prefers_longest_connector() {
         let edges = split_chained_edges("A-.->B-.->C");
@@ -495,6 +901,22 @@ mod tests {
         assert_eq!(database.direction(), Direction::LeftRight);
     }
 
+    #[test]
+    fn test_parser_distributes_label_over_ampersand_targets() {
+        let parser = FlowchartParser::new();
+        let mut database = FlowchartDatabase::new();
+
+        parser
+            .parse("graph TD\n    A --> |ok| B & C{Check}", &mut database)
+            .unwrap();
+        let edges: Vec<_> = database
+            .edges()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.label.as_deref()))
+            .collect();
+        assert_eq!(edges, vec![("A", "B", Some("ok")), ("A", "C", Some("ok"))]);
+        assert_eq!(database.get_node("C").unwrap().shape, NodeShape::Diamond);
+    }
+
     #[test]
     fn test_parser_stores_node_shapes() {
         let parser = FlowchartParser::new();
@@ -605,6 +1027,29 @@ mod tests {
         assert_eq!(database.node_count(), 3);
     }
 
+    #[test]
+    fn test_fh_hide_directive() {
+        let parser = FlowchartParser::new();
+        let mut database = FlowchartDatabase::new();
+
+        let input = r#"graph TD
+            %%fh: hide Debug, A-->C, Missing%%
+            A --> B
+            A --> C
+            B --> Debug"#;
+        parser.parse(input, &mut database).unwrap();
+
+        assert_eq!(database.node_count(), 4);
+        assert!(database.is_node_hidden("Debug"));
+        assert!(!database.is_node_hidden("A"));
+        let edge = database.edges_between("A", "C")[0].clone();
+        assert!(database.is_edge_hidden(&edge));
+        assert_eq!(
+            database.take_warnings(),
+            vec!["Cannot hide unknown node 'Missing'"]
+        );
+    }
+
     #[test]
     fn test_parser_handles_empty_lines() {
         let parser = FlowchartParser::new();
@@ -724,6 +1169,31 @@ mod tests {
         assert_eq!(database.get_node("J").unwrap().shape, NodeShape::Asymmetric);
     }
 
+    #[test]
+    fn test_parser_handles_stadium_double_circle_and_mirrored_shapes() {
+        let parser = FlowchartParser::new();
+        let mut database = FlowchartDatabase::new();
+
+        let input = r#"flowchart LR
+            A([Stadium])
+            B(((Double)))
+            C[\Alt\] --> D[\Inverted/]
+            A --> E([Inline stadium]) --> F(((Inline double)))"#;
+
+        parser.parse(input, &mut database).unwrap();
+        assert_eq!(database.node_count(), 6);
+
+        let shape = |id: &str| database.get_node(id).unwrap().shape;
+        assert_eq!(shape("A"), NodeShape::Stadium);
+        assert_eq!(shape("B"), NodeShape::DoubleCircle);
+        assert_eq!(shape("C"), NodeShape::ParallelogramAlt);
+        assert_eq!(shape("D"), NodeShape::InvTrapezoid);
+        assert_eq!(shape("E"), NodeShape::Stadium);
+        assert_eq!(shape("F"), NodeShape::DoubleCircle);
+        assert_eq!(database.get_node("B").unwrap().label, "Double");
+        assert_eq!(database.get_node("D").unwrap().label, "Inverted");
+    }
+
     #[test]
     fn test_parser_handles_flowchart_keyword() {
         let parser = FlowchartParser::new();
@@ -827,6 +1297,88 @@ mod tests {
         assert_eq!(database.node_count(), 4);
     }
 
+    #[test]
+    fn test_parser_handles_extended_connectors() {
+        let parser = FlowchartParser::new();
+        let mut database = FlowchartDatabase::new();
+
+        let input = r#"graph TD
+            A ---->|far| B -..-> C
+            C ====> D --- E"#;
+
+        parser.parse(input, &mut database).unwrap();
+        let edges: Vec<_> = database
+            .edges()
+            .map(|e| (e.to.as_str(), e.edge_type, e.min_length, e.label.as_deref()))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                ("B", EdgeType::Arrow, 3, Some("far")),
+                ("C", EdgeType::DottedArrow, 2, None),
+                ("D", EdgeType::ThickArrow, 3, None),
+                ("E", EdgeType::Line, 1, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_handles_chained_bidirectional_edges() {
+        let parser = FlowchartParser::new();
+        let mut database = FlowchartDatabase::new();
+
+        parser
+            .parse(
+                "graph LR\n    A <--> B o--o C x--x D\n    Bo --o E",
+                &mut database,
+            )
+            .unwrap();
+        let edges: Vec<_> = database
+            .edges()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.start))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                ("A", "B", EdgeTerminator::Arrow),
+                ("B", "C", EdgeTerminator::Open),
+                ("C", "D", EdgeTerminator::Cross),
+                ("Bo", "E", EdgeTerminator::None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_attaches_click_links_to_nodes() {
+        let parser = FlowchartParser::new();
+        let mut database = FlowchartDatabase::new();
+
+        parser
+            .parse(
+                "graph LR\n    A --> B\n    click A \"https://example.com\" \"Docs\"\n    click B myCallback",
+                &mut database,
+            )
+            .unwrap();
+        let link = database.get_node("A").unwrap().link.as_ref().unwrap();
+        assert_eq!(link.url, "https://example.com");
+        assert_eq!(link.tooltip.as_deref(), Some("Docs"));
+        assert_eq!(database.get_node("B").unwrap().link, None);
+        assert!(database.parse_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_connector_len_matches_edge_grammar() {
+        assert_eq!(connector_len("--> B"), Some(3));
+        assert_eq!(connector_len("-----> B"), Some(6));
+        assert_eq!(connector_len("---o"), Some(4));
+        assert_eq!(connector_len("--o B"), Some(3));
+        assert_eq!(connector_len("-..- B"), Some(4));
+        assert_eq!(connector_len("====>"), Some(5));
+        assert_eq!(connector_len("-- text"), None);
+        assert_eq!(connector_len("== B"), None);
+        assert_eq!(connector_len("~~ B"), None);
+    }
+
     #[test]
     fn test_parser_handles_chained_additional_edge_types() {
         let parser = FlowchartParser::new();
@@ -845,6 +1397,55 @@ mod tests {
         assert_eq!(edges[3].edge_type, EdgeType::ThickLine);
     }
 
+    #[test]
+    fn test_parser_keeps_connector_per_hop_for_every_pair() {
+        let connectors = [
+            ("-.->", EdgeType::DottedArrow),
+            ("==>", EdgeType::ThickArrow),
+            ("===", EdgeType::ThickLine),
+            ("-->", EdgeType::Arrow),
+            ("---", EdgeType::Line),
+            ("-.-", EdgeType::DottedLine),
+            ("--o", EdgeType::OpenArrow),
+            ("--x", EdgeType::CrossArrow),
+            ("~~~", EdgeType::Invisible),
+        ];
+        for (first, first_type) in connectors {
+            for (second, second_type) in connectors {
+                for (spaced, labelled) in [(true, false), (false, false), (true, true)] {
+                    let gap = if spaced { " " } else { "" };
+                    let label = if labelled { "|go|" } else { "" };
+                    let input = format!(
+                        "graph LR\n    A{gap}{first}{label}{gap}B{gap}{second}{label}{gap}C"
+                    );
+                    let mut database = FlowchartDatabase::new();
+                    FlowchartParser::new().parse(&input, &mut database).unwrap();
+
+                    let edges: Vec<_> = database
+                        .edges()
+                        .map(|e| (e.from.as_str(), e.to.as_str(), e.edge_type))
+                        .collect();
+                    assert_eq!(
+                        edges,
+                        vec![("A", "B", first_type), ("B", "C", second_type)],
+                        "{}",
+                        input
+                    );
+                }
+            }
+        }
+
+        let mut database = FlowchartDatabase::new();
+        FlowchartParser::new()
+            .parse("graph LR\n    A --> B -.-> C ==> D", &mut database)
+            .unwrap();
+        let types: Vec<_> = database.edges().map(|e| e.edge_type).collect();
+        assert_eq!(
+            types,
+            vec![EdgeType::Arrow, EdgeType::DottedArrow, EdgeType::ThickArrow]
+        );
+    }
+
     #[test]
     fn test_parser_handles_empty_subgraph() {
         let parser = FlowchartParser::new();
@@ -900,4 +1501,182 @@ mod tests {
         assert_eq!(database.node_subgraph("A").unwrap().id, "subgraph_0");
         assert_eq!(database.node_subgraph("D").unwrap().id, "subgraph_1");
     }
+
+    #[test]
+    fn test_parser_records_nested_subgraph_parents() {
+        let mut database = FlowchartDatabase::new();
+        let input = r#"graph TD
+            subgraph "Cloud"
+                subgraph "Cluster"
+                    subgraph "Pod"
+                        A --> B
+                    end
+                    B --> C
+                end
+                subgraph "Storage"
+                    C --> D
+                end
+                D --> E
+            end
+            E --> F"#;
+        FlowchartParser::new().parse(input, &mut database).unwrap();
+
+        let titles = |chain: Vec<&Subgraph>| -> Vec<String> {
+            chain.into_iter().map(|s| s.title.clone()).collect()
+        };
+        assert_eq!(database.node_subgraph("A").unwrap().title, "Pod");
+        assert_eq!(
+            titles(database.node_subgraphs("A")),
+            ["Pod", "Cluster", "Cloud"]
+        );
+        assert_eq!(titles(database.node_subgraphs("C")), ["Cluster", "Cloud"]);
+        assert_eq!(titles(database.node_subgraphs("D")), ["Storage", "Cloud"]);
+        assert_eq!(titles(database.node_subgraphs("E")), ["Cloud"]);
+        assert!(database.node_subgraphs("F").is_empty());
+
+        let cloud = database.node_subgraph("E").unwrap();
+        assert!(cloud.parent.is_none());
+        let storage = database.node_subgraph("D").unwrap();
+        assert_eq!(database.parent_subgraph(&storage.id).unwrap().id, cloud.id);
+    }
+
+    const CONCATENATED: &str = "graph TD\n    A-->B\n\ngraph LR\n    C-->D\n";
+
+    #[test]
+    fn test_repeated_header_warns_by_default() {
+        let mut database = FlowchartDatabase::new();
+        FlowchartParser::new()
+            .parse(CONCATENATED, &mut database)
+            .unwrap();
+
+        assert_eq!(database.direction(), Direction::TopDown);
+        assert_eq!(database.node_count(), 4);
+        assert_eq!(
+            database.take_warnings(),
+            vec!["Repeated header 'graph LR' on line 4 ignored; keeping 'graph TD'"]
+        );
+    }
+
+    #[test]
+    fn test_repeated_header_error_policy() {
+        let parser = FlowchartParser::new().with_repeated_headers(RepeatedHeaders::Error);
+        let mut database = FlowchartDatabase::new();
+        let err = parser.parse(CONCATENATED, &mut database).unwrap_err();
+        assert!(err.to_string().contains("line 4"));
+    }
+
+    #[test]
+    fn test_repeated_header_split_policy_parses_first_diagram() {
+        let parser = FlowchartParser::new().with_repeated_headers(RepeatedHeaders::Split);
+        let mut database = FlowchartDatabase::new();
+        parser.parse(CONCATENATED, &mut database).unwrap();
+
+        assert!(database.has_node("B"));
+        assert!(!database.has_node("C"));
+        assert!(database.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_id_case_near_duplicates() {
+        let input = "graph TD\n    Start[Begin] --> B\n    start --> C\n    style START fill:#f9f";
+        let mut database = FlowchartDatabase::new();
+        FlowchartParser::new().parse(input, &mut database).unwrap();
+        assert_eq!(database.node_count(), 4);
+        assert_eq!(
+            database.take_warnings(),
+            vec!["IDs 'Start' and 'start' differ only in case; treating them as different"]
+        );
+        let mut database = FlowchartDatabase::new();
+        FlowchartParser::new()
+            .with_id_case(IdCase::Insensitive)
+            .parse(input, &mut database)
+            .unwrap();
+        assert_eq!(database.node_count(), 3);
+        assert_eq!(database.successors("Start"), vec!["B", "C"]);
+        assert_eq!(database.get_node("start").unwrap().label, "Begin");
+        assert!(database.resolve_node_style("START").is_some());
+        assert!(database.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_split_diagrams_keeps_preamble_with_first() {
+        let input = "%% notes\nflowchart LR; A-->B\ngraph TD\n  C-->D";
+        assert_eq!(
+            split_diagrams(input),
+            vec!["%% notes\nflowchart LR; A-->B\n", "graph TD\n  C-->D"]
+        );
+        assert_eq!(split_diagrams("graph TD\nA-->B"), vec!["graph TD\nA-->B"]);
+    }
+
+    #[test]
+    fn test_repeated_headers_from_str() {
+        for policy in RepeatedHeaders::ALL {
+            assert_eq!(policy.to_string().parse::<RepeatedHeaders>(), Ok(policy));
+        }
+        assert!("strict".parse::<RepeatedHeaders>().is_err());
+    }
+
+    #[test]
+    fn test_skipped_statement_warning_has_position() {
+        let mut database = FlowchartDatabase::new();
+        FlowchartParser::new()
+            .parse("graph TD\n    A-->B\n    C-->D; E[[[oops\n", &mut database)
+            .unwrap();
+
+        let warnings = database.take_parse_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].line, Some(3));
+        assert_eq!(warnings[0].column, Some(12));
+        assert_eq!(warnings[0].statement.as_deref(), Some("E[[[oops"));
+        assert!(warnings[0]
+            .to_string()
+            .starts_with("3:12: Skipped invalid statement"));
+    }
+
+    #[test]
+    fn test_statement_positions_survive_inline_label_rewrites() {
+        let input =
+            "graph TD\n  A--|yes|-->B; C--|no|---D\n  subgraph \"S\"\n    E\n  end\n  F-->G";
+        let statements = extract_located_statements(input);
+        let found: Vec<(&str, usize, usize)> = statements
+            .iter()
+            .map(|s| (s.text.as_str(), s.line, s.column))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("A-->|yes|B", 2, 3),
+                ("C---|no|D", 2, 17),
+                ("subgraph \"S\" E end", 3, 3),
+                ("F-->G", 6, 3),
+            ]
+        );
+        assert_eq!(&input[statements[1].span.clone()], "C--|no|---D");
+    }
+
+    #[test]
+    fn test_directive_warning_points_at_directive_line() {
+        let mut database = FlowchartDatabase::new();
+        FlowchartParser::new()
+            .parse("graph TD\n  A-->B\n  %%fh: hide Z%%", &mut database)
+            .unwrap();
+
+        let warnings = database.take_parse_warnings();
+        assert_eq!(warnings[0].message, "Cannot hide unknown node 'Z'");
+        assert_eq!((warnings[0].line, warnings[0].column), (Some(3), Some(3)));
+        assert_eq!(warnings[0].statement.as_deref(), Some("%%fh: hide Z%%"));
+    }
+
+    #[test]
+    fn test_copy_paste_artifacts_parse_without_warnings() {
+        let input = "\u{feff}graph LR;\r\n  subgraph \"Web\";\r\n    A[Start]\u{a0}-->\u{a0}B;\r\n  end;\r\n  B --> C;\r\n";
+        let mut database = FlowchartDatabase::new();
+        FlowchartParser::new().parse(input, &mut database).unwrap();
+
+        assert!(database.take_parse_warnings().is_empty());
+        assert_eq!(database.direction(), Direction::LeftRight);
+        assert_eq!(database.get_node("A").unwrap().label, "Start");
+        assert_eq!(database.node_subgraph("B").unwrap().title, "Web");
+        assert_eq!(database.edge_count(), 2);
+    }
 }
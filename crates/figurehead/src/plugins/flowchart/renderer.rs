@@ -3,22 +3,172 @@
 //! Converts positioned nodes into ASCII diagrams using various character sets.
 
 use anyhow::Result;
-use tracing::{debug, info, span, trace, Level};
+use tracing::{debug, info, span, trace, warn, Level};
 
-use super::{FlowchartDatabase, FlowchartLayoutAlgorithm, PositionedNode, PositionedSubgraph};
+use super::sizing;
+use super::{
+    EdgeRegion, FlowchartDatabase, FlowchartLayoutAlgorithm, FlowchartLayoutResult, LayoutConfig,
+    NodeRegion, PositionedEdge, PositionedNode, PositionedSubgraph, RenderedFlowchart,
+};
+use crate::core::footer::visible_width;
 use crate::core::{
-    wrap_label, AsciiCanvas, BoxChars, CharacterSet, Database, DiamondStyle, EdgeType,
-    LayoutAlgorithm, NodeShape, Renderer,
+    mirror_text, reweigh_canvas, wrap_label, AsciiCanvas, AspectRatio, BoxChars, CanvasLimit,
+    CanvasMode, CellBlock, CharacterSet, Database, DiamondStyle, Direction, EdgeCurve,
+    EdgeTerminator, EdgeType, Footer, FooterSection, LabelPlacement, LayoutAlgorithm, LineWeight,
+    MirrorAxis, NodeShape, OverflowPolicy, Point, Rect, Renderer, Segment, SplitLabels, Theme,
 };
+use crate::plugins::observer::LayoutProbe;
+use std::collections::{HashMap, HashSet};
 
 /// Flowchart ASCII renderer
+#[derive(Debug, Clone)]
 pub struct FlowchartRenderer {
     style: CharacterSet,
     diamond_style: DiamondStyle,
+    label_placement: LabelPlacement,
+    split_labels: SplitLabels,
+    canvas_mode: CanvasMode,
+    rounded_edges: bool,
+    edge_curve: EdgeCurve,
+    line_weight: LineWeight,
+    mirror_reversed: bool,
+    overflow_policy: OverflowPolicy,
+    overflow_limit: CanvasLimit,
+    layout_config: LayoutConfig,
+    color: bool,
+    theme: Option<Theme>,
+    max_width: Option<usize>,
+    aspect_hint: Option<AspectRatio>,
+    link_footnotes: bool,
+    debug_overlay: bool,
 }
 
+/// Widest offset across the flow that a [`EdgeCurve::Linear`] edge cuts
+/// across diagonally; wider ones are routed with turns
+const MAX_DIAGONAL: usize = 3;
+
 /// Max label width before wrapping (must match layout config)
-const MAX_LABEL_WIDTH: usize = 30;
+pub(super) const MAX_LABEL_WIDTH: usize = 30;
+
+/// Cache key for a pre-rendered node: everything that affects its glyphs
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    shape: NodeShape,
+    width: usize,
+    height: usize,
+    label: String,
+    style: CharacterSet,
+    diamond_style: DiamondStyle,
+}
+
+/// Where an edge label is drawn
+///
+/// Edges that end up on the same slot have their labels joined (`Yes / No`)
+/// and drawn once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LabelSlot {
+    /// Label sits on the edge's own path
+    Edge { from: String, to: String },
+    /// Label sits on the segment shared by edges merging at this junction
+    Merge(Point),
+    /// Label sits on the trunk shared by edges splitting at this junction
+    Split(Point),
+}
+
+/// Labels sharing a slot, tagged with their edge's declaration index
+type SlotLabels = Vec<(usize, String)>;
+
+/// Label wrap width when condensing an oversized diagram
+const CONDENSED_LABEL_WIDTH: usize = 12;
+
+/// How far a label may move to get clear of junctions, nodes and labels
+const MAX_LABEL_SHIFT: usize = 3;
+
+/// Direction a label may be shifted in
+#[derive(Debug, Clone, Copy)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// Cells edge labels must not cover besides junction glyphs
+struct LabelObstacles<'a> {
+    nodes: &'a [PositionedNode],
+    /// Labels drawn so far, one row high
+    labels: Vec<Rect>,
+}
+
+impl LabelObstacles<'_> {
+    fn covers(&self, point: Point) -> bool {
+        self.nodes.iter().any(|n| n.rect().contains(point))
+            || self.labels.iter().any(|label| label.contains(point))
+    }
+}
+
+/// Glyphs that join or end lines; a label drawn over one breaks the edge
+fn is_junction_glyph(c: char) -> bool {
+    matches!(
+        c,
+        '┌' | '┐'
+            | '└'
+            | '┘'
+            | '├'
+            | '┤'
+            | '┬'
+            | '┴'
+            | '┼'
+            | '╭'
+            | '╮'
+            | '╰'
+            | '╯'
+            | '+'
+            | '▼'
+            | '▲'
+            | '▶'
+            | '◀'
+            | '▽'
+            | '△'
+            | '▷'
+            | '◁'
+            | '○'
+            | '×'
+            | '◆'
+            | '◇'
+            | 'v'
+            | '^'
+            | '>'
+            | '<'
+    )
+}
+
+impl LabelSlot {
+    fn for_edge(edge: &PositionedEdge) -> Self {
+        match edge.merge_junction {
+            Some(junction) => LabelSlot::Merge(junction),
+            None => LabelSlot::Edge {
+                from: edge.from_id.clone(),
+                to: edge.to_id.clone(),
+            },
+        }
+    }
+}
+
+/// Per-render cache of node cell blocks
+///
+/// Generated diagrams often contain many nodes with the same shape, size and
+/// label. Each distinct node is drawn once into a [`CellBlock`] and then
+/// blitted at every position it appears.
+#[derive(Debug, Default)]
+struct NodeGlyphCache {
+    blocks: HashMap<GlyphKey, CellBlock>,
+    hits: usize,
+}
+
+impl NodeGlyphCache {
+    fn len(&self) -> usize {
+        self.blocks.len()
+    }
+}
 
 impl FlowchartRenderer {
     /// Create a new renderer with default Unicode style and Box diamond
@@ -26,6 +176,22 @@ impl FlowchartRenderer {
         Self {
             style: CharacterSet::Unicode,
             diamond_style: DiamondStyle::Box,
+            label_placement: LabelPlacement::Auto,
+            split_labels: SplitLabels::Shared,
+            canvas_mode: CanvasMode::Text,
+            rounded_edges: false,
+            edge_curve: EdgeCurve::Step,
+            line_weight: LineWeight::Light,
+            mirror_reversed: false,
+            overflow_policy: OverflowPolicy::Compact,
+            overflow_limit: CanvasLimit::default(),
+            layout_config: LayoutConfig::default(),
+            color: false,
+            theme: None,
+            max_width: None,
+            aspect_hint: None,
+            link_footnotes: false,
+            debug_overlay: false,
         }
     }
 
@@ -34,6 +200,22 @@ impl FlowchartRenderer {
         Self {
             style,
             diamond_style: DiamondStyle::Box,
+            label_placement: LabelPlacement::Auto,
+            split_labels: SplitLabels::Shared,
+            canvas_mode: CanvasMode::Text,
+            rounded_edges: false,
+            edge_curve: EdgeCurve::Step,
+            line_weight: LineWeight::Light,
+            mirror_reversed: false,
+            overflow_policy: OverflowPolicy::Compact,
+            overflow_limit: CanvasLimit::default(),
+            layout_config: LayoutConfig::default(),
+            color: false,
+            theme: None,
+            max_width: None,
+            aspect_hint: None,
+            link_footnotes: false,
+            debug_overlay: false,
         }
     }
 
@@ -42,6 +224,22 @@ impl FlowchartRenderer {
         Self {
             style,
             diamond_style,
+            label_placement: LabelPlacement::Auto,
+            split_labels: SplitLabels::Shared,
+            canvas_mode: CanvasMode::Text,
+            rounded_edges: false,
+            edge_curve: EdgeCurve::Step,
+            line_weight: LineWeight::Light,
+            mirror_reversed: false,
+            overflow_policy: OverflowPolicy::Compact,
+            overflow_limit: CanvasLimit::default(),
+            layout_config: LayoutConfig::default(),
+            color: false,
+            theme: None,
+            max_width: None,
+            aspect_hint: None,
+            link_footnotes: false,
+            debug_overlay: false,
         }
     }
 
@@ -50,9 +248,141 @@ impl FlowchartRenderer {
         Self {
             style: config.style,
             diamond_style: config.diamond_style,
+            label_placement: config.label_placement,
+            split_labels: config.split_labels,
+            canvas_mode: config.canvas_mode,
+            rounded_edges: config.rounded_edges,
+            edge_curve: config.edge_curve,
+            line_weight: config.line_weight,
+            mirror_reversed: config.mirror_reversed,
+            overflow_policy: config.overflow_policy,
+            overflow_limit: config.overflow_limit,
+            layout_config: LayoutConfig::default(),
+            color: config.color,
+            theme: config.theme,
+            max_width: config.max_width,
+            aspect_hint: config.aspect_hint,
+            link_footnotes: config.link_footnotes,
+            debug_overlay: config.debug_overlay,
         }
     }
 
+    /// Set the edge label placement strategy
+    pub fn with_label_placement(mut self, placement: LabelPlacement) -> Self {
+        self.label_placement = placement;
+        self
+    }
+
+    /// Set the canvas used for shapes and edges
+    pub fn with_canvas_mode(mut self, mode: CanvasMode) -> Self {
+        self.canvas_mode = mode;
+        self
+    }
+
+    /// Draw edge turns with rounded corners (`╭ ╮ ╰ ╯`)
+    pub fn with_rounded_edges(mut self, rounded: bool) -> Self {
+        self.rounded_edges = rounded;
+        self
+    }
+
+    /// Draw short hops between offset nodes diagonally ([`EdgeCurve::Linear`])
+    /// or with right-angle turns
+    pub fn with_edge_curve(mut self, curve: EdgeCurve) -> Self {
+        self.edge_curve = curve;
+        self
+    }
+
+    /// Color nodes and edges without a `classDef` or `style` with `theme`
+    /// when color is on
+    pub fn with_theme(mut self, theme: Option<Theme>) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Draw nodes and edges with light, heavy or double lines
+    ///
+    /// See [`LineWeight`] for how subgraphs and thick edges follow along.
+    pub fn with_line_weight(mut self, weight: LineWeight) -> Self {
+        self.line_weight = weight;
+        self
+    }
+
+    /// Draw a label shared by every branch of a split once, or per branch
+    pub fn with_split_labels(mut self, split_labels: SplitLabels) -> Self {
+        self.split_labels = split_labels;
+        self
+    }
+
+    /// Draw BT and RL diagrams by rendering TD or LR and mirroring the canvas
+    ///
+    /// Reversed flows then route exactly like forward ones, with arrows,
+    /// corners and junctions flipped. Only applies to the text canvas.
+    pub fn with_mirror_reversed(mut self, mirror: bool) -> Self {
+        self.mirror_reversed = mirror;
+        self
+    }
+
+    /// Choose what happens when the layout is larger than `limit`
+    pub fn with_overflow(mut self, policy: OverflowPolicy, limit: CanvasLimit) -> Self {
+        self.overflow_policy = policy;
+        self.overflow_limit = limit;
+        self
+    }
+
+    /// Color styled nodes with ANSI 24-bit escapes (from `classDef`/`style`)
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Fit the drawing into `max_width` columns (`None` = no limit)
+    ///
+    /// Wider layouts are redrawn with wrapped layers, then condensed labels,
+    /// then the compact character set, stopping at the first that fits.
+    pub fn with_max_width(mut self, max_width: Option<usize>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Lay the drawing out close to `aspect` (`None` = lay out as usual)
+    ///
+    /// Layers are wrapped onto extra rows when that brings the proportions
+    /// closer; a fixed [`LayoutConfig::max_layer_nodes`] is kept as it is.
+    pub fn with_aspect_hint(mut self, aspect: Option<AspectRatio>) -> Self {
+        self.aspect_hint = aspect;
+        self
+    }
+
+    /// Mark linked nodes with `[n]` and list the links below the drawing
+    ///
+    /// Links come from `click` statements; diagrams without any are drawn
+    /// as usual.
+    pub fn with_link_footnotes(mut self, footnotes: bool) -> Self {
+        self.link_footnotes = footnotes;
+        self
+    }
+
+    /// Draw the layout debug overlay over the diagram
+    ///
+    /// See [`RenderConfig::debug_overlay`](crate::core::RenderConfig::debug_overlay).
+    /// Reversed flows are drawn natively rather than mirrored, so the
+    /// overlay shows the layout that was actually computed.
+    pub fn with_debug_overlay(mut self, overlay: bool) -> Self {
+        self.debug_overlay = overlay;
+        self
+    }
+
+    /// Set the layout configuration used when rendering
+    pub fn with_layout_config(mut self, config: LayoutConfig) -> Self {
+        self.layout_config = config;
+        self
+    }
+
+    /// Get the layout configuration used when rendering
+    pub fn layout_config(&self) -> &LayoutConfig {
+        &self.layout_config
+    }
+
     /// Get the current character set
     pub fn style(&self) -> CharacterSet {
         self.style
@@ -63,6 +393,141 @@ impl FlowchartRenderer {
         self.diamond_style
     }
 
+    /// Get the current edge label placement strategy
+    pub fn label_placement(&self) -> LabelPlacement {
+        self.label_placement
+    }
+
+    /// Get the current canvas mode
+    pub fn canvas_mode(&self) -> CanvasMode {
+        self.canvas_mode
+    }
+
+    /// How labels shared by every branch of a split are drawn
+    pub fn split_labels(&self) -> SplitLabels {
+        self.split_labels
+    }
+
+    /// Whether edge turns are drawn with rounded corners
+    pub fn rounded_edges(&self) -> bool {
+        self.rounded_edges
+    }
+
+    /// How edges between offset nodes are drawn
+    pub fn edge_curve(&self) -> EdgeCurve {
+        self.edge_curve
+    }
+
+    /// Weight of the lines drawing nodes and edges
+    pub fn line_weight(&self) -> LineWeight {
+        self.line_weight
+    }
+
+    /// Whether BT and RL diagrams are drawn by mirroring TD and LR ones
+    pub fn mirror_reversed(&self) -> bool {
+        self.mirror_reversed
+    }
+
+    /// Whether styled nodes are colored with ANSI escapes
+    pub fn color(&self) -> bool {
+        self.color
+    }
+
+    /// Colors for nodes and edges without a `classDef` or `style`
+    pub fn theme(&self) -> Option<Theme> {
+        self.theme
+    }
+
+    /// Widest output allowed, in columns
+    pub fn max_width(&self) -> Option<usize> {
+        self.max_width
+    }
+
+    /// Proportions the layout is steered towards
+    pub fn aspect_hint(&self) -> Option<AspectRatio> {
+        self.aspect_hint
+    }
+
+    /// Whether the layout debug overlay is drawn
+    pub fn debug_overlay(&self) -> bool {
+        self.debug_overlay
+    }
+
+    /// Glyph for an edge turn, swapped for its rounded form when enabled
+    fn edge_corner(&self, corner: char) -> char {
+        if !self.rounded_edges {
+            return corner;
+        }
+        match corner {
+            '┌' => '╭',
+            '┐' => '╮',
+            '└' => '╰',
+            '┘' => '╯',
+            other => other,
+        }
+    }
+
+    /// Empty cells between a node's bounding box and its visible outline, in
+    /// the middle of the side facing `side`
+    ///
+    /// Most shapes fill their box edge to edge. Inline diamonds only draw
+    /// their label row, and parallelograms and trapezoids slant away from the
+    /// box at their middle row.
+    fn outline_inset(&self, shape: NodeShape, node: &PositionedNode, side: Direction) -> usize {
+        let vertical = matches!(side, Direction::TopDown | Direction::BottomUp);
+        let h = node.height;
+        match shape {
+            NodeShape::Diamond if vertical && self.diamond_style == DiamondStyle::Inline => h / 2,
+            NodeShape::Parallelogram | NodeShape::ParallelogramAlt if !vertical && h > 2 => {
+                (h / 2) % 2
+            }
+            NodeShape::Trapezoid | NodeShape::InvTrapezoid if !vertical && h > 2 => {
+                (h / 2).min(node.width.min(4) / 2)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Move an edge endpoint from a node's bounding box onto its outline
+    ///
+    /// `point` is where a route leaves or enters the node. When it is the
+    /// attachment point of a side whose outline is inset, it moves inward so
+    /// the line starts just outside the outline; with `arrow`, the point is
+    /// the one the arrowhead is drawn in front of, so it lands on the outline
+    /// itself. Other points are returned unchanged.
+    fn attach_to_outline(
+        &self,
+        point: Point,
+        node: &PositionedNode,
+        shape: NodeShape,
+        arrow: bool,
+    ) -> Point {
+        let rect = node.rect();
+        let center = rect.center();
+        let (side, border) = if point.x == center.x && point.y <= rect.y {
+            (Direction::BottomUp, Point::new(center.x, rect.y))
+        } else if point.x == center.x && point.y + 1 >= rect.bottom() {
+            (Direction::TopDown, Point::new(center.x, rect.bottom() - 1))
+        } else if point.y == center.y && point.x <= rect.x {
+            (Direction::RightLeft, Point::new(rect.x, center.y))
+        } else if point.y == center.y && point.x + 1 >= rect.right() {
+            (Direction::LeftRight, Point::new(rect.right() - 1, center.y))
+        } else {
+            return point;
+        };
+
+        let inset = self.outline_inset(shape, node, side);
+        if inset == 0 {
+            return point;
+        }
+        let outline = border.step(side, -(inset as isize));
+        if arrow {
+            outline
+        } else {
+            outline.step(side, 1)
+        }
+    }
+
     fn draw_node(
         &self,
         canvas: &mut AsciiCanvas,
@@ -78,14 +543,60 @@ impl FlowchartRenderer {
             NodeShape::RoundedRect => {
                 self.draw_rectangle(canvas, node, label, BoxChars::rounded(self.style))
             }
+            NodeShape::Stadium => self.draw_stadium(canvas, node, label),
             NodeShape::Diamond => self.draw_diamond(canvas, node, label),
-            NodeShape::Circle | NodeShape::Terminal => self.draw_circle(canvas, node, label),
+            NodeShape::Circle | NodeShape::DoubleCircle | NodeShape::Terminal => {
+                self.draw_circle(canvas, node, label, shape)
+            }
             NodeShape::Hexagon => self.draw_hexagon(canvas, node, label),
             NodeShape::Asymmetric => self.draw_asymmetric(canvas, node, label),
             NodeShape::Cylinder => self.draw_cylinder(canvas, node, label),
-            NodeShape::Parallelogram => self.draw_parallelogram(canvas, node, label),
-            NodeShape::Trapezoid => self.draw_trapezoid(canvas, node, label),
+            NodeShape::Parallelogram => self.draw_parallelogram(canvas, node, label, false),
+            NodeShape::ParallelogramAlt => self.draw_parallelogram(canvas, node, label, true),
+            NodeShape::Trapezoid => self.draw_trapezoid(canvas, node, label, false),
+            NodeShape::InvTrapezoid => self.draw_trapezoid(canvas, node, label, true),
+        }
+    }
+
+    /// Draw a node through the glyph cache, rendering it only on first use
+    fn draw_node_cached(
+        &self,
+        cache: &mut NodeGlyphCache,
+        canvas: &mut AsciiCanvas,
+        node: &PositionedNode,
+        shape: NodeShape,
+        label: &str,
+    ) {
+        let key = GlyphKey {
+            shape,
+            width: node.width,
+            height: node.height,
+            label: label.to_string(),
+            style: self.style,
+            diamond_style: self.diamond_style,
+        };
+
+        if let Some(block) = cache.blocks.get(&key) {
+            cache.hits += 1;
+            canvas.blit(node.x, node.y, block);
+            return;
         }
+
+        // Draw at the origin; labels may overhang the right edge by their width
+        let origin = PositionedNode {
+            id: node.id.clone(),
+            x: 0,
+            y: 0,
+            width: node.width,
+            height: node.height,
+        };
+        let block = CellBlock::capture(
+            node.width + label.chars().count() + 2,
+            node.height + 1,
+            |scratch| self.draw_node(scratch, &origin, shape, label),
+        );
+        canvas.blit(node.x, node.y, &block);
+        cache.blocks.insert(key, block);
     }
 
     /// Draw a subgraph boundary with centered title
@@ -243,7 +754,7 @@ impl FlowchartRenderer {
         }
 
         // Wrap and draw label(s) centered vertically and horizontally
-        let lines = wrap_label(label, MAX_LABEL_WIDTH);
+        let lines = wrap_label(label, self.layout_config.max_label_width);
         let total_lines = lines.len();
         let start_y = y + (h.saturating_sub(total_lines)) / 2;
 
@@ -279,45 +790,57 @@ impl FlowchartRenderer {
         }
     }
 
+    /// Rounded box with parenthesis sides: `( label )`
+    fn draw_stadium(&self, canvas: &mut AsciiCanvas, node: &PositionedNode, label: &str) {
+        self.draw_rectangle(canvas, node, label, BoxChars::rounded(self.style));
+        for row in node.y + 1..node.y + node.height - 1 {
+            canvas.set_char(node.x, row, '(');
+            canvas.set_char(node.x + node.width - 1, row, ')');
+        }
+    }
+
     fn draw_hexagon(&self, canvas: &mut AsciiCanvas, node: &PositionedNode, label: &str) {
         let x = node.x;
         let y = node.y;
         let w = node.width;
         let h = node.height;
-
-        // Top slant
-        if w > 2 {
-            canvas.set_char(x + 1, y, '/');
-            for i in x + 2..x + w - 2 {
-                canvas.set_char(i, y, '-');
-            }
-            canvas.set_char(x + w - 2, y, '\\');
-        }
-
-        // Middle
         let mid_y = y + h / 2;
-        canvas.set_char(x, mid_y, '<');
-        canvas.set_char(x + w - 1, mid_y, '>');
-        let label_x = x + (w.saturating_sub(label.len())) / 2;
-        canvas.draw_text(label_x.max(x + 1), mid_y, label);
 
-        // Upper and lower slants
-        for row in 1..h - 1 {
+        for row in 0..h {
             let current_y = y + row;
+            let inset = sizing::slant_inset(h, row);
+            let (left, right) = (x + inset, x + w - 1 - inset);
             if current_y == mid_y {
+                canvas.set_char(left, current_y, '<');
+                canvas.set_char(right, current_y, '>');
                 continue;
             }
-            canvas.set_char(x, current_y, '/');
-            canvas.set_char(x + w - 1, current_y, '\\');
+            let upper = current_y < mid_y;
+            canvas.set_char(left, current_y, if upper { '/' } else { '\\' });
+            canvas.set_char(right, current_y, if upper { '\\' } else { '/' });
+            if row == 0 || row == h - 1 {
+                for i in left + 1..right {
+                    canvas.set_char(i, current_y, '-');
+                }
+            }
         }
 
-        // Bottom slant
-        if w > 2 {
-            canvas.set_char(x + 1, y + h - 1, '\\');
-            for i in x + 2..x + w - 2 {
-                canvas.set_char(i, y + h - 1, '-');
-            }
-            canvas.set_char(x + w - 2, y + h - 1, '/');
+        let lines = sizing::label_lines(
+            label,
+            NodeShape::Hexagon,
+            self.layout_config.max_label_width,
+        );
+        self.draw_label_lines(canvas, node, &lines);
+    }
+
+    /// Draw wrapped label lines centered in a node, inside its outline rows
+    fn draw_label_lines(&self, canvas: &mut AsciiCanvas, node: &PositionedNode, lines: &[String]) {
+        use unicode_width::UnicodeWidthStr;
+
+        let start_y = node.y + node.height.saturating_sub(lines.len()) / 2;
+        for (i, line) in lines.iter().enumerate() {
+            let label_x = node.x + node.width.saturating_sub(line.width()) / 2;
+            canvas.draw_text(label_x.max(node.x + 1), start_y + i, line);
         }
     }
 
@@ -371,11 +894,19 @@ impl FlowchartRenderer {
         canvas.set_char(x + w - 1, y + h - 1, bottom_right);
     }
 
-    fn draw_parallelogram(&self, canvas: &mut AsciiCanvas, node: &PositionedNode, label: &str) {
+    /// Slanted box; `mirrored` leans it the other way, as in `A[\label\]`
+    fn draw_parallelogram(
+        &self,
+        canvas: &mut AsciiCanvas,
+        node: &PositionedNode,
+        label: &str,
+        mirrored: bool,
+    ) {
         let x = node.x;
         let y = node.y;
         let w = node.width;
         let h = node.height;
+        let (forward, back) = if mirrored { ('\\', '/') } else { ('/', '\\') };
 
         for row in 0..h {
             let row_y = y + row;
@@ -385,22 +916,22 @@ impl FlowchartRenderer {
 
             // Fill top/bottom with angled ends
             if row == 0 {
-                canvas.set_char(left_x, row_y, '/');
+                canvas.set_char(left_x, row_y, forward);
                 for i in left_x + 1..right_x {
                     let line = if self.style.is_ascii() { '-' } else { '─' };
                     canvas.set_char(i, row_y, line);
                 }
-                canvas.set_char(right_x, row_y, '/');
+                canvas.set_char(right_x, row_y, forward);
             } else if row == h - 1 {
-                canvas.set_char(left_x, row_y, '\\');
+                canvas.set_char(left_x, row_y, back);
                 for i in left_x + 1..right_x {
                     let line = if self.style.is_ascii() { '-' } else { '─' };
                     canvas.set_char(i, row_y, line);
                 }
-                canvas.set_char(right_x, row_y, '\\');
+                canvas.set_char(right_x, row_y, back);
             } else {
-                canvas.set_char(left_x, row_y, '/');
-                canvas.set_char(right_x, row_y, '/');
+                canvas.set_char(left_x, row_y, forward);
+                canvas.set_char(right_x, row_y, forward);
             }
 
             if row == h / 2 {
@@ -410,45 +941,69 @@ impl FlowchartRenderer {
         }
     }
 
-    fn draw_trapezoid(&self, canvas: &mut AsciiCanvas, node: &PositionedNode, label: &str) {
+    /// Box narrowing towards the top; `inverted` flips it upside down, as in
+    /// `A[\label/]`
+    fn draw_trapezoid(
+        &self,
+        canvas: &mut AsciiCanvas,
+        node: &PositionedNode,
+        label: &str,
+        inverted: bool,
+    ) {
         let x = node.x;
-        let y = node.y;
         let w = node.width;
         let h = node.height;
+        // Screen row of `row` counted from the narrow edge
+        let row_y = |row: usize| {
+            if inverted {
+                node.y + h - 1 - row
+            } else {
+                node.y + row
+            }
+        };
+        let (rising, falling) = if inverted { ('\\', '/') } else { ('/', '\\') };
 
         let top_padding = w.min(4) / 2;
         let span_char = if self.style.is_ascii() { '-' } else { '⠒' };
 
-        // Top edge narrower
+        // Narrow edge
         let top_left = x + top_padding;
         let top_right = x + w - 1 - top_padding;
-        canvas.set_char(top_left, y, '/');
+        canvas.set_char(top_left, row_y(0), rising);
         for i in top_left + 1..top_right {
-            canvas.set_char(i, y, span_char);
+            canvas.set_char(i, row_y(0), span_char);
         }
-        canvas.set_char(top_right, y, '\\');
+        canvas.set_char(top_right, row_y(0), falling);
 
         // Sides
         for row in 1..h - 1 {
             let left_x = x + row.min(top_padding);
             let right_x = x + w - 1 - row.min(top_padding);
-            canvas.set_char(left_x, y + row, '/');
-            canvas.set_char(right_x, y + row, '\\');
+            canvas.set_char(left_x, row_y(row), rising);
+            canvas.set_char(right_x, row_y(row), falling);
             if row == h / 2 {
                 let label_x = left_x + (right_x.saturating_sub(left_x) + 1 - label.len()) / 2;
-                canvas.draw_text(label_x.max(left_x + 1), y + row, label);
+                canvas.draw_text(label_x.max(left_x + 1), row_y(row), label);
             }
         }
 
         // Base
-        let base_left = if self.style.is_ascii() { '+' } else { '└' };
-        let base_right = if self.style.is_ascii() { '+' } else { '┘' };
-        canvas.set_char(x, y + h - 1, base_left);
+        let base_left = match (self.style.is_ascii(), inverted) {
+            (true, _) => '+',
+            (false, false) => '└',
+            (false, true) => '┌',
+        };
+        let base_right = match (self.style.is_ascii(), inverted) {
+            (true, _) => '+',
+            (false, false) => '┘',
+            (false, true) => '┐',
+        };
+        canvas.set_char(x, row_y(h - 1), base_left);
         for i in x + 1..x + w - 1 {
             let line = if self.style.is_ascii() { '-' } else { '─' };
-            canvas.set_char(i, y + h - 1, line);
+            canvas.set_char(i, row_y(h - 1), line);
         }
-        canvas.set_char(x + w - 1, y + h - 1, base_right);
+        canvas.set_char(x + w - 1, row_y(h - 1), base_right);
     }
 
     fn draw_diamond(&self, canvas: &mut AsciiCanvas, node: &PositionedNode, label: &str) {
@@ -591,86 +1146,59 @@ impl FlowchartRenderer {
                     }
                 } else {
                     // Tall: full staggered diagonals
-                    let half_h = h / 2;
-
-                    // Top point
-                    canvas.set_char(center_x, y, '⟋');
-                    canvas.set_char(center_x + 1, y, '⟍');
-
-                    // Upper expanding rows - stagger 2 chars per row for shallower angle
-                    for row in 1..half_h {
-                        let offset = row * 2;
-                        let left_x = center_x.saturating_sub(offset);
-                        let right_x = center_x + 1 + offset;
-                        canvas.set_char(left_x, y + row, '⟋');
-                        canvas.set_char(right_x, y + row, '⟍');
-                    }
-
-                    // Middle row with brackets and label
-                    canvas.set_char(x, mid_y, '⧼');
-                    canvas.set_char(x + w - 1, mid_y, '⧽');
+                    self.draw_diamond_outline(canvas, node, ('⟋', '⟍'), ('⧼', '⧽'));
                     let label_x = x + (w.saturating_sub(label.len())) / 2;
                     canvas.draw_text(label_x.max(x + 1), mid_y, label);
-
-                    // Lower contracting rows - staggered
-                    for row in 1..half_h {
-                        let offset = (half_h - row) * 2;
-                        let left_x = center_x.saturating_sub(offset);
-                        let right_x = center_x + 1 + offset;
-                        canvas.set_char(left_x, mid_y + row, '⟍');
-                        canvas.set_char(right_x, mid_y + row, '⟋');
-                    }
-
-                    // Bottom point
-                    canvas.set_char(center_x, y + h - 1, '⟍');
-                    canvas.set_char(center_x + 1, y + h - 1, '⟋');
                 }
             }
             _ => {
-                // Default ASCII/Unicode: steep /\ diagonals
+                // Default ASCII/Unicode: /\ diagonals meeting at the points
                 //     /\        row 0: top point
-                //    /  \       row 1: expanding
-                //   <text>      row 2: middle (widest, with label)
-                //    \  /       row 3: contracting
+                //   /    \      row 1: expanding
+                //  < text >     row 2: middle (widest, with label)
+                //   \    /      row 3: contracting
                 //     \/        row 4: bottom point
+                self.draw_diamond_outline(canvas, node, ('/', '\\'), ('<', '>'));
                 let mid_y = y + h / 2;
-                let half_h = h / 2;
-                let center_x = x + w / 2;
-
-                // Top point
-                canvas.set_char(center_x, y, '/');
-                canvas.set_char(center_x + 1, y, '\\');
-
-                // Upper expanding rows (between top point and middle)
-                for row in 1..half_h {
-                    let left_x = center_x.saturating_sub(row);
-                    let right_x = center_x + 1 + row;
-                    canvas.set_char(left_x, y + row, '/');
-                    canvas.set_char(right_x, y + row, '\\');
-                }
-
-                // Middle row with label
-                canvas.set_char(x, mid_y, '<');
-                canvas.set_char(x + w - 1, mid_y, '>');
                 let label_x = x + (w.saturating_sub(label.len())) / 2;
                 canvas.draw_text(label_x.max(x + 1), mid_y, label);
-
-                // Lower contracting rows (between middle and bottom point)
-                for row in 1..half_h {
-                    let left_x = center_x.saturating_sub(half_h - row);
-                    let right_x = center_x + 1 + (half_h - row);
-                    canvas.set_char(left_x, mid_y + row, '\\');
-                    canvas.set_char(right_x, mid_y + row, '/');
-                }
-
-                // Bottom point
-                canvas.set_char(center_x, y + h - 1, '\\');
-                canvas.set_char(center_x + 1, y + h - 1, '/');
             }
         }
     }
 
-    fn draw_circle(&self, canvas: &mut AsciiCanvas, node: &PositionedNode, label: &str) {
+    /// Draw a tall diamond's outline along [`sizing::diamond_inset`]
+    ///
+    /// `rising` holds the glyphs of the upper left and right sides (the lower
+    /// sides use them swapped) and `points` the glyphs of the middle row.
+    fn draw_diamond_outline(
+        &self,
+        canvas: &mut AsciiCanvas,
+        node: &PositionedNode,
+        rising: (char, char),
+        points: (char, char),
+    ) {
+        let (w, h) = (node.width, node.height);
+        let mid = h / 2;
+        for row in 0..h {
+            let inset = sizing::diamond_inset(w, h, row);
+            let (left, right) = (node.x + inset, node.x + w - 1 - inset);
+            let (left_char, right_char) = match row.cmp(&mid) {
+                std::cmp::Ordering::Less => rising,
+                std::cmp::Ordering::Equal => points,
+                std::cmp::Ordering::Greater => (rising.1, rising.0),
+            };
+            canvas.set_char(left, node.y + row, left_char);
+            canvas.set_char(right, node.y + row, right_char);
+        }
+    }
+
+    fn draw_circle(
+        &self,
+        canvas: &mut AsciiCanvas,
+        node: &PositionedNode,
+        label: &str,
+        shape: NodeShape,
+    ) {
         let x = node.x;
         let y = node.y;
         let w = node.width;
@@ -694,10 +1222,9 @@ impl FlowchartRenderer {
             canvas.set_char(x + w - 1, y + row, ')');
         }
 
-        // Label
-        let label_y = y + h / 2;
-        let label_x = x + (w.saturating_sub(label.len())) / 2;
-        canvas.draw_text(label_x.max(x + 1), label_y, label);
+        // Label, wrapped to keep the circle round
+        let lines = sizing::label_lines(label, shape, self.layout_config.max_label_width);
+        self.draw_label_lines(canvas, node, &lines);
 
         // Bottom
         for i in 0..w {
@@ -710,14 +1237,17 @@ impl FlowchartRenderer {
             };
             canvas.set_char(x + i, y + h - 1, ch);
         }
+
+        // Inner ring of a double circle
+        if shape == NodeShape::DoubleCircle && w > 4 {
+            for row in 0..h {
+                canvas.set_char(x + 1, y + row, '(');
+                canvas.set_char(x + w - 2, y + row, ')');
+            }
+        }
     }
 
-    fn draw_edge(
-        &self,
-        canvas: &mut AsciiCanvas,
-        waypoints: &[(usize, usize)],
-        edge_type: EdgeType,
-    ) {
+    fn draw_edge(&self, canvas: &mut AsciiCanvas, waypoints: &[Point], edge_type: EdgeType) {
         if waypoints.len() < 2 {
             return;
         }
@@ -735,8 +1265,8 @@ impl FlowchartRenderer {
             return;
         }
 
-        let (x1, y1) = waypoints[0];
-        let (x2, y2) = waypoints[waypoints.len() - 1];
+        let Point { x: x1, y: y1 } = waypoints[0];
+        let Point { x: x2, y: y2 } = waypoints[waypoints.len() - 1];
 
         // Determine if we need orthogonal routing
         if y1 == y2 {
@@ -798,7 +1328,7 @@ impl FlowchartRenderer {
                 } else {
                     '┘'
                 };
-                canvas.set_char(x1, turn_y, corner1);
+                canvas.set_char(x1, turn_y, self.edge_corner(corner1));
 
                 // Horizontal segment to target x
                 self.draw_horizontal_line(canvas, turn_y, x1, x2, &chars);
@@ -811,7 +1341,7 @@ impl FlowchartRenderer {
                 } else {
                     '┌'
                 };
-                canvas.set_char(x2, turn_y, corner2);
+                canvas.set_char(x2, turn_y, self.edge_corner(corner2));
 
                 // Vertical segment from corner to arrow position
                 self.draw_vertical_line(canvas, x2, turn_y, y2, &chars);
@@ -839,7 +1369,7 @@ impl FlowchartRenderer {
                 } else {
                     '└'
                 };
-                canvas.set_char(turn_x, y1, corner);
+                canvas.set_char(turn_x, y1, self.edge_corner(corner));
 
                 // Vertical segment from corner toward target
                 self.draw_vertical_line(canvas, turn_x, y2, y1, &chars);
@@ -852,21 +1382,135 @@ impl FlowchartRenderer {
         }
     }
 
+    /// Draw a short hop between offset nodes as a straight run that cuts
+    /// across diagonally just before the target
+    ///
+    /// Only two-point routes whose offset across the flow is at most
+    /// [`MAX_DIAGONAL`] cells, and shorter than the run along it, are drawn;
+    /// returns false, drawing nothing, for any other route.
+    fn draw_diagonal_edge(
+        &self,
+        canvas: &mut AsciiCanvas,
+        waypoints: &[Point],
+        edge_type: EdgeType,
+    ) -> bool {
+        let [from, to] = waypoints else {
+            return false;
+        };
+        let chars = EdgeChars::for_type(edge_type, self.style);
+        if chars.is_invisible() {
+            return false;
+        }
+        let (dx, dy) = (from.x.abs_diff(to.x), from.y.abs_diff(to.y));
+        let vertical = dy > dx;
+        let (run, offset) = if vertical { (dy, dx) } else { (dx, dy) };
+        if offset == 0 || offset > MAX_DIAGONAL || run <= offset {
+            return false;
+        }
+
+        // `\` joins cells that step the same way on both axes
+        let falling = (to.x > from.x) == (to.y > from.y);
+        let slash = match (falling, self.style.is_ascii()) {
+            (true, true) => '\\',
+            (true, false) => '╲',
+            (false, true) => '/',
+            (false, false) => '╱',
+        };
+        let step = |a: usize, b: usize, n: usize| if b > a { a + n } else { a - n };
+        // The straight run ends `offset` cells before the target; each cell
+        // after it moves one step across, the last one being the arrow
+        let mut cells = Vec::with_capacity(offset);
+        if vertical {
+            let turn_y = step(to.y, from.y, offset + 1);
+            self.draw_vertical_line(canvas, from.x, from.y, turn_y, &chars);
+            cells.extend(
+                (1..=offset).map(|n| Point::new(step(from.x, to.x, n), step(turn_y, to.y, n))),
+            );
+        } else {
+            let turn_x = step(to.x, from.x, offset + 1);
+            self.draw_horizontal_line(canvas, from.y, from.x, turn_x, &chars);
+            cells.extend(
+                (1..=offset).map(|n| Point::new(step(turn_x, to.x, n), step(from.y, to.y, n))),
+            );
+        }
+        let last = cells.len() - 1;
+        for (index, cell) in cells.into_iter().enumerate() {
+            let glyph = match (index == last && edge_type.has_arrow(), vertical) {
+                (false, _) => slash,
+                (true, true) if to.y > from.y => chars.arrow_down,
+                (true, true) => chars.arrow_up,
+                (true, false) if to.x > from.x => chars.arrow_right,
+                (true, false) => chars.arrow_left,
+            };
+            canvas.set_char(cell.x, cell.y, glyph);
+        }
+        true
+    }
+
     /// Draw a multi-segment edge (for back-edges routed around the diagram)
+    /// Draw `marker` where an edge leaves `from` at `tail`, pointing back at
+    /// the node
+    fn draw_start_marker(
+        &self,
+        canvas: &mut AsciiCanvas,
+        from: &PositionedNode,
+        tail: Point,
+        marker: EdgeTerminator,
+    ) {
+        let rect = from.rect();
+        let center = rect.center();
+        let heading = if tail.x == center.x && tail.y > center.y {
+            Direction::BottomUp
+        } else if tail.x == center.x {
+            Direction::TopDown
+        } else if tail.x > center.x {
+            Direction::RightLeft
+        } else {
+            Direction::LeftRight
+        };
+        // Up- and left-going flows leave from the border, which the node
+        // is drawn over
+        let on_border = match heading {
+            Direction::TopDown => tail.y == rect.y,
+            Direction::LeftRight => tail.x == rect.x,
+            _ => false,
+        };
+        let cell = if on_border {
+            tail.step(heading, -1)
+        } else {
+            tail
+        };
+        // Edges split from a shared trunk keep its junction
+        if matches!(
+            canvas.get_char(cell.x, cell.y),
+            '┬' | '┴' | '├' | '┤' | '┼' | '+'
+        ) {
+            return;
+        }
+        let style = match self.style {
+            CharacterSet::Ascii | CharacterSet::Compact => CharacterSet::Ascii,
+            style => style,
+        };
+        if let Some(glyph) = marker.glyph(heading, style) {
+            canvas.set_char(cell.x, cell.y, glyph);
+        }
+    }
+
     fn draw_multi_segment_edge(
         &self,
         canvas: &mut AsciiCanvas,
-        waypoints: &[(usize, usize)],
+        waypoints: &[Point],
         chars: &EdgeChars,
         has_arrow: bool,
     ) {
         // Draw each segment between consecutive waypoints
-        for i in 0..waypoints.len() - 1 {
-            let (x1, y1) = waypoints[i];
-            let (x2, y2) = waypoints[i + 1];
-            let is_last = i == waypoints.len() - 2;
+        let segment_count = waypoints.len() - 1;
+        for (i, segment) in Segment::path(waypoints).enumerate() {
+            let Point { x: x1, y: y1 } = segment.start;
+            let Point { x: x2, y: y2 } = segment.end;
+            let is_last = i == segment_count - 1;
 
-            if x1 == x2 {
+            if segment.is_vertical() {
                 // Vertical segment
                 let end_y = if is_last && has_arrow {
                     if y2 > y1 {
@@ -878,7 +1522,7 @@ impl FlowchartRenderer {
                     y2
                 };
                 self.draw_vertical_line(canvas, x1, y1, end_y, chars);
-            } else if y1 == y2 {
+            } else if segment.is_horizontal() {
                 // Horizontal segment
                 let end_x = if is_last && has_arrow {
                     if x2 > x1 {
@@ -894,16 +1538,15 @@ impl FlowchartRenderer {
 
             // Draw corner at waypoint (except at start and end)
             if i > 0 {
-                let (prev_x, prev_y) = waypoints[i - 1];
-                let corner = self.get_corner_char(prev_x, prev_y, x1, y1, x2, y2);
-                canvas.set_char(x1, y1, corner);
+                let corner = self.get_corner_char(waypoints[i - 1], segment.start, segment.end);
+                canvas.set_char(x1, y1, self.edge_corner(corner));
             }
         }
 
         // Draw arrow at the end
         if has_arrow {
-            let (x1, y1) = waypoints[waypoints.len() - 2];
-            let (x2, y2) = waypoints[waypoints.len() - 1];
+            let Point { x: x1, y: y1 } = waypoints[waypoints.len() - 2];
+            let Point { x: x2, y: y2 } = waypoints[waypoints.len() - 1];
             let arrow = if x1 == x2 {
                 if y2 > y1 {
                     chars.arrow_down
@@ -939,29 +1582,21 @@ impl FlowchartRenderer {
     }
 
     /// Get the appropriate corner character based on incoming and outgoing directions
-    fn get_corner_char(
-        &self,
-        prev_x: usize,
-        prev_y: usize,
-        curr_x: usize,
-        curr_y: usize,
-        next_x: usize,
-        next_y: usize,
-    ) -> char {
+    fn get_corner_char(&self, prev: Point, curr: Point, next: Point) -> char {
         if self.style.is_ascii() {
             return '+';
         }
 
         // Determine incoming and outgoing directions
-        let from_left = prev_x < curr_x;
-        let from_right = prev_x > curr_x;
-        let from_top = prev_y < curr_y;
-        let from_bottom = prev_y > curr_y;
+        let from_left = prev.x < curr.x;
+        let from_right = prev.x > curr.x;
+        let from_top = prev.y < curr.y;
+        let from_bottom = prev.y > curr.y;
 
-        let to_left = next_x < curr_x;
-        let to_right = next_x > curr_x;
-        let to_top = next_y < curr_y;
-        let to_bottom = next_y > curr_y;
+        let to_left = next.x < curr.x;
+        let to_right = next.x > curr.x;
+        let to_top = next.y < curr.y;
+        let to_bottom = next.y > curr.y;
 
         match (
             from_left,
@@ -989,80 +1624,244 @@ impl FlowchartRenderer {
         }
     }
 
-    fn draw_edge_label(&self, canvas: &mut AsciiCanvas, waypoints: &[(usize, usize)], label: &str) {
+    /// Draw an edge label near the end of its route
+    ///
+    /// The position is chosen from the route's shape; [`Self::place_label`]
+    /// then nudges it off junctions, nodes and other labels.
+    fn draw_edge_label(
+        &self,
+        canvas: &mut AsciiCanvas,
+        obstacles: &mut LabelObstacles,
+        waypoints: &[Point],
+        label: &str,
+        direction: Direction,
+    ) {
         if waypoints.len() < 2 || label.is_empty() {
             return;
         }
 
-        let (x1, y1) = waypoints[0];
-        let (x2, y2) = waypoints[waypoints.len() - 1];
+        let Point { x: x1, y: y1 } = waypoints[0];
+        let Point { x: x2, y: y2 } = waypoints[waypoints.len() - 1];
+        let vertical_flow = matches!(direction, Direction::TopDown | Direction::BottomUp);
 
         if y1 == y2 {
             // Horizontal edge: place label above if possible, otherwise below
             let mid_x = (x1 + x2) / 2;
             let start_x = mid_x.saturating_sub(label.len() / 2);
             let label_y = if y1 > 0 { y1 - 1 } else { y1 + 1 };
-            canvas.draw_text(start_x, label_y, label);
+            self.place_label(canvas, obstacles, start_x, label_y, label, Axis::Horizontal);
         } else if x1 == x2 {
             // Vertical edge: place label to the right of the line
             let mid_y = (y1 + y2) / 2;
             let label_x = x1 + 1;
-            canvas.draw_text(label_x, mid_y, label);
+            self.place_label(canvas, obstacles, label_x, mid_y, label, Axis::Vertical);
+        } else if vertical_flow && y2 > y1 {
+            // Going down (including splits): place label above the arrow,
+            // centered on the branch
+            let label_y = y2.saturating_sub(2); // One row above arrow
+            let label_x = x2.saturating_sub(label.len() / 2);
+            self.place_label(canvas, obstacles, label_x, label_y, label, Axis::Vertical);
+        } else if vertical_flow {
+            // Going up: place label on the outside of the branch
+            let label_y = y2 + 1; // Arrow row
+            let label_x = if x2 < x1 {
+                // Left branch: label to the left (with 1 char gap)
+                x2.saturating_sub(label.len() + 1)
+            } else {
+                // Right branch: label to the right
+                x2 + 1
+            };
+            self.place_label(canvas, obstacles, label_x, label_y, label, Axis::Vertical);
         } else {
-            // Orthogonal route (including splits): place label on the segment near target
-            if y2 > y1 {
-                // Going down: place label above the arrow, centered on the branch
-                let label_y = y2.saturating_sub(2); // One row above arrow
-                let label_x = x2.saturating_sub(label.len() / 2);
-                canvas.draw_text(label_x, label_y, label);
-            } else if y2 < y1 {
-                // Going up: place label on the outside of the branch
-                let label_y = y2 + 1; // Arrow row
-                if x2 < x1 {
-                    // Left branch: label to the left (with 1 char gap)
-                    let label_x = x2.saturating_sub(label.len() + 1);
-                    canvas.draw_text(label_x, label_y, label);
-                } else {
-                    // Right branch: label to the right
-                    let label_x = x2 + 1;
-                    canvas.draw_text(label_x, label_y, label);
-                }
-            } else if x2 > x1 {
-                // Going right: place label above/below based on position
-                if y2 < y1 {
-                    // Upper branch: label above
-                    let label_y = y2.saturating_sub(1);
-                    let start_x = x2.saturating_sub(label.len());
-                    canvas.draw_text(start_x, label_y, label);
-                } else {
-                    // Lower branch or straight: label below
-                    let label_y = y2 + 1;
-                    let start_x = x2.saturating_sub(label.len());
-                    canvas.draw_text(start_x, label_y, label);
-                }
+            // Going right or left: upper branch labels go above the final
+            // segment, lower branch labels below it
+            let label_y = if y2 < y1 {
+                y2.saturating_sub(1)
             } else {
-                // Going left: place label above/below based on position
-                if y2 < y1 {
-                    let label_y = y2.saturating_sub(1);
-                    let start_x = x2 + 1;
-                    canvas.draw_text(start_x, label_y, label);
-                } else {
-                    let label_y = y2 + 1;
-                    let start_x = x2 + 1;
-                    canvas.draw_text(start_x, label_y, label);
-                }
-            }
+                y2 + 1
+            };
+            let start_x = if x2 > x1 {
+                x2.saturating_sub(label.len())
+            } else {
+                x2 + 1
+            };
+            self.place_label(canvas, obstacles, start_x, label_y, label, Axis::Horizontal);
         }
     }
 
-    fn draw_junction(
+    /// Draw a self-loop label just outside the loop: right of its outer
+    /// side in vertical flows, right of its top in horizontal ones
+    fn draw_self_loop_label(
         &self,
         canvas: &mut AsciiCanvas,
-        junction: (usize, usize),
+        obstacles: &mut LabelObstacles,
+        waypoints: &[Point],
+        label: &str,
+    ) {
+        let Some(&outer) = waypoints.get(2) else {
+            return;
+        };
+        if waypoints.len() == 5 {
+            let middle = waypoints[3].y;
+            self.place_label(
+                canvas,
+                obstacles,
+                outer.x + 2,
+                middle,
+                label,
+                Axis::Vertical,
+            );
+        } else {
+            self.place_label(
+                canvas,
+                obstacles,
+                outer.x + 2,
+                outer.y,
+                label,
+                Axis::Horizontal,
+            );
+        }
+    }
+
+    /// Draw the label shared by every branch of a split next to its junction
+    ///
+    /// In vertical flows the label sits beside the trunk just before the
+    /// junction (`│ ok` above `┴`); in horizontal flows it follows the
+    /// junction between the branches (`─┤ ok`). Returns false when those
+    /// cells are taken.
+    fn draw_split_label(
+        &self,
+        canvas: &mut AsciiCanvas,
+        obstacles: &mut LabelObstacles,
+        junction: Point,
+        label: &str,
+        direction: Direction,
+    ) -> bool {
+        let width = label.chars().count();
+        let (x, y) = if direction.is_vertical() {
+            match direction {
+                Direction::BottomUp => (junction.x + 2, junction.y + 1),
+                _ => match junction.y.checked_sub(1) {
+                    Some(y) => (junction.x + 2, y),
+                    None => return false,
+                },
+            }
+        } else {
+            match direction {
+                Direction::RightLeft => match junction.x.checked_sub(width + 1) {
+                    Some(x) => (x, junction.y),
+                    None => return false,
+                },
+                _ => (junction.x + 2, junction.y),
+            }
+        };
+
+        let free = (x..x + width)
+            .all(|cx| canvas.get_char(cx, y) == ' ' && !obstacles.covers(Point::new(cx, y)));
+        if !free {
+            return false;
+        }
+        canvas.draw_text(x, y, label);
+        obstacles.labels.push(Rect::new(x, y, width, 1));
+        true
+    }
+
+    /// Draw a label at `(x, y)`, or at the nearest free spot along `axis`
+    ///
+    /// A spot is free when the label covers no junction, corner or arrow
+    /// glyph, no node and no other label. If nothing within
+    /// [`MAX_LABEL_SHIFT`] cells is free, the label is drawn where asked.
+    fn place_label(
+        &self,
+        canvas: &mut AsciiCanvas,
+        obstacles: &mut LabelObstacles,
+        x: usize,
+        y: usize,
+        label: &str,
+        axis: Axis,
+    ) {
+        let width = label.chars().count();
+        let is_free = |lx: usize, ly: usize| {
+            (lx..lx + width).all(|cx| {
+                !is_junction_glyph(canvas.get_char(cx, ly)) && !obstacles.covers(Point::new(cx, ly))
+            })
+        };
+
+        let shifted = |distance: usize, forward: bool| {
+            let moved = |v: usize| {
+                if forward {
+                    Some(v + distance)
+                } else {
+                    v.checked_sub(distance)
+                }
+            };
+            match axis {
+                Axis::Horizontal => moved(x).map(|lx| (lx, y)),
+                Axis::Vertical => moved(y).map(|ly| (x, ly)),
+            }
+        };
+
+        let (lx, ly) = std::iter::once(Some((x, y)))
+            .chain((1..=MAX_LABEL_SHIFT).flat_map(|d| [shifted(d, true), shifted(d, false)]))
+            .flatten()
+            .find(|&(lx, ly)| is_free(lx, ly))
+            .unwrap_or((x, y));
+
+        canvas.draw_text(lx, ly, label);
+        obstacles.labels.push(Rect::new(lx, ly, width, 1));
+    }
+
+    /// Draw a TD edge label to the right of a vertical segment (`├ label`)
+    ///
+    /// Returns false when the edge has no suitable vertical run, in which case
+    /// the caller falls back to the default placement.
+    fn draw_edge_label_beside(
+        &self,
+        canvas: &mut AsciiCanvas,
+        edge: &PositionedEdge,
+        label: &str,
+    ) -> bool {
+        if edge.waypoints.len() != 2 || label.is_empty() {
+            // Back-edges are routed around the diagram; leave them alone
+            return false;
+        }
+
+        let Point { x: x1, y: y1 } = edge.waypoints[0];
+        let Point { x: x2, y: y2 } = edge.waypoints[1];
+        if y2 <= y1 {
+            return false;
+        }
+
+        // Pick the vertical run that carries only this edge, excluding the arrow row
+        let (column, first_row, last_row) = match (edge.junction, edge.merge_junction) {
+            (Some(junction), None) => (x2, junction.y + 1, y2.saturating_sub(2)),
+            (None, Some(merge)) => (x1, y1, merge.y.saturating_sub(1)),
+            (None, None) if x1 == x2 => (x1, y1, y2.saturating_sub(2)),
+            (None, None) => {
+                let turn_y = y2.saturating_sub(2).max(y1 + 1);
+                (x1, y1, turn_y.saturating_sub(1))
+            }
+            (Some(_), Some(_)) => return false,
+        };
+        if last_row < first_row {
+            return false;
+        }
+
+        let row = (first_row + last_row) / 2;
+        let tick = if self.style.is_ascii() { '+' } else { '├' };
+        canvas.set_char(column, row, tick);
+        canvas.draw_text(column + 2, row, label);
+        true
+    }
+
+    fn draw_junction(
+        &self,
+        canvas: &mut AsciiCanvas,
+        junction: Point,
         direction: crate::core::Direction,
         _group_size: usize,
     ) {
-        let (jx, jy) = junction;
+        let Point { x: jx, y: jy } = junction;
 
         // Draw the junction point
         // Junction receives line from source direction and splits perpendicular
@@ -1106,9 +1905,9 @@ impl FlowchartRenderer {
     fn draw_split_edge(
         &self,
         canvas: &mut AsciiCanvas,
-        from_center: (usize, usize),
-        junction: (usize, usize),
-        to_center: (usize, usize),
+        from_center: Point,
+        junction: Point,
+        to_center: Point,
         edge_type: EdgeType,
         direction: crate::core::Direction,
     ) {
@@ -1117,9 +1916,9 @@ impl FlowchartRenderer {
             return;
         }
 
-        let (fx, fy) = from_center;
-        let (jx, jy) = junction;
-        let (tx, ty) = to_center;
+        let Point { x: fx, y: fy } = from_center;
+        let Point { x: jx, y: jy } = junction;
+        let Point { x: tx, y: ty } = to_center;
         let has_arrow = edge_type.has_arrow();
 
         match direction {
@@ -1150,7 +1949,7 @@ impl FlowchartRenderer {
                     '│'
                 };
                 if corner_x != jx {
-                    canvas.set_char(corner_x, jy, corner);
+                    canvas.set_char(corner_x, jy, self.edge_corner(corner));
                 }
                 // Vertical down to target
                 let end_y = if has_arrow { ty.saturating_sub(1) } else { ty };
@@ -1185,7 +1984,7 @@ impl FlowchartRenderer {
                     '│'
                 };
                 if corner_x != jx {
-                    canvas.set_char(corner_x, jy, corner);
+                    canvas.set_char(corner_x, jy, self.edge_corner(corner));
                 }
                 let end_y = if has_arrow { ty + 1 } else { ty };
                 self.draw_vertical_line(canvas, corner_x, end_y, jy, &chars);
@@ -1214,7 +2013,7 @@ impl FlowchartRenderer {
                     '─'
                 };
                 if corner_y != jy {
-                    canvas.set_char(jx, corner_y, corner);
+                    canvas.set_char(jx, corner_y, self.edge_corner(corner));
                 }
                 // Horizontal to target
                 let end_x = if has_arrow { tx.saturating_sub(1) } else { tx };
@@ -1243,7 +2042,7 @@ impl FlowchartRenderer {
                     '─'
                 };
                 if corner_y != jy {
-                    canvas.set_char(jx, corner_y, corner);
+                    canvas.set_char(jx, corner_y, self.edge_corner(corner));
                 }
                 let end_x = if has_arrow { tx + 1 } else { tx };
                 self.draw_horizontal_line(canvas, corner_y, end_x, jx, &chars);
@@ -1257,10 +2056,10 @@ impl FlowchartRenderer {
     fn draw_merge_junction(
         &self,
         canvas: &mut AsciiCanvas,
-        junction: (usize, usize),
+        junction: Point,
         direction: crate::core::Direction,
     ) {
-        let (jx, jy) = junction;
+        let Point { x: jx, y: jy } = junction;
 
         // Merge junction: multiple lines come in, one goes out to target
         // TopDown: lines come from UP (multiple), merge goes DOWN → ┬
@@ -1303,9 +2102,9 @@ impl FlowchartRenderer {
     fn draw_merge_edge(
         &self,
         canvas: &mut AsciiCanvas,
-        from_center: (usize, usize),
-        merge_junction: (usize, usize),
-        to_center: (usize, usize),
+        from_center: Point,
+        merge_junction: Point,
+        to_center: Point,
         edge_type: EdgeType,
         direction: crate::core::Direction,
     ) {
@@ -1314,9 +2113,9 @@ impl FlowchartRenderer {
             return;
         }
 
-        let (fx, fy) = from_center;
-        let (mx, my) = merge_junction;
-        let (_tx, _ty) = to_center; // Used only for context in some directions
+        let Point { x: fx, y: fy } = from_center;
+        let Point { x: mx, y: my } = merge_junction;
+        let _ = to_center; // Used only for context in some directions
 
         match direction {
             crate::core::Direction::TopDown => {
@@ -1337,7 +2136,7 @@ impl FlowchartRenderer {
                     '│'
                 };
                 if corner_x != mx {
-                    canvas.set_char(corner_x, corner_y, corner);
+                    canvas.set_char(corner_x, corner_y, self.edge_corner(corner));
                 }
 
                 // Horizontal to merge junction
@@ -1367,7 +2166,7 @@ impl FlowchartRenderer {
                     '│'
                 };
                 if corner_x != mx {
-                    canvas.set_char(corner_x, corner_y, corner);
+                    canvas.set_char(corner_x, corner_y, self.edge_corner(corner));
                     self.draw_horizontal_line(
                         canvas,
                         corner_y,
@@ -1396,7 +2195,7 @@ impl FlowchartRenderer {
                     '─'
                 };
                 if corner_y != my {
-                    canvas.set_char(corner_x, corner_y, corner);
+                    canvas.set_char(corner_x, corner_y, self.edge_corner(corner));
                 }
 
                 // Vertical to merge junction
@@ -1427,7 +2226,7 @@ impl FlowchartRenderer {
                     '─'
                 };
                 if corner_y != my {
-                    canvas.set_char(corner_x, corner_y, corner);
+                    canvas.set_char(corner_x, corner_y, self.edge_corner(corner));
                     self.draw_vertical_line(
                         canvas,
                         corner_x,
@@ -1443,8 +2242,8 @@ impl FlowchartRenderer {
     fn draw_merge_to_target(
         &self,
         canvas: &mut AsciiCanvas,
-        merge_junction: (usize, usize),
-        to_center: (usize, usize),
+        merge_junction: Point,
+        to_center: Point,
         edge_type: EdgeType,
         direction: crate::core::Direction,
     ) {
@@ -1453,8 +2252,8 @@ impl FlowchartRenderer {
             return;
         }
 
-        let (mx, my) = merge_junction;
-        let (tx, ty) = to_center;
+        let Point { x: mx, y: my } = merge_junction;
+        let Point { x: tx, y: ty } = to_center;
         let has_arrow = edge_type.has_arrow();
 
         match direction {
@@ -1523,9 +2322,8 @@ impl FlowchartRenderer {
                         junction_cross // True crossing in the middle
                     }
                 }
-                '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' | '+' => {
-                    existing
-                } // Keep existing junctions
+                '┌' | '┐' | '└' | '┘' | '╭' | '╮' | '╰' | '╯' | '├' | '┤' | '┬' | '┴' | '┼'
+                | '+' => existing, // Keep existing junctions
                 _ => chars.horizontal,
             };
             canvas.set_char(x, y, new_char);
@@ -1566,9 +2364,8 @@ impl FlowchartRenderer {
                         junction_cross // True crossing in the middle
                     }
                 }
-                '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' | '+' => {
-                    existing
-                } // Keep existing junctions
+                '┌' | '┐' | '└' | '┘' | '╭' | '╮' | '╰' | '╯' | '├' | '┤' | '┬' | '┴' | '┼'
+                | '+' => existing, // Keep existing junctions
                 _ => chars.vertical,
             };
             canvas.set_char(x, y, new_char);
@@ -1590,91 +2387,383 @@ struct EdgeChars {
 impl EdgeChars {
     fn for_type(edge_type: EdgeType, style: CharacterSet) -> Self {
         let ascii = matches!(style, CharacterSet::Ascii | CharacterSet::Compact);
-        let dots = if ascii { '.' } else { '┄' };
-        match edge_type {
-            EdgeType::Arrow | EdgeType::Line | EdgeType::OpenArrow | EdgeType::CrossArrow => {
-                if ascii {
-                    Self {
-                        horizontal: '-',
-                        vertical: '|',
-                        arrow_right: '>',
-                        arrow_left: '<',
-                        arrow_down: 'v',
-                        arrow_up: '^',
-                        invisible: false,
-                    }
-                } else {
-                    Self {
-                        horizontal: '─',
-                        vertical: '│',
-                        arrow_right: '▶',
-                        arrow_left: '◀',
-                        arrow_down: '▼',
-                        arrow_up: '▲',
-                        invisible: false,
-                    }
-                }
+        let (horizontal, vertical) = match (edge_type, ascii) {
+            (EdgeType::Invisible, _) => (' ', ' '),
+            (EdgeType::DottedArrow | EdgeType::DottedLine, true) => ('.', ':'),
+            (EdgeType::DottedArrow | EdgeType::DottedLine, false) => ('┄', '┆'),
+            (EdgeType::ThickArrow | EdgeType::ThickLine, true) => ('=', '|'),
+            (EdgeType::ThickArrow | EdgeType::ThickLine, false) => ('═', '║'),
+            (_, true) => ('-', '|'),
+            (_, false) => ('─', '│'),
+        };
+
+        // Heads come from the shared terminator table; compact edges use ASCII
+        let terminator = EdgeTerminator::for_edge_type(edge_type);
+        let head_style = if ascii { CharacterSet::Ascii } else { style };
+        let head =
+            |heading: Direction, line: char| terminator.glyph(heading, head_style).unwrap_or(line);
+
+        Self {
+            horizontal,
+            vertical,
+            arrow_right: head(Direction::LeftRight, horizontal),
+            arrow_left: head(Direction::RightLeft, horizontal),
+            arrow_down: head(Direction::TopDown, vertical),
+            arrow_up: head(Direction::BottomUp, vertical),
+            invisible: edge_type == EdgeType::Invisible,
+        }
+    }
+
+    fn is_invisible(&self) -> bool {
+        self.invisible
+    }
+}
+
+/// Arms of a line glyph: whether it reaches up, down, left and right
+fn glyph_arms(c: char) -> [bool; 4] {
+    match c {
+        '─' | '┄' | '═' => [false, false, true, true],
+        '│' | '┆' | '║' => [true, true, false, false],
+        '┌' | '╭' => [false, true, false, true],
+        '┐' | '╮' => [false, true, true, false],
+        '└' | '╰' => [true, false, false, true],
+        '┘' | '╯' => [true, false, true, false],
+        '├' => [true, true, false, true],
+        '┤' => [true, true, true, false],
+        '┬' => [false, true, true, true],
+        '┴' => [true, false, true, true],
+        '┼' => [true; 4],
+        '▼' => [true, false, false, false],
+        '▲' => [false, true, false, false],
+        '▶' => [false, false, true, false],
+        '◀' => [false, false, false, true],
+        _ => [false; 4],
+    }
+}
+
+/// Light glyph with exactly the given arms, if it is a turn or junction
+fn joined_glyph(arms: [bool; 4]) -> Option<char> {
+    Some(match arms {
+        [false, true, false, true] => '┌',
+        [false, true, true, false] => '┐',
+        [true, false, false, true] => '└',
+        [true, false, true, false] => '┘',
+        [true, true, false, true] => '├',
+        [true, true, true, false] => '┤',
+        [false, true, true, true] => '┬',
+        [true, false, true, true] => '┴',
+        [true, true, true, true] => '┼',
+        _ => return None,
+    })
+}
+
+/// Redraw the turns and junctions along `route` to join every line that
+/// reaches them
+///
+/// A route drawn over a split or merge bar leaves the glyph of whichever was
+/// drawn last (`┐` where the bar goes on, `┴` where the route turns off);
+/// the joined glyph takes the arms of its neighbours instead. Straight
+/// cells are left alone, so plain crossings stay as drawn.
+fn join_route_cells(canvas: &mut AsciiCanvas, route: &[Point]) {
+    let mut cells = Vec::new();
+    for pair in route.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        for x in a.x.min(b.x)..=a.x.max(b.x) {
+            for y in a.y.min(b.y)..=a.y.max(b.y) {
+                cells.push((x, y));
             }
-            EdgeType::DottedArrow | EdgeType::DottedLine => {
-                if ascii {
-                    Self {
-                        horizontal: dots,
-                        vertical: ':',
-                        arrow_right: '>',
-                        arrow_left: '<',
-                        arrow_down: 'v',
-                        arrow_up: '^',
-                        invisible: false,
-                    }
-                } else {
-                    Self {
-                        horizontal: '┄',
-                        vertical: '┆',
-                        arrow_right: '▷',
-                        arrow_left: '◁',
-                        arrow_down: '▽',
-                        arrow_up: '△',
-                        invisible: false,
-                    }
-                }
+        }
+    }
+    for (x, y) in cells {
+        let current = canvas.get_char(x, y);
+        if joined_glyph(glyph_arms(current)).is_none() {
+            continue;
+        }
+        let reaches = |dx: isize, dy: isize, arm: usize| {
+            let (Some(nx), Some(ny)) = (x.checked_add_signed(dx), y.checked_add_signed(dy)) else {
+                return false;
+            };
+            nx < canvas.width && ny < canvas.height && glyph_arms(canvas.get_char(nx, ny))[arm]
+        };
+        let arms = [
+            reaches(0, -1, 1),
+            reaches(0, 1, 0),
+            reaches(-1, 0, 3),
+            reaches(1, 0, 2),
+        ];
+        if arms != glyph_arms(current) {
+            if let Some(glyph) = joined_glyph(arms) {
+                canvas.set_char(x, y, glyph);
             }
-            EdgeType::ThickArrow | EdgeType::ThickLine => {
-                if ascii {
-                    Self {
-                        horizontal: '=',
-                        vertical: '|',
-                        arrow_right: '>',
-                        arrow_left: '<',
-                        arrow_down: 'v',
-                        arrow_up: '^',
-                        invisible: false,
-                    }
-                } else {
-                    Self {
-                        horizontal: '═',
-                        vertical: '║',
-                        arrow_right: '▶',
-                        arrow_left: '◀',
-                        arrow_down: '▼',
-                        arrow_up: '▲',
-                        invisible: false,
-                    }
+        }
+    }
+}
+
+impl FlowchartRenderer {
+    /// Stand-in for a diagram whose layout is over the canvas limit
+    fn render_overflow(
+        &self,
+        database: &FlowchartDatabase,
+        width: usize,
+        height: usize,
+        probe: &mut LayoutProbe,
+    ) -> Result<RenderedFlowchart> {
+        warn!(
+            width,
+            height,
+            limit = %self.overflow_limit,
+            policy = %self.overflow_policy,
+            "Flowchart is over the canvas limit"
+        );
+        match self.overflow_policy {
+            OverflowPolicy::Compact => {
+                // Short labels and wrapped layers; summarize if still too big
+                let mut layout_config = self.layout_config.clone();
+                layout_config.max_label_width = match layout_config.max_label_width {
+                    0 => CONDENSED_LABEL_WIDTH,
+                    width => width.min(CONDENSED_LABEL_WIDTH),
+                };
+                if layout_config.max_layer_nodes == 0 {
+                    layout_config.max_layer_nodes =
+                        (self.overflow_limit.width / (CONDENSED_LABEL_WIDTH + 5)).max(2);
                 }
+                let mut condensed = self.clone().with_layout_config(layout_config);
+                condensed.style = CharacterSet::Compact;
+                condensed.overflow_policy = OverflowPolicy::Summary;
+                condensed.render_regions(database, probe)
             }
-            EdgeType::Invisible => Self {
-                horizontal: ' ',
-                vertical: ' ',
-                arrow_right: ' ',
-                arrow_left: ' ',
-                arrow_down: ' ',
-                arrow_up: ' ',
-                invisible: true,
-            },
+            OverflowPolicy::Error => Err(anyhow::anyhow!(
+                "Flowchart is {}x{} cells, over the {} canvas limit",
+                width,
+                height,
+                self.overflow_limit
+            )),
+            OverflowPolicy::Render | OverflowPolicy::Summary => Ok(RenderedFlowchart::text_only(
+                self.overflow_limit.notice("flowchart", width, height),
+            )),
         }
     }
 
-    fn is_invisible(&self) -> bool {
-        self.invisible
+    /// Layer size limit whose layout comes closest to `aspect`
+    ///
+    /// Tries wrapping the widest layer at every size below its own and
+    /// returns `None` when none beats the unwrapped `layout`.
+    fn layer_limit_for_aspect(
+        &self,
+        database: &FlowchartDatabase,
+        layout: &FlowchartLayoutResult,
+        aspect: AspectRatio,
+    ) -> Result<Option<usize>> {
+        // Nodes of one layer share the centre line across the flow
+        let horizontal = database.direction().is_horizontal();
+        let mut layer_sizes: HashMap<usize, usize> = HashMap::new();
+        for node in &layout.nodes {
+            let centre = match horizontal {
+                true => node.x + node.width / 2,
+                false => node.y + node.height / 2,
+            };
+            *layer_sizes.entry(centre).or_default() += 1;
+        }
+        let widest = layer_sizes.values().copied().max().unwrap_or(0);
+
+        let mut best = (aspect.distance(layout.width, layout.height), None);
+        for limit in (2..widest).rev() {
+            let wrapped = FlowchartLayoutAlgorithm::with_config(LayoutConfig {
+                diamond_style: self.diamond_style,
+                max_layer_nodes: limit,
+                ..self.layout_config.clone()
+            })
+            .layout(database)?;
+            let distance = aspect.distance(wrapped.width, wrapped.height);
+            trace!(
+                limit,
+                wrapped.width,
+                wrapped.height,
+                distance,
+                "Tried layer limit"
+            );
+            if distance < best.0 {
+                best = (distance, Some(limit));
+            }
+        }
+        debug!(%aspect, limit = ?best.1, "Chose layer wrapping for the aspect ratio");
+        Ok(best.1)
+    }
+
+    /// Redraw a layout wider than `max_width` until it fits
+    ///
+    /// Each step keeps the ones before it: wide layers wrap onto extra rows
+    /// (with the gap between nodes closed up), labels wrap at
+    /// [`CONDENSED_LABEL_WIDTH`], the compact character set is used, and
+    /// finally LR/RL charts are laid out top-down. When nothing fits, the
+    /// last attempt is returned.
+    fn render_within_width(
+        &self,
+        database: &FlowchartDatabase,
+        layout: &FlowchartLayoutResult,
+        max_width: usize,
+        probe: &mut LayoutProbe,
+    ) -> Result<RenderedFlowchart> {
+        let widest_node = layout.nodes.iter().map(|n| n.width).max().unwrap_or(1);
+        let per_row = (max_width / (widest_node + 1)).max(2);
+        let fits = |attempt: &RenderedFlowchart| {
+            let width = attempt.text.lines().map(visible_width).max().unwrap_or(0);
+            debug!(width, max_width, "Re-rendered flowchart to fit the width");
+            width <= max_width
+        };
+
+        let mut narrower = self.clone();
+        narrower.max_width = None;
+        narrower.layout_config.node_sep = narrower.layout_config.node_sep.min(1);
+        if narrower.layout_config.max_layer_nodes == 0
+            || narrower.layout_config.max_layer_nodes > per_row
+        {
+            narrower.layout_config.max_layer_nodes = per_row;
+        }
+        let attempt = narrower.render_regions(database, probe)?;
+        if fits(&attempt) {
+            return Ok(attempt);
+        }
+
+        narrower.layout_config.max_label_width = match narrower.layout_config.max_label_width {
+            0 => CONDENSED_LABEL_WIDTH,
+            width => width.min(CONDENSED_LABEL_WIDTH),
+        };
+        let attempt = narrower.render_regions(database, probe)?;
+        if fits(&attempt) {
+            return Ok(attempt);
+        }
+
+        narrower.style = CharacterSet::Compact;
+        let mut attempt = narrower.render_regions(database, probe)?;
+        if !fits(&attempt) && database.direction().is_horizontal() {
+            // Long chains are wide because of their depth; turn them on end
+            let mut vertical = database.clone();
+            vertical.set_direction(Direction::TopDown);
+            attempt = narrower.render_regions(&vertical, probe)?;
+        }
+        if !fits(&attempt) {
+            warn!(max_width, "Flowchart is still wider than the maximum width");
+        }
+        Ok(attempt)
+    }
+
+    /// Split junctions whose branches all carry the same labels, when those
+    /// labels are drawn once on the trunk
+    fn shared_split_junctions(
+        &self,
+        database: &FlowchartDatabase,
+        edges: &[PositionedEdge],
+    ) -> HashSet<Point> {
+        if self.split_labels != SplitLabels::Shared {
+            return HashSet::new();
+        }
+
+        let labels_of = |edge: &PositionedEdge| -> Vec<&str> {
+            database
+                .edges()
+                .filter(|e| e.from == edge.from_id && e.to == edge.to_id)
+                .filter_map(|e| e.label.as_deref())
+                .collect()
+        };
+        let mut branches: HashMap<Point, Vec<&PositionedEdge>> = HashMap::new();
+        for edge in edges.iter().filter(|e| e.lane.is_none()) {
+            if let Some(junction) = edge.junction {
+                branches.entry(junction).or_default().push(edge);
+            }
+        }
+
+        branches
+            .into_iter()
+            .filter(|(_, group)| {
+                let first = labels_of(group[0]);
+                group.len() > 1
+                    && !first.is_empty()
+                    && group.iter().all(|edge| labels_of(edge) == first)
+            })
+            .map(|(junction, _)| junction)
+            .collect()
+    }
+
+    /// Draw the diagram with `[n]` markers on linked nodes, then list the
+    /// links under it
+    fn render_link_footnotes(
+        &self,
+        database: &FlowchartDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<RenderedFlowchart> {
+        let (marked, links) = database.link_footnotes();
+        debug!(link_count = links.len(), "Rendering link footnotes");
+
+        let renderer = self.clone().with_link_footnotes(false);
+        let mut rendered = renderer.render_regions(&marked, probe)?;
+
+        let mut section = FooterSection::titled("Links");
+        for (i, link) in links.iter().enumerate() {
+            let text = match &link.tooltip {
+                Some(tooltip) => format!("{} ({})", link.url, tooltip),
+                None => link.url.clone(),
+            };
+            section.push(format!("[{}]", i + 1), text);
+        }
+        rendered.text = Footer::new().with_section(section).attach(&rendered.text);
+        Ok(rendered)
+    }
+
+    /// Render a BT or RL diagram as its TD or LR twin, then flip the canvas
+    fn render_mirrored(
+        &self,
+        database: &FlowchartDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<RenderedFlowchart> {
+        let (forward, axis) = match database.direction() {
+            Direction::BottomUp => (Direction::TopDown, MirrorAxis::Vertical),
+            _ => (Direction::LeftRight, MirrorAxis::Horizontal),
+        };
+        debug!(?forward, ?axis, "Rendering reversed flowchart by mirroring");
+
+        let mut twin = database.clone();
+        twin.set_direction(forward);
+        let renderer = self.clone().with_mirror_reversed(false);
+        let rendered = renderer.render_regions(&twin, probe)?;
+
+        // Flip the node regions the same way as the text
+        let width = rendered.text.lines().map(|l| l.chars().count()).max();
+        let height = rendered.text.lines().count();
+        let nodes = rendered
+            .nodes
+            .into_iter()
+            .map(|node| match axis {
+                MirrorAxis::Vertical => NodeRegion {
+                    y: height.saturating_sub(node.y + node.height),
+                    ..node
+                },
+                MirrorAxis::Horizontal => NodeRegion {
+                    x: width.unwrap_or(0).saturating_sub(node.x + node.width),
+                    ..node
+                },
+            })
+            .collect();
+        let edges = rendered
+            .edges
+            .into_iter()
+            .map(|edge| EdgeRegion {
+                cells: edge
+                    .cells
+                    .into_iter()
+                    .map(|(x, y)| match axis {
+                        MirrorAxis::Vertical => (x, height.saturating_sub(y + 1)),
+                        MirrorAxis::Horizontal => (width.unwrap_or(0).saturating_sub(x + 1), y),
+                    })
+                    .collect(),
+                ..edge
+            })
+            .collect();
+        Ok(RenderedFlowchart {
+            text: mirror_text(&rendered.text, axis),
+            nodes,
+            edges,
+        })
     }
 }
 
@@ -1688,6 +2777,53 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
     type Output = String;
 
     fn render(&self, database: &FlowchartDatabase) -> Result<Self::Output> {
+        self.render_probed(database, &mut LayoutProbe::default())
+    }
+
+    fn name(&self) -> &'static str {
+        "ascii"
+    }
+
+    fn version(&self) -> &'static str {
+        "0.2.0"
+    }
+
+    fn format(&self) -> &'static str {
+        "ascii"
+    }
+}
+
+impl FlowchartRenderer {
+    /// Render the diagram along with where each node was drawn
+    ///
+    /// Node regions are in the character cells of the returned text, so
+    /// callers can decorate the drawing afterwards (see
+    /// [`annotate`](super::annotate)). Summaries and braille output carry
+    /// no regions.
+    pub fn render_with_regions(&self, database: &FlowchartDatabase) -> Result<RenderedFlowchart> {
+        self.render_regions(database, &mut LayoutProbe::default())
+    }
+
+    /// Render the diagram, timing the layout through `probe`
+    pub(crate) fn render_probed(
+        &self,
+        database: &FlowchartDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<String> {
+        let rendered = self.render_regions(database, probe)?;
+        if self.color {
+            return Ok(super::ansi::to_ansi(&rendered, database, self.theme));
+        }
+        Ok(rendered.text)
+    }
+
+    /// [`render_with_regions`](Self::render_with_regions), timing each
+    /// layout through `probe`
+    pub(crate) fn render_regions(
+        &self,
+        database: &FlowchartDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<RenderedFlowchart> {
         let render_span = span!(
             Level::INFO,
             "render_flowchart",
@@ -1699,13 +2835,82 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
 
         trace!("Starting flowchart rendering");
 
-        // First, compute the layout
-        let layout_algo = FlowchartLayoutAlgorithm::new();
+        if self.link_footnotes
+            && database
+                .nodes()
+                .any(|node| node.link.is_some() && !database.is_node_hidden(&node.id))
+        {
+            return self.render_link_footnotes(database, probe);
+        }
+
+        if self.mirror_reversed
+            && !self.debug_overlay
+            && self.canvas_mode == CanvasMode::Text
+            && database.direction().is_reversed()
+        {
+            return self.render_mirrored(database, probe);
+        }
+
+        // Hidden elements stay in the model but are never laid out
+        let visible;
+        let database = if database.has_hidden() {
+            visible = database.visible_view();
+            debug!(
+                visible_nodes = visible.node_count(),
+                visible_edges = visible.edge_count(),
+                "Rendering visible view"
+            );
+            &visible
+        } else {
+            database
+        };
+
+        // First, compute the layout, sizing diamonds for the style drawn
+        let layout_algo = FlowchartLayoutAlgorithm::with_config(LayoutConfig {
+            diamond_style: self.diamond_style,
+            ..self.layout_config.clone()
+        });
+        let started = probe.start();
         let layout = layout_algo.layout(database)?;
+        probe.finish(started, layout.width, layout.height);
 
         if layout.nodes.is_empty() {
             debug!("Empty layout, returning empty string");
-            return Ok(String::new());
+            return Ok(RenderedFlowchart::default());
+        }
+
+        if let Some(aspect) = self.aspect_hint {
+            if self.layout_config.max_layer_nodes == 0 {
+                if let Some(limit) = self.layer_limit_for_aspect(database, &layout, aspect)? {
+                    let mut wrapped = self.clone();
+                    wrapped.aspect_hint = None;
+                    wrapped.layout_config.max_layer_nodes = limit;
+                    return wrapped.render_regions(database, probe);
+                }
+            }
+        }
+
+        if let Some(max_width) = self.max_width {
+            if layout.width > max_width && self.canvas_mode == CanvasMode::Text {
+                return self.render_within_width(database, &layout, max_width, probe);
+            }
+        }
+
+        if self.overflow_policy != OverflowPolicy::Render
+            && self.overflow_limit.exceeded_by(layout.width, layout.height)
+        {
+            return self.render_overflow(database, layout.width, layout.height, probe);
+        }
+
+        #[cfg(debug_assertions)]
+        for violation in super::validate_layout(&layout) {
+            tracing::warn!(%violation, "Layout invariant violated");
+        }
+
+        if self.canvas_mode == CanvasMode::Braille {
+            let output = super::braille::render_braille(database, &layout);
+            info!(output_len = output.len(), "Braille rendering completed");
+            return Ok(RenderedFlowchart::text_only(output));
         }
 
         // Create canvas
@@ -1748,17 +2953,25 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
         let mut edges_drawn = 0;
 
         // Track which junctions we've drawn (split junctions)
-        let mut drawn_split_junctions: std::collections::HashSet<(usize, usize)> =
+        let mut drawn_split_junctions: std::collections::HashSet<Point> =
             std::collections::HashSet::new();
         // Track which merge junctions we've drawn and drawn the final segment for
-        let mut drawn_merge_junctions: std::collections::HashSet<(usize, usize)> =
+        let mut drawn_merge_junctions: std::collections::HashSet<Point> =
             std::collections::HashSet::new();
 
         // Collect labels to draw after all edges (so labels don't interfere with edge drawing)
-        let mut labels_to_draw: Vec<(Vec<(usize, usize)>, String)> = Vec::new();
+        let mut labels_to_draw: Vec<(LabelSlot, &PositionedEdge, SlotLabels)> = Vec::new();
+        let shared_splits = self.shared_split_junctions(database, &layout.edges);
+
+        // Cells each edge is drawn over, for owners in cell grids
+        let mut edge_cells: Vec<Vec<(usize, usize)>> = Vec::with_capacity(layout.edges.len());
+
+        // Routes drawn across split and merge bars, rejoined once all are drawn
+        let mut joined_routes: Vec<Vec<Point>> = Vec::new();
 
         // First pass: draw all edge lines
         for edge in &layout.edges {
+            canvas.track_writes();
             let edge_data = database
                 .edges()
                 .find(|e| e.from == edge.from_id && e.to == edge.to_id);
@@ -1778,28 +2991,34 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
             let from_node = layout.nodes.iter().find(|n| n.id == edge.from_id);
             let to_node = layout.nodes.iter().find(|n| n.id == edge.to_id);
 
-            // Compute edge exit/entry points based on direction
-            let (from_center, to_center) = if let (Some(from), Some(to)) = (from_node, to_node) {
-                let fc = match database.direction() {
-                    crate::core::Direction::TopDown => {
-                        (from.x + from.width / 2, from.y + from.height)
-                    }
-                    crate::core::Direction::BottomUp => (from.x + from.width / 2, from.y),
-                    crate::core::Direction::LeftRight => {
-                        (from.x + from.width, from.y + from.height / 2)
-                    }
-                    crate::core::Direction::RightLeft => (from.x, from.y + from.height / 2),
-                };
-                let tc = match database.direction() {
-                    crate::core::Direction::TopDown => (to.x + to.width / 2, to.y),
-                    crate::core::Direction::BottomUp => (to.x + to.width / 2, to.y + to.height),
-                    crate::core::Direction::LeftRight => (to.x, to.y + to.height / 2),
-                    crate::core::Direction::RightLeft => (to.x + to.width, to.y + to.height / 2),
-                };
-                (Some(fc), Some(tc))
-            } else {
-                (None, None)
+            // Compute edge exit/entry points based on direction, moved onto
+            // the outline of shapes that do not fill their box
+            let shape_of = |id: &str| database.get_node(id).map(|n| n.shape).unwrap_or_default();
+            let has_arrow = edge_type.has_arrow();
+            let (from_center, to_center) = match (from_node, to_node) {
+                (Some(from), Some(to)) => (
+                    Some(self.attach_to_outline(
+                        from.rect().exit(database.direction()),
+                        from,
+                        shape_of(&from.id),
+                        false,
+                    )),
+                    Some(self.attach_to_outline(
+                        to.rect().entry(database.direction()),
+                        to,
+                        shape_of(&to.id),
+                        has_arrow,
+                    )),
+                ),
+                _ => (None, None),
             };
+            let mut waypoints = edge.waypoints.clone();
+            if let (Some(from), Some(first)) = (from_node, waypoints.first_mut()) {
+                *first = self.attach_to_outline(*first, from, shape_of(&from.id), false);
+            }
+            if let (Some(to), Some(last)) = (to_node, waypoints.last_mut()) {
+                *last = self.attach_to_outline(*last, to, shape_of(&to.id), has_arrow);
+            }
 
             // Handle split junction (edges from same source)
             if let Some(junction) = edge.junction {
@@ -1815,7 +3034,27 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
                 }
 
                 // Draw split edge through junction
-                if let (Some(fc), Some(tc)) = (from_center, to_center) {
+                if edge.lane.is_some() || edge.waypoints.len() > 2 {
+                    // Routed to a wrapped row or around other nodes; the
+                    // route passes the junction cell, so restore it afterwards
+                    self.draw_edge(&mut canvas, &waypoints, edge_type);
+                    joined_routes.push(waypoints.clone());
+                    self.draw_junction(
+                        &mut canvas,
+                        junction,
+                        database.direction(),
+                        edge.group_size.unwrap_or(1),
+                    );
+                    if let Some(merge_junction) = edge.merge_junction {
+                        if edge.waypoints.contains(&merge_junction) {
+                            self.draw_merge_junction(
+                                &mut canvas,
+                                merge_junction,
+                                database.direction(),
+                            );
+                        }
+                    }
+                } else if let (Some(fc), Some(tc)) = (from_center, to_center) {
                     // If this edge also has a merge junction, draw split to merge, not to target
                     if let Some(merge_junction) = edge.merge_junction {
                         // Split edge goes: source -> split junction -> ... -> merge junction
@@ -1844,8 +3083,13 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
             // Skip merge junction handling for back-edges (they have special routing)
             else if let Some(merge_junction) = edge.merge_junction {
                 if edge.waypoints.len() > 2 {
-                    // Back-edge with special routing - use the waypoints directly
-                    self.draw_edge(&mut canvas, &edge.waypoints, edge_type);
+                    // Back-edge or detour around nodes - use the waypoints directly
+                    self.draw_edge(&mut canvas, &waypoints, edge_type);
+                    joined_routes.push(waypoints.clone());
+                    if edge.waypoints.contains(&merge_junction) {
+                        // A detour joins the merge at its junction
+                        self.draw_merge_junction(&mut canvas, merge_junction, database.direction());
+                    }
                 } else if let (Some(fc), Some(tc)) = (from_center, to_center) {
                     // Draw edge from source to merge junction
                     self.draw_merge_edge(
@@ -1872,19 +3116,110 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
                 }
             } else {
                 // Regular edge (no split, no merge)
-                self.draw_edge(&mut canvas, &edge.waypoints, edge_type);
+                let diagonal = self.edge_curve == EdgeCurve::Linear
+                    && self.draw_diagonal_edge(&mut canvas, &waypoints, edge_type);
+                if !diagonal {
+                    self.draw_edge(&mut canvas, &waypoints, edge_type);
+                }
+            }
+
+            // Bidirectional edges mark their source end too
+            let start = edge_data.map_or(EdgeTerminator::None, |e| e.start);
+            if let (Some(from), Some(&tail)) = (from_node, waypoints.first()) {
+                if start.is_visible() && edge_type != EdgeType::Invisible {
+                    self.draw_start_marker(&mut canvas, from, tail, start);
+                }
             }
 
-            // Collect label for later drawing
-            if let Some(label) = edge_label {
-                labels_to_draw.push((edge.waypoints.clone(), label.to_string()));
+            // Collect labels for later drawing. Parallel edges share one
+            // path and merged edges share their final segment, so their
+            // labels are combined into one slot instead of overwriting.
+            let slot = match edge.junction {
+                Some(junction) if shared_splits.contains(&junction) => LabelSlot::Split(junction),
+                _ => LabelSlot::for_edge(edge),
+            };
+            let labels = database
+                .edges()
+                .enumerate()
+                .filter(|(_, e)| e.from == edge.from_id && e.to == edge.to_id)
+                .filter_map(|(index, e)| e.label.clone().map(|label| (index, label)));
+            match labels_to_draw.iter_mut().find(|(s, _, _)| *s == slot) {
+                Some((_, _, existing)) => existing.extend(labels),
+                None => {
+                    let collected: SlotLabels = labels.collect();
+                    if !collected.is_empty() {
+                        labels_to_draw.push((slot, edge, collected));
+                    }
+                }
             }
+            edge_cells.push(canvas.take_writes());
             edges_drawn += 1;
         }
 
+        if !self.style.is_ascii() {
+            for route in &joined_routes {
+                join_route_cells(&mut canvas, route);
+            }
+        }
+
         // Second pass: draw all labels (after edge lines, so they overlay correctly)
-        for (waypoints, label) in &labels_to_draw {
-            self.draw_edge_label(&mut canvas, waypoints, label);
+        let mut obstacles = LabelObstacles {
+            nodes: &layout.nodes,
+            labels: Vec::new(),
+        };
+        for (slot, edge, labels) in &mut labels_to_draw {
+            // Declaration order, each distinct label once
+            labels.sort();
+            let mut texts: Vec<&str> = Vec::new();
+            for (_, text) in labels.iter() {
+                if !texts.contains(&text.as_str()) {
+                    texts.push(text);
+                }
+            }
+            // Edge labels get a single row, so line breaks become spaces
+            let label = &texts.join(" / ").replace('\n', " ");
+            canvas.track_writes();
+            if let LabelSlot::Split(junction) = *slot {
+                let placed = self.draw_split_label(
+                    &mut canvas,
+                    &mut obstacles,
+                    junction,
+                    label,
+                    database.direction(),
+                );
+                if !placed {
+                    // No room beside the trunk: label every branch instead
+                    for branch in layout.edges.iter().filter(|e| e.junction == Some(junction)) {
+                        self.draw_edge_label(
+                            &mut canvas,
+                            &mut obstacles,
+                            &branch.waypoints,
+                            label,
+                            database.direction(),
+                        );
+                    }
+                }
+            } else if edge.from_id == edge.to_id {
+                self.draw_self_loop_label(&mut canvas, &mut obstacles, &edge.waypoints, label);
+            } else {
+                let placed_beside = self.label_placement == LabelPlacement::Beside
+                    && database.direction() == Direction::TopDown
+                    && self.draw_edge_label_beside(&mut canvas, edge, label);
+                if !placed_beside {
+                    self.draw_edge_label(
+                        &mut canvas,
+                        &mut obstacles,
+                        &edge.waypoints,
+                        label,
+                        database.direction(),
+                    );
+                }
+            }
+            let writes = canvas.take_writes();
+            let index = layout.edges.iter().position(|e| std::ptr::eq(e, *edge));
+            if let Some(cells) = index.and_then(|i| edge_cells.get_mut(i)) {
+                cells.extend(writes);
+            }
         }
         debug!(edges_drawn, "Drew edges");
         drop(_edge_enter);
@@ -1893,6 +3228,7 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
         let node_span = span!(Level::DEBUG, "draw_nodes", node_count = layout.nodes.len());
         let _node_enter = node_span.enter();
         let mut nodes_drawn = 0;
+        let mut glyph_cache = NodeGlyphCache::default();
         for node in &layout.nodes {
             if let Some(node_data) = database.get_node(&node.id) {
                 trace!(
@@ -1905,11 +3241,22 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
                     node_height = node.height,
                     "Drawing node"
                 );
-                self.draw_node(&mut canvas, node, node_data.shape, &node_data.label);
+                self.draw_node_cached(
+                    &mut glyph_cache,
+                    &mut canvas,
+                    node,
+                    node_data.shape,
+                    &node_data.label,
+                );
                 nodes_drawn += 1;
             }
         }
-        debug!(nodes_drawn, "Drew nodes");
+        debug!(
+            nodes_drawn,
+            unique_glyphs = glyph_cache.len(),
+            cache_hits = glyph_cache.hits,
+            "Drew nodes"
+        );
         drop(_node_enter);
 
         // Redraw subgraph titles last to fix overlap with nodes/edges
@@ -1917,6 +3264,15 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
             self.redraw_subgraph_title(&mut canvas, subgraph);
         }
 
+        reweigh_canvas(&mut canvas, self.line_weight);
+
+        if self.debug_overlay {
+            debug!("Drawing debug overlay");
+            return Ok(super::overlay::debug_overlay(
+                canvas, database, &layout, self.style,
+            ));
+        }
+
         let output = canvas.to_string();
         info!(
             output_len = output.len(),
@@ -1925,19 +3281,36 @@ impl Renderer<FlowchartDatabase> for FlowchartRenderer {
             "Rendering completed"
         );
 
-        Ok(output)
-    }
-
-    fn name(&self) -> &'static str {
-        "ascii"
-    }
-
-    fn version(&self) -> &'static str {
-        "0.2.0"
-    }
-
-    fn format(&self) -> &'static str {
-        "ascii"
+        let (left, top) = canvas.trim_offset();
+        let nodes = layout
+            .nodes
+            .iter()
+            .map(|node| NodeRegion {
+                id: node.id.clone(),
+                x: node.x.saturating_sub(left),
+                y: node.y.saturating_sub(top),
+                width: node.width,
+                height: node.height,
+            })
+            .collect();
+        let edges = layout
+            .edges
+            .iter()
+            .zip(edge_cells)
+            .map(|(edge, cells)| EdgeRegion {
+                from: edge.from_id.clone(),
+                to: edge.to_id.clone(),
+                cells: cells
+                    .into_iter()
+                    .filter_map(|(x, y)| Some((x.checked_sub(left)?, y.checked_sub(top)?)))
+                    .collect(),
+            })
+            .collect();
+        Ok(RenderedFlowchart {
+            text: output,
+            nodes,
+            edges,
+        })
     }
 }
 
@@ -2009,6 +3382,220 @@ mod tests {
         assert!(output.contains("yes"));
     }
 
+    #[test]
+    fn test_line_breaks_give_nodes_extra_rows() {
+        let mut db = FlowchartDatabase::with_direction(Direction::LeftRight);
+        db.add_simple_node("A", "Start").unwrap();
+        db.add_simple_node("B", "line one\nline two").unwrap();
+        db.add_simple_edge("A", "B").unwrap();
+
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4, "{}", output);
+        assert!(lines[1].contains("│ line one │"), "{}", output);
+        // The edge meets both nodes on their center rows
+        assert!(lines[2].contains("│ Start │─▶│ line two │"), "{}", output);
+    }
+
+    #[test]
+    fn test_tall_diamonds_are_sized_for_their_points() {
+        let mut db = FlowchartDatabase::new();
+        db.add_shaped_node("A", "x", NodeShape::Diamond).unwrap();
+
+        let renderer = FlowchartRenderer::with_styles(CharacterSet::Ascii, DiamondStyle::Tall);
+        let output = renderer.render(&db).unwrap();
+        let rows: Vec<&str> = output.lines().filter(|l| !l.trim().is_empty()).collect();
+        assert_eq!(
+            rows.iter().map(|l| l.trim()).collect::<Vec<_>>(),
+            vec!["/\\", "/    \\", "<  x   >", "\\    /", "\\/"],
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_self_loop_is_drawn_with_its_label() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_simple_node("A", "A").unwrap();
+        db.add_labeled_edge("A", "A", EdgeType::Arrow, "retry")
+            .unwrap();
+
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].contains("┌──┐"), "{}", output);
+        assert!(
+            lines.iter().any(|l| l.contains("│ A │◀┘ retry")),
+            "{}",
+            output
+        );
+
+        let mut db = FlowchartDatabase::with_direction(Direction::LeftRight);
+        db.add_simple_node("A", "A").unwrap();
+        db.add_labeled_edge("A", "A", EdgeType::Arrow, "retry")
+            .unwrap();
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(
+            output.contains("┌─┐ retry\n │ ▼\n┌───┐\n│ A │"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_decision_labels_to_same_target_are_combined() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_shaped_node("A", "Ok?", NodeShape::Diamond).unwrap();
+        db.add_simple_node("B", "B").unwrap();
+        db.add_labeled_edge("A", "B", EdgeType::Arrow, "Yes")
+            .unwrap();
+        db.add_labeled_edge("A", "B", EdgeType::Arrow, "No")
+            .unwrap();
+
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(output.contains("Yes / No"), "{}", output);
+    }
+
+    #[test]
+    fn test_decision_split_keeps_labels_on_branches() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_shaped_node("A", "Ok?", NodeShape::Diamond).unwrap();
+        db.add_simple_node("B", "B").unwrap();
+        db.add_simple_node("C", "C").unwrap();
+        db.add_labeled_edge("A", "B", EdgeType::Arrow, "Yes")
+            .unwrap();
+        db.add_labeled_edge("A", "C", EdgeType::Arrow, "No")
+            .unwrap();
+
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(output.contains("Yes"), "{}", output);
+        assert!(output.contains("No"), "{}", output);
+        assert!(!output.contains(" / "), "{}", output);
+    }
+
+    #[test]
+    fn test_bidirectional_edges_mark_both_ends() {
+        let db = crate::parse("graph LR\n A <--> B o--o C").unwrap();
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(output.contains("│ A │◀─▶│ B │○─○│ C │"), "{}", output);
+
+        let db = crate::parse("graph TD\n A x--x B").unwrap();
+        let output = FlowchartRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        let column: String = output
+            .lines()
+            .map(|l| l.chars().nth(2).unwrap_or(' '))
+            .collect();
+        assert!(column.contains("x|x"), "{}", output);
+    }
+
+    #[test]
+    fn test_link_footnotes_mark_nodes_and_list_links() {
+        let db = crate::parse(
+            "graph LR\n A[Docs] --> B --> C\n click A \"https://example.com\" \"Read\"\n click C \"https://example.com/c\"",
+        )
+        .unwrap();
+
+        let plain = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(!plain.contains("[1]"), "{}", plain);
+
+        let output = FlowchartRenderer::with_config(
+            crate::core::RenderConfig::default().with_link_footnotes(true),
+        )
+        .render(&db)
+        .unwrap();
+        assert!(output.contains("│ Docs [1] │"), "{}", output);
+        assert!(output.contains("│ C [2] │"), "{}", output);
+        assert!(
+            output
+                .ends_with("\n\nLinks\n[1] https://example.com (Read)\n[2] https://example.com/c"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_split_label_shared_by_all_branches_is_drawn_once() {
+        let db = crate::parse("graph TD\n A -->|ok| B & C").unwrap();
+
+        let shared = FlowchartRenderer::new().render(&db).unwrap();
+        assert_eq!(shared.matches("ok").count(), 1, "{}", shared);
+        let lines: Vec<&str> = shared.lines().collect();
+        let junction = lines.iter().position(|l| l.contains('┴')).unwrap();
+        assert!(lines[junction - 1].ends_with("│ ok"), "{}", shared);
+
+        let per_branch = FlowchartRenderer::new()
+            .with_split_labels(SplitLabels::PerBranch)
+            .render(&db)
+            .unwrap();
+        assert_eq!(per_branch.matches("ok").count(), 2, "{}", per_branch);
+    }
+
+    #[test]
+    fn test_merged_edge_labels_are_not_lost() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_simple_node("A", "A").unwrap();
+        db.add_simple_node("B", "B").unwrap();
+        db.add_simple_node("C", "C").unwrap();
+        db.add_labeled_edge("A", "C", EdgeType::Arrow, "left")
+            .unwrap();
+        db.add_labeled_edge("B", "C", EdgeType::Arrow, "right")
+            .unwrap();
+
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(output.contains("left / right"), "{}", output);
+    }
+
+    #[test]
+    fn test_duplicate_merged_labels_are_deduplicated() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_simple_node("A", "A").unwrap();
+        db.add_simple_node("B", "B").unwrap();
+        db.add_simple_node("C", "C").unwrap();
+        db.add_labeled_edge("A", "C", EdgeType::Arrow, "done")
+            .unwrap();
+        db.add_labeled_edge("B", "C", EdgeType::Arrow, "done")
+            .unwrap();
+
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert_eq!(output.matches("done").count(), 1, "{}", output);
+    }
+
+    #[test]
+    fn test_open_and_cross_edges_use_their_terminators() {
+        let mut db = FlowchartDatabase::with_direction(Direction::LeftRight);
+        db.add_simple_node("A", "A").unwrap();
+        db.add_simple_node("B", "B").unwrap();
+        db.add_typed_edge("A", "B", EdgeType::OpenArrow).unwrap();
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(output.contains("─○"), "{}", output);
+
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_simple_node("A", "A").unwrap();
+        db.add_simple_node("B", "B").unwrap();
+        db.add_typed_edge("A", "B", EdgeType::CrossArrow).unwrap();
+        let output = FlowchartRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        assert!(output.contains("\n  x\n"), "{}", output);
+    }
+
+    #[test]
+    fn test_hidden_nodes_are_not_rendered() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_simple_node("A", "Start").unwrap();
+        db.add_simple_node("B", "Debug probe").unwrap();
+        db.add_simple_node("C", "End").unwrap();
+        db.add_simple_edge("A", "B").unwrap();
+        db.add_simple_edge("A", "C").unwrap();
+        db.hide_node("B");
+
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(output.contains("Start") && output.contains("End"));
+        assert!(!output.contains("Debug probe"), "{}", output);
+        assert_eq!(db.node_count(), 3);
+    }
+
     #[test]
     fn test_ascii_style_uses_ascii_chars() {
         let mut db = FlowchartDatabase::with_direction(Direction::LeftRight);
@@ -2171,4 +3758,476 @@ mod tests {
         assert!(output.contains("C"));
         assert!(output.contains("D"));
     }
+
+    #[test]
+    fn test_stadium_double_circle_and_inverted_trapezoid_outlines() {
+        let renderer = FlowchartRenderer::new();
+        let node = PositionedNode {
+            id: "A".to_string(),
+            x: 0,
+            y: 0,
+            width: 9,
+            height: 3,
+        };
+        let draw = |shape| {
+            let mut canvas = AsciiCanvas::new(9, 3);
+            renderer.draw_node(&mut canvas, &node, shape, "Go");
+            canvas.to_string()
+        };
+
+        assert_eq!(draw(NodeShape::Stadium), "╭───────╮\n(  Go   )\n╰───────╯");
+        assert_eq!(
+            draw(NodeShape::DoubleCircle),
+            "((-----))\n(( Go  ))\n((-----))"
+        );
+        assert_eq!(
+            draw(NodeShape::InvTrapezoid),
+            "┌───────┐\n \\ Go  /\n  \\⠒⠒⠒/"
+        );
+    }
+
+    #[test]
+    fn test_glyph_cache_matches_direct_drawing() {
+        let shapes = [
+            NodeShape::Rectangle,
+            NodeShape::RoundedRect,
+            NodeShape::Circle,
+            NodeShape::Diamond,
+            NodeShape::Hexagon,
+            NodeShape::Subroutine,
+            NodeShape::Cylinder,
+            NodeShape::Asymmetric,
+            NodeShape::Parallelogram,
+            NodeShape::Trapezoid,
+            NodeShape::Terminal,
+            NodeShape::Stadium,
+            NodeShape::DoubleCircle,
+            NodeShape::ParallelogramAlt,
+            NodeShape::InvTrapezoid,
+        ];
+        let renderer = FlowchartRenderer::new();
+
+        for shape in shapes {
+            // The second node is drawn from the block cached for the first
+            let nodes = [(3, 2), (18, 9)].map(|(x, y)| PositionedNode {
+                id: format!("N{}", x),
+                x,
+                y,
+                width: 9,
+                height: 5,
+            });
+
+            let mut direct = AsciiCanvas::new(40, 20);
+            let mut cache = NodeGlyphCache::default();
+            let mut cached = AsciiCanvas::new(40, 20);
+            for node in &nodes {
+                renderer.draw_node(&mut direct, node, shape, "Label");
+                renderer.draw_node_cached(&mut cache, &mut cached, node, shape, "Label");
+            }
+
+            assert_eq!(cache.hits, 1);
+            assert_eq!(direct.to_string(), cached.to_string(), "shape {:?}", shape);
+        }
+    }
+
+    #[test]
+    fn test_glyph_cache_reuses_identical_nodes() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_simple_node("Root", "Root").unwrap();
+        for i in 0..5 {
+            let id = format!("N{}", i);
+            db.add_simple_node(&id, "Same").unwrap();
+            db.add_simple_edge("Root", &id).unwrap();
+        }
+
+        let renderer = FlowchartRenderer::new();
+        let layout = FlowchartLayoutAlgorithm::new().layout(&db).unwrap();
+        let mut canvas = AsciiCanvas::new(layout.width, layout.height);
+        let mut direct = AsciiCanvas::new(layout.width, layout.height);
+        let mut cache = NodeGlyphCache::default();
+        for node in &layout.nodes {
+            let data = db.get_node(&node.id).unwrap();
+            renderer.draw_node_cached(&mut cache, &mut canvas, node, data.shape, &data.label);
+            renderer.draw_node(&mut direct, node, data.shape, &data.label);
+        }
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.hits, 4);
+        assert_eq!(canvas.to_string(), direct.to_string());
+        assert_eq!(renderer.render(&db).unwrap().matches("Same").count(), 5);
+    }
+
+    #[test]
+    fn test_beside_label_placement_td() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_shaped_node("A", "Decide", NodeShape::Diamond)
+            .unwrap();
+        db.add_simple_node("B", "Do it").unwrap();
+        db.add_simple_node("C", "Skip").unwrap();
+        db.add_labeled_edge("A", "B", EdgeType::Arrow, "yes")
+            .unwrap();
+        db.add_labeled_edge("A", "C", EdgeType::Arrow, "no")
+            .unwrap();
+
+        let renderer = FlowchartRenderer::new().with_label_placement(LabelPlacement::Beside);
+        let output = renderer.render(&db).unwrap();
+
+        assert!(
+            output.contains("├ yes"),
+            "Expected ├ yes in output:\n{}",
+            output
+        );
+        assert!(
+            output.contains("├ no"),
+            "Expected ├ no in output:\n{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_beside_label_placement_straight_edge_ascii() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_simple_node("A", "Start").unwrap();
+        db.add_simple_node("B", "End").unwrap();
+        db.add_labeled_edge("A", "B", EdgeType::Arrow, "go")
+            .unwrap();
+
+        let config = crate::core::RenderConfig::new(CharacterSet::Ascii, DiamondStyle::Box)
+            .with_label_placement(LabelPlacement::Beside);
+        let output = FlowchartRenderer::with_config(config).render(&db).unwrap();
+
+        assert!(
+            output.contains("+ go"),
+            "Expected + go in output:\n{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_rounded_edges_replace_sharp_turns() {
+        // Rounded nodes, so every sharp corner left would come from an edge
+        let db = crate::parse(
+            "graph TD\n A(Start) --> B(Left)\n A --> C(Right)\n B --> D(End)\n C --> D\n D --> A",
+        )
+        .unwrap();
+
+        let sharp = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(sharp.contains(['┌', '┐', '└', '┘']), "{}", sharp);
+
+        let config = crate::core::RenderConfig::default().with_rounded_edges(true);
+        let rounded = FlowchartRenderer::with_config(config).render(&db).unwrap();
+        assert!(!rounded.contains(['┌', '┐', '└', '┘']), "{}", rounded);
+        assert_eq!(
+            sharp.chars().filter(|c| "┌┐└┘╭╮╰╯".contains(*c)).count(),
+            rounded.chars().filter(|c| "╭╮╰╯".contains(*c)).count()
+        );
+    }
+
+    #[test]
+    fn test_detours_join_split_and_merge_bars() {
+        let cases = [
+            ("graph TD\n A-->B-->C\n A-->C", ["  ├───┐", "  ├───┘"]),
+            ("digraph { A -> B -> C; A -> C }", ["  ├───┐", "  ├───┘"]),
+            ("graph LR\n A-->B-->C\n A-->C", ["│ A │─┬─▶", "│ B │──┬▶"]),
+            (
+                "graph TD\n A-->B & C & D\n B & C & D-->E\n E-->F-->G\n A-->G",
+                ["┌─────┼─────┬───┐", "├─────────┘"],
+            ),
+        ];
+        for (input, rows) in cases {
+            let output = crate::render(input).unwrap();
+            for row in rows {
+                assert!(output.contains(row), "{}\n{}", row, output);
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_weight_redraws_nodes_and_edges() {
+        let db = crate::parse("graph TD\n A[Start] --> B[Next]\n B ==> C[Done]").unwrap();
+        let light = FlowchartRenderer::new().render(&db).unwrap();
+
+        let heavy = FlowchartRenderer::new()
+            .with_line_weight(LineWeight::Heavy)
+            .render(&db)
+            .unwrap();
+        assert!(heavy.contains("┏━━━━━━━┓"), "{}", heavy);
+        assert!(heavy.contains('┃') && heavy.contains('║'), "{}", heavy);
+        assert!(!heavy.contains(['┌', '─', '│']), "{}", heavy);
+
+        // Double nodes push the thick edge to heavy so it still stands out
+        let double = FlowchartRenderer::new()
+            .with_line_weight(LineWeight::Double)
+            .render(&db)
+            .unwrap();
+        assert!(double.contains("╔═══════╗"), "{}", double);
+        assert!(double.contains('┃'), "{}", double);
+
+        // Only glyphs change, never the layout
+        let shape = |text: &str| text.replace(|c: char| !c.is_alphanumeric(), ".");
+        assert_eq!(shape(&heavy), shape(&light));
+        assert_eq!(shape(&double), shape(&light));
+
+        let ascii = FlowchartRenderer::with_style(CharacterSet::Ascii);
+        assert_eq!(
+            ascii
+                .clone()
+                .with_line_weight(LineWeight::Heavy)
+                .render(&db)
+                .unwrap(),
+            ascii.render(&db).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_mirror_reversed_flips_forward_rendering() {
+        let forward = crate::parse("graph TD\n A[Start] --> B[End]\n A --> C[Other]").unwrap();
+        let reversed = crate::parse("graph BT\n A[Start] --> B[End]\n A --> C[Other]").unwrap();
+
+        let expected = mirror_text(
+            &FlowchartRenderer::new().render(&forward).unwrap(),
+            MirrorAxis::Vertical,
+        );
+        let mirrored = FlowchartRenderer::new()
+            .with_mirror_reversed(true)
+            .render(&reversed)
+            .unwrap();
+        assert_eq!(mirrored, expected);
+
+        // Start sits at the bottom with arrows pointing up at its targets
+        let lines: Vec<&str> = mirrored.lines().collect();
+        assert!(lines.last().is_some_and(|l| l.contains('┘')));
+        assert!(lines[lines.len() - 2].contains("Start"));
+        assert!(
+            mirrored.contains('▲') && !mirrored.contains('▼'),
+            "{}",
+            mirrored
+        );
+    }
+
+    #[test]
+    fn test_overflow_policy_over_canvas_limit() {
+        let db = crate::parse(
+            "graph LR\n A[Gather requirements] --> B[Write the design doc] --> C[Ship it]",
+        )
+        .unwrap();
+        let limit = CanvasLimit::new(20, 5);
+
+        let summary = FlowchartRenderer::new()
+            .with_overflow(OverflowPolicy::Summary, limit)
+            .render(&db)
+            .unwrap();
+        assert!(
+            summary.starts_with("[flowchart too large to draw:"),
+            "{}",
+            summary
+        );
+        assert!(summary.ends_with("limit 20x5]"), "{}", summary);
+
+        let error = FlowchartRenderer::new()
+            .with_overflow(OverflowPolicy::Error, limit)
+            .render(&db)
+            .unwrap_err();
+        assert!(error.to_string().contains("20x5 canvas limit"), "{}", error);
+
+        let full = FlowchartRenderer::new()
+            .with_overflow(OverflowPolicy::Render, limit)
+            .render(&db)
+            .unwrap();
+        assert!(full.contains("Write the design doc"), "{}", full);
+
+        // Compact re-lays the chart with wrapped layers and short labels
+        let wide = CanvasLimit::new(60, 40);
+        let compact = FlowchartRenderer::new()
+            .with_overflow(OverflowPolicy::Compact, wide)
+            .render(&db)
+            .unwrap();
+        assert!(!compact.starts_with('['), "{}", compact);
+        assert!(
+            compact.lines().all(|l| l.chars().count() <= 60),
+            "{}",
+            compact
+        );
+    }
+
+    #[test]
+    fn test_max_width_fits_wide_chains() {
+        let db = crate::parse(
+            "graph LR\n A[Gather requirements] --> B[Write the design doc] --> C[Review] --> D[Ship it] --> E[Celebrate]",
+        )
+        .unwrap();
+
+        let unbounded = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(unbounded.lines().any(|l| l.chars().count() > 60));
+
+        for max_width in [80, 60, 40] {
+            let fitted = FlowchartRenderer::new()
+                .with_max_width(Some(max_width))
+                .render(&db)
+                .unwrap();
+            assert!(
+                fitted.lines().all(|l| l.chars().count() <= max_width),
+                "{}:\n{}",
+                max_width,
+                fitted
+            );
+            assert!(fitted.contains("Ship it"), "{}", fitted);
+        }
+
+        // Charts that already fit are drawn exactly as without a limit
+        let roomy = FlowchartRenderer::new()
+            .with_max_width(Some(500))
+            .render(&db)
+            .unwrap();
+        assert_eq!(roomy, unbounded);
+    }
+
+    #[test]
+    fn test_linear_curve_cuts_short_hops_diagonally() {
+        let db = crate::parse("graph TD\n A-->B\n B-->C\n A-->D\n D-->E\n E-->C").unwrap();
+
+        let step = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(!step.contains('╱'), "{}", step);
+
+        let linear = FlowchartRenderer::new()
+            .with_edge_curve(EdgeCurve::Linear)
+            .render(&db)
+            .unwrap();
+        let lines: Vec<&str> = linear.lines().collect();
+        let row = lines.iter().position(|l| l.contains('╱')).unwrap();
+        // The diagonal steps one column per row and ends in the arrow
+        let column = |row: usize, c: char| lines[row].chars().position(|x| x == c);
+        assert_eq!(column(row + 1, '╱'), column(row, '╱').map(|x| x - 1));
+        assert!(lines[row + 2].contains('▼'), "{}", linear);
+
+        let ascii = FlowchartRenderer::with_styles(CharacterSet::Ascii, DiamondStyle::Box)
+            .with_edge_curve(EdgeCurve::Linear)
+            .render(&db)
+            .unwrap();
+        assert!(ascii.contains('/'), "{}", ascii);
+    }
+
+    #[test]
+    fn test_aspect_hint_wraps_wide_layers() {
+        let children: String = (1..=12).map(|i| format!(" R --> N{}\n", i)).collect();
+        let db = crate::parse(&format!("graph TD\n{}", children)).unwrap();
+        let size = |text: &str| {
+            (
+                text.lines().map(visible_width).max().unwrap(),
+                text.lines().count(),
+            )
+        };
+
+        let plain = FlowchartRenderer::new().render(&db).unwrap();
+        let square = AspectRatio::new(1, 1);
+        let shaped = FlowchartRenderer::new()
+            .with_aspect_hint(Some(square))
+            .render(&db)
+            .unwrap();
+        let ((plain_w, plain_h), (shaped_w, shaped_h)) = (size(&plain), size(&shaped));
+        assert!(shaped_w < plain_w && shaped_h > plain_h, "{}", shaped);
+        assert!(square.distance(shaped_w, shaped_h) < square.distance(plain_w, plain_h));
+        assert!(shaped.contains("N12"), "{}", shaped);
+
+        // A wide target keeps the single row
+        let banner = FlowchartRenderer::new()
+            .with_aspect_hint(Some(AspectRatio::new(8, 1)))
+            .render(&db)
+            .unwrap();
+        assert_eq!(banner, plain);
+    }
+
+    #[test]
+    fn test_arrows_touch_inset_outlines() {
+        let db = crate::parse("graph TD\n A[Start] --> B{Decide}\n B --> C[End]").unwrap();
+        let output = FlowchartRenderer::with_styles(CharacterSet::Unicode, DiamondStyle::Inline)
+            .render(&db)
+            .unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        let row = lines.iter().position(|l| l.contains("Decide")).unwrap();
+        assert!(lines[row - 1].trim() == "▼", "{}", output);
+        assert!(lines[row + 1].trim() == "│", "{}", output);
+
+        let db = crate::parse("graph LR\n A[Start] --> P[/Para/]\n P --> T[/Trap\\]").unwrap();
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(output.contains("─▶/  Para/─"), "{}", output);
+        assert!(output.contains("─▶/ Trap \\"), "{}", output);
+    }
+
+    #[test]
+    fn test_beside_label_placement_ignored_for_lr() {
+        let mut db = FlowchartDatabase::with_direction(Direction::LeftRight);
+        db.add_simple_node("A", "Start").unwrap();
+        db.add_simple_node("B", "End").unwrap();
+        db.add_labeled_edge("A", "B", EdgeType::Arrow, "go")
+            .unwrap();
+
+        let auto = FlowchartRenderer::new().render(&db).unwrap();
+        let beside = FlowchartRenderer::new()
+            .with_label_placement(LabelPlacement::Beside)
+            .render(&db)
+            .unwrap();
+        assert_eq!(auto, beside);
+    }
+
+    #[test]
+    fn test_wrapped_layer_keeps_every_arrow() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_simple_node("R", "Root").unwrap();
+        for id in ["A", "B", "C", "D", "E", "F", "G"] {
+            db.add_simple_node(id, id).unwrap();
+            db.add_simple_edge("R", id).unwrap();
+        }
+
+        let renderer = FlowchartRenderer::new().with_layout_config(LayoutConfig {
+            max_layer_nodes: 3,
+            ..LayoutConfig::default()
+        });
+        let output = renderer.render(&db).unwrap();
+
+        assert_eq!(output.matches('▼').count(), 7, "{}", output);
+        // Three rows of children, each in its own band of lines
+        let child_rows = output
+            .lines()
+            .filter(|line| {
+                line.contains("│ A │") || line.contains("│ D │") || line.contains("│ G │")
+            })
+            .count();
+        assert_eq!(child_rows, 3, "{}", output);
+    }
+
+    #[test]
+    fn test_label_does_not_cover_merge_junction() {
+        let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+        db.add_simple_node("B", "Good").unwrap();
+        db.add_simple_node("C", "Bad").unwrap();
+        db.add_simple_node("D", "End").unwrap();
+        db.add_simple_edge("B", "D").unwrap();
+        db.add_labeled_edge("C", "D", EdgeType::Arrow, "retry later")
+            .unwrap();
+
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(output.contains("retry later"), "{}", output);
+        assert!(
+            output.contains("┬"),
+            "merge junction was overwritten:\n{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_lr_branch_labels_stay_outside_nodes() {
+        let mut db = FlowchartDatabase::with_direction(Direction::LeftRight);
+        db.add_shaped_node("A", "Ok?", NodeShape::Diamond).unwrap();
+        db.add_simple_node("B", "Good").unwrap();
+        db.add_simple_node("C", "Bad").unwrap();
+        db.add_labeled_edge("A", "B", EdgeType::Arrow, "Yes")
+            .unwrap();
+        db.add_labeled_edge("A", "C", EdgeType::Arrow, "No")
+            .unwrap();
+
+        let output = FlowchartRenderer::new().render(&db).unwrap();
+        assert!(output.contains("Yes"), "{}", output);
+        assert!(output.contains("No"), "{}", output);
+    }
 }
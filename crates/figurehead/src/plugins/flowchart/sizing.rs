@@ -0,0 +1,190 @@
+//! Shape-aware node sizing shared by the layout and the renderer
+//!
+//! Most shapes are a box around their label, but a few only look right in
+//! certain proportions. A terminal cell is about twice as tall as it is
+//! wide, so a circle looks round when it is twice as wide as it is tall: its
+//! label is wrapped to get close to that. A tall diamond gains a row above
+//! and below the label for every eight columns so its diagonals keep a
+//! steady slope, and a hexagon slants one column per row away from its
+//! middle, so taller hexagons need wider margins.
+//!
+//! The layout sizes nodes with [`node_size`]; the renderer draws outlines
+//! with [`diamond_inset`] and [`slant_inset`] so both agree on the shape.
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::core::{wrap_label, DiamondStyle, NodeShape};
+
+/// Columns per extra row above and below a tall diamond's label
+const DIAMOND_COLUMNS_PER_ROW: usize = 8;
+
+/// Wrap a label the way `shape` draws it
+///
+/// Circles wrap at whatever width brings them closest to a 2:1 box, never
+/// wider than `max_width` (0 = no limit); every other shape wraps at
+/// `max_width`.
+pub fn label_lines(label: &str, shape: NodeShape, max_width: usize) -> Vec<String> {
+    let lines = wrap_label(label, max_width);
+    if !matches!(shape, NodeShape::Circle | NodeShape::DoubleCircle) {
+        return lines;
+    }
+
+    let widest = label_width(&lines);
+    let longest_word = label
+        .split_whitespace()
+        .map(|w| w.width())
+        .max()
+        .unwrap_or(0);
+    let misfit = |lines: &[String]| {
+        let (width, height) = (label_width(lines) + 4, lines.len() + 2);
+        width.abs_diff(2 * height)
+    };
+    (longest_word..widest)
+        .rev()
+        .map(|width| wrap_label(label, width))
+        .fold(lines, |best, candidate| {
+            if misfit(&candidate) < misfit(&best) {
+                candidate
+            } else {
+                best
+            }
+        })
+}
+
+/// Width of the widest line
+pub fn label_width(lines: &[String]) -> usize {
+    lines.iter().map(|line| line.width()).max().unwrap_or(0)
+}
+
+/// Width and height of a node drawing `lines` as `shape`, before the
+/// configured minimum sizes are applied
+pub fn node_size(
+    shape: NodeShape,
+    lines: &[String],
+    diamond_style: DiamondStyle,
+) -> (usize, usize) {
+    let width = label_width(lines);
+    let rows = lines.len().max(1);
+    match shape {
+        NodeShape::Rectangle
+        | NodeShape::RoundedRect
+        | NodeShape::Stadium
+        | NodeShape::Subroutine
+        | NodeShape::Circle
+        | NodeShape::Terminal => (width + 4, rows + 2),
+        NodeShape::Diamond => match diamond_style {
+            DiamondStyle::Box => (width + 6, rows + 2),
+            DiamondStyle::Inline => (width + 6, rows),
+            DiamondStyle::Tall => {
+                // Even, so the two-cell points sit on the middle
+                let width = (width + 6).next_multiple_of(2);
+                let half = (width / DIAMOND_COLUMNS_PER_ROW).max(2) + rows / 2;
+                (width, 2 * half + 1)
+            }
+        },
+        NodeShape::Hexagon => {
+            // Odd, so the slants above and below the points match
+            let height = (rows + 2) | 1;
+            (width + 4 + 2 * slant_inset(height, 0), height)
+        }
+        NodeShape::DoubleCircle
+        | NodeShape::Asymmetric
+        | NodeShape::Parallelogram
+        | NodeShape::ParallelogramAlt
+        | NodeShape::Trapezoid
+        | NodeShape::InvTrapezoid => (width + 6, rows + 2),
+        NodeShape::Cylinder => (width + 6, rows + 4),
+    }
+}
+
+/// Columns between the left edge of a tall diamond and its outline on `row`
+///
+/// Zero on the middle row; on the first and last rows the outline is the
+/// two-cell point in the middle. The right side mirrors the left.
+pub fn diamond_inset(width: usize, height: usize, row: usize) -> usize {
+    let half = height / 2;
+    if half == 0 {
+        return 0;
+    }
+    let reach = (width / 2).saturating_sub(1);
+    reach * row.abs_diff(half) / half
+}
+
+/// Columns between the left edge of a hexagon and its slanted side on `row`
+pub fn slant_inset(height: usize, row: usize) -> usize {
+    row.abs_diff(height / 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size(label: &str, shape: NodeShape, style: DiamondStyle) -> (usize, usize) {
+        node_size(shape, &label_lines(label, shape, 30), style)
+    }
+
+    #[test]
+    fn test_circles_wrap_toward_twice_as_wide_as_tall() {
+        let lines = label_lines("a long circle label", NodeShape::Circle, 30);
+        assert_eq!(lines, vec!["a long", "circle", "label"]);
+        assert_eq!(
+            size("a long circle label", NodeShape::Circle, DiamondStyle::Box),
+            (10, 5)
+        );
+        // Short labels and other shapes keep a single line
+        assert_eq!(label_lines("ok", NodeShape::Circle, 30), vec!["ok"]);
+        assert_eq!(
+            label_lines("a long circle label", NodeShape::Rectangle, 30).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_tall_diamonds_grow_with_their_label() {
+        assert_eq!(size("x", NodeShape::Diamond, DiamondStyle::Tall), (8, 5));
+        let (width, height) = size(
+            "a much longer question",
+            NodeShape::Diamond,
+            DiamondStyle::Tall,
+        );
+        assert_eq!((width, height), (28, 7));
+        // Box diamonds stay three rows however long the label
+        assert_eq!(
+            size(
+                "a much longer question",
+                NodeShape::Diamond,
+                DiamondStyle::Box
+            )
+            .1,
+            3
+        );
+    }
+
+    #[test]
+    fn test_diamond_outline_meets_at_the_points() {
+        let (width, height) = (8, 5);
+        let insets: Vec<usize> = (0..height)
+            .map(|row| diamond_inset(width, height, row))
+            .collect();
+        assert_eq!(insets, vec![3, 1, 0, 1, 3]);
+        // The top point is the two middle cells
+        assert_eq!(width - 1 - insets[0], insets[0] + 1);
+    }
+
+    #[test]
+    fn test_taller_hexagons_get_wider_slants() {
+        assert_eq!(size("h", NodeShape::Hexagon, DiamondStyle::Box), (7, 3));
+        let lines = vec!["one".to_string(), "two".to_string(), "six".to_string()];
+        assert_eq!(
+            node_size(NodeShape::Hexagon, &lines, DiamondStyle::Box),
+            (11, 5)
+        );
+        assert_eq!(slant_inset(5, 0), 2);
+        // Two lines still get a middle row for the points
+        let lines = vec!["one".to_string(), "two".to_string()];
+        assert_eq!(
+            node_size(NodeShape::Hexagon, &lines, DiamondStyle::Box).1,
+            5
+        );
+    }
+}
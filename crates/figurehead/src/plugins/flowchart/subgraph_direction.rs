@@ -0,0 +1,181 @@
+//! Subgraphs laid out in their own direction
+//!
+//! A `direction LR` statement inside a subgraph lays its members out left to
+//! right whatever the diagram's direction. Such a subgraph is laid out on its
+//! own first. The outer layout then treats it as a single node of that size
+//! (its block), and once the outer nodes are placed the block is swapped back
+//! for its members. Edges inside the block keep the routes from its own
+//! layout; edges crossing its border are routed with the rest of the diagram.
+
+use anyhow::Result;
+use std::collections::HashSet;
+
+use super::{
+    FlowchartDatabase, FlowchartLayoutAlgorithm, FlowchartLayoutResult, LayoutConfig,
+    PositionedEdge, PositionedNode,
+};
+use crate::core::{Database, EdgeData, LayoutAlgorithm, NodeData, Point};
+
+/// A subgraph laid out on its own, standing in the outer layout as one node
+pub(super) struct DirectionBlock {
+    /// Id of the node standing in for the block in the outer layout
+    pub placeholder: String,
+    pub members: HashSet<String>,
+    pub layout: FlowchartLayoutResult,
+    /// Extent of the block's nodes and edges
+    pub size: (usize, usize),
+}
+
+impl DirectionBlock {
+    fn contains(&self, id: &str) -> bool {
+        self.members.contains(id)
+    }
+}
+
+/// Lay out every subgraph whose direction differs from the diagram's
+pub(super) fn direction_blocks(
+    config: &LayoutConfig,
+    database: &FlowchartDatabase,
+) -> Result<Vec<DirectionBlock>> {
+    let inner = FlowchartLayoutAlgorithm::with_config(LayoutConfig {
+        padding: 0,
+        ..config.clone()
+    });
+
+    let mut blocks = Vec::new();
+    for subgraph in database.subgraphs() {
+        let Some(direction) = subgraph.direction else {
+            continue;
+        };
+        if direction == database.direction() || subgraph.members.is_empty() {
+            continue;
+        }
+
+        let members: HashSet<String> = subgraph.members.iter().cloned().collect();
+        let mut graph = FlowchartDatabase::with_direction(direction);
+        for node in database.nodes().filter(|n| members.contains(&n.id)) {
+            graph.add_node(node.clone())?;
+        }
+        for edge in database.edges() {
+            if members.contains(&edge.from) && members.contains(&edge.to) {
+                graph.add_edge(edge.clone())?;
+            }
+        }
+
+        let layout = inner.layout(&graph)?;
+        let nodes = layout
+            .nodes
+            .iter()
+            .map(|n| Point::new(n.x + n.width, n.y + n.height));
+        let edges = layout
+            .edges
+            .iter()
+            .flat_map(|e| &e.waypoints)
+            .map(|p| Point::new(p.x + 1, p.y + 1));
+        let size = nodes
+            .chain(edges)
+            .fold((0, 0), |(w, h), p| (w.max(p.x), h.max(p.y)));
+
+        blocks.push(DirectionBlock {
+            placeholder: format!("{}::block", subgraph.id),
+            members,
+            layout,
+            size,
+        });
+    }
+    Ok(blocks)
+}
+
+/// The diagram with each block's members replaced by its placeholder
+///
+/// Edges inside a block are dropped; edges crossing into it are kept once
+/// per outer node. Subgraphs are re-added in order, so their ids match.
+pub(super) fn outer_graph(
+    database: &FlowchartDatabase,
+    blocks: &[DirectionBlock],
+) -> Result<FlowchartDatabase> {
+    let stand_in = |id: &str| -> String {
+        blocks
+            .iter()
+            .find(|b| b.contains(id))
+            .map_or(id, |b| b.placeholder.as_str())
+            .to_string()
+    };
+
+    let mut graph = FlowchartDatabase::with_direction(database.direction());
+    let mut added = HashSet::new();
+    for node in database.nodes() {
+        let id = stand_in(&node.id);
+        if added.insert(id.clone()) {
+            if id == node.id {
+                graph.add_node(node.clone())?;
+            } else {
+                graph.add_node(NodeData::new(id, ""))?;
+            }
+        }
+    }
+
+    let mut linked = HashSet::new();
+    for edge in database.edges() {
+        let (from, to) = (stand_in(&edge.from), stand_in(&edge.to));
+        let inside = from == to && from != edge.from;
+        if inside || !linked.insert((from.clone(), to.clone())) {
+            continue;
+        }
+        graph.add_edge(EdgeData {
+            from,
+            to,
+            ..edge.clone()
+        })?;
+    }
+
+    for subgraph in database.subgraphs() {
+        let mut members: Vec<String> = Vec::new();
+        for id in subgraph.members.iter().map(|m| stand_in(m)) {
+            if !members.contains(&id) {
+                members.push(id);
+            }
+        }
+        graph.add_subgraph(subgraph.title.clone(), members);
+    }
+    Ok(graph)
+}
+
+/// Whether an edge runs between two members of the same block
+pub(super) fn is_inside_block(blocks: &[DirectionBlock], edge: &EdgeData) -> bool {
+    blocks
+        .iter()
+        .any(|b| b.contains(&edge.from) && b.contains(&edge.to))
+}
+
+/// Swap each placed block for its members and return its edges, all moved to
+/// where the block was placed
+pub(super) fn expand_blocks(
+    blocks: &[DirectionBlock],
+    positioned_nodes: &mut Vec<PositionedNode>,
+) -> Vec<PositionedEdge> {
+    let mut edges = Vec::new();
+    for block in blocks {
+        let Some(index) = positioned_nodes
+            .iter()
+            .position(|n| n.id == block.placeholder)
+        else {
+            continue;
+        };
+        let origin = positioned_nodes.remove(index);
+        let shift = |p: Point| Point::new(p.x + origin.x, p.y + origin.y);
+
+        positioned_nodes.extend(block.layout.nodes.iter().map(|n| PositionedNode {
+            x: n.x + origin.x,
+            y: n.y + origin.y,
+            ..n.clone()
+        }));
+        edges.extend(block.layout.edges.iter().map(|e| PositionedEdge {
+            waypoints: e.waypoints.iter().copied().map(shift).collect(),
+            junction: e.junction.map(shift),
+            merge_junction: e.merge_junction.map(shift),
+            ..e.clone()
+        }));
+    }
+    edges
+}
@@ -0,0 +1,311 @@
+//! Layout invariant checks
+//!
+//! [`validate_layout`] inspects a [`FlowchartLayoutResult`] without rendering
+//! it. The renderer runs it in debug builds, and callers that build or patch
+//! layouts by hand can use it before drawing.
+
+use std::fmt;
+
+use super::{FlowchartLayoutResult, PositionedNode};
+use crate::core::Point;
+
+/// Which end of an edge a violation refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeEnd {
+    Start,
+    End,
+}
+
+impl fmt::Display for EdgeEnd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeEnd::Start => write!(f, "start"),
+            EdgeEnd::End => write!(f, "end"),
+        }
+    }
+}
+
+/// A broken layout invariant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutViolation {
+    /// Two node boxes share at least one cell
+    NodeOverlap { first: String, second: String },
+    /// A node, subgraph or edge waypoint lies outside the canvas
+    OutOfBounds {
+        element: String,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    },
+    /// An edge refers to a node that is not in the layout
+    MissingNode {
+        from: String,
+        to: String,
+        missing: String,
+    },
+    /// An edge endpoint is not on or next to its node
+    DanglingEndpoint {
+        from: String,
+        to: String,
+        end: EdgeEnd,
+        point: Point,
+    },
+}
+
+impl fmt::Display for LayoutViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutViolation::NodeOverlap { first, second } => {
+                write!(f, "nodes '{}' and '{}' overlap", first, second)
+            }
+            LayoutViolation::OutOfBounds {
+                element,
+                x,
+                y,
+                width,
+                height,
+            } => write!(
+                f,
+                "{} reaches ({}, {}) outside the {}x{} canvas",
+                element, x, y, width, height
+            ),
+            LayoutViolation::MissingNode { from, to, missing } => write!(
+                f,
+                "edge {} -> {} references node '{}' missing from the layout",
+                from, to, missing
+            ),
+            LayoutViolation::DanglingEndpoint {
+                from,
+                to,
+                end,
+                point,
+            } => write!(
+                f,
+                "edge {} -> {} {} {:?} is not attached to its node",
+                from, to, end, point
+            ),
+        }
+    }
+}
+
+/// Check a layout for overlapping nodes, out-of-bounds coordinates and
+/// dangling edge endpoints
+///
+/// Returns every violation found; an empty list means the layout is sound.
+///
+/// # Example
+/// ```rust
+/// use figurehead::unstable::flowchart::{validate_layout, FlowchartLayoutAlgorithm};
+/// use figurehead::unstable::LayoutAlgorithm;
+///
+/// let db = figurehead::parse("graph TD; A-->B; A-->C").unwrap();
+/// let layout = FlowchartLayoutAlgorithm::new().layout(&db).unwrap();
+/// assert!(validate_layout(&layout).is_empty());
+/// ```
+pub fn validate_layout(layout: &FlowchartLayoutResult) -> Vec<LayoutViolation> {
+    let mut violations = Vec::new();
+    check_overlaps(layout, &mut violations);
+    check_bounds(layout, &mut violations);
+    check_endpoints(layout, &mut violations);
+    violations
+}
+
+fn overlaps(a: &PositionedNode, b: &PositionedNode) -> bool {
+    a.x < b.x + b.width && b.x < a.x + a.width && a.y < b.y + b.height && b.y < a.y + a.height
+}
+
+/// Distance in cells from a point to a node box (0 when inside)
+fn distance_to_box(node: &PositionedNode, Point { x, y }: Point) -> usize {
+    let right = node.x + node.width.max(1) - 1;
+    let bottom = node.y + node.height.max(1) - 1;
+    let dx = if x < node.x {
+        node.x - x
+    } else {
+        x.saturating_sub(right)
+    };
+    let dy = if y < node.y {
+        node.y - y
+    } else {
+        y.saturating_sub(bottom)
+    };
+    dx.max(dy)
+}
+
+fn check_overlaps(layout: &FlowchartLayoutResult, violations: &mut Vec<LayoutViolation>) {
+    for (i, a) in layout.nodes.iter().enumerate() {
+        for b in &layout.nodes[i + 1..] {
+            if overlaps(a, b) {
+                violations.push(LayoutViolation::NodeOverlap {
+                    first: a.id.clone(),
+                    second: b.id.clone(),
+                });
+            }
+        }
+    }
+}
+
+fn check_bounds(layout: &FlowchartLayoutResult, violations: &mut Vec<LayoutViolation>) {
+    let (width, height) = (layout.width, layout.height);
+    let mut check = |element: String, right: usize, bottom: usize| {
+        if right >= width || bottom >= height {
+            violations.push(LayoutViolation::OutOfBounds {
+                element,
+                x: right,
+                y: bottom,
+                width,
+                height,
+            });
+        }
+    };
+
+    for node in &layout.nodes {
+        check(
+            format!("node '{}'", node.id),
+            (node.x + node.width).saturating_sub(1),
+            (node.y + node.height).saturating_sub(1),
+        );
+    }
+    for subgraph in &layout.subgraphs {
+        check(
+            format!("subgraph '{}'", subgraph.id),
+            (subgraph.x + subgraph.width).saturating_sub(1),
+            (subgraph.y + subgraph.height).saturating_sub(1),
+        );
+    }
+    for edge in &layout.edges {
+        for point in &edge.waypoints {
+            check(
+                format!("edge {} -> {}", edge.from_id, edge.to_id),
+                point.x,
+                point.y,
+            );
+        }
+    }
+}
+
+fn check_endpoints(layout: &FlowchartLayoutResult, violations: &mut Vec<LayoutViolation>) {
+    let find = |id: &str| layout.nodes.iter().find(|n| n.id == id);
+
+    for edge in &layout.edges {
+        let ends = [
+            (EdgeEnd::Start, &edge.from_id, edge.waypoints.first()),
+            (EdgeEnd::End, &edge.to_id, edge.waypoints.last()),
+        ];
+        for (end, id, point) in ends {
+            let Some(node) = find(id) else {
+                violations.push(LayoutViolation::MissingNode {
+                    from: edge.from_id.clone(),
+                    to: edge.to_id.clone(),
+                    missing: id.clone(),
+                });
+                continue;
+            };
+            if let Some(&point) = point {
+                if distance_to_box(node, point) > 1 {
+                    violations.push(LayoutViolation::DanglingEndpoint {
+                        from: edge.from_id.clone(),
+                        to: edge.to_id.clone(),
+                        end,
+                        point,
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::flowchart::PositionedEdge;
+
+    fn node(id: &str, x: usize, y: usize) -> PositionedNode {
+        PositionedNode {
+            id: id.to_string(),
+            x,
+            y,
+            width: 5,
+            height: 3,
+        }
+    }
+
+    fn edge(from: &str, to: &str, waypoints: Vec<(usize, usize)>) -> PositionedEdge {
+        PositionedEdge {
+            from_id: from.to_string(),
+            to_id: to.to_string(),
+            waypoints: waypoints.into_iter().map(Point::from).collect(),
+            junction: None,
+            merge_junction: None,
+            group_index: None,
+            group_size: None,
+            lane: None,
+        }
+    }
+
+    fn layout(nodes: Vec<PositionedNode>, edges: Vec<PositionedEdge>) -> FlowchartLayoutResult {
+        FlowchartLayoutResult {
+            nodes,
+            edges,
+            subgraphs: vec![],
+            width: 12,
+            height: 10,
+        }
+    }
+
+    #[test]
+    fn test_valid_layout_has_no_violations() {
+        let result = layout(
+            vec![node("A", 0, 0), node("B", 0, 6)],
+            vec![edge("A", "B", vec![(2, 3), (2, 5)])],
+        );
+        assert!(validate_layout(&result).is_empty());
+    }
+
+    #[test]
+    fn test_detects_overlap() {
+        let result = layout(vec![node("A", 0, 0), node("B", 4, 2)], vec![]);
+        assert_eq!(
+            validate_layout(&result),
+            vec![LayoutViolation::NodeOverlap {
+                first: "A".to_string(),
+                second: "B".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_detects_out_of_bounds() {
+        let result = layout(
+            vec![node("A", 8, 0)],
+            vec![edge("A", "A", vec![(9, 3), (9, 10)])],
+        );
+        let violations = validate_layout(&result);
+        assert_eq!(violations.len(), 3, "{:?}", violations);
+        assert!(violations
+            .iter()
+            .all(|v| !matches!(v, LayoutViolation::NodeOverlap { .. })));
+        assert!(violations[0].to_string().contains("node 'A'"));
+    }
+
+    #[test]
+    fn test_detects_dangling_and_missing_endpoints() {
+        let result = layout(
+            vec![node("A", 0, 0), node("B", 0, 6)],
+            vec![
+                edge("A", "B", vec![(2, 3), (9, 9)]),
+                edge("A", "Z", vec![(2, 3), (2, 5)]),
+            ],
+        );
+        let violations = validate_layout(&result);
+        assert!(violations.contains(&LayoutViolation::DanglingEndpoint {
+            from: "A".to_string(),
+            to: "B".to_string(),
+            end: EdgeEnd::End,
+            point: Point::new(9, 9),
+        }));
+        assert!(violations
+            .iter()
+            .any(|v| matches!(v, LayoutViolation::MissingNode { missing, .. } if missing == "Z")));
+    }
+}
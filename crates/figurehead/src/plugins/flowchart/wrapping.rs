@@ -0,0 +1,249 @@
+//! Wrapping wide layers onto extra rows
+//!
+//! One root with twenty children puts twenty nodes in a single layer, which
+//! makes the diagram far wider than it is tall. With
+//! [`LayoutConfig::max_layer_nodes`](super::LayoutConfig::max_layer_nodes)
+//! set, a layer holding more nodes is split into rows that share its rank
+//! band. Rows sit on a common slot grid, so the gaps between slots line up;
+//! an edge into a later row drops through those gaps (its lane) and turns
+//! into the target along the channel just before that row.
+//!
+//! Rows are ordered by distance from the previous layer. Leaves with several
+//! incoming edges stay in the first row so merges are drawn as usual, and
+//! nodes with outgoing edges stay in the last row so their edges leave the
+//! band unobstructed.
+
+use super::FlowchartDatabase;
+use crate::core::{Direction, Point};
+
+/// Rows between two wrapped rows of a band: blank, channel, arrow
+pub(crate) const WRAP_ROW_SEP: usize = 3;
+
+/// Split a layer into rows of at most `max` nodes
+///
+/// Returns the layer as a single row when it fits, when `max` is 0, or when
+/// a node both merges and fans out (it needs unobstructed edges on both
+/// sides of the band). Rows keep the layer's order.
+pub(crate) fn wrap_layer<'a>(
+    database: &FlowchartDatabase,
+    layer: &[&'a str],
+    max: usize,
+) -> Vec<Vec<&'a str>> {
+    if max == 0 || layer.len() <= max {
+        return vec![layer.to_vec()];
+    }
+
+    let mut first = Vec::new();
+    let mut last = Vec::new();
+    let mut flexible = Vec::new();
+    for (index, &node_id) in layer.iter().enumerate() {
+        let incoming = database.predecessors(node_id).len();
+        let outgoing = database.successors(node_id).len();
+        match (incoming > 1, outgoing > 0) {
+            (true, true) => return vec![layer.to_vec()],
+            (true, false) => first.push(index),
+            (false, true) => last.push(index),
+            (false, false) => flexible.push(index),
+        }
+    }
+
+    let row_count = layer.len().div_ceil(max);
+    let mut rows: Vec<Vec<usize>> = vec![Vec::new(); row_count];
+    rows[0] = first;
+    rows[row_count - 1] = last;
+
+    // Fill rows front to back; anything left over (when the fixed nodes
+    // overflow their rows) goes to the shortest row
+    let mut flexible = flexible.into_iter();
+    for row in rows.iter_mut() {
+        while row.len() < max {
+            match flexible.next() {
+                Some(index) => row.push(index),
+                None => break,
+            }
+        }
+    }
+    for index in flexible {
+        if let Some(row) = rows.iter_mut().min_by_key(|row| row.len()) {
+            row.push(index);
+        }
+    }
+
+    rows.into_iter()
+        .filter(|row| !row.is_empty())
+        .map(|mut row| {
+            row.sort_unstable();
+            row.into_iter().map(|index| layer[index]).collect()
+        })
+        .collect()
+}
+
+/// Gap between slots of a band with `rows` rows
+///
+/// Each row after the first needs its own lane, with a blank column (or row)
+/// on either side.
+pub(crate) fn lane_gap(rows: usize, node_sep: usize) -> usize {
+    node_sep.max(2 * rows.saturating_sub(1) + 1)
+}
+
+/// Offset of a row's lane from the far edge of its slot
+pub(crate) fn lane_offset(row: usize) -> usize {
+    2 * row.saturating_sub(1) + 1
+}
+
+/// Waypoints for a forward edge into a node on a wrapped row
+///
+/// The edge leaves the source as usual, turns onto `lane` one cell later,
+/// follows the lane past the earlier rows and enters the target through the
+/// channel in front of it.
+pub(crate) fn lane_route(
+    direction: Direction,
+    from: &super::PositionedNode,
+    to: &super::PositionedNode,
+    lane: usize,
+) -> Vec<Point> {
+    let (from, to) = (from.rect(), to.rect());
+    let exit = from.exit(direction);
+    let entry = to.entry(direction);
+    let arrow_end = match direction {
+        // Up- and left-going routes end on the target's border
+        Direction::BottomUp => entry.offset(0, -1),
+        Direction::RightLeft => entry.offset(-1, 0),
+        _ => entry,
+    };
+    // Turn one cell past the exit; the channel runs just before the arrow
+    let turn = exit.step(direction, 1);
+    let channel = arrow_end.step(direction, -2);
+
+    let points = if direction.is_vertical() {
+        vec![
+            exit,
+            turn,
+            Point::new(lane, turn.y),
+            Point::new(lane, channel.y),
+            Point::new(entry.x, channel.y),
+            arrow_end,
+        ]
+    } else {
+        vec![
+            exit,
+            turn,
+            Point::new(turn.x, lane),
+            Point::new(channel.x, lane),
+            Point::new(channel.x, entry.y),
+            arrow_end,
+        ]
+    };
+    simplify_path(points)
+}
+
+/// Drop repeated points and points in the middle of straight runs
+fn simplify_path(points: Vec<Point>) -> Vec<Point> {
+    let mut path: Vec<Point> = Vec::with_capacity(points.len());
+    for point in points {
+        if path.last() == Some(&point) {
+            continue;
+        }
+        if let [.., a, b] = path.as_slice() {
+            let straight = (a.x == b.x && b.x == point.x) || (a.y == b.y && b.y == point.y);
+            if straight {
+                path.pop();
+            }
+        }
+        path.push(point);
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::flowchart::PositionedNode;
+
+    fn fan_out(children: usize) -> FlowchartDatabase {
+        let mut db = FlowchartDatabase::new();
+        db.add_simple_node("R", "Root").unwrap();
+        for i in 0..children {
+            let id = format!("C{:02}", i);
+            db.add_simple_node(&id, &id).unwrap();
+            db.add_simple_edge("R", &id).unwrap();
+        }
+        db
+    }
+
+    fn points(coords: &[(usize, usize)]) -> Vec<Point> {
+        coords.iter().map(|&c| Point::from(c)).collect()
+    }
+
+    fn node(id: &str, x: usize, y: usize) -> PositionedNode {
+        PositionedNode {
+            id: id.to_string(),
+            x,
+            y,
+            width: 7,
+            height: 3,
+        }
+    }
+
+    #[test]
+    fn test_small_layer_is_not_wrapped() {
+        let db = fan_out(3);
+        let layer = ["C00", "C01", "C02"];
+        assert_eq!(wrap_layer(&db, &layer, 0), vec![layer.to_vec()]);
+        assert_eq!(wrap_layer(&db, &layer, 3), vec![layer.to_vec()]);
+    }
+
+    #[test]
+    fn test_wide_layer_splits_in_order() {
+        let db = fan_out(5);
+        let layer = ["C00", "C01", "C02", "C03", "C04"];
+        assert_eq!(
+            wrap_layer(&db, &layer, 2),
+            vec![vec!["C00", "C01"], vec!["C02", "C03"], vec!["C04"]]
+        );
+    }
+
+    #[test]
+    fn test_nodes_with_children_stay_in_last_row() {
+        let mut db = fan_out(4);
+        db.add_simple_node("X", "X").unwrap();
+        db.add_simple_edge("C00", "X").unwrap();
+        let layer = ["C00", "C01", "C02", "C03"];
+        assert_eq!(
+            wrap_layer(&db, &layer, 2),
+            vec![vec!["C01", "C02"], vec!["C00", "C03"]]
+        );
+    }
+
+    #[test]
+    fn test_merging_node_with_children_blocks_wrapping() {
+        let mut db = fan_out(4);
+        db.add_simple_node("Q", "Q").unwrap();
+        db.add_simple_edge("Q", "C00").unwrap();
+        db.add_simple_node("X", "X").unwrap();
+        db.add_simple_edge("C00", "X").unwrap();
+        let layer = ["C00", "C01", "C02", "C03"];
+        assert_eq!(wrap_layer(&db, &layer, 2), vec![layer.to_vec()]);
+    }
+
+    #[test]
+    fn test_lane_route_top_down() {
+        let from = node("R", 10, 1);
+        let to = node("C", 2, 12);
+        assert_eq!(
+            lane_route(Direction::TopDown, &from, &to, 9),
+            points(&[(13, 4), (13, 5), (9, 5), (9, 10), (5, 10), (5, 12)])
+        );
+    }
+
+    #[test]
+    fn test_lane_route_drops_straight_runs() {
+        // Source centre already on the lane: no jog at the turn row
+        let from = node("R", 6, 1);
+        let to = node("C", 2, 12);
+        assert_eq!(
+            lane_route(Direction::TopDown, &from, &to, 9),
+            points(&[(9, 4), (9, 10), (5, 10), (5, 12)])
+        );
+    }
+}
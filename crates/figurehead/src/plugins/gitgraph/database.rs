@@ -2,7 +2,8 @@
 //!
 //! Stores commits, branches, and their relationships.
 
-use crate::core::{Database, Direction, EdgeData, NodeData, NodeShape};
+use super::GitGraphOptions;
+use crate::core::{Database, Direction, EdgeData, NodeData, NodeShape, ParseWarning};
 use anyhow::Result;
 use std::collections::{HashMap, HashSet};
 use tracing::debug;
@@ -10,27 +11,68 @@ use tracing::debug;
 /// Git graph database
 pub struct GitGraphDatabase {
     nodes: HashMap<String, NodeData>,
+    /// Node ids in the order they were added
+    order: Vec<String>,
     edges: Vec<EdgeData>,
     direction: Direction,
+    /// Branch each commit was made on (commit id -> branch name)
+    branches: HashMap<String, String>,
+    /// Branch each branch-name marker belongs to (marker id -> branch name)
+    markers: HashMap<String, String>,
+    /// Branch names in the order they first appeared
+    branch_order: Vec<String>,
+    /// Tags attached to each tagged commit (commit id -> tags)
+    tags: HashMap<String, Vec<String>>,
+    /// Summary rows that stand for a folded section of commits
+    folded: HashSet<String>,
+    options: GitGraphOptions,
+    /// Warnings recorded while parsing into this database
+    warnings: Vec<ParseWarning>,
 }
 
 impl GitGraphDatabase {
+    /// Create an empty top-down database
     pub fn new() -> Self {
         Self {
             nodes: HashMap::new(),
+            order: Vec::new(),
             edges: Vec::new(),
             direction: Direction::TopDown, // Default to top-down, but can be changed
+            branches: HashMap::new(),
+            markers: HashMap::new(),
+            branch_order: Vec::new(),
+            tags: HashMap::new(),
+            folded: HashSet::new(),
+            options: GitGraphOptions::default(),
+            warnings: Vec::new(),
         }
     }
 
+    /// Create an empty database with the given direction
     pub fn with_direction(direction: Direction) -> Self {
         Self {
             nodes: HashMap::new(),
+            order: Vec::new(),
             edges: Vec::new(),
             direction,
+            branches: HashMap::new(),
+            markers: HashMap::new(),
+            branch_order: Vec::new(),
+            tags: HashMap::new(),
+            folded: HashSet::new(),
+            options: GitGraphOptions::default(),
+            warnings: Vec::new(),
         }
     }
 
+    /// Record a parse warning
+    pub(crate) fn add_parse_warning(&mut self, warning: ParseWarning) {
+        self.warnings.push(warning);
+    }
+
+    /// Add a commit, labeled with its message or id
+    ///
+    /// Adding an existing id again is a no-op.
     pub fn add_commit(
         &mut self,
         id: impl Into<String>,
@@ -44,11 +86,13 @@ impl GitGraphDatabase {
         }
 
         let node = NodeData::with_shape(&id, &label, NodeShape::Circle);
+        self.order.push(id.clone());
         self.nodes.insert(id.clone(), node);
         debug!(commit_id = %id, "Added commit to database");
         Ok(())
     }
 
+    /// Add an edge from a commit to its parent, creating missing commits
     pub fn add_parent_edge(
         &mut self,
         child: impl Into<String>,
@@ -81,8 +125,19 @@ impl Database for GitGraphDatabase {
     type Node = NodeData;
     type Edge = EdgeData;
 
+    fn parse_warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    fn take_parse_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
     fn add_node(&mut self, node: NodeData) -> Result<()> {
         let id = node.id.clone();
+        if !self.nodes.contains_key(&id) {
+            self.order.push(id.clone());
+        }
         self.nodes.insert(id, node);
         Ok(())
     }
@@ -113,7 +168,7 @@ impl Database for GitGraphDatabase {
     }
 
     fn nodes(&self) -> impl Iterator<Item = &NodeData> {
-        self.nodes.values()
+        self.order.iter().map(|id| &self.nodes[id])
     }
 
     fn edges(&self) -> impl Iterator<Item = &EdgeData> {
@@ -122,23 +177,129 @@ impl Database for GitGraphDatabase {
 
     fn clear(&mut self) {
         self.nodes.clear();
+        self.order.clear();
         self.edges.clear();
+        self.branches.clear();
+        self.markers.clear();
+        self.branch_order.clear();
+        self.tags.clear();
+        self.folded.clear();
+        self.warnings.clear();
     }
 }
 
 impl GitGraphDatabase {
+    /// Whether a commit with this id exists
     pub fn has_node(&self, id: &str) -> bool {
         self.nodes.contains_key(id)
     }
 
+    /// Direction the graph grows in
     pub fn direction(&self) -> Direction {
         self.direction
     }
 
+    /// Set the direction the graph grows in
     pub fn set_direction(&mut self, direction: Direction) {
         self.direction = direction;
     }
 
+    /// Options from the diagram's `options` block
+    pub fn options(&self) -> &GitGraphOptions {
+        &self.options
+    }
+
+    /// Replace the diagram options
+    pub fn set_options(&mut self, options: GitGraphOptions) {
+        self.options = options;
+    }
+
+    /// Record the branch a commit was made on
+    pub fn set_commit_branch(&mut self, id: impl Into<String>, branch: impl Into<String>) {
+        let branch = branch.into();
+        self.declare_branch(&branch);
+        self.branches.insert(id.into(), branch);
+    }
+
+    /// Record that a node is the name marker of a branch
+    ///
+    /// Markers share their branch's lane but are not counted as commits.
+    pub fn set_branch_marker(&mut self, id: impl Into<String>, branch: impl Into<String>) {
+        let branch = branch.into();
+        self.declare_branch(&branch);
+        self.markers.insert(id.into(), branch);
+    }
+
+    /// Returns true if the node names a branch rather than being a commit
+    pub fn is_branch_marker(&self, id: &str) -> bool {
+        self.markers.contains_key(id)
+    }
+
+    /// Attach a tag to a commit (`commit tag: "v1.0"`), replacing any others
+    pub fn set_commit_tag(&mut self, id: impl Into<String>, tag: impl Into<String>) {
+        self.tags.insert(id.into(), vec![tag.into()]);
+    }
+
+    /// Attach another tag to a commit, keeping the ones it already has
+    pub fn add_commit_tag(&mut self, id: impl Into<String>, tag: impl Into<String>) {
+        self.tags.entry(id.into()).or_default().push(tag.into());
+    }
+
+    /// First tag attached to a commit, if any
+    pub fn commit_tag(&self, id: &str) -> Option<&str> {
+        self.commit_tags(id).first().map(|s| s.as_str())
+    }
+
+    /// Every tag attached to a commit, in the order they were added
+    pub fn commit_tags(&self, id: &str) -> &[String] {
+        self.tags.get(id).map_or(&[], |tags| tags.as_slice())
+    }
+
+    /// Mark a node as the summary row of a folded section
+    pub fn mark_folded(&mut self, id: impl Into<String>) {
+        self.folded.insert(id.into());
+    }
+
+    /// Returns true if the node is a summary row produced by folding
+    pub fn is_folded(&self, id: &str) -> bool {
+        self.folded.contains(id)
+    }
+
+    /// Note a branch name, keeping the order branches first appeared in
+    ///
+    /// Lanes and branch colors follow this order, so a branch keeps its lane
+    /// however its commits interleave with other branches.
+    pub fn declare_branch(&mut self, branch: &str) {
+        if !self.branch_order.iter().any(|b| b == branch) {
+            self.branch_order.push(branch.to_string());
+        }
+    }
+
+    /// Branch names in the order they first appeared
+    pub fn branch_names(&self) -> &[String] {
+        &self.branch_order
+    }
+
+    /// Branch a commit or branch marker belongs to, if known
+    pub fn node_branch(&self, id: &str) -> Option<&str> {
+        self.commit_branch(id)
+            .or_else(|| self.markers.get(id).map(|s| s.as_str()))
+    }
+
+    /// Get the branch a commit was made on, if known
+    pub fn commit_branch(&self, id: &str) -> Option<&str> {
+        self.branches.get(id).map(|s| s.as_str())
+    }
+
+    /// Count of nodes that are commits with a known branch
+    pub fn commit_count(&self) -> usize {
+        self.branches
+            .keys()
+            .filter(|id| self.nodes.contains_key(id.as_str()))
+            .count()
+    }
+
+    /// Commits with no children (branch tips)
     pub fn source_nodes(&self) -> Vec<&str> {
         let targets: HashSet<&str> = self.edges.iter().map(|e| e.to.as_str()).collect();
         self.nodes
@@ -148,6 +309,7 @@ impl GitGraphDatabase {
             .collect()
     }
 
+    /// Commits with no parents (roots)
     pub fn sink_nodes(&self) -> Vec<&str> {
         let sources: HashSet<&str> = self.edges.iter().map(|e| e.from.as_str()).collect();
         self.nodes
@@ -157,6 +319,7 @@ impl GitGraphDatabase {
             .collect()
     }
 
+    /// Children of a commit
     pub fn predecessors(&self, id: &str) -> Vec<&str> {
         self.edges
             .iter()
@@ -165,6 +328,7 @@ impl GitGraphDatabase {
             .collect()
     }
 
+    /// Parents of a commit
     pub fn successors(&self, id: &str) -> Vec<&str> {
         self.edges
             .iter()
@@ -173,14 +337,25 @@ impl GitGraphDatabase {
             .collect()
     }
 
+    /// Number of children of a commit
     pub fn in_degree(&self, id: &str) -> usize {
         self.edges.iter().filter(|e| e.to == id).count()
     }
 
+    /// Number of parents of a commit
     pub fn out_degree(&self, id: &str) -> usize {
         self.edges.iter().filter(|e| e.from == id).count()
     }
 
+    /// Node ids in the order they were added
+    ///
+    /// Commands add commits after their parents, so for parsed diagrams this
+    /// is the history in the order it was written.
+    pub fn chronological_order(&self) -> Vec<&str> {
+        self.order.iter().map(|id| id.as_str()).collect()
+    }
+
+    /// Commits ordered so that every commit comes before its parents
     pub fn topological_sort(&self) -> Vec<&str> {
         // For git graphs, we want commits in chronological order (oldest first)
         // This is a simple topological sort using Kahn's algorithm
@@ -255,6 +430,22 @@ mod tests {
         assert!(db.has_node("c1"));
     }
 
+    #[test]
+    fn test_nodes_keep_the_order_they_were_added() {
+        let mut db = GitGraphDatabase::new();
+        for id in ["b", "c", "a"] {
+            db.add_commit(id, None::<String>).unwrap();
+        }
+        db.add_node(NodeData::new("c", "again")).unwrap();
+        db.set_commit_tag("c", "v1");
+
+        assert_eq!(db.chronological_order(), ["b", "c", "a"]);
+        let labels: Vec<_> = db.nodes().map(|n| n.label.as_str()).collect();
+        assert_eq!(labels, ["b", "again", "a"]);
+        assert_eq!(db.commit_tag("c"), Some("v1"));
+        assert_eq!(db.commit_tag("a"), None);
+    }
+
     #[test]
     fn test_add_parent_edge() {
         let mut db = GitGraphDatabase::new();
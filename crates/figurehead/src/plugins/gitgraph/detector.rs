@@ -2,7 +2,7 @@
 //!
 //! Detects git graph diagram syntax patterns.
 
-use crate::core::Detector;
+use crate::core::{header_keyword, Detector};
 use tracing::{debug, info, trace};
 
 /// Git graph detector implementation
@@ -15,6 +15,7 @@ impl Default for GitGraphDetector {
 }
 
 impl GitGraphDetector {
+    /// Create a detector
     pub fn new() -> Self {
         Self
     }
@@ -99,6 +100,10 @@ impl Detector for GitGraphDetector {
         "gitgraph"
     }
 
+    fn prefix_confidence(&self, header: &str) -> Option<f64> {
+        header_keyword(header.trim_end_matches(':'), "gitGraph").then_some(1.0)
+    }
+
     fn patterns(&self) -> Vec<&'static str> {
         vec![
             "gitGraph",
@@ -137,6 +142,16 @@ mod tests {
         assert!(detector.confidence("commit\n   branch develop") > 0.3);
     }
 
+    #[test]
+    fn test_detects_colon_header_and_options_block() {
+        let detector = GitGraphDetector::new();
+        let input =
+            "gitGraph TB:\n  options\n  {\n    \"showBranches\": false\n  }\n  end\n  commit";
+        assert!(detector.detect(input));
+        assert!(detector.confidence(input) > 0.5);
+        assert!(detector.detect("gitGraph:\n  commit"));
+    }
+
     #[test]
     fn test_rejects_non_git_graph() {
         let detector = GitGraphDetector::new();
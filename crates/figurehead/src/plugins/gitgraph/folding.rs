@@ -0,0 +1,240 @@
+//! Section folding for long git graphs
+//!
+//! Long histories are collapsed so they stay terminal-sized: each run of
+//! consecutive commits on the same branch (a "section") is replaced by a
+//! single summary row. Individual sections can be kept expanded by name.
+
+use std::collections::{HashMap, HashSet};
+
+use super::GitGraphDatabase;
+use crate::core::{Database, EdgeData, NodeData};
+
+/// Default number of commits a graph may have before it is folded
+pub const DEFAULT_FOLD_THRESHOLD: usize = 30;
+
+/// Section name that expands every section
+pub const EXPAND_ALL: &str = "*";
+
+/// Options controlling how git graphs are folded by section
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldOptions {
+    /// Graphs with at most this many commits are rendered unfolded
+    pub threshold: usize,
+    /// Sections (branch names) that stay expanded; `*` expands everything
+    pub expand: Vec<String>,
+}
+
+impl FoldOptions {
+    /// Fold graphs with more than `threshold` commits
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            expand: Vec::new(),
+        }
+    }
+
+    /// Never fold
+    pub fn disabled() -> Self {
+        Self::new(usize::MAX)
+    }
+
+    /// Keep the given section expanded
+    pub fn with_expanded(mut self, section: impl Into<String>) -> Self {
+        self.expand.push(section.into());
+        self
+    }
+
+    /// Returns true if the named section should stay expanded
+    pub fn is_expanded(&self, section: &str) -> bool {
+        self.expand
+            .iter()
+            .any(|s| s == EXPAND_ALL || s.eq_ignore_ascii_case(section))
+    }
+}
+
+impl Default for FoldOptions {
+    fn default() -> Self {
+        Self::new(DEFAULT_FOLD_THRESHOLD)
+    }
+}
+
+/// Fold a git graph by section
+///
+/// Returns `None` when the graph is short enough (or every section is
+/// expanded) so the caller can render the original database unchanged.
+pub fn fold_sections(
+    database: &GitGraphDatabase,
+    options: &FoldOptions,
+) -> Option<GitGraphDatabase> {
+    if database.commit_count() <= options.threshold {
+        return None;
+    }
+
+    // Group consecutive commits on the same branch in chronological order
+    let order = database.chronological_order();
+    let mut runs: Vec<(Option<&str>, Vec<&str>)> = Vec::new();
+    for id in order {
+        let branch = database
+            .commit_branch(id)
+            .filter(|branch| !options.is_expanded(branch));
+        match runs.last_mut() {
+            Some((Some(run_branch), members)) if branch == Some(*run_branch) => members.push(id),
+            _ => runs.push((branch, vec![id])),
+        }
+    }
+
+    if runs
+        .iter()
+        .all(|(branch, members)| branch.is_none() || members.len() < 2)
+    {
+        return None;
+    }
+
+    let mut folded = GitGraphDatabase::with_direction(database.direction());
+    folded.set_options(database.options().clone());
+    for branch in database.branch_names() {
+        folded.declare_branch(branch);
+    }
+    let mut representative: HashMap<&str, String> = HashMap::new();
+
+    for (index, (branch, members)) in runs.iter().enumerate() {
+        match branch {
+            Some(branch) if members.len() >= 2 => {
+                let id = format!("fold_{}", index);
+                let label = format!("{}: {} commits", branch, members.len());
+                folded.add_node(NodeData::new(&id, &label)).ok()?;
+                folded.mark_folded(&id);
+                folded.set_commit_branch(&id, *branch);
+                for member in members {
+                    // Tags of hidden commits stay visible on the summary row
+                    for tag in database.commit_tags(member) {
+                        folded.add_commit_tag(&id, tag);
+                    }
+                    representative.insert(member, id.clone());
+                }
+            }
+            _ => {
+                for member in members {
+                    if let Some(node) = database.get_node(member) {
+                        folded.add_node(node.clone()).ok()?;
+                        if let Some(branch) = database.commit_branch(member) {
+                            folded.set_commit_branch(*member, branch);
+                        } else if let Some(branch) = database.node_branch(member) {
+                            folded.set_branch_marker(*member, branch);
+                        }
+                        for tag in database.commit_tags(member) {
+                            folded.add_commit_tag(*member, tag);
+                        }
+                    }
+                    representative.insert(member, member.to_string());
+                }
+            }
+        }
+    }
+
+    // Re-point edges at the summary rows, dropping ones that fold away
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+    for edge in database.edges() {
+        let (Some(from), Some(to)) = (
+            representative.get(edge.from.as_str()),
+            representative.get(edge.to.as_str()),
+        ) else {
+            continue;
+        };
+        if from == to || !seen.insert((from.clone(), to.clone())) {
+            continue;
+        }
+        let mut remapped = EdgeData::new(from, to);
+        remapped.edge_type = edge.edge_type;
+        remapped.label = edge.label.clone();
+        folded.add_edge(remapped).ok()?;
+    }
+
+    Some(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linear_history(branch: &str, count: usize) -> GitGraphDatabase {
+        let mut db = GitGraphDatabase::new();
+        for i in 0..count {
+            let id = format!("c{}", i);
+            db.add_commit(&id, None::<String>).unwrap();
+            db.set_commit_branch(&id, branch);
+            if i > 0 {
+                db.add_parent_edge(&id, format!("c{}", i - 1)).unwrap();
+            }
+        }
+        db
+    }
+
+    #[test]
+    fn test_short_graph_is_not_folded() {
+        let db = linear_history("main", 5);
+        assert!(fold_sections(&db, &FoldOptions::new(10)).is_none());
+    }
+
+    #[test]
+    fn test_long_run_folds_into_summary() {
+        let db = linear_history("main", 12);
+        let folded = fold_sections(&db, &FoldOptions::new(10)).unwrap();
+
+        assert_eq!(folded.node_count(), 1);
+        assert_eq!(folded.edge_count(), 0);
+        let node = folded.nodes().next().unwrap();
+        assert!(folded.is_folded(&node.id));
+        assert_eq!(node.label, "main: 12 commits");
+    }
+
+    #[test]
+    fn test_folded_row_keeps_hidden_tags() {
+        let mut db = linear_history("main", 12);
+        db.set_commit_tag("c3", "v1");
+        db.set_commit_tag("c8", "v2");
+        let folded = fold_sections(&db, &FoldOptions::new(10)).unwrap();
+
+        let node = folded.nodes().next().unwrap();
+        assert_eq!(folded.commit_tags(&node.id), ["v1", "v2"]);
+    }
+
+    #[test]
+    fn test_subroutine_commit_is_not_a_folded_row() {
+        let mut db = linear_history("main", 2);
+        db.add_node(NodeData::with_shape(
+            "c1",
+            "c1",
+            crate::core::NodeShape::Subroutine,
+        ))
+        .unwrap();
+        assert!(!db.is_folded("c1"));
+    }
+
+    #[test]
+    fn test_expanded_section_is_kept() {
+        let mut db = linear_history("main", 4);
+        for i in 4..8 {
+            let id = format!("c{}", i);
+            db.add_commit(&id, None::<String>).unwrap();
+            db.set_commit_branch(&id, "dev");
+            db.add_parent_edge(&id, format!("c{}", i - 1)).unwrap();
+        }
+
+        let options = FoldOptions::new(2).with_expanded("dev");
+        let folded = fold_sections(&db, &options).unwrap();
+
+        // main collapses to one row, dev keeps its 4 commits
+        assert_eq!(folded.node_count(), 5);
+        assert_eq!(folded.edge_count(), 4);
+        assert!(folded.has_node("c4"));
+        assert!(!folded.has_node("c0"));
+    }
+
+    #[test]
+    fn test_expand_all_disables_folding() {
+        let db = linear_history("main", 12);
+        let options = FoldOptions::new(2).with_expanded(EXPAND_ALL);
+        assert!(fold_sections(&db, &options).is_none());
+    }
+}
@@ -1,31 +1,99 @@
 //! Git graph layout implementation
 //!
-//! Arranges commits in a chronological graph layout.
+//! Arranges commits like `git log --graph`: one row (or column, for `LR`)
+//! per commit in the order the history was written, with each branch in its
+//! own lane. Edges bend once: a fork leaves its parent sideways and runs
+//! down the new branch's lane, a merge runs down the merged lane and joins
+//! the merge commit sideways.
 
 use anyhow::Result;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tracing::{info, span, trace, Level};
 use unicode_width::UnicodeWidthStr;
 
 use super::GitGraphDatabase;
-use crate::core::{Database, Direction, LayoutAlgorithm};
+use crate::core::{Database, Direction, EdgeType, LayoutAlgorithm};
+
+/// A name shown beside a commit, like `git log --decorate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommitRef {
+    /// Branch whose latest commit this is, with the branch's lane
+    Branch { name: String, lane: usize },
+    /// Tag attached to the commit
+    Tag(String),
+}
+
+impl CommitRef {
+    /// Text drawn for the reference
+    pub fn text(&self) -> String {
+        match self {
+            CommitRef::Branch { name, .. } => name.clone(),
+            CommitRef::Tag(tag) => format!("tag: {}", tag),
+        }
+    }
+}
 
 /// Position data for a laid out commit
 #[derive(Debug, Clone)]
 pub struct PositionedCommit {
     pub id: String,
+    /// Cell of the commit glyph
     pub x: usize,
     pub y: usize,
-    pub width: usize,
-    pub height: usize,
+    /// Index into [`GitGraphLayoutResult::lanes`]
+    pub lane: usize,
+    /// Commit id or section summary; empty when commit labels are hidden
+    pub label: String,
+    /// Branches ending here and the commit's tag
+    pub refs: Vec<CommitRef>,
+    /// Where the label and its references start
+    pub label_x: usize,
+    pub label_y: usize,
+    /// Summary row standing in for a folded run of commits
+    pub folded: bool,
+}
+
+impl PositionedCommit {
+    /// Label followed by its references: `c3 (main, tag: v1.0)`
+    pub fn text(&self) -> String {
+        decorated(&self.label, &self.refs)
+    }
+
+    /// References in parentheses, or nothing when there are none
+    pub fn refs_text(&self) -> String {
+        refs_text(&self.refs)
+    }
+}
+
+fn refs_text(refs: &[CommitRef]) -> String {
+    if refs.is_empty() {
+        return String::new();
+    }
+    let refs: Vec<String> = refs.iter().map(CommitRef::text).collect();
+    format!("({})", refs.join(", "))
+}
+
+fn decorated(label: &str, refs: &[CommitRef]) -> String {
+    match (label.is_empty(), refs.is_empty()) {
+        (_, true) => label.to_string(),
+        (true, false) => refs_text(refs),
+        (false, false) => format!("{} {}", label, refs_text(refs)),
+    }
 }
 
 /// Position data for a laid out edge
 #[derive(Debug, Clone)]
 pub struct PositionedEdge {
+    /// Parent commit, the earlier end of the edge
     pub from_id: String,
+    /// Child commit, the later end of the edge
     pub to_id: String,
+    /// Parent glyph, the bend (if any), child glyph
     pub waypoints: Vec<(usize, usize)>,
+    /// Lane of the branch line the edge follows, for coloring
+    pub lane: usize,
+    /// Cherry-pick links, drawn dotted
+    pub dotted: bool,
 }
 
 /// Layout output containing positioned elements
@@ -33,6 +101,8 @@ pub struct PositionedEdge {
 pub struct GitGraphLayoutResult {
     pub commits: Vec<PositionedCommit>,
     pub edges: Vec<PositionedEdge>,
+    /// Branch drawn in each lane, main branch first
+    pub lanes: Vec<String>,
     pub width: usize,
     pub height: usize,
 }
@@ -45,11 +115,69 @@ impl GitGraphLayoutAlgorithm {
         Self
     }
 
-    fn calculate_commit_size(&self, label: &str) -> (usize, usize) {
-        let label_width = UnicodeWidthStr::width(label);
-        let width = (label_width + 4).max(8); // Padding for circle
-        let height = 3; // Standard height for commit circle
-        (width, height)
+    /// Branches that have commits, in the order they first appeared
+    ///
+    /// A branch's lane depends only on that order, never on how its commits
+    /// interleave with other branches, so it stays put as history grows.
+    fn assign_lanes(&self, database: &GitGraphDatabase) -> Vec<String> {
+        let used: HashSet<&str> = database
+            .nodes()
+            .filter(|node| !database.is_branch_marker(&node.id))
+            .filter_map(|node| database.node_branch(&node.id))
+            .collect();
+        database
+            .branch_names()
+            .iter()
+            .filter(|branch| used.contains(branch.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Label and references drawn beside each row
+    fn decorate(
+        &self,
+        database: &GitGraphDatabase,
+        rows: &[&str],
+        lanes: &[String],
+    ) -> Vec<(String, Vec<CommitRef>, bool)> {
+        let options = database.options();
+
+        // A branch is named at its latest commit
+        let mut tips: HashMap<&str, Vec<CommitRef>> = HashMap::new();
+        if options.show_branches {
+            for (lane, branch) in lanes.iter().enumerate() {
+                let tip = rows.iter().rev().find(|id| {
+                    database.commit_branch(id) == Some(branch.as_str()) && !database.is_folded(id)
+                });
+                if let Some(tip) = tip {
+                    tips.entry(tip).or_default().push(CommitRef::Branch {
+                        name: branch.clone(),
+                        lane,
+                    });
+                }
+            }
+        }
+
+        rows.iter()
+            .map(|id| {
+                let node = database.get_node(id);
+                let folded = database.is_folded(id);
+                let is_commit = database.commit_branch(id).is_some();
+                let label = match node {
+                    Some(_) if is_commit && !folded && !options.show_commit_label => String::new(),
+                    Some(node) => node.label.clone(),
+                    None => id.to_string(),
+                };
+                let mut refs = tips.remove(id).unwrap_or_default();
+                refs.extend(
+                    database
+                        .commit_tags(id)
+                        .iter()
+                        .map(|tag| CommitRef::Tag(tag.clone())),
+                );
+                (label, refs, folded)
+            })
+            .collect()
     }
 }
 
@@ -73,156 +201,169 @@ impl LayoutAlgorithm<GitGraphDatabase> for GitGraphLayoutAlgorithm {
 
         trace!("Starting git graph layout");
 
-        let nodes: Vec<_> = database.nodes().collect();
-        if nodes.is_empty() {
+        // Branch markers only name branches; they get no row of their own
+        let rows: Vec<&str> = database
+            .chronological_order()
+            .into_iter()
+            .filter(|id| !database.is_branch_marker(id))
+            .collect();
+        if rows.is_empty() {
             return Ok(GitGraphLayoutResult {
                 commits: Vec::new(),
                 edges: Vec::new(),
+                lanes: Vec::new(),
                 width: 0,
                 height: 0,
             });
         }
 
-        // Calculate commit sizes
-        let mut commit_sizes: HashMap<&str, (usize, usize)> = HashMap::new();
-        for node in &nodes {
-            let size = self.calculate_commit_size(&node.label);
-            commit_sizes.insert(&node.id, size);
-        }
-
-        // Topological sort to get chronological order
-        let sorted = database.topological_sort();
         let direction = database.direction();
 
-        // Assign positions based on direction
-        let mut positioned_commits = Vec::new();
-        let mut max_width = 0;
-        let mut max_height = 0;
+        // One lane per branch; nodes without a branch share the first lane
+        let lanes = self.assign_lanes(database);
+        let lane_count = lanes.len().max(1);
+        let lane_of = |id: &str| {
+            database
+                .node_branch(id)
+                .and_then(|branch| lanes.iter().position(|lane| lane == branch))
+                .unwrap_or(0)
+        };
+        let decorations = self.decorate(database, &rows, &lanes);
 
-        let node_sep = 4; // Spacing between commits
         let padding = 2;
-
-        match direction {
-            Direction::TopDown | Direction::BottomUp => {
-                // Vertical layout: commits arranged top to bottom
-                let mut y = padding;
-                let center_x = padding + 10usize; // Center line for commits
-
-                let commit_ids: Vec<&str> = if direction.is_reversed() {
-                    sorted.iter().rev().copied().collect()
-                } else {
-                    sorted.to_vec()
-                };
-
-                for commit_id in commit_ids {
-                    if let Some(_node) = database.get_node(commit_id) {
-                        let (width, height) = commit_sizes[commit_id];
-                        let x = center_x.saturating_sub(width / 2);
-
-                        positioned_commits.push(PositionedCommit {
-                            id: commit_id.to_string(),
-                            x,
-                            y,
-                            width,
-                            height,
-                        });
-
-                        max_width = max_width.max(x + width + padding);
-                        y += height + node_sep;
-                    }
+        let lane_sep = match direction {
+            Direction::TopDown | Direction::BottomUp => 2, // Glyph and a connector column
+            Direction::LeftRight | Direction::RightLeft => 3, // Glyph, label and a gap
+        };
+
+        // Time runs along the main axis; reversed directions start at the far end
+        let mut slots: Vec<usize> = Vec::with_capacity(rows.len());
+        let mut extent = 0;
+        for (label, refs, _) in &decorations {
+            slots.push(extent);
+            extent += match direction {
+                Direction::TopDown | Direction::BottomUp => 1,
+                Direction::LeftRight | Direction::RightLeft => {
+                    // The label starts two cells right of the glyph, clear
+                    // of lines bending down from it
+                    (UnicodeWidthStr::width(decorated(label, refs).as_str()) + 3).max(3)
                 }
-                max_height = y + padding;
-            }
-            Direction::LeftRight | Direction::RightLeft => {
-                // Horizontal layout: commits arranged left to right
-                let mut x = padding;
-                let center_y = padding + 2usize; // Center line for commits
-
-                let commit_ids: Vec<&str> = if direction.is_reversed() {
-                    sorted.iter().rev().copied().collect()
-                } else {
-                    sorted.to_vec()
-                };
-
-                for commit_id in commit_ids {
-                    if let Some(_node) = database.get_node(commit_id) {
-                        let (width, height) = commit_sizes[commit_id];
-                        let y = center_y.saturating_sub(height / 2);
-
-                        positioned_commits.push(PositionedCommit {
-                            id: commit_id.to_string(),
-                            x,
-                            y,
-                            width,
-                            height,
-                        });
-
-                        max_height = max_height.max(y + height + padding);
-                        x += width + node_sep;
-                    }
+            };
+        }
+        let last_slot = *slots.last().unwrap_or(&0);
+
+        let mut positioned_commits = Vec::with_capacity(rows.len());
+        for ((id, (label, refs, folded)), slot) in rows.iter().zip(decorations).zip(&slots) {
+            let slot = if direction.is_reversed() {
+                last_slot - slot
+            } else {
+                *slot
+            };
+            let lane = lane_of(id);
+            let cross = padding + lane * lane_sep;
+            let (x, y, label_x, label_y) = match direction {
+                Direction::TopDown | Direction::BottomUp => {
+                    let y = padding + slot;
+                    (cross, y, padding + lane_count * lane_sep, y)
                 }
-                max_width = x + padding;
-            }
+                Direction::LeftRight | Direction::RightLeft => {
+                    let x = padding + slot;
+                    (x, cross, x + 2, cross + 1)
+                }
+            };
+            positioned_commits.push(PositionedCommit {
+                id: id.to_string(),
+                x,
+                y,
+                lane,
+                label,
+                refs,
+                label_x,
+                label_y,
+                folded,
+            });
         }
 
-        // Route edges (parent relationships)
+        let (max_width, max_height) = match direction {
+            Direction::TopDown | Direction::BottomUp => {
+                let text_width = positioned_commits
+                    .iter()
+                    .map(|c| UnicodeWidthStr::width(c.text().as_str()))
+                    .max()
+                    .unwrap_or(0);
+                (
+                    padding + lane_count * lane_sep + text_width + padding,
+                    padding + rows.len() + padding,
+                )
+            }
+            Direction::LeftRight | Direction::RightLeft => (
+                padding + extent + padding,
+                padding + (lane_count - 1) * lane_sep + 2 + padding,
+            ),
+        };
+
+        // Route edges from the earlier commit to the later one
         let mut positioned_edges = Vec::new();
-        let commit_positions: HashMap<&str, &PositionedCommit> = positioned_commits
-            .iter()
-            .map(|c| (c.id.as_str(), c))
+        let index_of: HashMap<&str, usize> =
+            rows.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let lane_parents: HashSet<&str> = database
+            .edges()
+            .filter(|edge| lane_of(&edge.from) == lane_of(&edge.to))
+            .filter(|edge| !matches!(edge.edge_type, EdgeType::DottedArrow))
+            .filter_map(|edge| {
+                let from = index_of.get(edge.from.as_str())?;
+                let to = index_of.get(edge.to.as_str())?;
+                Some(if from < to {
+                    edge.to.as_str()
+                } else {
+                    edge.from.as_str()
+                })
+            })
             .collect();
 
         for edge in database.edges() {
-            if let (Some(from), Some(to)) = (
-                commit_positions.get(edge.from.as_str()),
-                commit_positions.get(edge.to.as_str()),
-            ) {
-                // Calculate edge waypoints based on direction
-                let (exit_x, exit_y, entry_x, entry_y) = match direction {
-                    Direction::TopDown => {
-                        // Child (bottom) to parent (top)
-                        (
-                            from.x + from.width / 2,
-                            from.y,
-                            to.x + to.width / 2,
-                            to.y + to.height,
-                        )
-                    }
-                    Direction::BottomUp => {
-                        // Reversed: parent (top) to child (bottom)
-                        (
-                            from.x + from.width / 2,
-                            from.y + from.height,
-                            to.x + to.width / 2,
-                            to.y,
-                        )
-                    }
-                    Direction::LeftRight => {
-                        // Child (left) to parent (right)
-                        (
-                            from.x + from.width,
-                            from.y + from.height / 2,
-                            to.x,
-                            to.y + to.height / 2,
-                        )
-                    }
-                    Direction::RightLeft => {
-                        // Reversed: parent (right) to child (left)
-                        (
-                            from.x,
-                            from.y + from.height / 2,
-                            to.x + to.width,
-                            to.y + to.height / 2,
-                        )
-                    }
-                };
-
-                positioned_edges.push(PositionedEdge {
-                    from_id: edge.from.clone(),
-                    to_id: edge.to.clone(),
-                    waypoints: vec![(exit_x, exit_y), (entry_x, entry_y)],
-                });
+            let (Some(&a), Some(&b)) = (
+                index_of.get(edge.from.as_str()),
+                index_of.get(edge.to.as_str()),
+            ) else {
+                continue;
+            };
+            if a == b {
+                continue;
             }
+            let (parent, child) = if a < b {
+                (&positioned_commits[a], &positioned_commits[b])
+            } else {
+                (&positioned_commits[b], &positioned_commits[a])
+            };
+            let dotted = matches!(edge.edge_type, EdgeType::DottedArrow);
+
+            let start = (parent.x, parent.y);
+            let end = (child.x, child.y);
+            let (waypoints, lane) = if parent.lane == child.lane {
+                (vec![start, end], child.lane)
+            } else {
+                // A commit with a parent in its own lane joins other lanes
+                // on its own row (a merge); otherwise it starts a branch and
+                // the line leaves the parent's row (a fork)
+                let joins_here = dotted || lane_parents.contains(child.id.as_str());
+                let bend = match (direction, joins_here) {
+                    (Direction::TopDown | Direction::BottomUp, true) => (parent.x, child.y),
+                    (Direction::TopDown | Direction::BottomUp, false) => (child.x, parent.y),
+                    (Direction::LeftRight | Direction::RightLeft, true) => (child.x, parent.y),
+                    (Direction::LeftRight | Direction::RightLeft, false) => (parent.x, child.y),
+                };
+                let lane = if joins_here { parent.lane } else { child.lane };
+                (vec![start, bend, end], lane)
+            };
+
+            positioned_edges.push(PositionedEdge {
+                from_id: parent.id.clone(),
+                to_id: child.id.clone(),
+                waypoints,
+                lane,
+                dotted,
+            });
         }
 
         info!(
@@ -236,6 +377,7 @@ impl LayoutAlgorithm<GitGraphDatabase> for GitGraphLayoutAlgorithm {
         Ok(GitGraphLayoutResult {
             commits: positioned_commits,
             edges: positioned_edges,
+            lanes,
             width: max_width,
             height: max_height,
         })
@@ -273,4 +415,94 @@ mod tests {
         assert!(result.width > 0);
         assert!(result.height > 0);
     }
+
+    #[test]
+    fn test_branches_keep_their_lane_in_first_appearance_order() {
+        let mut db = GitGraphDatabase::new();
+        db.declare_branch("main");
+        for (id, branch) in [
+            ("c1", "main"),
+            ("c2", "feature"),
+            ("c3", "hotfix"),
+            ("c4", "feature"),
+            ("c5", "main"),
+        ] {
+            db.add_commit(id, None::<String>).unwrap();
+            db.set_commit_branch(id, branch);
+        }
+
+        let result = GitGraphLayoutAlgorithm::new().layout(&db).unwrap();
+        assert_eq!(result.lanes, vec!["main", "feature", "hotfix"]);
+        let commit = |id: &str| result.commits.iter().find(|c| c.id == id).unwrap();
+        assert_eq!(commit("c2").lane, 1);
+        assert_eq!(commit("c4").lane, 1);
+        assert_eq!(commit("c2").x, commit("c4").x);
+        assert!(commit("c1").x < commit("c2").x);
+        assert!(commit("c2").x < commit("c3").x);
+    }
+
+    fn parse(input: &str) -> GitGraphDatabase {
+        use crate::core::Parser;
+        let mut db = GitGraphDatabase::new();
+        super::super::GitGraphParser::new()
+            .parse(input, &mut db)
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_commits_keep_written_order_one_row_each() {
+        let db = parse(
+            "gitGraph\n commit id: \"z\"\n branch dev\n commit id: \"a\"\n checkout main\n commit id: \"m\"",
+        );
+
+        let result = GitGraphLayoutAlgorithm::new().layout(&db).unwrap();
+        let rows: Vec<_> = result
+            .commits
+            .iter()
+            .map(|c| (c.id.as_str(), c.y))
+            .collect();
+        assert_eq!(rows, vec![("z", 2), ("a", 3), ("m", 4)]);
+        // Branch markers name branches instead of taking a row
+        assert!(result.commits.iter().all(|c| !c.id.starts_with("branch_")));
+    }
+
+    #[test]
+    fn test_forks_bend_at_the_parent_and_merges_at_the_child() {
+        let db = parse(
+            "gitGraph\n commit id: \"a\"\n branch dev\n commit id: \"b\"\n checkout main\n merge dev id: \"m\"",
+        );
+
+        let result = GitGraphLayoutAlgorithm::new().layout(&db).unwrap();
+        let commit = |id: &str| result.commits.iter().find(|c| c.id == id).unwrap();
+        let (a, b, m) = (commit("a"), commit("b"), commit("m"));
+        let edge = |from: &str, to: &str| {
+            result
+                .edges
+                .iter()
+                .find(|e| e.from_id == from && e.to_id == to)
+                .unwrap()
+        };
+        assert_eq!(
+            edge("a", "b").waypoints,
+            vec![(a.x, a.y), (b.x, a.y), (b.x, b.y)]
+        );
+        assert_eq!(
+            edge("b", "m").waypoints,
+            vec![(b.x, b.y), (b.x, m.y), (m.x, m.y)]
+        );
+        assert_eq!(edge("a", "m").waypoints, vec![(a.x, a.y), (m.x, m.y)]);
+        assert_eq!(edge("b", "m").lane, 1);
+    }
+
+    #[test]
+    fn test_branch_tips_and_tags_become_refs() {
+        let db = parse(
+            "gitGraph\n commit id: \"a\" tag: \"v1\"\n branch dev\n commit id: \"b\"\n checkout main\n commit id: \"c\"",
+        );
+
+        let result = GitGraphLayoutAlgorithm::new().layout(&db).unwrap();
+        let text: Vec<_> = result.commits.iter().map(PositionedCommit::text).collect();
+        assert_eq!(text, vec!["a (tag: v1)", "b (dev)", "c (main)"]);
+    }
 }
@@ -2,25 +2,38 @@
 //!
 //! Implements git commit graph visualization with ASCII art syntax.
 //!
-//! Syntax examples:
-//! - Linear: `*--*--*`
-//! - With labels: `*[commit msg]--*[another]--*`
-//! - Branches: `*--*--*` with branch labels
-//! - Merges: `*--*--*` showing merge commits
+//! Reads Mermaid `gitGraph` commands (`commit`, `branch`, `checkout`,
+//! `merge`, `cherry-pick`) and draws the history like `git log --graph`:
+//!
+//! ```text
+//! ○─╮ a
+//! │ ○ b (dev)
+//! ○ │ c (tag: v1)
+//! ○─╯ c2 (main)
+//! ```
+//!
+//! Each branch keeps its own lane, forks leave their parent sideways,
+//! merges join the merge commit on its row and cherry-picks are linked by
+//! a dotted line. Branch names and tags are shown beside the commit they
+//! point at.
 
 mod database;
 mod detector;
-mod layout;
+mod folding;
+#[allow(missing_docs)]
+pub(crate) mod layout;
+mod options;
 mod parser;
 mod renderer;
-mod syntax_parser;
+#[allow(missing_docs)]
+pub(crate) mod syntax_parser;
 
 pub use database::GitGraphDatabase;
 pub use detector::GitGraphDetector;
-pub use layout::{GitGraphLayoutAlgorithm, GitGraphLayoutResult};
+pub use folding::{fold_sections, FoldOptions, DEFAULT_FOLD_THRESHOLD, EXPAND_ALL};
+pub use options::GitGraphOptions;
 pub use parser::GitGraphParser;
 pub use renderer::GitGraphRenderer;
-pub use syntax_parser::GitGraphSyntaxParser;
 
 use crate::core::{Detector, Diagram};
 use std::sync::Arc;
@@ -0,0 +1,189 @@
+//! Git graph `options` block
+//!
+//! Mermaid accepts a JSON options block between the `gitGraph` header and
+//! the first command:
+//!
+//! ```text
+//! gitGraph TB:
+//!     options
+//!     {
+//!         "showBranches": false,
+//!         "mainBranchName": "trunk"
+//!     }
+//!     end
+//!     commit
+//! ```
+//!
+//! Recognized keys become [`GitGraphOptions`] fields. Keys that only affect
+//! mermaid.js styling are ignored with a warning so the diagram still renders.
+
+use crate::core::{parse_directive_value, Direction, DirectiveValue};
+
+/// Options read from a git graph `options` block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitGraphOptions {
+    /// Draw the markers that name each branch
+    pub show_branches: bool,
+    /// Draw commit ids next to commits
+    pub show_commit_label: bool,
+    /// Name of the branch commits go to before any `branch` command
+    pub main_branch_name: String,
+}
+
+impl Default for GitGraphOptions {
+    fn default() -> Self {
+        Self {
+            show_branches: true,
+            show_commit_label: true,
+            main_branch_name: "main".to_string(),
+        }
+    }
+}
+
+impl GitGraphOptions {
+    /// Read the options block from git graph input
+    ///
+    /// Returns the defaults when there is no block. Never fails: malformed
+    /// blocks, unknown keys and wrong value types are returned as warnings.
+    pub fn from_input(input: &str) -> (Self, Vec<String>) {
+        let mut options = Self::default();
+        let mut warnings = Vec::new();
+
+        let lines: Vec<&str> = input.lines().map(str::trim).collect();
+        let Some((block, _)) = split_options_block(&lines) else {
+            return (options, warnings);
+        };
+
+        let members = match parse_directive_value(&block) {
+            Some(DirectiveValue::Object(members)) => members,
+            _ => {
+                warnings.push("Ignoring malformed gitGraph options block".to_string());
+                return (options, warnings);
+            }
+        };
+
+        for (key, value) in members {
+            match (key.as_str(), value) {
+                ("showBranches", DirectiveValue::Bool(b)) => options.show_branches = b,
+                ("showCommitLabel", DirectiveValue::Bool(b)) => options.show_commit_label = b,
+                ("mainBranchName", DirectiveValue::String(s)) if !s.trim().is_empty() => {
+                    options.main_branch_name = s.trim().to_string()
+                }
+                ("showBranches" | "showCommitLabel" | "mainBranchName", value) => {
+                    warnings.push(format!(
+                        "gitGraph option '{}' has an invalid {} value; ignored",
+                        key,
+                        value.kind()
+                    ))
+                }
+                _ => warnings.push(format!("Unsupported gitGraph option '{}' ignored", key)),
+            }
+        }
+
+        (options, warnings)
+    }
+}
+
+/// Direction from a `gitGraph` header line
+///
+/// Accepts `gitGraph`, `gitGraph:`, `gitGraph TB:` and `gitGraph LR`.
+/// Returns `None` for lines that are not a header, `Some(None)` for a header
+/// without an orientation.
+pub(crate) fn parse_header(line: &str) -> Option<Option<Direction>> {
+    let line = line.trim();
+    let keyword_len = "gitgraph".len();
+    if !line
+        .get(..keyword_len)
+        .is_some_and(|keyword| keyword.eq_ignore_ascii_case("gitgraph"))
+    {
+        return None;
+    }
+    let rest = line[keyword_len..].trim().trim_end_matches(':').trim();
+    if rest.is_empty() {
+        return Some(None);
+    }
+    // Anything other than a direction (e.g. `gitGraphs`) is not a header
+    rest.parse::<Direction>().ok().map(Some)
+}
+
+/// Find the `options ... end` block in trimmed input lines
+///
+/// Returns the block's JSON text and the range of lines it covers, so the
+/// command parser can skip them.
+pub(crate) fn split_options_block(lines: &[&str]) -> Option<(String, std::ops::Range<usize>)> {
+    let start = lines.iter().position(|l| {
+        let lower = l.to_ascii_lowercase();
+        lower == "options"
+            || (lower.starts_with("options") && lower[7..].trim_start().starts_with('{'))
+    })?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|l| l.eq_ignore_ascii_case("end"))
+        .map(|offset| start + 1 + offset)
+        .unwrap_or(lines.len());
+
+    let mut block = lines[start][7..].trim().to_string();
+    for line in &lines[start + 1..end] {
+        block.push('\n');
+        block.push_str(line);
+    }
+    let covered_end = (end + 1).min(lines.len());
+    Some((block, start..covered_end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_forms() {
+        assert_eq!(parse_header("gitGraph"), Some(None));
+        assert_eq!(parse_header("gitGraph:"), Some(None));
+        assert_eq!(parse_header("gitGraph TB:"), Some(Some(Direction::TopDown)));
+        assert_eq!(
+            parse_header("gitGraph LR"),
+            Some(Some(Direction::LeftRight))
+        );
+        assert_eq!(parse_header("commit"), None);
+        assert_eq!(parse_header("gitGraphs"), None);
+    }
+
+    #[test]
+    fn test_no_options_block_uses_defaults() {
+        let (options, warnings) = GitGraphOptions::from_input("gitGraph\n  commit");
+        assert_eq!(options, GitGraphOptions::default());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_parses_mainline_options() {
+        let input = "gitGraph\n  options\n  {\n    \"showBranches\": false,\n    \"showCommitLabel\": false,\n    \"mainBranchName\": \"trunk\"\n  }\n  end\n  commit";
+        let (options, warnings) = GitGraphOptions::from_input(input);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert!(!options.show_branches);
+        assert!(!options.show_commit_label);
+        assert_eq!(options.main_branch_name, "trunk");
+    }
+
+    #[test]
+    fn test_unknown_and_mistyped_options_warn() {
+        let input = "gitGraph\noptions\n{\"nodeSpacing\": 150, \"showBranches\": \"no\"}\nend";
+        let (options, warnings) = GitGraphOptions::from_input(input);
+        assert!(options.show_branches);
+        assert_eq!(
+            warnings,
+            vec![
+                "Unsupported gitGraph option 'nodeSpacing' ignored",
+                "gitGraph option 'showBranches' has an invalid string value; ignored",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_block_warns() {
+        let (_, warnings) =
+            GitGraphOptions::from_input("gitGraph\noptions\n{ showBranches: }\nend");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("malformed"));
+    }
+}
@@ -2,11 +2,15 @@
 //!
 //! Parses git graph syntax using the syntax parser abstraction.
 
+use super::options::{parse_header, GitGraphOptions};
 use super::syntax_parser::GitGraphSyntaxParser;
 use super::GitGraphDatabase;
-use crate::core::{Database, EdgeData, NodeData, NodeShape, Parser, SyntaxParser};
+use crate::core::{
+    normalize_input, Database, EdgeData, LenientStatements, NodeData, NodeShape, Parser,
+    SyntaxParser,
+};
 use anyhow::Result;
-use tracing::{debug, info, span, trace, Level};
+use tracing::{debug, info, span, trace, warn, Level};
 
 /// Git graph parser implementation
 pub struct GitGraphParser {
@@ -14,6 +18,7 @@ pub struct GitGraphParser {
 }
 
 impl GitGraphParser {
+    /// Create a parser
     pub fn new() -> Self {
         Self {
             syntax_parser: GitGraphSyntaxParser::new(),
@@ -34,23 +39,30 @@ impl Parser<GitGraphDatabase> for GitGraphParser {
 
         trace!("Starting git graph parsing");
 
-        // Check for direction specification: gitGraph TD or gitGraph LR
-        let lines: Vec<&str> = input.lines().map(|l| l.trim()).collect();
-        for line in &lines {
-            let line_lower = line.to_lowercase();
-            if line_lower.starts_with("gitgraph") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 2 {
-                    if let Ok(direction) = parts[1].parse::<crate::core::Direction>() {
-                        database.set_direction(direction);
-                        debug!(direction = ?direction, "Parsed git graph direction");
-                    }
-                }
-            }
+        let normalized = normalize_input(input);
+        let input = normalized.as_ref();
+
+        // Check for direction specification: gitGraph TD, gitGraph LR or gitGraph TB:
+        if let Some(direction) = input.lines().find_map(parse_header).flatten() {
+            database.set_direction(direction);
+            debug!(direction = ?direction, "Parsed git graph direction");
+        }
+
+        let (options, warnings) = GitGraphOptions::from_input(input);
+        for warning in warnings {
+            warn!(%warning, "gitGraph options");
         }
+        debug!(?options, "Parsed git graph options");
+        // The main branch always takes the first lane
+        database.declare_branch(&options.main_branch_name);
+        database.set_options(options);
 
-        // Parse syntax into AST
-        let syntax_nodes = self.syntax_parser.parse(input)?;
+        // Parse syntax into AST, skipping commands it cannot read
+        let mut lenient = LenientStatements::new("gitgraph");
+        let syntax_nodes = self.syntax_parser.parse_statements(input, &mut lenient);
+        for warning in lenient.finish()? {
+            database.add_parse_warning(warning);
+        }
         debug!(
             syntax_node_count = syntax_nodes.len(),
             "Parsed syntax nodes"
@@ -65,27 +77,37 @@ impl Parser<GitGraphDatabase> for GitGraphParser {
                 crate::core::SyntaxNode::Node {
                     id,
                     label,
-                    metadata: _metadata,
+                    metadata,
                 } => {
                     // Create commit node
                     let shape = NodeShape::Circle; // Commits are circles
 
                     let node = NodeData::with_shape(&id, label.as_deref().unwrap_or(&id), shape);
                     database.add_node(node)?;
+                    if let Some(branch) = metadata.get("branch") {
+                        database.set_commit_branch(&id, branch);
+                    } else if metadata.get("type").map(|t| t.as_str()) == Some("branch") {
+                        database.set_branch_marker(&id, label.as_deref().unwrap_or(&id));
+                    }
+                    if let Some(tag) = metadata.get("tag") {
+                        database.set_commit_tag(&id, tag);
+                    }
                     node_count += 1;
                 }
                 crate::core::SyntaxNode::Edge {
                     from,
                     to,
                     label,
-                    metadata: _metadata,
+                    metadata,
                 } => {
-                    // Create parent edge (in git, edges go from child to parent)
-                    let edge = if let Some(label) = label {
-                        EdgeData::with_label(&from, &to, crate::core::EdgeType::Arrow, label)
-                    } else {
-                        EdgeData::new(&from, &to)
+                    // Create parent edge; cherry-picks link back to the
+                    // copied commit with a dotted line
+                    let edge_type = match metadata.get("type").map(|t| t.as_str()) {
+                        Some("cherry-pick") => crate::core::EdgeType::DottedArrow,
+                        _ => crate::core::EdgeType::Arrow,
                     };
+                    let mut edge = EdgeData::with_type(&from, &to, edge_type);
+                    edge.label = label;
                     database.add_edge(edge)?;
                     edge_count += 1;
                 }
@@ -146,4 +168,95 @@ mod tests {
         // Should have 2 commits + 1 branch node = 3 nodes
         assert_eq!(database.node_count(), 3);
     }
+
+    #[test]
+    fn test_parse_colon_header_with_direction() {
+        let parser = GitGraphParser::new();
+        let mut database = GitGraphDatabase::new();
+        database.set_direction(crate::core::Direction::LeftRight);
+
+        parser
+            .parse("gitGraph TB:\n   commit\n   commit", &mut database)
+            .unwrap();
+        assert_eq!(database.direction(), crate::core::Direction::TopDown);
+        assert_eq!(database.node_count(), 2);
+    }
+
+    #[test]
+    fn test_parse_options_block() {
+        let parser = GitGraphParser::new();
+        let mut database = GitGraphDatabase::new();
+
+        let input = r#"gitGraph:
+   options
+   {
+       "showBranches": false,
+       "showCommitLabel": false,
+       "mainBranchName": "trunk"
+   }
+   end
+   commit
+   branch develop
+   commit
+   checkout trunk
+   merge develop"#;
+        parser.parse(input, &mut database).unwrap();
+
+        assert!(!database.options().show_commit_label);
+        assert_eq!(database.options().main_branch_name, "trunk");
+        // 3 commits (incl. merge), no branch marker node
+        assert_eq!(database.node_count(), 3);
+        assert_eq!(database.commit_branch("c1"), Some("trunk"));
+        assert!(!database.has_node("branch_develop"));
+    }
+
+    #[test]
+    fn test_parse_tags_and_cherry_pick_edges() {
+        let parser = GitGraphParser::new();
+        let mut database = GitGraphDatabase::new();
+
+        let input = r#"gitGraph
+   commit id: "a" tag: "v1"
+   branch develop
+   commit id: "b"
+   checkout main
+   cherry-pick id: "b"
+   merge develop tag: "v2""#;
+        parser.parse(input, &mut database).unwrap();
+
+        assert_eq!(database.commit_tag("a"), Some("v1"));
+        assert_eq!(database.commit_tag("c1"), Some("cherry-pick:b"));
+        assert_eq!(database.commit_tag("c2"), Some("v2"));
+        assert_eq!(database.commit_branch("c2"), Some("main"));
+        let dotted: Vec<_> = database
+            .edges()
+            .filter(|e| e.edge_type == crate::core::EdgeType::DottedArrow)
+            .map(|e| (e.from.as_str(), e.to.as_str()))
+            .collect();
+        assert_eq!(dotted, vec![("b", "c1")]);
+        assert!(database.parse_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_commands_are_skipped_with_warnings() {
+        let parser = GitGraphParser::new();
+        let mut database = GitGraphDatabase::new();
+
+        let input = "gitGraph\n   commit\n   rebase main\n   checkout nowhere\n   commit";
+        parser.parse(input, &mut database).unwrap();
+        assert_eq!(database.node_count(), 2);
+
+        let warnings: Vec<_> = database
+            .parse_warnings()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                "3:4: Skipped invalid statement 'rebase main': unknown git graph command",
+                "4:4: Cannot check out unknown branch 'nowhere'",
+            ]
+        );
+    }
 }
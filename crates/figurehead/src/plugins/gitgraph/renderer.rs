@@ -3,102 +3,233 @@
 //! Converts positioned commits into ASCII diagrams.
 
 use anyhow::Result;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use tracing::{debug, info, span, trace, Level};
 
-use super::layout::{GitGraphLayoutAlgorithm, PositionedCommit};
+use super::folding::{fold_sections, FoldOptions};
+use super::layout::{
+    CommitRef, GitGraphLayoutAlgorithm, GitGraphLayoutResult, PositionedCommit, PositionedEdge,
+};
 use super::GitGraphDatabase;
-use crate::core::{AsciiCanvas, CharacterSet, Database, LayoutAlgorithm, Renderer};
+use crate::core::{
+    AsciiCanvas, CellGrid, CharacterSet, Database, Direction, LayoutAlgorithm, Palette, Renderer,
+    Role, RoleRecorder,
+};
+use crate::plugins::observer::LayoutProbe;
+use unicode_width::UnicodeWidthStr;
 
 /// Git graph ASCII renderer
 pub struct GitGraphRenderer {
     style: CharacterSet,
+    folding: FoldOptions,
+    color: bool,
+    legend: bool,
 }
 
 impl GitGraphRenderer {
+    /// Create a renderer with the default character set
     pub fn new() -> Self {
         Self {
             style: CharacterSet::default(),
+            folding: FoldOptions::default(),
+            color: false,
+            legend: false,
         }
     }
 
+    /// Create a renderer with the given character set
     pub fn with_style(style: CharacterSet) -> Self {
-        Self { style }
+        Self {
+            style,
+            folding: FoldOptions::default(),
+            color: false,
+            legend: false,
+        }
     }
 
-    fn draw_commit(&self, canvas: &mut AsciiCanvas, commit: &PositionedCommit, label: &str) {
-        let x = commit.x + commit.width / 2;
-        let y = commit.y + commit.height / 2;
+    /// Set how long histories are folded into per-section summary rows
+    pub fn with_folding(mut self, folding: FoldOptions) -> Self {
+        self.folding = folding;
+        self
+    }
 
-        // Draw commit as circle (using * or ○)
-        let commit_char = if self.style.is_ascii() { '*' } else { '○' };
-        canvas.set_char(x, y, commit_char);
+    /// Color each branch's commits and lines with ANSI escapes
+    ///
+    /// Colors follow the lane, so a branch keeps its color for the whole
+    /// history.
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
 
-        // Draw label below commit
-        let label_x = x.saturating_sub(label.len() / 2);
-        canvas.draw_text(label_x.max(0), commit.y + commit.height + 1, label);
+    /// Append a legend naming the branch drawn in each lane
+    pub fn with_legend(mut self, enabled: bool) -> Self {
+        self.legend = enabled;
+        self
     }
 
-    fn draw_edge(&self, canvas: &mut AsciiCanvas, waypoints: &[(usize, usize)]) {
-        if waypoints.len() < 2 {
-            return;
+    /// Legend rows, one per lane in drawing order
+    fn draw_legend(&self, lanes: &[String], direction: Direction) -> String {
+        let glyph = if self.style.is_ascii() { '|' } else { '│' };
+        let heading = match direction {
+            Direction::TopDown | Direction::BottomUp => "Lanes, left to right:",
+            Direction::LeftRight | Direction::RightLeft => "Lanes, top to bottom:",
+        };
+        let palette = Palette::default();
+        let mut rows = vec![heading.to_string()];
+        for (lane, branch) in lanes.iter().enumerate() {
+            let glyph = if self.color {
+                format!("\x1b[{}m{}\x1b[0m", palette.code(Role::Branch(lane)), glyph)
+            } else {
+                glyph.to_string()
+            };
+            rows.push(format!("  {} {}", glyph, branch));
         }
+        rows.join("\n")
+    }
 
-        let (x1, y1) = waypoints[0];
-        let (x2, y2) = waypoints[waypoints.len() - 1];
+    /// Draw a commit glyph with its label and references
+    ///
+    /// The label takes the commit's branch color; references are drawn like
+    /// `git log --decorate`, each branch name in its own lane color.
+    fn draw_commit(
+        &self,
+        canvas: &mut AsciiCanvas,
+        roles: &mut RoleRecorder,
+        commit: &PositionedCommit,
+    ) {
+        // Draw commit as circle (using * or ○), folded sections as an ellipsis
+        let commit_char = match (commit.folded, self.style.is_ascii()) {
+            (true, true) => ':',
+            (true, false) => '⋮',
+            (false, true) => '*',
+            (false, false) => '○',
+        };
+        canvas.set_char(commit.x, commit.y, commit_char);
+        canvas.draw_text(commit.label_x, commit.label_y, &commit.label);
+        roles.record(canvas, Role::Branch(commit.lane));
+
+        if commit.refs.is_empty() {
+            return;
+        }
+        let mut x = commit.label_x + UnicodeWidthStr::width(commit.label.as_str());
+        if !commit.label.is_empty() {
+            x += 1;
+        }
+        canvas.draw_text(x, commit.label_y, &commit.refs_text());
+        roles.record(canvas, Role::Label);
+
+        x += 1; // Opening parenthesis
+        for reference in &commit.refs {
+            let width = UnicodeWidthStr::width(reference.text().as_str());
+            if let CommitRef::Branch { lane, .. } = reference {
+                roles.paint_text(canvas, x, commit.label_y, width, Role::Branch(*lane));
+            }
+            x += width + 2; // Separating ", "
+        }
+    }
 
-        let line_char = if self.style.is_ascii() { '|' } else { '│' };
-        let h_line_char = if self.style.is_ascii() { '-' } else { '─' };
+    /// Line character for a cell joining the given directions
+    fn line_char(&self, joins: u8, dotted: bool) -> char {
+        let vertical = joins & (UP | DOWN) != 0;
+        let horizontal = joins & (LEFT | RIGHT) != 0;
+        match (self.style.is_ascii(), dotted) {
+            (true, _) if vertical && horizontal => '+',
+            (true, true) if vertical => ':',
+            (true, true) => '.',
+            (true, false) if vertical => '|',
+            (true, false) => '-',
+            (false, true) if !horizontal => '┆',
+            (false, true) if !vertical => '┄',
+            (false, _) => match joins {
+                j if j == DOWN | LEFT => '╮',
+                j if j == DOWN | RIGHT => '╭',
+                j if j == UP | LEFT => '╯',
+                j if j == UP | RIGHT => '╰',
+                j if j == LEFT | RIGHT | DOWN => '┬',
+                j if j == LEFT | RIGHT | UP => '┴',
+                j if j == UP | DOWN | RIGHT => '├',
+                j if j == UP | DOWN | LEFT => '┤',
+                j if j == UP | DOWN | LEFT | RIGHT => '┼',
+                _ if vertical => '│',
+                _ => '─',
+            },
+        }
+    }
 
-        // Draw based on orientation
-        if x1 == x2 {
-            // Pure vertical line
-            let (start, end) = if y1 < y2 { (y1, y2) } else { (y2, y1) };
-            for y in start..=end {
-                if canvas.get_char(x1, y) == ' ' {
-                    canvas.set_char(x1, y, line_char);
-                }
-            }
-        } else if y1 == y2 {
-            // Pure horizontal line
-            let (start, end) = if x1 < x2 { (x1, x2) } else { (x2, x1) };
-            for x in start..=end {
-                if canvas.get_char(x, y1) == ' ' {
-                    canvas.set_char(x, y1, h_line_char);
+    /// Draw an edge between commit glyphs, joining lines it meets
+    ///
+    /// `joins` holds the directions solid lines leave each cell in so far,
+    /// so a fork passing another lane's line becomes `┼` and two forks from
+    /// one commit share a `┬`. Dotted cherry-pick links only fill blank
+    /// cells and never change solid lines.
+    fn draw_edge(
+        &self,
+        canvas: &mut AsciiCanvas,
+        joins: &mut HashMap<(usize, usize), u8>,
+        edge: &PositionedEdge,
+    ) {
+        let mut cells: Vec<((usize, usize), u8)> = Vec::new();
+        for pair in edge.waypoints.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            let (forward, backward) = match (x1.cmp(&x2), y1.cmp(&y2)) {
+                (Ordering::Less, _) => (RIGHT, LEFT),
+                (Ordering::Greater, _) => (LEFT, RIGHT),
+                (_, Ordering::Less) => (DOWN, UP),
+                (_, Ordering::Greater) => (UP, DOWN),
+                _ => continue,
+            };
+            let steps = x1.abs_diff(x2).max(y1.abs_diff(y2));
+            for step in 0..=steps {
+                let cell = match forward {
+                    RIGHT => (x1 + step, y1),
+                    LEFT => (x1 - step, y1),
+                    DOWN => (x1, y1 + step),
+                    _ => (x1, y1 - step),
+                };
+                let mut bits = 0;
+                if step < steps {
+                    bits |= forward;
                 }
-            }
-        } else {
-            // L-shaped path: draw both segments
-            // Vertical segment
-            let (v_start, v_end) = if y1 < y2 { (y1, y2) } else { (y2, y1) };
-            for y in v_start..=v_end {
-                if canvas.get_char(x1, y) == ' ' {
-                    canvas.set_char(x1, y, line_char);
+                if step > 0 {
+                    bits |= backward;
                 }
+                cells.push((cell, bits));
             }
+        }
 
-            // Horizontal segment
-            let (h_start, h_end) = if x1 < x2 { (x1, x2) } else { (x2, x1) };
-            let mid_y = if y1 == y2 { y1 } else { y1.min(y2) };
-            for x in h_start..=h_end {
-                let existing = canvas.get_char(x, mid_y);
-                if existing == ' ' || existing == line_char {
-                    canvas.set_char(x, mid_y, h_line_char);
-                }
+        let first = edge.waypoints.first().copied();
+        let last = edge.waypoints.last().copied();
+        let mut own: HashMap<(usize, usize), u8> = HashMap::new();
+        for (cell, bits) in cells {
+            *own.entry(cell).or_default() |= bits;
+        }
+        for (cell, bits) in own {
+            // Commit glyphs sit on the ends
+            if Some(cell) == first || Some(cell) == last {
+                continue;
             }
-
-            // Corner
-            let corner = if self.style.is_ascii() {
-                '+'
-            } else if y1 < y2 {
-                '└'
+            let (x, y) = cell;
+            if edge.dotted {
+                if canvas.get_char(x, y) == ' ' {
+                    canvas.set_char(x, y, self.line_char(bits, true));
+                }
             } else {
-                '┌'
-            };
-            canvas.set_char(x2, mid_y, corner);
+                let joined = joins.entry(cell).or_default();
+                *joined |= bits;
+                canvas.set_char(x, y, self.line_char(*joined, false));
+            }
         }
     }
 }
 
+/// Directions a line leaves a cell in
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
 impl Default for GitGraphRenderer {
     fn default() -> Self {
         Self::new()
@@ -109,6 +240,29 @@ impl Renderer<GitGraphDatabase> for GitGraphRenderer {
     type Output = String;
 
     fn render(&self, database: &GitGraphDatabase) -> Result<Self::Output> {
+        self.render_probed(database, &mut LayoutProbe::default())
+    }
+
+    fn name(&self) -> &'static str {
+        "ascii"
+    }
+
+    fn version(&self) -> &'static str {
+        "0.1.0"
+    }
+
+    fn format(&self) -> &'static str {
+        "ascii"
+    }
+}
+
+impl GitGraphRenderer {
+    /// Render the database, timing the layout through `probe`
+    pub(crate) fn render_probed(
+        &self,
+        database: &GitGraphDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<String> {
         let render_span = span!(
             Level::INFO,
             "render_gitgraph",
@@ -120,31 +274,33 @@ impl Renderer<GitGraphDatabase> for GitGraphRenderer {
 
         trace!("Starting git graph rendering");
 
+        let folded = fold_sections(database, &self.folding);
+        if let Some(folded) = &folded {
+            debug!(
+                commit_count = database.commit_count(),
+                row_count = folded.node_count(),
+                "Folded long history by section"
+            );
+        }
+        let database = folded.as_ref().unwrap_or(database);
+
         // Compute layout
         let layout_algo = GitGraphLayoutAlgorithm::new();
+        let started = probe.start();
         let layout = layout_algo.layout(database)?;
+        probe.finish(started, layout.width, layout.height);
 
         if layout.commits.is_empty() {
             debug!("Empty layout, returning empty string");
             return Ok(String::new());
         }
 
-        // Create canvas
-        let mut canvas = AsciiCanvas::new(layout.width, layout.height);
-
-        // Draw edges first (so commits overlay them)
-        for edge in &layout.edges {
-            self.draw_edge(&mut canvas, &edge.waypoints);
-        }
-
-        // Draw commits
-        for commit in &layout.commits {
-            if let Some(node_data) = database.get_node(&commit.id) {
-                self.draw_commit(&mut canvas, commit, &node_data.label);
-            }
+        let (canvas, roles) = self.draw_layout(&layout, self.color);
+        let mut output = roles.finish(&canvas);
+        if self.legend && !layout.lanes.is_empty() {
+            output.push_str("\n\n");
+            output.push_str(&self.draw_legend(&layout.lanes, database.direction()));
         }
-
-        let output = canvas.to_string();
         info!(
             output_len = output.len(),
             canvas_width = layout.width,
@@ -155,16 +311,51 @@ impl Renderer<GitGraphDatabase> for GitGraphRenderer {
         Ok(output)
     }
 
-    fn name(&self) -> &'static str {
-        "ascii"
+    /// Render the database as a [`CellGrid`]
+    ///
+    /// Cells of a commit's glyph, label and references carry the commit
+    /// ID, and cells of the line between two commits carry `from->to`;
+    /// roles become palette colors when color is on. The legend is left
+    /// out.
+    pub fn render_cells(&self, database: &GitGraphDatabase) -> Result<CellGrid> {
+        let folded = fold_sections(database, &self.folding);
+        let database = folded.as_ref().unwrap_or(database);
+        let layout = GitGraphLayoutAlgorithm::new().layout(database)?;
+        if layout.commits.is_empty() {
+            return Ok(CellGrid::default());
+        }
+        let (canvas, roles) = self.draw_layout(&layout, true);
+        Ok(roles.finish_cells(&canvas, self.color))
     }
 
-    fn version(&self) -> &'static str {
-        "0.1.0"
-    }
+    /// Draw the layout, recording roles and owners when `record` is set
+    fn draw_layout(
+        &self,
+        layout: &GitGraphLayoutResult,
+        record: bool,
+    ) -> (AsciiCanvas, RoleRecorder) {
+        let mut canvas = AsciiCanvas::new(layout.width, layout.height);
+        let mut roles = RoleRecorder::new(record, &canvas);
+
+        // Draw edges first (so commits overlay them), solid lines before the
+        // dotted cherry-pick links that only fill blank cells
+        let mut joins = HashMap::new();
+        let (dotted, solid): (Vec<_>, Vec<_>) = layout.edges.iter().partition(|e| e.dotted);
+        for edge in solid.into_iter().chain(dotted) {
+            canvas.track_writes();
+            self.draw_edge(&mut canvas, &mut joins, edge);
+            let id = format!("{}->{}", edge.from_id, edge.to_id);
+            roles.own_cells(&canvas.take_writes(), &id);
+            roles.record(&canvas, Role::Branch(edge.lane));
+        }
 
-    fn format(&self) -> &'static str {
-        "ascii"
+        // Draw commits
+        for commit in &layout.commits {
+            canvas.track_writes();
+            self.draw_commit(&mut canvas, &mut roles, commit);
+            roles.own_cells(&canvas.take_writes(), &commit.id);
+        }
+        (canvas, roles)
     }
 }
 
@@ -185,4 +376,139 @@ mod tests {
         assert!(!output.is_empty());
         assert!(output.contains("Initial") || output.contains("Feature"));
     }
+
+    fn long_history(count: usize) -> GitGraphDatabase {
+        let mut db = GitGraphDatabase::new();
+        for i in 0..count {
+            let id = format!("c{}", i);
+            db.add_commit(&id, None::<String>).unwrap();
+            db.set_commit_branch(&id, "main");
+            if i > 0 {
+                db.add_parent_edge(&id, format!("c{}", i - 1)).unwrap();
+            }
+        }
+        db
+    }
+
+    #[test]
+    fn test_show_commit_label_option_hides_ids() {
+        let mut db = long_history(3);
+        assert!(GitGraphRenderer::new().render(&db).unwrap().contains("c1"));
+
+        db.set_options(super::super::GitGraphOptions {
+            show_commit_label: false,
+            ..Default::default()
+        });
+        let output = GitGraphRenderer::new().render(&db).unwrap();
+        assert!(!output.contains("c1"), "{}", output);
+        assert!(output.contains('○'));
+    }
+
+    #[test]
+    fn test_long_history_renders_folded_summary() {
+        let db = long_history(40);
+
+        let output = GitGraphRenderer::new().render(&db).unwrap();
+        assert!(output.contains("main: 40 commits"));
+        assert!(output.contains('⋮'));
+        assert!(!output.contains("c12"));
+    }
+
+    #[test]
+    fn test_expanded_section_renders_every_commit() {
+        let db = long_history(40);
+
+        let renderer =
+            GitGraphRenderer::new().with_folding(FoldOptions::default().with_expanded("main"));
+        let output = renderer.render(&db).unwrap();
+        assert!(output.contains("c12"));
+        assert!(!output.contains("commits"));
+    }
+
+    const BRANCHED: &str = "gitGraph\n commit\n branch dev\n checkout dev\n commit\n checkout main\n commit\n merge dev\n";
+
+    fn parse_gitgraph(input: &str) -> GitGraphDatabase {
+        use crate::core::Parser;
+        let mut db = GitGraphDatabase::new();
+        super::super::GitGraphParser::new()
+            .parse(input, &mut db)
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_branch_legend_lists_lanes() {
+        let db = parse_gitgraph(BRANCHED);
+        assert!(!GitGraphRenderer::new()
+            .render(&db)
+            .unwrap()
+            .contains("Lanes"));
+
+        let output = GitGraphRenderer::new()
+            .with_legend(true)
+            .render(&db)
+            .unwrap();
+        assert!(
+            output.ends_with("Lanes, left to right:\n  │ main\n  │ dev"),
+            "{}",
+            output
+        );
+    }
+
+    #[test]
+    fn test_branch_colors_follow_lanes() {
+        let db = parse_gitgraph(BRANCHED);
+        let palette = Palette::default();
+        let main = format!("\x1b[{}m", palette.code(Role::Branch(0)));
+        let dev = format!("\x1b[{}m", palette.code(Role::Branch(1)));
+
+        let output = GitGraphRenderer::new()
+            .with_color(true)
+            .with_legend(true)
+            .render(&db)
+            .unwrap();
+        assert!(output.contains(&format!("{}dev\x1b[0m", dev)), "{}", output);
+        assert!(output.contains(&format!("{}c1\x1b[0m", main)), "{}", output);
+        assert!(output.contains(&format!("{}│\x1b[0m main", main)));
+        assert!(output.contains(&format!("{}│\x1b[0m dev", dev)));
+    }
+
+    const FORKS_AND_MERGES: &str = r#"gitGraph
+   commit id: "a"
+   branch dev
+   branch feat
+   checkout dev
+   commit id: "d1"
+   checkout feat
+   commit id: "f1"
+   checkout main
+   cherry-pick id: "d1"
+   checkout dev
+   commit id: "d2"
+   checkout main
+   merge dev tag: "v2"
+   merge feat"#;
+
+    #[test]
+    fn test_forks_and_merges_draw_like_git_log() {
+        let db = parse_gitgraph(FORKS_AND_MERGES);
+
+        let output = GitGraphRenderer::new().render(&db).unwrap();
+        assert_eq!(
+            output,
+            "○─┬─╮ a\n\
+             │ ○ │ d1\n\
+             │ │ ○ f1 (feat)\n\
+             ○┄│ │ c1 (tag: cherry-pick:d1)\n\
+             │ ○ │ d2 (dev)\n\
+             ○─╯ │ c2 (tag: v2)\n\
+             ○───╯ c3 (main)"
+        );
+
+        let ascii = GitGraphRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        assert!(ascii.starts_with("*-+-+ a\n"), "{}", ascii);
+        assert!(ascii.contains("*.| | c1"), "{}", ascii);
+    }
 }
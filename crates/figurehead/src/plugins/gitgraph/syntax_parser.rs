@@ -3,14 +3,20 @@
 //! Parses Mermaid.js git graph syntax into syntax nodes.
 //!
 //! Supported syntax:
-//! - `gitGraph` keyword to start
+//! - `gitGraph` keyword to start (also `gitGraph:` and `gitGraph TB:`)
+//! - an `options { ... } end` block (see [`GitGraphOptions`])
 //! - `commit` to add commits (with optional `id: "..."`, `type: NORMAL|REVERSE|HIGHLIGHT`, `tag: "..."`)
-//! - `branch <name>` to create and checkout a new branch
-//! - `checkout <name>` to switch to an existing branch
-//! - `merge <name>` to merge a branch into current branch
-
-use crate::core::{SyntaxMetadata, SyntaxNode, SyntaxParser};
+//! - `branch <name>` to create a branch at the current commit and check it out
+//! - `checkout <name>` (or `switch <name>`) to switch to an existing branch
+//! - `merge <name>` to merge a branch into the current branch (taking the
+//!   same `id:`, `type:` and `tag:` options as `commit`)
+//! - `cherry-pick id: "..."` to copy a commit from another branch onto the
+//!   current one
+
+use super::options::{parse_header, GitGraphOptions};
+use crate::core::{LenientStatements, StatementSplitter, SyntaxMetadata, SyntaxNode, SyntaxParser};
 use anyhow::Result;
+use std::collections::HashMap;
 use tracing::{debug, trace};
 
 /// Git graph syntax parser
@@ -64,165 +70,272 @@ impl GitGraphSyntaxParser {
     }
 }
 
-impl SyntaxParser for GitGraphSyntaxParser {
-    fn parse(&self, input: &str) -> Result<Vec<SyntaxNode>> {
-        trace!("Parsing git graph syntax");
-        let mut nodes = Vec::new();
-        let mut current_branch = "main".to_string();
-        let mut branches: std::collections::HashMap<String, Vec<String>> =
-            std::collections::HashMap::new();
-        branches.insert("main".to_string(), Vec::new());
-        let mut commit_counter = 0;
+/// Commits made so far on each branch, and where each branch started
+#[derive(Default)]
+struct History {
+    /// Commits on each branch, oldest first
+    commits: HashMap<String, Vec<String>>,
+    /// Commit each branch was created from
+    bases: HashMap<String, String>,
+    /// Branch each commit was made on
+    owners: HashMap<String, String>,
+}
 
-        // Split input into lines
-        let lines: Vec<&str> = input
-            .lines()
-            .map(|l| l.trim())
-            .filter(|l| !l.is_empty())
-            .collect();
+impl History {
+    fn has_branch(&self, branch: &str) -> bool {
+        self.commits.contains_key(branch)
+    }
 
-        if lines.is_empty() {
-            return Ok(nodes);
+    /// Start a branch at the head of `from`
+    fn create_branch(&mut self, branch: &str, from: &str) {
+        if let Some(base) = self.head(from) {
+            self.bases.insert(branch.to_string(), base);
         }
+        self.commits.insert(branch.to_string(), Vec::new());
+    }
 
-        // Skip gitGraph keyword if present
-        let mut line_iter = lines.iter().peekable();
-        if let Some(first_line) = line_iter.peek() {
-            if first_line.trim().eq_ignore_ascii_case("gitgraph") {
-                line_iter.next();
-            }
-        }
+    /// Latest commit reachable from a branch: its last commit, or the commit
+    /// it was created from
+    fn head(&self, branch: &str) -> Option<String> {
+        self.commits
+            .get(branch)
+            .and_then(|commits| commits.last())
+            .or_else(|| self.bases.get(branch))
+            .cloned()
+    }
 
-        // Parse each command
-        for line in line_iter {
-            let line_lower = line.to_lowercase();
+    fn branch_of(&self, commit: &str) -> Option<&str> {
+        self.owners.get(commit).map(String::as_str)
+    }
 
-            if line_lower.starts_with("commit") {
-                // Parse commit command
-                let (id, commit_type, tag) = Self::parse_commit_attributes(line);
-                let commit_id = id.clone().unwrap_or_else(|| {
-                    commit_counter += 1;
-                    format!("c{}", commit_counter)
-                });
+    fn record(&mut self, branch: &str, commit: &str) {
+        self.commits
+            .entry(branch.to_string())
+            .or_default()
+            .push(commit.to_string());
+        self.owners.insert(commit.to_string(), branch.to_string());
+    }
+}
 
-                let commit_type_str = commit_type.unwrap_or_else(|| "NORMAL".to_string());
+/// Branch name following a command keyword (`merge develop tag: "v1"`)
+fn branch_argument(rest: &str) -> String {
+    rest.split_whitespace()
+        .next()
+        .unwrap_or("")
+        .trim_matches('"')
+        .to_string()
+}
 
-                let mut metadata = SyntaxMetadata::new()
-                    .with_attr("type", "commit")
-                    .with_attr("commit_type", &commit_type_str);
+impl GitGraphSyntaxParser {
+    /// Add a commit on `branch` after its head, with an optional extra parent
+    #[allow(clippy::too_many_arguments)]
+    fn push_commit(
+        nodes: &mut Vec<SyntaxNode>,
+        history: &mut History,
+        branch: &str,
+        id: String,
+        label: Option<String>,
+        commit_type: &str,
+        tag: Option<&str>,
+        merged: Option<(String, &str)>,
+    ) {
+        let mut metadata = SyntaxMetadata::new()
+            .with_attr("type", "commit")
+            .with_attr("commit_type", commit_type)
+            .with_attr("branch", branch);
+        if let Some(tag) = tag {
+            metadata = metadata.with_attr("tag", tag);
+        }
+        let parent = history.head(branch);
+        nodes.push(SyntaxNode::Node {
+            id: id.clone(),
+            label,
+            metadata,
+        });
+        history.record(branch, &id);
+
+        // The merged (or cherry-picked) commit first, then the branch parent
+        if let Some((from, edge_type)) = merged {
+            nodes.push(SyntaxNode::Edge {
+                from,
+                to: id.clone(),
+                label: None,
+                metadata: SyntaxMetadata::new().with_attr("type", edge_type),
+            });
+        }
+        if let Some(parent) = parent {
+            nodes.push(SyntaxNode::Edge {
+                from: parent,
+                to: id,
+                label: None,
+                metadata: SyntaxMetadata::new().with_attr("type", "parent"),
+            });
+        }
+    }
 
-                if let Some(tag_val) = &tag {
-                    metadata = metadata.with_attr("tag", tag_val);
-                }
+    /// Parse commands into syntax nodes, skipping the ones it cannot read
+    pub(crate) fn parse_statements(
+        &self,
+        input: &str,
+        lenient: &mut LenientStatements,
+    ) -> Vec<SyntaxNode> {
+        trace!("Parsing git graph syntax");
+        let mut nodes = Vec::new();
+        let (options, _) = GitGraphOptions::from_input(input);
+        let mut current_branch = options.main_branch_name.clone();
+        let mut history = History::default();
+        history.create_branch(&current_branch, &current_branch);
+        let mut commit_counter = 0;
+        let mut next_id = |id: Option<String>| {
+            id.unwrap_or_else(|| {
+                commit_counter += 1;
+                format!("c{}", commit_counter)
+            })
+        };
+
+        // One command per line; the options block is a single statement
+        let statements = StatementSplitter::new()
+            .with_semicolons(false)
+            .with_blocks(&["options"])
+            .split(input);
 
-                let commit_label = id;
-                nodes.push(SyntaxNode::Node {
-                    id: commit_id.clone(),
-                    label: commit_label,
-                    metadata,
-                });
-
-                // Add commit to current branch and create edge
-                let prev_commit_opt = {
-                    let branch_commits = branches.get_mut(&current_branch).unwrap();
-                    let prev = if branch_commits.is_empty() {
-                        None
-                    } else {
-                        Some(branch_commits.last().unwrap().clone())
-                    };
-                    branch_commits.push(commit_id.clone());
-                    prev
-                };
+        // Parse each command
+        for statement in &statements {
+            let line = statement.text.as_str();
+            let line_lower = line.to_lowercase();
+            // The header and the options block (its keys are not commands)
+            if parse_header(line).is_some() || line_lower.starts_with("options") {
+                continue;
+            }
 
-                // Create edge from previous commit on this branch if exists
-                if let Some(prev_commit) = prev_commit_opt {
-                    nodes.push(SyntaxNode::Edge {
-                        from: prev_commit,
-                        to: commit_id,
-                        label: None,
-                        metadata: SyntaxMetadata::new().with_attr("type", "parent"),
-                    });
-                }
+            if line_lower.starts_with("commit") {
+                // commit id: "Alpha" type: HIGHLIGHT tag: "v1.0"
+                lenient.accept();
+                let (id, commit_type, tag) = Self::parse_commit_attributes(line);
+                let commit_id = next_id(id.clone());
+                Self::push_commit(
+                    &mut nodes,
+                    &mut history,
+                    &current_branch,
+                    commit_id,
+                    id,
+                    commit_type.as_deref().unwrap_or("NORMAL"),
+                    tag.as_deref(),
+                    None,
+                );
             } else if line_lower.starts_with("branch") {
-                // Parse branch command: branch develop
-                let branch_name = line[6..].trim().trim_matches('"').to_string();
-                if !branches.contains_key(&branch_name) {
-                    branches.insert(branch_name.clone(), Vec::new());
-                    nodes.push(SyntaxNode::Node {
-                        id: format!("branch_{}", branch_name),
-                        label: Some(branch_name.clone()),
-                        metadata: SyntaxMetadata::new().with_attr("type", "branch"),
-                    });
+                // branch develop: create it at the current head and check it out
+                lenient.accept();
+                let branch_name = branch_argument(&line[6..]);
+                if !history.has_branch(&branch_name) {
+                    history.create_branch(&branch_name, &current_branch);
+                    if options.show_branches {
+                        nodes.push(SyntaxNode::Node {
+                            id: format!("branch_{}", branch_name),
+                            label: Some(branch_name.clone()),
+                            metadata: SyntaxMetadata::new().with_attr("type", "branch"),
+                        });
+                    }
                 }
                 current_branch = branch_name;
             } else if line_lower.starts_with("checkout") || line_lower.starts_with("switch") {
-                // Parse checkout command: checkout develop
-                let branch_name = if line_lower.starts_with("checkout") {
-                    line[8..].trim().trim_matches('"').to_string()
+                // checkout develop
+                lenient.accept();
+                let keyword_len = if line_lower.starts_with("checkout") {
+                    8
                 } else {
-                    line[6..].trim().trim_matches('"').to_string()
+                    6
                 };
-                if branches.contains_key(&branch_name) {
+                let branch_name = branch_argument(&line[keyword_len..]);
+                if history.has_branch(&branch_name) {
                     current_branch = branch_name;
+                } else {
+                    let message = format!("Cannot check out unknown branch '{}'", branch_name);
+                    lenient.warn(statement, message);
                 }
             } else if line_lower.starts_with("merge") {
-                // Parse merge command: merge develop
-                let branch_name = line[5..].trim().trim_matches('"').to_string();
-                let (last_merged_opt, prev_commit_opt) = {
-                    let merged_commits = branches.get(&branch_name);
-                    let last_merged = merged_commits.and_then(|c| c.last()).cloned();
-
-                    let prev_commit = branches
-                        .get(&current_branch)
-                        .and_then(|c| if !c.is_empty() { c.last() } else { None })
-                        .cloned();
-
-                    (last_merged, prev_commit)
+                // merge develop id: "m1" tag: "v2" type: REVERSE
+                lenient.accept();
+                let branch_name = branch_argument(&line[5..]);
+                let problem = if !history.has_branch(&branch_name) {
+                    Some(format!("Cannot merge unknown branch '{}'", branch_name))
+                } else if branch_name == current_branch {
+                    Some(format!("Cannot merge branch '{}' into itself", branch_name))
+                } else {
+                    None
                 };
-
-                if let Some(last_merged) = last_merged_opt {
-                    // Create merge commit
-                    commit_counter += 1;
-                    let merge_commit_id = format!("c{}", commit_counter);
-                    nodes.push(SyntaxNode::Node {
-                        id: merge_commit_id.clone(),
-                        label: None,
-                        metadata: SyntaxMetadata::new()
-                            .with_attr("type", "commit")
-                            .with_attr("commit_type", "MERGE"),
-                    });
-
-                    branches
-                        .get_mut(&current_branch)
-                        .unwrap()
-                        .push(merge_commit_id.clone());
-
-                    // Edge from merged branch's last commit
-                    nodes.push(SyntaxNode::Edge {
-                        from: last_merged,
-                        to: merge_commit_id.clone(),
-                        label: None,
-                        metadata: SyntaxMetadata::new().with_attr("type", "merge"),
-                    });
-
-                    // Edge from current branch's previous commit if exists
-                    if let Some(prev_commit) = prev_commit_opt {
-                        nodes.push(SyntaxNode::Edge {
-                            from: prev_commit,
-                            to: merge_commit_id,
-                            label: None,
-                            metadata: SyntaxMetadata::new().with_attr("type", "parent"),
-                        });
-                    }
+                if let Some(message) = problem {
+                    lenient.warn(statement, message);
+                    continue;
                 }
+                let Some(merged) = history.head(&branch_name) else {
+                    let message = format!("Cannot merge branch '{}' with no commits", branch_name);
+                    lenient.warn(statement, message);
+                    continue;
+                };
+                let (id, commit_type, tag) = Self::parse_commit_attributes(line);
+                let commit_id = next_id(id.clone());
+                Self::push_commit(
+                    &mut nodes,
+                    &mut history,
+                    &current_branch,
+                    commit_id,
+                    id,
+                    commit_type.as_deref().unwrap_or("MERGE"),
+                    tag.as_deref(),
+                    Some((merged, "merge")),
+                );
+            } else if line_lower.starts_with("cherry-pick") {
+                // cherry-pick id: "c2" tag: "picked"
+                lenient.accept();
+                let (id, _, tag) = Self::parse_commit_attributes(line);
+                let Some(picked) = id else {
+                    lenient.warn(statement, "cherry-pick needs the id of a commit");
+                    continue;
+                };
+                let problem = match history.branch_of(&picked) {
+                    None => Some(format!("Cannot cherry-pick unknown commit '{}'", picked)),
+                    Some(branch) if branch == current_branch => Some(format!(
+                        "Cannot cherry-pick '{}' onto its own branch",
+                        picked
+                    )),
+                    Some(_) => None,
+                };
+                if let Some(message) = problem {
+                    lenient.warn(statement, message);
+                    continue;
+                }
+                let tag = tag.unwrap_or_else(|| format!("cherry-pick:{}", picked));
+                let commit_id = next_id(None);
+                Self::push_commit(
+                    &mut nodes,
+                    &mut history,
+                    &current_branch,
+                    commit_id,
+                    None,
+                    "CHERRY_PICK",
+                    Some(&tag),
+                    Some((picked, "cherry-pick")),
+                );
+            } else {
+                lenient.reject(statement, "unknown git graph command");
             }
         }
 
         debug!(
-            commit_count = commit_counter,
-            branch_count = branches.len(),
+            commit_count = history.owners.len(),
+            branch_count = history.commits.len(),
             "Parsed git graph"
         );
+        nodes
+    }
+}
+
+impl SyntaxParser for GitGraphSyntaxParser {
+    fn parse(&self, input: &str) -> Result<Vec<SyntaxNode>> {
+        let mut lenient = LenientStatements::new("gitgraph");
+        let nodes = self.parse_statements(input, &mut lenient);
+        lenient.finish()?;
         Ok(nodes)
     }
 
@@ -312,6 +425,143 @@ mod tests {
         assert!(commit_nodes.len() >= 2);
     }
 
+    #[test]
+    fn test_commits_record_branch() {
+        let parser = GitGraphSyntaxParser::new();
+        let input = r#"gitGraph
+   commit
+   branch develop
+   checkout develop
+   commit"#;
+        let nodes = parser.parse(input).unwrap();
+
+        let branches: Vec<_> = nodes
+            .iter()
+            .filter_map(|n| match n {
+                SyntaxNode::Node { metadata, .. }
+                    if metadata.get("type").map(String::as_str) == Some("commit") =>
+                {
+                    metadata.get("branch")
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(branches, vec!["main", "develop"]);
+    }
+
+    fn edges(nodes: &[SyntaxNode]) -> Vec<(&str, &str, &str)> {
+        nodes
+            .iter()
+            .filter_map(|n| match n {
+                SyntaxNode::Edge {
+                    from, to, metadata, ..
+                } => Some((
+                    from.as_str(),
+                    to.as_str(),
+                    metadata.get("type").map_or("", String::as_str),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_first_commit_on_a_branch_links_to_its_base() {
+        let parser = GitGraphSyntaxParser::new();
+        let input = r#"gitGraph
+   commit
+   commit
+   branch develop
+   commit
+   checkout main
+   commit"#;
+        let nodes = parser.parse(input).unwrap();
+
+        assert_eq!(
+            edges(&nodes),
+            vec![
+                ("c1", "c2", "parent"),
+                ("c2", "c3", "parent"),
+                ("c2", "c4", "parent"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_and_cherry_pick_link_their_sources() {
+        let parser = GitGraphSyntaxParser::new();
+        let input = r#"gitGraph
+   commit id: "a"
+   branch develop
+   commit id: "b"
+   commit id: "c"
+   checkout main
+   cherry-pick id: "b"
+   merge develop id: "m" tag: "v2" type: HIGHLIGHT"#;
+        let nodes = parser.parse(input).unwrap();
+
+        assert_eq!(
+            edges(&nodes),
+            vec![
+                ("a", "b", "parent"),
+                ("b", "c", "parent"),
+                ("b", "c1", "cherry-pick"),
+                ("a", "c1", "parent"),
+                ("c", "m", "merge"),
+                ("c1", "m", "parent"),
+            ]
+        );
+        let attrs = |id: &str, key: &str| {
+            nodes.iter().find_map(|n| match n {
+                SyntaxNode::Node {
+                    id: node_id,
+                    metadata,
+                    ..
+                } if node_id == id => metadata.get(key).cloned(),
+                _ => None,
+            })
+        };
+        assert_eq!(attrs("c1", "tag").as_deref(), Some("cherry-pick:b"));
+        assert_eq!(attrs("c1", "commit_type").as_deref(), Some("CHERRY_PICK"));
+        assert_eq!(attrs("m", "tag").as_deref(), Some("v2"));
+        assert_eq!(attrs("m", "commit_type").as_deref(), Some("HIGHLIGHT"));
+        assert_eq!(attrs("m", "branch").as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn test_invalid_merges_and_cherry_picks_warn() {
+        let parser = GitGraphSyntaxParser::new();
+        let input = r#"gitGraph
+   commit id: "a"
+   branch empty
+   checkout main
+   merge main
+   merge nowhere
+   cherry-pick id: "a"
+   cherry-pick id: "zzz"
+   cherry-pick"#;
+        let mut lenient = LenientStatements::new("gitgraph");
+        let nodes = parser.parse_statements(input, &mut lenient);
+        let messages: Vec<String> = lenient
+            .finish()
+            .unwrap()
+            .into_iter()
+            .map(|w| w.message)
+            .collect();
+
+        assert_eq!(
+            messages,
+            vec![
+                "Cannot merge branch 'main' into itself",
+                "Cannot merge unknown branch 'nowhere'",
+                "Cannot cherry-pick 'a' onto its own branch",
+                "Cannot cherry-pick unknown commit 'zzz'",
+                "cherry-pick needs the id of a commit",
+            ]
+        );
+        assert!(edges(&nodes).is_empty());
+    }
+
     #[test]
     fn test_can_parse() {
         let parser = GitGraphSyntaxParser::new();
@@ -4,15 +4,27 @@
 //! Each plugin implements the core traits for its specific diagram type.
 
 pub mod class;
+pub mod er;
 pub mod flowchart;
 pub mod gitgraph;
+pub mod observer;
 pub mod orchestrator;
+#[cfg(feature = "async")]
+pub mod orchestrator_async;
 pub mod sequence;
 pub mod state;
 
 pub use class::*;
+// `er::Relationship` stays namespaced; the class diagram's takes the short name
+pub use er::{
+    Attribute, AttributeKey, Cardinality, Entity, ErDatabase, ErDetector, ErDiagram, ErParser,
+    ErRenderer,
+};
 pub use flowchart::*;
 pub use gitgraph::*;
+pub use observer::{ProcessObserver, Stage, StageEvent, StageSize};
 pub use orchestrator::*;
+#[cfg(feature = "async")]
+pub use orchestrator_async::*;
 pub use sequence::*;
 pub use state::*;
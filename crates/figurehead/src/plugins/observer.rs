@@ -0,0 +1,198 @@
+//! Pipeline metrics hooks
+//!
+//! An [`Orchestrator`](super::Orchestrator) given a [`ProcessObserver`]
+//! reports every pipeline stage as it finishes, with its duration and the
+//! size of its result. Servers and editor integrations can feed these
+//! straight into their metrics system instead of scraping tracing output.
+
+use std::time::{Duration, Instant};
+
+/// A step of the processing pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    /// Choosing the diagram type
+    Detect,
+    /// Parsing markup into a database
+    Parse,
+    /// Positioning elements on the canvas
+    Layout,
+    /// Drawing the laid-out diagram
+    Render,
+}
+
+impl Stage {
+    /// Lowercase stage name, suitable as a metric label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Detect => "detect",
+            Stage::Parse => "parse",
+            Stage::Layout => "layout",
+            Stage::Render => "render",
+        }
+    }
+}
+
+/// Size of what a stage produced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageSize {
+    /// Length of the input that was classified, in bytes
+    Input {
+        /// Input length in bytes
+        bytes: usize,
+    },
+    /// Elements in the parsed database
+    ///
+    /// Nodes are flowchart nodes, commits, participants, classes, states or
+    /// entities; edges are edges, messages, relationships or transitions.
+    Elements {
+        /// Number of nodes
+        nodes: usize,
+        /// Number of edges
+        edges: usize,
+    },
+    /// Laid-out canvas size in character cells
+    Canvas {
+        /// Canvas width
+        width: usize,
+        /// Canvas height
+        height: usize,
+    },
+    /// Length of the rendered output, in bytes
+    Output {
+        /// Output length in bytes
+        bytes: usize,
+    },
+}
+
+/// A finished pipeline stage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageEvent<'a> {
+    /// Which stage finished
+    pub stage: Stage,
+    /// Diagram type name (`flowchart`, `gitgraph`, `sequence`, `class`, `state`,
+    /// `er`)
+    pub diagram_type: &'a str,
+    /// Wall-clock time spent in the stage
+    ///
+    /// Render time does not include the layout it ran.
+    pub elapsed: Duration,
+    /// Size of the stage's result
+    pub size: StageSize,
+}
+
+/// Receives a [`StageEvent`] each time an orchestrator stage succeeds
+///
+/// Failed stages are not reported; the error is returned to the caller as
+/// usual. Any `Fn(&StageEvent)` closure can be used as an observer.
+///
+/// Timings use [`std::time::Instant`], which is unavailable on
+/// `wasm32-unknown-unknown`, so don't attach observers there.
+///
+/// # Example
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use figurehead::plugins::{Orchestrator, Stage, StageEvent};
+///
+/// let stages = Arc::new(Mutex::new(Vec::new()));
+/// let seen = stages.clone();
+///
+/// let mut orchestrator = Orchestrator::with_all_plugins();
+/// orchestrator.register_default_detectors();
+/// orchestrator.set_observer(Arc::new(move |event: &StageEvent| {
+///     seen.lock().unwrap().push(event.stage);
+/// }));
+/// orchestrator.process("graph LR; A-->B").unwrap();
+///
+/// assert_eq!(
+///     *stages.lock().unwrap(),
+///     [Stage::Detect, Stage::Parse, Stage::Layout, Stage::Render]
+/// );
+/// ```
+pub trait ProcessObserver: Send + Sync {
+    /// Called after a stage completes
+    fn on_stage(&self, event: &StageEvent<'_>);
+}
+
+impl<F> ProcessObserver for F
+where
+    F: Fn(&StageEvent<'_>) + Send + Sync,
+{
+    fn on_stage(&self, event: &StageEvent<'_>) {
+        self(event)
+    }
+}
+
+/// Layout time and canvas size recorded while rendering
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LayoutMetrics {
+    pub elapsed: Duration,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Collects the layout metrics of one render
+///
+/// Renderers run their own layout, so the orchestrator passes a probe down
+/// the render call and reads the metrics back afterwards. A disabled probe
+/// (the default) never reads the clock.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LayoutProbe {
+    enabled: bool,
+    metrics: Option<LayoutMetrics>,
+}
+
+impl LayoutProbe {
+    /// A probe that records layout metrics
+    pub(crate) fn enabled() -> Self {
+        Self {
+            enabled: true,
+            metrics: None,
+        }
+    }
+
+    /// Start timing a layout (`None` when disabled)
+    pub(crate) fn start(&self) -> Option<Instant> {
+        self.enabled.then(Instant::now)
+    }
+
+    /// Record a finished layout's canvas size
+    ///
+    /// Several layouts in one render add up their time and keep the last size.
+    pub(crate) fn finish(&mut self, started: Option<Instant>, width: usize, height: usize) {
+        let Some(started) = started else {
+            return;
+        };
+        let earlier = self.metrics.map_or(Duration::ZERO, |m| m.elapsed);
+        self.metrics = Some(LayoutMetrics {
+            elapsed: earlier + started.elapsed(),
+            width,
+            height,
+        });
+    }
+
+    /// Metrics recorded so far, if any layout was timed
+    pub(crate) fn metrics(&self) -> Option<LayoutMetrics> {
+        self.metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_records_only_when_enabled() {
+        let mut disabled = LayoutProbe::default();
+        let started = disabled.start();
+        disabled.finish(started, 10, 4);
+        assert_eq!(disabled.metrics(), None);
+
+        let mut probe = LayoutProbe::enabled();
+        let started = probe.start();
+        probe.finish(started, 10, 4);
+        let started = probe.start();
+        probe.finish(started, 12, 6);
+        let metrics = probe.metrics().unwrap();
+        assert_eq!((metrics.width, metrics.height), (12, 6));
+    }
+}
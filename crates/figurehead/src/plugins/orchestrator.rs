@@ -4,23 +4,47 @@
 //! Detector → Parser → Database → Layout → Renderer
 
 use anyhow::Result;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
 use tracing::{debug, info, span, trace, warn, Level};
 
-use crate::core::{Database, Detector, Parser, RenderConfig, Renderer};
+use crate::core::footer::visible_width;
+use crate::core::{
+    header_line, CanvasLimit, CellGrid, Database, Detector, Diagram, FigureheadError,
+    InitDirective, OverflowPolicy, Palette, ParseWarning, Parser, RenderConfig, Renderer,
+    RoleLayer, Theme,
+};
 use crate::plugins::class::ClassDatabase;
+use crate::plugins::er::ErDatabase;
 use crate::plugins::flowchart::FlowchartDatabase;
 use crate::plugins::gitgraph::GitGraphDatabase;
+use crate::plugins::observer::{LayoutProbe, ProcessObserver, Stage, StageEvent, StageSize};
 use crate::plugins::sequence::SequenceDatabase;
 use crate::plugins::state::StateDatabase;
 
+/// A diagram type an [`Orchestrator`] is set up to process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginInfo {
+    /// Name returned by detection and accepted by [`Orchestrator::process_as`]
+    pub name: &'static str,
+    /// Version of the configured parser
+    pub parser_version: &'static str,
+    /// Version of the configured renderer
+    pub renderer_version: &'static str,
+    /// Whether a detector is registered under this name, so that
+    /// [`Orchestrator::process`] can pick the type on its own
+    pub detector: bool,
+}
+
 /// Plugin orchestrator that coordinates the entire pipeline
 ///
 /// The orchestrator wires detectors, parsers, layout, and renderer pieces
 /// together so callers can run a full pipeline without handling each trait
 /// manually.
 pub struct Orchestrator {
-    detectors: HashMap<String, Box<dyn Detector>>,
+    detectors: HashMap<String, Arc<dyn Detector>>,
     flowchart_parser: Option<crate::plugins::flowchart::FlowchartParser>,
     flowchart_layout: Option<crate::plugins::flowchart::FlowchartLayoutAlgorithm>,
     ascii_renderer: Option<crate::plugins::flowchart::FlowchartRenderer>,
@@ -32,6 +56,12 @@ pub struct Orchestrator {
     class_renderer: Option<crate::plugins::class::ClassRenderer>,
     state_parser: Option<crate::plugins::state::StateParser>,
     state_renderer: Option<crate::plugins::state::StateRenderer>,
+    er_parser: Option<crate::plugins::er::ErParser>,
+    er_renderer: Option<crate::plugins::er::ErRenderer>,
+    diagrams: Vec<Box<dyn ExternalDiagram>>,
+    observer: Option<Arc<dyn ProcessObserver>>,
+    overflow_policy: OverflowPolicy,
+    overflow_limit: CanvasLimit,
 }
 
 impl Orchestrator {
@@ -50,6 +80,12 @@ impl Orchestrator {
             class_renderer: None,
             state_parser: None,
             state_renderer: None,
+            er_parser: None,
+            er_renderer: None,
+            diagrams: Vec::new(),
+            observer: None,
+            overflow_policy: OverflowPolicy::default(),
+            overflow_limit: CanvasLimit::default(),
         }
     }
 
@@ -78,6 +114,12 @@ impl Orchestrator {
             class_renderer: None,
             state_parser: None,
             state_renderer: None,
+            er_parser: None,
+            er_renderer: None,
+            diagrams: Vec::new(),
+            observer: None,
+            overflow_policy: config.overflow_policy,
+            overflow_limit: config.overflow_limit,
         }
     }
 
@@ -99,24 +141,103 @@ impl Orchestrator {
                 config,
             )),
             gitgraph_parser: Some(crate::plugins::gitgraph::GitGraphParser::new()),
-            gitgraph_renderer: Some(crate::plugins::gitgraph::GitGraphRenderer::new()),
+            gitgraph_renderer: Some(
+                crate::plugins::gitgraph::GitGraphRenderer::with_style(config.style)
+                    .with_color(config.color),
+            ),
             sequence_parser: Some(crate::plugins::sequence::SequenceParser::new()),
-            sequence_renderer: Some(crate::plugins::sequence::SequenceRenderer::new()),
+            sequence_renderer: Some(
+                crate::plugins::sequence::SequenceRenderer::with_style(config.style)
+                    .with_color(config.color),
+            ),
             class_parser: Some(crate::plugins::class::ClassParser::new()),
-            class_renderer: Some(crate::plugins::class::ClassRenderer::new()),
+            class_renderer: Some(
+                crate::plugins::class::ClassRenderer::with_style(config.style)
+                    .with_color(config.color),
+            ),
             state_parser: Some(crate::plugins::state::StateParser::new()),
-            state_renderer: Some(crate::plugins::state::StateRenderer::new()),
+            state_renderer: Some(
+                crate::plugins::state::StateRenderer::with_style(config.style)
+                    .with_color(config.color),
+            ),
+            er_parser: Some(crate::plugins::er::ErParser::new()),
+            er_renderer: Some(
+                crate::plugins::er::ErRenderer::with_style(config.style).with_color(config.color),
+            ),
+            diagrams: Vec::new(),
+            observer: None,
+            overflow_policy: config.overflow_policy,
+            overflow_limit: config.overflow_limit,
         }
     }
 
     /// Register a detector plugin
     pub fn register_detector(&mut self, name: String, detector: Box<dyn Detector>) {
-        self.detectors.insert(name, detector);
+        self.detectors.insert(name, Arc::from(detector));
+    }
+
+    /// Register a diagram type implemented outside this crate
+    ///
+    /// The diagram's detector is registered under [`Diagram::name`], so
+    /// [`process`](Self::process) picks it like a built-in type, and
+    /// [`process_as`](Self::process_as) accepts the name. Its output goes
+    /// through the same observer, empty-diagram and overflow handling.
+    /// Registering a name again replaces the earlier diagram, and a
+    /// registered diagram takes the place of a built-in type of the same name.
+    pub fn register_diagram<D>(&mut self)
+    where
+        D: Diagram + 'static,
+        D::Renderer: Renderer<D::Database, Output = String>,
+    {
+        let name = D::name();
+        debug!(diagram_type = name, "Registering external diagram");
+        self.detectors.insert(name.to_string(), D::detector());
+        self.diagrams.retain(|diagram| diagram.name() != name);
+        self.diagrams.push(Box::new(RegisteredDiagram::<D> {
+            parser: D::create_parser(),
+            renderer: D::create_renderer(),
+        }));
+    }
+
+    /// Replace the flowchart parser (e.g. to change its repeated header policy)
+    pub fn set_flowchart_parser(&mut self, parser: crate::plugins::flowchart::FlowchartParser) {
+        self.flowchart_parser = Some(parser);
+    }
+
+    /// Replace the class parser (e.g. to change its ID case handling)
+    pub fn set_class_parser(&mut self, parser: crate::plugins::class::ClassParser) {
+        self.class_parser = Some(parser);
+    }
+
+    /// Replace the flowchart renderer (e.g. to change its layout config)
+    ///
+    /// Init directives in the input still adjust the layout on top of it.
+    pub fn set_flowchart_renderer(
+        &mut self,
+        renderer: crate::plugins::flowchart::FlowchartRenderer,
+    ) {
+        self.ascii_renderer = Some(renderer);
+    }
+
+    /// Replace the git graph renderer (e.g. to change section folding)
+    pub fn set_gitgraph_renderer(&mut self, renderer: crate::plugins::gitgraph::GitGraphRenderer) {
+        self.gitgraph_renderer = Some(renderer);
+    }
+
+    /// Replace the sequence renderer (e.g. to enable the message index)
+    pub fn set_sequence_renderer(&mut self, renderer: crate::plugins::sequence::SequenceRenderer) {
+        self.sequence_renderer = Some(renderer);
     }
 
-    /// Register the default set of detectors (flowchart, gitgraph, sequence, class, state)
+    /// Report each pipeline stage's timing and size to `observer`
+    pub fn set_observer(&mut self, observer: Arc<dyn ProcessObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// Register the default set of detectors (flowchart, gitgraph, sequence, class, state, er)
     pub fn register_default_detectors(&mut self) -> &mut Self {
         use crate::plugins::class::ClassDetector;
+        use crate::plugins::er::ErDetector;
         use crate::plugins::flowchart::FlowchartDetector;
         use crate::plugins::gitgraph::GitGraphDetector;
         use crate::plugins::sequence::SequenceDetector;
@@ -126,6 +247,7 @@ impl Orchestrator {
         self.register_detector("sequence".to_string(), Box::new(SequenceDetector::new()));
         self.register_detector("class".to_string(), Box::new(ClassDetector::new()));
         self.register_detector("state".to_string(), Box::new(StateDetector::new()));
+        self.register_detector("er".to_string(), Box::new(ErDetector::new()));
         self
     }
 
@@ -134,6 +256,64 @@ impl Orchestrator {
         self.detectors.keys().cloned().collect()
     }
 
+    /// List the diagram types with both a parser and a renderer configured
+    ///
+    /// Built-in types come in a fixed order (flowchart, gitgraph, sequence,
+    /// class, state, er), followed by [registered](Self::register_diagram)
+    /// ones in registration order; detectors registered under other names
+    /// are left out.
+    pub fn plugins(&self) -> Vec<PluginInfo> {
+        let flowchart = self.flowchart_layout.as_ref().and(plugin_versions(
+            &self.flowchart_parser,
+            &self.ascii_renderer,
+        ));
+        [
+            ("flowchart", flowchart),
+            (
+                "gitgraph",
+                plugin_versions(&self.gitgraph_parser, &self.gitgraph_renderer),
+            ),
+            (
+                "sequence",
+                plugin_versions(&self.sequence_parser, &self.sequence_renderer),
+            ),
+            (
+                "class",
+                plugin_versions(&self.class_parser, &self.class_renderer),
+            ),
+            (
+                "state",
+                plugin_versions(&self.state_parser, &self.state_renderer),
+            ),
+            ("er", plugin_versions(&self.er_parser, &self.er_renderer)),
+        ]
+        .into_iter()
+        .filter(|(name, _)| self.external(name).is_none())
+        .chain(
+            self.diagrams
+                .iter()
+                .map(|diagram| (diagram.name(), Some(diagram.versions()))),
+        )
+        .filter_map(|(name, versions)| {
+            let (parser_version, renderer_version) = versions?;
+            Some(PluginInfo {
+                name,
+                parser_version,
+                renderer_version,
+                detector: self.detectors.contains_key(name),
+            })
+        })
+        .collect()
+    }
+
+    /// Registered diagram named `name`
+    fn external(&self, name: &str) -> Option<&dyn ExternalDiagram> {
+        self.diagrams
+            .iter()
+            .find(|diagram| diagram.name() == name)
+            .map(Box::as_ref)
+    }
+
     /// Check if flowchart plugins are available
     pub fn has_flowchart_plugins(&self) -> bool {
         self.flowchart_parser.is_some()
@@ -143,18 +323,39 @@ impl Orchestrator {
 
     /// Detect diagram type from input text
     ///
-    /// Finds the detector with highest confidence score.
+    /// Finds the detector with highest confidence score. The header line is
+    /// checked first ([`Detector::prefix_confidence`]); the full input is only
+    /// scanned when no detector recognizes the header, which keeps detection
+    /// cheap on large inputs.
     pub fn detect_diagram_type(&self, input: &str) -> Result<String> {
         let detect_span = span!(Level::INFO, "detect_diagram_type", input_len = input.len());
         let _enter = detect_span.enter();
 
         trace!("Starting diagram type detection");
+        let started = self.start_timer();
+
+        let header = header_line(input);
+        let by_header: Vec<(&String, f64)> = self
+            .detectors
+            .iter()
+            .filter_map(|(name, detector)| Some((name, detector.prefix_confidence(header)?)))
+            .filter(|(_, confidence)| *confidence > 0.5)
+            .collect();
+        let confidences: Box<dyn Iterator<Item = (&String, f64)>> = if by_header.is_empty() {
+            trace!("No detector recognized the header, scanning the full input");
+            Box::new(
+                self.detectors
+                    .iter()
+                    .map(|(name, detector)| (name, detector.confidence(input))),
+            )
+        } else {
+            Box::new(by_header.into_iter())
+        };
 
         // Find detector with highest confidence
         let mut best_match: Option<(&str, f64)> = None;
 
-        for (name, detector) in &self.detectors {
-            let confidence = detector.confidence(input);
+        for (name, confidence) in confidences {
             trace!(detector = name, confidence, "Checking detector");
 
             if confidence > 0.5 {
@@ -170,11 +371,17 @@ impl Orchestrator {
 
         if let Some((name, confidence)) = best_match {
             info!(detector = name, confidence, "Detected diagram type");
+            self.report(
+                Stage::Detect,
+                name,
+                started,
+                StageSize::Input { bytes: input.len() },
+            );
             return Ok(name.to_string());
         }
 
         warn!("No suitable detector found for input");
-        Err(anyhow::anyhow!("No suitable detector found for input"))
+        Err(FigureheadError::Undetected.into())
     }
 
     /// Process input through the complete pipeline (for flowcharts only)
@@ -193,22 +400,162 @@ impl Orchestrator {
         debug!(diagram_type, "Diagram type detected");
         drop(_detect_enter);
 
-        match diagram_type.as_str() {
+        self.process_as(&diagram_type, input)
+    }
+
+    /// Process input as the named diagram type, skipping detection
+    ///
+    /// For inputs that detection misreads. `diagram_type` is one of the
+    /// names listed by [`plugins`](Self::plugins), e.g. `"sequence"`.
+    ///
+    /// # Example
+    /// ```
+    /// use figurehead::plugins::Orchestrator;
+    ///
+    /// let orchestrator = Orchestrator::with_all_plugins();
+    /// let output = orchestrator.process_as("sequence", "Alice->>Bob: Hi").unwrap();
+    /// assert!(output.contains("Hi"));
+    /// assert!(orchestrator.process_as("pie", "pie").is_err());
+    /// ```
+    pub fn process_as(&self, diagram_type: &str, input: &str) -> Result<String> {
+        if let Some(diagram) = self.external(diagram_type) {
+            return diagram.process(self, input);
+        }
+        match diagram_type {
             "flowchart" => self.process_flowchart(input),
             "gitgraph" => self.process_gitgraph(input),
             "sequence" => self.process_sequence(input),
             "class" => self.process_class(input),
             "state" => self.process_state(input),
+            "er" => self.process_er(input),
             _ => {
                 warn!(diagram_type, "Unsupported diagram type");
-                Err(anyhow::anyhow!(
-                    "Unsupported diagram type: {}",
-                    diagram_type
-                ))
+                Err(FigureheadError::UnsupportedDiagramType {
+                    diagram_type: diagram_type.to_string(),
+                }
+                .into())
+            }
+        }
+    }
+
+    /// Process input and return the output with the parse warnings
+    /// recorded along the way
+    ///
+    /// Parsing skips statements it can't read; each skipped statement comes
+    /// back as a warning, for every diagram type.
+    pub fn process_with_warnings(&self, input: &str) -> Result<(String, Vec<ParseWarning>)> {
+        let diagram_type = self.detect_diagram_type(input)?;
+        if let Some(diagram) = self.external(&diagram_type) {
+            return diagram.process_with_warnings(self, input);
+        }
+        fn warned<D: Database>((output, mut database): (String, D)) -> (String, Vec<ParseWarning>) {
+            (output, database.take_parse_warnings())
+        }
+        match diagram_type.as_str() {
+            "flowchart" => self.process_flowchart_with_database(input).map(warned),
+            "gitgraph" => self.process_gitgraph_with_database(input).map(warned),
+            "sequence" => self.process_sequence_with_database(input).map(warned),
+            "class" => self.process_class_with_database(input).map(warned),
+            "state" => self.process_state_with_database(input).map(warned),
+            "er" => self.process_er_with_database(input).map(warned),
+            _ => self
+                .process_as(&diagram_type, input)
+                .map(|output| (output, Vec::new())),
+        }
+    }
+
+    /// Process input into an HTML `<pre>` block
+    ///
+    /// Flowcharts are colored from their `classDef` and `style` statements;
+    /// other diagram types keep the colors of their text output (the role
+    /// palette when color is on).
+    pub fn process_html(&self, input: &str) -> Result<String> {
+        match self.detect_diagram_type(input)?.as_str() {
+            "flowchart" => self.process_flowchart_html(input),
+            _ => Ok(crate::core::ansi_to_html(&self.process(input)?)),
+        }
+    }
+
+    /// Process flowchart input into an HTML `<pre>` block (skip detection)
+    ///
+    /// See [`FlowchartRenderer::render_html`](crate::plugins::flowchart::FlowchartRenderer::render_html).
+    pub fn process_flowchart_html(&self, input: &str) -> Result<String> {
+        let mut database = self.parse_flowchart(input)?;
+        let renderer = self.flowchart_renderer_for(input, &mut database)?;
+        self.render_stage("flowchart", input, |probe| {
+            renderer.render_html_probed(&database, probe)
+        })
+    }
+
+    /// Process input into a [`CellGrid`]
+    ///
+    /// Cells carry the ID of the node or edge drawn over them (edges are
+    /// named `from->to`) and the colors the text output would have;
+    /// flowchart cells also take the colors of their `classDef` and `style`
+    /// statements. Diagram types registered at runtime come out uncolored
+    /// and without owners.
+    pub fn process_cells(&self, input: &str) -> Result<CellGrid> {
+        fn parse_with<D: Database, P: Parser<D>>(
+            parser: &Option<P>,
+            mut database: D,
+            input: &str,
+        ) -> Result<D> {
+            let parser = parser
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("No parser available"))?;
+            parser.parse(input, &mut database)?;
+            Ok(database)
+        }
+        fn renderer<R>(renderer: &Option<R>) -> Result<&R> {
+            renderer
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("No renderer available"))
+        }
+
+        let diagram_type = self.detect_diagram_type(input)?;
+        if self.external(&diagram_type).is_some() {
+            let text = self.process_as(&diagram_type, input)?;
+            return Ok(RoleLayer::new().to_grid(&text, &Palette::default()));
+        }
+        match diagram_type.as_str() {
+            "flowchart" => self.process_flowchart_cells(input),
+            "gitgraph" => {
+                let database = parse_with(&self.gitgraph_parser, GitGraphDatabase::new(), input)?;
+                renderer(&self.gitgraph_renderer)?.render_cells(&database)
+            }
+            "sequence" => {
+                let mut database =
+                    parse_with(&self.sequence_parser, SequenceDatabase::new(), input)?;
+                let renderer = self.sequence_renderer_for(input, &mut database)?;
+                renderer.render_cells(&database)
             }
+            "class" => {
+                let database = parse_with(&self.class_parser, ClassDatabase::new(), input)?;
+                renderer(&self.class_renderer)?.render_cells(&database)
+            }
+            "state" => {
+                let database = parse_with(&self.state_parser, StateDatabase::new(), input)?;
+                renderer(&self.state_renderer)?.render_cells(&database)
+            }
+            "er" => {
+                let database = parse_with(&self.er_parser, ErDatabase::new(), input)?;
+                renderer(&self.er_renderer)?.render_cells(&database)
+            }
+            _ => self
+                .process_as(&diagram_type, input)
+                .map(|_| CellGrid::default()),
         }
     }
 
+    /// Process flowchart input into a [`CellGrid`] (skip detection)
+    ///
+    /// See [`FlowchartRenderer::render_cells`](crate::plugins::flowchart::FlowchartRenderer::render_cells).
+    pub fn process_flowchart_cells(&self, input: &str) -> Result<CellGrid> {
+        let mut database = self.parse_flowchart(input)?;
+        let renderer = self.flowchart_renderer_for(input, &mut database)?;
+        renderer.render_cells(&database)
+    }
+
     /// Process flowchart input directly (skip detection)
     ///
     /// Useful when the caller already knows the diagram type.
@@ -227,7 +574,17 @@ impl Orchestrator {
             .ok_or_else(|| anyhow::anyhow!("No flowchart parser available"))?;
 
         let mut database = FlowchartDatabase::new();
+        let started = self.start_timer();
         parser.parse(input, &mut database)?;
+        self.report(
+            Stage::Parse,
+            "flowchart",
+            started,
+            StageSize::Elements {
+                nodes: database.node_count(),
+                edges: database.edge_count(),
+            },
+        );
         debug!(
             node_count = database.node_count(),
             edge_count = database.edge_count(),
@@ -238,12 +595,11 @@ impl Orchestrator {
         // Step 2: Render the result
         let render_span = span!(Level::DEBUG, "pipeline_render");
         let _render_enter = render_span.enter();
-        let renderer = self
-            .ascii_renderer
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No ASCII renderer available"))?;
+        let renderer = self.flowchart_renderer_for(input, &mut database)?;
 
-        let canvas = renderer.render(&database)?;
+        let canvas = self.render_stage("flowchart", input, |probe| {
+            renderer.render_probed(&database, probe)
+        })?;
         debug!(output_len = canvas.len(), "Rendering completed");
         drop(_render_enter);
 
@@ -256,7 +612,8 @@ impl Orchestrator {
     /// Process flowchart input and return both output and the parsed database
     ///
     /// This method is useful when callers need access to the parsed data structure
-    /// (e.g., for applying style-based colorization to the output).
+    /// (e.g., for applying style-based colorization to the output, or for
+    /// reading its parse warnings).
     pub fn process_flowchart_with_database(
         &self,
         input: &str,
@@ -279,7 +636,17 @@ impl Orchestrator {
             .ok_or_else(|| anyhow::anyhow!("No flowchart parser available"))?;
 
         let mut database = FlowchartDatabase::new();
+        let started = self.start_timer();
         parser.parse(input, &mut database)?;
+        self.report(
+            Stage::Parse,
+            "flowchart",
+            started,
+            StageSize::Elements {
+                nodes: database.node_count(),
+                edges: database.edge_count(),
+            },
+        );
         debug!(
             node_count = database.node_count(),
             edge_count = database.edge_count(),
@@ -290,12 +657,11 @@ impl Orchestrator {
         // Step 2: Render the result
         let render_span = span!(Level::DEBUG, "pipeline_render");
         let _render_enter = render_span.enter();
-        let renderer = self
-            .ascii_renderer
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No ASCII renderer available"))?;
+        let renderer = self.flowchart_renderer_for(input, &mut database)?;
 
-        let canvas = renderer.render(&database)?;
+        let canvas = self.render_stage("flowchart", input, |probe| {
+            renderer.render_probed(&database, probe)
+        })?;
         debug!(output_len = canvas.len(), "Rendering completed");
         drop(_render_enter);
 
@@ -304,10 +670,118 @@ impl Orchestrator {
         Ok((canvas, database))
     }
 
+    /// Parse flowchart input into a database without rendering it
+    ///
+    /// Useful for inspecting a diagram, e.g. describing a single node.
+    pub fn parse_flowchart(&self, input: &str) -> Result<FlowchartDatabase> {
+        let parser = self
+            .flowchart_parser
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No flowchart parser available"))?;
+
+        let mut database = FlowchartDatabase::new();
+        let started = self.start_timer();
+        parser.parse(input, &mut database)?;
+        self.report(
+            Stage::Parse,
+            "flowchart",
+            started,
+            StageSize::Elements {
+                nodes: database.node_count(),
+                edges: database.edge_count(),
+            },
+        );
+        Ok(database)
+    }
+
+    /// Flowchart renderer adjusted for any `%%{init: ...}%%` directive in the input
+    ///
+    /// Unsupported directive options are logged and recorded as parse
+    /// warnings on `database` rather than failing the render.
+    fn flowchart_renderer_for(
+        &self,
+        input: &str,
+        database: &mut FlowchartDatabase,
+    ) -> Result<Cow<'_, crate::plugins::flowchart::FlowchartRenderer>> {
+        let renderer = self
+            .ascii_renderer
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No ASCII renderer available"))?;
+
+        let Some(directive) = InitDirective::from_input(input) else {
+            return Ok(Cow::Borrowed(renderer));
+        };
+
+        let mut layout_config = renderer.layout_config().clone();
+        let mut warnings = directive.warnings;
+        warnings.extend(layout_config.apply_init(&directive.config.flowchart));
+        let theme = directive.config.theme.as_deref().and_then(|name| {
+            let theme = Theme::named(name);
+            if theme.is_none() {
+                warnings.push(format!("Theme '{}' is not supported; ignored", name));
+            }
+            theme
+        });
+        for warning in warnings {
+            warn!(%warning, "Init directive");
+            database.add_parse_warning(crate::plugins::flowchart::ParseWarning::new(warning));
+        }
+        debug!(?layout_config, "Applied init directive");
+
+        let mut renderer = renderer.clone().with_layout_config(layout_config);
+        if let Some(Ok(curve)) = directive.config.flowchart.curve.as_deref().map(str::parse) {
+            renderer = renderer.with_edge_curve(curve);
+        }
+        if theme.is_some() {
+            renderer = renderer.with_theme(theme);
+        }
+        Ok(Cow::Owned(renderer))
+    }
+
+    /// Sequence renderer adjusted for any `%%{init: ...}%%` directive in the input
+    ///
+    /// Unsupported directive options are logged and recorded as parse
+    /// warnings on `database` rather than failing the render.
+    fn sequence_renderer_for(
+        &self,
+        input: &str,
+        database: &mut SequenceDatabase,
+    ) -> Result<Cow<'_, crate::plugins::sequence::SequenceRenderer>> {
+        let renderer = self
+            .sequence_renderer
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No sequence renderer available"))?;
+
+        let Some(directive) = InitDirective::from_input(input) else {
+            return Ok(Cow::Borrowed(renderer));
+        };
+
+        for warning in directive.warnings {
+            warn!(%warning, "Init directive");
+            database.add_parse_warning(crate::core::ParseWarning::new(warning));
+        }
+        debug!(init = ?directive.config.sequence, "Applied init directive");
+
+        Ok(Cow::Owned(
+            renderer.clone().with_init(&directive.config.sequence),
+        ))
+    }
+
     /// Process git graph input directly (skip detection)
     ///
     /// Useful when the caller already knows the diagram type.
     pub fn process_gitgraph(&self, input: &str) -> Result<String> {
+        self.process_gitgraph_with_database(input)
+            .map(|(output, _)| output)
+    }
+
+    /// Process git graph input and return both output and the parsed database
+    ///
+    /// Useful for reading the database's parse warnings.
+    pub fn process_gitgraph_with_database(
+        &self,
+        input: &str,
+    ) -> Result<(String, GitGraphDatabase)> {
         let gitgraph_span = span!(Level::INFO, "process_gitgraph", input_len = input.len());
         let _enter = gitgraph_span.enter();
 
@@ -322,7 +796,17 @@ impl Orchestrator {
             .ok_or_else(|| anyhow::anyhow!("No git graph parser available"))?;
 
         let mut database = GitGraphDatabase::new();
+        let started = self.start_timer();
         parser.parse(input, &mut database)?;
+        self.report(
+            Stage::Parse,
+            "gitgraph",
+            started,
+            StageSize::Elements {
+                nodes: database.node_count(),
+                edges: database.edge_count(),
+            },
+        );
         debug!(
             node_count = database.node_count(),
             edge_count = database.edge_count(),
@@ -338,18 +822,31 @@ impl Orchestrator {
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No git graph renderer available"))?;
 
-        let canvas = renderer.render(&database)?;
+        let canvas = self.render_stage("gitgraph", input, |probe| {
+            renderer.render_probed(&database, probe)
+        })?;
         debug!(output_len = canvas.len(), "Rendering completed");
         drop(_render_enter);
 
         info!("Git graph processing completed successfully");
-        Ok(canvas)
+        Ok((canvas, database))
     }
 
     /// Process sequence diagram input directly (skip detection)
     ///
     /// Useful when the caller already knows the diagram type.
     pub fn process_sequence(&self, input: &str) -> Result<String> {
+        self.process_sequence_with_database(input)
+            .map(|(output, _)| output)
+    }
+
+    /// Process sequence diagram input and return both output and the parsed database
+    ///
+    /// Useful for reading the database's parse warnings.
+    pub fn process_sequence_with_database(
+        &self,
+        input: &str,
+    ) -> Result<(String, SequenceDatabase)> {
         let sequence_span = span!(Level::INFO, "process_sequence", input_len = input.len());
         let _enter = sequence_span.enter();
 
@@ -364,7 +861,17 @@ impl Orchestrator {
             .ok_or_else(|| anyhow::anyhow!("No sequence parser available"))?;
 
         let mut database = SequenceDatabase::new();
+        let started = self.start_timer();
         parser.parse(input, &mut database)?;
+        self.report(
+            Stage::Parse,
+            "sequence",
+            started,
+            StageSize::Elements {
+                nodes: database.participant_count(),
+                edges: database.message_count(),
+            },
+        );
         debug!(
             participant_count = database.participant_count(),
             message_count = database.message_count(),
@@ -375,23 +882,30 @@ impl Orchestrator {
         // Step 2: Render the result
         let render_span = span!(Level::DEBUG, "pipeline_render");
         let _render_enter = render_span.enter();
-        let renderer = self
-            .sequence_renderer
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No sequence renderer available"))?;
+        let renderer = self.sequence_renderer_for(input, &mut database)?;
 
-        let canvas = renderer.render(&database)?;
+        let canvas = self.render_stage("sequence", input, |probe| {
+            renderer.render_probed(&database, probe)
+        })?;
         debug!(output_len = canvas.len(), "Rendering completed");
         drop(_render_enter);
 
         info!("Sequence diagram processing completed successfully");
-        Ok(canvas)
+        Ok((canvas, database))
     }
 
     /// Process class diagram input directly (skip detection)
     ///
     /// Useful when the caller already knows the diagram type.
     pub fn process_class(&self, input: &str) -> Result<String> {
+        self.process_class_with_database(input)
+            .map(|(output, _)| output)
+    }
+
+    /// Process class diagram input and return both output and the parsed database
+    ///
+    /// Useful for reading the database's parse warnings.
+    pub fn process_class_with_database(&self, input: &str) -> Result<(String, ClassDatabase)> {
         let class_span = span!(Level::INFO, "process_class", input_len = input.len());
         let _enter = class_span.enter();
 
@@ -406,7 +920,17 @@ impl Orchestrator {
             .ok_or_else(|| anyhow::anyhow!("No class parser available"))?;
 
         let mut database = ClassDatabase::new();
+        let started = self.start_timer();
         parser.parse(input, &mut database)?;
+        self.report(
+            Stage::Parse,
+            "class",
+            started,
+            StageSize::Elements {
+                nodes: database.class_count(),
+                edges: database.relationship_count(),
+            },
+        );
         debug!(
             class_count = database.class_count(),
             relationship_count = database.relationship_count(),
@@ -422,18 +946,28 @@ impl Orchestrator {
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No class renderer available"))?;
 
-        let canvas = renderer.render_database(&database)?;
+        let canvas = self.render_stage("class", input, |probe| {
+            renderer.render_probed(&database, probe)
+        })?;
         debug!(output_len = canvas.len(), "Rendering completed");
         drop(_render_enter);
 
         info!("Class diagram processing completed successfully");
-        Ok(canvas)
+        Ok((canvas, database))
     }
 
     /// Process state diagram input directly (skip detection)
     ///
     /// Useful when the caller already knows the diagram type.
     pub fn process_state(&self, input: &str) -> Result<String> {
+        self.process_state_with_database(input)
+            .map(|(output, _)| output)
+    }
+
+    /// Process state diagram input and return both output and the parsed database
+    ///
+    /// Useful for reading the database's parse warnings.
+    pub fn process_state_with_database(&self, input: &str) -> Result<(String, StateDatabase)> {
         let state_span = span!(Level::INFO, "process_state", input_len = input.len());
         let _enter = state_span.enter();
 
@@ -448,7 +982,17 @@ impl Orchestrator {
             .ok_or_else(|| anyhow::anyhow!("No state parser available"))?;
 
         let mut database = StateDatabase::new();
+        let started = self.start_timer();
         parser.parse(input, &mut database)?;
+        self.report(
+            Stage::Parse,
+            "state",
+            started,
+            StageSize::Elements {
+                nodes: database.state_count(),
+                edges: database.transition_count(),
+            },
+        );
         debug!(
             state_count = database.state_count(),
             transition_count = database.transition_count(),
@@ -464,13 +1008,269 @@ impl Orchestrator {
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No state renderer available"))?;
 
-        let canvas = renderer.render(&database)?;
+        let canvas = self.render_stage("state", input, |probe| {
+            renderer.render_probed(&database, probe)
+        })?;
         debug!(output_len = canvas.len(), "Rendering completed");
         drop(_render_enter);
 
         info!("State diagram processing completed successfully");
-        Ok(canvas)
+        Ok((canvas, database))
+    }
+
+    /// Process entity relationship diagram input directly (skip detection)
+    ///
+    /// Useful when the caller already knows the diagram type.
+    pub fn process_er(&self, input: &str) -> Result<String> {
+        self.process_er_with_database(input)
+            .map(|(output, _)| output)
     }
+
+    /// Process entity relationship diagram input and return both output and the parsed database
+    ///
+    /// Useful for reading the database's parse warnings.
+    pub fn process_er_with_database(&self, input: &str) -> Result<(String, ErDatabase)> {
+        let er_span = span!(Level::INFO, "process_er", input_len = input.len());
+        let _enter = er_span.enter();
+
+        info!("Processing ER diagram");
+
+        // Step 1: Parse the input
+        let parse_span = span!(Level::DEBUG, "pipeline_parse");
+        let _parse_enter = parse_span.enter();
+        let parser = self
+            .er_parser
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No ER parser available"))?;
+
+        let mut database = ErDatabase::new();
+        let started = self.start_timer();
+        parser.parse(input, &mut database)?;
+        self.report(
+            Stage::Parse,
+            "er",
+            started,
+            StageSize::Elements {
+                nodes: database.entity_count(),
+                edges: database.relationship_count(),
+            },
+        );
+        debug!(
+            entity_count = database.entity_count(),
+            relationship_count = database.relationship_count(),
+            "Parsing completed"
+        );
+        drop(_parse_enter);
+
+        // Step 2: Render the result
+        let render_span = span!(Level::DEBUG, "pipeline_render");
+        let _render_enter = render_span.enter();
+        let renderer = self
+            .er_renderer
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No ER renderer available"))?;
+
+        let canvas = self.render_stage("er", input, |probe| {
+            renderer.render_probed(&database, probe)
+        })?;
+        debug!(output_len = canvas.len(), "Rendering completed");
+        drop(_render_enter);
+
+        info!("ER diagram processing completed successfully");
+        Ok((canvas, database))
+    }
+
+    /// Start timing a stage, if anyone is observing
+    fn start_timer(&self) -> Option<Instant> {
+        self.observer.as_ref().map(|_| Instant::now())
+    }
+
+    /// Report a finished stage to the observer
+    fn report(&self, stage: Stage, diagram_type: &str, started: Option<Instant>, size: StageSize) {
+        if let (Some(observer), Some(started)) = (&self.observer, started) {
+            observer.on_stage(&StageEvent {
+                stage,
+                diagram_type,
+                elapsed: started.elapsed(),
+                size,
+            });
+        }
+    }
+
+    /// Run a renderer, reporting its layout and drawing as separate stages
+    ///
+    /// `render` times its layout through the probe it is given, which only
+    /// reads the clock when someone is observing.
+    fn render_stage(
+        &self,
+        diagram_type: &str,
+        input: &str,
+        render: impl FnOnce(&mut LayoutProbe) -> Result<String>,
+    ) -> Result<String> {
+        let Some(observer) = &self.observer else {
+            let output = render(&mut LayoutProbe::default())?;
+            let output = empty_placeholder(diagram_type, input, output);
+            return self.check_overflow(diagram_type, output);
+        };
+        let started = Instant::now();
+        let mut probe = LayoutProbe::enabled();
+        let output = empty_placeholder(diagram_type, input, render(&mut probe)?);
+        let mut elapsed = started.elapsed();
+
+        if let Some(layout) = probe.metrics() {
+            observer.on_stage(&StageEvent {
+                stage: Stage::Layout,
+                diagram_type,
+                elapsed: layout.elapsed,
+                size: StageSize::Canvas {
+                    width: layout.width,
+                    height: layout.height,
+                },
+            });
+            elapsed = elapsed.saturating_sub(layout.elapsed);
+        }
+        observer.on_stage(&StageEvent {
+            stage: Stage::Render,
+            diagram_type,
+            elapsed,
+            size: StageSize::Output {
+                bytes: output.len(),
+            },
+        });
+        self.check_overflow(diagram_type, output)
+    }
+
+    /// Apply the overflow policy to a drawing larger than the canvas limit
+    ///
+    /// Flowcharts check their layout before drawing; this catches the
+    /// diagram types that are measured only once drawn.
+    fn check_overflow(&self, diagram_type: &str, output: String) -> Result<String> {
+        if self.overflow_policy == OverflowPolicy::Render || diagram_type == "flowchart" {
+            return Ok(output);
+        }
+        let width = output.lines().map(visible_width).max().unwrap_or(0);
+        let height = output.lines().count();
+        if !self.overflow_limit.exceeded_by(width, height) {
+            return Ok(output);
+        }
+
+        warn!(
+            diagram_type,
+            width,
+            height,
+            limit = %self.overflow_limit,
+            "Diagram is over the canvas limit"
+        );
+        match self.overflow_policy {
+            OverflowPolicy::Error => Err(anyhow::anyhow!(
+                "Diagram is {}x{} cells, over the {} canvas limit",
+                width,
+                height,
+                self.overflow_limit
+            )),
+            _ => Ok(self.overflow_limit.notice(diagram_type, width, height)),
+        }
+    }
+}
+
+/// One-line notice standing in for a diagram with nothing to draw
+///
+/// A header with only directives (`classDef`, `style`, ...) draws nothing;
+/// the notice keeps that from passing silently as a blank section. Blank
+/// input still yields blank output.
+fn empty_placeholder(diagram_type: &str, input: &str, output: String) -> String {
+    if !output.trim().is_empty() || input.trim().is_empty() {
+        return output;
+    }
+    let elements = match diagram_type {
+        "gitgraph" => "commits",
+        "sequence" => "participants",
+        "class" => "classes",
+        "state" => "states",
+        "er" => "entities",
+        _ => "nodes",
+    };
+    format!("[empty {}: no {}]", diagram_type, elements)
+}
+
+/// A [`Diagram`] registered at runtime, with its pipeline types erased
+trait ExternalDiagram: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Parser and renderer versions
+    fn versions(&self) -> (&'static str, &'static str);
+
+    /// Parse and render `input`, reporting to the orchestrator's observer
+    fn process(&self, orchestrator: &Orchestrator, input: &str) -> Result<String> {
+        self.process_with_warnings(orchestrator, input)
+            .map(|(output, _)| output)
+    }
+
+    /// Like [`process`](Self::process), also returning the parse warnings
+    fn process_with_warnings(
+        &self,
+        orchestrator: &Orchestrator,
+        input: &str,
+    ) -> Result<(String, Vec<ParseWarning>)>;
+}
+
+struct RegisteredDiagram<D: Diagram> {
+    parser: D::Parser,
+    renderer: D::Renderer,
+}
+
+impl<D> ExternalDiagram for RegisteredDiagram<D>
+where
+    D: Diagram,
+    D::Renderer: Renderer<D::Database, Output = String>,
+{
+    fn name(&self) -> &'static str {
+        D::name()
+    }
+
+    fn versions(&self) -> (&'static str, &'static str) {
+        (self.parser.version(), self.renderer.version())
+    }
+
+    fn process_with_warnings(
+        &self,
+        orchestrator: &Orchestrator,
+        input: &str,
+    ) -> Result<(String, Vec<ParseWarning>)> {
+        let name = D::name();
+        let external_span = span!(Level::INFO, "process_external", diagram_type = name);
+        let _enter = external_span.enter();
+
+        let mut database = D::create_database();
+        let started = orchestrator.start_timer();
+        self.parser.parse(input, &mut database)?;
+        orchestrator.report(
+            Stage::Parse,
+            name,
+            started,
+            StageSize::Elements {
+                nodes: database.node_count(),
+                edges: database.edge_count(),
+            },
+        );
+
+        // External renderers run their layout inside the render stage
+        let output = orchestrator.render_stage(name, input, |_| self.renderer.render(&database))?;
+        Ok((output, database.take_parse_warnings()))
+    }
+}
+
+/// Parser and renderer versions, when both are configured
+fn plugin_versions<D, P, R>(
+    parser: &Option<P>,
+    renderer: &Option<R>,
+) -> Option<(&'static str, &'static str)>
+where
+    D: Database,
+    P: Parser<D>,
+    R: Renderer<D>,
+{
+    Some((parser.as_ref()?.version(), renderer.as_ref()?.version()))
 }
 
 impl Default for Orchestrator {
@@ -544,6 +1344,54 @@ mod tests {
         assert_eq!(result.unwrap(), "flowchart");
     }
 
+    /// Counts full-content checks, to see when detection skips them
+    struct CountingDetector(Arc<std::sync::atomic::AtomicUsize>);
+
+    impl Detector for CountingDetector {
+        fn detect(&self, input: &str) -> bool {
+            self.confidence(input) > 0.5
+        }
+
+        fn confidence(&self, _input: &str) -> f64 {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            0.9
+        }
+
+        fn diagram_type(&self) -> &'static str {
+            "counting"
+        }
+
+        fn patterns(&self) -> Vec<&'static str> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn test_header_detection_skips_full_scans() {
+        let scans = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut orchestrator = Orchestrator::new();
+        orchestrator.register_default_detectors();
+        orchestrator.register_detector(
+            "counting".to_string(),
+            Box::new(CountingDetector(scans.clone())),
+        );
+
+        let mut input = String::from("%% exported notes\nsequenceDiagram\n");
+        input.push_str(&"    Alice->>Bob: hi\n".repeat(50_000));
+        assert_eq!(
+            orchestrator.detect_diagram_type(&input).unwrap(),
+            "sequence"
+        );
+        assert_eq!(scans.load(std::sync::atomic::Ordering::Relaxed), 0);
+
+        // Without a recognized header every detector scans the input
+        assert_eq!(
+            orchestrator.detect_diagram_type("A --> B").unwrap(),
+            "counting"
+        );
+        assert_eq!(scans.load(std::sync::atomic::Ordering::Relaxed), 1);
+    }
+
     #[test]
     fn test_process_with_missing_plugins() {
         let orchestrator = Orchestrator::new();
@@ -638,6 +1486,196 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_process_as_skips_detection() {
+        // No detectors registered, so only an explicit type can work
+        let orchestrator = Orchestrator::with_all_plugins();
+        let input = "Alice->>Bob: Hello";
+        assert!(orchestrator.process(input).is_err());
+
+        let output = orchestrator.process_as("sequence", input).unwrap();
+        assert!(output.contains("Alice") && output.contains("Hello"));
+        assert_eq!(
+            orchestrator
+                .process_as("pie", "pie title Pets")
+                .unwrap_err()
+                .to_string(),
+            "Unsupported diagram type: pie"
+        );
+        assert_eq!(
+            Orchestrator::new()
+                .process_as("sequence", input)
+                .unwrap_err()
+                .to_string(),
+            "No sequence parser available"
+        );
+    }
+
+    #[test]
+    fn test_plugins_lists_configured_types() {
+        let mut orchestrator = Orchestrator::with_flowchart_plugins();
+        assert_eq!(
+            orchestrator.plugins(),
+            [PluginInfo {
+                name: "flowchart",
+                parser_version: "0.1.0",
+                renderer_version: "0.2.0",
+                detector: false,
+            }]
+        );
+        orchestrator.register_default_detectors();
+        assert!(orchestrator.plugins()[0].detector);
+
+        let mut orchestrator = Orchestrator::with_all_plugins();
+        orchestrator.register_default_detectors();
+        let plugins = orchestrator.plugins();
+        let names: Vec<_> = plugins.iter().map(|plugin| plugin.name).collect();
+        assert_eq!(
+            names,
+            ["flowchart", "gitgraph", "sequence", "class", "state", "er"]
+        );
+        assert!(plugins.iter().all(|plugin| plugin.detector));
+        assert!(Orchestrator::new().plugins().is_empty());
+    }
+
+    /// Stand-in for a downstream DSL: a `tally` header, then one item per line
+    struct TallyDiagram;
+    struct TallyDetector;
+    struct TallyParser;
+    struct TallyRenderer;
+
+    impl Detector for TallyDetector {
+        fn detect(&self, input: &str) -> bool {
+            self.confidence(input) > 0.5
+        }
+
+        fn confidence(&self, input: &str) -> f64 {
+            self.prefix_confidence(header_line(input)).unwrap_or(0.0)
+        }
+
+        fn diagram_type(&self) -> &'static str {
+            "tally"
+        }
+
+        fn patterns(&self) -> Vec<&'static str> {
+            vec!["tally"]
+        }
+
+        fn prefix_confidence(&self, header: &str) -> Option<f64> {
+            (header == "tally").then_some(1.0)
+        }
+    }
+
+    impl Parser<FlowchartDatabase> for TallyParser {
+        fn parse(&self, input: &str, database: &mut FlowchartDatabase) -> Result<()> {
+            for item in input.lines().skip(1).map(str::trim) {
+                database.add_simple_node(item, item)?;
+            }
+            Ok(())
+        }
+
+        fn name(&self) -> &'static str {
+            "tally"
+        }
+
+        fn version(&self) -> &'static str {
+            "1.2.0"
+        }
+
+        fn can_parse(&self, input: &str) -> bool {
+            TallyDetector.detect(input)
+        }
+    }
+
+    impl Renderer<FlowchartDatabase> for TallyRenderer {
+        type Output = String;
+
+        fn render(&self, database: &FlowchartDatabase) -> Result<String> {
+            let items: Vec<_> = database.nodes().map(|node| node.label.as_str()).collect();
+            Ok(format!("{} items: {}", items.len(), items.join(", ")))
+        }
+
+        fn name(&self) -> &'static str {
+            "tally"
+        }
+
+        fn version(&self) -> &'static str {
+            "1.0.0"
+        }
+
+        fn format(&self) -> &'static str {
+            "text"
+        }
+    }
+
+    impl Diagram for TallyDiagram {
+        type Database = FlowchartDatabase;
+        type Parser = TallyParser;
+        type Renderer = TallyRenderer;
+
+        fn detector() -> Arc<dyn Detector> {
+            Arc::new(TallyDetector)
+        }
+
+        fn create_parser() -> Self::Parser {
+            TallyParser
+        }
+
+        fn create_database() -> Self::Database {
+            FlowchartDatabase::new()
+        }
+
+        fn create_renderer() -> Self::Renderer {
+            TallyRenderer
+        }
+
+        fn name() -> &'static str {
+            "tally"
+        }
+
+        fn version() -> &'static str {
+            "1.0.0"
+        }
+    }
+
+    #[test]
+    fn test_registered_diagrams_join_the_pipeline() {
+        let mut orchestrator = Orchestrator::with_all_plugins();
+        orchestrator.register_default_detectors();
+        orchestrator.register_diagram::<TallyDiagram>();
+
+        let input = "tally\napples\npears";
+        assert_eq!(orchestrator.detect_diagram_type(input).unwrap(), "tally");
+        assert_eq!(
+            orchestrator.process(input).unwrap(),
+            "2 items: apples, pears"
+        );
+        assert_eq!(
+            orchestrator.process_as("tally", "graph TD\nA").unwrap(),
+            "1 items: A"
+        );
+        // Built-in types are untouched
+        assert!(orchestrator
+            .process("graph TD\nA --> B")
+            .unwrap()
+            .contains('A'));
+
+        let plugins = orchestrator.plugins();
+        assert_eq!(
+            plugins.last(),
+            Some(&PluginInfo {
+                name: "tally",
+                parser_version: "1.2.0",
+                renderer_version: "1.0.0",
+                detector: true,
+            })
+        );
+
+        // Registering again replaces rather than duplicates
+        orchestrator.register_diagram::<TallyDiagram>();
+        assert_eq!(orchestrator.plugins().len(), plugins.len());
+    }
+
     #[test]
     fn test_process_with_wrong_diagram_type() {
         // Create a mock detector that returns a wrong type
@@ -669,6 +1707,20 @@ mod tests {
         assert!(output.is_empty());
     }
 
+    #[test]
+    fn test_directive_only_diagrams_render_placeholder() {
+        let mut orchestrator = Orchestrator::with_all_plugins();
+        orchestrator.register_default_detectors();
+
+        let output = orchestrator
+            .process("graph TD\n    classDef hot fill:#f00\n    style A fill:#0f0")
+            .unwrap();
+        assert_eq!(output, "[empty flowchart: no nodes]");
+
+        let output = orchestrator.process("sequenceDiagram\n").unwrap();
+        assert_eq!(output, "[empty sequence: no participants]");
+    }
+
     #[test]
     fn test_process_invalid_syntax() {
         let orchestrator = Orchestrator::with_flowchart_plugins();
@@ -752,4 +1804,314 @@ mod tests {
         assert!(output.contains("+eat()"));
         assert!(output.contains("#digest()*"));
     }
+
+    #[test]
+    fn test_init_directive_adjusts_flowchart_spacing() {
+        let mut orchestrator = Orchestrator::with_all_plugins();
+        orchestrator.register_default_detectors();
+
+        let plain = orchestrator
+            .process("graph LR\n    A --> B\n    A --> C")
+            .unwrap();
+        let spaced = orchestrator
+            .process("%%{init: {'flowchart': {'rankSpacing': 150}}}%%\ngraph LR\n    A --> B\n    A --> C")
+            .unwrap();
+
+        let width = |s: &str| s.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        assert!(width(&spaced) > width(&plain));
+    }
+
+    #[test]
+    fn test_init_directive_curve_selects_edge_drawing() {
+        let orchestrator = Orchestrator::with_all_plugins();
+        let input = "graph TD\n A-->B\n B-->C\n A-->D\n D-->E\n E-->C";
+
+        let step = orchestrator.process_flowchart(input).unwrap();
+        let linear = orchestrator
+            .process_flowchart(&format!(
+                "%%{{init: {{'flowchart': {{'curve': 'linear'}}}}}}%%\n{}",
+                input
+            ))
+            .unwrap();
+        assert!(!step.contains('╱'));
+        assert!(linear.contains('╱'), "{}", linear);
+    }
+
+    #[test]
+    fn test_init_directive_unsupported_keys_warn() {
+        let orchestrator = Orchestrator::with_all_plugins();
+
+        let (output, mut database) = orchestrator
+            .process_flowchart_with_database("%%{init: {'theme': 'solarized', 'flowchart': {'htmlLabels': true}}}%%\ngraph TD\n    A --> B")
+            .unwrap();
+        assert!(output.contains('A'));
+
+        let warnings = database.take_warnings();
+        assert!(warnings.iter().any(|w| w.contains("flowchart.htmlLabels")));
+        assert!(warnings.iter().any(|w| w.contains("Theme 'solarized'")));
+    }
+
+    #[test]
+    fn test_init_directive_theme_overrides_config_theme() {
+        let config = RenderConfig::default()
+            .with_color(true)
+            .with_theme(Some(Theme::NEUTRAL));
+        let orchestrator = Orchestrator::all_plugins(config);
+        let input = "graph LR\n    A --> B";
+
+        let neutral = orchestrator.process_flowchart(input).unwrap();
+        assert!(neutral.contains("\x1b[38;2;102;102;102m"), "{}", neutral);
+
+        let (forest, mut database) = orchestrator
+            .process_flowchart_with_database(&format!(
+                "%%{{init: {{'theme': 'forest'}}}}%%\n{}",
+                input
+            ))
+            .unwrap();
+        assert!(forest.contains("\x1b[38;2;0;128;0m"), "{}", forest);
+        assert!(database.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_init_directive_adjusts_sequence_layout() {
+        let orchestrator = Orchestrator::with_all_plugins();
+        let input = "sequenceDiagram\n    Alice->>Bob: Hi";
+
+        let plain = orchestrator.process_sequence(input).unwrap();
+        let spaced = orchestrator
+            .process_sequence(&format!(
+                "%%{{init: {{'sequence': {{'actorMargin': 150, 'mirrorActors': true}}, 'logLevel': 1}}}}%%\n{}",
+                input
+            ))
+            .unwrap();
+
+        let width = |s: &str| s.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        assert!(width(&spaced) > width(&plain), "{}", spaced);
+        assert!(spaced.lines().count() > plain.lines().count(), "{}", spaced);
+    }
+
+    #[test]
+    fn test_color_config_reaches_non_flowchart_renderers() {
+        let inputs = [
+            "sequenceDiagram\n    Alice->>Bob: Hi",
+            "stateDiagram-v2\n    [*] --> Idle\n    Idle --> Busy : go",
+            "classDiagram\n    Animal <|-- Dog",
+        ];
+
+        let mut plain = Orchestrator::with_all_plugins();
+        plain.register_default_detectors();
+        let mut colored = Orchestrator::all_plugins(RenderConfig::default().with_color(true));
+        colored.register_default_detectors();
+
+        for input in inputs {
+            assert!(!plain.process(input).unwrap().contains('\x1b'), "{}", input);
+            assert!(
+                colored.process(input).unwrap().contains('\x1b'),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_overflow_policy_applies_to_every_diagram_type() {
+        let input =
+            "sequenceDiagram\n    Alice->>Bob: A fairly long greeting\n    Bob-->>Alice: Yo";
+        let limit = CanvasLimit::new(10, 4);
+
+        let mut orchestrator = Orchestrator::all_plugins(
+            RenderConfig::default()
+                .with_overflow_policy(OverflowPolicy::Summary)
+                .with_overflow_limit(limit),
+        );
+        orchestrator.register_default_detectors();
+        let output = orchestrator.process(input).unwrap();
+        assert!(
+            output.starts_with("[sequence too large to draw:"),
+            "{}",
+            output
+        );
+
+        let mut strict = Orchestrator::all_plugins(
+            RenderConfig::default()
+                .with_overflow_policy(OverflowPolicy::Error)
+                .with_overflow_limit(limit),
+        );
+        strict.register_default_detectors();
+        assert!(strict.process(input).is_err());
+
+        let mut default = Orchestrator::with_all_plugins();
+        default.register_default_detectors();
+        assert!(default.process(input).unwrap().contains("Alice"));
+    }
+
+    #[test]
+    fn test_observer_sees_each_stage_with_sizes() {
+        use std::sync::Mutex;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let seen = events.clone();
+        let mut orchestrator = Orchestrator::with_all_plugins();
+        orchestrator.register_default_detectors();
+        orchestrator.set_observer(Arc::new(move |event: &StageEvent| {
+            seen.lock()
+                .unwrap()
+                .push((event.stage, event.diagram_type.to_string(), event.size));
+        }));
+
+        let input = "sequenceDiagram\n    Alice->>Bob: Hi\n    Bob-->>Alice: Yo";
+        let output = orchestrator.process(input).unwrap();
+
+        let events = events.lock().unwrap();
+        let stages: Vec<Stage> = events.iter().map(|(stage, _, _)| *stage).collect();
+        assert_eq!(
+            stages,
+            [Stage::Detect, Stage::Parse, Stage::Layout, Stage::Render]
+        );
+        assert!(events.iter().all(|(_, kind, _)| kind == "sequence"));
+        assert_eq!(events[0].2, StageSize::Input { bytes: input.len() });
+        assert_eq!(events[1].2, StageSize::Elements { nodes: 2, edges: 2 });
+        assert!(matches!(events[2].2, StageSize::Canvas { width, .. } if width > 0));
+        assert_eq!(
+            events[3].2,
+            StageSize::Output {
+                bytes: output.len()
+            }
+        );
+    }
+
+    #[test]
+    fn test_observed_layouts_on_parallel_threads_stay_apart() {
+        use std::sync::Mutex;
+
+        let layout_width = |input: &'static str| {
+            std::thread::spawn(move || {
+                let widths = Arc::new(Mutex::new(Vec::new()));
+                let seen = widths.clone();
+                let mut orchestrator = Orchestrator::with_all_plugins();
+                orchestrator.register_default_detectors();
+                orchestrator.set_observer(Arc::new(move |event: &StageEvent| {
+                    if let StageSize::Canvas { width, .. } = event.size {
+                        seen.lock().unwrap().push(width);
+                    }
+                }));
+                let output = orchestrator.process(input).unwrap();
+                let widths = widths.lock().unwrap().clone();
+                (widths, output)
+            })
+        };
+
+        let narrow = layout_width("graph TD\n A-->B");
+        let wide = layout_width("graph LR\n A-->B-->C-->D-->E");
+        for (widths, output) in [narrow.join().unwrap(), wide.join().unwrap()] {
+            let drawn = output.lines().map(|l| l.chars().count()).max().unwrap();
+            assert_eq!(widths.len(), 1);
+            assert!(widths[0] >= drawn, "{:?} for\n{}", widths, output);
+        }
+    }
+
+    #[test]
+    fn test_process_with_warnings_covers_every_diagram_type() {
+        let mut orchestrator = Orchestrator::with_all_plugins();
+        orchestrator.register_default_detectors();
+
+        let (output, warnings) = orchestrator
+            .process_with_warnings("sequenceDiagram\n    Alice->>Bob: Hi\n    Alice=>Bob")
+            .unwrap();
+        assert!(output.contains("Hi"));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].statement.as_deref(), Some("Alice=>Bob"));
+
+        let (_, warnings) = orchestrator
+            .process_with_warnings("gitGraph\n   commit\n   rebase main")
+            .unwrap();
+        assert_eq!(warnings.len(), 1);
+
+        let (_, warnings) = orchestrator
+            .process_with_warnings("graph TD\n A-->B")
+            .unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_process_html_styles_flowcharts_and_wraps_others() {
+        let mut orchestrator = Orchestrator::with_all_plugins();
+        orchestrator.register_default_detectors();
+
+        let html = orchestrator
+            .process_html("graph TD\n A[Go] --> B\n style A fill:#0f0")
+            .unwrap();
+        assert!(
+            html.contains("<span style=\"background-color:#0f0\"> Go </span>"),
+            "{}",
+            html
+        );
+
+        let html = orchestrator
+            .process_html("sequenceDiagram\n Alice->>Bob: <hi>")
+            .unwrap();
+        assert!(html.starts_with("<pre class=\"figurehead\">"));
+        assert!(html.contains("&lt;hi&gt;"));
+    }
+
+    #[test]
+    fn test_process_cells_owns_nodes_and_edges_of_every_type() {
+        let mut orchestrator = Orchestrator::with_all_plugins();
+        orchestrator.register_default_detectors();
+        let owners = |input: &str| -> Vec<String> {
+            let grid = orchestrator.process_cells(input).unwrap();
+            assert!(grid.rows.iter().all(|row| row.len() == grid.width));
+            let mut owners: Vec<String> = grid
+                .rows
+                .iter()
+                .flatten()
+                .filter_map(|cell| cell.owner.clone())
+                .collect();
+            owners.sort();
+            owners.dedup();
+            owners
+        };
+
+        assert_eq!(owners("graph TD\n A[Go] --> B"), ["A", "A->B", "B"]);
+        assert_eq!(
+            owners("sequenceDiagram\n Alice->>Bob: hi"),
+            ["Alice", "Alice->Bob", "Bob"]
+        );
+        assert_eq!(
+            owners("classDiagram\n Animal <|-- Dog"),
+            ["Animal", "Animal->Dog", "Dog"]
+        );
+        assert_eq!(owners("stateDiagram-v2\n Idle --> Busy"), ["Busy", "Idle"]);
+        assert_eq!(
+            owners("erDiagram\n CUSTOMER ||--o{ ORDER : places"),
+            ["CUSTOMER", "CUSTOMER->ORDER", "ORDER"]
+        );
+        assert_eq!(
+            owners("gitGraph\n commit id: \"a\"\n branch dev\n commit id: \"b\""),
+            ["a", "a->b", "b"]
+        );
+    }
+
+    #[test]
+    fn test_process_cells_match_text_output() {
+        let mut plain = Orchestrator::with_all_plugins();
+        plain.register_default_detectors();
+        let mut colored = Orchestrator::all_plugins(RenderConfig::default().with_color(true));
+        colored.register_default_detectors();
+
+        let input = "sequenceDiagram\n Alice->>Bob: hi";
+        let grid = colored.process_cells(input).unwrap();
+        let text: Vec<String> = grid
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.ch).collect::<String>())
+            .map(|line| line.trim_end().to_string())
+            .collect();
+        assert_eq!(text.join("\n"), plain.process(input).unwrap());
+        assert!(grid
+            .rows
+            .iter()
+            .flatten()
+            .any(|cell| cell.style.color.is_some()));
+    }
 }
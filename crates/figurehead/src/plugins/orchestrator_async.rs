@@ -0,0 +1,237 @@
+//! Async orchestrator API for embedding in async hosts
+//!
+//! Enabled with the `async` feature. Rendering is CPU-bound, so the pipeline
+//! runs on tokio's blocking pool instead of the caller's executor threads.
+//! Cancellation is cooperative: the pipeline checks its token between stages,
+//! and the awaiting future resolves as soon as the token is cancelled.
+
+use anyhow::Result;
+use std::future::{poll_fn, Future};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use tokio::sync::Notify;
+use tracing::{debug, info, span, Level};
+
+use super::Orchestrator;
+
+/// Handle used to cancel an in-flight [`Orchestrator::process_async`] call
+///
+/// Clones share the same state, so one clone can be handed to the pipeline
+/// while another is kept by the caller (e.g. tied to a request timeout).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<CancellationState>,
+}
+
+#[derive(Debug, Default)]
+struct CancellationState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    /// Create a new token that has not been cancelled
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of all work observing this token
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Returns true once [`cancel`](Self::cancel) has been called
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the token is cancelled
+    pub async fn cancelled(&self) {
+        loop {
+            // Register before checking the flag so a concurrent cancel is not missed
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    fn check(&self, stage: &str) -> Result<()> {
+        if self.is_cancelled() {
+            debug!(stage, "Pipeline cancelled");
+            Err(anyhow::anyhow!("Processing cancelled"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Cancels the token when the owning future is dropped before completion
+struct CancelOnDrop {
+    token: CancellationToken,
+    armed: bool,
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if self.armed {
+            self.token.cancel();
+        }
+    }
+}
+
+impl Orchestrator {
+    /// Process input through the complete pipeline without blocking the executor
+    ///
+    /// Detection, parsing, layout and rendering run on tokio's blocking pool.
+    /// Cancelling `cancel` (or dropping the returned future) makes the call
+    /// resolve with a "Processing cancelled" error; the background work stops
+    /// at the next stage boundary.
+    ///
+    /// Must be called from within a tokio runtime.
+    pub async fn process_async(
+        self: Arc<Self>,
+        input: impl Into<String>,
+        cancel: CancellationToken,
+    ) -> Result<String> {
+        let input = input.into();
+        let async_span = span!(Level::INFO, "process_async", input_len = input.len());
+
+        let mut guard = CancelOnDrop {
+            token: cancel.clone(),
+            armed: true,
+        };
+
+        let worker_token = cancel.clone();
+        let mut task = tokio::task::spawn_blocking(move || {
+            let _enter = async_span.enter();
+            self.process_cancellable(&input, &worker_token)
+        });
+        let mut cancelled = pin!(cancel.cancelled());
+
+        let result = poll_fn(|cx| {
+            if let Poll::Ready(joined) = std::pin::Pin::new(&mut task).poll(cx) {
+                return Poll::Ready(match joined {
+                    Ok(result) => result,
+                    Err(e) => Err(anyhow::anyhow!("Processing task failed: {}", e)),
+                });
+            }
+            if cancelled.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(anyhow::anyhow!("Processing cancelled")));
+            }
+            Poll::Pending
+        })
+        .await;
+
+        guard.armed = false;
+        result
+    }
+
+    /// Blocking pipeline that checks `cancel` between stages
+    fn process_cancellable(&self, input: &str, cancel: &CancellationToken) -> Result<String> {
+        cancel.check("detect")?;
+        let diagram_type = self.detect_diagram_type(input)?;
+
+        cancel.check("process")?;
+        let output = self.process_as(&diagram_type, input)?;
+
+        // Don't hand back results the caller has already abandoned
+        cancel.check("finish")?;
+        info!(output_len = output.len(), "Async pipeline completed");
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+    }
+
+    fn orchestrator() -> Arc<Orchestrator> {
+        let mut orchestrator = Orchestrator::with_all_plugins();
+        orchestrator.register_default_detectors();
+        Arc::new(orchestrator)
+    }
+
+    #[test]
+    fn test_process_async_matches_sync() {
+        let orchestrator = orchestrator();
+        let input = "graph TD\n    A[Start] --> B[End]";
+        let expected = orchestrator.process(input).unwrap();
+
+        let output = runtime()
+            .block_on(orchestrator.process_async(input, CancellationToken::new()))
+            .unwrap();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_process_async_propagates_errors() {
+        let result = runtime()
+            .block_on(orchestrator().process_async("not a diagram", CancellationToken::new()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_process_async_cancelled_before_start() {
+        let token = CancellationToken::new();
+        token.cancel();
+
+        let err = runtime()
+            .block_on(orchestrator().process_async("graph TD\n    A --> B", token))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Processing cancelled");
+    }
+
+    #[test]
+    fn test_cancelled_future_resolves_after_cancel() {
+        let token = CancellationToken::new();
+        let waiter = token.clone();
+
+        runtime().block_on(async move {
+            let handle = tokio::spawn(async move { waiter.cancelled().await });
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            token.cancel();
+            handle.await.unwrap();
+        });
+    }
+
+    #[test]
+    fn test_dropping_future_cancels_token() {
+        let token = CancellationToken::new();
+        let future = orchestrator().process_async("graph TD\n    A --> B", token.clone());
+        drop(future);
+        // An unpolled future never armed its guard
+        assert!(!token.is_cancelled());
+
+        // A long chain keeps the blocking task busy past the first poll
+        let input: String = std::iter::once("graph TD".to_string())
+            .chain((0..300).map(|i| format!("    N{} --> N{}", i, i + 1)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        runtime().block_on(async {
+            let future = orchestrator().process_async(input, token.clone());
+            let mut future = Box::pin(future);
+            // Poll once to start the work, then abandon it
+            let _ = poll_fn(|cx| {
+                let _ = future.as_mut().poll(cx);
+                Poll::Ready(())
+            })
+            .await;
+            drop(future);
+        });
+        assert!(token.is_cancelled());
+    }
+}
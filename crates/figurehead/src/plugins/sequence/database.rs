@@ -2,8 +2,9 @@
 //!
 //! Stores participants and messages for sequence diagrams.
 
-use crate::core::Database;
-use anyhow::Result;
+use crate::core::{Database, ParseWarning};
+use anyhow::{bail, Result};
+use std::fmt;
 
 /// Line style for message arrows
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -58,6 +59,20 @@ impl ArrowType {
             head: ArrowHead::None,
         }
     }
+
+    pub fn solid_async() -> Self {
+        Self {
+            line: LineStyle::Solid,
+            head: ArrowHead::Open,
+        }
+    }
+
+    pub fn dotted_async() -> Self {
+        Self {
+            line: LineStyle::Dotted,
+            head: ArrowHead::Open,
+        }
+    }
 }
 
 impl Default for ArrowType {
@@ -66,6 +81,16 @@ impl Default for ArrowType {
     }
 }
 
+/// How a participant is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParticipantKind {
+    /// A labeled box (`participant`)
+    #[default]
+    Participant,
+    /// A stick figure over its label (`actor`)
+    Actor,
+}
+
 /// A participant in the sequence diagram
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Participant {
@@ -73,6 +98,8 @@ pub struct Participant {
     pub id: String,
     /// Display label (may differ from id via "as" syntax)
     pub label: String,
+    /// Box or stick figure
+    pub kind: ParticipantKind,
 }
 
 impl Participant {
@@ -81,6 +108,7 @@ impl Participant {
         Self {
             label: id.clone(),
             id,
+            kind: ParticipantKind::default(),
         }
     }
 
@@ -88,6 +116,57 @@ impl Participant {
         Self {
             id: id.into(),
             label: label.into(),
+            kind: ParticipantKind::default(),
+        }
+    }
+
+    pub fn with_kind(mut self, kind: ParticipantKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn is_actor(&self) -> bool {
+        self.kind == ParticipantKind::Actor
+    }
+}
+
+/// Left-to-right order of participant columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+pub enum ParticipantOrder {
+    /// Order of first mention, whether in a declaration or a message
+    FirstMention,
+    /// Explicitly declared participants (`participant`/`actor`) in
+    /// declaration order, then the implicit ones by first mention
+    #[default]
+    Declared,
+}
+
+impl ParticipantOrder {
+    /// Every participant order
+    pub const ALL: [ParticipantOrder; 2] =
+        [ParticipantOrder::FirstMention, ParticipantOrder::Declared];
+}
+
+impl fmt::Display for ParticipantOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParticipantOrder::FirstMention => write!(f, "first-mention"),
+            ParticipantOrder::Declared => write!(f, "declared"),
+        }
+    }
+}
+
+impl std::str::FromStr for ParticipantOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "first-mention" => Ok(ParticipantOrder::FirstMention),
+            "declared" => Ok(ParticipantOrder::Declared),
+            _ => Err(format!(
+                "Unknown participant order '{}'. Use 'first-mention' or 'declared'",
+                s
+            )),
         }
     }
 }
@@ -129,17 +208,61 @@ impl Message {
     }
 }
 
-/// Block kind for future loop/alt support
+/// Kind of a combined fragment, or of a divider between its branches
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BlockKind {
     Loop,
     Alt,
+    /// Next branch of an `alt` block
     Else,
     Opt,
     Par,
+    /// Next branch of a `par` block
+    And,
+}
+
+impl BlockKind {
+    /// All kinds, in declaration order
+    pub const ALL: [BlockKind; 6] = [
+        BlockKind::Loop,
+        BlockKind::Alt,
+        BlockKind::Else,
+        BlockKind::Opt,
+        BlockKind::Par,
+        BlockKind::And,
+    ];
+
+    /// The keyword introducing this kind in the syntax
+    pub fn keyword(self) -> &'static str {
+        match self {
+            BlockKind::Loop => "loop",
+            BlockKind::Alt => "alt",
+            BlockKind::Else => "else",
+            BlockKind::Opt => "opt",
+            BlockKind::Par => "par",
+            BlockKind::And => "and",
+        }
+    }
+
+    /// The kind with the given keyword
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.keyword() == keyword)
+    }
+
+    /// The block this kind divides, if it starts a branch instead of a block
+    pub fn divides(self) -> Option<BlockKind> {
+        match self {
+            BlockKind::Else => Some(BlockKind::Alt),
+            BlockKind::And => Some(BlockKind::Par),
+            _ => None,
+        }
+    }
 }
 
 /// Sequence item - either a message or block marker
+///
+/// A `BlockStart` of a dividing kind (`else`, `and`) starts the next branch
+/// of the enclosing block and has no matching `BlockEnd`.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SequenceItem {
     Message(Message),
@@ -154,22 +277,46 @@ pub enum SequenceItem {
 }
 
 /// Sequence diagram database
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct SequenceDatabase {
     participants: Vec<Participant>,
+    /// IDs of explicitly declared participants, in declaration order
+    declared: Vec<String>,
     items: Vec<SequenceItem>,
+    /// Kinds of the blocks opened but not yet ended, outermost first
+    open_blocks: Vec<BlockKind>,
+    /// Warnings recorded while parsing into this database
+    warnings: Vec<ParseWarning>,
 }
 
 impl SequenceDatabase {
+    /// Create an empty database
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Add a participant (maintains order)
+    /// Record a parse warning
+    pub(crate) fn add_parse_warning(&mut self, warning: ParseWarning) {
+        self.warnings.push(warning);
+    }
+
+    /// Add an explicitly declared participant (maintains order)
+    ///
+    /// Declaring a participant that messages already mentioned gives it the
+    /// declared label and kind; a second declaration changes nothing.
     pub fn add_participant(&mut self, participant: Participant) -> Result<()> {
-        // Don't add duplicates
-        if !self.participants.iter().any(|p| p.id == participant.id) {
-            self.participants.push(participant);
+        let first_declaration = !self.declared.contains(&participant.id);
+        if first_declaration {
+            self.declared.push(participant.id.clone());
+        }
+        match self
+            .participants
+            .iter_mut()
+            .find(|p| p.id == participant.id)
+        {
+            Some(existing) if first_declaration => *existing = participant,
+            Some(_) => {}
+            None => self.participants.push(participant),
         }
         Ok(())
     }
@@ -191,6 +338,49 @@ impl SequenceDatabase {
         Ok(())
     }
 
+    /// Open a block, or start the next branch of the innermost open block
+    /// for `else` and `and`
+    pub fn start_block(&mut self, kind: BlockKind, label: impl Into<String>) -> Result<()> {
+        let depth = match kind.divides() {
+            Some(parent) => {
+                if self.open_blocks.last() != Some(&parent) {
+                    bail!(
+                        "'{}' is only allowed directly inside '{}'",
+                        kind.keyword(),
+                        parent.keyword()
+                    );
+                }
+                self.open_blocks.len() - 1
+            }
+            None => {
+                self.open_blocks.push(kind);
+                self.open_blocks.len() - 1
+            }
+        };
+        self.items.push(SequenceItem::BlockStart {
+            kind,
+            label: label.into(),
+            depth,
+        });
+        Ok(())
+    }
+
+    /// Close the innermost open block
+    pub fn end_block(&mut self) -> Result<()> {
+        if self.open_blocks.pop().is_none() {
+            bail!("'end' without an open block");
+        }
+        self.items.push(SequenceItem::BlockEnd {
+            depth: self.open_blocks.len(),
+        });
+        Ok(())
+    }
+
+    /// Number of blocks opened but not yet ended
+    pub fn block_depth(&self) -> usize {
+        self.open_blocks.len()
+    }
+
     /// Get all participants in order
     pub fn participants(&self) -> &[Participant] {
         &self.participants
@@ -209,6 +399,20 @@ impl SequenceDatabase {
         })
     }
 
+    /// Copy of this database with each message label replaced by its
+    /// 1-based position among messages, for compact numbered rendering
+    pub fn with_numbered_messages(&self) -> SequenceDatabase {
+        let mut numbered = self.clone();
+        let messages = numbered.items.iter_mut().filter_map(|item| match item {
+            SequenceItem::Message(m) => Some(m),
+            _ => None,
+        });
+        for (i, message) in messages.enumerate() {
+            message.label = (i + 1).to_string();
+        }
+        numbered
+    }
+
     /// Get participant count
     pub fn participant_count(&self) -> usize {
         self.participants.len()
@@ -224,10 +428,58 @@ impl SequenceDatabase {
         self.participants.iter().position(|p| p.id == id)
     }
 
+    /// Whether the participant was declared with `participant`/`actor`
+    /// rather than only mentioned in a message
+    pub fn is_declared(&self, id: &str) -> bool {
+        self.declared.iter().any(|declared| declared == id)
+    }
+
+    /// Copy of this database with participants reordered
+    ///
+    /// Participants listed in `previous` (e.g. the columns of an earlier
+    /// render) keep that left-to-right order, and new ones follow in `order`.
+    /// This keeps columns from shuffling when edits reorder messages.
+    pub fn with_participant_order(
+        &self,
+        order: ParticipantOrder,
+        previous: &[String],
+    ) -> SequenceDatabase {
+        let mut base: Vec<&Participant> = self.participants.iter().collect();
+        if order == ParticipantOrder::Declared {
+            // Stable sort: declared ones by declaration index, others keep
+            // their first-mention order after them
+            base.sort_by_key(|p| {
+                self.declared
+                    .iter()
+                    .position(|id| *id == p.id)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+
+        let mut participants: Vec<Participant> = previous
+            .iter()
+            .filter_map(|id| base.iter().find(|p| p.id == *id))
+            .map(|p| (*p).clone())
+            .collect();
+        for participant in base {
+            if !participants.iter().any(|p| p.id == participant.id) {
+                participants.push(participant.clone());
+            }
+        }
+
+        SequenceDatabase {
+            participants,
+            ..self.clone()
+        }
+    }
+
     /// Clear all data
     pub fn clear_all(&mut self) {
         self.participants.clear();
+        self.declared.clear();
         self.items.clear();
+        self.open_blocks.clear();
+        self.warnings.clear();
     }
 }
 
@@ -238,6 +490,14 @@ impl Database for SequenceDatabase {
     type Node = Participant;
     type Edge = Message;
 
+    fn parse_warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    fn take_parse_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
     fn add_node(&mut self, node: Self::Node) -> Result<()> {
         self.add_participant(node)
     }
@@ -318,4 +578,100 @@ mod tests {
         assert_eq!(db.participants()[0].id, "A");
         assert_eq!(db.participants()[0].label, "Alice");
     }
+
+    #[test]
+    fn test_declaration_after_mention_sets_label_and_kind() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("A", "B", "Hi")).unwrap();
+        db.add_participant(Participant::with_label("A", "Alice").with_kind(ParticipantKind::Actor))
+            .unwrap();
+        db.add_participant(Participant::new("A")).unwrap();
+
+        assert_eq!(db.participant_count(), 2);
+        assert_eq!(db.participants()[0].label, "Alice");
+        assert!(db.participants()[0].is_actor());
+    }
+
+    #[test]
+    fn test_with_numbered_messages() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("Alice", "Bob", "Hello"))
+            .unwrap();
+        db.add_message(Message::new("Bob", "Alice", "Hi")).unwrap();
+
+        let numbered = db.with_numbered_messages();
+        let labels: Vec<_> = numbered.messages().map(|m| m.label.as_str()).collect();
+        assert_eq!(labels, vec!["1", "2"]);
+        // Original is untouched
+        assert_eq!(db.messages().next().unwrap().label, "Hello");
+    }
+
+    fn ids(db: &SequenceDatabase) -> Vec<&str> {
+        db.participants().iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_declared_order_puts_declarations_first() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("Alice", "Bob", "Hi")).unwrap();
+        db.add_participant(Participant::new("Carol")).unwrap();
+        db.add_participant(Participant::new("Bob")).unwrap();
+        db.add_message(Message::new("Dave", "Alice", "Yo")).unwrap();
+
+        assert!(db.is_declared("Bob"));
+        assert!(!db.is_declared("Alice"));
+        let reordered = db.with_participant_order(ParticipantOrder::Declared, &[]);
+        assert_eq!(ids(&reordered), vec!["Carol", "Bob", "Alice", "Dave"]);
+        let unchanged = db.with_participant_order(ParticipantOrder::FirstMention, &[]);
+        assert_eq!(ids(&unchanged), vec!["Alice", "Bob", "Carol", "Dave"]);
+    }
+
+    #[test]
+    fn test_previous_columns_stay_put() {
+        // An edit moved Bob's first mention ahead of Alice's and added Carol
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("Bob", "Carol", "Ask")).unwrap();
+        db.add_message(Message::new("Alice", "Bob", "Hi")).unwrap();
+
+        let previous = vec!["Alice".to_string(), "Bob".to_string(), "Gone".to_string()];
+        let stable = db.with_participant_order(ParticipantOrder::FirstMention, &previous);
+        assert_eq!(ids(&stable), vec!["Alice", "Bob", "Carol"]);
+        assert_eq!(stable.participant_index("Carol"), Some(2));
+    }
+
+    #[test]
+    fn test_participant_order_from_str() {
+        for order in ParticipantOrder::ALL {
+            assert_eq!(order.to_string().parse::<ParticipantOrder>(), Ok(order));
+        }
+        assert!("alphabetical".parse::<ParticipantOrder>().is_err());
+    }
+
+    #[test]
+    fn test_blocks_nest_and_divide() {
+        let mut db = SequenceDatabase::new();
+        db.start_block(BlockKind::Alt, "ok").unwrap();
+        db.start_block(BlockKind::Loop, "").unwrap();
+        assert_eq!(db.block_depth(), 2);
+        assert!(db.start_block(BlockKind::Else, "error").is_err());
+        db.end_block().unwrap();
+        db.start_block(BlockKind::Else, "error").unwrap();
+        assert!(db.start_block(BlockKind::And, "").is_err());
+        db.end_block().unwrap();
+        assert!(db.end_block().is_err());
+
+        let depths: Vec<_> = db
+            .items()
+            .iter()
+            .map(|item| match item {
+                SequenceItem::BlockStart { kind, depth, .. } => (kind.keyword(), *depth),
+                SequenceItem::BlockEnd { depth } => ("end", *depth),
+                SequenceItem::Message(_) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            depths,
+            vec![("alt", 0), ("loop", 1), ("end", 1), ("else", 0), ("end", 0)]
+        );
+    }
 }
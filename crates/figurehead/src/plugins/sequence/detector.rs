@@ -2,12 +2,13 @@
 //!
 //! Identifies sequence diagram syntax from input text.
 
-use crate::core::Detector;
+use crate::core::{header_keyword, Detector};
 
 /// Detector for sequence diagram syntax
 pub struct SequenceDetector;
 
 impl SequenceDetector {
+    /// Create a detector
     pub fn new() -> Self {
         Self
     }
@@ -59,6 +60,10 @@ impl Detector for SequenceDetector {
         "sequence"
     }
 
+    fn prefix_confidence(&self, header: &str) -> Option<f64> {
+        header_keyword(header, "sequenceDiagram").then_some(1.0)
+    }
+
     fn patterns(&self) -> Vec<&'static str> {
         vec![
             "sequenceDiagram",
@@ -5,7 +5,19 @@
 use anyhow::Result;
 use unicode_width::UnicodeWidthStr;
 
-use super::database::{Participant, SequenceDatabase};
+use super::database::{BlockKind, Participant, ParticipantKind, SequenceDatabase, SequenceItem};
+
+/// Default columns between neighbouring participant boxes
+pub const PARTICIPANT_SPACING: usize = 4;
+
+/// Columns right of the lifeline taken by a self-message's loop
+pub const SELF_MESSAGE_WIDTH: usize = 3;
+
+/// Columns right of the lifeline up to the end of a self-message's label,
+/// which is drawn one space after the loop
+pub fn self_message_reach(label: &str) -> usize {
+    SELF_MESSAGE_WIDTH + 1 + UnicodeWidthStr::width(label)
+}
 
 /// Positioned participant for rendering
 #[derive(Debug, Clone)]
@@ -14,6 +26,7 @@ pub struct PositionedParticipant {
     pub label: String,
     pub x: usize,     // Center x position
     pub width: usize, // Width of the participant box
+    pub kind: ParticipantKind,
 }
 
 /// Positioned message for rendering
@@ -24,17 +37,76 @@ pub struct PositionedMessage {
     pub y: usize,
     pub label: String,
     pub arrow: super::database::ArrowType,
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+    pub depth: usize,
+}
+
+impl PositionedMessage {
+    /// Whether the message goes from a participant to itself, drawn as a
+    /// loop on the right of its lifeline from `y` down to `y + 1`
+    pub fn is_self(&self) -> bool {
+        self.from_x == self.to_x
+    }
+}
+
+/// Positioned combined fragment (`loop`, `alt`, ...) for rendering
+#[derive(Debug, Clone)]
+pub struct PositionedFrame {
+    pub kind: BlockKind,
+    pub label: String,
+    pub left: usize,
+    pub right: usize,
+    pub top: usize,
+    pub bottom: usize,
+    /// Rows dividing the branches (`else`, `and`), with their labels
+    pub dividers: Vec<(usize, String)>,
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     pub depth: usize,
 }
 
+impl PositionedFrame {
+    /// Text shown on the top border (`loop [Every minute]`)
+    pub fn title(&self) -> String {
+        if self.label.is_empty() {
+            self.kind.keyword().to_string()
+        } else {
+            format!("{} [{}]", self.kind.keyword(), self.label)
+        }
+    }
+}
+
+/// Rows and participants of a frame, before its columns are known
+struct FrameRows {
+    kind: BlockKind,
+    label: String,
+    top: usize,
+    bottom: usize,
+    dividers: Vec<(usize, String)>,
+    depth: usize,
+    /// Leftmost and rightmost participant index of the messages inside
+    span: Option<(usize, usize)>,
+    /// Participant index and reach of the self-messages inside
+    self_messages: Vec<(usize, usize)>,
+    /// Frames nested inside this one, plus one
+    levels: usize,
+}
+
 /// Layout result containing all positioned elements
 #[derive(Debug)]
 pub struct SequenceLayoutResult {
     pub participants: Vec<PositionedParticipant>,
     pub messages: Vec<PositionedMessage>,
+    /// Frames in the order they open, outer frames before the ones inside
+    pub frames: Vec<PositionedFrame>,
     pub width: usize,
     pub height: usize,
+    /// Rows taken by the participant headers (taller with actors)
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
+    pub header_height: usize,
     pub lifeline_start_y: usize, // Y where lifelines begin (after headers)
+    pub lifeline_end_y: usize,   // Last row of the lifelines
+    /// Y of the participant boxes repeated under the lifelines, if any
+    pub footer_y: Option<usize>,
 }
 
 /// Sequence diagram layout algorithm
@@ -43,18 +115,40 @@ pub struct SequenceLayoutAlgorithm {
     participant_spacing: usize,
     message_height: usize,
     header_height: usize,
+    lifeline_padding: usize,
+    mirrored_participants: bool,
 }
 
 impl SequenceLayoutAlgorithm {
     pub fn new() -> Self {
         Self {
             participant_padding: 2, // Padding inside participant box
-            participant_spacing: 4, // Space between participants
-            message_height: 2,      // Vertical space per message
-            header_height: 3,       // Space for participant header
+            participant_spacing: PARTICIPANT_SPACING,
+            message_height: 2,   // Vertical space per message
+            header_height: 3,    // Space for participant header (box)
+            lifeline_padding: 0, // Extra lifeline rows after the last message
+            mirrored_participants: false,
         }
     }
 
+    /// Leave at least `columns` between neighbouring participant boxes
+    pub fn with_participant_spacing(mut self, columns: usize) -> Self {
+        self.participant_spacing = columns;
+        self
+    }
+
+    /// Extend lifelines `rows` further past the last message
+    pub fn with_lifeline_padding(mut self, rows: usize) -> Self {
+        self.lifeline_padding = rows;
+        self
+    }
+
+    /// Repeat the participant boxes below the lifelines
+    pub fn with_mirrored_participants(mut self, mirrored: bool) -> Self {
+        self.mirrored_participants = mirrored;
+        self
+    }
+
     /// Calculate the width needed for a participant
     fn participant_width(&self, participant: &Participant) -> usize {
         let label_width = UnicodeWidthStr::width(participant.label.as_str());
@@ -70,12 +164,23 @@ impl SequenceLayoutAlgorithm {
             return Ok(SequenceLayoutResult {
                 participants: Vec::new(),
                 messages: Vec::new(),
+                frames: Vec::new(),
                 width: 0,
                 height: 0,
+                header_height: 0,
                 lifeline_start_y: 0,
+                lifeline_end_y: 0,
+                footer_y: None,
             });
         }
 
+        // Actors stand one row taller than boxes: figure above the label
+        let header_height = if participants.iter().any(Participant::is_actor) {
+            self.header_height + 1
+        } else {
+            self.header_height
+        };
+
         // Calculate participant widths
         let widths: Vec<usize> = participants
             .iter()
@@ -89,15 +194,19 @@ impl SequenceLayoutAlgorithm {
                 database.participant_index(&msg.from),
                 database.participant_index(&msg.to),
             ) {
-                let (left_idx, right_idx) = if from_idx < to_idx {
-                    (from_idx, to_idx)
+                let (left_idx, right_idx, label_width) = if from_idx == to_idx {
+                    // A self-message's loop and label need room before the
+                    // next lifeline (the last one widens the diagram instead)
+                    if to_idx + 1 == participants.len() {
+                        continue;
+                    }
+                    (from_idx, to_idx + 1, self_message_reach(&msg.label) + 2)
                 } else {
-                    (to_idx, from_idx)
+                    // Message spans from left to right participant
+                    let label_width = UnicodeWidthStr::width(msg.label.as_str()) + 4; // Arrow chars
+                    (from_idx.min(to_idx), from_idx.max(to_idx), label_width)
                 };
 
-                // Message spans from left to right participant
-                let label_width = UnicodeWidthStr::width(msg.label.as_str()) + 4; // Arrow chars
-
                 // Calculate current span
                 let mut current_span = widths[left_idx] / 2 + widths[right_idx] / 2;
                 current_span += adjusted_spacing[left_idx..right_idx].iter().sum::<usize>();
@@ -116,9 +225,72 @@ impl SequenceLayoutAlgorithm {
             }
         }
 
-        // Position participants
+        // Place rows: one per message (plus a gap), frame border or divider
+        let mut frames: Vec<FrameRows> = Vec::new();
+        let mut open: Vec<usize> = Vec::new();
+        let mut rows = Vec::new();
+        let mut y = header_height;
+        for item in database.items() {
+            match item {
+                SequenceItem::Message(msg) => {
+                    let (Some(from_idx), Some(to_idx)) = (
+                        database.participant_index(&msg.from),
+                        database.participant_index(&msg.to),
+                    ) else {
+                        continue;
+                    };
+                    let (left_idx, right_idx) = (from_idx.min(to_idx), from_idx.max(to_idx));
+                    for &i in &open {
+                        let span = frames[i].span.get_or_insert((left_idx, right_idx));
+                        *span = (span.0.min(left_idx), span.1.max(right_idx));
+                        if from_idx == to_idx {
+                            let reach = self_message_reach(&msg.label);
+                            frames[i].self_messages.push((from_idx, reach));
+                        }
+                    }
+                    rows.push((msg, from_idx, to_idx, y));
+                    // A self-message takes an extra row to come back
+                    y += self.message_height + usize::from(from_idx == to_idx);
+                }
+                SequenceItem::BlockStart { kind, label, depth } => {
+                    match (kind.divides(), open.last()) {
+                        (Some(_), Some(&i)) => frames[i].dividers.push((y, label.clone())),
+                        (Some(_), None) => continue,
+                        (None, _) => {
+                            open.push(frames.len());
+                            frames.push(FrameRows {
+                                kind: *kind,
+                                label: label.clone(),
+                                top: y,
+                                bottom: y,
+                                dividers: Vec::new(),
+                                depth: *depth,
+                                span: None,
+                                self_messages: Vec::new(),
+                                levels: 1,
+                            });
+                        }
+                    }
+                    y += 1;
+                }
+                SequenceItem::BlockEnd { .. } => {
+                    if let Some(i) = open.pop() {
+                        Self::close_frame(&mut frames, &open, i, y);
+                        y += self.message_height;
+                    }
+                }
+            }
+        }
+        while let Some(i) = open.pop() {
+            Self::close_frame(&mut frames, &open, i, y);
+            y += self.message_height;
+        }
+
+        // Position participants, leaving room left of the first lifeline
+        // for the borders of nested frames
+        let max_levels = frames.iter().map(|f| f.levels).max().unwrap_or(0);
         let mut positioned_participants = Vec::new();
-        let mut x = 2; // Left margin
+        let mut x = 2 + max_levels.saturating_sub(1); // Left margin
 
         for (i, participant) in participants.iter().enumerate() {
             let width = widths[i];
@@ -129,6 +301,7 @@ impl SequenceLayoutAlgorithm {
                 label: participant.label.clone(),
                 x: center_x,
                 width,
+                kind: participant.kind,
             });
 
             x += width
@@ -139,44 +312,80 @@ impl SequenceLayoutAlgorithm {
                 };
         }
 
-        let total_width = x + 2; // Right margin
-
         // Position messages
-        let mut positioned_messages = Vec::new();
-        let mut y = self.header_height;
+        let positioned_messages: Vec<_> = rows
+            .into_iter()
+            .map(|(msg, from_idx, to_idx, y)| PositionedMessage {
+                from_x: positioned_participants[from_idx].x,
+                to_x: positioned_participants[to_idx].x,
+                y,
+                label: msg.label.clone(),
+                arrow: msg.arrow,
+                depth: msg.depth,
+            })
+            .collect();
 
-        for msg in &messages {
-            if let (Some(from_idx), Some(to_idx)) = (
-                database.participant_index(&msg.from),
-                database.participant_index(&msg.to),
-            ) {
-                let from_x = positioned_participants[from_idx].x;
-                let to_x = positioned_participants[to_idx].x;
-
-                positioned_messages.push(PositionedMessage {
-                    from_x,
-                    to_x,
-                    y,
-                    label: msg.label.clone(),
-                    arrow: msg.arrow,
-                    depth: msg.depth,
-                });
-
-                y += self.message_height;
-            }
-        }
+        // Frames reach past the lifelines they enclose, further for the
+        // ones with frames inside, and fit their title
+        let positioned_frames: Vec<_> = frames
+            .into_iter()
+            .map(|rows| {
+                let (first, last) = rows.span.unwrap_or((0, participants.len() - 1));
+                let pad = 1 + rows.levels;
+                let mut frame = PositionedFrame {
+                    kind: rows.kind,
+                    label: rows.label,
+                    left: positioned_participants[first].x - pad,
+                    right: positioned_participants[last].x + pad,
+                    top: rows.top,
+                    bottom: rows.bottom,
+                    dividers: rows.dividers,
+                    depth: rows.depth,
+                };
+                let title_width = UnicodeWidthStr::width(frame.title().as_str());
+                frame.right = frame.right.max(frame.left + title_width + 3);
+                for (i, reach) in rows.self_messages {
+                    frame.right = frame.right.max(positioned_participants[i].x + reach + pad);
+                }
+                frame
+            })
+            .collect();
 
-        // Add space for lifelines after last message
-        let total_height = y + 1;
+        let frames_right = positioned_frames.iter().map(|f| f.right + 1);
+        let self_messages_right = positioned_messages
+            .iter()
+            .filter(|m| m.is_self())
+            .map(|m| m.from_x + self_message_reach(&m.label) + 1);
+        let content_right = frames_right.chain(self_messages_right).max();
+        let total_width = x.max(content_right.unwrap_or(0)) + 2; // Right margin
+
+        // Add space for lifelines after last message, then the repeated
+        // participant boxes
+        let lifeline_end_y = y + self.lifeline_padding;
+        let footer_y = self.mirrored_participants.then_some(lifeline_end_y + 1);
+        let total_height = footer_y.map_or(lifeline_end_y + 1, |y| y + header_height);
 
         Ok(SequenceLayoutResult {
             participants: positioned_participants,
             messages: positioned_messages,
+            frames: positioned_frames,
             width: total_width,
             height: total_height,
-            lifeline_start_y: self.header_height - 1,
+            header_height,
+            lifeline_start_y: header_height - 1,
+            lifeline_end_y,
+            footer_y,
         })
     }
+
+    /// End frame `i` at row `y` and count it in the levels of the frame
+    /// around it
+    fn close_frame(frames: &mut [FrameRows], open: &[usize], i: usize, y: usize) {
+        frames[i].bottom = y;
+        if let Some(&parent) = open.last() {
+            frames[parent].levels = frames[parent].levels.max(frames[i].levels + 1);
+        }
+    }
 }
 
 impl Default for SequenceLayoutAlgorithm {
@@ -244,4 +453,89 @@ mod tests {
         // Second message goes left (from_x > to_x)
         assert!(result.messages[1].from_x > result.messages[1].to_x);
     }
+
+    #[test]
+    fn test_lifeline_padding_and_mirrored_participants() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("Alice", "Bob", "Hello"))
+            .unwrap();
+
+        let plain = SequenceLayoutAlgorithm::new().layout(&db).unwrap();
+        assert_eq!(plain.footer_y, None);
+        assert_eq!(plain.lifeline_end_y, plain.height - 1);
+
+        let padded = SequenceLayoutAlgorithm::new()
+            .with_lifeline_padding(3)
+            .with_mirrored_participants(true)
+            .layout(&db)
+            .unwrap();
+        assert_eq!(padded.lifeline_end_y, plain.lifeline_end_y + 3);
+        assert_eq!(padded.footer_y, Some(padded.lifeline_end_y + 1));
+        assert_eq!(padded.height, padded.lifeline_end_y + 4);
+    }
+
+    #[test]
+    fn test_frames_enclose_their_messages() {
+        let mut db = SequenceDatabase::new();
+        for id in ["Alice", "Bob", "Carol"] {
+            db.add_participant(Participant::new(id)).unwrap();
+        }
+        db.start_block(BlockKind::Loop, "Every minute").unwrap();
+        db.add_message(Message::new("Alice", "Bob", "Ping").with_depth(1))
+            .unwrap();
+        db.start_block(BlockKind::Alt, "ok").unwrap();
+        db.add_message(Message::new("Bob", "Alice", "Pong").with_depth(2))
+            .unwrap();
+        db.start_block(BlockKind::Else, "").unwrap();
+        db.end_block().unwrap();
+        db.end_block().unwrap();
+        db.add_message(Message::new("Alice", "Carol", "Bye"))
+            .unwrap();
+
+        let result = SequenceLayoutAlgorithm::new().layout(&db).unwrap();
+        let (outer, inner) = (&result.frames[0], &result.frames[1]);
+        let (alice, bob) = (result.participants[0].x, result.participants[1].x);
+        assert_eq!(outer.title(), "loop [Every minute]");
+        assert!(outer.left < inner.left && inner.left < alice);
+        assert!(bob < inner.right && inner.right < outer.right);
+        assert!(outer.right < result.participants[2].x);
+
+        let rows: Vec<_> = result.messages.iter().map(|m| m.y).collect();
+        assert!(outer.top < inner.top && inner.top < rows[1]);
+        assert_eq!(inner.dividers, vec![(rows[1] + 2, String::new())]);
+        assert!(inner.bottom < outer.bottom && outer.bottom < rows[2]);
+        assert!(outer.right < result.width);
+    }
+
+    #[test]
+    fn test_actors_make_the_header_taller() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("Alice", "Bob", "Hello"))
+            .unwrap();
+        let boxes = SequenceLayoutAlgorithm::new().layout(&db).unwrap();
+
+        db.add_participant(Participant::new("Alice").with_kind(ParticipantKind::Actor))
+            .unwrap();
+        let actors = SequenceLayoutAlgorithm::new().layout(&db).unwrap();
+        assert_eq!(actors.participants[0].kind, ParticipantKind::Actor);
+        assert_eq!(actors.header_height, boxes.header_height + 1);
+        assert_eq!(actors.lifeline_start_y, boxes.lifeline_start_y + 1);
+        assert_eq!(actors.messages[0].y, boxes.messages[0].y + 1);
+    }
+
+    #[test]
+    fn test_self_message_gets_room_and_an_extra_row() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("A", "A", "a long self call"))
+            .unwrap();
+        db.add_message(Message::new("A", "B", "")).unwrap();
+        db.add_message(Message::new("B", "B", "tick")).unwrap();
+
+        let result = SequenceLayoutAlgorithm::new().layout(&db).unwrap();
+        let (a, b) = (result.participants[0].x, result.participants[1].x);
+        assert!(result.messages[0].is_self());
+        assert!(a + self_message_reach("a long self call") < b);
+        assert_eq!(result.messages[1].y, result.messages[0].y + 3);
+        assert!(b + self_message_reach("tick") < result.width);
+    }
 }
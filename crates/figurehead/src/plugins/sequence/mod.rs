@@ -6,18 +6,23 @@
 //! ```text
 //! sequenceDiagram
 //!     Alice->>Bob: Hello
-//!     Bob-->>Alice: Hi there
+//!     loop Every minute
+//!         Bob-->>Alice: Hi there
+//!     end
 //! ```
+//!
+//! `loop`, `alt`/`else`, `opt` and `par`/`and` blocks are drawn as labeled
+//! frames around their messages.
 
 mod database;
 mod detector;
-mod layout;
+#[allow(missing_docs)]
+pub(crate) mod layout;
 mod parser;
 mod renderer;
 
-pub use database::SequenceDatabase;
+pub use database::{ParticipantOrder, SequenceDatabase};
 pub use detector::SequenceDetector;
-pub use layout::{SequenceLayoutAlgorithm, SequenceLayoutResult};
 pub use parser::SequenceParser;
 pub use renderer::SequenceRenderer;
 
@@ -2,14 +2,18 @@
 //!
 //! Parses sequence diagram syntax into the database.
 
-use super::database::{ArrowHead, ArrowType, LineStyle, Message, Participant, SequenceDatabase};
-use crate::core::Parser;
+use super::database::{
+    ArrowHead, ArrowType, BlockKind, LineStyle, Message, Participant, ParticipantKind,
+    SequenceDatabase,
+};
+use crate::core::{normalize_input, LenientStatements, Parser, StatementSplitter};
 use anyhow::Result;
 
 /// Sequence diagram parser
 pub struct SequenceParser;
 
 impl SequenceParser {
+    /// Create a parser
     pub fn new() -> Self {
         Self
     }
@@ -71,31 +75,45 @@ impl SequenceParser {
         None
     }
 
-    /// Parse a participant line like "participant Alice" or "participant A as Alice"
+    /// Parse a participant line like "participant Alice" or "actor A as Alice"
     fn parse_participant_line(&self, line: &str) -> Option<Participant> {
         let line = line.trim();
 
-        // Handle "participant X as Label" or "actor X as Label"
-        let prefixes = ["participant ", "actor "];
+        let prefixes = [
+            ("participant ", ParticipantKind::Participant),
+            ("actor ", ParticipantKind::Actor),
+        ];
 
-        for prefix in prefixes {
+        for (prefix, kind) in prefixes {
             if let Some(rest) = line.strip_prefix(prefix) {
                 let rest = rest.trim();
 
                 // Check for "as" alias syntax
-                if let Some(as_pos) = rest.find(" as ") {
+                let participant = if let Some(as_pos) = rest.find(" as ") {
                     let id = rest[..as_pos].trim().to_string();
                     let label = rest[as_pos + 4..].trim().to_string();
-                    return Some(Participant::with_label(id, label));
+                    Participant::with_label(id, label)
                 } else {
                     // Just an id
-                    let id = rest.to_string();
-                    return Some(Participant::new(id));
-                }
+                    Participant::new(rest)
+                };
+                return Some(participant.with_kind(kind));
             }
         }
         None
     }
+
+    /// Parse a block line like "loop Every minute", "else" or "end" into
+    /// its kind (`None` for `end`) and label
+    fn parse_block_line<'a>(&self, line: &'a str) -> Option<(Option<BlockKind>, &'a str)> {
+        let (keyword, label) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let label = label.trim();
+        match BlockKind::from_keyword(keyword) {
+            Some(kind) => Some((Some(kind), label)),
+            None if keyword == "end" && label.is_empty() => Some((None, label)),
+            None => None,
+        }
+    }
 }
 
 impl Default for SequenceParser {
@@ -106,30 +124,76 @@ impl Default for SequenceParser {
 
 impl Parser<SequenceDatabase> for SequenceParser {
     fn parse(&self, input: &str, database: &mut SequenceDatabase) -> Result<()> {
-        for line in input.lines() {
-            let line = line.trim();
-
-            // Skip empty lines and the diagram declaration
-            if line.is_empty() || line.to_lowercase().starts_with("sequencediagram") {
+        let normalized = normalize_input(input);
+        let input = normalized.as_ref();
+        let mut lenient = LenientStatements::new("sequence");
+        let splitter = StatementSplitter::new().with_semicolons(false);
+        // Statements opening the blocks not ended yet, to point at when
+        // the input ends first
+        let mut open_blocks = Vec::new();
+        for statement in splitter.split(input) {
+            let line = statement.text.as_str();
+
+            // Skip the diagram declaration
+            if line.to_lowercase().starts_with("sequencediagram") {
                 continue;
             }
 
             // Try to parse as participant declaration
             if let Some(participant) = self.parse_participant_line(line) {
                 database.add_participant(participant)?;
+                lenient.accept();
+                continue;
+            }
+
+            // Try to parse as block start, branch or end
+            if let Some((kind, label)) = self.parse_block_line(line) {
+                let started = match kind {
+                    Some(kind) => database.start_block(kind, label),
+                    None => database.end_block(),
+                };
+                match started {
+                    Ok(()) => {
+                        match kind {
+                            Some(kind) if kind.divides().is_none() => {
+                                open_blocks.push((kind, statement.clone()))
+                            }
+                            Some(_) => {}
+                            None => {
+                                open_blocks.pop();
+                            }
+                        }
+                        lenient.accept();
+                    }
+                    Err(error) => lenient.reject(&statement, error),
+                }
                 continue;
             }
 
             // Try to parse as message
             if let Some((from, to, label, arrow)) = self.parse_message_line(line) {
-                let message = Message::new(from, to, label).with_arrow(arrow);
+                let message = Message::new(from, to, label)
+                    .with_arrow(arrow)
+                    .with_depth(database.block_depth());
                 database.add_message(message)?;
+                lenient.accept();
                 continue;
             }
 
-            // Unknown line - skip for now (could add warnings later)
+            lenient.reject(&statement, "expected a participant or a message");
+        }
+
+        while let Some((kind, statement)) = open_blocks.pop() {
+            lenient.warn(
+                &statement,
+                format!("Unclosed '{}' block ended with the diagram", kind.keyword()),
+            );
+            database.end_block()?;
         }
 
+        for warning in lenient.finish()? {
+            database.add_parse_warning(warning);
+        }
         Ok(())
     }
 
@@ -149,7 +213,9 @@ impl Parser<SequenceDatabase> for SequenceParser {
 
 #[cfg(test)]
 mod tests {
+    use super::super::database::SequenceItem;
     use super::*;
+    use crate::core::Database;
 
     #[test]
     fn test_parse_simple_message() {
@@ -248,6 +314,28 @@ mod tests {
 
         assert_eq!(db.participant_count(), 2);
         assert_eq!(db.participants()[0].id, "User");
+        assert!(db.participants()[0].is_actor());
+        assert!(!db.participants()[1].is_actor());
+    }
+
+    #[test]
+    fn test_parse_actor_alias_declared_after_use() {
+        let parser = SequenceParser::new();
+        let mut db = SequenceDatabase::new();
+
+        parser
+            .parse(
+                "sequenceDiagram\n    U->>S: Request\n    actor U as Customer",
+                &mut db,
+            )
+            .unwrap();
+
+        let customer = &db.participants()[0];
+        assert_eq!(
+            (customer.id.as_str(), customer.label.as_str()),
+            ("U", "Customer")
+        );
+        assert!(customer.is_actor());
     }
 
     #[test]
@@ -278,4 +366,110 @@ mod tests {
         assert_eq!(msg.arrow.line, LineStyle::Solid);
         assert_eq!(msg.arrow.head, ArrowHead::Open);
     }
+
+    #[test]
+    fn test_unreadable_line_is_skipped_with_warning() {
+        let parser = SequenceParser::new();
+        let mut db = SequenceDatabase::new();
+
+        parser
+            .parse(
+                "sequenceDiagram\n    Alice->>Bob: Hi\n    Alice=>Bob\n    Bob-->>Alice: Bye",
+                &mut db,
+            )
+            .unwrap();
+
+        assert_eq!(db.message_count(), 2);
+        let warnings = db.parse_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].line, Some(3));
+        assert_eq!(warnings[0].statement.as_deref(), Some("Alice=>Bob"));
+    }
+
+    fn block_items(db: &SequenceDatabase) -> Vec<String> {
+        db.items()
+            .iter()
+            .map(|item| match item {
+                SequenceItem::Message(m) => format!("{}:{}", m.label, m.depth),
+                SequenceItem::BlockStart { kind, label, .. } => {
+                    format!("{} {}", kind.keyword(), label)
+                }
+                SequenceItem::BlockEnd { .. } => "end".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let parser = SequenceParser::new();
+        let mut db = SequenceDatabase::new();
+
+        parser
+            .parse(
+                "sequenceDiagram
+    loop Every minute
+        Alice->>Bob: Ping
+        alt is well
+            Bob-->>Alice: Fine
+        else is sick
+            Bob-->>Alice: Not so good
+        end
+    end
+    par
+        Alice->>Bob: Hi
+    and to Carol
+        Alice->>Carol: Hi
+    end
+    opt Extra
+    end",
+                &mut db,
+            )
+            .unwrap();
+
+        assert!(db.parse_warnings().is_empty(), "{:?}", db.parse_warnings());
+        assert_eq!(
+            block_items(&db),
+            vec![
+                "loop Every minute",
+                "Ping:1",
+                "alt is well",
+                "Fine:2",
+                "else is sick",
+                "Not so good:2",
+                "end",
+                "end",
+                "par ",
+                "Hi:1",
+                "and to Carol",
+                "Hi:1",
+                "end",
+                "opt Extra",
+                "end",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_misplaced_block_lines_are_skipped_with_warnings() {
+        let parser = SequenceParser::new();
+        let mut db = SequenceDatabase::new();
+
+        parser
+            .parse(
+                "sequenceDiagram\n    end\n    loop Forever\n    else\n    A->>B: Hi",
+                &mut db,
+            )
+            .unwrap();
+
+        assert_eq!(block_items(&db), vec!["loop Forever", "Hi:1", "end"]);
+        let warnings: Vec<_> = db.parse_warnings().iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "2:5: Skipped invalid statement 'end': 'end' without an open block",
+                "4:5: Skipped invalid statement 'else': 'else' is only allowed directly inside 'alt'",
+                "3:5: Unclosed 'loop' block ended with the diagram",
+            ]
+        );
+    }
 }
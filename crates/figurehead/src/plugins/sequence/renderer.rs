@@ -3,31 +3,174 @@
 //! Renders sequence diagrams as ASCII art.
 
 use anyhow::Result;
+use std::cmp::Reverse;
 
-use super::database::{ArrowHead, ArrowType, LineStyle, SequenceDatabase};
-use super::layout::SequenceLayoutAlgorithm;
-use crate::core::{AsciiCanvas, CharacterSet};
+use super::database::{
+    ArrowHead, ArrowType, LineStyle, ParticipantKind, ParticipantOrder, SequenceDatabase,
+};
+use super::layout::{
+    PositionedFrame, PositionedMessage, SequenceLayoutAlgorithm, PARTICIPANT_SPACING,
+    SELF_MESSAGE_WIDTH,
+};
+use crate::core::{
+    AsciiCanvas, CellGrid, CharacterSet, Footer, FooterSection, Role, RoleRecorder,
+    SequenceInitConfig,
+};
+use crate::plugins::observer::LayoutProbe;
+
+/// Mermaid's default `actorMargin`, in pixels
+const MERMAID_ACTOR_MARGIN: f64 = 50.0;
 
 /// Sequence diagram renderer
+#[derive(Clone)]
 pub struct SequenceRenderer {
     style: CharacterSet,
+    message_index: bool,
+    color: bool,
+    hops: bool,
+    participant_order: ParticipantOrder,
+    previous_columns: Vec<String>,
+    lifeline_padding: usize,
+    participant_spacing: usize,
+    mirrored_participants: bool,
 }
 
 impl SequenceRenderer {
+    /// Create a renderer with the default character set
     pub fn new() -> Self {
         Self {
             style: CharacterSet::default(),
+            message_index: false,
+            color: false,
+            hops: false,
+            participant_order: ParticipantOrder::default(),
+            previous_columns: Vec::new(),
+            lifeline_padding: 0,
+            participant_spacing: PARTICIPANT_SPACING,
+            mirrored_participants: false,
         }
     }
 
+    /// Create a renderer with the given character set
     pub fn with_style(style: CharacterSet) -> Self {
-        Self { style }
+        Self {
+            style,
+            ..Self::new()
+        }
+    }
+
+    /// Draw only message numbers on arrows and list the full messages in an
+    /// index below the diagram (`1 Alice→Bob: Hello`)
+    pub fn with_message_index(mut self, enabled: bool) -> Self {
+        self.message_index = enabled;
+        self
+    }
+
+    /// Color participants, lifelines, messages and labels with ANSI codes
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
+
+    /// Mark messages passing other lifelines with a hop (`─(─`) instead of
+    /// a junction (`─┼─`)
+    pub fn with_hops(mut self, enabled: bool) -> Self {
+        self.hops = enabled;
+        self
+    }
+
+    /// Choose the left-to-right order of participant columns
+    pub fn with_participant_order(mut self, order: ParticipantOrder) -> Self {
+        self.participant_order = order;
+        self
+    }
+
+    /// Keep participants from an earlier render in their columns
+    ///
+    /// Pass the result of [`column_order`](Self::column_order) from the
+    /// previous render; participants it lists keep their relative order and
+    /// new ones are added to the right, so edits cause less diff churn.
+    pub fn with_previous_columns(mut self, columns: Vec<String>) -> Self {
+        self.previous_columns = columns;
+        self
+    }
+
+    /// Extend lifelines `rows` further past the last message
+    pub fn with_lifeline_padding(mut self, rows: usize) -> Self {
+        self.lifeline_padding = rows;
+        self
+    }
+
+    /// Repeat the participant boxes at the bottom (Mermaid's `mirrorActors`),
+    /// so long diagrams stay readable when scrolled to the end
+    pub fn with_mirrored_participants(mut self, mirrored: bool) -> Self {
+        self.mirrored_participants = mirrored;
+        self
+    }
+
+    /// Leave at least `columns` between neighbouring participant boxes
+    pub fn with_participant_spacing(mut self, columns: usize) -> Self {
+        self.participant_spacing = columns;
+        self
+    }
+
+    /// Apply the `sequence` section of an init directive
+    ///
+    /// `actorMargin` is scaled relative to Mermaid's default, so
+    /// `actorMargin: 100` doubles the default gap between participants.
+    pub fn with_init(mut self, init: &SequenceInitConfig) -> Self {
+        if let Some(px) = init.actor_margin {
+            let columns = px / MERMAID_ACTOR_MARGIN * PARTICIPANT_SPACING as f64;
+            self.participant_spacing = (columns.round() as usize).max(1);
+        }
+        if let Some(mirrored) = init.mirror_actors {
+            self.mirrored_participants = mirrored;
+        }
+        self
+    }
+
+    /// Participant IDs from left to right, as this renderer would draw them
+    pub fn column_order(&self, database: &SequenceDatabase) -> Vec<String> {
+        self.ordered(database)
+            .participants()
+            .iter()
+            .map(|p| p.id.clone())
+            .collect()
+    }
+
+    /// Apply the participant order and previous columns
+    fn ordered(&self, database: &SequenceDatabase) -> SequenceDatabase {
+        database.with_participant_order(self.participant_order, &self.previous_columns)
     }
 
     fn is_unicode(&self) -> bool {
         !self.style.is_ascii()
     }
 
+    /// Line character for solid or dotted messages
+    fn line_char(&self, solid: bool) -> char {
+        match (solid, self.is_unicode()) {
+            (true, true) => '─',
+            (true, false) => '-',
+            (false, true) => '╌',
+            (false, false) => '.',
+        }
+    }
+
+    /// Arrowhead character for a message pointing right or left
+    ///
+    /// Async messages get an open head (`▷`) to tell them from calls (`▶`).
+    fn head_char(&self, head: ArrowHead, going_right: bool) -> Option<char> {
+        let (right, left) = match (head, self.is_unicode()) {
+            (ArrowHead::Arrow, true) => ('▶', '◀'),
+            (ArrowHead::Arrow, false) => ('>', '<'),
+            (ArrowHead::Open, true) => ('▷', '◁'),
+            (ArrowHead::Open, false) => (')', '('),
+            (ArrowHead::None, _) => return None,
+        };
+        Some(if going_right { right } else { left })
+    }
+
     /// Draw a horizontal line with style options
     fn draw_styled_horizontal(
         &self,
@@ -38,17 +181,7 @@ impl SequenceRenderer {
         solid: bool,
     ) {
         let (start, end) = if x1 < x2 { (x1, x2) } else { (x2, x1) };
-        let line_char = if solid {
-            if self.is_unicode() {
-                '─'
-            } else {
-                '-'
-            }
-        } else if self.is_unicode() {
-            '╌'
-        } else {
-            '-'
-        };
+        let line_char = self.line_char(solid);
         for x in start..=end {
             canvas.set_char(x, y, line_char);
         }
@@ -121,46 +254,32 @@ impl SequenceRenderer {
         canvas.draw_text_centered(x, y + 1, label);
     }
 
-    /// Draw a message arrow with label
+    /// Draw an actor: a stick figure with the label underneath
+    fn draw_actor(&self, canvas: &mut AsciiCanvas, x: usize, y: usize, label: &str) {
+        let (body, legs) = if self.is_unicode() {
+            ("╱│╲", "╱ ╲")
+        } else {
+            ("/|\\", "/ \\")
+        };
+        canvas.set_char(x, y, 'o');
+        canvas.draw_text_centered(x, y + 1, body);
+        canvas.draw_text_centered(x, y + 2, legs);
+        canvas.draw_text_centered(x, y + 3, label);
+    }
+
+    /// Draw a message arrow
     fn draw_message(
         &self,
         canvas: &mut AsciiCanvas,
         from_x: usize,
         to_x: usize,
         y: usize,
-        label: &str,
         arrow: &ArrowType,
     ) {
-        let unicode = self.is_unicode();
         let going_right = to_x > from_x;
         let solid = arrow.line == LineStyle::Solid;
-
-        // Determine arrow head characters
-        let (arrow_char, arrow_offset) = match arrow.head {
-            ArrowHead::Arrow => {
-                if unicode {
-                    if going_right {
-                        ('▶', 0)
-                    } else {
-                        ('◀', 0)
-                    }
-                } else if going_right {
-                    ('>', 0)
-                } else {
-                    ('<', 0)
-                }
-            }
-            ArrowHead::Open => {
-                if going_right {
-                    (')', 0)
-                } else {
-                    ('(', 0)
-                }
-            }
-            ArrowHead::None => {
-                (' ', 1) // No arrow, just line
-            }
-        };
+        let head = self.head_char(arrow.head, going_right);
+        let arrow_offset = usize::from(head.is_none());
 
         // Draw the line (leaving space for arrow)
         let (line_start, line_end) = if going_right {
@@ -174,62 +293,358 @@ impl SequenceRenderer {
         }
 
         // Draw arrow head
-        if arrow.head != ArrowHead::None {
-            canvas.set_char(to_x, y, arrow_char);
+        if let Some(head) = head {
+            canvas.set_char(to_x, y, head);
         }
+    }
 
-        // Draw label centered on the line
-        if !label.is_empty() {
-            let center_x = (from_x + to_x) / 2;
-            canvas.draw_text_centered(center_x, y, label);
+    /// Draw a message from a participant to itself: out to the right on
+    /// row `y` and back into the lifeline on the row below
+    fn draw_self_message(&self, canvas: &mut AsciiCanvas, x: usize, y: usize, arrow: &ArrowType) {
+        let line = self.line_char(arrow.line == LineStyle::Solid);
+        let (top, bottom) = if self.is_unicode() {
+            ('┐', '┘')
+        } else {
+            ('+', '+')
+        };
+        let right = x + SELF_MESSAGE_WIDTH;
+        for dx in x + 1..right {
+            canvas.set_char(dx, y, line);
+            canvas.set_char(dx, y + 1, line);
+        }
+        canvas.set_char(right, y, top);
+        canvas.set_char(right, y + 1, bottom);
+        if let Some(head) = self.head_char(arrow.head, false) {
+            canvas.set_char(x + 1, y + 1, head);
         }
     }
 
+    /// Mark where a message passes the lifelines between its endpoints
+    ///
+    /// Without this the message line simply overwrites those lifelines and
+    /// a long-distance message reads as ending at the first one.
+    fn draw_lifeline_crossings(
+        &self,
+        canvas: &mut AsciiCanvas,
+        from_x: usize,
+        to_x: usize,
+        y: usize,
+        lifelines: &[usize],
+    ) {
+        let (left, right) = if from_x < to_x {
+            (from_x, to_x)
+        } else {
+            (to_x, from_x)
+        };
+        let glyph = self.crossing_glyph();
+        for &x in lifelines.iter().filter(|&&x| left < x && x < right) {
+            if matches!(canvas.get_char(x, y), '─' | '╌' | '-' | '.') {
+                canvas.set_char(x, y, glyph);
+            }
+        }
+    }
+
+    /// Mark for a message passing a lifeline: a hop or a junction
+    fn crossing_glyph(&self) -> char {
+        match (self.hops, self.is_unicode()) {
+            (true, _) => '(',
+            (false, true) => '┼',
+            (false, false) => '+',
+        }
+    }
+
+    /// Draw a frame border or divider row from `left` to `right`, crossing
+    /// the lifelines in between
+    fn draw_frame_row(
+        &self,
+        canvas: &mut AsciiCanvas,
+        left: usize,
+        right: usize,
+        y: usize,
+        ends: (char, char),
+        line: char,
+    ) {
+        let crossing = if self.is_unicode() { '┼' } else { '+' };
+        for x in left + 1..right {
+            let ch = match canvas.get_char(x, y) {
+                '│' | '|' => crossing,
+                _ => line,
+            };
+            canvas.set_char(x, y, ch);
+        }
+        canvas.set_char(left, y, ends.0);
+        canvas.set_char(right, y, ends.1);
+    }
+
+    /// Draw a combined fragment: a box titled with its kind and label, and
+    /// a dashed divider with the label of each further branch
+    fn draw_frame(&self, canvas: &mut AsciiCanvas, frame: &PositionedFrame) {
+        let (corners, horizontal, vertical, tees, dashed) = if self.is_unicode() {
+            (['┌', '┐', '└', '┘'], '─', '│', ('├', '┤'), '╌')
+        } else {
+            (['+'; 4], '-', '|', ('+', '+'), '-')
+        };
+        let (left, right) = (frame.left, frame.right);
+
+        for y in frame.top + 1..frame.bottom {
+            canvas.set_char(left, y, vertical);
+            canvas.set_char(right, y, vertical);
+        }
+        let top = (corners[0], corners[1]);
+        self.draw_frame_row(canvas, left, right, frame.top, top, horizontal);
+        let bottom = (corners[2], corners[3]);
+        self.draw_frame_row(canvas, left, right, frame.bottom, bottom, horizontal);
+        canvas.draw_text(left + 2, frame.top, &frame.title());
+
+        for (y, label) in &frame.dividers {
+            self.draw_frame_row(canvas, left, right, *y, tees, dashed);
+            if !label.is_empty() {
+                canvas.draw_text(left + 2, *y, &format!("[{}]", label));
+            }
+        }
+    }
+
+    /// Draw a message label centered on its arrow
+    ///
+    /// A label centered over a lifeline the message crosses would hide the
+    /// crossing, so it moves to the widest stretch between lifelines that
+    /// holds it. A label too wide for any stretch stays centered and the
+    /// crossings it covers are drawn over it.
+    fn draw_message_label(
+        &self,
+        canvas: &mut AsciiCanvas,
+        from_x: usize,
+        to_x: usize,
+        y: usize,
+        label: &str,
+        lifelines: &[usize],
+    ) {
+        if label.is_empty() {
+            return;
+        }
+        let (left, right) = (from_x.min(to_x), from_x.max(to_x));
+        let width = label.chars().count();
+        let crossed: Vec<usize> = lifelines
+            .iter()
+            .copied()
+            .filter(|&x| left < x && x < right)
+            .collect();
+        let covers = |center: usize| {
+            let start = center.saturating_sub(width / 2);
+            crossed.iter().any(|&x| start <= x && x < start + width)
+        };
+
+        let mut center_x = (left + right) / 2;
+        if covers(center_x) {
+            let stops: Vec<usize> = std::iter::once(left)
+                .chain(crossed.iter().copied())
+                .chain(std::iter::once(right))
+                .collect();
+            let widest = stops
+                .windows(2)
+                .map(|pair| (pair[0], pair[1]))
+                .filter(|&(a, b)| b - a > width)
+                .max_by_key(|&(a, b)| (b - a, Reverse(((a + b) / 2).abs_diff(center_x))));
+            if let Some((a, b)) = widest {
+                center_x = (a + b) / 2;
+            }
+        }
+        canvas.draw_text_centered(center_x, y, label);
+        let start = center_x.saturating_sub(width / 2);
+        for &x in crossed.iter().filter(|&&x| start <= x && x < start + width) {
+            canvas.set_char(x, y, self.crossing_glyph());
+        }
+    }
+
+    /// Build the numbered message index listed under the diagram
+    fn message_index(&self, database: &SequenceDatabase) -> FooterSection {
+        let arrow = if self.is_unicode() { "→" } else { "->" };
+        let label_of = |id: &str| {
+            database
+                .participants()
+                .iter()
+                .find(|p| p.id == id)
+                .map_or(id.to_string(), |p| p.label.clone())
+        };
+
+        let mut section = FooterSection::new();
+        for (i, msg) in database.messages().enumerate() {
+            let mut text = format!("{}{}{}", label_of(&msg.from), arrow, label_of(&msg.to));
+            if !msg.label.is_empty() {
+                text.push_str(": ");
+                text.push_str(&msg.label);
+            }
+            section.push((i + 1).to_string(), text);
+        }
+        section
+    }
+
     /// Render the database to ASCII
     pub fn render(&self, database: &SequenceDatabase) -> Result<String> {
-        let layout_algo = SequenceLayoutAlgorithm::new();
+        self.render_probed(database, &mut LayoutProbe::default())
+    }
+
+    /// Render the database, timing the layout through `probe`
+    pub(crate) fn render_probed(
+        &self,
+        database: &SequenceDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<String> {
+        let reordered;
+        let database = if self.participant_order == ParticipantOrder::FirstMention
+            && self.previous_columns.is_empty()
+        {
+            database
+        } else {
+            reordered = self.ordered(database);
+            &reordered
+        };
+
+        if self.message_index && database.message_count() > 0 {
+            let diagram = self.render_diagram(&database.with_numbered_messages(), probe)?;
+            let footer = Footer::new().with_section(self.message_index(database));
+            return Ok(footer.attach(&diagram));
+        }
+        self.render_diagram(database, probe)
+    }
+
+    /// Render the database as a [`CellGrid`]
+    ///
+    /// Cells of a participant's box and lifeline carry the participant's
+    /// ID, and cells of a message's arrow and label carry `from->to`; roles
+    /// become palette colors when color is on. The message index footer is
+    /// left out.
+    pub fn render_cells(&self, database: &SequenceDatabase) -> Result<CellGrid> {
+        let mut database = self.ordered(database);
+        if self.message_index {
+            database = database.with_numbered_messages();
+        }
+        let drawn = self.draw_diagram(&database, &mut LayoutProbe::default(), true)?;
+        Ok(match drawn {
+            Some((canvas, roles)) => roles.finish_cells(&canvas, self.color),
+            None => CellGrid::default(),
+        })
+    }
+
+    fn render_diagram(
+        &self,
+        database: &SequenceDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<String> {
+        Ok(match self.draw_diagram(database, probe, self.color)? {
+            Some((canvas, roles)) => roles.finish(&canvas),
+            None => String::new(),
+        })
+    }
+
+    /// Draw the diagram, recording roles and owners when `record` is set
+    fn draw_diagram(
+        &self,
+        database: &SequenceDatabase,
+        probe: &mut LayoutProbe,
+        record: bool,
+    ) -> Result<Option<(AsciiCanvas, RoleRecorder)>> {
+        let layout_algo = SequenceLayoutAlgorithm::new()
+            .with_lifeline_padding(self.lifeline_padding)
+            .with_participant_spacing(self.participant_spacing)
+            .with_mirrored_participants(self.mirrored_participants);
+        let started = probe.start();
         let layout = layout_algo.layout(database)?;
+        probe.finish(started, layout.width, layout.height);
 
         if layout.participants.is_empty() {
-            return Ok(String::new());
+            return Ok(None);
         }
 
         let mut canvas = AsciiCanvas::new(layout.width, layout.height);
+        let mut roles = RoleRecorder::new(record, &canvas);
 
-        // Draw participant headers
-        for participant in &layout.participants {
-            self.draw_participant(
-                &mut canvas,
-                participant.x,
-                0,
-                &participant.label,
-                participant.width,
-            );
+        // Draw participant headers, and footers when mirrored
+        for y in std::iter::once(0).chain(layout.footer_y) {
+            for participant in &layout.participants {
+                canvas.track_writes();
+                if participant.kind == ParticipantKind::Actor {
+                    self.draw_actor(&mut canvas, participant.x, y, &participant.label);
+                } else {
+                    self.draw_participant(
+                        &mut canvas,
+                        participant.x,
+                        y,
+                        &participant.label,
+                        participant.width,
+                    );
+                }
+                roles.own_cells(&canvas.take_writes(), &participant.id);
+            }
         }
+        roles.record(&canvas, Role::Node);
 
         // Draw lifelines
         for participant in &layout.participants {
+            canvas.track_writes();
             self.draw_lifeline(
                 &mut canvas,
                 participant.x,
                 layout.lifeline_start_y,
-                layout.height - 1,
+                layout.lifeline_end_y,
             );
+            roles.own_cells(&canvas.take_writes(), &participant.id);
+        }
+        roles.record(&canvas, Role::Lifeline);
+
+        // Draw frames around blocks, outer ones first
+        for frame in &layout.frames {
+            self.draw_frame(&mut canvas, frame);
         }
+        roles.record(&canvas, Role::Frame);
 
-        // Draw messages
+        // Draw messages, then their labels on top
+        let lifelines: Vec<usize> = layout.participants.iter().map(|p| p.x).collect();
+        let participant_at = |x: usize| {
+            layout
+                .participants
+                .iter()
+                .find(|p| p.x == x)
+                .map_or("", |p| p.id.as_str())
+        };
+        let id = |msg: &PositionedMessage| {
+            format!(
+                "{}->{}",
+                participant_at(msg.from_x),
+                participant_at(msg.to_x)
+            )
+        };
         for msg in &layout.messages {
-            self.draw_message(
-                &mut canvas,
-                msg.from_x,
-                msg.to_x,
-                msg.y,
-                &msg.label,
-                &msg.arrow,
-            );
+            canvas.track_writes();
+            if msg.is_self() {
+                self.draw_self_message(&mut canvas, msg.from_x, msg.y, &msg.arrow);
+            } else {
+                self.draw_message(&mut canvas, msg.from_x, msg.to_x, msg.y, &msg.arrow);
+                self.draw_lifeline_crossings(&mut canvas, msg.from_x, msg.to_x, msg.y, &lifelines);
+            }
+            roles.own_cells(&canvas.take_writes(), &id(msg));
         }
+        roles.record(&canvas, Role::Edge);
+        for msg in &layout.messages {
+            canvas.track_writes();
+            if msg.is_self() {
+                let label_x = msg.from_x + SELF_MESSAGE_WIDTH + 2;
+                canvas.draw_text(label_x, msg.y, &msg.label);
+            } else {
+                self.draw_message_label(
+                    &mut canvas,
+                    msg.from_x,
+                    msg.to_x,
+                    msg.y,
+                    &msg.label,
+                    &lifelines,
+                );
+            }
+            roles.own_cells(&canvas.take_writes(), &id(msg));
+        }
+        roles.record(&canvas, Role::Label);
 
-        Ok(canvas.to_string())
+        Ok(Some((canvas, roles)))
     }
 }
 
@@ -261,7 +676,7 @@ impl crate::core::Renderer<SequenceDatabase> for SequenceRenderer {
 
 #[cfg(test)]
 mod tests {
-    use super::super::database::{ArrowType, Message, Participant};
+    use super::super::database::{ArrowType, BlockKind, Message, Participant};
     use super::*;
 
     #[test]
@@ -331,4 +746,307 @@ mod tests {
         // Should contain dotted line character
         assert!(output.contains('╌') || output.contains('-'));
     }
+
+    #[test]
+    fn test_render_with_message_index() {
+        let mut db = SequenceDatabase::new();
+        db.add_participant(Participant::with_label("A", "Alice"))
+            .unwrap();
+        db.add_message(Message::new("A", "Bob", "A rather long greeting"))
+            .unwrap();
+        db.add_message(Message::new("Bob", "A", "")).unwrap();
+
+        let output = SequenceRenderer::new()
+            .with_message_index(true)
+            .render(&db)
+            .unwrap();
+
+        let (diagram, index) = output.split_once("\n\n").unwrap();
+        assert!(!diagram.contains("greeting"));
+        assert!(diagram.contains('1') && diagram.contains('2'));
+        assert_eq!(index, "1 Alice→Bob: A rather long greeting\n2 Bob→Alice");
+    }
+
+    #[test]
+    fn test_message_index_ascii_and_padding() {
+        let mut db = SequenceDatabase::new();
+        for i in 0..10 {
+            db.add_message(Message::new("A", "B", format!("m{}", i)))
+                .unwrap();
+        }
+
+        let output = SequenceRenderer::with_style(CharacterSet::Ascii)
+            .with_message_index(true)
+            .render(&db)
+            .unwrap();
+
+        assert!(output.contains("\n 1 A->B: m0\n"));
+        assert!(output.ends_with("10 A->B: m9"));
+    }
+
+    #[test]
+    fn test_message_index_uses_columns_in_wide_diagrams() {
+        let mut db = SequenceDatabase::new();
+        db.add_participant(Participant::new("A")).unwrap();
+        db.add_participant(Participant::new("B")).unwrap();
+        db.add_participant(Participant::with_label(
+            "C",
+            "A participant with a long name",
+        ))
+        .unwrap();
+        for i in 0..4 {
+            db.add_message(Message::new("A", "B", format!("m{}", i)))
+                .unwrap();
+        }
+
+        let output = SequenceRenderer::with_style(CharacterSet::Ascii)
+            .with_message_index(true)
+            .render(&db)
+            .unwrap();
+
+        let (_, index) = output.rsplit_once("\n\n").unwrap();
+        assert_eq!(index, "1 A->B: m0   2 A->B: m1   3 A->B: m2   4 A->B: m3");
+    }
+
+    #[test]
+    fn test_color_uses_role_palette() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("Alice", "Bob", "Hi")).unwrap();
+
+        let plain = SequenceRenderer::new().render(&db).unwrap();
+        let colored = SequenceRenderer::new()
+            .with_color(true)
+            .render(&db)
+            .unwrap();
+
+        assert!(!plain.contains('\x1b'));
+        let palette = crate::core::Palette::default();
+        for role in [Role::Node, Role::Lifeline, Role::Edge, Role::Label] {
+            let code = format!("\x1b[{}m", palette.code(role));
+            assert!(colored.contains(&code), "missing {:?}", role);
+        }
+        assert!(colored.contains("\x1b[1;33mHi\x1b[0m"));
+    }
+
+    fn three_participants_far_message() -> SequenceDatabase {
+        let mut db = SequenceDatabase::new();
+        for id in ["A", "B", "C"] {
+            db.add_participant(Participant::new(id)).unwrap();
+        }
+        db.add_message(Message::new("A", "C", "")).unwrap();
+        db
+    }
+
+    #[test]
+    fn test_long_message_crosses_intermediate_lifeline() {
+        let db = three_participants_far_message();
+
+        let output = SequenceRenderer::new().render(&db).unwrap();
+        let message_row = output.lines().find(|l| l.contains('▶')).unwrap();
+        assert_eq!(message_row.matches('┼').count(), 1, "{}", output);
+
+        let ascii = SequenceRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        let message_row = ascii.lines().find(|l| l.contains('>')).unwrap();
+        assert!(message_row.contains("-+-"), "{}", ascii);
+    }
+
+    #[test]
+    fn test_label_over_intermediate_lifeline_keeps_the_crossing() {
+        let mut db = SequenceDatabase::new();
+        for id in ["A", "B", "C"] {
+            db.add_participant(Participant::new(id)).unwrap();
+        }
+        db.add_message(Message::new("C", "A", "hi")).unwrap();
+
+        // The label, then the index, would be centered on B's lifeline
+        for (renderer, text) in [
+            (SequenceRenderer::new(), "hi"),
+            (SequenceRenderer::new().with_message_index(true), "1"),
+        ] {
+            let output = renderer.render(&db).unwrap();
+            let rows: Vec<Vec<char>> = output.lines().map(|l| l.chars().collect()).collect();
+            let lifelines: Vec<usize> = (0..rows[4].len()).filter(|&x| rows[4][x] == '│').collect();
+            assert_eq!(rows[3][lifelines[1]], '┼', "{}", output);
+            assert!(output.lines().nth(3).unwrap().contains(text), "{}", output);
+        }
+    }
+
+    #[test]
+    fn test_hop_mark_for_long_messages() {
+        let db = three_participants_far_message();
+
+        let output = SequenceRenderer::new().with_hops(true).render(&db).unwrap();
+        assert!(output.contains("─(─"), "{}", output);
+        assert!(!output.contains('┼'));
+    }
+
+    #[test]
+    fn test_adjacent_message_has_no_crossing() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("A", "B", "")).unwrap();
+
+        let output = SequenceRenderer::new().render(&db).unwrap();
+        assert!(!output.contains('┼'), "{}", output);
+    }
+
+    #[test]
+    fn test_previous_columns_keep_lifelines_in_place() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("Bob", "Alice", "Hi")).unwrap();
+
+        let renderer = SequenceRenderer::with_style(CharacterSet::Ascii)
+            .with_previous_columns(vec!["Alice".to_string(), "Bob".to_string()]);
+        assert_eq!(renderer.column_order(&db), vec!["Alice", "Bob"]);
+
+        let output = renderer.render(&db).unwrap();
+        let header = output.lines().nth(1).unwrap();
+        assert!(header.find("Alice").unwrap() < header.find("Bob").unwrap());
+        assert!(output.contains("<"), "{}", output);
+    }
+
+    #[test]
+    fn test_mirrored_participants_and_lifeline_padding() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("Alice", "Bob", "Hi")).unwrap();
+
+        let plain = SequenceRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        let output = SequenceRenderer::with_style(CharacterSet::Ascii)
+            .with_lifeline_padding(2)
+            .with_mirrored_participants(true)
+            .render(&db)
+            .unwrap();
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), plain.lines().count() + 2 + 3, "{}", output);
+        // The header row is repeated as the second-to-last line
+        assert_eq!(lines[1], lines[lines.len() - 2]);
+        assert!(lines[lines.len() - 4].contains('|'), "{}", output);
+    }
+
+    #[test]
+    fn test_init_actor_margin_and_mirror_actors() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("Alice", "Bob", "Hi")).unwrap();
+
+        let width = |s: &str| s.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        let plain = SequenceRenderer::new().render(&db).unwrap();
+        let init = SequenceInitConfig {
+            actor_margin: Some(150.0),
+            mirror_actors: Some(true),
+        };
+        let output = SequenceRenderer::new()
+            .with_init(&init)
+            .render(&db)
+            .unwrap();
+
+        assert_eq!(width(&output), width(&plain) + 8, "{}", output);
+        assert_eq!(output.lines().count(), plain.lines().count() + 3);
+    }
+
+    #[test]
+    fn test_render_frames() {
+        let mut db = SequenceDatabase::new();
+        db.start_block(BlockKind::Alt, "ok").unwrap();
+        db.add_message(Message::new("A", "B", "Hi").with_depth(1))
+            .unwrap();
+        db.start_block(BlockKind::Else, "busy").unwrap();
+        db.add_message(Message::new("A", "B", "Later").with_depth(1))
+            .unwrap();
+        db.end_block().unwrap();
+
+        let output = SequenceRenderer::new().render(&db).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        let top = lines
+            .iter()
+            .position(|l| l.contains("┌─alt [ok]─"))
+            .unwrap();
+        let divider = lines.iter().position(|l| l.contains("├╌[busy]╌")).unwrap();
+        let bottom = lines.iter().rposition(|l| l.contains('┘')).unwrap();
+        assert!(top < divider && divider < bottom, "{}", output);
+        assert!(lines[divider].ends_with('┤'), "{}", output);
+        assert_eq!(lines[bottom].matches('┼').count(), 2, "{}", output);
+
+        let ascii = SequenceRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        assert!(ascii.contains("+-alt [ok]-"), "{}", ascii);
+        assert!(ascii.contains("+-[busy]-"), "{}", ascii);
+    }
+
+    #[test]
+    fn test_render_actor_as_stick_figure() {
+        let mut db = SequenceDatabase::new();
+        db.add_participant(Participant::with_label("U", "User").with_kind(ParticipantKind::Actor))
+            .unwrap();
+        db.add_message(Message::new("U", "System", "Login"))
+            .unwrap();
+
+        let output = SequenceRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[0].trim_start().starts_with("o "), "{}", output);
+        assert!(lines[1].contains("/|\\") && lines[1].contains("| System |"));
+        assert!(lines[2].contains("/ \\"), "{}", output);
+        assert!(lines[3].contains("User"), "{}", output);
+        assert!(lines[4].contains("|---Login"), "{}", output);
+    }
+
+    #[test]
+    fn test_declarations_set_column_order_by_default() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("Alice", "Bob", "Hi")).unwrap();
+        db.add_participant(Participant::new("Bob")).unwrap();
+
+        assert_eq!(
+            SequenceRenderer::new().column_order(&db),
+            vec!["Bob", "Alice"]
+        );
+        let first_mention =
+            SequenceRenderer::new().with_participant_order(ParticipantOrder::FirstMention);
+        assert_eq!(first_mention.column_order(&db), vec!["Alice", "Bob"]);
+    }
+
+    #[test]
+    fn test_self_message_loops_back_to_its_lifeline() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("A", "A", "tick")).unwrap();
+        db.add_message(Message::new("A", "B", "")).unwrap();
+
+        let output = SequenceRenderer::new().render(&db).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        let out = lines.iter().position(|l| l.contains("──┐ tick")).unwrap();
+        assert!(lines[out].trim_start().starts_with("│──┐"), "{}", output);
+        assert!(lines[out + 1].contains("│◀─┘"), "{}", output);
+
+        let ascii = SequenceRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        assert!(ascii.contains("|--+ tick"), "{}", ascii);
+        assert!(ascii.contains("|<-+"), "{}", ascii);
+    }
+
+    #[test]
+    fn test_async_and_dotted_arrows_are_distinct() {
+        let mut db = SequenceDatabase::new();
+        db.add_message(Message::new("A", "B", "call")).unwrap();
+        db.add_message(Message::new("A", "B", "fire").with_arrow(ArrowType::solid_async()))
+            .unwrap();
+        db.add_message(Message::new("B", "A", "back").with_arrow(ArrowType::dotted_arrow()))
+            .unwrap();
+
+        let output = SequenceRenderer::new().render(&db).unwrap();
+        assert!(output.contains('▶') && output.contains('▷'), "{}", output);
+        assert!(output.contains("◀╌"), "{}", output);
+
+        let ascii = SequenceRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        assert!(ascii.contains("-->") && ascii.contains("--)"), "{}", ascii);
+        assert!(ascii.contains("<.") && ascii.contains("..|"), "{}", ascii);
+    }
 }
@@ -2,14 +2,50 @@
 //!
 //! Stores states and transitions for state diagrams using core types.
 
-use crate::core::{Database, EdgeData, NodeData, NodeShape};
+use crate::core::{Database, EdgeData, NodeData, NodeShape, ParseWarning};
 use anyhow::Result;
+use std::collections::HashMap;
 
 /// Internal ID for start terminal
 pub const START_TERMINAL: &str = "[*]_start";
 /// Internal ID for end terminal
 pub const END_TERMINAL: &str = "[*]_end";
 
+/// Pseudostate declared with `state id <<kind>>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pseudostate {
+    /// Splits one transition into concurrent ones, drawn as a thick bar
+    Fork,
+    /// Merges concurrent transitions into one, drawn as a thick bar
+    Join,
+    /// Picks one outgoing transition, drawn as a small diamond
+    Choice,
+}
+
+impl Pseudostate {
+    /// Every pseudostate
+    pub const ALL: [Pseudostate; 3] = [Pseudostate::Fork, Pseudostate::Join, Pseudostate::Choice];
+
+    /// Name inside the `<<...>>` annotation
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Pseudostate::Fork => "fork",
+            Pseudostate::Join => "join",
+            Pseudostate::Choice => "choice",
+        }
+    }
+
+    /// The pseudostate annotated with `keyword`
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.keyword() == keyword)
+    }
+
+    /// Whether this is drawn as a bar (fork or join)
+    pub fn is_bar(self) -> bool {
+        matches!(self, Pseudostate::Fork | Pseudostate::Join)
+    }
+}
+
 /// State diagram database using core NodeData and EdgeData
 #[derive(Debug, Default)]
 pub struct StateDatabase {
@@ -17,13 +53,28 @@ pub struct StateDatabase {
     transitions: Vec<EdgeData>,
     has_start: bool,
     has_end: bool,
+    /// Transition weights keyed by internal (from, to) IDs
+    weights: HashMap<(String, String), f64>,
+    /// Fork, join and choice pseudostates by state ID
+    pseudostates: HashMap<String, Pseudostate>,
+    /// Concurrent regions of composite states by state ID, each region a
+    /// diagram of its own
+    regions: HashMap<String, Vec<StateDatabase>>,
+    /// Warnings recorded while parsing into this database
+    warnings: Vec<ParseWarning>,
 }
 
 impl StateDatabase {
+    /// Create an empty database
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Record a parse warning
+    pub(crate) fn add_parse_warning(&mut self, warning: ParseWarning) {
+        self.warnings.push(warning);
+    }
+
     /// Add a state
     pub fn add_state(&mut self, state: NodeData) -> Result<()> {
         // Don't add duplicates
@@ -33,6 +84,47 @@ impl StateDatabase {
         Ok(())
     }
 
+    /// Declare `id` as a fork, join or choice pseudostate
+    ///
+    /// Pseudostates have no label; a choice takes the diamond shape.
+    pub fn add_pseudostate(&mut self, id: &str, kind: Pseudostate) -> Result<()> {
+        let shape = match kind {
+            Pseudostate::Choice => NodeShape::Diamond,
+            Pseudostate::Fork | Pseudostate::Join => NodeShape::Rectangle,
+        };
+        let state = NodeData::with_shape(id, "", shape);
+        match self.states.iter_mut().find(|s| s.id == id) {
+            Some(existing) => *existing = state,
+            None => self.states.push(state),
+        }
+        self.pseudostates.insert(id.to_string(), kind);
+        Ok(())
+    }
+
+    /// The pseudostate kind of a state, if it was declared as one
+    pub fn pseudostate(&self, id: &str) -> Option<Pseudostate> {
+        self.pseudostates.get(id).copied()
+    }
+
+    /// Make `id` a composite state made of `regions`, which run
+    /// concurrently (`--` separates them in the syntax)
+    ///
+    /// Empty regions are dropped.
+    pub fn add_composite_state(&mut self, id: &str, regions: Vec<StateDatabase>) -> Result<()> {
+        self.ensure_state_internal(id)?;
+        let regions = regions.into_iter().filter(|r| r.state_count() > 0);
+        self.regions
+            .entry(id.to_string())
+            .or_default()
+            .extend(regions);
+        Ok(())
+    }
+
+    /// Regions of a composite state (empty for simple states)
+    pub fn regions(&self, id: &str) -> &[StateDatabase] {
+        self.regions.get(id).map_or(&[], Vec::as_slice)
+    }
+
     /// Ensure a state exists (creates implicit state if needed)
     fn ensure_state_internal(&mut self, id: &str) -> Result<()> {
         if !self.states.iter().any(|s| s.id == id) {
@@ -74,11 +166,49 @@ impl StateDatabase {
             edge_type: transition.edge_type,
             label: transition.label,
             style: transition.style.clone(),
+            min_length: transition.min_length,
+            start: transition.start,
         };
         self.transitions.push(modified);
         Ok(())
     }
 
+    /// Attach a weight (such as an observed frequency) to the transitions
+    /// from `from` to `to`
+    ///
+    /// `[*]` refers to the start terminal as source and the end terminal as
+    /// target. The renderer draws the heaviest transitions with thick lines.
+    pub fn set_transition_weight(&mut self, from: &str, to: &str, weight: f64) -> Result<()> {
+        if !weight.is_finite() || weight < 0.0 {
+            anyhow::bail!(
+                "Invalid weight {} for transition '{} --> {}'",
+                weight,
+                from,
+                to
+            );
+        }
+        let key = (source_id(from), target_id(to));
+        if !self
+            .transitions
+            .iter()
+            .any(|t| t.from == key.0 && t.to == key.1)
+        {
+            anyhow::bail!("Unknown transition '{} --> {}'", from, to);
+        }
+        self.weights.insert(key, weight);
+        Ok(())
+    }
+
+    /// Weight of the transitions from `from` to `to`, if one was set
+    pub fn transition_weight(&self, from: &str, to: &str) -> Option<f64> {
+        self.weights.get(&(source_id(from), target_id(to))).copied()
+    }
+
+    /// Largest transition weight in the diagram
+    pub fn max_transition_weight(&self) -> Option<f64> {
+        self.weights.values().copied().reduce(f64::max)
+    }
+
     /// Check if diagram has a start terminal
     pub fn has_start_terminal(&self) -> bool {
         self.has_start
@@ -118,6 +248,28 @@ impl StateDatabase {
     pub fn clear_all(&mut self) {
         self.states.clear();
         self.transitions.clear();
+        self.weights.clear();
+        self.pseudostates.clear();
+        self.regions.clear();
+        self.warnings.clear();
+    }
+}
+
+/// Internal ID of a transition source (`[*]` is the start terminal)
+fn source_id(id: &str) -> String {
+    if id == "[*]" {
+        START_TERMINAL.to_string()
+    } else {
+        id.to_string()
+    }
+}
+
+/// Internal ID of a transition target (`[*]` is the end terminal)
+fn target_id(id: &str) -> String {
+    if id == "[*]" {
+        END_TERMINAL.to_string()
+    } else {
+        id.to_string()
     }
 }
 
@@ -125,6 +277,14 @@ impl Database for StateDatabase {
     type Node = NodeData;
     type Edge = EdgeData;
 
+    fn parse_warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    fn take_parse_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
     fn add_node(&mut self, node: Self::Node) -> Result<()> {
         self.add_state(node)
     }
@@ -187,6 +347,15 @@ mod tests {
         assert_eq!(db.transition_count(), 1);
     }
 
+    #[test]
+    fn test_states_have_no_subgraphs() {
+        let mut db = StateDatabase::new();
+        db.add_transition(EdgeData::new("Idle", "Running")).unwrap();
+        assert_eq!(db.subgraph_count(), 0);
+        assert!(db.node_subgraph("Idle").is_none());
+        assert!(db.node_subgraphs("Idle").is_empty());
+    }
+
     #[test]
     fn test_start_terminal_converted() {
         let mut db = StateDatabase::new();
@@ -238,4 +407,56 @@ mod tests {
         let transition = &db.transitions()[0];
         assert_eq!(transition.label, Some("start".to_string()));
     }
+
+    #[test]
+    fn test_transition_weights() {
+        let mut db = StateDatabase::new();
+        db.add_transition(EdgeData::new("[*]", "Idle")).unwrap();
+        db.add_transition(EdgeData::new("Idle", "Busy")).unwrap();
+
+        db.set_transition_weight("[*]", "Idle", 3.0).unwrap();
+        db.set_transition_weight("Idle", "Busy", 120.0).unwrap();
+        assert_eq!(db.transition_weight(START_TERMINAL, "Idle"), Some(3.0));
+        assert_eq!(db.transition_weight("Idle", "Busy"), Some(120.0));
+        assert_eq!(db.max_transition_weight(), Some(120.0));
+
+        assert!(db.set_transition_weight("Busy", "Idle", 1.0).is_err());
+        assert!(db.set_transition_weight("Idle", "Busy", -1.0).is_err());
+        assert_eq!(db.transition_weight("Busy", "Idle"), None);
+    }
+
+    #[test]
+    fn test_pseudostates() {
+        let mut db = StateDatabase::new();
+        db.add_transition(EdgeData::new("[*]", "split")).unwrap();
+        db.add_pseudostate("split", Pseudostate::Fork).unwrap();
+        db.add_pseudostate("pick", Pseudostate::Choice).unwrap();
+
+        assert_eq!(db.state_count(), 3);
+        assert_eq!(db.pseudostate("split"), Some(Pseudostate::Fork));
+        assert_eq!(db.get_node("split").unwrap().label, "");
+        assert_eq!(db.get_node("pick").unwrap().shape, NodeShape::Diamond);
+        assert_eq!(db.pseudostate("[*]"), None);
+        assert_eq!(Pseudostate::from_keyword("join"), Some(Pseudostate::Join));
+        assert_eq!(Pseudostate::from_keyword("history"), None);
+    }
+
+    #[test]
+    fn test_composite_state_regions() {
+        let mut north = StateDatabase::new();
+        north.add_transition(EdgeData::new("[*]", "Red")).unwrap();
+        let mut south = StateDatabase::new();
+        south.add_transition(EdgeData::new("[*]", "Green")).unwrap();
+
+        let mut db = StateDatabase::new();
+        db.add_transition(EdgeData::new("[*]", "Lights")).unwrap();
+        db.add_composite_state("Lights", vec![north, StateDatabase::new(), south])
+            .unwrap();
+
+        assert_eq!(db.state_count(), 2);
+        let regions = db.regions("Lights");
+        assert_eq!(regions.len(), 2);
+        assert!(regions[1].get_node("Green").is_some());
+        assert!(db.regions("[*]_start").is_empty());
+    }
 }
@@ -2,12 +2,13 @@
 //!
 //! Identifies state diagram syntax from input text.
 
-use crate::core::Detector;
+use crate::core::{header_keyword, Detector};
 
 /// Detector for state diagram syntax
 pub struct StateDetector;
 
 impl StateDetector {
+    /// Create a detector
     pub fn new() -> Self {
         Self
     }
@@ -57,6 +58,11 @@ impl Detector for StateDetector {
         "state"
     }
 
+    fn prefix_confidence(&self, header: &str) -> Option<f64> {
+        (header_keyword(header, "stateDiagram") || header_keyword(header, "stateDiagram-v2"))
+            .then_some(1.0)
+    }
+
     fn patterns(&self) -> Vec<&'static str> {
         vec!["stateDiagram", "stateDiagram-v2", "[*]", "-->", "state "]
     }
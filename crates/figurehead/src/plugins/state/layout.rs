@@ -2,7 +2,7 @@
 //!
 //! Positions states and transitions for rendering.
 
-use super::database::{StateDatabase, START_TERMINAL};
+use super::database::{Pseudostate, StateDatabase, START_TERMINAL};
 use crate::core::{LayoutAlgorithm, NodeShape};
 use anyhow::Result;
 use std::collections::{HashMap, HashSet, VecDeque};
@@ -20,7 +20,30 @@ pub struct PositionedState {
     pub y: usize,
     pub width: usize,
     pub height: usize,
+    #[cfg_attr(not(feature = "unstable"), allow(dead_code))]
     pub rank: usize,
+    /// Set for fork, join and choice pseudostates
+    pub pseudostate: Option<Pseudostate>,
+    /// Concurrent regions of a composite state, laid out in lanes inside it
+    pub regions: Vec<StateLayoutResult>,
+    /// Columns of the dashed lines between the lanes
+    pub dividers: Vec<usize>,
+}
+
+impl PositionedState {
+    /// Column where transitions meet this state, coming from or going to
+    /// column `other_x`
+    ///
+    /// Fork and join bars are met right above or below the other state, so
+    /// their transitions run straight; other states are met at the center.
+    fn port_x(&self, other_x: usize) -> usize {
+        match self.pseudostate {
+            Some(kind) if kind.is_bar() && self.width > 2 => {
+                other_x.clamp(self.x + 1, self.x + self.width - 2)
+            }
+            _ => self.x + self.width / 2,
+        }
+    }
 }
 
 /// Positioned transition for rendering
@@ -29,6 +52,8 @@ pub struct PositionedTransition {
     pub from_id: String,
     pub to_id: String,
     pub label: Option<String>,
+    /// Weight attached with [`StateDatabase::set_transition_weight`]
+    pub weight: Option<f64>,
     pub from_x: usize,
     pub from_y: usize,
     pub to_x: usize,
@@ -44,6 +69,40 @@ pub struct StateLayoutResult {
     pub height: usize,
 }
 
+impl StateLayoutResult {
+    /// Move everything `dx` columns right and `dy` rows down (`width` and
+    /// `height` keep measuring from the original origin)
+    fn translate(&mut self, dx: usize, dy: usize) {
+        for state in &mut self.states {
+            state.x += dx;
+            state.y += dy;
+            for divider in &mut state.dividers {
+                *divider += dx;
+            }
+            for region in &mut state.regions {
+                region.translate(dx, dy);
+            }
+        }
+        for transition in &mut self.transitions {
+            transition.from_x += dx;
+            transition.to_x += dx;
+            transition.from_y += dy;
+            transition.to_y += dy;
+        }
+    }
+
+    /// Width including transition labels, which may reach past the states
+    fn content_width(&self) -> usize {
+        self.transitions
+            .iter()
+            .filter_map(|t| {
+                let label = t.label.as_deref()?;
+                Some(t.from_x.max(t.to_x) + 2 + label.chars().count())
+            })
+            .fold(self.width, usize::max)
+    }
+}
+
 /// State diagram layout algorithm
 pub struct StateLayoutAlgorithm {
     /// Minimum state box width
@@ -117,9 +176,18 @@ impl StateLayoutAlgorithm {
     }
 
     /// Calculate state dimensions
-    fn calculate_state_size(&self, label: &str, shape: NodeShape) -> (usize, usize) {
-        match shape {
-            NodeShape::Terminal => (self.terminal_size, self.terminal_size),
+    fn calculate_state_size(
+        &self,
+        label: &str,
+        shape: NodeShape,
+        pseudostate: Option<Pseudostate>,
+    ) -> (usize, usize) {
+        match (shape, pseudostate) {
+            // Widened later to reach the states on either side
+            (_, Some(Pseudostate::Fork | Pseudostate::Join)) => (self.min_state_width, 1),
+            (_, Some(Pseudostate::Choice)) | (NodeShape::Terminal, _) => {
+                (self.terminal_size, self.terminal_size)
+            }
             _ => {
                 let label_width = label.chars().count();
                 let width = (label_width + self.padding * 2).max(self.min_state_width);
@@ -128,6 +196,72 @@ impl StateLayoutAlgorithm {
         }
     }
 
+    /// Size of a composite state: its label on top, then its regions side
+    /// by side with a divider between each
+    fn composite_size(&self, label: &str, regions: &[StateLayoutResult]) -> (usize, usize) {
+        let lanes_width = self.lanes_width(regions);
+        let inner = lanes_width.max(label.chars().count());
+        let content_height = regions.iter().map(|r| r.height).max().unwrap_or(0);
+        // Border, label and a blank row above the lanes; blank and border below
+        (inner + self.padding * 2 + 2, content_height + 5)
+    }
+
+    /// Width of regions laid out side by side with dividers between them
+    fn lanes_width(&self, regions: &[StateLayoutResult]) -> usize {
+        let lanes: usize = regions.iter().map(StateLayoutResult::content_width).sum();
+        lanes + regions.len().saturating_sub(1) * (self.padding * 2 + 1)
+    }
+
+    /// Move the regions of a composite state into its box and place the
+    /// dividers between them
+    fn place_regions(&self, state: &mut PositionedState) {
+        if state.regions.is_empty() {
+            return;
+        }
+        let lanes_width = self.lanes_width(&state.regions);
+        let inner = state.width - self.padding * 2 - 2;
+        let mut x = state.x + 1 + self.padding + (inner - lanes_width) / 2;
+        let count = state.regions.len();
+        for (i, region) in state.regions.iter_mut().enumerate() {
+            let lane_width = region.content_width();
+            region.translate(x, state.y + 3);
+            x += lane_width;
+            if i + 1 < count {
+                state.dividers.push(x + self.padding);
+                x += self.padding * 2 + 1;
+            }
+        }
+    }
+
+    /// Stretch fork and join bars across the centers of the states they
+    /// connect, so each of their transitions can run straight
+    fn widen_bars(&self, db: &StateDatabase, states: &mut [PositionedState]) {
+        let centers: HashMap<String, usize> = states
+            .iter()
+            .map(|s| (s.id.clone(), s.x + s.width / 2))
+            .collect();
+        for state in states.iter_mut() {
+            if !state.pseudostate.is_some_and(Pseudostate::is_bar) {
+                continue;
+            }
+            let neighbors = db.transitions().iter().filter_map(|t| {
+                if t.from == state.id {
+                    centers.get(&t.to)
+                } else if t.to == state.id {
+                    centers.get(&t.from)
+                } else {
+                    None
+                }
+            });
+            let own = state.x + state.width / 2;
+            let (left, right) = neighbors.fold((own, own), |(l, r), &x| (l.min(x), r.max(x)));
+            let left = left.saturating_sub(1).min(state.x);
+            let right = (right + 1).max(state.x + state.width - 1);
+            state.x = left;
+            state.width = right - left + 1;
+        }
+    }
+
     /// Layout the database
     pub fn layout(&self, db: &StateDatabase) -> Result<StateLayoutResult> {
         if db.state_count() == 0 {
@@ -141,6 +275,19 @@ impl StateLayoutAlgorithm {
 
         let ranks = self.assign_ranks(db);
 
+        // Composite states are sized around their laid-out regions
+        let mut regions: HashMap<&str, Vec<StateLayoutResult>> = HashMap::new();
+        for state in db.states() {
+            let laid_out = db
+                .regions(&state.id)
+                .iter()
+                .map(|region| self.layout(region))
+                .collect::<Result<Vec<_>>>()?;
+            if !laid_out.is_empty() {
+                regions.insert(&state.id, laid_out);
+            }
+        }
+
         // Group states by rank
         let mut by_rank: HashMap<usize, Vec<&crate::core::NodeData>> = HashMap::new();
         for state in db.states() {
@@ -167,7 +314,14 @@ impl StateLayoutAlgorithm {
             let mut row_width = 0;
 
             for (i, state) in states_in_rank.iter().enumerate() {
-                let (w, h) = self.calculate_state_size(&state.label, state.shape);
+                let (w, h) = match regions.get(state.id.as_str()) {
+                    Some(regions) => self.composite_size(&state.label, regions),
+                    None => self.calculate_state_size(
+                        &state.label,
+                        state.shape,
+                        db.pseudostate(&state.id),
+                    ),
+                };
                 state_dims.push((w, h));
                 max_height = max_height.max(h);
                 row_width += w;
@@ -185,7 +339,6 @@ impl StateLayoutAlgorithm {
 
         // Second pass: position states with centers aligned
         let mut positioned_states: Vec<PositionedState> = Vec::new();
-        let mut state_positions: HashMap<String, (usize, usize, usize, usize)> = HashMap::new();
         let mut current_y = 0;
 
         for (rank, (ref state_dims, max_height, row_width)) in rank_info.iter().enumerate() {
@@ -203,7 +356,7 @@ impl StateLayoutAlgorithm {
                 let (w, h) = state_dims[i];
                 let y_offset = (max_height - h) / 2;
 
-                let pos_state = PositionedState {
+                let mut pos_state = PositionedState {
                     id: state.id.clone(),
                     label: state.label.clone(),
                     shape: state.shape,
@@ -212,9 +365,12 @@ impl StateLayoutAlgorithm {
                     width: w,
                     height: h,
                     rank,
+                    pseudostate: db.pseudostate(&state.id),
+                    regions: regions.remove(state.id.as_str()).unwrap_or_default(),
+                    dividers: Vec::new(),
                 };
+                self.place_regions(&mut pos_state);
 
-                state_positions.insert(state.id.clone(), (current_x, current_y + y_offset, w, h));
                 positioned_states.push(pos_state);
 
                 current_x += w + self.h_spacing;
@@ -223,28 +379,33 @@ impl StateLayoutAlgorithm {
             current_y += max_height + self.v_spacing;
         }
 
+        self.widen_bars(db, &mut positioned_states);
+
         // Position transitions
+        let index: HashMap<&str, &PositionedState> = positioned_states
+            .iter()
+            .map(|state| (state.id.as_str(), state))
+            .collect();
         let mut positioned_transitions: Vec<PositionedTransition> = Vec::new();
 
         for edge in db.transitions() {
-            if let (Some(&(fx, fy, fw, fh)), Some(&(tx, ty, tw, _th))) = (
-                state_positions.get(&edge.from),
-                state_positions.get(&edge.to),
-            ) {
-                // Connect from center-bottom of source to center-top of target
-                let from_x = fx + fw / 2;
-                let from_y = fy + fh;
-                let to_x = tx + tw / 2;
-                let to_y = ty;
+            if let (Some(from), Some(to)) =
+                (index.get(edge.from.as_str()), index.get(edge.to.as_str()))
+            {
+                // Connect from the bottom of the source to the top of the
+                // target, at their centers (or straight across a bar)
+                let from_x = from.port_x(to.x + to.width / 2);
+                let to_x = to.port_x(from.x + from.width / 2);
 
                 positioned_transitions.push(PositionedTransition {
                     from_id: edge.from.clone(),
                     to_id: edge.to.clone(),
                     label: edge.label.clone(),
+                    weight: db.transition_weight(&edge.from, &edge.to),
                     from_x,
-                    from_y,
+                    from_y: from.y + from.height,
                     to_x,
-                    to_y,
+                    to_y: to.y,
                 });
             }
         }
@@ -346,7 +507,7 @@ mod tests {
     #[test]
     fn test_terminal_state_size() {
         let algo = StateLayoutAlgorithm::new();
-        let (w, h) = algo.calculate_state_size("", NodeShape::Terminal);
+        let (w, h) = algo.calculate_state_size("", NodeShape::Terminal, None);
         assert_eq!(w, algo.terminal_size);
         assert_eq!(h, algo.terminal_size);
     }
@@ -378,4 +539,63 @@ mod tests {
         // It should NOT be at x=0 for a branching diagram
         assert!(start.x > 0, "Start terminal x={} should be > 0", start.x);
     }
+
+    #[test]
+    fn test_fork_bar_spans_its_branches() {
+        let mut db = StateDatabase::new();
+        db.add_pseudostate("fork", Pseudostate::Fork).unwrap();
+        db.add_pseudostate("join", Pseudostate::Join).unwrap();
+        db.add_transition(EdgeData::new("[*]", "fork")).unwrap();
+        for branch in ["Left", "Middle", "Right"] {
+            db.add_transition(EdgeData::new("fork", branch)).unwrap();
+            db.add_transition(EdgeData::new(branch, "join")).unwrap();
+        }
+
+        let result = StateLayoutAlgorithm::new().layout(&db).unwrap();
+        let state = |id: &str| result.states.iter().find(|s| s.id == id).unwrap();
+        let (fork, join) = (state("fork"), state("join"));
+        assert_eq!((fork.height, join.height), (1, 1));
+        assert!(fork.x < state("Left").x + state("Left").width / 2);
+        assert!(fork.x + fork.width > state("Right").x + state("Right").width / 2);
+
+        // Each branch leaves the fork and enters the join in its own column
+        for t in &result.transitions {
+            if t.from_id == "fork" || t.to_id == "join" {
+                assert_eq!(t.from_x, t.to_x, "{} --> {}", t.from_id, t.to_id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_composite_state_holds_its_regions_in_lanes() {
+        let mut left = StateDatabase::new();
+        left.add_transition(EdgeData::new("[*]", "Off")).unwrap();
+        left.add_transition(EdgeData::new("Off", "On")).unwrap();
+        let mut right = StateDatabase::new();
+        right
+            .add_transition(EdgeData::new("[*]", "Waiting"))
+            .unwrap();
+
+        let mut db = StateDatabase::new();
+        db.add_transition(EdgeData::new("[*]", "Active")).unwrap();
+        db.add_composite_state("Active", vec![left, right]).unwrap();
+
+        let result = StateLayoutAlgorithm::new().layout(&db).unwrap();
+        let active = result.states.iter().find(|s| s.id == "Active").unwrap();
+        assert_eq!(active.regions.len(), 2);
+        assert_eq!(active.dividers.len(), 1);
+
+        let inside = |s: &PositionedState| {
+            active.x < s.x
+                && s.x + s.width < active.x + active.width
+                && active.y + 1 < s.y
+                && s.y + s.height < active.y + active.height
+        };
+        let (lane0, lane1) = (&active.regions[0], &active.regions[1]);
+        assert!(lane0.states.iter().chain(&lane1.states).all(inside));
+        let divider = active.dividers[0];
+        assert!(lane0.states.iter().all(|s| s.x + s.width < divider));
+        assert!(lane1.states.iter().all(|s| divider < s.x));
+        assert_eq!(result.height, active.y + active.height);
+    }
 }
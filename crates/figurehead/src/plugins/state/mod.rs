@@ -10,16 +10,22 @@
 //!     Processing --> Done : complete
 //!     Done --> [*]
 //! ```
+//!
+//! `state id <<fork>>` and `<<join>>` declare pseudostates drawn as thick
+//! bars; `<<choice>>` declares one drawn as a small diamond.
+//!
+//! `state Id { ... }` declares a composite state; `--` lines inside it split
+//! it into concurrent regions, drawn side by side in lanes of its box.
 
 mod database;
 mod detector;
-mod layout;
+#[allow(missing_docs)]
+pub(crate) mod layout;
 mod parser;
 mod renderer;
 
-pub use database::{StateDatabase, END_TERMINAL, START_TERMINAL};
+pub use database::{Pseudostate, StateDatabase, END_TERMINAL, START_TERMINAL};
 pub use detector::StateDetector;
-pub use layout::{StateLayoutAlgorithm, StateLayoutResult};
 pub use parser::StateParser;
 pub use renderer::StateRenderer;
 
@@ -2,16 +2,28 @@
 //!
 //! Parses state diagram syntax into the database.
 
-use super::database::StateDatabase;
-use crate::core::{EdgeData, EdgeType, NodeData, NodeShape, Parser as CoreParser};
+use super::database::{Pseudostate, StateDatabase};
+use crate::core::{
+    normalize_input, EdgeData, EdgeType, LenientStatements, NodeData, NodeShape,
+    Parser as CoreParser, SpannedStatement, StatementSplitter,
+};
 use anyhow::Result;
 use chumsky::prelude::*;
+use tracing::warn;
 
 /// Parsed state diagram statement
 #[derive(Debug, Clone, PartialEq)]
 pub enum Statement {
     /// State declaration: `state "description" as id`
     StateDecl { id: String, label: String },
+    /// Pseudostate declaration: `state id <<fork>>`
+    PseudostateDecl { id: String, kind: Pseudostate },
+    /// Start of a composite state: `state id {`
+    CompositeStart { id: String },
+    /// Separator between concurrent regions of a composite state: `--`
+    RegionSeparator,
+    /// End of a composite state: `}`
+    CompositeEnd,
     /// Transition: `from --> to` or `from --> to : label`
     Transition {
         from: String,
@@ -24,6 +36,7 @@ pub enum Statement {
 pub struct StateParser;
 
 impl StateParser {
+    /// Create a parser
     pub fn new() -> Self {
         Self
     }
@@ -107,11 +120,68 @@ impl StateParser {
             .map(|(label, id)| Statement::StateDecl { id, label })
     }
 
+    /// Parse a pseudostate declaration: `state id <<fork>>`, `<<join>>` or
+    /// `<<choice>>`
+    fn pseudostate_decl_parser<'src>(
+    ) -> impl chumsky::Parser<'src, &'src str, Statement, extra::Err<Rich<'src, char>>> + Clone
+    {
+        let ws = any()
+            .filter(|c: &char| c.is_whitespace())
+            .repeated()
+            .at_least(1)
+            .collect::<String>();
+
+        let kind = Self::identifier()
+            .delimited_by(just("<<"), just(">>"))
+            .try_map(|keyword, span| {
+                Pseudostate::from_keyword(&keyword).ok_or_else(|| {
+                    Rich::custom(span, format!("unknown pseudostate '<<{}>>'", keyword))
+                })
+            });
+
+        just("state")
+            .ignore_then(ws)
+            .ignore_then(Self::identifier())
+            .then_ignore(ws)
+            .then(kind)
+            .map(|(id, kind)| Statement::PseudostateDecl { id, kind })
+    }
+
+    /// Parse the lines delimiting composite states and their regions:
+    /// `state id {`, `--` and `}`
+    fn composite_parser<'src>(
+    ) -> impl chumsky::Parser<'src, &'src str, Statement, extra::Err<Rich<'src, char>>> + Clone
+    {
+        let ws = any()
+            .filter(|c: &char| c.is_whitespace())
+            .repeated()
+            .collect::<String>();
+        let ws1 = any()
+            .filter(|c: &char| c.is_whitespace())
+            .repeated()
+            .at_least(1)
+            .collect::<String>();
+
+        let start = just("state")
+            .ignore_then(ws1)
+            .ignore_then(Self::identifier())
+            .then_ignore(ws)
+            .then_ignore(just('{'))
+            .map(|id| Statement::CompositeStart { id });
+
+        start
+            .or(just("--").to(Statement::RegionSeparator))
+            .or(just('}').to(Statement::CompositeEnd))
+    }
+
     /// Parse a single statement
     fn statement_parser<'src>(
     ) -> impl chumsky::Parser<'src, &'src str, Statement, extra::Err<Rich<'src, char>>> + Clone
     {
-        Self::state_decl_parser().or(Self::transition_parser())
+        Self::state_decl_parser()
+            .or(Self::pseudostate_decl_parser())
+            .or(Self::composite_parser())
+            .or(Self::transition_parser())
     }
 
     /// Parse a statement from input
@@ -134,11 +204,6 @@ impl StateParser {
         let trimmed = line.trim().to_lowercase();
         trimmed.starts_with("statediagram")
     }
-
-    /// Check if a line is a comment
-    fn is_comment(&self, line: &str) -> bool {
-        line.trim().starts_with("%%")
-    }
 }
 
 impl Default for StateParser {
@@ -149,33 +214,74 @@ impl Default for StateParser {
 
 impl CoreParser<StateDatabase> for StateParser {
     fn parse(&self, input: &str, database: &mut StateDatabase) -> Result<()> {
-        for line in input.lines() {
-            let trimmed = line.trim();
-
-            // Skip empty lines, comments, and header
-            if trimmed.is_empty() || self.is_comment(trimmed) || self.is_header_line(trimmed) {
+        let normalized = normalize_input(input);
+        let input = normalized.as_ref();
+        // One statement per line; `;` may appear in transition labels
+        let splitter = StatementSplitter::new().with_semicolons(false);
+        let mut lenient = LenientStatements::new("state");
+        // Composite states being read, innermost last, with the statement
+        // opening each and the regions read so far
+        let mut open: Vec<(SpannedStatement, String, Vec<StateDatabase>)> = Vec::new();
+        for statement in splitter.split(input) {
+            if self.is_header_line(&statement.text) {
                 continue;
             }
 
-            // Try to parse the line
-            match self.parse_statement(trimmed) {
-                Ok(Statement::StateDecl { id, label }) => {
-                    database.add_state(NodeData::with_shape(&id, &label, NodeShape::Rectangle))?;
+            let parsed = lenient.parse(&statement, |text| self.parse_statement(text));
+            let target = match open.last_mut() {
+                Some((_, _, regions)) => regions.last_mut().unwrap(),
+                None => &mut *database,
+            };
+            match parsed {
+                Some(Statement::StateDecl { id, label }) => {
+                    target.add_state(NodeData::with_shape(&id, &label, NodeShape::Rectangle))?;
                 }
-                Ok(Statement::Transition { from, to, label }) => {
+                Some(Statement::PseudostateDecl { id, kind }) => {
+                    target.add_pseudostate(&id, kind)?;
+                }
+                Some(Statement::Transition { from, to, label }) => {
                     let edge = match label {
                         Some(lbl) => EdgeData::with_label(&from, &to, EdgeType::Arrow, lbl),
                         None => EdgeData::new(&from, &to),
                     };
-                    database.add_transition(edge)?;
+                    target.add_transition(edge)?;
                 }
-                Err(_) => {
-                    // Skip unparseable lines for now
-                    continue;
+                Some(Statement::CompositeStart { id }) => {
+                    open.push((statement, id, vec![StateDatabase::new()]));
                 }
+                Some(Statement::RegionSeparator) => match open.last_mut() {
+                    Some((_, _, regions)) => regions.push(StateDatabase::new()),
+                    None => lenient.warn(&statement, "'--' outside of a composite state"),
+                },
+                Some(Statement::CompositeEnd) => match open.pop() {
+                    Some((_, id, regions)) => {
+                        let parent = match open.last_mut() {
+                            Some((_, _, regions)) => regions.last_mut().unwrap(),
+                            None => &mut *database,
+                        };
+                        parent.add_composite_state(&id, regions)?;
+                    }
+                    None => lenient.warn(&statement, "'}' without an open composite state"),
+                },
+                None => {}
             }
         }
+        while let Some((statement, id, regions)) = open.pop() {
+            lenient.warn(
+                &statement,
+                format!("Unclosed composite state '{}' ended with the diagram", id),
+            );
+            let parent = match open.last_mut() {
+                Some((_, _, regions)) => regions.last_mut().unwrap(),
+                None => &mut *database,
+            };
+            parent.add_composite_state(&id, regions)?;
+        }
+        for warning in lenient.finish()? {
+            database.add_parse_warning(warning);
+        }
 
+        apply_fh_directives(input, database);
         Ok(())
     }
 
@@ -193,9 +299,46 @@ impl CoreParser<StateDatabase> for StateParser {
     }
 }
 
+/// Apply figurehead-specific `%%fh: ...%%` directives
+///
+/// Mermaid treats these lines as comments. Supported:
+/// `%%fh: weight A-->B 42%%` attaches a weight to the transitions from `A`
+/// to `B` (see [`StateDatabase::set_transition_weight`]).
+fn apply_fh_directives(input: &str, database: &mut StateDatabase) {
+    for line in input.lines() {
+        let Some(body) = line.trim().strip_prefix("%%fh:") else {
+            continue;
+        };
+        let body = body.trim().trim_end_matches("%%").trim();
+        let (command, args) = body.split_once(char::is_whitespace).unwrap_or((body, ""));
+
+        match command {
+            "weight" => {
+                if let Err(error) = apply_weight(args.trim(), database) {
+                    warn!(directive = %line.trim(), %error, "Ignoring weight directive");
+                }
+            }
+            _ => warn!(%command, "Unknown figurehead directive"),
+        }
+    }
+}
+
+/// Apply `A-->B 42` from a weight directive
+fn apply_weight(args: &str, database: &mut StateDatabase) -> Result<()> {
+    let (transition, weight) = args
+        .rsplit_once(char::is_whitespace)
+        .ok_or_else(|| anyhow::anyhow!("Expected 'from-->to weight'"))?;
+    let (from, to) = transition
+        .split_once("-->")
+        .ok_or_else(|| anyhow::anyhow!("Expected 'from-->to weight'"))?;
+    let weight: f64 = weight.parse()?;
+    database.set_transition_weight(from.trim(), to.trim(), weight)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::Database;
 
     #[test]
     fn test_parse_simple_transition() {
@@ -254,6 +397,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_pseudostate_declarations() {
+        let parser = StateParser::new();
+        assert_eq!(
+            parser.parse_statement("state fork_state <<fork>>").unwrap(),
+            Statement::PseudostateDecl {
+                id: "fork_state".to_string(),
+                kind: Pseudostate::Fork,
+            }
+        );
+        assert!(parser.parse_statement("state h <<history>>").is_err());
+
+        let mut db = StateDatabase::new();
+        let input = r#"
+stateDiagram-v2
+    state if_state <<choice>>
+    [*] --> if_state
+    if_state --> Small : n < 10
+    if_state --> Large : n >= 10
+"#;
+        parser.parse(input, &mut db).unwrap();
+        assert_eq!(db.pseudostate("if_state"), Some(Pseudostate::Choice));
+        assert_eq!(db.transition_count(), 3);
+    }
+
+    #[test]
+    fn test_parse_concurrent_regions() {
+        let parser = StateParser::new();
+        let mut db = StateDatabase::new();
+        let input = r#"
+stateDiagram-v2
+    [*] --> Active
+    state Active {
+        [*] --> NumLockOff
+        NumLockOff --> NumLockOn
+        --
+        [*] --> CapsLockOff
+        state CapsLockOff {
+            [*] --> Idle
+        }
+    }
+    Active --> [*]
+    --
+    }
+"#;
+        parser.parse(input, &mut db).unwrap();
+
+        assert_eq!(db.state_count(), 3); // [*]_start, Active, [*]_end
+        let regions = db.regions("Active");
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].transition_count(), 2);
+        assert_eq!(regions[1].regions("CapsLockOff").len(), 1);
+
+        let warnings: Vec<_> = db.parse_warnings().iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "14:5: '--' outside of a composite state",
+                "15:5: '}' without an open composite state",
+            ]
+        );
+
+        let mut db = StateDatabase::new();
+        parser
+            .parse("stateDiagram-v2\n    state Open {\n    A --> B", &mut db)
+            .unwrap();
+        assert_eq!(db.regions("Open").len(), 1);
+        assert_eq!(
+            db.parse_warnings()[0].message,
+            "Unclosed composite state 'Open' ended with the diagram"
+        );
+    }
+
     #[test]
     fn test_parse_full_diagram() {
         let parser = StateParser::new();
@@ -288,6 +504,28 @@ stateDiagram-v2
         assert_eq!(db.transition_count(), 1);
     }
 
+    #[test]
+    fn test_weight_directive() {
+        let parser = StateParser::new();
+        let mut db = StateDatabase::new();
+
+        let input = r#"
+stateDiagram-v2
+    %%fh: weight Idle-->Busy 120%%
+    %%fh: weight [*] --> Idle 3%%
+    %%fh: weight Busy-->Idle lots%%
+    [*] --> Idle
+    Idle --> Busy
+    Busy --> Idle
+"#;
+
+        parser.parse(input, &mut db).unwrap();
+        assert_eq!(db.transition_weight("Idle", "Busy"), Some(120.0));
+        assert_eq!(db.transition_weight("[*]", "Idle"), Some(3.0));
+        assert_eq!(db.transition_weight("Busy", "Idle"), None);
+        assert_eq!(db.transition_count(), 3);
+    }
+
     #[test]
     fn test_can_parse() {
         let parser = StateParser::new();
@@ -295,4 +533,30 @@ stateDiagram-v2
         assert!(parser.can_parse("[*] --> Idle"));
         assert!(!parser.can_parse("graph TD\nA --> B"));
     }
+
+    #[test]
+    fn test_unreadable_statement_is_skipped_with_warning() {
+        let parser = StateParser::new();
+        let mut db = StateDatabase::new();
+
+        parser
+            .parse(
+                "stateDiagram-v2\n    [*] --> Idle\n    Idle -> Busy",
+                &mut db,
+            )
+            .unwrap();
+        assert_eq!(db.transition_count(), 1);
+        let warnings = db.take_parse_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0]
+            .to_string()
+            .starts_with("3:5: Skipped invalid statement 'Idle -> Busy'"));
+
+        assert!(parser
+            .parse(
+                "stateDiagram-v2\n    Idle -> Busy",
+                &mut StateDatabase::new()
+            )
+            .is_err());
+    }
 }
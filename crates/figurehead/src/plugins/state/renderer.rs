@@ -2,9 +2,15 @@
 //!
 //! Renders state diagrams as ASCII art.
 
-use super::database::{StateDatabase, START_TERMINAL};
-use super::layout::{PositionedTransition, StateLayoutAlgorithm, StateLayoutResult};
-use crate::core::{AsciiCanvas, CharacterSet, NodeShape, Renderer};
+use super::database::{Pseudostate, StateDatabase, START_TERMINAL};
+use super::layout::{
+    PositionedState, PositionedTransition, StateLayoutAlgorithm, StateLayoutResult,
+};
+use crate::core::{
+    AsciiCanvas, CellGrid, CharacterSet, Direction, EdgeTerminator, NodeShape, Renderer, Role,
+    RoleRecorder,
+};
+use crate::plugins::observer::LayoutProbe;
 use anyhow::Result;
 use std::collections::HashMap;
 
@@ -42,26 +48,87 @@ impl BoxChars {
     }
 }
 
+/// Edge label text and its top-left cell, drawn after all edges
+type PendingLabel<'a> = (usize, usize, &'a str);
+
+/// Transitions weighing at least this share of the heaviest transition are
+/// drawn with thick lines
+const HEAVY_SHARE: f64 = 0.5;
+
+/// Corner of a turn drawn entirely with thick lines
+fn heavy_corner(corner: char) -> char {
+    match corner {
+        '┌' => '╔',
+        '┐' => '╗',
+        '└' => '╚',
+        '┘' => '╝',
+        other => other,
+    }
+}
+
+/// Junction where a thick vertical line meets a thin horizontal bar
+fn heavy_stem(junction: char) -> char {
+    match junction {
+        '┌' => '╓',
+        '┐' => '╖',
+        '└' => '╙',
+        '┘' => '╜',
+        '┬' => '╥',
+        '┴' => '╨',
+        other => other,
+    }
+}
+
 /// State diagram renderer
 pub struct StateRenderer {
     style: CharacterSet,
+    color: bool,
 }
 
 impl StateRenderer {
+    /// Create a renderer with the default character set
     pub fn new() -> Self {
         Self {
             style: CharacterSet::default(),
+            color: false,
         }
     }
 
+    /// Create a renderer with the given character set
     pub fn with_style(style: CharacterSet) -> Self {
-        Self { style }
+        Self {
+            style,
+            color: false,
+        }
+    }
+
+    /// Color states, transitions and transition labels with ANSI codes
+    pub fn with_color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
     }
 
     fn is_unicode(&self) -> bool {
         !self.style.is_ascii()
     }
 
+    /// Head drawn where a transition enters its target state from above
+    fn arrow_down(&self) -> char {
+        EdgeTerminator::Arrow
+            .glyph(Direction::TopDown, self.style)
+            .unwrap_or('v')
+    }
+
+    /// Horizontal and vertical line characters, thick for heavy transitions
+    fn line_chars(&self, heavy: bool) -> (char, char) {
+        match (self.is_unicode(), heavy) {
+            (true, true) => ('═', '║'),
+            (true, false) => ('─', '│'),
+            (false, true) => ('=', '|'),
+            (false, false) => ('-', '|'),
+        }
+    }
+
     fn box_chars(&self) -> BoxChars {
         if self.is_unicode() {
             BoxChars::unicode()
@@ -94,6 +161,20 @@ impl StateRenderer {
         }
     }
 
+    /// Draw a fork or join bar
+    fn draw_bar(&self, canvas: &mut AsciiCanvas, x: usize, y: usize, width: usize) {
+        let bar = if self.is_unicode() { '━' } else { '=' };
+        for i in 0..width {
+            canvas.set_char(x + i, y, bar);
+        }
+    }
+
+    /// Draw a choice pseudostate as a small diamond
+    fn draw_choice(&self, canvas: &mut AsciiCanvas, x: usize, y: usize, width: usize) {
+        let diamond = if self.is_unicode() { "◇" } else { "< >" };
+        canvas.draw_text_centered(x + width / 2, y + 1, diamond);
+    }
+
     /// Draw a state box
     fn draw_state_box(
         &self,
@@ -133,22 +214,25 @@ impl StateRenderer {
     }
 
     /// Draw a single edge between two points with optional label
-    fn draw_single_edge(
+    #[allow(clippy::too_many_arguments)]
+    fn draw_single_edge<'a>(
         &self,
         canvas: &mut AsciiCanvas,
         from_x: usize,
         from_y: usize,
         to_x: usize,
         to_y: usize,
-        label: Option<&str>,
+        label: Option<&'a str>,
+        heavy: bool,
+        labels: &mut Vec<PendingLabel<'a>>,
     ) {
         if from_y >= to_y {
             return;
         }
 
-        let arrow_down = if self.is_unicode() { '▼' } else { 'v' };
-        let v_line = if self.is_unicode() { '│' } else { '|' };
-        let h_line = if self.is_unicode() { '─' } else { '-' };
+        let arrow_down = self.arrow_down();
+        let (h_line, v_line) = self.line_chars(heavy);
+        let corner = |c: char| if heavy { heavy_corner(c) } else { c };
 
         if from_x == to_x {
             // Straight vertical line
@@ -161,7 +245,7 @@ impl StateRenderer {
             if let Some(lbl) = label {
                 if !lbl.is_empty() {
                     let label_y = from_y + (to_y - from_y) / 2;
-                    canvas.draw_text(from_x + 2, label_y, lbl);
+                    labels.push((from_x + 2, label_y, lbl));
                 }
             }
         } else {
@@ -183,7 +267,7 @@ impl StateRenderer {
             } else {
                 '+'
             };
-            canvas.set_char(from_x, mid_y, corner1);
+            canvas.set_char(from_x, mid_y, corner(corner1));
 
             // Horizontal segment
             let (h_start, h_end) = if to_x > from_x {
@@ -205,7 +289,7 @@ impl StateRenderer {
             } else {
                 '+'
             };
-            canvas.set_char(to_x, mid_y, corner2);
+            canvas.set_char(to_x, mid_y, corner(corner2));
 
             // Second vertical segment
             for y in (mid_y + 1)..to_y {
@@ -218,39 +302,56 @@ impl StateRenderer {
                 if !lbl.is_empty() {
                     let label_x = (from_x + to_x) / 2;
                     let label_start = label_x.saturating_sub(lbl.chars().count() / 2);
-                    canvas.draw_text(label_start, mid_y.saturating_sub(1), lbl);
+                    labels.push((label_start, mid_y.saturating_sub(1), lbl));
                 }
             }
         }
     }
 
     /// Draw split edges (one source to multiple targets)
-    fn draw_split_edges(
+    ///
+    /// The shared trunk is thick when any branch is heavy; the bar across
+    /// the branches stays thin.
+    fn draw_split_edges<'a>(
         &self,
         canvas: &mut AsciiCanvas,
         from_x: usize,
         from_y: usize,
-        targets: &[(usize, usize, Option<&str>)], // (to_x, to_y, label)
+        targets: &[(usize, usize, Option<&'a str>, bool)], // (to_x, to_y, label, heavy)
+        labels: &mut Vec<PendingLabel<'a>>,
     ) {
         if targets.is_empty() {
             return;
         }
 
-        let arrow_down = if self.is_unicode() { '▼' } else { 'v' };
-        let v_line = if self.is_unicode() { '│' } else { '|' };
-        let h_line = if self.is_unicode() { '─' } else { '-' };
+        let arrow_down = self.arrow_down();
+        let (h_line, _) = self.line_chars(false);
+        let trunk_heavy = targets.iter().any(|(_, _, _, heavy)| *heavy);
 
         // Find the min to_y to determine junction row
-        let min_to_y = targets.iter().map(|(_, y, _)| *y).min().unwrap_or(from_y);
+        let min_to_y = targets
+            .iter()
+            .map(|(_, y, _, _)| *y)
+            .min()
+            .unwrap_or(from_y);
         let junction_y = from_y + (min_to_y - from_y) / 2;
 
         // Find the span of target x positions
-        let min_x = targets.iter().map(|(x, _, _)| *x).min().unwrap_or(from_x);
-        let max_x = targets.iter().map(|(x, _, _)| *x).max().unwrap_or(from_x);
+        let min_x = targets
+            .iter()
+            .map(|(x, _, _, _)| *x)
+            .min()
+            .unwrap_or(from_x);
+        let max_x = targets
+            .iter()
+            .map(|(x, _, _, _)| *x)
+            .max()
+            .unwrap_or(from_x);
 
         // Draw vertical line from source down to junction
+        let (_, trunk_line) = self.line_chars(trunk_heavy);
         for y in from_y..junction_y {
-            canvas.set_char(from_x, y, v_line);
+            canvas.set_char(from_x, y, trunk_line);
         }
 
         // Draw horizontal bar across all targets
@@ -271,10 +372,16 @@ impl StateRenderer {
         } else {
             '+'
         };
+        let junction_char = if trunk_heavy {
+            heavy_stem(junction_char)
+        } else {
+            junction_char
+        };
         canvas.set_char(from_x, junction_y, junction_char);
 
         // Draw corners and vertical lines to each target
-        for (to_x, to_y, label) in targets {
+        for (to_x, to_y, label, heavy) in targets {
+            let (_, v_line) = self.line_chars(*heavy);
             // Corner at target x on junction row
             let corner = if self.is_unicode() {
                 if *to_x == min_x {
@@ -287,6 +394,7 @@ impl StateRenderer {
             } else {
                 '+'
             };
+            let corner = if *heavy { heavy_stem(corner) } else { corner };
             canvas.set_char(*to_x, junction_y, corner);
 
             // Vertical line from junction to target
@@ -299,17 +407,19 @@ impl StateRenderer {
             if let Some(lbl) = label {
                 if !lbl.is_empty() {
                     let label_start = to_x.saturating_sub(lbl.chars().count() / 2);
-                    canvas.draw_text(label_start, junction_y.saturating_sub(1), lbl);
+                    labels.push((label_start, junction_y.saturating_sub(1), lbl));
                 }
             }
         }
     }
 
     /// Draw merge edges (multiple sources to one target)
+    ///
+    /// The shared stem into the target is thick when any source is heavy.
     fn draw_merge_edges(
         &self,
         canvas: &mut AsciiCanvas,
-        sources: &[(usize, usize)], // (from_x, from_y)
+        sources: &[(usize, usize, bool)], // (from_x, from_y, heavy)
         to_x: usize,
         to_y: usize,
     ) {
@@ -317,20 +427,21 @@ impl StateRenderer {
             return;
         }
 
-        let arrow_down = if self.is_unicode() { '▼' } else { 'v' };
-        let v_line = if self.is_unicode() { '│' } else { '|' };
-        let h_line = if self.is_unicode() { '─' } else { '-' };
+        let arrow_down = self.arrow_down();
+        let (h_line, _) = self.line_chars(false);
+        let stem_heavy = sources.iter().any(|(_, _, heavy)| *heavy);
 
         // Find the max from_y to determine junction row
-        let max_from_y = sources.iter().map(|(_, y)| *y).max().unwrap_or(0);
+        let max_from_y = sources.iter().map(|(_, y, _)| *y).max().unwrap_or(0);
         let junction_y = max_from_y + (to_y - max_from_y) / 2;
 
         // Find the span of source x positions
-        let min_x = sources.iter().map(|(x, _)| *x).min().unwrap_or(to_x);
-        let max_x = sources.iter().map(|(x, _)| *x).max().unwrap_or(to_x);
+        let min_x = sources.iter().map(|(x, _, _)| *x).min().unwrap_or(to_x);
+        let max_x = sources.iter().map(|(x, _, _)| *x).max().unwrap_or(to_x);
 
         // Draw vertical lines from each source to junction row
-        for (from_x, from_y) in sources {
+        for (from_x, from_y, heavy) in sources {
+            let (_, v_line) = self.line_chars(*heavy);
             for y in *from_y..junction_y {
                 canvas.set_char(*from_x, y, v_line);
             }
@@ -346,6 +457,7 @@ impl StateRenderer {
             } else {
                 '+'
             };
+            let corner = if *heavy { heavy_stem(corner) } else { corner };
             canvas.set_char(*from_x, junction_y, corner);
         }
 
@@ -375,67 +487,116 @@ impl StateRenderer {
         } else {
             '+'
         };
+        let junction_char = if stem_heavy {
+            heavy_stem(junction_char)
+        } else {
+            junction_char
+        };
         canvas.set_char(to_x, junction_y, junction_char);
 
         // Draw vertical line from junction to target
+        let (_, stem_line) = self.line_chars(stem_heavy);
         for y in (junction_y + 1)..to_y {
-            canvas.set_char(to_x, y, v_line);
+            canvas.set_char(to_x, y, stem_line);
         }
         canvas.set_char(to_x, to_y, arrow_down);
     }
 
-    /// Render the layout result
-    fn render_layout(&self, layout: &StateLayoutResult) -> String {
-        if layout.states.is_empty() {
-            return String::new();
+    /// A layout followed by the layouts of every region nested in it
+    fn nested_layouts(layout: &StateLayoutResult) -> Vec<&StateLayoutResult> {
+        let mut layouts = vec![layout];
+        let mut i = 0;
+        while i < layouts.len() {
+            let current = layouts[i];
+            layouts.extend(current.states.iter().flat_map(|s| &s.regions));
+            i += 1;
         }
+        layouts
+    }
 
-        // Calculate canvas size with extra space for arrows
-        let extra_height = layout.transitions.len() * 2;
-        let width = layout.width + 20; // Extra space for labels
-        let height = layout.height + extra_height + 2;
-
-        let mut canvas = AsciiCanvas::new(width, height);
+    /// Draw one state in its shape
+    fn draw_state(&self, canvas: &mut AsciiCanvas, state: &PositionedState) {
+        if !state.regions.is_empty() {
+            self.draw_composite(canvas, state);
+            return;
+        }
+        match (state.pseudostate, state.shape) {
+            (Some(Pseudostate::Fork | Pseudostate::Join), _) => {
+                self.draw_bar(canvas, state.x, state.y, state.width);
+            }
+            (Some(Pseudostate::Choice), _) => {
+                self.draw_choice(canvas, state.x, state.y, state.width);
+            }
+            (None, NodeShape::Terminal) => {
+                // Use ID to determine if start or end terminal
+                let is_start = state.id == START_TERMINAL;
+                self.draw_terminal(canvas, state.x, state.y, state.width, is_start);
+            }
+            _ => {
+                self.draw_state_box(
+                    canvas,
+                    state.x,
+                    state.y,
+                    state.width,
+                    state.height,
+                    &state.label,
+                );
+            }
+        }
+    }
 
-        // Draw states
-        for state in &layout.states {
-            match state.shape {
-                NodeShape::Terminal => {
-                    // Use ID to determine if start or end terminal
-                    let is_start = state.id == START_TERMINAL;
-                    self.draw_terminal(&mut canvas, state.x, state.y, state.width, is_start);
-                }
-                _ => {
-                    self.draw_state_box(
-                        &mut canvas,
-                        state.x,
-                        state.y,
-                        state.width,
-                        state.height,
-                        &state.label,
-                    );
-                }
+    /// Draw a composite state: its box with the label on top, and dashed
+    /// dividers between its regions
+    fn draw_composite(&self, canvas: &mut AsciiCanvas, state: &PositionedState) {
+        self.draw_state_box(canvas, state.x, state.y, state.width, state.height, "");
+        canvas.draw_text_centered(state.x + state.width / 2, state.y + 1, &state.label);
+        let divider = if self.is_unicode() { '┆' } else { ':' };
+        for &x in &state.dividers {
+            for y in state.y + 2..state.y + state.height - 1 {
+                canvas.set_char(x, y, divider);
             }
         }
+    }
 
-        // Group transitions by source for split detection
-        let mut by_source: HashMap<String, Vec<&PositionedTransition>> = HashMap::new();
+    /// Draw the transitions of one layout, queueing their labels
+    fn draw_transitions<'a>(
+        &self,
+        canvas: &mut AsciiCanvas,
+        layout: &'a StateLayoutResult,
+        labels: &mut Vec<PendingLabel<'a>>,
+    ) {
+        // Group transitions leaving a source at the same column for split
+        // detection (a fork bar sends each one off from its own column)
+        let mut by_source: HashMap<(&str, usize), Vec<&PositionedTransition>> = HashMap::new();
         for trans in &layout.transitions {
             by_source
-                .entry(trans.from_id.clone())
+                .entry((&trans.from_id, trans.from_x))
                 .or_default()
                 .push(trans);
         }
 
-        // Group transitions by target for merge detection
-        let mut by_target: HashMap<String, Vec<&PositionedTransition>> = HashMap::new();
+        // Group transitions entering a target at the same column for merge
+        // detection
+        let mut by_target: HashMap<(&str, usize), Vec<&PositionedTransition>> = HashMap::new();
         for trans in &layout.transitions {
             by_target
-                .entry(trans.to_id.clone())
+                .entry((&trans.to_id, trans.to_x))
                 .or_default()
                 .push(trans);
         }
 
+        // Heavy transitions (by attached weight) are drawn thick
+        let max_weight = layout
+            .transitions
+            .iter()
+            .filter_map(|t| t.weight)
+            .reduce(f64::max)
+            .filter(|max| *max > 0.0);
+        let is_heavy = |t: &PositionedTransition| match (t.weight, max_weight) {
+            (Some(weight), Some(max)) => weight >= max * HEAVY_SHARE,
+            _ => false,
+        };
+
         // Track which transitions we've already drawn
         let mut drawn: std::collections::HashSet<(&str, &str)> = std::collections::HashSet::new();
 
@@ -443,11 +604,18 @@ impl StateRenderer {
         for transitions in by_source.values() {
             if transitions.len() > 1 {
                 let first = transitions[0];
-                let targets: Vec<(usize, usize, Option<&str>)> = transitions
+                let targets: Vec<(usize, usize, Option<&str>, bool)> = transitions
                     .iter()
-                    .map(|t| (t.to_x, t.to_y.saturating_sub(1), t.label.as_deref()))
+                    .map(|t| {
+                        (
+                            t.to_x,
+                            t.to_y.saturating_sub(1),
+                            t.label.as_deref(),
+                            is_heavy(t),
+                        )
+                    })
                     .collect();
-                self.draw_split_edges(&mut canvas, first.from_x, first.from_y, &targets);
+                self.draw_split_edges(canvas, first.from_x, first.from_y, &targets, labels);
                 for t in transitions {
                     drawn.insert((&t.from_id, &t.to_id));
                 }
@@ -464,10 +632,12 @@ impl StateRenderer {
                     .collect();
                 if undrawn.len() > 1 {
                     let first = undrawn[0];
-                    let sources: Vec<(usize, usize)> =
-                        undrawn.iter().map(|t| (t.from_x, t.from_y)).collect();
+                    let sources: Vec<(usize, usize, bool)> = undrawn
+                        .iter()
+                        .map(|t| (t.from_x, t.from_y, is_heavy(t)))
+                        .collect();
                     self.draw_merge_edges(
-                        &mut canvas,
+                        canvas,
                         &sources,
                         first.to_x,
                         first.to_y.saturating_sub(1),
@@ -483,26 +653,99 @@ impl StateRenderer {
         for trans in &layout.transitions {
             if !drawn.contains(&(trans.from_id.as_str(), trans.to_id.as_str())) {
                 self.draw_single_edge(
-                    &mut canvas,
+                    canvas,
                     trans.from_x,
                     trans.from_y,
                     trans.to_x,
                     trans.to_y.saturating_sub(1),
                     trans.label.as_deref(),
+                    is_heavy(trans),
+                    labels,
                 );
             }
         }
+    }
 
-        canvas.to_string()
+    /// Render the layout result
+    fn render_layout(&self, layout: &StateLayoutResult) -> String {
+        match self.draw_layout(layout, self.color) {
+            Some((canvas, roles)) => roles.finish(&canvas),
+            None => String::new(),
+        }
+    }
+
+    /// Draw the layout, recording roles and owners when `record` is set
+    fn draw_layout(
+        &self,
+        layout: &StateLayoutResult,
+        record: bool,
+    ) -> Option<(AsciiCanvas, RoleRecorder)> {
+        if layout.states.is_empty() {
+            return None;
+        }
+
+        // Calculate canvas size with extra space for arrows
+        let extra_height = layout.transitions.len() * 2;
+        let width = layout.width + 20; // Extra space for labels
+        let height = layout.height + extra_height + 2;
+
+        let mut canvas = AsciiCanvas::new(width, height);
+        let mut roles = RoleRecorder::new(record, &canvas);
+
+        // Composite states hold their regions' layouts; draw every level
+        let layouts = Self::nested_layouts(layout);
+        for state in layouts.iter().flat_map(|l| &l.states) {
+            self.draw_state(&mut canvas, state);
+            roles.own(state.x, state.y, state.width, state.height, &state.id);
+        }
+
+        roles.record(&canvas, Role::Node);
+
+        // Labels are drawn after every edge so lines never cut through them
+        let mut labels: Vec<PendingLabel> = Vec::new();
+        for nested in &layouts {
+            self.draw_transitions(&mut canvas, nested, &mut labels);
+        }
+        roles.record(&canvas, Role::Edge);
+
+        for (x, y, label) in labels {
+            canvas.draw_text(x, y, label);
+        }
+        roles.record(&canvas, Role::Label);
+
+        Some((canvas, roles))
     }
 
     /// Render the database to ASCII
     pub fn render(&self, database: &StateDatabase) -> Result<String> {
+        self.render_probed(database, &mut LayoutProbe::default())
+    }
+
+    /// Render the database, timing the layout through `probe`
+    pub(crate) fn render_probed(
+        &self,
+        database: &StateDatabase,
+        probe: &mut LayoutProbe,
+    ) -> Result<String> {
         let layout_algo = StateLayoutAlgorithm::new();
+        let started = probe.start();
         let layout = layout_algo.layout(database)?;
+        probe.finish(started, layout.width, layout.height);
 
         Ok(self.render_layout(&layout))
     }
+
+    /// Render the database as a [`CellGrid`]
+    ///
+    /// Each cell of a drawn state's box carries the state's ID; roles
+    /// become palette colors when color is on.
+    pub fn render_cells(&self, database: &StateDatabase) -> Result<CellGrid> {
+        let layout = StateLayoutAlgorithm::new().layout(database)?;
+        Ok(match self.draw_layout(&layout, true) {
+            Some((canvas, roles)) => roles.finish_cells(&canvas, self.color),
+            None => CellGrid::default(),
+        })
+    }
 }
 
 impl Default for StateRenderer {
@@ -534,7 +777,7 @@ impl Renderer<StateDatabase> for StateRenderer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{EdgeData, NodeData};
+    use crate::core::{EdgeData, EdgeType, NodeData};
 
     #[test]
     fn test_render_empty() {
@@ -606,4 +849,98 @@ mod tests {
             first_line
         );
     }
+
+    #[test]
+    fn test_heavy_transitions_use_thick_lines() {
+        let mut db = StateDatabase::new();
+        db.add_transition(EdgeData::new("Idle", "Busy")).unwrap();
+        db.add_transition(EdgeData::new("Busy", "Done")).unwrap();
+        let light = StateRenderer::new().render(&db).unwrap();
+        assert!(!light.contains('║'));
+
+        db.set_transition_weight("Idle", "Busy", 100.0).unwrap();
+        db.set_transition_weight("Busy", "Done", 20.0).unwrap();
+        let output = StateRenderer::new().render(&db).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        let busy = lines.iter().position(|l| l.contains("Busy")).unwrap();
+        assert!(lines[..busy].iter().any(|l| l.contains('║')));
+        assert!(!lines[busy..].iter().any(|l| l.contains('║')));
+    }
+
+    #[test]
+    fn test_color_marks_states_edges_and_labels() {
+        let mut db = StateDatabase::new();
+        db.add_state(NodeData::new("Idle", "Idle")).unwrap();
+        db.add_state(NodeData::new("Busy", "Busy")).unwrap();
+        db.add_transition(EdgeData::with_label("Idle", "Busy", EdgeType::Arrow, "go"))
+            .unwrap();
+
+        let plain = StateRenderer::new().render(&db).unwrap();
+        let colored = StateRenderer::new().with_color(true).render(&db).unwrap();
+
+        let palette = crate::core::Palette::default();
+        for role in [Role::Node, Role::Edge, Role::Label] {
+            let code = format!("\x1b[{}m", palette.code(role));
+            assert!(colored.contains(&code), "missing {:?}", role);
+        }
+        assert!(colored.contains("go"));
+        assert!(plain.contains("go"));
+    }
+
+    #[test]
+    fn test_fork_join_bars_and_choice_diamond() {
+        let mut db = StateDatabase::new();
+        db.add_pseudostate("fork", Pseudostate::Fork).unwrap();
+        db.add_pseudostate("join", Pseudostate::Join).unwrap();
+        db.add_pseudostate("pick", Pseudostate::Choice).unwrap();
+        db.add_transition(EdgeData::new("[*]", "fork")).unwrap();
+        for branch in ["A", "B"] {
+            db.add_transition(EdgeData::new("fork", branch)).unwrap();
+            db.add_transition(EdgeData::new(branch, "join")).unwrap();
+        }
+        db.add_transition(EdgeData::new("join", "pick")).unwrap();
+        db.add_transition(EdgeData::new("pick", "[*]")).unwrap();
+
+        let output = StateRenderer::new().render(&db).unwrap();
+        let bars: Vec<&str> = output.lines().filter(|l| l.contains('━')).collect();
+        assert_eq!(bars.len(), 2, "{}", output);
+        assert!(output.contains('◇'), "{}", output);
+        // Branches run straight from the fork: no split bar is drawn
+        assert!(!output.contains('┴') && !output.contains('┬'), "{}", output);
+
+        let ascii = StateRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        assert!(ascii.contains("====") && ascii.contains("< >"), "{}", ascii);
+    }
+
+    #[test]
+    fn test_composite_state_draws_regions_in_lanes() {
+        let mut left = StateDatabase::new();
+        left.add_transition(EdgeData::new("Idle", "Busy")).unwrap();
+        let mut right = StateDatabase::new();
+        right.add_transition(EdgeData::new("Off", "On")).unwrap();
+        let mut db = StateDatabase::new();
+        db.add_composite_state("Active", vec![left, right]).unwrap();
+        db.add_transition(EdgeData::new("[*]", "Active")).unwrap();
+
+        let output = StateRenderer::new().render(&db).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        let row = |text: &str| lines.iter().find(|l| l.contains(text)).unwrap();
+        assert!(row("Active").starts_with('│'), "{}", output);
+        // Both regions share rows, split by the dashed divider
+        let inner = row("Idle");
+        assert!(inner.contains("Off") && inner.contains('┆'), "{}", output);
+        assert!(inner.find("Idle") < inner.find('┆') && inner.find('┆') < inner.find("Off"));
+        assert!(row("Busy").contains("On"), "{}", output);
+
+        let ascii = StateRenderer::with_style(CharacterSet::Ascii)
+            .render(&db)
+            .unwrap();
+        assert!(
+            ascii.lines().any(|l| l.contains("Idle") && l.contains(':')),
+            "{}",
+            ascii
+        );
+    }
 }
@@ -0,0 +1,250 @@
+//! Layout robustness helpers for tests (`testing` feature)
+//!
+//! [`jitter_flowchart`] rebuilds a flowchart with node order shuffled and
+//! labels padded by random amounts, driven by a seed so failures reproduce.
+//! The `assert_*` functions panic on the layout invariant violations reported
+//! by [`validate_layout`]: overlapping node boxes, edges that do not start and
+//! end on their nodes, and anything outside the canvas.
+//!
+//! [`snapshot`] compares rendered diagrams against golden files, for
+//! downstream projects as well as this crate's own fixtures.
+//!
+//! ```rust
+//! use figurehead::unstable::flowchart::FlowchartLayoutAlgorithm;
+//! use figurehead::unstable::LayoutAlgorithm;
+//! use figurehead::testing::{assert_layout_invariants, jitter_flowchart, Jitter};
+//!
+//! let db = figurehead::parse("graph TD; A-->B; A-->C; B-->D; C-->D").unwrap();
+//! for seed in 0..8 {
+//!     let jittered = jitter_flowchart(&db, &Jitter::new(seed));
+//!     let layout = FlowchartLayoutAlgorithm::new().layout(&jittered).unwrap();
+//!     assert_layout_invariants(&layout);
+//! }
+//! ```
+
+pub mod mermaid;
+pub mod snapshot;
+
+use crate::core::{Database, EdgeData, NodeData};
+use crate::plugins::flowchart::{
+    validate_layout, FlowchartDatabase, FlowchartLayoutResult, LayoutViolation,
+};
+
+/// Small deterministic PRNG (SplitMix64) so tests need no extra dependencies
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Create a generator from a seed
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Next pseudo-random value
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0..bound` (0 when `bound` is 0)
+    pub fn below(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            return 0;
+        }
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Fisher-Yates shuffle
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+/// How to perturb a diagram before layout
+#[derive(Debug, Clone)]
+pub struct Jitter {
+    /// Seed for the random generator
+    pub seed: u64,
+    /// Upper bound on characters appended to each label
+    pub max_label_growth: usize,
+    /// Shuffle node insertion order
+    pub shuffle_nodes: bool,
+    /// Pad labels with double-width characters as well as ASCII
+    pub wide_chars: bool,
+}
+
+impl Jitter {
+    /// Default perturbation for a seed: shuffled nodes, labels grown by up to
+    /// 12 characters including wide ones
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            max_label_growth: 12,
+            shuffle_nodes: true,
+            wide_chars: true,
+        }
+    }
+
+    /// Set the upper bound on characters appended to each label
+    pub fn with_max_label_growth(mut self, max: usize) -> Self {
+        self.max_label_growth = max;
+        self
+    }
+
+    /// Set whether node insertion order is shuffled
+    pub fn with_shuffle_nodes(mut self, shuffle: bool) -> Self {
+        self.shuffle_nodes = shuffle;
+        self
+    }
+
+    /// Set whether padding includes double-width characters
+    pub fn with_wide_chars(mut self, wide: bool) -> Self {
+        self.wide_chars = wide;
+        self
+    }
+}
+
+/// Rebuild a flowchart with perturbed node order and label widths
+///
+/// Edges, direction and subgraph membership are preserved.
+pub fn jitter_flowchart(database: &FlowchartDatabase, jitter: &Jitter) -> FlowchartDatabase {
+    let mut rng = SeededRng::new(jitter.seed);
+    let padding: &[char] = if jitter.wide_chars {
+        &['x', 'w', ' ', '漢']
+    } else {
+        &['x', 'w', ' ']
+    };
+
+    let mut nodes: Vec<NodeData> = database.nodes().cloned().collect();
+    if jitter.shuffle_nodes {
+        rng.shuffle(&mut nodes);
+    }
+
+    let mut jittered = FlowchartDatabase::with_direction(database.direction());
+    for mut node in nodes {
+        let growth = rng.below(jitter.max_label_growth + 1);
+        for _ in 0..growth {
+            node.label.push(padding[rng.below(padding.len())]);
+        }
+        // Layout trims labels; keep the padding visible
+        node.label = node.label.trim_end().to_string();
+        if node.label.is_empty() {
+            node.label = node.id.clone();
+        }
+        let _ = jittered.add_node(node);
+    }
+    for edge in database.edges() {
+        let _ = jittered.add_edge(EdgeData::clone(edge));
+    }
+    for subgraph in database.subgraphs() {
+        jittered.add_subgraph(subgraph.title.clone(), subgraph.members.clone());
+    }
+    jittered
+}
+
+fn assert_none(layout: &FlowchartLayoutResult, keep: impl Fn(&LayoutViolation) -> bool) {
+    let violations: Vec<String> = validate_layout(layout)
+        .iter()
+        .filter(|v| keep(v))
+        .map(ToString::to_string)
+        .collect();
+    assert!(violations.is_empty(), "{}", violations.join("; "));
+}
+
+/// Panic if any two node boxes share a cell
+pub fn assert_no_node_overlap(layout: &FlowchartLayoutResult) {
+    assert_none(layout, |v| matches!(v, LayoutViolation::NodeOverlap { .. }));
+}
+
+/// Panic if an edge does not start next to its source and end next to its target
+pub fn assert_edges_terminate_on_nodes(layout: &FlowchartLayoutResult) {
+    assert_none(layout, |v| {
+        matches!(
+            v,
+            LayoutViolation::MissingNode { .. } | LayoutViolation::DanglingEndpoint { .. }
+        )
+    });
+}
+
+/// Panic if a node, subgraph or waypoint lies outside the canvas
+pub fn assert_within_bounds(layout: &FlowchartLayoutResult) {
+    assert_none(layout, |v| matches!(v, LayoutViolation::OutOfBounds { .. }));
+}
+
+/// Run every layout invariant check
+pub fn assert_layout_invariants(layout: &FlowchartLayoutResult) {
+    assert_none(layout, |_| true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let a: Vec<u64> = {
+            let mut rng = SeededRng::new(7);
+            (0..4).map(|_| rng.next_u64()).collect()
+        };
+        let mut rng = SeededRng::new(7);
+        let b: Vec<u64> = (0..4).map(|_| rng.next_u64()).collect();
+        assert_eq!(a, b);
+        assert_ne!(SeededRng::new(8).next_u64(), a[0]);
+    }
+
+    #[test]
+    fn test_jitter_preserves_structure() {
+        let db = crate::parse("graph LR; A[Start] --> B{Check}; B -->|no| C; B --> D").unwrap();
+        let jittered = jitter_flowchart(&db, &Jitter::new(3));
+
+        assert_eq!(jittered.node_count(), db.node_count());
+        assert_eq!(jittered.edge_count(), db.edge_count());
+        assert_eq!(jittered.direction(), db.direction());
+        let check = jittered.get_node("B").unwrap();
+        assert!(check.label.starts_with("Check"));
+        assert_eq!(check.shape, db.get_node("B").unwrap().shape);
+    }
+
+    #[test]
+    fn test_same_seed_same_jitter() {
+        let db = crate::parse("graph TD; A-->B; B-->C; C-->D").unwrap();
+        let labels = |db: &FlowchartDatabase| -> Vec<(String, String)> {
+            db.nodes()
+                .map(|n| (n.id.clone(), n.label.clone()))
+                .collect()
+        };
+        let a = jitter_flowchart(&db, &Jitter::new(42));
+        let b = jitter_flowchart(&db, &Jitter::new(42));
+        assert_eq!(labels(&a), labels(&b));
+    }
+
+    #[test]
+    #[should_panic(expected = "overlap")]
+    fn test_overlap_assertion_fires() {
+        use crate::plugins::flowchart::PositionedNode;
+
+        let node = |id: &str, x| PositionedNode {
+            id: id.to_string(),
+            x,
+            y: 0,
+            width: 5,
+            height: 3,
+        };
+        let layout = FlowchartLayoutResult {
+            nodes: vec![node("A", 0), node("B", 4)],
+            edges: vec![],
+            subgraphs: vec![],
+            width: 10,
+            height: 3,
+        };
+        assert_no_node_overlap(&layout);
+    }
+}
@@ -0,0 +1,402 @@
+//! Topology comparison against mermaid.js (`testing` feature)
+//!
+//! String snapshots only tell us the drawing changed, not whether it still
+//! means the same thing as Mermaid's. [`render_mermaid_svg`] renders a
+//! diagram with mermaid-cli (`mmdc`), [`Topology::from_mermaid_svg`] pulls
+//! node positions and edges back out of the SVG, and [`compare`] checks
+//! figurehead's layout against it: the same nodes, the same edges pointing
+//! the same way, and each edge running the same way along the flow.
+//!
+//! mermaid-cli is optional; [`mermaid_cli`] returns `None` when it cannot be
+//! found, so tests can skip instead of failing.
+//!
+//! ```rust,no_run
+//! use figurehead::testing::mermaid::{compare, mermaid_cli, render_mermaid_svg, Topology};
+//!
+//! let input = "graph TD; A-->B; A-->C";
+//! if let Some(mmdc) = mermaid_cli() {
+//!     let svg = render_mermaid_svg(&mmdc, input).unwrap();
+//!     let expected = Topology::from_mermaid_svg(&svg);
+//!     let (actual, direction) = Topology::from_flowchart(input).unwrap();
+//!     assert!(compare(&expected, &actual, direction).is_empty());
+//! }
+//! ```
+
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::core::{Database, Direction, LayoutAlgorithm};
+use crate::plugins::flowchart::FlowchartLayoutAlgorithm;
+
+/// Nodes and edges of a laid out flowchart, independent of the renderer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Topology {
+    /// Node id to the center of its box, in the renderer's own units
+    pub nodes: BTreeMap<String, (f64, f64)>,
+    /// Edges as `(from, to)`
+    pub edges: BTreeSet<(String, String)>,
+}
+
+impl Topology {
+    /// Parse and lay out flowchart input with figurehead
+    ///
+    /// Returns the diagram direction alongside, for [`compare`].
+    pub fn from_flowchart(input: &str) -> Result<(Self, Direction)> {
+        let database = crate::parse(input)?;
+        let layout = FlowchartLayoutAlgorithm::new().layout(&database)?;
+
+        let nodes = layout
+            .nodes
+            .iter()
+            .map(|n| {
+                let center = (
+                    n.x as f64 + n.width as f64 / 2.0,
+                    n.y as f64 + n.height as f64 / 2.0,
+                );
+                (n.id.clone(), center)
+            })
+            .collect();
+        let edges = database
+            .edges()
+            .map(|e| (e.from.clone(), e.to.clone()))
+            .collect();
+        Ok((Self { nodes, edges }, database.direction()))
+    }
+
+    /// Extract nodes and edges from an SVG rendered by mermaid.js
+    ///
+    /// Nodes are the `<g class="node ...">` groups (ids `flowchart-<id>-<n>`)
+    /// placed by their `translate(x, y)`. Edges are read from the
+    /// `LS-<from> LE-<to>` classes of older releases, or else from path ids
+    /// `L_<from>_<to>_<n>` (`-` separated before v11), split where both
+    /// halves name known nodes.
+    pub fn from_mermaid_svg(svg: &str) -> Self {
+        let mut topology = Self::default();
+
+        for attrs in tags(svg, "g") {
+            let classes = attrs.get("class").map_or("", String::as_str);
+            if !classes.split_whitespace().any(|c| c == "node") {
+                continue;
+            }
+            let Some(id) = attrs.get("id").and_then(|id| node_id(id)) else {
+                continue;
+            };
+            let position = attrs
+                .get("transform")
+                .and_then(|t| translate(t))
+                .unwrap_or_default();
+            topology.nodes.insert(id, position);
+        }
+
+        for attrs in tags(svg, "path") {
+            let classes = attrs.get("class").map_or("", String::as_str);
+            if !classes.split_whitespace().any(|c| c == "flowchart-link") {
+                continue;
+            }
+            let class_end = |prefix: &str| {
+                classes
+                    .split_whitespace()
+                    .find_map(|c| c.strip_prefix(prefix))
+                    .map(str::to_string)
+            };
+            let edge = match (class_end("LS-"), class_end("LE-")) {
+                (Some(from), Some(to)) => Some((from, to)),
+                _ => attrs
+                    .get("data-id")
+                    .or_else(|| attrs.get("id"))
+                    .and_then(|id| split_edge_id(id, &topology.nodes)),
+            };
+            if let Some(edge) = edge {
+                topology.edges.insert(edge);
+            }
+        }
+
+        topology
+    }
+}
+
+/// A way figurehead's layout means something different from Mermaid's
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// Mermaid draws a node figurehead does not
+    MissingNode(String),
+    /// figurehead draws a node Mermaid does not
+    ExtraNode(String),
+    /// Mermaid draws an edge figurehead does not
+    MissingEdge(String, String),
+    /// figurehead draws an edge Mermaid does not
+    ExtraEdge(String, String),
+    /// Both draw the edge, pointing opposite ways (`from`, `to` as Mermaid)
+    ReversedEdge(String, String),
+    /// The edge runs with the flow in one layout and against it in the other
+    OrderFlipped(String, String),
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingNode(id) => write!(f, "node '{}' missing", id),
+            Self::ExtraNode(id) => write!(f, "node '{}' not in mermaid output", id),
+            Self::MissingEdge(from, to) => write!(f, "edge {} -> {} missing", from, to),
+            Self::ExtraEdge(from, to) => {
+                write!(f, "edge {} -> {} not in mermaid output", from, to)
+            }
+            Self::ReversedEdge(from, to) => {
+                write!(f, "edge {} -> {} points the other way", from, to)
+            }
+            Self::OrderFlipped(from, to) => write!(
+                f,
+                "edge {} -> {} runs the other way along the flow than in mermaid",
+                from, to
+            ),
+        }
+    }
+}
+
+/// Compare figurehead's topology (`actual`) against Mermaid's (`expected`)
+///
+/// Edge order along the flow is only checked where both layouts put the two
+/// ends on different ranks; exact positions never matter.
+pub fn compare(expected: &Topology, actual: &Topology, direction: Direction) -> Vec<Divergence> {
+    let mut divergences = Vec::new();
+
+    for id in expected.nodes.keys() {
+        if !actual.nodes.contains_key(id) {
+            divergences.push(Divergence::MissingNode(id.clone()));
+        }
+    }
+    for id in actual.nodes.keys() {
+        if !expected.nodes.contains_key(id) {
+            divergences.push(Divergence::ExtraNode(id.clone()));
+        }
+    }
+
+    let flipped = |(from, to): &(String, String)| (to.clone(), from.clone());
+    for edge in &expected.edges {
+        if actual.edges.contains(edge) {
+            continue;
+        }
+        let (from, to) = edge.clone();
+        if actual.edges.contains(&flipped(edge)) {
+            divergences.push(Divergence::ReversedEdge(from, to));
+        } else {
+            divergences.push(Divergence::MissingEdge(from, to));
+        }
+    }
+    for edge in &actual.edges {
+        if !expected.edges.contains(edge) && !expected.edges.contains(&flipped(edge)) {
+            divergences.push(Divergence::ExtraEdge(edge.0.clone(), edge.1.clone()));
+        }
+    }
+
+    // Position along the flow: larger is further downstream
+    let along = |(x, y): (f64, f64)| match direction {
+        Direction::TopDown => y,
+        Direction::BottomUp => -y,
+        Direction::LeftRight => x,
+        Direction::RightLeft => -x,
+    };
+    let step = |topology: &Topology, from: &str, to: &str| {
+        let from = along(*topology.nodes.get(from)?);
+        let to = along(*topology.nodes.get(to)?);
+        let delta = to - from;
+        (delta.abs() > 0.5).then_some(delta > 0.0)
+    };
+    for (from, to) in expected.edges.intersection(&actual.edges) {
+        if let (Some(expected_step), Some(actual_step)) =
+            (step(expected, from, to), step(actual, from, to))
+        {
+            if expected_step != actual_step {
+                divergences.push(Divergence::OrderFlipped(from.clone(), to.clone()));
+            }
+        }
+    }
+
+    divergences
+}
+
+/// The mermaid-cli executable: `$MMDC` if set, otherwise `mmdc` on `PATH`
+pub fn mermaid_cli() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("MMDC") {
+        return Some(PathBuf::from(path));
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join("mmdc"))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Render `input` to SVG with mermaid-cli
+pub fn render_mermaid_svg(mmdc: &Path, input: &str) -> Result<String> {
+    static RUN: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "figurehead-mermaid-{}-{}",
+        std::process::id(),
+        RUN.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir)?;
+    let (source, target) = (dir.join("input.mmd"), dir.join("output.svg"));
+    std::fs::write(&source, input)?;
+
+    let output = Command::new(mmdc)
+        .arg("--quiet")
+        .arg("--input")
+        .arg(&source)
+        .arg("--output")
+        .arg(&target)
+        .output()
+        .with_context(|| format!("failed to run {}", mmdc.display()))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_dir_all(&dir);
+        bail!(
+            "mermaid-cli failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let svg = std::fs::read_to_string(&target);
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(svg?)
+}
+
+/// Attributes of every `<name ...>` tag in the document, in order
+fn tags<'a>(svg: &'a str, name: &'a str) -> impl Iterator<Item = HashMap<String, String>> + 'a {
+    svg.match_indices('<')
+        .filter_map(move |(start, _)| {
+            let rest = svg[start + 1..].strip_prefix(name)?;
+            // `<g` must not match `<glyph`
+            rest.starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .then(|| &rest[..rest.find('>').unwrap_or(rest.len())])
+        })
+        .map(attributes)
+}
+
+/// `name="value"` pairs of a tag body
+fn attributes(body: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    let mut rest = body;
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq]
+            .split_whitespace()
+            .last()
+            .unwrap_or("")
+            .to_string();
+        let value = rest[eq + 1..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let Some(end) = value[1..].find(quote) else {
+            break;
+        };
+        attrs.insert(name, value[1..end + 1].to_string());
+        rest = &value[end + 2..];
+    }
+    attrs
+}
+
+/// Node id from a mermaid group id like `flowchart-Start-3`
+fn node_id(id: &str) -> Option<String> {
+    let id = id.strip_prefix("flowchart-")?;
+    let (name, counter) = id.rsplit_once('-')?;
+    counter
+        .chars()
+        .all(|c| c.is_ascii_digit())
+        .then(|| name.to_string())
+}
+
+/// `(x, y)` of a `translate(x, y)` transform
+fn translate(transform: &str) -> Option<(f64, f64)> {
+    let args = transform.split("translate(").nth(1)?.split(')').next()?;
+    let mut numbers = args
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(str::parse::<f64>);
+    let x = numbers.next()?.ok()?;
+    let y = numbers.next().unwrap_or(Ok(0.0)).ok()?;
+    Some((x, y))
+}
+
+/// Split an edge id (`L_A_B_0` or `L-A-B-0`) into its known endpoint nodes
+fn split_edge_id(id: &str, nodes: &BTreeMap<String, (f64, f64)>) -> Option<(String, String)> {
+    let separator = id.chars().nth(1).filter(|c| *c == '_' || *c == '-')?;
+    let body = id.strip_prefix('L')?.strip_prefix(separator)?;
+    let (body, _counter) = body.rsplit_once(separator)?;
+    body.match_indices(separator).find_map(|(at, _)| {
+        let (from, to) = (&body[..at], &body[at + 1..]);
+        (nodes.contains_key(from) && nodes.contains_key(to))
+            .then(|| (from.to_string(), to.to_string()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Trimmed-down mermaid v11 output for `graph TD; A-->B_1; A-->C`
+    const SVG: &str = r#"<svg id="my-svg"><g class="root"><g class="edgePaths">
+<path d="M0,0L0,10" id="L_A_B_1_0" class="edge-thickness-normal edge-pattern-solid flowchart-link" data-id="L_A_B_1_0"></path>
+<path d="M0,0L0,10" id="L-A-C-0" class="flowchart-link LS-A LE-C"></path>
+</g><g class="nodes">
+<g class="node default" id="flowchart-A-0" transform="translate(60, 35)"><rect/></g>
+<g class="node default" id="flowchart-B_1-1" transform="translate(20,139)"><rect/></g>
+<g class="node default" id="flowchart-C-3" transform="translate(100, 139)"><rect/></g>
+</g></g></svg>"#;
+
+    #[test]
+    fn test_topology_from_mermaid_svg() {
+        let topology = Topology::from_mermaid_svg(SVG);
+        assert_eq!(topology.nodes.len(), 3);
+        assert_eq!(topology.nodes["B_1"], (20.0, 139.0));
+
+        let edges: Vec<_> = topology.edges.iter().cloned().collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("A".to_string(), "B_1".to_string()),
+                ("A".to_string(), "C".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matching_layouts_have_no_divergences() {
+        let expected = Topology::from_mermaid_svg(SVG);
+        let (actual, direction) = Topology::from_flowchart("graph TD; A-->B_1; A-->C").unwrap();
+        assert_eq!(compare(&expected, &actual, direction), vec![]);
+    }
+
+    #[test]
+    fn test_divergences_are_reported() {
+        let expected = Topology::from_mermaid_svg(SVG);
+        let (actual, direction) = Topology::from_flowchart("graph TD; B_1-->A; A-->D").unwrap();
+
+        let divergences = compare(&expected, &actual, direction);
+        assert!(divergences.contains(&Divergence::MissingNode("C".to_string())));
+        assert!(divergences.contains(&Divergence::ExtraNode("D".to_string())));
+        assert!(divergences.contains(&Divergence::ReversedEdge(
+            "A".to_string(),
+            "B_1".to_string()
+        )));
+        assert!(divergences.contains(&Divergence::MissingEdge("A".to_string(), "C".to_string())));
+    }
+
+    #[test]
+    fn test_order_flip_along_the_flow() {
+        let mut expected = Topology::default();
+        expected.nodes.insert("A".to_string(), (0.0, 0.0));
+        expected.nodes.insert("B".to_string(), (0.0, 50.0));
+        expected.edges.insert(("A".to_string(), "B".to_string()));
+
+        let mut actual = expected.clone();
+        actual.nodes.insert("B".to_string(), (0.0, -5.0));
+        assert_eq!(
+            compare(&expected, &actual, Direction::TopDown),
+            vec![Divergence::OrderFlipped("A".to_string(), "B".to_string())]
+        );
+        // Side by side along the flow never counts as flipped
+        assert!(compare(&expected, &actual, Direction::LeftRight).is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+//! Golden-file snapshot tests (`testing` feature)
+//!
+//! [`Snapshots`] renders diagrams with a fixed [`RenderConfig`] (no color,
+//! no terminal-dependent width) and compares the result against
+//! `<dir>/<name>.txt`. Trailing whitespace, trailing blank lines and CRLF
+//! line endings are ignored on both sides, so fixtures survive editors and
+//! git checkouts that touch them.
+//!
+//! Set `UPDATE_FIXTURES=1` to write the current output instead of comparing.
+//!
+//! ```rust,no_run
+//! use figurehead::testing::snapshot::Snapshots;
+//!
+//! let snapshots = Snapshots::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"));
+//! snapshots.assert("login_flow", "graph LR; A[Login]-->B{OK?}-->C[Home]");
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::core::RenderConfig;
+
+/// Environment variable that switches [`Snapshots`] to writing fixtures
+pub const UPDATE_ENV: &str = "UPDATE_FIXTURES";
+
+/// Strip trailing whitespace from each line and trailing blank lines
+///
+/// CRLF line endings become LF.
+pub fn normalize(text: &str) -> String {
+    let mut lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+/// A rendered diagram that differs from its fixture
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotMismatch {
+    /// Fixture file
+    pub path: PathBuf,
+    /// Normalized fixture contents (`None` when the fixture is missing)
+    pub expected: Option<String>,
+    /// Normalized rendering
+    pub actual: String,
+}
+
+impl SnapshotMismatch {
+    /// First line (1-based) where the fixture and the rendering differ
+    pub fn first_difference(&self) -> Option<usize> {
+        let expected = self.expected.as_deref()?;
+        let mut expected_lines = expected.lines();
+        let mut actual_lines = self.actual.lines();
+        let mut line = 1;
+        loop {
+            match (expected_lines.next(), actual_lines.next()) {
+                (None, None) => return None,
+                (a, b) if a != b => return Some(line),
+                _ => line += 1,
+            }
+        }
+    }
+}
+
+impl fmt::Display for SnapshotMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(expected) = &self.expected else {
+            return write!(
+                f,
+                "Fixture not found: {}\nRun with {}=1 to create it.\n\nActual output:\n{}",
+                self.path.display(),
+                UPDATE_ENV,
+                self.actual
+            );
+        };
+        write!(f, "Snapshot mismatch for {}", self.path.display())?;
+        if let Some(line) = self.first_difference() {
+            write!(f, " (first difference on line {})", line)?;
+        }
+        write!(
+            f,
+            "\n\n=== Expected ===\n{}\n=== Actual ===\n{}\nRun with {}=1 to update.",
+            expected, self.actual, UPDATE_ENV
+        )
+    }
+}
+
+impl std::error::Error for SnapshotMismatch {}
+
+/// Renders diagrams and checks them against golden files in one directory
+#[derive(Debug, Clone)]
+pub struct Snapshots {
+    dir: PathBuf,
+    config: RenderConfig,
+    update: bool,
+}
+
+impl Snapshots {
+    /// Fixtures in `dir`, rendered with the default config
+    ///
+    /// Fixtures are updated instead of compared when `UPDATE_FIXTURES` is
+    /// set.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            config: RenderConfig::default(),
+            update: std::env::var_os(UPDATE_ENV).is_some(),
+        }
+    }
+
+    /// Set the render config; color and the width limit are always off
+    pub fn with_config(mut self, config: RenderConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set whether fixtures are written instead of compared
+    pub fn with_update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Directory holding the fixtures
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Fixture file for `name`
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.txt", name))
+    }
+
+    /// Render `input` the way fixtures are rendered, normalized
+    ///
+    /// Panics if the diagram fails to render.
+    pub fn render(&self, input: &str) -> String {
+        let config = self.config.with_color(false).with_max_width(None);
+        let output = crate::render_pure(input, &config)
+            .unwrap_or_else(|err| panic!("render should succeed: {}", err));
+        normalize(&output)
+    }
+
+    /// Compare `input`'s rendering with fixture `name`
+    ///
+    /// In update mode the fixture is written and this always succeeds.
+    pub fn check(&self, name: &str, input: &str) -> Result<(), SnapshotMismatch> {
+        let path = self.path(name);
+        let actual = self.render(input);
+
+        if self.update {
+            fs::write(&path, &actual)
+                .unwrap_or_else(|err| panic!("failed to write {}: {}", path.display(), err));
+            return Ok(());
+        }
+
+        let expected = fs::read_to_string(&path).ok().map(|text| normalize(&text));
+        if expected.as_deref() == Some(actual.as_str()) {
+            return Ok(());
+        }
+        Err(SnapshotMismatch {
+            path,
+            expected,
+            actual,
+        })
+    }
+
+    /// Panic unless `input`'s rendering matches fixture `name`
+    pub fn assert(&self, name: &str, input: &str) {
+        if let Err(mismatch) = self.check(name, input) {
+            panic!("{}", mismatch);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "figurehead-snapshot-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_normalize_ignores_trailing_whitespace() {
+        assert_eq!(normalize("a  \r\nb\t\n\n  \n"), "a\nb");
+        assert_eq!(normalize(""), "");
+    }
+
+    #[test]
+    fn test_check_writes_then_compares_fixtures() {
+        let dir = temp_dir("roundtrip");
+        let snapshots = Snapshots::new(&dir).with_update(false);
+        let missing = snapshots.check("chain", "graph LR; A-->B").unwrap_err();
+        assert_eq!(missing.expected, None);
+        assert!(missing.to_string().contains("Fixture not found"));
+
+        Snapshots::new(&dir)
+            .with_update(true)
+            .check("chain", "graph LR; A-->B")
+            .unwrap();
+        snapshots.assert("chain", "graph LR; A-->B");
+
+        // Editors adding trailing spaces don't break the fixture
+        let path = snapshots.path("chain");
+        let padded = fs::read_to_string(&path).unwrap().replace('\n', "   \r\n");
+        fs::write(&path, padded + "\n\n").unwrap();
+        snapshots.assert("chain", "graph LR; A-->B");
+
+        let changed = snapshots.check("chain", "graph LR; A-->C").unwrap_err();
+        assert!(changed.first_difference().is_some());
+        assert!(changed.to_string().contains("Snapshot mismatch"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -7,10 +7,13 @@
 use wasm_bindgen::prelude::*;
 
 #[cfg(target_arch = "wasm32")]
-use crate::core::{CharacterSet, Database, Parser, RenderConfig, Renderer};
+use crate::core::{
+    CharacterSet, Database, DiamondStyle, EdgeTerminator, FigureheadError, Parser, RenderConfig,
+    Renderer,
+};
 #[cfg(target_arch = "wasm32")]
 use crate::plugins::flowchart::{
-    clear_warnings, take_warnings, FlowchartDatabase, FlowchartParser, FlowchartRenderer,
+    FlowchartDatabase, FlowchartParser, FlowchartRenderer, ParseWarning,
 };
 #[cfg(target_arch = "wasm32")]
 use crate::plugins::Orchestrator;
@@ -87,13 +90,32 @@ pub fn render_flowchart_with_style(input: &str, style: &str) -> String {
         .expect("Failed to render diagram")
 }
 
+/// Convert collected parse warnings to a JSON array
+///
+/// Each entry has `message`, `line`, `column` and `statement`; the position
+/// fields are `null` for warnings that do not point into the input.
+#[cfg(target_arch = "wasm32")]
+fn warnings_json(warnings: Vec<ParseWarning>) -> serde_json::Value {
+    warnings
+        .into_iter()
+        .map(|warning| {
+            serde_json::json!({
+                "message": warning.message,
+                "line": warning.line,
+                "column": warning.column,
+                "statement": warning.statement,
+            })
+        })
+        .collect()
+}
+
 /// Parse a Mermaid flowchart and return node/edge counts
 ///
 /// # Arguments
 /// * `input` - Mermaid flowchart syntax
 ///
 /// # Returns
-/// * JSON string with node_count, edge_count, and direction
+/// * JSON string with node_count, edge_count, direction, and warnings
 /// * Throws a JavaScript error if parsing fails
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
@@ -110,6 +132,7 @@ pub fn parse_flowchart(input: &str) -> String {
         "node_count": database.node_count(),
         "edge_count": database.edge_count(),
         "direction": format!("{:?}", database.direction()),
+        "warnings": warnings_json(database.take_parse_warnings()),
     });
 
     serde_json::to_string(&result).expect("Failed to serialize JSON")
@@ -145,7 +168,7 @@ pub fn render_diagram(input: &str) -> Result<String, JsValue> {
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub fn render_diagram_with_style(input: &str, style: &str) -> Result<String, JsValue> {
-    let character_set: CharacterSet = style.parse().map_err(|e| JsValue::from_str(&e))?;
+    let character_set: CharacterSet = style.parse().map_err(|e: String| JsValue::from_str(&e))?;
 
     let config = RenderConfig {
         style: character_set,
@@ -165,12 +188,12 @@ pub fn render_diagram_with_style(input: &str, style: &str) -> Result<String, JsV
 /// * `style` - Character set style ("ascii", "unicode", "unicode-math", or "compact")
 ///
 /// # Returns
-/// * JSON string with fields: output, warnings, error
+/// * JSON string with fields: output, warnings, error. Each warning is an
+///   object with `message`, `line`, `column` and `statement` (position
+///   fields are `null` when unknown), so editors can mark the offending line.
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub fn render_diagram_json(input: &str, style: &str) -> String {
-    clear_warnings();
-
     let character_set: CharacterSet = match style.parse() {
         Ok(cs) => cs,
         Err(e) => {
@@ -190,26 +213,237 @@ pub fn render_diagram_json(input: &str, style: &str) -> String {
     let mut orchestrator = Orchestrator::all_plugins(config);
     orchestrator.register_default_detectors();
 
-    match orchestrator.process(input) {
-        Ok(output) => {
-            let warnings = take_warnings();
+    // Every diagram type keeps its parse warnings on its database
+    let result = orchestrator.process_with_warnings(input);
+
+    match result {
+        Ok((output, warnings)) => serde_json::json!({
+            "output": output,
+            "warnings": warnings_json(warnings),
+            "error": null
+        })
+        .to_string(),
+        Err(e) => serde_json::json!({
+            "output": "",
+            "warnings": [],
+            "error": format!("{}", e)
+        })
+        .to_string(),
+    }
+}
+
+/// Error thrown by the configurable bindings
+///
+/// Thrown as an object rather than a string so editors can tell a parse
+/// error from a bad option and mark the offending line.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct DiagramError {
+    /// `"parse"`, `"undetected"`, `"unsupported"`, `"option"` or `"render"`
+    pub kind: String,
+    /// Human-readable description
+    pub message: String,
+    /// Line of the offending statement (1-based), for parse errors
+    pub line: Option<usize>,
+    /// Column of the offending statement (1-based), for parse errors
+    pub column: Option<usize>,
+    /// Text of the offending statement, for parse errors
+    pub statement: Option<String>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl DiagramError {
+    /// Error for an option value the bindings don't accept
+    fn option(message: impl Into<String>) -> Self {
+        Self {
+            kind: "option".to_string(),
+            message: message.into(),
+            line: None,
+            column: None,
+            statement: None,
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl From<FigureheadError> for DiagramError {
+    fn from(error: FigureheadError) -> Self {
+        let message = error.to_string();
+        let (kind, span, statement) = match error {
+            FigureheadError::Parse {
+                span, statement, ..
+            } => ("parse", Some(span), Some(statement)),
+            FigureheadError::Undetected => ("undetected", None, None),
+            FigureheadError::UnsupportedDiagramType { .. } => ("unsupported", None, None),
+            FigureheadError::Other(_) => ("render", None, None),
+        };
+        Self {
+            kind: kind.to_string(),
+            message,
+            line: span.map(|span| span.line),
+            column: span.map(|span| span.column),
+            statement,
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl From<anyhow::Error> for DiagramError {
+    fn from(error: anyhow::Error) -> Self {
+        FigureheadError::from(error).into()
+    }
+}
+
+/// Orchestrator with every plugin and the default detectors
+#[cfg(target_arch = "wasm32")]
+fn orchestrator(config: RenderConfig) -> Orchestrator {
+    let mut orchestrator = Orchestrator::all_plugins(config);
+    orchestrator.register_default_detectors();
+    orchestrator
+}
+
+/// Render any supported diagram type with the given options (auto-detects)
+///
+/// # Arguments
+/// * `input` - Mermaid diagram syntax (flowchart, gitgraph, etc.)
+/// * `style` - Character set style ("ascii", "unicode", "unicode-math", or "compact")
+/// * `diamond` - Decision node style ("box", "tall", or "inline")
+/// * `max_width` - Widest allowed output in columns, or `undefined` for no limit
+/// * `html` - Return an HTML `<pre>` block colored from `classDef` and
+///   `style` statements instead of plain text
+///
+/// # Returns
+/// * The rendered diagram as a String
+/// * Throws a [`DiagramError`] if an option is invalid or the diagram fails
+///   to parse or render
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn render_with_config(
+    input: &str,
+    style: &str,
+    diamond: &str,
+    max_width: Option<usize>,
+    html: bool,
+) -> Result<String, DiagramError> {
+    let style: CharacterSet = style.parse().map_err(DiagramError::option)?;
+    let diamond: DiamondStyle = diamond.parse().map_err(DiagramError::option)?;
+    let config = RenderConfig::new(style, diamond)
+        .with_max_width(max_width)
+        .with_color(html);
+
+    let orchestrator = orchestrator(config);
+    let output = if html {
+        orchestrator.process_html(input)?
+    } else {
+        orchestrator.process(input)?
+    };
+    Ok(output)
+}
+
+/// Detect the diagram type of the input
+///
+/// # Arguments
+/// * `input` - Mermaid diagram syntax
+///
+/// # Returns
+/// * The diagram type, e.g. "flowchart", "sequence" or "gitgraph"
+/// * Throws a [`DiagramError`] of kind "undetected" if no type matches
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn detect_type(input: &str) -> Result<String, DiagramError> {
+    Ok(orchestrator(RenderConfig::default()).detect_diagram_type(input)?)
+}
+
+/// Parse a diagram and return its contents as JSON
+///
+/// # Arguments
+/// * `input` - Mermaid diagram syntax
+///
+/// # Returns
+/// * JSON string with `type`, `direction`, `nodes`, `edges`, `subgraphs`
+///   and `warnings` (same shape as `figurehead convert --format json`)
+/// * Throws a [`DiagramError`] if parsing fails or the diagram is not a
+///   flowchart, the only type with a JSON form so far
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+pub fn parse_to_json(input: &str) -> Result<String, DiagramError> {
+    let diagram_type = detect_type(input)?;
+    if diagram_type != "flowchart" {
+        return Err(DiagramError {
+            kind: "unsupported".to_string(),
+            message: format!(
+                "JSON output only supports flowcharts so far, not {}",
+                diagram_type
+            ),
+            line: None,
+            column: None,
+            statement: None,
+        });
+    }
+
+    let parsed = crate::parse_with_warnings(input)?;
+    let database = parsed.database;
+    let marker = |terminator: EdgeTerminator| match terminator {
+        EdgeTerminator::Arrow => Some("arrow"),
+        EdgeTerminator::Open => Some("circle"),
+        EdgeTerminator::Cross => Some("cross"),
+        EdgeTerminator::Diamond { filled: true } => Some("diamond"),
+        EdgeTerminator::Diamond { filled: false } => Some("hollow_diamond"),
+        EdgeTerminator::Triangle => Some("triangle"),
+        EdgeTerminator::None => None,
+    };
+    let nodes: Vec<serde_json::Value> = database
+        .nodes()
+        .map(|node| {
             serde_json::json!({
-                "output": output,
-                "warnings": warnings,
-                "error": null
+                "id": node.id,
+                "label": node.label,
+                "shape": node.shape.to_string(),
+                "classes": node.classes,
+                "link": node.link.as_ref().map(|link| serde_json::json!({
+                    "url": link.url,
+                    "tooltip": link.tooltip,
+                })),
+                "hidden": database.is_node_hidden(&node.id),
             })
-            .to_string()
-        }
-        Err(e) => {
-            let warnings = take_warnings();
+        })
+        .collect();
+    let edges: Vec<serde_json::Value> = database
+        .edges()
+        .map(|edge| {
             serde_json::json!({
-                "output": "",
-                "warnings": warnings,
-                "error": format!("{}", e)
+                "from": edge.from,
+                "to": edge.to,
+                "type": edge.edge_type.to_string(),
+                "label": edge.label,
+                "start": marker(edge.start),
+                "min_length": edge.min_length,
             })
-            .to_string()
-        }
-    }
+        })
+        .collect();
+    let subgraphs: Vec<serde_json::Value> = database
+        .subgraphs()
+        .map(|subgraph| {
+            serde_json::json!({
+                "id": subgraph.id,
+                "title": subgraph.title,
+                "members": subgraph.members,
+                "direction": subgraph.direction.map(|d| d.to_string()),
+                "parent": subgraph.parent,
+            })
+        })
+        .collect();
+
+    let result = serde_json::json!({
+        "type": diagram_type,
+        "direction": database.direction().to_string(),
+        "nodes": nodes,
+        "edges": edges,
+        "subgraphs": subgraphs,
+        "warnings": warnings_json(parsed.warnings),
+    });
+    Ok(result.to_string())
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -193,3 +193,15 @@ fn test_semicolon_separated() {
     assert_eq!(db.node_count(), 4);
     assert_eq!(db.edge_count(), 3);
 }
+
+#[test]
+fn test_prelude_renders_with_config() {
+    let config = RenderConfig::new(CharacterSet::Ascii, DiamondStyle::default());
+    let mut db = FlowchartDatabase::new();
+    FlowchartParser::new()
+        .parse("graph LR; A-->B", &mut db)
+        .unwrap();
+    let ascii = FlowchartRenderer::with_config(config).render(&db).unwrap();
+    assert!(ascii.contains("->"));
+    assert!(!ascii.contains('─'));
+}
@@ -62,3 +62,49 @@ fn test_io_error() {
     assert!(error_msg.contains("IO error"));
     assert!(error_msg.contains("File not found"));
 }
+
+#[test]
+fn test_render_reports_parse_errors_with_spans() {
+    use figurehead::{render, FigureheadError, SourceSpan};
+
+    let cases = [
+        (
+            "graph TD\n    ]]]",
+            "flowchart",
+            SourceSpan::new(2, 5, 3),
+            "]]]",
+        ),
+        ("erDiagram\n  ?? x", "er", SourceSpan::new(2, 3, 4), "?? x"),
+        (
+            "classDiagram\n  A ~~ B",
+            "class",
+            SourceSpan::new(2, 3, 6),
+            "A ~~ B",
+        ),
+    ];
+    for (input, expected_type, expected_span, expected_statement) in cases {
+        let error = render(input).unwrap_err();
+        assert_eq!(error.span(), Some(expected_span), "{}", input);
+        match error {
+            FigureheadError::Parse {
+                diagram_type,
+                statement,
+                ..
+            } => {
+                assert_eq!(diagram_type, expected_type);
+                assert_eq!(statement, expected_statement);
+            }
+            other => panic!("expected a parse error for {:?}, got {:?}", input, other),
+        }
+    }
+}
+
+#[test]
+fn test_render_reports_undetected_input() {
+    use figurehead::{render, FigureheadError};
+
+    let error = render("just some prose").unwrap_err();
+    assert!(matches!(error, FigureheadError::Undetected));
+    assert_eq!(error.span(), None);
+    assert_eq!(error.to_string(), "No suitable detector found for input");
+}
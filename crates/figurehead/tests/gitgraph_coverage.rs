@@ -1,9 +1,9 @@
 //! Comprehensive tests for git graph plugin to improve coverage
 
-use figurehead::core::{
-    Database, Detector, Direction, LayoutAlgorithm, Parser, Renderer, SyntaxParser,
-};
+use figurehead::core::{Database, Detector, Direction, Parser, Renderer};
 use figurehead::plugins::gitgraph::*;
+use figurehead::unstable::gitgraph::{GitGraphLayoutAlgorithm, GitGraphSyntaxParser};
+use figurehead::unstable::{LayoutAlgorithm, SyntaxParser};
 use figurehead::CharacterSet;
 
 #[test]
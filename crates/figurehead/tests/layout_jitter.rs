@@ -0,0 +1,51 @@
+//! Layout invariants under seeded label/order jitter
+
+use figurehead::plugins::flowchart::FlowchartDatabase;
+use figurehead::testing::{assert_layout_invariants, jitter_flowchart, Jitter};
+use figurehead::unstable::flowchart::FlowchartLayoutAlgorithm;
+use figurehead::unstable::LayoutAlgorithm;
+
+const SEEDS: u64 = 32;
+
+fn check_all_seeds(input: &str) {
+    let db = figurehead::parse(input).unwrap();
+    for seed in 0..SEEDS {
+        let jittered: FlowchartDatabase = jitter_flowchart(&db, &Jitter::new(seed));
+        let layout = FlowchartLayoutAlgorithm::new().layout(&jittered).unwrap();
+        // Report the seed alongside the violation so failures are reproducible
+        let result = std::panic::catch_unwind(|| assert_layout_invariants(&layout));
+        assert!(result.is_ok(), "invariant violated for seed {}", seed);
+    }
+}
+
+#[test]
+fn test_chain_top_down() {
+    check_all_seeds("graph TD\n    A --> B --> C --> D");
+}
+
+#[test]
+fn test_diamond_fan_out_and_merge() {
+    check_all_seeds(
+        "graph TD\n    A --> B\n    A --> C\n    A --> D\n    B --> E\n    C --> E\n    D --> E",
+    );
+}
+
+#[test]
+fn test_left_right_with_labels_and_shapes() {
+    check_all_seeds(
+        "graph LR\n    A[Start] -->|go| B{Decide}\n    B -->|yes| C((Done))\n    B -->|no| D[(Store)]\n    D --> A",
+    );
+}
+
+#[test]
+fn test_bottom_up_and_right_left() {
+    check_all_seeds("graph BT\n    A --> B\n    A --> C\n    C --> D");
+    check_all_seeds("graph RL\n    A --> B\n    B --> C\n    A --> C");
+}
+
+#[test]
+fn test_subgraphs() {
+    check_all_seeds(
+        "graph TD\n    subgraph one\n    A --> B\n    end\n    subgraph two\n    C --> D\n    end\n    B --> C",
+    );
+}
@@ -1,5 +1,7 @@
-use figurehead::core::{Direction, LayoutAlgorithm};
-use figurehead::plugins::flowchart::{FlowchartDatabase, FlowchartLayoutAlgorithm};
+use figurehead::core::Direction;
+use figurehead::plugins::flowchart::FlowchartDatabase;
+use figurehead::unstable::flowchart::{FlowchartLayoutAlgorithm, LayoutConfig};
+use figurehead::unstable::{LayoutAlgorithm, Segment};
 
 #[test]
 fn test_compact_vertical_gap_is_four() {
@@ -8,7 +10,10 @@ fn test_compact_vertical_gap_is_four() {
     db.add_simple_node("B", "B").unwrap();
     db.add_simple_edge("A", "B").unwrap();
 
-    let layout = FlowchartLayoutAlgorithm::new();
+    let layout = FlowchartLayoutAlgorithm::with_config(LayoutConfig {
+        adaptive_rank_sep: false,
+        ..LayoutConfig::default()
+    });
     let result = layout.layout(&db).unwrap();
 
     let node_a = result.nodes.iter().find(|n| n.id == "A").unwrap();
@@ -24,6 +29,22 @@ fn test_compact_vertical_gap_is_four() {
     );
 }
 
+#[test]
+fn test_adaptive_vertical_gap_for_straight_edge_is_two() {
+    let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
+    db.add_simple_node("A", "A").unwrap();
+    db.add_simple_node("B", "B").unwrap();
+    db.add_simple_edge("A", "B").unwrap();
+
+    let result = FlowchartLayoutAlgorithm::new().layout(&db).unwrap();
+
+    let node_a = result.nodes.iter().find(|n| n.id == "A").unwrap();
+    let node_b = result.nodes.iter().find(|n| n.id == "B").unwrap();
+
+    // A stub below A and the arrowhead above B
+    assert_eq!(node_b.y, node_a.y + node_a.height + 2);
+}
+
 #[test]
 fn test_compact_horizontal_gap_is_one() {
     let mut db = FlowchartDatabase::with_direction(Direction::TopDown);
@@ -150,3 +171,93 @@ fn test_split_renders_junction_character() {
         output
     );
 }
+
+fn fan_out(direction: Direction, children: usize) -> FlowchartDatabase {
+    let mut db = FlowchartDatabase::with_direction(direction);
+    db.add_simple_node("Root", "Root").unwrap();
+    for i in 0..children {
+        let id = format!("C{}", i);
+        db.add_simple_node(&id, &id).unwrap();
+        db.add_simple_edge("Root", &id).unwrap();
+    }
+    db
+}
+
+fn wrapping_layout(max_layer_nodes: usize) -> FlowchartLayoutAlgorithm {
+    FlowchartLayoutAlgorithm::with_config(LayoutConfig {
+        max_layer_nodes,
+        ..LayoutConfig::default()
+    })
+}
+
+#[test]
+fn test_wide_layer_wraps_onto_rows() {
+    let db = fan_out(Direction::TopDown, 10);
+    let result = wrapping_layout(4).layout(&db).unwrap();
+
+    let mut rows: Vec<usize> = result
+        .nodes
+        .iter()
+        .filter(|n| n.id != "Root")
+        .map(|n| n.y)
+        .collect();
+    rows.sort_unstable();
+    rows.dedup();
+    assert_eq!(rows.len(), 3, "10 children at 4 per row need 3 rows");
+    for y in rows {
+        let in_row = result.nodes.iter().filter(|n| n.y == y).count();
+        assert!(in_row <= 4, "row at y={} holds {} nodes", y, in_row);
+    }
+
+    let unwrapped = FlowchartLayoutAlgorithm::new().layout(&db).unwrap();
+    assert!(result.width < unwrapped.width);
+}
+
+#[test]
+fn test_wrapped_rows_keep_a_valid_layout() {
+    use figurehead::testing::{assert_edges_terminate_on_nodes, assert_no_node_overlap};
+
+    for direction in [
+        Direction::TopDown,
+        Direction::BottomUp,
+        Direction::LeftRight,
+        Direction::RightLeft,
+    ] {
+        let db = fan_out(direction, 7);
+        let result = wrapping_layout(3).layout(&db).unwrap();
+        assert_no_node_overlap(&result);
+        assert_edges_terminate_on_nodes(&result);
+
+        // Lane routes never pass through a node other than their own ends
+        for edge in result.edges.iter().filter(|e| e.lane.is_some()) {
+            for segment in Segment::path(&edge.waypoints) {
+                for node in &result.nodes {
+                    if node.id == edge.from_id || node.id == edge.to_id {
+                        continue;
+                    }
+                    assert!(
+                        !segment.intersects_rect(&node.rect()),
+                        "{:?}: edge {}->{} crosses node {}",
+                        direction,
+                        edge.from_id,
+                        edge.to_id,
+                        node.id
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_wrapping_is_off_by_default() {
+    let db = fan_out(Direction::TopDown, 10);
+    let result = FlowchartLayoutAlgorithm::new().layout(&db).unwrap();
+    let child_y = result.nodes.iter().find(|n| n.id == "C0").unwrap().y;
+    assert!(result
+        .nodes
+        .iter()
+        .filter(|n| n.id != "Root")
+        .all(|n| n.y == child_y));
+    assert!(result.edges.iter().all(|e| e.lane.is_none()));
+}
@@ -1,7 +1,7 @@
 //! Tests for public API functions in lib.rs
 
 use figurehead::prelude::Database;
-use figurehead::{parse, render, render_with_style, CharacterSet, Direction};
+use figurehead::{parse, parse_with_warnings, render, render_with_style, CharacterSet, Direction};
 
 #[test]
 fn test_render_flowchart() {
@@ -78,3 +78,14 @@ fn test_parse_flowchart_lr() {
     let db = result.unwrap();
     assert_eq!(db.direction(), Direction::LeftRight);
 }
+
+#[test]
+fn test_parse_with_warnings_reports_skipped_statements() {
+    let parsed = parse_with_warnings("graph TD\n    A --> B\n    ~~~\n    B --> C").unwrap();
+    assert_eq!(parsed.database.node_count(), 3);
+    assert_eq!(parsed.warnings.len(), 1);
+    assert_eq!(parsed.warnings[0].line, Some(3));
+
+    let clean = parse_with_warnings("graph TD\n    A --> B").unwrap();
+    assert!(clean.warnings.is_empty());
+}
@@ -5,6 +5,8 @@
 
 use figurehead::core::logging::init_logging;
 use figurehead::prelude::*;
+use figurehead::unstable::flowchart::FlowchartLayoutAlgorithm;
+use figurehead::unstable::LayoutAlgorithm;
 use figurehead::{parse, render};
 use tracing_subscriber::util::SubscriberInitExt;
 
@@ -0,0 +1,60 @@
+//! Layout topology compared against mermaid.js
+//!
+//! Renders each diagram with mermaid-cli and checks that figurehead draws
+//! the same nodes and edges, pointing the same way along the flow. Skipped
+//! when `mmdc` is not installed (set `MMDC` to point at it):
+//! `npm install -g @mermaid-js/mermaid-cli`
+
+use figurehead::testing::mermaid::{compare, mermaid_cli, render_mermaid_svg, Topology};
+
+fn assert_matches_mermaid(input: &str) {
+    let Some(mmdc) = mermaid_cli() else {
+        eprintln!("mermaid-cli not found, skipping");
+        return;
+    };
+    let svg = render_mermaid_svg(&mmdc, input).expect("mermaid-cli should render");
+    let expected = Topology::from_mermaid_svg(&svg);
+    let (actual, direction) = Topology::from_flowchart(input).unwrap();
+
+    let divergences = compare(&expected, &actual, direction);
+    assert!(
+        divergences.is_empty(),
+        "figurehead diverges from mermaid for:\n{}\n\n{}",
+        input,
+        divergences
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    );
+}
+
+#[test]
+fn test_simple_chains() {
+    assert_matches_mermaid("graph LR; A-->B-->C");
+    assert_matches_mermaid("graph TD; A-->B-->C");
+}
+
+#[test]
+fn test_decision_in_every_direction() {
+    for direction in ["TD", "BT", "LR", "RL"] {
+        assert_matches_mermaid(&format!(
+            "graph {}\n    A[Start] --> B{{Decision}}\n    B -->|Yes| C[Process 1]\n    B -->|No| D[Process 2]\n    C --> E[End]\n    D --> E",
+            direction
+        ));
+    }
+}
+
+#[test]
+fn test_back_edges_and_shapes() {
+    assert_matches_mermaid(
+        "graph TD\n    A([Begin]) --> B[(Store)]\n    B --> C{{Check}}\n    C -->|retry| A\n    C --> D((Done))",
+    );
+}
+
+#[test]
+fn test_subgraphs() {
+    assert_matches_mermaid(
+        "graph TD\n    subgraph \"Backend\"\n        API --> DB\n    end\n    Client --> API",
+    );
+}
@@ -3,37 +3,12 @@
 //! These tests compare rendered output against golden files in tests/fixtures/.
 //! To update fixtures after fixing rendering, run the tests with UPDATE_FIXTURES=1
 
-use figurehead::render;
-use std::fs;
-use std::path::Path;
+use figurehead::testing::snapshot::Snapshots;
+use figurehead::{CharacterSet, RenderConfig};
 
 /// Compare rendered output to a fixture file
 fn assert_fixture(name: &str, input: &str) {
-    let output = render(input).expect("render should succeed");
-    let fixture_path = Path::new(env!("CARGO_MANIFEST_DIR"))
-        .join("tests/fixtures")
-        .join(format!("{}.txt", name));
-
-    if std::env::var("UPDATE_FIXTURES").is_ok() {
-        fs::write(&fixture_path, &output).expect("failed to write fixture");
-        println!("Updated fixture: {}", fixture_path.display());
-        return;
-    }
-
-    let expected = fs::read_to_string(&fixture_path).unwrap_or_else(|_| {
-        panic!(
-            "Fixture not found: {}\nRun with UPDATE_FIXTURES=1 to create it.\n\nActual output:\n{}",
-            fixture_path.display(),
-            output
-        )
-    });
-
-    if output != expected {
-        panic!(
-            "Snapshot mismatch for '{}'!\n\n=== Expected ===\n{}\n=== Actual ===\n{}\n=== Diff ===\nRun with UPDATE_FIXTURES=1 to update.",
-            name, expected, output
-        );
-    }
+    Snapshots::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures")).assert(name, input);
 }
 
 #[test]
@@ -97,6 +72,38 @@ fn test_labeled_edges() {
     assert_fixture("labeled_edges", "graph TD; A-->|yes|B; A-->|no|C");
 }
 
+#[test]
+fn test_decision_yes_no_td() {
+    assert_fixture(
+        "decision_yes_no_td",
+        r#"graph TD
+            A{Is it ok?} -->|Yes| B[Good]
+            A -->|No| C[Bad]
+            B --> D[End]
+            C -->|retry| D"#,
+    );
+}
+
+#[test]
+fn test_decision_yes_no_lr() {
+    assert_fixture(
+        "decision_yes_no_lr",
+        r#"graph LR
+            A{Is it ok?} -->|Yes| B[Good]
+            A -->|No| C[Bad]"#,
+    );
+}
+
+#[test]
+fn test_decision_yes_no_bt() {
+    assert_fixture(
+        "decision_yes_no_bt",
+        r#"graph BT
+            A{Is it ok?} -->|Yes| B[Good]
+            A -->|No| C[Bad]"#,
+    );
+}
+
 #[test]
 fn test_asymmetric_shape() {
     assert_fixture("asymmetric_shape", "graph LR; A>Flag]");
@@ -399,6 +406,16 @@ fn test_class_all_relationships() {
     );
 }
 
+#[test]
+fn test_class_dashed_relationships() {
+    assert_fixture(
+        "class_dashed_relationships",
+        r#"classDiagram
+    Client ..> Service
+    Shape ..|> Drawable"#,
+    );
+}
+
 #[test]
 fn test_class_full_featured() {
     assert_fixture(
@@ -420,3 +437,76 @@ fn test_class_full_featured() {
     Vehicle <|-- Car"#,
     );
 }
+
+#[test]
+fn test_class_shared_ports() {
+    assert_fixture(
+        "class_shared_ports",
+        r#"classDiagram
+    Vehicle <|-- Car
+    Vehicle *-- Engine
+    Vehicle ..> Fuel : burns
+    Vehicle "1" --> "*" Wheel
+    Driver --> Vehicle : drives"#,
+    );
+}
+
+#[test]
+fn test_class_shared_ports_ascii() {
+    Snapshots::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+        .with_config(RenderConfig::new(CharacterSet::Ascii, Default::default()))
+        .assert(
+            "class_shared_ports_ascii",
+            r#"classDiagram
+    Vehicle <|-- Car
+    Vehicle *-- Engine
+    Vehicle ..> Fuel : burns
+    Vehicle "1" --> "*" Wheel
+    Driver --> Vehicle : drives"#,
+        );
+}
+
+// =============================================================================
+// ER diagram snapshots
+// =============================================================================
+
+#[test]
+fn test_er_relationships() {
+    assert_fixture(
+        "er_relationships",
+        r#"erDiagram
+    CUSTOMER ||--o{ ORDER : places
+    ORDER ||--|{ LINE-ITEM : contains
+    CUSTOMER }|..|{ DELIVERY-ADDRESS : uses"#,
+    );
+}
+
+#[test]
+fn test_er_relationships_ascii() {
+    Snapshots::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+        .with_config(RenderConfig::new(CharacterSet::Ascii, Default::default()))
+        .assert(
+            "er_relationships_ascii",
+            r#"erDiagram
+    CUSTOMER ||--o{ ORDER : places
+    ORDER ||--|{ LINE-ITEM : contains
+    CUSTOMER }|..|{ DELIVERY-ADDRESS : uses"#,
+        );
+}
+
+#[test]
+fn test_er_attributes() {
+    assert_fixture(
+        "er_attributes",
+        r#"erDiagram
+    CUSTOMER |o--o| ACCOUNT : holds
+    CUSTOMER {
+        string name
+        string custNumber PK "unique"
+        int accountId FK
+    }
+    ACCOUNT {
+        int id PK
+    }"#,
+    );
+}
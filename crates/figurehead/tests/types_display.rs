@@ -14,6 +14,10 @@ fn test_node_shape_display() {
     assert_eq!(NodeShape::Asymmetric.to_string(), "asymmetric");
     assert_eq!(NodeShape::Parallelogram.to_string(), "parallelogram");
     assert_eq!(NodeShape::Trapezoid.to_string(), "trapezoid");
+    assert_eq!(NodeShape::Stadium.to_string(), "stadium");
+    assert_eq!(NodeShape::DoubleCircle.to_string(), "double-circle");
+    assert_eq!(NodeShape::ParallelogramAlt.to_string(), "parallelogram-alt");
+    assert_eq!(NodeShape::InvTrapezoid.to_string(), "inv-trapezoid");
 }
 
 #[test]